@@ -53,6 +53,7 @@ Router.map(function () {
   this.route('policies');
   this.route('data-access');
   this.route('confirm', { path: '/confirm/:email_token' });
+  this.route('revert-email-change', { path: '/revert-email-change/:token' });
   this.route('accept-invite', { path: '/accept-invite/:token' });
 
   this.route('catch-all', { path: '*path' });
@@ -0,0 +1,27 @@
+import Route from '@ember/routing/route';
+import { inject as service } from '@ember/service';
+
+import ajax from '../utils/ajax';
+
+export default class RevertEmailChangeRoute extends Route {
+  @service notifications;
+  @service router;
+
+  async model(params) {
+    try {
+      await ajax(`/api/v1/revert_email_change/${params.token}`, { method: 'PUT', body: '{}' });
+
+      this.notifications.success(
+        'The change of your notification email address was reverted. Please also check the security of your account.',
+      );
+    } catch (error) {
+      if (error.errors) {
+        this.notifications.error(`Error in reverting the email change: ${error.errors[0].detail}`);
+      } else {
+        this.notifications.error(`Unknown error in reverting the email change`);
+      }
+    }
+
+    this.router.replaceWith('index');
+  }
+}
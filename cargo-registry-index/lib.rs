@@ -3,9 +3,12 @@ extern crate serde;
 #[macro_use]
 extern crate tracing;
 
+mod signing;
 #[cfg(feature = "testing")]
 pub mod testing;
 
+pub use signing::{SigningFormat, SigningKey, VerifyingKey};
+
 use anyhow::{anyhow, Context};
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
@@ -84,16 +87,8 @@ impl Credentials {
             .ssh_key()
             .ok_or_else(|| anyhow!("SSH key not available"))?;
 
-        let dir = if cfg!(target_os = "linux") {
-            // When running on production, ensure the file is created in tmpfs and not persisted to disk
-            "/dev/shm".into()
-        } else {
-            // For other platforms, default to std::env::tempdir()
-            std::env::temp_dir()
-        };
-
         let mut temp_key_file = tempfile::Builder::new()
-            .tempfile_in(dir)
+            .tempfile_in(secrets_dir())
             .context("Failed to create temporary file")?;
 
         temp_key_file
@@ -104,6 +99,18 @@ impl Credentials {
     }
 }
 
+/// Returns the directory that temporary files containing secrets are created
+/// in.
+fn secrets_dir() -> PathBuf {
+    if cfg!(target_os = "linux") {
+        // When running on production, ensure the file is created in tmpfs and not persisted to disk
+        "/dev/shm".into()
+    } else {
+        // For other platforms, default to std::env::tempdir()
+        std::env::temp_dir()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Crate {
     pub name: String,
@@ -123,6 +130,17 @@ pub struct Crate {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub features2: Option<BTreeMap<String, Vec<String>>>,
     pub yanked: Option<bool>,
+    /// The reason the owners gave when yanking this version, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub yank_reason: Option<String>,
+    /// The suggested replacement for this yanked version, if any. This is
+    /// either a `<version>` of the same crate or `<crate>@<version>`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub yank_replacement: Option<String>,
+    /// Set if the owners deprecated this version or the whole crate. Unlike
+    /// yanked versions, deprecated versions can still be selected by cargo.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<Deprecation>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub links: Option<String>,
     /// The schema version for this entry.
@@ -148,6 +166,43 @@ pub struct Crate {
     pub v: Option<u32>,
 }
 
+impl Crate {
+    /// Splits the features of a version into the `features` and `features2`
+    /// fields of its index entry, and returns the schema version `v` that the
+    /// entry needs.
+    #[allow(clippy::type_complexity)]
+    pub fn split_features(
+        features: BTreeMap<String, Vec<String>>,
+    ) -> (
+        BTreeMap<String, Vec<String>>,
+        Option<BTreeMap<String, Vec<String>>>,
+        Option<u32>,
+    ) {
+        let (features, features2): (BTreeMap<_, _>, BTreeMap<_, _>) =
+            features.into_iter().partition(|(_k, vals)| {
+                !vals
+                    .iter()
+                    .any(|v| v.starts_with("dep:") || v.contains("?/"))
+            });
+
+        if features2.is_empty() {
+            (features, None, None)
+        } else {
+            (features, Some(features2), Some(2))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    /// The explanation the owners gave when deprecating, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// The name of the crate the owners recommend to migrate to, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub successor: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct Dependency {
     pub name: String,
@@ -190,6 +245,8 @@ pub enum DependencyKind {
 pub struct RepositoryConfig {
     pub index_location: Url,
     pub credentials: Credentials,
+    /// The key that commits to the index are signed with, if any.
+    pub signing_key: Option<SigningKey>,
 }
 
 impl RepositoryConfig {
@@ -201,6 +258,18 @@ impl RepositoryConfig {
         let ssh_key = dotenv::var("GIT_SSH_KEY");
         let ssh_url = dotenv::var("GIT_SSH_REPO_URL");
 
+        let signing_key = dotenv::var("GIT_SIGNING_KEY").ok().map(|encoded_key| {
+            let format = dotenv::var("GIT_SIGNING_FORMAT")
+                .map(|format| format.parse().expect("failed to parse GIT_SIGNING_FORMAT"))
+                .unwrap_or(SigningFormat::OpenPgp);
+            let key = String::from_utf8(
+                base64::decode(encoded_key).expect("failed to base64 decode the signing key"),
+            )
+            .expect("failed to convert the signing key to a string");
+
+            SigningKey { format, key }
+        });
+
         match (username, password, http_url, ssh_key, ssh_url) {
             (extra_user, extra_pass, extra_http_url, Ok(encoded_key), Ok(ssh_url)) => {
                 if let (Ok(_), Ok(_), Ok(_)) = (extra_user, extra_pass, extra_http_url) {
@@ -221,6 +290,7 @@ impl RepositoryConfig {
                 Self {
                     index_location,
                     credentials,
+                    signing_key,
                 }
             }
             (Ok(username), Ok(password), Ok(http_url), Err(_), Err(_)) => {
@@ -230,6 +300,7 @@ impl RepositoryConfig {
                 Self {
                     index_location,
                     credentials,
+                    signing_key,
                 }
             }
             (_, _, Ok(http_url), _, _) => {
@@ -239,6 +310,7 @@ impl RepositoryConfig {
                 Self {
                     index_location,
                     credentials,
+                    signing_key,
                 }
             }
             _ => panic!("must have `GIT_REPO_URL` defined"),
@@ -250,6 +322,7 @@ pub struct Repository {
     checkout_path: TempDir,
     repository: git2::Repository,
     credentials: Credentials,
+    signing_key: Option<SigningKey>,
 }
 
 impl Repository {
@@ -306,6 +379,7 @@ impl Repository {
             checkout_path,
             repository,
             credentials: repository_config.credentials.clone(),
+            signing_key: repository_config.signing_key.clone(),
         })
     }
 
@@ -366,11 +440,16 @@ impl Repository {
     /// the commit to the `master` branch on the `origin` remote.
     ///
     /// Note that `modified_file` expects a file path **relative** to the
-    /// repository working folder!
+    /// repository working folder! If the file does not exist anymore, it is
+    /// removed from the repository.
     fn perform_commit_and_push(&self, msg: &str, modified_file: &Path) -> anyhow::Result<()> {
-        // git add $file
+        // git add $file (or git rm $file)
         let mut index = self.repository.index()?;
-        index.add_path(modified_file)?;
+        if self.checkout_path.path().join(modified_file).exists() {
+            index.add_path(modified_file)?;
+        } else {
+            index.remove_path(modified_file)?;
+        }
         index.write()?;
         let tree_id = index.write_tree()?;
         let tree = self.repository.find_tree(tree_id)?;
@@ -378,13 +457,106 @@ impl Repository {
         // git commit -m "..."
         let head = self.head_oid()?;
         let parent = self.repository.find_commit(head)?;
-        let sig = self.repository.signature()?;
-        self.repository
-            .commit(Some("HEAD"), &sig, &sig, msg, &tree, &[&parent])?;
+        self.create_commit(true, msg, &tree, &[&parent])?;
 
         self.push("refs/heads/master")
     }
 
+    /// Commits all changes in the working folder, including removed files, on
+    /// top of `HEAD` without pushing them.
+    pub fn commit_all(&self, msg: &str) -> anyhow::Result<git2::Oid> {
+        let mut index = self.repository.index()?;
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None)?;
+        index.update_all(["*"], None)?;
+        index.write()?;
+        let tree_id = index.write_tree()?;
+        let tree = self.repository.find_tree(tree_id)?;
+
+        let parent = self.repository.find_commit(self.head_oid()?)?;
+        self.create_commit(true, msg, &tree, &[&parent])
+    }
+
+    /// Creates a commit, signed with the configured signing key if there is
+    /// one, and optionally moves the current branch to it.
+    fn create_commit(
+        &self,
+        update_head: bool,
+        msg: &str,
+        tree: &git2::Tree<'_>,
+        parents: &[&git2::Commit<'_>],
+    ) -> anyhow::Result<git2::Oid> {
+        let sig = self.repository.signature()?;
+
+        let Some(signing_key) = &self.signing_key else {
+            let update_ref = update_head.then_some("HEAD");
+            return Ok(self
+                .repository
+                .commit(update_ref, &sig, &sig, msg, tree, parents)?);
+        };
+
+        let buffer = self
+            .repository
+            .commit_create_buffer(&sig, &sig, msg, tree, parents)?;
+        let content = buffer
+            .as_str()
+            .ok_or_else(|| anyhow!("Commit content is not valid UTF-8"))?;
+        let signature = signing_key.sign(content).context("Failed to sign commit")?;
+        let oid = self.repository.commit_signed(content, &signature, None)?;
+
+        if update_head {
+            self.repository.head()?.set_target(oid, msg)?;
+        }
+
+        Ok(oid)
+    }
+
+    /// Returns the number of commits in the history of `HEAD`.
+    pub fn commit_count(&self) -> anyhow::Result<usize> {
+        let mut revwalk = self.repository.revwalk()?;
+        revwalk.push_head()?;
+
+        let mut count = 0;
+        for oid in revwalk {
+            oid?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Checks the signatures of the `limit` most recent commits of `HEAD`
+    /// against the given key, and returns the commits that are unsigned or
+    /// whose signature is invalid.
+    pub fn unverified_commits(
+        &self,
+        key: &VerifyingKey,
+        limit: usize,
+    ) -> anyhow::Result<Vec<git2::Oid>> {
+        let mut revwalk = self.repository.revwalk()?;
+        revwalk.push_head()?;
+
+        let mut unverified = Vec::new();
+        for oid in revwalk.take(limit) {
+            let oid = oid?;
+            let verified = match self.repository.extract_signature(&oid, None) {
+                Ok((signature, content)) => {
+                    let signature = signature
+                        .as_str()
+                        .ok_or_else(|| anyhow!("Signature of {oid} is not valid UTF-8"))?;
+                    key.verify(signature, &content)
+                        .with_context(|| format!("Failed to verify the signature of {oid}"))?
+                }
+                Err(error) if error.code() == git2::ErrorCode::NotFound => false,
+                Err(error) => return Err(error.into()),
+            };
+
+            if !verified {
+                unverified.push(oid);
+            }
+        }
+
+        Ok(unverified)
+    }
+
     /// Gets a list of files that have been modified since a given `starting_commit`
     /// (use `starting_commit = None` for a list of all files).
     pub fn get_files_modified_since(
@@ -517,12 +689,11 @@ impl Repository {
     /// Reset `HEAD` to a single commit with all the index contents, but no parent
     pub fn squash_to_single_commit(&self, msg: &str) -> anyhow::Result<()> {
         let tree = self.repository.find_commit(self.head_oid()?)?.tree()?;
-        let sig = self.repository.signature()?;
 
         // We cannot update an existing `update_ref`, because that requires the
         // first parent of this commit to match the ref's current value.
         // Instead, create the commit and then do a hard reset.
-        let commit = self.repository.commit(None, &sig, &sig, msg, &tree, &[])?;
+        let commit = self.create_commit(false, msg, &tree, &[])?;
         let commit = self
             .repository
             .find_object(commit, Some(git2::ObjectType::Commit))?;
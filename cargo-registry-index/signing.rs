@@ -0,0 +1,192 @@
+//! Signing of the commits that are created in the index, and verification of
+//! the signatures of existing commits.
+//!
+//! Both OpenPGP and SSH keys are supported, like the `gpg.format` setting of
+//! git. The actual cryptography is delegated to the `gpg` and `ssh-keygen`
+//! binaries, which produce signatures in the format that `git verify-commit`
+//! expects.
+
+use anyhow::{anyhow, Context};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::str::FromStr;
+
+/// The namespace of SSH signatures created by git for commits.
+const SSH_NAMESPACE: &str = "git";
+
+/// The principal that the SSH verifying key is registered under in the
+/// temporary allowed signers file.
+const SSH_PRINCIPAL: &str = "index";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SigningFormat {
+    OpenPgp,
+    Ssh,
+}
+
+impl FromStr for SigningFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "openpgp" => Ok(Self::OpenPgp),
+            "ssh" => Ok(Self::Ssh),
+            _ => Err(anyhow!(
+                "unknown signing format `{s}`, expected `openpgp` or `ssh`"
+            )),
+        }
+    }
+}
+
+/// A private key that index commits are signed with.
+#[derive(Clone)]
+pub struct SigningKey {
+    pub format: SigningFormat,
+    /// The ASCII-armored OpenPGP secret key, or the OpenSSH private key.
+    pub key: String,
+}
+
+impl SigningKey {
+    /// Returns the ASCII-armored detached signature of the given commit
+    /// content.
+    pub(crate) fn sign(&self, content: &str) -> anyhow::Result<String> {
+        let dir = tempfile::tempdir_in(crate::secrets_dir())
+            .context("Failed to create temporary directory")?;
+
+        let output = match self.format {
+            SigningFormat::OpenPgp => {
+                gpg_import(dir.path(), &self.key)?;
+                run_with_input(
+                    Command::new("gpg").env("GNUPGHOME", dir.path()).args([
+                        "--batch",
+                        "--armor",
+                        "--detach-sign",
+                    ]),
+                    content.as_bytes(),
+                )?
+            }
+            SigningFormat::Ssh => {
+                let key_path = dir.path().join("key");
+                write_private_file(&key_path, &self.key)?;
+                run_with_input(
+                    Command::new("ssh-keygen")
+                        .args(["-Y", "sign", "-n", SSH_NAMESPACE, "-f"])
+                        .arg(&key_path),
+                    content.as_bytes(),
+                )?
+            }
+        };
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!("Signing the commit failed with: {stderr}"));
+        }
+
+        String::from_utf8(output.stdout).context("Signature is not valid UTF-8")
+    }
+}
+
+/// A public key that the signatures of index commits are checked against.
+pub struct VerifyingKey {
+    pub format: SigningFormat,
+    /// The ASCII-armored OpenPGP public key, or the OpenSSH public key.
+    pub key: String,
+}
+
+impl VerifyingKey {
+    /// Checks whether `signature` is a valid signature of `content` made with
+    /// this key.
+    pub(crate) fn verify(&self, signature: &str, content: &[u8]) -> anyhow::Result<bool> {
+        let dir = tempfile::tempdir().context("Failed to create temporary directory")?;
+        let signature_path = dir.path().join("signature");
+        write_private_file(&signature_path, signature)?;
+
+        let output = match self.format {
+            SigningFormat::OpenPgp => {
+                gpg_import(dir.path(), &self.key)?;
+                run_with_input(
+                    Command::new("gpg")
+                        .env("GNUPGHOME", dir.path())
+                        .args(["--batch", "--verify"])
+                        .arg(&signature_path)
+                        .arg("-"),
+                    content,
+                )?
+            }
+            SigningFormat::Ssh => {
+                let allowed_signers_path = dir.path().join("allowed_signers");
+                let allowed_signers = format!("{SSH_PRINCIPAL} {}\n", self.key.trim());
+                write_private_file(&allowed_signers_path, &allowed_signers)?;
+                run_with_input(
+                    Command::new("ssh-keygen")
+                        .args(["-Y", "verify", "-n", SSH_NAMESPACE, "-I", SSH_PRINCIPAL])
+                        .arg("-f")
+                        .arg(&allowed_signers_path)
+                        .arg("-s")
+                        .arg(&signature_path),
+                    content,
+                )?
+            }
+        };
+
+        Ok(output.status.success())
+    }
+}
+
+/// Imports a key into the GnuPG keyring in the given home directory.
+fn gpg_import(home: &Path, key: &str) -> anyhow::Result<()> {
+    let output = run_with_input(
+        Command::new("gpg")
+            .env("GNUPGHOME", home)
+            .args(["--batch", "--import"]),
+        key.as_bytes(),
+    )?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("Importing the key failed with: {stderr}"));
+    }
+
+    Ok(())
+}
+
+fn write_private_file(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        // `ssh-keygen` refuses to use private keys that others can read
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(contents.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Runs the command with `input` on its standard input and collects its
+/// output.
+fn run_with_input(command: &mut Command, input: &[u8]) -> anyhow::Result<Output> {
+    debug!(?command);
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {command:?}"))?;
+
+    // The input is small enough to fit into the pipe buffers, so there is no
+    // need to write it from a separate thread.
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input)
+        .context("Failed to write to the standard input of the command")?;
+
+    Ok(child.wait_with_output()?)
+}
@@ -7,6 +7,12 @@ use std::borrow::Cow;
 use std::path::Path;
 use url::Url;
 
+/// The version of the HTML that `text_to_html` renders.
+///
+/// Bump this whenever a change to the renderer changes its output, so that the
+/// stored readmes of existing crate versions are rendered again.
+pub const RENDERER_VERSION: i32 = 1;
+
 /// Context for markdown to HTML rendering.
 struct MarkdownRenderer<'a> {
     html_sanitizer: Builder<'a>,
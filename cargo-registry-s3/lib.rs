@@ -4,13 +4,47 @@ use chrono::prelude::Utc;
 use hmac::{Hmac, Mac};
 use reqwest::{
     blocking::{Body, Client, Response},
-    header,
+    header, Url,
 };
 use sha1::Sha1;
+use std::fmt;
 use std::time::Duration;
 
 pub use reqwest::Error;
 
+/// Errors of the requests of multipart uploads.
+#[derive(Debug)]
+pub enum MultipartError {
+    Request(Error),
+    /// The response of S3 lacks the named field, which is needed to continue
+    /// the upload.
+    MissingField(&'static str),
+}
+
+impl From<Error> for MultipartError {
+    fn from(error: Error) -> Self {
+        Self::Request(error)
+    }
+}
+
+impl fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Request(error) => error.fmt(f),
+            Self::MissingField(field) => write!(f, "the response does not contain the `{field}`"),
+        }
+    }
+}
+
+impl std::error::Error for MultipartError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Request(error) => Some(error),
+            Self::MissingField(_) => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Bucket {
     name: String,
@@ -64,6 +98,55 @@ impl Bucket {
             .map_err(Into::into)
     }
 
+    pub fn get(&self, client: &Client, path: &str) -> Result<Response, Error> {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let date = Utc::now().to_rfc2822();
+        let auth = self.auth("GET", &date, path, "", "");
+        let url = self.url(path);
+
+        client
+            .get(url)
+            .header(header::DATE, date)
+            .header(header::AUTHORIZATION, auth)
+            .timeout(Duration::from_secs(60))
+            .send()?
+            .error_for_status()
+    }
+
+    /// Lists up to `max_keys` objects whose keys start with `prefix` and come
+    /// after `start_after` in lexicographical order.
+    ///
+    /// The response body is the XML document of the `ListObjectsV2` API.
+    pub fn list(
+        &self,
+        client: &Client,
+        prefix: &str,
+        start_after: Option<&str>,
+        max_keys: u32,
+    ) -> Result<Response, Error> {
+        let date = Utc::now().to_rfc2822();
+        let auth = self.auth("GET", &date, "", "", "");
+        let url = self.url("");
+
+        let max_keys = max_keys.to_string();
+        let mut query = vec![
+            ("list-type", "2"),
+            ("prefix", prefix),
+            ("max-keys", &max_keys),
+        ];
+        if let Some(start_after) = start_after {
+            query.push(("start-after", start_after));
+        }
+
+        client
+            .get(url)
+            .query(&query)
+            .header(header::DATE, date)
+            .header(header::AUTHORIZATION, auth)
+            .send()?
+            .error_for_status()
+    }
+
     pub fn delete(&self, client: &Client, path: &str) -> Result<Response, Error> {
         let path = path.strip_prefix('/').unwrap_or(path);
         let date = Utc::now().to_rfc2822();
@@ -79,6 +162,140 @@ impl Bucket {
             .map_err(Into::into)
     }
 
+    /// Starts a multipart upload of the object at `path`, and returns the ID
+    /// of the upload.
+    pub fn initiate_multipart_upload(
+        &self,
+        client: &Client,
+        path: &str,
+    ) -> Result<String, MultipartError> {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let path = format!("{path}?uploads");
+        let date = Utc::now().to_rfc2822();
+        let auth = self.auth("POST", &date, &path, "", "");
+        let url = self.url(&path);
+
+        let xml = client
+            .post(url)
+            .header(header::DATE, date)
+            .header(header::AUTHORIZATION, auth)
+            .send()?
+            .error_for_status()?
+            .text()?;
+
+        upload_id(&xml)
+            .map(str::to_string)
+            .ok_or(MultipartError::MissingField("UploadId"))
+    }
+
+    /// Uploads part `part_number` of a multipart upload, and returns the ETag
+    /// of the part, which is needed to complete the upload.
+    ///
+    /// All parts but the last one must be at least 5 MiB large.
+    pub fn upload_part<R: Into<Body>>(
+        &self,
+        client: &Client,
+        path: &str,
+        upload_id: &str,
+        part_number: i32,
+        content: R,
+    ) -> Result<String, MultipartError> {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let path = format!("{path}?partNumber={part_number}&uploadId={upload_id}");
+        let date = Utc::now().to_rfc2822();
+        let auth = self.auth("PUT", &date, &path, "", "");
+        let url = self.url(&path);
+
+        let response = client
+            .put(url)
+            .header(header::DATE, date)
+            .header(header::AUTHORIZATION, auth)
+            .body(content.into())
+            .timeout(Duration::from_secs(60))
+            .send()?
+            .error_for_status()?;
+
+        response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .filter(|etag| !etag.is_empty())
+            .map(str::to_string)
+            .ok_or(MultipartError::MissingField("ETag"))
+    }
+
+    /// Completes a multipart upload by concatenating the `parts`, given as
+    /// part numbers and ETags in ascending order.
+    pub fn complete_multipart_upload(
+        &self,
+        client: &Client,
+        path: &str,
+        upload_id: &str,
+        parts: &[(i32, String)],
+    ) -> Result<Response, Error> {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let path = format!("{path}?uploadId={upload_id}");
+        let content_type = "application/xml";
+        let date = Utc::now().to_rfc2822();
+        let auth = self.auth("POST", &date, &path, "", content_type);
+        let url = self.url(&path);
+
+        let mut body = String::from("<CompleteMultipartUpload>");
+        for (part_number, etag) in parts {
+            body.push_str(&format!(
+                "<Part><PartNumber>{part_number}</PartNumber><ETag>{etag}</ETag></Part>"
+            ));
+        }
+        body.push_str("</CompleteMultipartUpload>");
+
+        client
+            .post(url)
+            .header(header::AUTHORIZATION, auth)
+            .header(header::CONTENT_TYPE, content_type)
+            .header(header::DATE, date)
+            .body(body)
+            .timeout(Duration::from_secs(60))
+            .send()?
+            .error_for_status()
+    }
+
+    /// Aborts a multipart upload and deletes the parts that were uploaded.
+    pub fn abort_multipart_upload(
+        &self,
+        client: &Client,
+        path: &str,
+        upload_id: &str,
+    ) -> Result<Response, Error> {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let path = format!("{path}?uploadId={upload_id}");
+        let date = Utc::now().to_rfc2822();
+        let auth = self.auth("DELETE", &date, &path, "", "");
+        let url = self.url(&path);
+
+        client
+            .delete(url)
+            .header(header::DATE, date)
+            .header(header::AUTHORIZATION, auth)
+            .send()?
+            .error_for_status()
+    }
+
+    /// Returns a URL that downloads the object at `path` without further
+    /// authentication until the Unix timestamp `expires`.
+    pub fn presigned_url(&self, path: &str, expires: i64) -> String {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let expires = expires.to_string();
+        let signature = self.signature("GET", &expires, path, "", "");
+        let params = [
+            ("AWSAccessKeyId", self.access_key.as_str()),
+            ("Expires", &expires),
+            ("Signature", &signature),
+        ];
+        Url::parse_with_params(&self.url(path), params)
+            .expect("bucket URLs are valid")
+            .into()
+    }
+
     pub fn host(&self) -> String {
         format!(
             "{}.s3{}.amazonaws.com",
@@ -92,23 +309,67 @@ impl Bucket {
     }
 
     fn auth(&self, verb: &str, date: &str, path: &str, md5: &str, content_type: &str) -> String {
+        let signature = self.signature(verb, date, path, md5, content_type);
+        format!("AWS {}:{}", self.access_key, signature)
+    }
+
+    /// Signs a request with the secret key. For presigned URLs, `date` is the
+    /// expiration timestamp.
+    fn signature(
+        &self,
+        verb: &str,
+        date: &str,
+        path: &str,
+        md5: &str,
+        content_type: &str,
+    ) -> String {
         let string = format!(
             "{verb}\n{md5}\n{ty}\n{date}\n{headers}/{name}/{path}",
             ty = content_type,
             headers = "",
             name = self.name,
         );
-        let signature = {
-            let key = self.secret_key.as_bytes();
-            let mut h = Hmac::<Sha1>::new_from_slice(key).expect("HMAC can take key of any size");
-            h.update(string.as_bytes());
-            let res = h.finalize().into_bytes();
-            base64::encode(res)
-        };
-        format!("AWS {}:{}", self.access_key, signature)
+        let key = self.secret_key.as_bytes();
+        let mut h = Hmac::<Sha1>::new_from_slice(key).expect("HMAC can take key of any size");
+        h.update(string.as_bytes());
+        let res = h.finalize().into_bytes();
+        base64::encode(res)
     }
 
     fn url(&self, path: &str) -> String {
         format!("{}://{}/{}", self.proto, self.host(), path)
     }
 }
+
+/// Extracts the ID of a multipart upload from the response that started it.
+///
+/// The ID is opaque and never contains markup, so it's extracted without
+/// parsing the whole document.
+fn upload_id(xml: &str) -> Option<&str> {
+    xml.split_once("<UploadId>")
+        .and_then(|(_, rest)| rest.split_once("</UploadId>"))
+        .map(|(upload_id, _)| upload_id)
+        .filter(|upload_id| !upload_id.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::upload_id;
+
+    #[test]
+    fn extracts_upload_id() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<InitiateMultipartUploadResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Bucket>crates-io</Bucket>
+  <Key>crates/foo/foo-1.0.0.crate</Key>
+  <UploadId>VXBsb2FkIElEIGZvciA2aWWpbmcncyBteS1tb3ZpZS5tMnRzIHVwbG9hZA</UploadId>
+</InitiateMultipartUploadResult>"#;
+        assert_eq!(
+            upload_id(xml),
+            Some("VXBsb2FkIElEIGZvciA2aWWpbmcncyBteS1tb3ZpZS5tMnRzIHVwbG9hZA")
+        );
+
+        assert_eq!(upload_id("<UploadId></UploadId>"), None);
+        assert_eq!(upload_id("<Error><Code>AccessDenied</Code></Error>"), None);
+    }
+}
@@ -0,0 +1 @@
+DROP MATERIALIZED VIEW search_terms;
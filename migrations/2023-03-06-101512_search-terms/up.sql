@@ -0,0 +1,12 @@
+CREATE MATERIALIZED VIEW search_terms (term, weight) AS
+  SELECT term, SUM(weight)::bigint FROM (
+    SELECT name AS term, downloads AS weight FROM crates
+    UNION ALL
+    SELECT lower(keyword) AS term, crates_cnt AS weight FROM keywords
+  ) t
+  GROUP BY term;
+
+CREATE UNIQUE INDEX search_terms_term ON search_terms (term);
+CREATE INDEX search_terms_term_tgrm ON search_terms USING gin (term gin_trgm_ops);
+
+COMMENT ON MATERIALIZED VIEW search_terms IS 'Crate names and keywords used for spelling suggestions. Refreshed by the `daily_db_maintenance` background job.';
@@ -0,0 +1,2 @@
+DROP TABLE publish_upload_chunks;
+DROP TABLE publish_uploads;
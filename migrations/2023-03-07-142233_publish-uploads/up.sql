@@ -0,0 +1,23 @@
+CREATE TABLE publish_uploads (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    crate_name VARCHAR NOT NULL,
+    size BIGINT NOT NULL,
+    received BIGINT NOT NULL DEFAULT 0,
+    cksum VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE publish_uploads IS 'Publish request bodies that are uploaded in multiple chunks and have not been completed yet.';
+COMMENT ON COLUMN publish_uploads.size IS 'The expected size of the complete publish request body in bytes.';
+COMMENT ON COLUMN publish_uploads.received IS 'The number of bytes that have been received so far.';
+COMMENT ON COLUMN publish_uploads.cksum IS 'The expected SHA-256 hex digest of the complete publish request body.';
+
+CREATE TABLE publish_upload_chunks (
+    upload_id INTEGER NOT NULL REFERENCES publish_uploads (id) ON DELETE CASCADE,
+    position BIGINT NOT NULL,
+    data BYTEA NOT NULL,
+    PRIMARY KEY (upload_id, position)
+);
+
+COMMENT ON COLUMN publish_upload_chunks.position IS 'The offset of this chunk within the complete publish request body.';
@@ -0,0 +1,10 @@
+CREATE TABLE reserved_crate_prefixes (
+    prefix VARCHAR PRIMARY KEY,
+    team_id INTEGER NOT NULL REFERENCES teams (id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX ON reserved_crate_prefixes (canon_crate_name(prefix));
+
+COMMENT ON TABLE reserved_crate_prefixes IS 'Crate name prefixes under which only members of the owning team may publish new crates.';
+COMMENT ON COLUMN reserved_crate_prefixes.team_id IS 'The team whose members may publish new crates with this prefix.';
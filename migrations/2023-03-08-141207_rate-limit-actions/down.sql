@@ -0,0 +1,13 @@
+DELETE FROM publish_rate_overrides WHERE action != 0;
+
+ALTER TABLE publish_rate_overrides
+    DROP CONSTRAINT publish_rate_overrides_pkey,
+    ADD PRIMARY KEY (user_id),
+    DROP COLUMN action;
+
+DELETE FROM publish_limit_buckets WHERE action != 0;
+
+ALTER TABLE publish_limit_buckets
+    DROP CONSTRAINT publish_limit_buckets_pkey,
+    ADD PRIMARY KEY (user_id),
+    DROP COLUMN action;
@@ -0,0 +1,13 @@
+ALTER TABLE publish_limit_buckets
+    ADD COLUMN action SMALLINT NOT NULL DEFAULT 0,
+    DROP CONSTRAINT publish_limit_buckets_pkey,
+    ADD PRIMARY KEY (user_id, action);
+
+COMMENT ON COLUMN publish_limit_buckets.action IS 'The rate limited action: 0 = publishing a new crate, 1 = publishing a new version of an existing crate.';
+
+ALTER TABLE publish_rate_overrides
+    ADD COLUMN action SMALLINT NOT NULL DEFAULT 0,
+    DROP CONSTRAINT publish_rate_overrides_pkey,
+    ADD PRIMARY KEY (user_id, action);
+
+COMMENT ON COLUMN publish_rate_overrides.action IS 'The rate limited action: 0 = publishing a new crate, 1 = publishing a new version of an existing crate.';
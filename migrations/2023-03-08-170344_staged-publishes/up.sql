@@ -0,0 +1,13 @@
+CREATE TABLE staged_publishes (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    crate_name VARCHAR NOT NULL,
+    version VARCHAR NOT NULL,
+    body BYTEA NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX staged_publishes_crate_name_version ON staged_publishes (canon_crate_name(crate_name), version);
+
+COMMENT ON TABLE staged_publishes IS 'Validated publish requests that are not visible until they are promoted.';
+COMMENT ON COLUMN staged_publishes.body IS 'The complete body of the publish request, in the same format as for `PUT /api/v1/crates/new`.';
@@ -0,0 +1,3 @@
+ALTER TABLE versions
+    DROP COLUMN yank_reason,
+    DROP COLUMN yank_replacement;
@@ -0,0 +1,6 @@
+ALTER TABLE versions
+    ADD COLUMN yank_reason VARCHAR,
+    ADD COLUMN yank_replacement VARCHAR;
+
+COMMENT ON COLUMN versions.yank_reason IS 'Optional explanation given by the owner when yanking this version.';
+COMMENT ON COLUMN versions.yank_replacement IS 'Optional suggested replacement for this yanked version, either `<version>` of the same crate or `<crate>@<version>`.';
@@ -0,0 +1,13 @@
+CREATE TABLE crate_deletions (
+    id SERIAL PRIMARY KEY,
+    crate_name VARCHAR NOT NULL,
+    version VARCHAR,
+    deleted_by INTEGER NOT NULL REFERENCES users (id),
+    published_at TIMESTAMP NOT NULL,
+    deleted_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX crate_deletions_crate_name ON crate_deletions (canon_crate_name(crate_name));
+
+COMMENT ON TABLE crate_deletions IS 'Audit log of crates and versions that were deleted by their owners within the deletion grace period.';
+COMMENT ON COLUMN crate_deletions.version IS 'The deleted version, or `NULL` if the whole crate was deleted.';
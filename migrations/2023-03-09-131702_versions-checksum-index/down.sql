@@ -0,0 +1 @@
+DROP INDEX versions_checksum;
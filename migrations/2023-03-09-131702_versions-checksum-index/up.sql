@@ -0,0 +1 @@
+CREATE INDEX versions_checksum ON versions (checksum);
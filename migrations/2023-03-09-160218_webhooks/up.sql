@@ -0,0 +1,31 @@
+CREATE TABLE webhooks (
+    id SERIAL PRIMARY KEY,
+    crate_id INTEGER NOT NULL REFERENCES crates (id) ON DELETE CASCADE,
+    url VARCHAR NOT NULL,
+    secret BYTEA NOT NULL,
+    created_by INTEGER NOT NULL REFERENCES users (id),
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX webhooks_crate_id ON webhooks (crate_id);
+
+COMMENT ON TABLE webhooks IS 'HTTPS endpoints that are notified about events of a crate.';
+COMMENT ON COLUMN webhooks.secret IS 'SHA-256 hash of the secret, which is used as the key for the payload signatures.';
+
+CREATE TABLE webhook_deliveries (
+    id SERIAL PRIMARY KEY,
+    webhook_id INTEGER NOT NULL REFERENCES webhooks (id) ON DELETE CASCADE,
+    event VARCHAR NOT NULL,
+    payload JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    response_status INTEGER,
+    error VARCHAR,
+    delivered_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX webhook_deliveries_webhook_id ON webhook_deliveries (webhook_id);
+
+COMMENT ON TABLE webhook_deliveries IS 'Log of the events that were sent, or are still to be sent, to webhooks.';
+COMMENT ON COLUMN webhook_deliveries.response_status IS 'The HTTP status of the response to the last attempt, if one was received.';
+COMMENT ON COLUMN webhook_deliveries.error IS 'The reason why the last attempt failed, if it did.';
@@ -0,0 +1,6 @@
+ALTER TABLE versions
+    ADD COLUMN docs_rs_status VARCHAR,
+    ADD COLUMN docs_rs_updated_at TIMESTAMP;
+
+COMMENT ON COLUMN versions.docs_rs_status IS 'The outcome of the last documentation build on docs.rs, either `success` or `failure`, or `NULL` if docs.rs has not reported one yet.';
+COMMENT ON COLUMN versions.docs_rs_updated_at IS 'When docs.rs last reported a documentation build outcome for this version.';
@@ -0,0 +1,4 @@
+ALTER TABLE crates
+    DROP COLUMN publish_requires_2fa;
+
+DROP TABLE totp_credentials;
@@ -0,0 +1,21 @@
+CREATE TABLE totp_credentials (
+    user_id INTEGER PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    secret BYTEA NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT false,
+    last_used_step BIGINT,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE totp_credentials IS 'Time-based one-time password (RFC 6238) authenticators of users.';
+COMMENT ON COLUMN totp_credentials.secret IS 'The shared secret of the authenticator.';
+COMMENT ON COLUMN totp_credentials.enabled IS 'Whether the user confirmed the enrollment with a valid code. Credentials that are not enabled are ignored.';
+COMMENT ON COLUMN totp_credentials.last_used_step IS 'The time step of the last code that was accepted, to prevent the same code from being used twice.';
+COMMENT ON COLUMN totp_credentials.failed_attempts IS 'The number of invalid codes that were entered since the last valid one.';
+COMMENT ON COLUMN totp_credentials.locked_until IS 'No codes are accepted until this time, after too many invalid codes were entered.';
+
+ALTER TABLE crates
+    ADD COLUMN publish_requires_2fa BOOLEAN NOT NULL DEFAULT false;
+
+COMMENT ON COLUMN crates.publish_requires_2fa IS 'Whether publishes of new versions must be confirmed with a one-time password of the publishing user.';
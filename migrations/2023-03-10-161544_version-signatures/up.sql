@@ -0,0 +1,10 @@
+CREATE TABLE version_signatures (
+    version_id INTEGER PRIMARY KEY REFERENCES versions (id) ON DELETE CASCADE,
+    kind VARCHAR NOT NULL,
+    size INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE version_signatures IS 'Signatures of crate files that were uploaded together with the version. The signatures themselves are stored next to the crate files.';
+COMMENT ON COLUMN version_signatures.kind IS 'The format of the signature, either `detached` or `sigstore_bundle`.';
+COMMENT ON COLUMN version_signatures.size IS 'The size of the signature in bytes.';
@@ -0,0 +1,8 @@
+ALTER TABLE versions
+    DROP COLUMN deprecated,
+    DROP COLUMN deprecation_message;
+
+ALTER TABLE crates
+    DROP COLUMN deprecated,
+    DROP COLUMN deprecation_message,
+    DROP COLUMN deprecation_successor;
@@ -0,0 +1,15 @@
+ALTER TABLE crates
+    ADD COLUMN deprecated BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN deprecation_message VARCHAR,
+    ADD COLUMN deprecation_successor VARCHAR;
+
+COMMENT ON COLUMN crates.deprecated IS 'Whether the owners marked the crate as deprecated. Unlike yanking, this does not prevent new dependencies on the crate.';
+COMMENT ON COLUMN crates.deprecation_message IS 'An optional explanation of the deprecation, given by the owners.';
+COMMENT ON COLUMN crates.deprecation_successor IS 'The name of an optional crate that users of the deprecated crate should migrate to.';
+
+ALTER TABLE versions
+    ADD COLUMN deprecated BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN deprecation_message VARCHAR;
+
+COMMENT ON COLUMN versions.deprecated IS 'Whether the owners marked this version as deprecated. Unlike yanking, this does not prevent new dependencies on the version.';
+COMMENT ON COLUMN versions.deprecation_message IS 'An optional explanation of the deprecation, given by the owners.';
@@ -0,0 +1,4 @@
+ALTER TABLE versions
+    ADD COLUMN rust_version VARCHAR;
+
+COMMENT ON COLUMN versions.rust_version IS 'The minimum supported Rust version from the `rust-version` field of the manifest, normalized to `major.minor.patch`.';
@@ -0,0 +1 @@
+DROP INDEX index_crates_name_prefix;
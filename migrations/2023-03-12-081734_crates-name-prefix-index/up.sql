@@ -0,0 +1,3 @@
+CREATE INDEX index_crates_name_prefix ON crates (canon_crate_name(name) text_pattern_ops);
+
+COMMENT ON INDEX index_crates_name_prefix IS 'Speeds up the prefix matches of crate names for the suggestions of the search box.';
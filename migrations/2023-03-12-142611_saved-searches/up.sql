@@ -0,0 +1,14 @@
+CREATE TABLE saved_searches (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    query VARCHAR NOT NULL,
+    webhook_url VARCHAR,
+    last_version_id INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX saved_searches_user_id ON saved_searches (user_id);
+
+COMMENT ON TABLE saved_searches IS 'Search queries of users that are periodically checked for newly published crates and versions.';
+COMMENT ON COLUMN saved_searches.webhook_url IS 'HTTPS endpoint that is notified about new matches instead of the email address of the user, if set.';
+COMMENT ON COLUMN saved_searches.last_version_id IS 'The newest version when the search was last checked. Only newer versions are notified about.';
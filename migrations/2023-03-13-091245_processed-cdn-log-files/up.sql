@@ -0,0 +1,9 @@
+CREATE TABLE processed_cdn_log_files (
+    path VARCHAR PRIMARY KEY,
+    downloads INTEGER NOT NULL,
+    processed_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE processed_cdn_log_files IS 'CDN access log files whose downloads were added to the version_downloads table. Used to never count a log file twice.';
+COMMENT ON COLUMN processed_cdn_log_files.path IS 'Key of the log file in the CDN log bucket.';
+COMMENT ON COLUMN processed_cdn_log_files.downloads IS 'Number of crate downloads that were counted from the log file.';
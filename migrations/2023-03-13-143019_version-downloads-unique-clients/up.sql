@@ -0,0 +1,3 @@
+ALTER TABLE version_downloads ADD COLUMN unique_clients BYTEA;
+
+COMMENT ON COLUMN version_downloads.unique_clients IS 'HyperLogLog registers of the hashed clients that downloaded the version on this date, according to the CDN logs. Used to estimate the number of unique clients.';
@@ -0,0 +1,10 @@
+CREATE TABLE version_cargo_downloads (
+    version_id INTEGER NOT NULL REFERENCES versions (id) ON DELETE CASCADE,
+    date DATE NOT NULL,
+    cargo_version VARCHAR NOT NULL,
+    downloads INTEGER NOT NULL,
+    PRIMARY KEY (version_id, date, cargo_version)
+);
+
+COMMENT ON TABLE version_cargo_downloads IS 'Daily downloads of versions by the minor version of cargo that downloaded them, according to the user agents in the CDN logs.';
+COMMENT ON COLUMN version_cargo_downloads.cargo_version IS 'Major and minor version of cargo, like `1.68`.';
@@ -0,0 +1,9 @@
+CREATE TABLE reverse_dependency_counts (
+    crate_id INTEGER PRIMARY KEY REFERENCES crates (id) ON DELETE CASCADE,
+    dependents INTEGER NOT NULL,
+    refreshed_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE reverse_dependency_counts IS 'Number of crates whose newest version depends on a crate. Refreshed periodically by the refresh_reverse_dependency_counts background job, since counting the reverse dependencies of popular crates on demand is too slow.';
+COMMENT ON COLUMN reverse_dependency_counts.dependents IS 'Number of crates whose newest non-yanked version depends on the crate.';
+COMMENT ON COLUMN reverse_dependency_counts.refreshed_at IS 'Time at which the count was last refreshed.';
@@ -0,0 +1,13 @@
+CREATE TABLE crate_download_trends (
+    crate_id INTEGER NOT NULL REFERENCES crates (id) ON DELETE CASCADE,
+    window_days INTEGER NOT NULL,
+    downloads BIGINT NOT NULL,
+    previous_downloads BIGINT NOT NULL,
+    computed_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (crate_id, window_days)
+);
+
+COMMENT ON TABLE crate_download_trends IS 'Downloads of crates in the most recent window of days compared to the window before it. Recomputed periodically by the update_download_trends background job.';
+COMMENT ON COLUMN crate_download_trends.window_days IS 'Length of the compared windows in days. The most recent window ends with the day before the computation.';
+COMMENT ON COLUMN crate_download_trends.downloads IS 'Downloads of the crate in the most recent window.';
+COMMENT ON COLUMN crate_download_trends.previous_downloads IS 'Downloads of the crate in the window before the most recent one.';
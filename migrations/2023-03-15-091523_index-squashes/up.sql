@@ -0,0 +1,11 @@
+CREATE TABLE index_squashes (
+    id SERIAL PRIMARY KEY,
+    previous_head VARCHAR NOT NULL,
+    snapshot_branch VARCHAR NOT NULL,
+    squashed_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE index_squashes IS 'Changelog of the squashes of the git index history, so that mirrors can find out when the history was rewritten and where the previous history was archived.';
+COMMENT ON COLUMN index_squashes.previous_head IS 'Commit that was the HEAD of the index before it was squashed.';
+COMMENT ON COLUMN index_squashes.snapshot_branch IS 'Branch that the previous history of the index was pushed to.';
+COMMENT ON COLUMN index_squashes.squashed_at IS 'Time at which the index was squashed.';
@@ -0,0 +1,11 @@
+CREATE TABLE index_inconsistencies (
+    crate_id INTEGER PRIMARY KEY REFERENCES crates (id) ON DELETE CASCADE,
+    git_mismatch BOOLEAN NOT NULL,
+    sparse_mismatch BOOLEAN NOT NULL,
+    detected_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE index_inconsistencies IS 'Crates whose index files did not match the database when they were last checked by the `check_index_consistency` background job.';
+COMMENT ON COLUMN index_inconsistencies.git_mismatch IS 'Whether the file in the git index differs from the one rendered from the database.';
+COMMENT ON COLUMN index_inconsistencies.sparse_mismatch IS 'Whether the file in the sparse HTTP index differs from the one rendered from the database.';
+COMMENT ON COLUMN index_inconsistencies.detected_at IS 'Time at which the mismatch was last detected.';
@@ -0,0 +1,14 @@
+CREATE TABLE index_changes (
+    id BIGSERIAL PRIMARY KEY,
+    crate_name VARCHAR NOT NULL,
+    version VARCHAR,
+    action VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE index_changes IS 'Append-only changelog of the mutations of the index, which mirrors follow with increasing sequence numbers.';
+COMMENT ON COLUMN index_changes.id IS 'Sequence number of the change.';
+COMMENT ON COLUMN index_changes.crate_name IS 'Name of the crate whose index file was changed. Not a foreign key, so that the changelog outlives deleted crates.';
+COMMENT ON COLUMN index_changes.version IS 'Version that was changed, or NULL if the change affects the whole crate.';
+COMMENT ON COLUMN index_changes.action IS 'Kind of the change: `publish`, `yank`, `unyank`, `delete` or `deprecate`.';
+COMMENT ON COLUMN index_changes.created_at IS 'Time at which the change was committed to the index.';
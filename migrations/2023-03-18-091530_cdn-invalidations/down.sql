@@ -0,0 +1 @@
+DROP TABLE cdn_invalidations;
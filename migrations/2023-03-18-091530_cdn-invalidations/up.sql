@@ -0,0 +1,9 @@
+CREATE TABLE cdn_invalidations (
+    id BIGSERIAL PRIMARY KEY,
+    path VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE cdn_invalidations IS 'Paths whose cached copies still have to be invalidated on the CDNs, batched by the `process_cdn_invalidations` job.';
+COMMENT ON COLUMN cdn_invalidations.path IS 'Path of the file to invalidate, such as `config.json` or `re/ge/regex`.';
+COMMENT ON COLUMN cdn_invalidations.created_at IS 'Time at which the invalidation was queued.';
@@ -0,0 +1,19 @@
+CREATE TABLE file_replications (
+    path VARCHAR NOT NULL,
+    replica VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    replicated_at TIMESTAMP,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error VARCHAR,
+    PRIMARY KEY (path, replica)
+);
+
+CREATE INDEX file_replications_pending ON file_replications (created_at) WHERE replicated_at IS NULL;
+
+COMMENT ON TABLE file_replications IS 'Replication state of the crate files and readmes in the replica buckets.';
+COMMENT ON COLUMN file_replications.path IS 'Path of the file in the buckets, such as `crates/regex/regex-1.0.0.crate`.';
+COMMENT ON COLUMN file_replications.replica IS 'Name of the replica bucket that the file is copied to.';
+COMMENT ON COLUMN file_replications.created_at IS 'Time at which the file was uploaded to the primary bucket and queued for replication.';
+COMMENT ON COLUMN file_replications.replicated_at IS 'Time at which the file was copied to the replica, or NULL if it was not copied yet.';
+COMMENT ON COLUMN file_replications.attempts IS 'Number of failed attempts to copy the file.';
+COMMENT ON COLUMN file_replications.last_error IS 'Error of the last failed attempt to copy the file.';
@@ -0,0 +1,12 @@
+CREATE TABLE crate_files (
+    version_id INTEGER PRIMARY KEY REFERENCES versions (id) ON DELETE CASCADE,
+    object_key VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX crate_files_object_key ON crate_files (object_key);
+
+COMMENT ON TABLE crate_files IS 'Storage keys of the crate files that are stored under their content address. Versions without an entry are stored under their crate name and version.';
+COMMENT ON COLUMN crate_files.version_id IS 'Version that the crate file belongs to.';
+COMMENT ON COLUMN crate_files.object_key IS 'Key of the crate file in the bucket, derived from its SHA-256 checksum. Identical crate files of different versions share the key.';
+COMMENT ON COLUMN crate_files.created_at IS 'Time at which the crate file was uploaded.';
@@ -0,0 +1,15 @@
+CREATE TABLE crate_file_checks (
+    version_id INTEGER PRIMARY KEY REFERENCES versions (id) ON DELETE CASCADE,
+    checked_at TIMESTAMP NOT NULL DEFAULT now(),
+    missing BOOLEAN NOT NULL,
+    checksum_mismatch BOOLEAN NOT NULL
+);
+
+CREATE INDEX crate_file_checks_checked_at ON crate_file_checks (checked_at);
+CREATE INDEX crate_file_checks_failed ON crate_file_checks (version_id) WHERE missing OR checksum_mismatch;
+
+COMMENT ON TABLE crate_file_checks IS 'Results of the last integrity check of the stored crate files by the `verify_crate_files` background job.';
+COMMENT ON COLUMN crate_file_checks.version_id IS 'Version whose crate file was checked.';
+COMMENT ON COLUMN crate_file_checks.checked_at IS 'Time at which the crate file was last checked.';
+COMMENT ON COLUMN crate_file_checks.missing IS 'Whether the crate file did not exist in storage.';
+COMMENT ON COLUMN crate_file_checks.checksum_mismatch IS 'Whether the checksum of the stored crate file differs from the one in the `versions` table.';
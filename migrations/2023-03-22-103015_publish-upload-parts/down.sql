@@ -0,0 +1,16 @@
+DROP TABLE publish_upload_parts;
+
+DELETE FROM publish_uploads;
+
+ALTER TABLE publish_uploads
+    DROP COLUMN storage_path,
+    DROP COLUMN storage_upload_id;
+
+CREATE TABLE publish_upload_chunks (
+    upload_id INTEGER NOT NULL REFERENCES publish_uploads (id) ON DELETE CASCADE,
+    position BIGINT NOT NULL,
+    data BYTEA NOT NULL,
+    PRIMARY KEY (upload_id, position)
+);
+
+COMMENT ON COLUMN publish_upload_chunks.position IS 'The offset of this chunk within the complete publish request body.';
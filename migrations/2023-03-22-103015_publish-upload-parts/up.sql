@@ -0,0 +1,25 @@
+-- Chunks of unfinished uploads can't be moved to the storage backend, so the
+-- clients have to start these uploads again.
+DELETE FROM publish_uploads;
+
+DROP TABLE publish_upload_chunks;
+
+ALTER TABLE publish_uploads
+    ADD COLUMN storage_path VARCHAR NOT NULL,
+    ADD COLUMN storage_upload_id VARCHAR NOT NULL;
+
+COMMENT ON COLUMN publish_uploads.storage_path IS 'The random path in the default bucket that the complete publish request body is assembled at.';
+COMMENT ON COLUMN publish_uploads.storage_upload_id IS 'The ID of the multipart upload in the storage backend.';
+
+CREATE TABLE publish_upload_parts (
+    upload_id INTEGER NOT NULL REFERENCES publish_uploads (id) ON DELETE CASCADE,
+    part_number INTEGER NOT NULL,
+    position BIGINT NOT NULL,
+    tag VARCHAR NOT NULL,
+    PRIMARY KEY (upload_id, part_number)
+);
+
+COMMENT ON TABLE publish_upload_parts IS 'Chunks of publish request bodies that have been stored as parts of a multipart upload in the storage backend.';
+COMMENT ON COLUMN publish_upload_parts.part_number IS 'The number of the part in the multipart upload, starting at 1.';
+COMMENT ON COLUMN publish_upload_parts.position IS 'The offset of this chunk within the complete publish request body.';
+COMMENT ON COLUMN publish_upload_parts.tag IS 'The tag that the storage backend identifies the part with when the upload is completed, like the ETag in S3.';
@@ -0,0 +1,3 @@
+ALTER TABLE readme_renderings ADD COLUMN renderer_version INTEGER NOT NULL DEFAULT 0;
+
+COMMENT ON COLUMN readme_renderings.renderer_version IS 'The version of the readme renderer that the stored readme was rendered with, which is part of its path. Readmes of version 0 were rendered before the path was versioned.';
@@ -0,0 +1,11 @@
+CREATE TABLE totp_recovery_codes (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    code BYTEA NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX totp_recovery_codes_user_id ON totp_recovery_codes (user_id);
+
+COMMENT ON TABLE totp_recovery_codes IS 'Single-use codes that can be used instead of a one-time password if the authenticator of a user was lost.';
+COMMENT ON COLUMN totp_recovery_codes.code IS 'The SHA-256 hash of the recovery code. Codes are deleted once they were used.';
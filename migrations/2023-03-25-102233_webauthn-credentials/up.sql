@@ -0,0 +1,29 @@
+CREATE TABLE webauthn_credentials (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    credential_id BYTEA NOT NULL UNIQUE,
+    public_key BYTEA NOT NULL,
+    sign_count BIGINT NOT NULL,
+    name VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    last_used_at TIMESTAMP
+);
+
+CREATE INDEX webauthn_credentials_user_id ON webauthn_credentials (user_id);
+
+COMMENT ON TABLE webauthn_credentials IS 'Security keys and passkeys that users registered as a second factor.';
+COMMENT ON COLUMN webauthn_credentials.credential_id IS 'The ID that the authenticator assigned to the credential.';
+COMMENT ON COLUMN webauthn_credentials.public_key IS 'The ES256 public key of the credential, as an uncompressed P-256 point.';
+COMMENT ON COLUMN webauthn_credentials.sign_count IS 'The signature counter of the last assertion. Authenticators increase it with every assertion, so a counter that does not increase indicates a cloned credential.';
+COMMENT ON COLUMN webauthn_credentials.name IS 'The name that the user gave the credential.';
+
+CREATE TABLE webauthn_challenges (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    ceremony VARCHAR NOT NULL,
+    challenge BYTEA NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, ceremony)
+);
+
+COMMENT ON TABLE webauthn_challenges IS 'The pending WebAuthn challenges of users. Challenges are deleted once they were answered.';
+COMMENT ON COLUMN webauthn_challenges.ceremony IS 'Either `registration` or `authentication`. Only the latest challenge of each ceremony is kept.';
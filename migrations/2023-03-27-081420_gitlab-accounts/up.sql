@@ -0,0 +1,23 @@
+CREATE TABLE linked_accounts (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    provider SMALLINT NOT NULL,
+    account_id INTEGER NOT NULL,
+    login VARCHAR NOT NULL,
+    avatar VARCHAR,
+    access_token VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (provider, account_id),
+    UNIQUE (user_id, provider)
+);
+
+COMMENT ON TABLE linked_accounts IS 'Accounts at OAuth identity providers other than GitHub that users can log in with. GitHub accounts are still stored in the `users` table.';
+COMMENT ON COLUMN linked_accounts.provider IS 'The identity provider of the account: 1 = GitLab.';
+COMMENT ON COLUMN linked_accounts.account_id IS 'The ID of the account at the identity provider.';
+COMMENT ON COLUMN linked_accounts.login IS 'The username of the account at the identity provider.';
+COMMENT ON COLUMN linked_accounts.access_token IS 'The OAuth access token of the account, used to check group memberships.';
+
+ALTER TABLE teams ADD COLUMN provider SMALLINT NOT NULL DEFAULT 0;
+ALTER TABLE teams DROP CONSTRAINT teams_github_id_key;
+ALTER TABLE teams ADD CONSTRAINT teams_provider_github_id_key UNIQUE (provider, github_id);
+
+COMMENT ON COLUMN teams.provider IS 'The identity provider of the team: 0 = GitHub team, 1 = GitLab group. `github_id` is the ID of the team or group at this provider.';
@@ -0,0 +1,13 @@
+-- Only keep the oldest GitLab account of every user
+DELETE FROM linked_accounts WHERE provider = 0;
+DELETE FROM linked_accounts a
+    USING linked_accounts b
+    WHERE a.user_id = b.user_id
+      AND a.provider = b.provider
+      AND (a.created_at, a.account_id) > (b.created_at, b.account_id);
+
+DROP INDEX linked_accounts_user_id;
+ALTER TABLE linked_accounts ADD CONSTRAINT linked_accounts_user_id_provider_key UNIQUE (user_id, provider);
+
+COMMENT ON TABLE linked_accounts IS 'Accounts at OAuth identity providers other than GitHub that users can log in with. GitHub accounts are still stored in the `users` table.';
+COMMENT ON COLUMN linked_accounts.provider IS 'The identity provider of the account: 1 = GitLab.';
@@ -0,0 +1,5 @@
+ALTER TABLE linked_accounts DROP CONSTRAINT linked_accounts_user_id_provider_key;
+CREATE INDEX linked_accounts_user_id ON linked_accounts (user_id);
+
+COMMENT ON TABLE linked_accounts IS 'Additional accounts at OAuth identity providers that users can log in with. Users can link several accounts of the same provider. The GitHub account that a user signed up with is still stored in the `users` table.';
+COMMENT ON COLUMN linked_accounts.provider IS 'The identity provider of the account: 0 = GitHub, 1 = GitLab.';
@@ -0,0 +1,8 @@
+DELETE FROM crate_owners WHERE owner_kind = 2;
+COMMENT ON COLUMN crate_owners.owner_kind IS NULL;
+
+ALTER TABLE api_tokens DROP COLUMN organization_id;
+
+DROP TABLE organization_invitations;
+DROP TABLE organization_members;
+DROP TABLE organizations;
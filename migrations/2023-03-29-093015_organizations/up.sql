@@ -0,0 +1,42 @@
+CREATE TABLE organizations (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    description VARCHAR,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX organizations_name_lower ON organizations (lower(name));
+
+COMMENT ON TABLE organizations IS 'Accounts that are shared by several users, and can own crates like users and teams do.';
+COMMENT ON COLUMN organizations.name IS 'The unique name of the organization, used as `org:name` when adding it as a crate owner.';
+
+CREATE TABLE organization_members (
+    organization_id INTEGER NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role SMALLINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (organization_id, user_id)
+);
+
+CREATE INDEX organization_members_user_id ON organization_members (user_id);
+
+COMMENT ON TABLE organization_members IS 'The users that are members of an organization.';
+COMMENT ON COLUMN organization_members.role IS 'The role of the member: 0 = viewer, 1 = publisher, 2 = admin. Admins manage the members and owners of the crates of the organization, publishers can publish its crates.';
+
+CREATE TABLE organization_invitations (
+    organization_id INTEGER NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    invited_user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    invited_by_user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role SMALLINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (organization_id, invited_user_id)
+);
+
+COMMENT ON TABLE organization_invitations IS 'Pending invitations of users to join an organization, which they have to accept before becoming members.';
+COMMENT ON COLUMN organization_invitations.role IS 'The role that the user will have once they accept the invitation, see `organization_members.role`.';
+
+ALTER TABLE api_tokens ADD COLUMN organization_id INTEGER REFERENCES organizations (id) ON DELETE CASCADE;
+
+COMMENT ON COLUMN api_tokens.organization_id IS 'Tokens of an organization can only be used for the crates that the organization owns, and for publishing new crates that it will own.';
+
+COMMENT ON COLUMN crate_owners.owner_kind IS '0 = user, 1 = team, 2 = organization';
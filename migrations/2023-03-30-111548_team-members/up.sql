@@ -0,0 +1,11 @@
+CREATE TABLE team_members (
+    team_id INTEGER NOT NULL REFERENCES teams (id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    verified_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (team_id, user_id)
+);
+
+CREATE INDEX team_members_verified_at ON team_members (verified_at);
+
+COMMENT ON TABLE team_members IS 'The users that were confirmed to be members of a GitHub team that owns crates. The memberships are validated again regularly, so that users who left the team are noticed even if they never use it again.';
+COMMENT ON COLUMN team_members.verified_at IS 'The last time that the membership was confirmed with the GitHub API.';
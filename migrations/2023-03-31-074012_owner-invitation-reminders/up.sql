@@ -0,0 +1,3 @@
+ALTER TABLE crate_owner_invitations ADD COLUMN reminder_sent_at TIMESTAMP;
+
+COMMENT ON COLUMN crate_owner_invitations.reminder_sent_at IS 'When the invited user was reminded that the invitation is about to expire, if they were reminded yet.';
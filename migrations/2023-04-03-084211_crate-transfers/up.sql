@@ -0,0 +1,21 @@
+CREATE TABLE crate_transfers (
+    id SERIAL PRIMARY KEY,
+    crate_id INTEGER NOT NULL REFERENCES crates (id) ON DELETE CASCADE,
+    from_user_id INTEGER NOT NULL REFERENCES users (id),
+    to_user_id INTEGER NOT NULL REFERENCES users (id),
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    accepted_at TIMESTAMP,
+    completes_at TIMESTAMP,
+    completed_at TIMESTAMP,
+    cancelled_at TIMESTAMP
+);
+
+CREATE UNIQUE INDEX crate_transfers_pending ON crate_transfers (crate_id) WHERE completed_at IS NULL AND cancelled_at IS NULL;
+CREATE INDEX crate_transfers_to_user_id ON crate_transfers (to_user_id);
+CREATE INDEX crate_transfers_completes_at ON crate_transfers (completes_at) WHERE completed_at IS NULL AND cancelled_at IS NULL;
+
+COMMENT ON TABLE crate_transfers IS 'Transfers of the ownership of a crate from one of its owners to another user. Completed transfers are kept as a public record of the ownership changes of the crate.';
+COMMENT ON COLUMN crate_transfers.accepted_at IS 'When the recipient accepted the transfer, which starts the waiting period.';
+COMMENT ON COLUMN crate_transfers.completes_at IS 'When the waiting period of an accepted transfer is over and the ownership is moved by the background worker.';
+COMMENT ON COLUMN crate_transfers.completed_at IS 'When the ownership was moved to the recipient.';
+COMMENT ON COLUMN crate_transfers.cancelled_at IS 'When the transfer was cancelled by an owner or an admin, or declined by the recipient.';
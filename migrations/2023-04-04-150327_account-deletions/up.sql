@@ -0,0 +1,12 @@
+CREATE TABLE account_deletions (
+    user_id INTEGER PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    token VARCHAR NOT NULL,
+    requested_at TIMESTAMP NOT NULL DEFAULT now(),
+    confirmed_at TIMESTAMP,
+    completed_at TIMESTAMP
+);
+
+COMMENT ON TABLE account_deletions IS 'Requests of users to delete their account. The rows of deleted accounts are kept as a record of when the deletion happened.';
+COMMENT ON COLUMN account_deletions.token IS 'The token that was emailed to the user to confirm the deletion, cleared once the account is deleted.';
+COMMENT ON COLUMN account_deletions.confirmed_at IS 'When the user confirmed the deletion. Their account is locked from then on, until the background worker deletes it.';
+COMMENT ON COLUMN account_deletions.completed_at IS 'When the personal data of the user was removed by the background worker.';
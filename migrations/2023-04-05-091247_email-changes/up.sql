@@ -0,0 +1,17 @@
+CREATE TABLE email_changes (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    old_email VARCHAR NOT NULL,
+    new_email VARCHAR NOT NULL,
+    revert_token TEXT NOT NULL DEFAULT random_string(26),
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    reverted_at TIMESTAMP
+);
+
+CREATE INDEX email_changes_user_id ON email_changes (user_id);
+CREATE UNIQUE INDEX email_changes_revert_token ON email_changes (revert_token);
+
+COMMENT ON TABLE email_changes IS 'Changes of verified email addresses. The previous address is notified of the change with a link to revert it.';
+COMMENT ON COLUMN email_changes.old_email IS 'The verified email address before the change, which is restored if the change is reverted.';
+COMMENT ON COLUMN email_changes.revert_token IS 'The token that was emailed to the previous address to revert the change.';
+COMMENT ON COLUMN email_changes.reverted_at IS 'When the change was reverted from the previous address, or NULL if it was not.';
@@ -0,0 +1,10 @@
+DELETE FROM emails WHERE NOT send_notifications;
+
+DROP INDEX emails_public_user_id;
+DROP INDEX emails_notifications_user_id;
+DROP INDEX emails_user_id_email;
+
+ALTER TABLE emails DROP COLUMN public;
+ALTER TABLE emails DROP COLUMN send_notifications;
+
+ALTER TABLE emails ADD CONSTRAINT emails_user_id_key UNIQUE (user_id);
@@ -0,0 +1,14 @@
+ALTER TABLE emails DROP CONSTRAINT emails_user_id_key;
+
+ALTER TABLE emails ADD COLUMN send_notifications BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE emails ADD COLUMN public BOOLEAN NOT NULL DEFAULT false;
+
+-- Until now every user had at most one email address, which received all notifications
+UPDATE emails SET send_notifications = true;
+
+CREATE UNIQUE INDEX emails_user_id_email ON emails (user_id, email);
+CREATE UNIQUE INDEX emails_notifications_user_id ON emails (user_id) WHERE send_notifications;
+CREATE UNIQUE INDEX emails_public_user_id ON emails (user_id) WHERE public;
+
+COMMENT ON COLUMN emails.send_notifications IS 'Whether notifications are sent to this address. Every user has at most one such address.';
+COMMENT ON COLUMN emails.public IS 'Whether this address is shown on the public profile of the user. Every user has at most one such address, which has to be verified.';
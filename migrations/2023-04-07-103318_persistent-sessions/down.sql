@@ -0,0 +1 @@
+DROP TABLE persistent_sessions;
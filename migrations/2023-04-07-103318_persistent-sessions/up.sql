@@ -0,0 +1,16 @@
+CREATE TABLE persistent_sessions (
+    id BIGSERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    last_used_at TIMESTAMP NOT NULL DEFAULT now(),
+    revoked BOOLEAN NOT NULL DEFAULT false,
+    last_ip_address VARCHAR,
+    last_user_agent VARCHAR
+);
+
+CREATE INDEX persistent_sessions_user_id ON persistent_sessions (user_id);
+
+COMMENT ON TABLE persistent_sessions IS 'Login sessions of users. The ID of the session is stored in the signed session cookie, and the session is only valid while its row is not revoked.';
+COMMENT ON COLUMN persistent_sessions.last_used_at IS 'When the session was last used to authenticate a request. This is updated at most once per minute.';
+COMMENT ON COLUMN persistent_sessions.last_ip_address IS 'The IP address of the last request that was authenticated with the session.';
+COMMENT ON COLUMN persistent_sessions.last_user_agent IS 'The `User-Agent` header of the last request that was authenticated with the session.';
@@ -0,0 +1,3 @@
+DROP TABLE admin_actions;
+DROP TABLE admin_role_assignments;
+DROP TABLE admin_roles;
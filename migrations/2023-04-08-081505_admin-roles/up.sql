@@ -0,0 +1,34 @@
+CREATE TABLE admin_roles (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL UNIQUE,
+    permissions TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE admin_roles IS 'Roles of the crates.io team, which grant admin permissions to the users they are assigned to.';
+COMMENT ON COLUMN admin_roles.permissions IS 'The admin permissions granted by the role, e.g. `crates:delete`.';
+
+CREATE TABLE admin_role_assignments (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role_id INTEGER NOT NULL REFERENCES admin_roles (id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, role_id)
+);
+
+COMMENT ON TABLE admin_role_assignments IS 'The admin roles that are assigned to users.';
+
+CREATE TABLE admin_actions (
+    id BIGSERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id),
+    permission VARCHAR NOT NULL,
+    target VARCHAR NOT NULL,
+    reason VARCHAR,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX admin_actions_created_at ON admin_actions (created_at);
+
+COMMENT ON TABLE admin_actions IS 'Audit log of the actions taken by users with admin permissions.';
+COMMENT ON COLUMN admin_actions.permission IS 'The admin permission that the action required, e.g. `crates:delete`.';
+COMMENT ON COLUMN admin_actions.target IS 'What the action was taken on, e.g. `crate serde` or `user 42`.';
+COMMENT ON COLUMN admin_actions.reason IS 'The reason for the action that the admin gave, if any.';
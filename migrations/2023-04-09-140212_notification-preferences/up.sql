@@ -0,0 +1,12 @@
+CREATE TABLE notification_preferences (
+    user_id INTEGER PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    owner_invites BOOLEAN NOT NULL DEFAULT TRUE,
+    security_alerts BOOLEAN NOT NULL DEFAULT TRUE,
+    digests BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE notification_preferences IS 'The kinds of emails that users want to receive. Users without a row receive all of them.';
+COMMENT ON COLUMN notification_preferences.owner_invites IS 'Invitations to become an owner of a crate, and updates about crate transfers and team ownership.';
+COMMENT ON COLUMN notification_preferences.security_alerts IS 'Alerts about exposed API tokens.';
+COMMENT ON COLUMN notification_preferences.digests IS 'Periodic summaries, like the alerts of saved searches.';
@@ -0,0 +1,12 @@
+CREATE TABLE follow_notification_settings (
+    user_id INTEGER PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    frequency SMALLINT NOT NULL,
+    webhook_url VARCHAR,
+    last_action_id INTEGER NOT NULL,
+    last_notified_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE follow_notification_settings IS 'How users want to be notified about new and yanked versions of the crates they follow. Users without a row are not notified.';
+COMMENT ON COLUMN follow_notification_settings.frequency IS '0 = never, 1 = daily, 2 = weekly';
+COMMENT ON COLUMN follow_notification_settings.webhook_url IS 'If set, notifications are sent to this URL instead of by email.';
+COMMENT ON COLUMN follow_notification_settings.last_action_id IS 'The newest version_owner_actions entry that the user was notified about.';
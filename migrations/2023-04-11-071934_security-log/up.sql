@@ -0,0 +1,17 @@
+CREATE TABLE security_log_entries (
+    id BIGSERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    event SMALLINT NOT NULL,
+    details VARCHAR,
+    ip_address VARCHAR,
+    user_agent VARCHAR,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX security_log_entries_user_id ON security_log_entries (user_id);
+
+COMMENT ON TABLE security_log_entries IS 'Security relevant events of user accounts, like logins and changes of email addresses, that users can review to audit their account.';
+COMMENT ON COLUMN security_log_entries.event IS '0 = login, 1 = token created, 2 = owner added, 3 = owner removed, 4 = email added, 5 = email removed, 6 = notification email changed';
+COMMENT ON COLUMN security_log_entries.details IS 'What the event was about, e.g. the name of the created token or the changed email address.';
+COMMENT ON COLUMN security_log_entries.ip_address IS 'The IP address of the request that caused the event.';
+COMMENT ON COLUMN security_log_entries.user_agent IS 'The `User-Agent` header of the request that caused the event.';
@@ -0,0 +1 @@
+ALTER TABLE persistent_sessions DROP COLUMN token;
@@ -0,0 +1,9 @@
+-- The sessions that were created so far are referenced by their ID in the
+-- session cookie, and can't be given a token. Their users have to log in again.
+DELETE FROM persistent_sessions;
+
+ALTER TABLE persistent_sessions ADD COLUMN token BYTEA NOT NULL;
+
+CREATE UNIQUE INDEX persistent_sessions_token ON persistent_sessions (token);
+
+COMMENT ON COLUMN persistent_sessions.token IS 'The SHA-256 hash of the opaque token that is stored in the session cookie.';
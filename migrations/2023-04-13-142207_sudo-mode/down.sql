@@ -0,0 +1 @@
+ALTER TABLE persistent_sessions DROP COLUMN sudo_until;
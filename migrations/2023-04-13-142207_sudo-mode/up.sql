@@ -0,0 +1,3 @@
+ALTER TABLE persistent_sessions ADD COLUMN sudo_until TIMESTAMP;
+
+COMMENT ON COLUMN persistent_sessions.sudo_until IS 'Until when the session can be used for sensitive actions, like creating API tokens or changing email addresses, without confirming them again. Sessions enter sudo mode when they are created, and when the user confirms their identity with a second factor.';
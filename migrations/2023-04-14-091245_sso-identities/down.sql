@@ -0,0 +1,4 @@
+COMMENT ON COLUMN teams.provider IS 'The identity provider of the team: 0 = GitHub team, 1 = GitLab group. `github_id` is the ID of the team or group at this provider.';
+
+DROP SEQUENCE sso_team_ids;
+DROP TABLE sso_identities;
@@ -0,0 +1,15 @@
+CREATE TABLE sso_identities (
+    subject VARCHAR PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX sso_identities_user_id ON sso_identities (user_id);
+
+COMMENT ON TABLE sso_identities IS 'Identities at the single sign-on identity provider of a private deployment that users log in with.';
+COMMENT ON COLUMN sso_identities.subject IS 'The `sub` claim of the identity, which identifies it at the identity provider.';
+
+CREATE SEQUENCE sso_team_ids;
+
+COMMENT ON SEQUENCE sso_team_ids IS 'The `github_id` of teams that are mapped from groups of the single sign-on identity provider, which have no ID of their own.';
+COMMENT ON COLUMN teams.provider IS 'The identity provider of the team: 0 = GitHub team, 1 = GitLab group, 2 = team of the single sign-on identity provider. `github_id` is the ID of the team or group at this provider.';
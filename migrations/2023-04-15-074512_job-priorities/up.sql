@@ -0,0 +1,5 @@
+ALTER TABLE background_jobs ADD COLUMN priority SMALLINT NOT NULL DEFAULT 0;
+
+COMMENT ON COLUMN background_jobs.priority IS 'Jobs with a higher priority are run before jobs with a lower priority, so that index updates are not delayed by long running maintenance jobs.';
+
+CREATE INDEX background_jobs_priority_id ON background_jobs (priority DESC, id);
@@ -0,0 +1 @@
+COMMENT ON COLUMN security_log_entries.event IS '0 = login, 1 = token created, 2 = owner added, 3 = owner removed, 4 = email added, 5 = email removed, 6 = notification email changed, 7 = authenticator reset by the crates.io team';
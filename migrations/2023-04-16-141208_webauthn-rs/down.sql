@@ -0,0 +1,9 @@
+DELETE FROM webauthn_challenges;
+ALTER TABLE webauthn_challenges DROP COLUMN state;
+ALTER TABLE webauthn_challenges ADD COLUMN challenge BYTEA NOT NULL;
+
+DELETE FROM webauthn_credentials;
+ALTER TABLE webauthn_credentials DROP COLUMN security_key;
+ALTER TABLE webauthn_credentials ADD COLUMN public_key BYTEA NOT NULL;
+
+COMMENT ON COLUMN webauthn_credentials.public_key IS 'The ES256 public key of the credential, as an uncompressed P-256 point.';
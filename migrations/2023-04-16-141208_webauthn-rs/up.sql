@@ -0,0 +1,14 @@
+-- The credentials and the state of pending ceremonies are stored in the format of
+-- the `webauthn-rs` crate from now on. The existing credentials only consist of
+-- their public key and can't be converted, so they have to be registered again.
+DELETE FROM webauthn_credentials;
+ALTER TABLE webauthn_credentials DROP COLUMN public_key;
+ALTER TABLE webauthn_credentials ADD COLUMN security_key JSONB NOT NULL;
+
+COMMENT ON COLUMN webauthn_credentials.security_key IS 'The credential as serialized by the `webauthn-rs` crate, including its public key and signature counter.';
+
+DELETE FROM webauthn_challenges;
+ALTER TABLE webauthn_challenges DROP COLUMN challenge;
+ALTER TABLE webauthn_challenges ADD COLUMN state JSONB NOT NULL;
+
+COMMENT ON COLUMN webauthn_challenges.state IS 'The state of the ceremony as serialized by the `webauthn-rs` crate, including the challenge.';
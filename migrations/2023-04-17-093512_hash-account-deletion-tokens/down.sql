@@ -0,0 +1,5 @@
+ALTER TABLE account_deletions
+    ALTER COLUMN token TYPE VARCHAR
+    USING encode(token, 'hex');
+
+COMMENT ON COLUMN account_deletions.token IS 'The token that was emailed to the user to confirm the deletion, cleared once the account is deleted.';
@@ -0,0 +1,7 @@
+-- Pending requests with a plaintext token can't be confirmed anymore, and
+-- have to be requested again
+ALTER TABLE account_deletions
+    ALTER COLUMN token TYPE BYTEA
+    USING CASE WHEN token = '' THEN ''::bytea ELSE sha256(convert_to(token, 'UTF8')) END;
+
+COMMENT ON COLUMN account_deletions.token IS 'The SHA256 hash of the token that was emailed to the user to confirm the deletion, cleared once the account is deleted.';
@@ -0,0 +1,6 @@
+DELETE FROM admin_actions WHERE user_id IS NULL;
+
+ALTER TABLE admin_actions ALTER COLUMN user_id SET NOT NULL;
+
+COMMENT ON COLUMN admin_actions.user_id IS NULL;
+COMMENT ON COLUMN admin_actions.permission IS 'The admin permission that the action required, e.g. `crates:delete`.';
@@ -0,0 +1,4 @@
+ALTER TABLE admin_actions ALTER COLUMN user_id DROP NOT NULL;
+
+COMMENT ON COLUMN admin_actions.user_id IS 'The admin that took the action, or NULL for actions taken with the `crates-admin` command line tool.';
+COMMENT ON COLUMN admin_actions.permission IS 'The admin permission that the action required, e.g. `crates:delete`, or the `crates-admin` command that it was taken with, e.g. `yank-version`.';
@@ -0,0 +1,10 @@
+ALTER TABLE users ADD COLUMN account_suspended BOOLEAN NOT NULL DEFAULT FALSE;
+
+COMMENT ON COLUMN users.account_suspended IS 'Whether the account lock was set by a suspension of the crates.io team, which can be lifted through the admin API, unlike e.g. the lock of deleted accounts.';
+
+-- Accounts that are still locked with the reason of their suspension
+UPDATE users SET account_suspended = TRUE
+FROM admin_actions
+WHERE admin_actions.permission = 'users:suspend'
+    AND admin_actions.target = 'user ' || users.gh_login
+    AND admin_actions.reason = users.account_lock_reason;
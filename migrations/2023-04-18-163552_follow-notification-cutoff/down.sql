@@ -0,0 +1,14 @@
+ALTER TABLE follow_notification_settings ADD COLUMN last_action_id INTEGER;
+
+UPDATE follow_notification_settings
+SET last_action_id = COALESCE(
+    (SELECT max(id) FROM version_owner_actions WHERE time <= notified_until),
+    0
+);
+
+ALTER TABLE follow_notification_settings
+    ALTER COLUMN last_action_id SET NOT NULL,
+    DROP COLUMN webhook_secret,
+    DROP COLUMN notified_until;
+
+COMMENT ON COLUMN follow_notification_settings.last_action_id IS 'The newest version_owner_actions entry that the user was notified about.';
@@ -0,0 +1,17 @@
+ALTER TABLE follow_notification_settings
+    ADD COLUMN webhook_secret VARCHAR,
+    ADD COLUMN notified_until TIMESTAMP;
+
+UPDATE follow_notification_settings
+SET notified_until = COALESCE(
+    (SELECT time FROM version_owner_actions WHERE id = last_action_id),
+    last_notified_at
+);
+
+ALTER TABLE follow_notification_settings
+    ALTER COLUMN notified_until SET NOT NULL,
+    ALTER COLUMN notified_until SET DEFAULT now(),
+    DROP COLUMN last_action_id;
+
+COMMENT ON COLUMN follow_notification_settings.webhook_secret IS 'The secret that the payloads sent to the webhook URL are signed with, which is shown to the user when the URL is set.';
+COMMENT ON COLUMN follow_notification_settings.notified_until IS 'The user was notified about all version_owner_actions entries up to this time.';
@@ -21,6 +21,7 @@ export default Factory.extend({
 
   emailVerified: null,
   emailVerificationToken: null,
+  emailRevertToken: null,
 
   afterCreate(model) {
     if (model.emailVerified === null) {
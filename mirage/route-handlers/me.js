@@ -98,6 +98,19 @@ export function register(server) {
     return { ok: true };
   });
 
+  server.put('/api/v1/revert_email_change/:token', (schema, request) => {
+    let { token } = request.params;
+
+    let user = schema.users.findBy({ emailRevertToken: token });
+    if (!user) {
+      return new Response(400, {}, { errors: [{ detail: 'Email change belonging to token not found.' }] });
+    }
+
+    user.update({ emailRevertToken: null });
+
+    return { ok: true };
+  });
+
   server.get('/api/v1/me/crate_owner_invitations', function (schema) {
     let { user } = getSession(schema);
     if (!user) {
@@ -28,6 +28,7 @@ export default BaseSerializer.extend({
     }
 
     delete hash.email_verification_token;
+    delete hash.email_revert_token;
     delete hash.followed_crate_ids;
   },
 });
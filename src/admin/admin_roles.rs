@@ -0,0 +1,109 @@
+use crate::db;
+use crate::models::{AdminPermission, AdminRole, NewAdminAction, User};
+use anyhow::{anyhow, Result};
+use diesel::prelude::*;
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    name = "admin-roles",
+    about = "Manage the admin roles of the crates.io team and their permissions."
+)]
+pub enum Command {
+    /// List all roles with their permissions and the users they are assigned to
+    List,
+    /// Create a role with the given permissions
+    Create {
+        /// Name of the role, e.g. `support`
+        name: String,
+        /// The permissions of the role, e.g. `users:suspend tokens:revoke`.
+        /// Available are `crates:delete`, `crates:limits`, `crates:reserve`,
+        /// `integrity:read`, `tokens:revoke` and `users:suspend`.
+        #[arg(required = true)]
+        permissions: Vec<String>,
+    },
+    /// Assign a role to a user
+    Grant {
+        /// Name of the role
+        role: String,
+        /// Login of the user
+        login: String,
+    },
+    /// Remove a role from a user
+    Revoke {
+        /// Name of the role
+        role: String,
+        /// Login of the user
+        login: String,
+    },
+}
+
+pub fn run(command: Command) -> Result<()> {
+    let conn = &mut db::oneoff_connection()?;
+
+    match command {
+        Command::List => {
+            for role in AdminRole::all(conn)? {
+                let members = role.member_logins(conn)?;
+                println!(
+                    "{}\t{}\t{}",
+                    role.name,
+                    role.permissions.join(" "),
+                    members.join(" ")
+                );
+            }
+        }
+        Command::Create { name, permissions } => {
+            let permissions = permissions
+                .iter()
+                .map(|param| {
+                    AdminPermission::from_param(param)
+                        .ok_or_else(|| anyhow!("Unknown admin permission `{param}`"))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            AdminRole::create(conn, &name, &permissions)?;
+            println!("Created the role `{name}`");
+        }
+        Command::Grant { role, login } => {
+            let (role, user) = find_role_and_user(conn, &role, &login)?;
+            conn.transaction(|conn| {
+                role.assign(conn, &user)?;
+                let target = format!("role {} of user {login}", role.name);
+                NewAdminAction::command_line("admin-roles grant", &target, None).create(conn)
+            })?;
+            println!("Assigned the role `{}` to {login}", role.name);
+        }
+        Command::Revoke { role, login } => {
+            let (role, user) = find_role_and_user(conn, &role, &login)?;
+            let unassigned = conn.transaction(|conn| {
+                if !role.unassign(conn, &user)? {
+                    return Ok(false);
+                }
+                let target = format!("role {} of user {login}", role.name);
+                NewAdminAction::command_line("admin-roles revoke", &target, None).create(conn)?;
+                QueryResult::Ok(true)
+            })?;
+            if !unassigned {
+                return Err(anyhow!("{login} doesn't have the role `{}`", role.name));
+            }
+            println!("Removed the role `{}` from {login}", role.name);
+        }
+    }
+
+    Ok(())
+}
+
+fn find_role_and_user(
+    conn: &mut PgConnection,
+    role: &str,
+    login: &str,
+) -> Result<(AdminRole, User)> {
+    let role = AdminRole::find_by_name(conn, role)
+        .optional()?
+        .ok_or_else(|| anyhow!("Role `{role}` not found"))?;
+    let user = User::find_by_login(conn, login)
+        .optional()?
+        .ok_or_else(|| anyhow!("User `{login}` not found"))?;
+
+    Ok((role, user))
+}
@@ -0,0 +1,69 @@
+use crate::db;
+use crate::models::CrateTransfer;
+use crate::schema::{crate_transfers, crates, users};
+use anyhow::{anyhow, Result};
+use diesel::prelude::*;
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    name = "crate-transfers",
+    about = "Review and cancel pending crate ownership transfers."
+)]
+pub enum Command {
+    /// List all pending transfers, and when the accepted ones will complete
+    List,
+    /// Cancel a pending transfer, also during its waiting period
+    Cancel {
+        /// The ID of the transfer, as shown by `list`
+        id: i32,
+    },
+}
+
+pub fn run(command: Command) -> Result<()> {
+    let conn = &mut db::oneoff_connection()?;
+
+    match command {
+        Command::List => {
+            let transfers: Vec<(CrateTransfer, String)> = crate_transfers::table
+                .inner_join(crates::table)
+                .filter(crate_transfers::completed_at.is_null())
+                .filter(crate_transfers::cancelled_at.is_null())
+                .select((crate_transfers::all_columns, crates::name))
+                .order(crate_transfers::created_at)
+                .load(conn)?;
+
+            for (transfer, crate_name) in transfers {
+                let from_user: String = users::table
+                    .find(transfer.from_user_id)
+                    .select(users::gh_login)
+                    .first(conn)?;
+                let to_user: String = users::table
+                    .find(transfer.to_user_id)
+                    .select(users::gh_login)
+                    .first(conn)?;
+                let status = match transfer.completes_at {
+                    Some(completes_at) => format!("completes at {completes_at} UTC"),
+                    None => "waiting for acceptance".to_string(),
+                };
+
+                println!(
+                    "{}\t{crate_name}\t{from_user} -> {to_user}\t{status}",
+                    transfer.id
+                );
+            }
+        }
+        Command::Cancel { id } => {
+            let transfer = CrateTransfer::find_pending(conn, id)
+                .optional()?
+                .ok_or_else(|| anyhow!("Transfer {id} not found or no longer pending"))?;
+
+            if !transfer.cancel(conn)? {
+                return Err(anyhow!("Transfer {id} is no longer pending"));
+            }
+
+            println!("Transfer {id} was cancelled");
+        }
+    }
+
+    Ok(())
+}
@@ -1,4 +1,10 @@
-use crate::{admin::dialoguer, config, db, models::Crate, schema::crates};
+use crate::{
+    admin::dialoguer,
+    config, db,
+    models::{Crate, NewAdminAction},
+    schema::crates,
+    worker,
+};
 
 use diesel::prelude::*;
 use reqwest::blocking::Client;
@@ -50,6 +56,17 @@ fn delete(opts: Opts, conn: &mut PgConnection) {
         .unwrap();
     println!("  {n} deleted");
 
+    let target = format!("crate {}", krate.name);
+    NewAdminAction::command_line("delete-crate", &target, None)
+        .create(conn)
+        .unwrap();
+
+    if config::SearchBackendConfig::from_environment().needs_sync() {
+        worker::sync_search_index(krate.name.clone())
+            .enqueue(conn)
+            .unwrap();
+    }
+
     if !opts.yes && !dialoguer::confirm("commit?") {
         panic!("aborting transaction");
     }
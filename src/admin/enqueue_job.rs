@@ -1,6 +1,8 @@
 use crate::schema::background_jobs::dsl::*;
-use crate::{db, worker};
+use crate::schema::crates;
+use crate::{config, db, worker};
 use anyhow::Result;
+use chrono::{Duration, NaiveDate, Utc};
 use diesel::prelude::*;
 
 #[derive(clap::Parser, Debug)]
@@ -19,10 +21,79 @@ pub enum Command {
     },
     DailyDbMaintenance,
     SquashIndex,
+    /// Squash the index if its history has grown beyond the given number of commits
+    SquashIndexIfNeeded {
+        #[arg(long, env = "INDEX_SQUASH_MAX_COMMITS", default_value_t = 10000)]
+        max_commits: usize,
+    },
     NormalizeIndex {
         #[arg(long = "dry-run")]
         dry_run: bool,
     },
+    BackfillChecksums,
+    /// Update the documents of all crates in the external search index
+    SyncSearchIndex,
+    /// Notify users about new versions matching their saved searches
+    CheckSavedSearches,
+    /// Notify users about new and yanked versions of the crates they follow
+    CheckFollowedCrates,
+    /// Invalidate the paths that are queued for invalidation on the CDNs
+    ProcessCdnInvalidations,
+    /// Count the downloads from the access logs of the CDN
+    ProcessCdnLogs,
+    /// Delete expired crate owner invitations and remind users of the ones expiring soon
+    ProcessOwnerInvitations {
+        /// After how many days invitations expire
+        #[arg(
+            long,
+            env = "OWNERSHIP_INVITATIONS_EXPIRATION_DAYS",
+            default_value_t = config::DEFAULT_OWNERSHIP_INVITATIONS_EXPIRATION_DAYS
+        )]
+        expiration_days: u64,
+        /// How many days before an invitation expires the invited user is reminded of it
+        #[arg(long, default_value_t = 7)]
+        reminder_days: u64,
+    },
+    /// Recount the reverse dependencies of all crates
+    RefreshReverseDependencyCounts,
+    /// Copy the crate files and readmes that are not replicated yet to the replicas
+    ReplicateFiles,
+    /// Render the readmes again that were rendered by an outdated renderer version
+    RerenderReadmes,
+    /// Recompute the download trends of all crates
+    UpdateDownloadTrends,
+    /// Export the version downloads of a day to Parquet files in storage
+    ExportDownloads {
+        /// The day to export, defaults to yesterday
+        #[arg(long)]
+        date: Option<NaiveDate>,
+    },
+    /// Compare the index files of a random sample of crates with the database
+    CheckIndexConsistency {
+        /// The number of crates to check
+        #[arg(long, default_value_t = 1000)]
+        sample_size: i64,
+        /// Also check the sparse HTTP index
+        #[arg(long)]
+        sparse: bool,
+        /// Correct the index files that do not match the database
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Compare the checksums of the least recently checked crate files with the database
+    VerifyCrateFiles {
+        /// The number of crate files to check
+        #[arg(long, default_value_t = 1000)]
+        sample_size: i64,
+    },
+    /// Ask GitHub again about the least recently confirmed team memberships
+    SyncTeamMemberships {
+        /// The number of memberships to check
+        #[arg(long, default_value_t = 500)]
+        batch_size: i64,
+    },
+    /// Move the ownership of crates whose accepted transfers are past their waiting period
+    CompleteCrateTransfers,
 }
 
 pub fn run(command: Command) -> Result<()> {
@@ -50,6 +121,208 @@ pub fn run(command: Command) -> Result<()> {
         } => Ok(worker::dump_db(database_url, target_name).enqueue(conn)?),
         Command::DailyDbMaintenance => Ok(worker::daily_db_maintenance().enqueue(conn)?),
         Command::SquashIndex => Ok(worker::squash_index().enqueue(conn)?),
+        Command::SquashIndexIfNeeded { max_commits } => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq_any(["squash_index", "squash_index_if_needed"]))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!(
+                    "Did not enqueue squash_index_if_needed, existing job already in progress"
+                );
+                Ok(())
+            } else {
+                Ok(worker::squash_index_if_needed(max_commits).enqueue(conn)?)
+            }
+        }
         Command::NormalizeIndex { dry_run } => Ok(worker::normalize_index(dry_run).enqueue(conn)?),
+        Command::BackfillChecksums => Ok(worker::backfill_checksums(0).enqueue(conn)?),
+        Command::SyncSearchIndex => {
+            let names: Vec<String> = crates::table.select(crates::name).load(conn)?;
+            for name in names {
+                worker::sync_search_index(name).enqueue(conn)?;
+            }
+            Ok(())
+        }
+        Command::CheckSavedSearches => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq("check_saved_searches"))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!("Did not enqueue check_saved_searches, existing job already in progress");
+                Ok(())
+            } else {
+                Ok(worker::check_saved_searches().enqueue(conn)?)
+            }
+        }
+        Command::CheckFollowedCrates => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq("check_followed_crates"))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!("Did not enqueue check_followed_crates, existing job already in progress");
+                Ok(())
+            } else {
+                Ok(worker::check_followed_crates().enqueue(conn)?)
+            }
+        }
+        Command::ProcessCdnInvalidations => Ok(worker::process_cdn_invalidations().enqueue(conn)?),
+        Command::ProcessCdnLogs => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq("process_cdn_logs"))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!("Did not enqueue process_cdn_logs, existing job already in progress");
+                Ok(())
+            } else {
+                Ok(worker::process_cdn_logs().enqueue(conn)?)
+            }
+        }
+        Command::ProcessOwnerInvitations {
+            expiration_days,
+            reminder_days,
+        } => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq("process_owner_invitations"))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!(
+                    "Did not enqueue process_owner_invitations, existing job already in progress"
+                );
+                Ok(())
+            } else {
+                let job = worker::process_owner_invitations(expiration_days, reminder_days);
+                Ok(job.enqueue(conn)?)
+            }
+        }
+        Command::RefreshReverseDependencyCounts => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq("refresh_reverse_dependency_counts"))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!(
+                    "Did not enqueue refresh_reverse_dependency_counts, existing job already in progress"
+                );
+                Ok(())
+            } else {
+                Ok(worker::refresh_reverse_dependency_counts().enqueue(conn)?)
+            }
+        }
+        Command::ReplicateFiles => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq("replicate_files"))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!("Did not enqueue replicate_files, existing job already in progress");
+                Ok(())
+            } else {
+                Ok(worker::replicate_files().enqueue(conn)?)
+            }
+        }
+        Command::RerenderReadmes => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq("rerender_readmes"))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!("Did not enqueue rerender_readmes, existing job already in progress");
+                Ok(())
+            } else {
+                Ok(worker::rerender_readmes(0).enqueue(conn)?)
+            }
+        }
+        Command::UpdateDownloadTrends => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq("update_download_trends"))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!(
+                    "Did not enqueue update_download_trends, existing job already in progress"
+                );
+                Ok(())
+            } else {
+                Ok(worker::update_download_trends().enqueue(conn)?)
+            }
+        }
+        Command::ExportDownloads { date } => {
+            let date = date.unwrap_or_else(|| Utc::now().date_naive() - Duration::days(1));
+            Ok(worker::export_downloads(date).enqueue(conn)?)
+        }
+        Command::CheckIndexConsistency {
+            sample_size,
+            sparse,
+            fix,
+        } => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq("check_index_consistency"))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!(
+                    "Did not enqueue check_index_consistency, existing job already in progress"
+                );
+                Ok(())
+            } else {
+                Ok(worker::check_index_consistency(sample_size, sparse, fix).enqueue(conn)?)
+            }
+        }
+        Command::VerifyCrateFiles { sample_size } => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq("verify_crate_files"))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!("Did not enqueue verify_crate_files, existing job already in progress");
+                Ok(())
+            } else {
+                Ok(worker::verify_crate_files(sample_size).enqueue(conn)?)
+            }
+        }
+        Command::SyncTeamMemberships { batch_size } => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq("sync_team_memberships"))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!("Did not enqueue sync_team_memberships, existing job already in progress");
+                Ok(())
+            } else {
+                Ok(worker::sync_team_memberships(batch_size).enqueue(conn)?)
+            }
+        }
+        Command::CompleteCrateTransfers => {
+            let count: i64 = background_jobs
+                .filter(job_type.eq("complete_crate_transfers"))
+                .count()
+                .get_result(conn)?;
+
+            if count > 0 {
+                println!(
+                    "Did not enqueue complete_crate_transfers, existing job already in progress"
+                );
+                Ok(())
+            } else {
+                Ok(worker::complete_crate_transfers().enqueue(conn)?)
+            }
+        }
     }
 }
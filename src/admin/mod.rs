@@ -1,3 +1,5 @@
+pub mod admin_roles;
+pub mod crate_transfers;
 pub mod delete_crate;
 pub mod delete_version;
 pub mod dialoguer;
@@ -6,9 +8,15 @@ pub mod git_import;
 pub mod migrate;
 pub mod on_call;
 pub mod populate;
+pub mod rebuild_index;
 pub mod render_readmes;
+pub mod reserved_names;
+pub mod reserved_prefixes;
+pub mod reset_totp;
+pub mod set_upload_limit;
 pub mod test_pagerduty;
 pub mod transfer_crates;
 pub mod upload_index;
+pub mod verify_index_signatures;
 pub mod verify_token;
 pub mod yank_version;
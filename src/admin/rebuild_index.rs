@@ -0,0 +1,143 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::process::Command;
+
+use cargo_registry_index::{Repository, RepositoryConfig};
+use chrono::Utc;
+use diesel::prelude::*;
+use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
+
+use crate::models::Crate;
+use crate::schema::crates;
+use crate::{admin::dialoguer, db};
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    name = "rebuild-index",
+    about = "Regenerate all index files from the database into a new branch of the git index"
+)]
+pub struct Opts {
+    /// The branch to push the rebuilt index to, defaults to `rebuild-<date>`
+    #[arg(long)]
+    branch: Option<String>,
+
+    /// Also push the rebuilt index to `master` after confirmation
+    #[arg(long)]
+    swap: bool,
+}
+
+/// Differences between the index and the database.
+#[derive(Default)]
+struct Discrepancies {
+    /// Crates that are missing from the index.
+    missing: Vec<String>,
+    /// Crates whose index file doesn't match the database.
+    changed: Vec<String>,
+    /// Index files of crates that don't exist in the database.
+    extra: Vec<String>,
+}
+
+impl Discrepancies {
+    fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.changed.is_empty() && self.extra.is_empty()
+    }
+
+    fn print(&self) {
+        for (description, names) in [
+            ("missing from the index", &self.missing),
+            ("different in the index", &self.changed),
+            ("in the index but not in the database", &self.extra),
+        ] {
+            println!("{} crates are {description}", names.len());
+            for name in names {
+                println!("  {name}");
+            }
+        }
+    }
+}
+
+pub fn run(opts: Opts) -> anyhow::Result<()> {
+    let conn = &mut db::oneoff_connection()?;
+
+    println!("fetching git repo");
+    let config = RepositoryConfig::from_environment();
+    let repo = Repository::open(&config)?;
+    repo.reset_head()?;
+    println!("HEAD is at {}", repo.head_oid()?);
+
+    // Other files in the index, like `config.json`, are left alone
+    let existing = repo
+        .get_files_modified_since(None)?
+        .iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?;
+            (Repository::relative_index_file(name) == *path).then(|| name.to_string())
+        })
+        .collect::<BTreeSet<_>>();
+
+    let krates: Vec<Crate> = Crate::all().order(crates::name).load(conn)?;
+    println!("rebuilding the index files of {} crates", krates.len());
+
+    let pb = ProgressBar::new(krates.len() as u64);
+    pb.set_style(ProgressStyle::with_template("{bar:60} ({pos}/{len}, ETA {eta})").unwrap());
+
+    let mut discrepancies = Discrepancies::default();
+    let mut rebuilt = BTreeSet::new();
+    for krate in krates.iter().progress_with(pb) {
+        let Some(contents) = krate.index_file(conn)? else {
+            continue;
+        };
+
+        rebuilt.insert(krate.name.to_lowercase());
+
+        let path = repo.index_file(&krate.name);
+        match fs::read_to_string(&path) {
+            Ok(previous) if previous == contents => continue,
+            Ok(_) => discrepancies.changed.push(krate.name.clone()),
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                discrepancies.missing.push(krate.name.clone())
+            }
+            Err(error) => return Err(error.into()),
+        }
+
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, contents)?;
+    }
+
+    for name in existing.difference(&rebuilt) {
+        discrepancies.extra.push(name.clone());
+        fs::remove_file(repo.index_file(name))?;
+    }
+
+    if discrepancies.is_empty() {
+        println!("the index matches the database");
+        return Ok(());
+    }
+
+    discrepancies.print();
+
+    let branch = opts
+        .branch
+        .unwrap_or_else(|| format!("rebuild-{}", Utc::now().format("%Y-%m-%d")));
+    if !dialoguer::confirm(&format!("push the rebuilt index to `{branch}`?")) {
+        return Ok(());
+    }
+
+    let oid = repo.commit_all("Rebuild index from the database")?;
+    repo.run_command(Command::new("git").args([
+        "push",
+        "origin",
+        &format!("HEAD:refs/heads/{branch}"),
+    ]))?;
+    println!("pushed {oid} to `{branch}`");
+
+    if opts.swap && dialoguer::confirm("push the rebuilt index to `master`?") {
+        // This is not a forced push, so it fails if `master` has moved on
+        // since the index was fetched, instead of discarding those commits.
+        repo.run_command(Command::new("git").args(["push", "origin", "HEAD:refs/heads/master"]))?;
+        println!("pushed {oid} to `master`");
+    }
+
+    Ok(())
+}
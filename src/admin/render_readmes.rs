@@ -1,18 +1,15 @@
 use crate::{
     config, db,
     models::Version,
-    schema::{crates, readme_renderings, versions},
-    uploaders::Uploader,
+    schema::{crate_files, crates, readme_renderings, versions},
+    worker::readmes::get_readme,
 };
-use anyhow::{anyhow, Context};
-use std::{io::Read, path::Path, sync::Arc, thread};
+use anyhow::Context;
+use std::{sync::Arc, thread};
 
-use cargo_registry_markdown::text_to_html;
 use chrono::{TimeZone, Utc};
 use diesel::prelude::*;
-use flate2::read::GzDecoder;
-use reqwest::{blocking::Client, header};
-use tar::{self, Archive};
+use reqwest::blocking::Client;
 
 const USER_AGENT: &str = "crates-admin";
 
@@ -84,7 +81,7 @@ pub fn run(opts: Opts) -> anyhow::Result<()> {
         total_pages + 1
     };
 
-    let client = Client::new();
+    let client = Client::builder().user_agent(USER_AGENT).build()?;
 
     for (page_num, version_ids_chunk) in version_ids.chunks(page_size).enumerate() {
         println!(
@@ -93,15 +90,20 @@ pub fn run(opts: Opts) -> anyhow::Result<()> {
             total_pages
         );
 
-        let versions: Vec<(Version, String)> = versions::table
+        let versions: Vec<(Version, String, Option<String>)> = versions::table
             .inner_join(crates::table)
+            .left_outer_join(crate_files::table)
             .filter(versions::id.eq_any(version_ids_chunk))
-            .select((versions::all_columns, crates::name))
+            .select((
+                versions::all_columns,
+                crates::name,
+                crate_files::object_key.nullable(),
+            ))
             .load(conn)
             .expect("error loading versions");
 
         let mut tasks = Vec::with_capacity(page_size);
-        for (version, krate_name) in versions {
+        for (version, krate_name, object_key) in versions {
             Version::record_readme_rendering(version.id, conn)
                 .context("Couldn't record rendering time")?;
 
@@ -109,7 +111,13 @@ pub fn run(opts: Opts) -> anyhow::Result<()> {
             let base_config = base_config.clone();
             let handle = thread::spawn::<_, anyhow::Result<()>>(move || {
                 println!("[{}-{}] Rendering README...", krate_name, version.num);
-                let readme = get_readme(base_config.uploader(), &client, &version, &krate_name)?;
+                let readme = get_readme(
+                    base_config.uploader(),
+                    &client,
+                    &krate_name,
+                    &version.num,
+                    object_key.as_deref(),
+                )?;
 
                 base_config
                     .uploader()
@@ -131,219 +139,3 @@ pub fn run(opts: Opts) -> anyhow::Result<()> {
 
     Ok(())
 }
-
-/// Renders the readme of an uploaded crate version.
-fn get_readme(
-    uploader: &Uploader,
-    client: &Client,
-    version: &Version,
-    krate_name: &str,
-) -> anyhow::Result<String> {
-    let pkg_name = format!("{}-{}", krate_name, version.num);
-
-    let location = uploader.crate_location(krate_name, &version.num.to_string());
-
-    let location = match uploader {
-        Uploader::S3 { .. } => location,
-        Uploader::Local => format!("http://localhost:8888/{location}"),
-    };
-
-    let mut extra_headers = header::HeaderMap::new();
-    extra_headers.insert(
-        header::USER_AGENT,
-        header::HeaderValue::from_static(USER_AGENT),
-    );
-    let request = client.get(location).headers(extra_headers);
-    let response = request.send().context("Failed to fetch crate")?;
-
-    if !response.status().is_success() {
-        return Err(anyhow!(
-            "Failed to get a 200 response: {}",
-            response.text().unwrap()
-        ));
-    }
-
-    let reader = GzDecoder::new(response);
-    let archive = Archive::new(reader);
-    render_pkg_readme(archive, &pkg_name)
-}
-
-fn render_pkg_readme<R: Read>(mut archive: Archive<R>, pkg_name: &str) -> anyhow::Result<String> {
-    let mut entries = archive.entries().context("Invalid tar archive entries")?;
-
-    let manifest: Manifest = {
-        let path = format!("{pkg_name}/Cargo.toml");
-        let contents = find_file_by_path(&mut entries, Path::new(&path))
-            .context("Failed to read Cargo.toml file")?;
-
-        toml::from_str(&contents).context("Failed to parse manifest file")?
-    };
-
-    let rendered = {
-        let readme_path = manifest
-            .package
-            .readme
-            .clone()
-            .unwrap_or_else(|| "README.md".into());
-        let path = format!("{pkg_name}/{readme_path}");
-        let contents = find_file_by_path(&mut entries, Path::new(&path))
-            .with_context(|| format!("Failed to read {readme_path} file"))?;
-
-        // pkg_path_in_vcs Unsupported from admin::render_readmes. See #4095
-        // Would need access to cargo_vcs_info
-        let pkg_path_in_vcs = None;
-
-        text_to_html(
-            &contents,
-            &readme_path,
-            manifest.package.repository.as_deref(),
-            pkg_path_in_vcs,
-        )
-    };
-    return Ok(rendered);
-
-    #[derive(Debug, Deserialize)]
-    struct Package {
-        readme: Option<String>,
-        repository: Option<String>,
-    }
-
-    #[derive(Debug, Deserialize)]
-    struct Manifest {
-        package: Package,
-    }
-}
-
-/// Search an entry by its path in a Tar archive.
-fn find_file_by_path<R: Read>(
-    entries: &mut tar::Entries<'_, R>,
-    path: &Path,
-) -> anyhow::Result<String> {
-    let mut file = entries
-        .filter_map(|entry| entry.ok())
-        .find(|file| match file.path() {
-            Ok(p) => p == path,
-            Err(_) => false,
-        })
-        .ok_or_else(|| anyhow!("Failed to find tarball entry: {}", path.display()))?;
-
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)
-        .context("Failed to read file contents")?;
-
-    Ok(contents)
-}
-
-#[cfg(test)]
-pub mod tests {
-    use std::io::Write;
-    use tar;
-
-    use super::render_pkg_readme;
-
-    pub fn add_file<W: Write>(pkg: &mut tar::Builder<W>, path: &str, content: &[u8]) {
-        let mut header = tar::Header::new_gnu();
-        header.set_size(content.len() as u64);
-        header.set_cksum();
-        pkg.append_data(&mut header, path, content).unwrap();
-    }
-
-    #[test]
-    fn test_render_pkg_readme() {
-        let mut pkg = tar::Builder::new(vec![]);
-        add_file(
-            &mut pkg,
-            "foo-0.0.1/Cargo.toml",
-            br#"
-[package]
-readme = "README.md"
-"#,
-        );
-        add_file(&mut pkg, "foo-0.0.1/README.md", b"readme");
-        let serialized_archive = pkg.into_inner().unwrap();
-        let result =
-            render_pkg_readme(tar::Archive::new(&*serialized_archive), "foo-0.0.1").unwrap();
-        assert!(result.contains("readme"))
-    }
-
-    #[test]
-    fn test_render_pkg_no_readme() {
-        let mut pkg = tar::Builder::new(vec![]);
-        add_file(
-            &mut pkg,
-            "foo-0.0.1/Cargo.toml",
-            br#"
-[package]
-"#,
-        );
-        let serialized_archive = pkg.into_inner().unwrap();
-        assert_err!(render_pkg_readme(
-            tar::Archive::new(&*serialized_archive),
-            "foo-0.0.1"
-        ));
-    }
-
-    #[test]
-    fn test_render_pkg_implicit_readme() {
-        let mut pkg = tar::Builder::new(vec![]);
-        add_file(
-            &mut pkg,
-            "foo-0.0.1/Cargo.toml",
-            br#"
-[package]
-"#,
-        );
-        add_file(&mut pkg, "foo-0.0.1/README.md", b"readme");
-        let serialized_archive = pkg.into_inner().unwrap();
-        let result =
-            render_pkg_readme(tar::Archive::new(&*serialized_archive), "foo-0.0.1").unwrap();
-        assert!(result.contains("readme"))
-    }
-
-    #[test]
-    fn test_render_pkg_readme_w_link() {
-        let mut pkg = tar::Builder::new(vec![]);
-        add_file(
-            &mut pkg,
-            "foo-0.0.1/Cargo.toml",
-            br#"
-[package]
-readme = "README.md"
-repository = "https://github.com/foo/foo"
-"#,
-        );
-        add_file(
-            &mut pkg,
-            "foo-0.0.1/README.md",
-            b"readme [link](./Other.md)",
-        );
-        let serialized_archive = pkg.into_inner().unwrap();
-        let result =
-            render_pkg_readme(tar::Archive::new(&*serialized_archive), "foo-0.0.1").unwrap();
-        assert!(result.contains("\"https://github.com/foo/foo/blob/HEAD/./Other.md\""))
-    }
-
-    #[test]
-    fn test_render_pkg_readme_not_at_root() {
-        let mut pkg = tar::Builder::new(vec![]);
-        add_file(
-            &mut pkg,
-            "foo-0.0.1/Cargo.toml",
-            br#"
-[package]
-readme = "docs/README.md"
-repository = "https://github.com/foo/foo"
-"#,
-        );
-        add_file(
-            &mut pkg,
-            "foo-0.0.1/docs/README.md",
-            b"docs/readme [link](./Other.md)",
-        );
-        let serialized_archive = pkg.into_inner().unwrap();
-        let result =
-            render_pkg_readme(tar::Archive::new(&*serialized_archive), "foo-0.0.1").unwrap();
-        assert!(result.contains("docs/readme"));
-        assert!(result.contains("\"https://github.com/foo/foo/blob/HEAD/docs/./Other.md\""))
-    }
-}
@@ -0,0 +1,75 @@
+use crate::schema::reserved_crate_names;
+use crate::{admin::dialoguer, db, sql::canon_crate_name};
+use anyhow::{anyhow, Result};
+use diesel::prelude::*;
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    name = "reserved-names",
+    about = "Manage the crate names that nobody is allowed to publish."
+)]
+pub enum Command {
+    /// List all reserved crate names
+    List,
+    /// Reserve a crate name. Fails if a crate with that name already exists.
+    Reserve {
+        /// The name to reserve
+        name: String,
+    },
+    /// Release a reserved crate name, allowing it to be published again
+    Release {
+        /// The reserved name to release
+        name: String,
+        /// Don't ask for confirmation: yes, we are sure. Best for scripting.
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+pub fn run(command: Command) -> Result<()> {
+    let conn = &mut db::oneoff_connection()?;
+
+    match command {
+        Command::List => {
+            let names: Vec<String> = reserved_crate_names::table
+                .select(reserved_crate_names::name)
+                .order(reserved_crate_names::name)
+                .load(conn)?;
+
+            for name in names {
+                println!("{name}");
+            }
+        }
+        Command::Reserve { name } => {
+            // The `ensure_reserved_name_not_in_use` trigger rejects names
+            // of existing crates, so only the error message is needed here.
+            diesel::insert_into(reserved_crate_names::table)
+                .values(reserved_crate_names::name.eq(&name))
+                .execute(conn)
+                .map_err(|error| anyhow!("Failed to reserve `{name}`: {error}"))?;
+
+            println!("`{name}` is now reserved");
+        }
+        Command::Release { name, yes } => {
+            let reserved = reserved_crate_names::table
+                .select(reserved_crate_names::name)
+                .filter(canon_crate_name(reserved_crate_names::name).eq(canon_crate_name(&name)))
+                .first::<String>(conn)
+                .optional()?
+                .ok_or_else(|| anyhow!("`{name}` is not a reserved crate name"))?;
+
+            if !yes {
+                let prompt = format!("Are you sure you want to release `{reserved}`?");
+                if !dialoguer::confirm(&prompt) {
+                    return Ok(());
+                }
+            }
+
+            diesel::delete(reserved_crate_names::table.find(&reserved)).execute(conn)?;
+
+            println!("`{reserved}` is no longer reserved");
+        }
+    }
+
+    Ok(())
+}
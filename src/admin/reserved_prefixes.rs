@@ -0,0 +1,113 @@
+use crate::models::{Crate, Team};
+use crate::schema::{crates, reserved_crate_prefixes, teams};
+use crate::sql::{canon_crate_name, lower, starts_with};
+use crate::{admin::dialoguer, db};
+use anyhow::{anyhow, Result};
+use diesel::prelude::*;
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    name = "reserved-prefixes",
+    about = "Manage crate name prefixes that are reserved for the members of a team."
+)]
+pub enum Command {
+    /// List all reserved prefixes and the teams they belong to
+    List,
+    /// Reserve a prefix for a team. Existing crates with the prefix keep their
+    /// current owners.
+    Reserve {
+        /// The prefix to reserve, e.g. `mycorp-`
+        prefix: String,
+        /// Login of the team, e.g. `github:mycorp:crates-team`
+        team: String,
+        /// Don't ask for confirmation: yes, we are sure. Best for scripting.
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Release a reserved prefix
+    Release {
+        /// The reserved prefix to release
+        prefix: String,
+    },
+}
+
+pub fn run(command: Command) -> Result<()> {
+    let conn = &mut db::oneoff_connection()?;
+
+    match command {
+        Command::List => {
+            let reservations: Vec<(String, String)> = reserved_crate_prefixes::table
+                .inner_join(teams::table)
+                .select((reserved_crate_prefixes::prefix, teams::login))
+                .order(reserved_crate_prefixes::prefix)
+                .load(conn)?;
+
+            for (prefix, team) in reservations {
+                println!("{prefix}\t{team}");
+            }
+        }
+        Command::Reserve { prefix, team, yes } => {
+            if !Crate::valid_name(&prefix) {
+                return Err(anyhow!("`{prefix}` is not a valid crate name prefix"));
+            }
+
+            let team: Team = teams::table
+                .filter(lower(teams::login).eq(team.to_lowercase()))
+                .first(conn)
+                .optional()?
+                .ok_or_else(|| {
+                    anyhow!("Team `{team}` not found. Add it as an owner of a crate first.")
+                })?;
+
+            let existing: Vec<String> = crates::table
+                .select(crates::name)
+                .filter(starts_with(
+                    canon_crate_name(crates::name),
+                    canon_crate_name(&prefix),
+                ))
+                .order(crates::name)
+                .load(conn)?;
+
+            if !existing.is_empty() {
+                println!(
+                    "The following crates already match `{prefix}` and will keep their current owners:"
+                );
+                for name in &existing {
+                    println!("  {name}");
+                }
+            }
+
+            if !yes {
+                let prompt = format!("Reserve `{prefix}` for `{}`?", team.login);
+                if !dialoguer::confirm(&prompt) {
+                    return Ok(());
+                }
+            }
+
+            diesel::insert_into(reserved_crate_prefixes::table)
+                .values((
+                    reserved_crate_prefixes::prefix.eq(&prefix),
+                    reserved_crate_prefixes::team_id.eq(team.id),
+                ))
+                .execute(conn)
+                .map_err(|error| anyhow!("Failed to reserve `{prefix}`: {error}"))?;
+
+            println!("`{prefix}` is now reserved for `{}`", team.login);
+        }
+        Command::Release { prefix } => {
+            let deleted = diesel::delete(reserved_crate_prefixes::table)
+                .filter(
+                    canon_crate_name(reserved_crate_prefixes::prefix).eq(canon_crate_name(&prefix)),
+                )
+                .execute(conn)?;
+
+            if deleted == 0 {
+                return Err(anyhow!("`{prefix}` is not a reserved prefix"));
+            }
+
+            println!("`{prefix}` is no longer reserved");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use crate::{
+    admin::dialoguer,
+    db,
+    models::{NewAdminAction, SecurityEvent, SecurityLogEntry, TotpCredential, User},
+    schema::users,
+};
+
+use diesel::prelude::*;
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    name = "reset-totp",
+    about = "Remove the one-time password authenticator and recovery codes of a user.",
+    after_help = "Only use this after the identity of the user was verified through a \
+        support request, since it disables the second factor of their account."
+)]
+pub struct Opts {
+    /// GitHub login of the user
+    login: String,
+    /// Reference of the verified support request, for the security log of the user
+    #[arg(long)]
+    support_request: String,
+    /// Don't ask for confirmation: yes, we are sure. Best for scripting.
+    #[arg(short, long)]
+    yes: bool,
+}
+
+pub fn run(opts: Opts) {
+    let conn = &mut db::oneoff_connection().unwrap();
+
+    let user: User = users::table
+        .filter(users::gh_login.eq(&opts.login))
+        .first(conn)
+        .unwrap();
+
+    let Some(credential) = TotpCredential::find(conn, user.id).unwrap() else {
+        println!("`{}` has no authenticator", user.gh_login);
+        return;
+    };
+
+    if !opts.yes {
+        let prompt = format!(
+            "Are you sure you want to remove the authenticator of `{}` (support request {})?",
+            user.gh_login, opts.support_request
+        );
+        if !dialoguer::confirm(&prompt) {
+            return;
+        }
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        credential.delete(conn)?;
+
+        let details = format!("support request {}", opts.support_request);
+        SecurityLogEntry::record(
+            conn,
+            user.id,
+            SecurityEvent::TotpReset,
+            Some(&details),
+            None,
+            None,
+        )?;
+
+        let target = format!("user {}", user.gh_login);
+        NewAdminAction::command_line("reset-totp", &target, Some(&details)).create(conn)?;
+        Ok(())
+    })
+    .unwrap();
+
+    info!(
+        login = %user.gh_login,
+        user_id = user.id,
+        support_request = %opts.support_request,
+        "Removed the authenticator of the user"
+    );
+    println!("The authenticator of `{}` was removed", user.gh_login);
+}
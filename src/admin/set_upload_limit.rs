@@ -0,0 +1,54 @@
+use crate::{admin::dialoguer, db, models::Crate, schema::crates};
+
+use diesel::prelude::*;
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    name = "set-upload-limit",
+    about = "Override the maximum upload size of a crate."
+)]
+pub struct Opts {
+    /// Name of the crate
+    crate_name: String,
+    /// Maximum upload size in bytes. If omitted, the override is removed and
+    /// the global limit applies again.
+    max_upload_size: Option<u32>,
+    /// Don't ask for confirmation: yes, we are sure. Best for scripting.
+    #[arg(short, long)]
+    yes: bool,
+}
+
+pub fn run(opts: Opts) {
+    let conn = &mut db::oneoff_connection().unwrap();
+
+    let krate: Crate = Crate::by_name(&opts.crate_name).first(conn).unwrap();
+
+    let current = match krate.max_upload_size {
+        Some(size) => format!("{size} bytes"),
+        None => "the global limit".to_string(),
+    };
+    let new = match opts.max_upload_size {
+        Some(size) => format!("{size} bytes"),
+        None => "the global limit".to_string(),
+    };
+
+    println!("The maximum upload size of `{}` is {current}", krate.name);
+
+    if !opts.yes {
+        let prompt = format!("Do you want to change it to {new}?");
+        if !dialoguer::confirm(&prompt) {
+            return;
+        }
+    }
+
+    let max_upload_size = opts
+        .max_upload_size
+        .map(|size| i32::try_from(size).expect("max upload size is too large"));
+
+    diesel::update(&krate)
+        .set(crates::max_upload_size.eq(max_upload_size))
+        .execute(conn)
+        .unwrap();
+
+    println!("The maximum upload size of `{}` is now {new}", krate.name);
+}
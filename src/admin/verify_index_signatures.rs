@@ -0,0 +1,43 @@
+use cargo_registry_index::{Repository, RepositoryConfig, SigningFormat, VerifyingKey};
+use std::path::PathBuf;
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    name = "verify-index-signatures",
+    about = "Verify that the most recent commits of the git index are signed with the given key"
+)]
+pub struct Opts {
+    /// Path to the public key that the commits are expected to be signed with
+    public_key: PathBuf,
+
+    /// The format of the public key, `openpgp` or `ssh`
+    #[arg(long, default_value = "openpgp")]
+    format: SigningFormat,
+
+    /// The number of commits to check, starting from `HEAD`
+    #[arg(long, default_value_t = 100)]
+    limit: usize,
+}
+
+pub fn run(opts: Opts) -> anyhow::Result<()> {
+    let key = VerifyingKey {
+        format: opts.format,
+        key: std::fs::read_to_string(&opts.public_key)?,
+    };
+
+    println!("fetching git repo");
+    let config = RepositoryConfig::from_environment();
+    let repo = Repository::open(&config)?;
+    println!("HEAD is at {}", repo.head_oid()?);
+
+    let unverified = repo.unverified_commits(&key, opts.limit)?;
+    if unverified.is_empty() {
+        println!("all checked commits are signed with the given key");
+        return Ok(());
+    }
+
+    for oid in &unverified {
+        println!("commit {oid} is not signed with the given key");
+    }
+    anyhow::bail!("{} commits failed verification", unverified.len())
+}
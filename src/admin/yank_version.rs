@@ -1,7 +1,7 @@
 use crate::{
     admin::dialoguer,
     db,
-    models::{Crate, Version},
+    models::{Crate, NewAdminAction, Version},
     schema::versions,
 };
 
@@ -64,6 +64,11 @@ fn yank(opts: Opts, conn: &mut PgConnection) {
         .execute(conn)
         .unwrap();
 
+    let target = format!("version {} of crate {}", v.num, krate.name);
+    NewAdminAction::command_line("yank-version", &target, None)
+        .create(conn)
+        .unwrap();
+
     crate::worker::sync_yanked(krate.name, v.num)
         .enqueue(conn)
         .unwrap();
@@ -9,7 +9,11 @@ use std::{sync::Arc, time::Duration};
 use crate::downloads_counter::DownloadsCounter;
 use crate::email::Emails;
 use crate::github::{GitHubClient, RealGitHubClient};
+use crate::gitlab::{GitLabClient, RealGitLabClient, GITLAB_URL};
 use crate::metrics::{InstanceMetrics, ServiceMetrics};
+use crate::search::{self, SearchBackend};
+use crate::sso::SsoClient;
+use crate::views::EncodableCrateSuggestion;
 use axum::extract::{FromRef, FromRequestParts, State};
 use diesel::r2d2;
 use moka::future::{Cache, CacheBuilder};
@@ -32,6 +36,16 @@ pub struct App {
     /// The GitHub OAuth2 configuration
     pub github_oauth: BasicClient,
 
+    /// GitLab API client
+    pub gitlab: Box<dyn GitLabClient>,
+
+    /// The GitLab OAuth2 configuration, if logging in with GitLab is enabled
+    pub gitlab_oauth: Option<BasicClient>,
+
+    /// The OAuth2 configuration of the single sign-on identity provider, if
+    /// logging in with single sign-on is enabled
+    pub sso_oauth: Option<SsoClient>,
+
     /// The server configuration
     pub config: config::Server,
 
@@ -41,11 +55,24 @@ pub struct App {
     /// `version_id` is only cached under the canonical spelling of the crate name.
     pub(crate) version_id_cacher: Cache<(String, String), i32>,
 
+    /// Cache the crate name suggestions of a `canonical_prefix:limit` pair
+    ///
+    /// This is used by the suggest endpoint, which is called on every keystroke in the search box.
+    pub(crate) suggestions_cacher: Cache<(String, i64), Arc<Vec<EncodableCrateSuggestion>>>,
+
+    /// Cache the distinct license expressions of all versions
+    ///
+    /// This is used by the license filter of the search, which has to evaluate every expression.
+    pub(crate) licenses_cacher: Cache<(), Arc<Vec<String>>>,
+
     /// Count downloads and periodically persist them in the database
     pub downloads_counter: DownloadsCounter,
 
     /// Backend used to send emails
-    pub emails: Emails,
+    pub emails: Arc<Emails>,
+
+    /// Backend used to match crates against the text of search queries
+    pub search: Box<dyn SearchBackend>,
 
     /// Metrics related to the service as a whole
     pub service_metrics: ServiceMetrics,
@@ -72,17 +99,19 @@ impl App {
     ///
     /// Configures and sets up:
     ///
-    /// - GitHub OAuth
+    /// - GitHub, GitLab and single sign-on OAuth
     /// - Database connection pools
     /// - A `git2::Repository` instance from the index repo checkout (that server.rs ensures exists)
     pub fn new(config: config::Server, http_client: Option<Client>) -> App {
-        use oauth2::{AuthUrl, TokenUrl};
+        use oauth2::{AuthUrl, RedirectUrl, TokenUrl};
 
         let instance_metrics =
             InstanceMetrics::new().expect("could not initialize instance metrics");
 
         let github = Box::new(RealGitHubClient::new(http_client.clone()));
 
+        let search = search::from_config(&config, http_client.clone());
+
         let github_oauth = BasicClient::new(
             config.gh_client_id.clone(),
             Some(config.gh_client_secret.clone()),
@@ -92,6 +121,40 @@ impl App {
             ),
         );
 
+        let gitlab = Box::new(RealGitLabClient::new(http_client.clone()));
+
+        let gitlab_oauth = match (&config.gitlab_client_id, &config.gitlab_client_secret) {
+            (Some(client_id), Some(client_secret)) => {
+                // Unlike GitHub, GitLab requires the callback URL in every request
+                let redirect_url = dotenv::var("GITLAB_REDIRECT_URL").unwrap_or_else(|_| {
+                    format!("https://{}/github-redirect.html", config.domain_name)
+                });
+                let client = BasicClient::new(
+                    client_id.clone(),
+                    Some(client_secret.clone()),
+                    AuthUrl::new(format!("{GITLAB_URL}/oauth/authorize")).unwrap(),
+                    Some(TokenUrl::new(format!("{GITLAB_URL}/oauth/token")).unwrap()),
+                )
+                .set_redirect_uri(
+                    RedirectUrl::new(redirect_url).expect("invalid GITLAB_REDIRECT_URL"),
+                );
+                Some(client)
+            }
+            _ => None,
+        };
+
+        let sso_oauth = config.sso.as_ref().map(|sso| {
+            SsoClient::new(
+                sso.client_id.clone(),
+                Some(sso.client_secret.clone()),
+                AuthUrl::new(sso.authorize_url.clone()).expect("invalid SSO_AUTHORIZE_URL"),
+                Some(TokenUrl::new(sso.token_url.clone()).expect("invalid SSO_TOKEN_URL")),
+            )
+            .set_redirect_uri(
+                RedirectUrl::new(sso.redirect_url.clone()).expect("invalid SSO_REDIRECT_URL"),
+            )
+        });
+
         let db_helper_threads = match (dotenv::var("DB_HELPER_THREADS"), config.env()) {
             (Ok(num), _) => num.parse().expect("couldn't parse DB_HELPER_THREADS"),
             (_, Env::Production) => 3,
@@ -170,6 +233,21 @@ impl App {
             .time_to_live(config.version_id_cache_ttl)
             .build();
 
+        let suggestions_cacher = CacheBuilder::new(config.suggestions_cache_size)
+            .time_to_live(config.suggestions_cache_ttl)
+            .build();
+
+        let licenses_cacher = CacheBuilder::new(1)
+            .time_to_live(config.licenses_cache_ttl)
+            .build();
+
+        let downloads_counter = match &config.downloads_journal_path {
+            Some(path) => {
+                DownloadsCounter::with_journal(path).expect("could not open the downloads journal")
+            }
+            None => DownloadsCounter::new(),
+        };
+
         let fastboot_client = match dotenv::var("USE_FASTBOOT") {
             Ok(val) if val == "staging-experimental" => Some(reqwest::Client::new()),
             _ => None,
@@ -180,9 +258,15 @@ impl App {
             read_only_replica_database: replica_database,
             github,
             github_oauth,
+            gitlab,
+            gitlab_oauth,
+            sso_oauth,
             version_id_cacher,
-            downloads_counter: DownloadsCounter::new(),
-            emails: Emails::from_environment(&config),
+            suggestions_cacher,
+            licenses_cacher,
+            downloads_counter,
+            emails: Arc::new(Emails::from_environment(&config)),
+            search,
             service_metrics: ServiceMetrics::new().expect("could not initialize service metrics"),
             instance_metrics,
             http_client,
@@ -1,11 +1,15 @@
+use crate::config;
 use crate::controllers;
 use crate::controllers::util::RequestPartsExt;
 use crate::middleware::log_request::RequestLogExt;
-use crate::middleware::session::RequestSession;
+use crate::middleware::session::{client_info, RequestSession, SESSION_TOKEN_KEY};
 use crate::models::token::{CrateScope, EndpointScope};
-use crate::models::{ApiToken, User};
+use crate::models::{
+    AdminPermission, ApiToken, NewAdminAction, Organization, PersistentSession, User,
+};
 use crate::util::errors::{
     account_locked, forbidden, internal, AppError, AppResult, InsecurelyGeneratedTokenRevoked,
+    MissingAdminPermission, RegistryAuthRequired,
 };
 use chrono::Utc;
 use diesel::PgConnection;
@@ -78,6 +82,13 @@ impl AuthCheck {
                 let error_message = "Crate scope mismatch";
                 return Err(internal(error_message).chain(forbidden()));
             }
+
+            if let Some(organization_id) = token.organization_id {
+                if !self.organization_matches(conn, organization_id, token.user_id)? {
+                    let error_message = "Organization mismatch";
+                    return Err(internal(error_message).chain(forbidden()));
+                }
+            }
         }
 
         Ok(auth)
@@ -113,6 +124,24 @@ impl AuthCheck {
                 .any(|token_scope| token_scope.matches(crate_name)),
         }
     }
+
+    fn organization_matches(
+        &self,
+        conn: &mut PgConnection,
+        organization_id: i32,
+        user_id: i32,
+    ) -> AppResult<bool> {
+        // Like tokens with crate scopes, tokens of an organization can't be
+        // used for endpoints that don't deal with crates.
+        let Some(crate_name) = &self.crate_name else { return Ok(false) };
+
+        Ok(Organization::token_may_access(
+            conn,
+            organization_id,
+            user_id,
+            crate_name,
+        )?)
+    }
 }
 
 #[derive(Debug)]
@@ -124,6 +153,7 @@ pub enum Authentication {
 #[derive(Debug)]
 pub struct CookieAuthentication {
     user: User,
+    session_id: i64,
 }
 
 #[derive(Debug)]
@@ -137,6 +167,16 @@ impl Authentication {
         self.user().id
     }
 
+    /// The ID of the persistent session that authenticated the request.
+    ///
+    /// This is `None` for requests authenticated with an API token.
+    pub fn session_id(&self) -> Option<i64> {
+        match self {
+            Authentication::Cookie(cookie) => Some(cookie.session_id),
+            _ => None,
+        }
+    }
+
     pub fn api_token_id(&self) -> Option<i32> {
         self.api_token().map(|token| token.id)
     }
@@ -156,25 +196,124 @@ impl Authentication {
     }
 }
 
+/// Checks that the user making the request has an admin role with the given
+/// permission.
+///
+/// Admin actions can only be taken with a session cookie, never with an API
+/// token. Every action has to be recorded with
+/// `AdminAuthentication::record_action()`.
+#[derive(Debug, Clone, Copy)]
+pub struct AdminCheck {
+    permission: AdminPermission,
+}
+
+impl AdminCheck {
+    #[must_use]
+    pub fn new(permission: AdminPermission) -> Self {
+        Self { permission }
+    }
+
+    pub fn check<T: RequestPartsExt>(
+        &self,
+        request: &T,
+        conn: &mut PgConnection,
+    ) -> AppResult<AdminAuthentication> {
+        let auth = AuthCheck::only_cookie().check(request, conn)?;
+
+        if !self.permission.is_granted_to(conn, auth.user_id())? {
+            let permission = self.permission.as_str();
+            return Err(Box::new(MissingAdminPermission { permission }));
+        }
+
+        Ok(AdminAuthentication {
+            auth,
+            permission: self.permission,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct AdminAuthentication {
+    auth: Authentication,
+    permission: AdminPermission,
+}
+
+impl AdminAuthentication {
+    pub fn user(&self) -> &User {
+        self.auth.user()
+    }
+
+    /// Records the action in the audit log of admin actions. `target`
+    /// describes what the action was taken on, e.g. `crate serde`.
+    pub fn record_action(
+        &self,
+        conn: &mut PgConnection,
+        target: &str,
+        reason: Option<&str>,
+    ) -> AppResult<()> {
+        NewAdminAction {
+            user_id: Some(self.auth.user_id()),
+            permission: self.permission.as_str(),
+            target,
+            reason,
+        }
+        .create(conn)?;
+
+        Ok(())
+    }
+}
+
+/// Checks that the request may read from the registry, i.e. fetch the index
+/// and download crates.
+///
+/// This is always allowed, unless the registry is configured with
+/// `auth-required`. Then any valid token or session grants read access,
+/// regardless of the scopes of the token. Requests without credentials are
+/// answered with a `401 Unauthorized` challenge, which tells cargo to retry
+/// with the token from its credential provider.
+pub fn check_registry_access<T: RequestPartsExt>(
+    config: &config::Server,
+    request: &T,
+    conn: &mut PgConnection,
+) -> AppResult<()> {
+    if !config.index_config.auth_required {
+        return Ok(());
+    }
+
+    let has_token = request.headers().contains_key(header::AUTHORIZATION);
+    let has_session = request.session().get(SESSION_TOKEN_KEY).is_some();
+    if !has_token && !has_session {
+        let login_url = format!("https://{}/settings/tokens", config.domain_name);
+        return Err(Box::new(RegistryAuthRequired { login_url }));
+    }
+
+    authenticate(request, conn)?;
+    Ok(())
+}
+
 fn authenticate_via_cookie<T: RequestPartsExt>(
     req: &T,
     conn: &mut PgConnection,
 ) -> AppResult<Option<CookieAuthentication>> {
-    let user_id_from_session = req
-        .session()
-        .get("user_id")
-        .and_then(|s| s.parse::<i32>().ok());
+    let Some(token) = req.session().get(SESSION_TOKEN_KEY) else { return Ok(None) };
+    let Some(session) = PersistentSession::find_by_token(conn, &token)? else { return Ok(None) };
 
-    let Some(id) = user_id_from_session else { return Ok(None) };
-
-    let user = User::find(conn, id)
-        .map_err(|err| err.chain(internal("user_id from cookie not found in database")))?;
+    let user = User::find(conn, session.user_id)
+        .map_err(|err| err.chain(internal("user of the session not found in database")))?;
 
     ensure_not_locked(&user)?;
 
-    req.request_log().add("uid", id);
+    let (ip_address, user_agent) = client_info(req.headers());
+    if let Err(error) = session.record_use(conn, ip_address, user_agent) {
+        warn!(%error, "Failed to record the use of a session");
+    }
+
+    req.request_log().add("uid", user.id);
 
-    Ok(Some(CookieAuthentication { user }))
+    Ok(Some(CookieAuthentication {
+        user,
+        session_id: session.id,
+    }))
 }
 
 fn authenticate_via_token<T: RequestPartsExt>(
@@ -226,7 +365,9 @@ fn authenticate<T: RequestPartsExt>(req: &T, conn: &mut PgConnection) -> AppResu
     return Err(internal("no cookie session or auth header found").chain(forbidden()));
 }
 
-fn ensure_not_locked(user: &User) -> AppResult<()> {
+/// Rejects users whose account is locked, e.g. because the crates.io team
+/// suspended it. The error includes the reason for the lock.
+pub(crate) fn ensure_not_locked(user: &User) -> AppResult<()> {
     if let Some(reason) = &user.account_lock_reason {
         let still_locked = if let Some(until) = user.account_lock_until {
             until > Utc::now().naive_utc()
@@ -5,23 +5,50 @@ use std::panic::AssertUnwindSafe;
 use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
 use crate::db::ConnectionPool;
+use crate::email::Emails;
+use crate::github::GitHubClient;
+use crate::search::Meilisearch;
 use crate::swirl::errors::EnqueueError;
 use crate::swirl::PerformError;
 use crate::uploaders::Uploader;
+use crate::util::webhooks::WebhookClient;
 use crate::worker;
-use crate::worker::cloudfront::CloudFront;
+use crate::worker::cdn::Cdn;
+use crate::worker::cdn_logs::CdnLogBucket;
 use cargo_registry_index::Repository;
 
 pub enum Job {
+    BackfillChecksums(BackfillChecksumsJob),
+    CheckFollowedCrates,
+    CheckIndexConsistency(CheckIndexConsistencyJob),
+    CheckSavedSearches,
+    CompleteCrateTransfers,
     DailyDbMaintenance,
+    DeleteAccount(DeleteAccountJob),
+    DeleteCrateFiles(DeleteCrateFilesJob),
+    DeliverWebhook(DeliverWebhookJob),
     DumpDb(DumpDbJob),
+    ExportDownloads(ExportDownloadsJob),
     IndexAddCrate(IndexAddCrateJob),
+    IndexDeleteVersions(IndexDeleteVersionsJob),
     IndexSquash,
+    IndexSquashIfNeeded(IndexSquashIfNeededJob),
+    IndexSyncDeprecated(IndexSyncDeprecatedJob),
     IndexSyncToHttp(IndexSyncToHttpJob),
     IndexUpdateYanked(IndexUpdateYankedJob),
     NormalizeIndex(NormalizeIndexJob),
+    ProcessCdnInvalidations,
+    ProcessCdnLogs,
+    ProcessOwnerInvitations(ProcessOwnerInvitationsJob),
+    RefreshReverseDependencyCounts,
     RenderAndUploadReadme(RenderAndUploadReadmeJob),
+    ReplicateFiles,
+    RerenderReadmes(RerenderReadmesJob),
+    SyncSearchIndex(SyncSearchIndexJob),
+    SyncTeamMemberships(SyncTeamMembershipsJob),
+    UpdateDownloadTrends,
     UpdateDownloads,
+    VerifyCrateFiles(VerifyCrateFilesJob),
 }
 
 /// Database state that is passed to `Job::perform()`.
@@ -39,41 +66,154 @@ pub(crate) struct PerformState<'a> {
 }
 
 impl Job {
+    const BACKFILL_CHECKSUMS: &str = "backfill_checksums";
+    const CHECK_FOLLOWED_CRATES: &str = "check_followed_crates";
+    const CHECK_INDEX_CONSISTENCY: &str = "check_index_consistency";
+    const CHECK_SAVED_SEARCHES: &str = "check_saved_searches";
+    const COMPLETE_CRATE_TRANSFERS: &str = "complete_crate_transfers";
     const DAILY_DB_MAINTENANCE: &str = "daily_db_maintenance";
+    const DELETE_ACCOUNT: &str = "delete_account";
+    const DELETE_CRATE_FILES: &str = "delete_crate_files";
+    const DELIVER_WEBHOOK: &str = "deliver_webhook";
     const DUMP_DB: &str = "dump_db";
+    const EXPORT_DOWNLOADS: &str = "export_downloads";
     const INDEX_ADD_CRATE: &str = "add_crate";
+    const INDEX_DELETE_VERSIONS: &str = "delete_versions";
     const INDEX_SQUASH: &str = "squash_index";
+    const INDEX_SQUASH_IF_NEEDED: &str = "squash_index_if_needed";
+    const INDEX_SYNC_DEPRECATED: &str = "sync_deprecated";
     const INDEX_SYNC_TO_HTTP: &str = "update_crate_index";
     const INDEX_UPDATE_YANKED: &str = "sync_yanked";
     const NORMALIZE_INDEX: &str = "normalize_index";
+    const PROCESS_CDN_INVALIDATIONS: &str = "process_cdn_invalidations";
+    const PROCESS_CDN_LOGS: &str = "process_cdn_logs";
+    const PROCESS_OWNER_INVITATIONS: &str = "process_owner_invitations";
+    const REFRESH_REVERSE_DEPENDENCY_COUNTS: &str = "refresh_reverse_dependency_counts";
     const RENDER_AND_UPLOAD_README: &str = "render_and_upload_readme";
+    const REPLICATE_FILES: &str = "replicate_files";
+    const RERENDER_READMES: &str = "rerender_readmes";
+    const SYNC_SEARCH_INDEX: &str = "sync_search_index";
+    const SYNC_TEAM_MEMBERSHIPS: &str = "sync_team_memberships";
+    const UPDATE_DOWNLOAD_TRENDS: &str = "update_download_trends";
     const UPDATE_DOWNLOADS: &str = "update_downloads";
+    const VERIFY_CRATE_FILES: &str = "verify_crate_files";
+
+    /// Index updates are run before all other jobs, so that publishes and
+    /// yanks aren't delayed by long running jobs.
+    pub const PRIORITY_INDEX: i16 = 100;
+    /// Emails and webhooks about recent events.
+    pub const PRIORITY_NOTIFICATION: i16 = 50;
+    pub const PRIORITY_DEFAULT: i16 = 0;
+    /// Maintenance and bulk jobs, like database dumps and backfills, are only
+    /// run when no other jobs are waiting.
+    pub const PRIORITY_BULK: i16 = -100;
 
     fn as_type_str(&self) -> &'static str {
         match self {
+            Job::BackfillChecksums(_) => Self::BACKFILL_CHECKSUMS,
+            Job::CheckFollowedCrates => Self::CHECK_FOLLOWED_CRATES,
+            Job::CheckIndexConsistency(_) => Self::CHECK_INDEX_CONSISTENCY,
+            Job::CheckSavedSearches => Self::CHECK_SAVED_SEARCHES,
+            Job::CompleteCrateTransfers => Self::COMPLETE_CRATE_TRANSFERS,
             Job::DailyDbMaintenance => Self::DAILY_DB_MAINTENANCE,
+            Job::DeleteAccount(_) => Self::DELETE_ACCOUNT,
+            Job::DeleteCrateFiles(_) => Self::DELETE_CRATE_FILES,
+            Job::DeliverWebhook(_) => Self::DELIVER_WEBHOOK,
             Job::DumpDb(_) => Self::DUMP_DB,
+            Job::ExportDownloads(_) => Self::EXPORT_DOWNLOADS,
             Job::IndexAddCrate(_) => Self::INDEX_ADD_CRATE,
+            Job::IndexDeleteVersions(_) => Self::INDEX_DELETE_VERSIONS,
             Job::IndexSquash => Self::INDEX_SQUASH,
+            Job::IndexSquashIfNeeded(_) => Self::INDEX_SQUASH_IF_NEEDED,
+            Job::IndexSyncDeprecated(_) => Self::INDEX_SYNC_DEPRECATED,
             Job::IndexSyncToHttp(_) => Self::INDEX_SYNC_TO_HTTP,
             Job::IndexUpdateYanked(_) => Self::INDEX_UPDATE_YANKED,
             Job::NormalizeIndex(_) => Self::NORMALIZE_INDEX,
+            Job::ProcessCdnInvalidations => Self::PROCESS_CDN_INVALIDATIONS,
+            Job::ProcessCdnLogs => Self::PROCESS_CDN_LOGS,
+            Job::ProcessOwnerInvitations(_) => Self::PROCESS_OWNER_INVITATIONS,
+            Job::RefreshReverseDependencyCounts => Self::REFRESH_REVERSE_DEPENDENCY_COUNTS,
             Job::RenderAndUploadReadme(_) => Self::RENDER_AND_UPLOAD_README,
+            Job::ReplicateFiles => Self::REPLICATE_FILES,
+            Job::RerenderReadmes(_) => Self::RERENDER_READMES,
+            Job::SyncSearchIndex(_) => Self::SYNC_SEARCH_INDEX,
+            Job::SyncTeamMemberships(_) => Self::SYNC_TEAM_MEMBERSHIPS,
+            Job::UpdateDownloadTrends => Self::UPDATE_DOWNLOAD_TRENDS,
             Job::UpdateDownloads => Self::UPDATE_DOWNLOADS,
+            Job::VerifyCrateFiles(_) => Self::VERIFY_CRATE_FILES,
+        }
+    }
+
+    /// The priority of the job, jobs with a higher priority are run first.
+    pub fn priority(&self) -> i16 {
+        match self {
+            Job::IndexAddCrate(_)
+            | Job::IndexDeleteVersions(_)
+            | Job::IndexSyncDeprecated(_)
+            | Job::IndexSyncToHttp(_)
+            | Job::IndexUpdateYanked(_) => Self::PRIORITY_INDEX,
+            Job::CheckFollowedCrates
+            | Job::CheckSavedSearches
+            | Job::DeliverWebhook(_)
+            | Job::ProcessOwnerInvitations(_) => Self::PRIORITY_NOTIFICATION,
+            Job::CompleteCrateTransfers
+            | Job::DeleteAccount(_)
+            | Job::DeleteCrateFiles(_)
+            | Job::ProcessCdnInvalidations
+            | Job::RenderAndUploadReadme(_)
+            | Job::SyncSearchIndex(_)
+            | Job::SyncTeamMemberships(_)
+            | Job::UpdateDownloads => Self::PRIORITY_DEFAULT,
+            Job::BackfillChecksums(_)
+            | Job::CheckIndexConsistency(_)
+            | Job::DailyDbMaintenance
+            | Job::DumpDb(_)
+            | Job::ExportDownloads(_)
+            | Job::IndexSquash
+            | Job::IndexSquashIfNeeded(_)
+            | Job::NormalizeIndex(_)
+            | Job::ProcessCdnLogs
+            | Job::RefreshReverseDependencyCounts
+            | Job::ReplicateFiles
+            | Job::RerenderReadmes(_)
+            | Job::UpdateDownloadTrends
+            | Job::VerifyCrateFiles(_) => Self::PRIORITY_BULK,
         }
     }
 
     fn to_value(&self) -> serde_json::Result<serde_json::Value> {
         match self {
+            Job::BackfillChecksums(inner) => serde_json::to_value(inner),
+            Job::CheckFollowedCrates => Ok(serde_json::Value::Null),
+            Job::CheckIndexConsistency(inner) => serde_json::to_value(inner),
+            Job::CheckSavedSearches => Ok(serde_json::Value::Null),
+            Job::CompleteCrateTransfers => Ok(serde_json::Value::Null),
             Job::DailyDbMaintenance => Ok(serde_json::Value::Null),
+            Job::DeleteAccount(inner) => serde_json::to_value(inner),
+            Job::DeleteCrateFiles(inner) => serde_json::to_value(inner),
+            Job::DeliverWebhook(inner) => serde_json::to_value(inner),
             Job::DumpDb(inner) => serde_json::to_value(inner),
+            Job::ExportDownloads(inner) => serde_json::to_value(inner),
             Job::IndexAddCrate(inner) => serde_json::to_value(inner),
+            Job::IndexDeleteVersions(inner) => serde_json::to_value(inner),
             Job::IndexSquash => Ok(serde_json::Value::Null),
+            Job::IndexSquashIfNeeded(inner) => serde_json::to_value(inner),
+            Job::IndexSyncDeprecated(inner) => serde_json::to_value(inner),
             Job::IndexSyncToHttp(inner) => serde_json::to_value(inner),
             Job::IndexUpdateYanked(inner) => serde_json::to_value(inner),
             Job::NormalizeIndex(inner) => serde_json::to_value(inner),
+            Job::ProcessCdnInvalidations => Ok(serde_json::Value::Null),
+            Job::ProcessCdnLogs => Ok(serde_json::Value::Null),
+            Job::ProcessOwnerInvitations(inner) => serde_json::to_value(inner),
+            Job::RefreshReverseDependencyCounts => Ok(serde_json::Value::Null),
             Job::RenderAndUploadReadme(inner) => serde_json::to_value(inner),
+            Job::ReplicateFiles => Ok(serde_json::Value::Null),
+            Job::RerenderReadmes(inner) => serde_json::to_value(inner),
+            Job::SyncSearchIndex(inner) => serde_json::to_value(inner),
+            Job::SyncTeamMemberships(inner) => serde_json::to_value(inner),
+            Job::UpdateDownloadTrends => Ok(serde_json::Value::Null),
             Job::UpdateDownloads => Ok(serde_json::Value::Null),
+            Job::VerifyCrateFiles(inner) => serde_json::to_value(inner),
         }
     }
 
@@ -82,7 +222,11 @@ impl Job {
 
         let job_data = self.to_value()?;
         diesel::insert_into(background_jobs)
-            .values((job_type.eq(self.as_type_str()), data.eq(job_data)))
+            .values((
+                job_type.eq(self.as_type_str()),
+                data.eq(job_data),
+                priority.eq(self.priority()),
+            ))
             .execute(conn)?;
         Ok(())
     }
@@ -93,15 +237,37 @@ impl Job {
     ) -> Result<Self, PerformError> {
         use serde_json::from_value;
         Ok(match job_type {
+            Self::BACKFILL_CHECKSUMS => Job::BackfillChecksums(from_value(value)?),
+            Self::CHECK_FOLLOWED_CRATES => Job::CheckFollowedCrates,
+            Self::CHECK_INDEX_CONSISTENCY => Job::CheckIndexConsistency(from_value(value)?),
+            Self::CHECK_SAVED_SEARCHES => Job::CheckSavedSearches,
+            Self::COMPLETE_CRATE_TRANSFERS => Job::CompleteCrateTransfers,
             Self::DAILY_DB_MAINTENANCE => Job::DailyDbMaintenance,
+            Self::DELETE_ACCOUNT => Job::DeleteAccount(from_value(value)?),
+            Self::DELETE_CRATE_FILES => Job::DeleteCrateFiles(from_value(value)?),
+            Self::DELIVER_WEBHOOK => Job::DeliverWebhook(from_value(value)?),
             Self::DUMP_DB => Job::DumpDb(from_value(value)?),
+            Self::EXPORT_DOWNLOADS => Job::ExportDownloads(from_value(value)?),
             Self::INDEX_ADD_CRATE => Job::IndexAddCrate(from_value(value)?),
+            Self::INDEX_DELETE_VERSIONS => Job::IndexDeleteVersions(from_value(value)?),
             Self::INDEX_SQUASH => Job::IndexSquash,
+            Self::INDEX_SQUASH_IF_NEEDED => Job::IndexSquashIfNeeded(from_value(value)?),
+            Self::INDEX_SYNC_DEPRECATED => Job::IndexSyncDeprecated(from_value(value)?),
             Self::INDEX_SYNC_TO_HTTP => Job::IndexSyncToHttp(from_value(value)?),
             Self::INDEX_UPDATE_YANKED => Job::IndexUpdateYanked(from_value(value)?),
             Self::NORMALIZE_INDEX => Job::NormalizeIndex(from_value(value)?),
+            Self::PROCESS_CDN_INVALIDATIONS => Job::ProcessCdnInvalidations,
+            Self::PROCESS_CDN_LOGS => Job::ProcessCdnLogs,
+            Self::PROCESS_OWNER_INVITATIONS => Job::ProcessOwnerInvitations(from_value(value)?),
+            Self::REFRESH_REVERSE_DEPENDENCY_COUNTS => Job::RefreshReverseDependencyCounts,
             Self::RENDER_AND_UPLOAD_README => Job::RenderAndUploadReadme(from_value(value)?),
+            Self::REPLICATE_FILES => Job::ReplicateFiles,
+            Self::RERENDER_READMES => Job::RerenderReadmes(from_value(value)?),
+            Self::SYNC_SEARCH_INDEX => Job::SyncSearchIndex(from_value(value)?),
+            Self::SYNC_TEAM_MEMBERSHIPS => Job::SyncTeamMemberships(from_value(value)?),
+            Self::UPDATE_DOWNLOAD_TRENDS => Job::UpdateDownloadTrends,
             Self::UPDATE_DOWNLOADS => Job::UpdateDownloads,
+            Self::VERIFY_CRATE_FILES => Job::VerifyCrateFiles(from_value(value)?),
             job_type => Err(PerformError::from(format!("Unknown job type {job_type}")))?,
         })
     }
@@ -116,17 +282,64 @@ impl Job {
             .as_ref()
             .expect("Application should configure a background runner environment");
         match self {
+            Job::BackfillChecksums(args) => {
+                worker::perform_backfill_checksums(env, conn, args.after_id)
+            }
+            Job::CheckFollowedCrates => worker::perform_check_followed_crates(env, conn),
+            Job::CheckIndexConsistency(args) => worker::perform_check_index_consistency(
+                env,
+                conn,
+                args.sample_size,
+                args.sparse,
+                args.fix,
+            ),
+            Job::CheckSavedSearches => worker::perform_check_saved_searches(env, conn),
+            Job::CompleteCrateTransfers => worker::perform_complete_crate_transfers(env, conn),
             Job::DailyDbMaintenance => {
-                worker::perform_daily_db_maintenance(&mut *fresh_connection(pool)?)
+                worker::perform_daily_db_maintenance(env, &mut *fresh_connection(pool)?)
+            }
+            Job::DeleteAccount(args) => worker::perform_delete_account(env, conn, args.user_id),
+            Job::DeleteCrateFiles(args) => worker::perform_delete_crate_files(
+                env,
+                conn,
+                &args.krate,
+                &args.version_nums,
+                &args.object_keys,
+            ),
+            Job::DeliverWebhook(args) => {
+                worker::perform_deliver_webhook(env, conn, pool, args.delivery_id)
             }
             Job::DumpDb(args) => worker::perform_dump_db(env, args.database_url, args.target_name),
+            Job::ExportDownloads(args) => worker::perform_export_downloads(env, conn, args.date),
             Job::IndexAddCrate(args) => worker::perform_index_add_crate(env, conn, &args.krate),
-            Job::IndexSquash => worker::perform_index_squash(env),
-            Job::IndexSyncToHttp(args) => worker::perform_index_sync_to_http(env, args.crate_name),
+            Job::IndexDeleteVersions(args) => {
+                worker::perform_index_delete_versions(env, conn, &args.krate, &args.version_nums)
+            }
+            Job::IndexSquash => worker::perform_index_squash(env, conn),
+            Job::IndexSquashIfNeeded(args) => {
+                worker::perform_index_squash_if_needed(env, conn, args.max_commits)
+            }
+            Job::IndexSyncDeprecated(args) => {
+                worker::perform_index_sync_deprecated(env, conn, &args.krate)
+            }
+            Job::IndexSyncToHttp(args) => {
+                worker::perform_index_sync_to_http(env, conn, args.crate_name)
+            }
             Job::IndexUpdateYanked(args) => {
                 worker::perform_index_update_yanked(env, conn, &args.krate, &args.version_num)
             }
             Job::NormalizeIndex(args) => worker::perform_normalize_index(env, args),
+            Job::ProcessCdnInvalidations => worker::perform_process_cdn_invalidations(env, conn),
+            Job::ProcessCdnLogs => worker::perform_process_cdn_logs(env, conn),
+            Job::ProcessOwnerInvitations(args) => worker::perform_process_owner_invitations(
+                env,
+                conn,
+                args.expiration_days,
+                args.reminder_days,
+            ),
+            Job::RefreshReverseDependencyCounts => {
+                worker::perform_refresh_reverse_dependency_counts(conn)
+            }
             Job::RenderAndUploadReadme(args) => worker::perform_render_and_upload_readme(
                 conn,
                 env,
@@ -136,7 +349,19 @@ impl Job {
                 args.base_url.as_deref(),
                 args.pkg_path_in_vcs.as_deref(),
             ),
+            Job::ReplicateFiles => worker::perform_replicate_files(env, conn, pool),
+            Job::RerenderReadmes(args) => {
+                worker::perform_rerender_readmes(env, conn, args.after_id)
+            }
+            Job::SyncSearchIndex(args) => worker::perform_sync_search_index(env, conn, &args.krate),
+            Job::SyncTeamMemberships(args) => {
+                worker::perform_sync_team_memberships(env, conn, args.batch_size)
+            }
+            Job::UpdateDownloadTrends => worker::perform_update_download_trends(conn),
             Job::UpdateDownloads => worker::perform_update_downloads(&mut *fresh_connection(pool)?),
+            Job::VerifyCrateFiles(args) => {
+                worker::perform_verify_crate_files(env, conn, args.sample_size)
+            }
         }
     }
 }
@@ -145,7 +370,7 @@ impl Job {
 ///
 /// This will error when run from our main test framework, as there most work is expected to be
 /// done within an existing transaction.
-fn fresh_connection(
+pub(crate) fn fresh_connection(
     pool: Option<ConnectionPool>,
 ) -> Result<PooledConnection<ConnectionManager<PgConnection>>, PerformError> {
     let Some(pool) = pool else {
@@ -156,17 +381,67 @@ fn fresh_connection(
     Ok(pool.get()?)
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct BackfillChecksumsJob {
+    pub(super) after_id: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CheckIndexConsistencyJob {
+    pub(super) sample_size: i64,
+    pub(super) sparse: bool,
+    pub(super) fix: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteAccountJob {
+    pub(super) user_id: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteCrateFilesJob {
+    pub(super) krate: String,
+    pub(super) version_nums: Vec<String>,
+    pub(super) object_keys: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeliverWebhookJob {
+    pub(super) delivery_id: i32,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct DumpDbJob {
     pub(super) database_url: String,
     pub(super) target_name: String,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct ExportDownloadsJob {
+    pub(super) date: chrono::NaiveDate,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct IndexAddCrateJob {
     pub(super) krate: cargo_registry_index::Crate,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct IndexDeleteVersionsJob {
+    pub(super) krate: String,
+    pub(super) version_nums: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IndexSquashIfNeededJob {
+    pub(super) max_commits: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IndexSyncDeprecatedJob {
+    pub(super) krate: String,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct IndexSyncToHttpJob {
     pub(super) crate_name: String,
@@ -183,6 +458,12 @@ pub struct NormalizeIndexJob {
     pub dry_run: bool,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct ProcessOwnerInvitationsJob {
+    pub(super) expiration_days: u64,
+    pub(super) reminder_days: u64,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RenderAndUploadReadmeJob {
     pub(super) version_id: i32,
@@ -192,11 +473,36 @@ pub struct RenderAndUploadReadmeJob {
     pub(super) pkg_path_in_vcs: Option<String>,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct RerenderReadmesJob {
+    pub(super) after_id: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SyncSearchIndexJob {
+    pub(super) krate: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SyncTeamMembershipsJob {
+    pub(super) batch_size: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VerifyCrateFilesJob {
+    pub(super) sample_size: i64,
+}
+
 pub struct Environment {
     index: Arc<Mutex<Repository>>,
     pub uploader: Uploader,
     http_client: AssertUnwindSafe<Client>,
-    cloudfront: Option<CloudFront>,
+    cdns: Vec<Arc<dyn Cdn>>,
+    search_index: AssertUnwindSafe<Option<Meilisearch>>,
+    emails: Arc<Emails>,
+    cdn_logs: Option<CdnLogBucket>,
+    github: AssertUnwindSafe<Arc<dyn GitHubClient>>,
+    webhook_client: WebhookClient,
 }
 
 impl Clone for Environment {
@@ -205,37 +511,64 @@ impl Clone for Environment {
             index: self.index.clone(),
             uploader: self.uploader.clone(),
             http_client: AssertUnwindSafe(self.http_client.0.clone()),
-            cloudfront: self.cloudfront.clone(),
+            cdns: self.cdns.clone(),
+            search_index: AssertUnwindSafe(self.search_index.0.clone()),
+            emails: self.emails.clone(),
+            cdn_logs: self.cdn_logs.clone(),
+            github: AssertUnwindSafe(self.github.0.clone()),
+            webhook_client: self.webhook_client.clone(),
         }
     }
 }
 
 impl Environment {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         index: Repository,
         uploader: Uploader,
         http_client: Client,
-        cloudfront: Option<CloudFront>,
+        cdns: Vec<Arc<dyn Cdn>>,
+        search_index: Option<Meilisearch>,
+        emails: Arc<Emails>,
+        cdn_logs: Option<CdnLogBucket>,
+        github: Arc<dyn GitHubClient>,
+        webhook_client: WebhookClient,
     ) -> Self {
         Self::new_shared(
             Arc::new(Mutex::new(index)),
             uploader,
             http_client,
-            cloudfront,
+            cdns,
+            search_index,
+            emails,
+            cdn_logs,
+            github,
+            webhook_client,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn new_shared(
         index: Arc<Mutex<Repository>>,
         uploader: Uploader,
         http_client: Client,
-        cloudfront: Option<CloudFront>,
+        cdns: Vec<Arc<dyn Cdn>>,
+        search_index: Option<Meilisearch>,
+        emails: Arc<Emails>,
+        cdn_logs: Option<CdnLogBucket>,
+        github: Arc<dyn GitHubClient>,
+        webhook_client: WebhookClient,
     ) -> Self {
         Self {
             index,
             uploader,
             http_client: AssertUnwindSafe(http_client),
-            cloudfront,
+            cdns,
+            search_index: AssertUnwindSafe(search_index),
+            emails,
+            cdn_logs,
+            github: AssertUnwindSafe(github),
+            webhook_client,
         }
     }
 
@@ -250,7 +583,33 @@ impl Environment {
         &self.http_client
     }
 
-    pub(crate) fn cloudfront(&self) -> Option<&CloudFront> {
-        self.cloudfront.as_ref()
+    /// Returns the CDNs whose cached files are invalidated when they change.
+    pub(crate) fn cdns(&self) -> &[Arc<dyn Cdn>] {
+        &self.cdns
+    }
+
+    /// Returns the external search index, if one is configured.
+    pub(crate) fn search_index(&self) -> Option<&Meilisearch> {
+        self.search_index.0.as_ref()
+    }
+
+    /// Returns the backend used to send notification emails.
+    pub(crate) fn emails(&self) -> &Emails {
+        &self.emails
+    }
+
+    /// Returns the bucket containing the access logs of the CDN, if one is configured.
+    pub(crate) fn cdn_logs(&self) -> Option<&CdnLogBucket> {
+        self.cdn_logs.as_ref()
+    }
+
+    /// Returns the client used to validate the memberships of GitHub teams.
+    pub(crate) fn github(&self) -> &dyn GitHubClient {
+        &**self.github
+    }
+
+    /// Returns the client used to deliver the payloads of webhooks.
+    pub(crate) fn webhook_client(&self) -> &WebhookClient {
+        &self.webhook_client
     }
 }
@@ -16,8 +16,12 @@
 extern crate tracing;
 
 use cargo_registry::config;
-use cargo_registry::worker::cloudfront::CloudFront;
-use cargo_registry::{background_jobs::*, db, ssh};
+use cargo_registry::github::RealGitHubClient;
+use cargo_registry::search::Meilisearch;
+use cargo_registry::util::webhooks::WebhookClient;
+use cargo_registry::worker::cdn::cdns_from_environment;
+use cargo_registry::worker::cdn_logs::CdnLogBucket;
+use cargo_registry::{background_jobs::*, db, ssh, Emails};
 use cargo_registry_index::{Repository, RepositoryConfig};
 use reqwest::blocking::Client;
 use std::sync::{Arc, Mutex};
@@ -69,18 +73,27 @@ fn main() {
     let clone_duration = clone_start.elapsed();
     info!(duration = ?clone_duration, "Index cloned");
 
-    let cloudfront = CloudFront::from_environment();
+    let cdns = cdns_from_environment();
+    let emails = Arc::new(Emails::from_environment(&config));
+    let cdn_logs = CdnLogBucket::from_environment();
 
     let build_runner = || {
         let client = Client::builder()
             .timeout(Duration::from_secs(45))
             .build()
             .expect("Couldn't build client");
+        let search_index = Meilisearch::from_config(&config.search_backend, client.clone());
+        let github = Arc::new(RealGitHubClient::new(Some(client.clone())));
         let environment = Environment::new_shared(
             repository.clone(),
             uploader.clone(),
             client,
-            cloudfront.clone(),
+            cdns.clone(),
+            search_index,
+            emails.clone(),
+            cdn_logs.clone(),
+            github,
+            WebhookClient::new(),
         );
         swirl::Runner::production_runner(environment, db_url.clone(), job_start_timeout)
     };
@@ -1,8 +1,10 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 use cargo_registry::admin::{
-    delete_crate, delete_version, enqueue_job, git_import, migrate, populate, render_readmes,
-    test_pagerduty, transfer_crates, upload_index, verify_token, yank_version,
+    admin_roles, crate_transfers, delete_crate, delete_version, enqueue_job, git_import, migrate,
+    populate, rebuild_index, render_readmes, reserved_names, reserved_prefixes, reset_totp,
+    set_upload_limit, test_pagerduty, transfer_crates, upload_index, verify_index_signatures,
+    verify_token, yank_version,
 };
 
 #[derive(clap::Parser, Debug)]
@@ -12,15 +14,27 @@ enum Command {
     DeleteVersion(delete_version::Opts),
     Populate(populate::Opts),
     RenderReadmes(render_readmes::Opts),
+    ResetTotp(reset_totp::Opts),
+    SetUploadLimit(set_upload_limit::Opts),
     TestPagerduty(test_pagerduty::Opts),
     TransferCrates(transfer_crates::Opts),
     VerifyToken(verify_token::Opts),
     Migrate(migrate::Opts),
     UploadIndex(upload_index::Opts),
+    VerifyIndexSignatures(verify_index_signatures::Opts),
     YankVersion(yank_version::Opts),
     GitImport(git_import::Opts),
+    RebuildIndex(rebuild_index::Opts),
     #[clap(subcommand)]
     EnqueueJob(enqueue_job::Command),
+    #[clap(subcommand)]
+    ReservedNames(reserved_names::Command),
+    #[clap(subcommand)]
+    ReservedPrefixes(reserved_prefixes::Command),
+    #[clap(subcommand)]
+    CrateTransfers(crate_transfers::Command),
+    #[clap(subcommand)]
+    AdminRoles(admin_roles::Command),
 }
 
 fn main() -> anyhow::Result<()> {
@@ -38,14 +52,22 @@ fn main() -> anyhow::Result<()> {
         Command::DeleteVersion(opts) => delete_version::run(opts),
         Command::Populate(opts) => populate::run(opts),
         Command::RenderReadmes(opts) => render_readmes::run(opts)?,
+        Command::ResetTotp(opts) => reset_totp::run(opts),
+        Command::SetUploadLimit(opts) => set_upload_limit::run(opts),
         Command::TestPagerduty(opts) => test_pagerduty::run(opts)?,
         Command::TransferCrates(opts) => transfer_crates::run(opts),
         Command::VerifyToken(opts) => verify_token::run(opts).unwrap(),
         Command::Migrate(opts) => migrate::run(opts)?,
         Command::UploadIndex(opts) => upload_index::run(opts)?,
+        Command::VerifyIndexSignatures(opts) => verify_index_signatures::run(opts)?,
         Command::YankVersion(opts) => yank_version::run(opts),
         Command::GitImport(opts) => git_import::run(opts)?,
+        Command::RebuildIndex(opts) => rebuild_index::run(opts)?,
         Command::EnqueueJob(command) => enqueue_job::run(command)?,
+        Command::ReservedNames(command) => reserved_names::run(command)?,
+        Command::ReservedPrefixes(command) => reserved_prefixes::run(command)?,
+        Command::CrateTransfers(command) => crate_transfers::run(command)?,
+        Command::AdminRoles(command) => admin_roles::run(command)?,
     }
 
     Ok(())
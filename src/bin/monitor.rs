@@ -16,6 +16,8 @@ fn main() -> Result<()> {
     check_failing_background_jobs(conn)?;
     check_stalled_update_downloads(conn)?;
     check_spam_attack(conn)?;
+    check_index_inconsistencies(conn)?;
+    check_crate_file_integrity(conn)?;
     Ok(())
 }
 
@@ -105,6 +107,63 @@ fn check_stalled_update_downloads(conn: &mut PgConnection) -> Result<()> {
     })
 }
 
+/// Check for crates whose index files did not match the database when the
+/// `check_index_consistency` background job last sampled them
+fn check_index_inconsistencies(conn: &mut PgConnection) -> Result<()> {
+    const EVENT_KEY: &str = "index_inconsistencies";
+
+    println!("Checking for index files that do not match the database");
+
+    let count: i64 = index_inconsistencies::table.count().get_result(conn)?;
+
+    let event = if count == 0 {
+        on_call::Event::Resolve {
+            incident_key: EVENT_KEY.into(),
+            description: Some("Index files match the database".into()),
+        }
+    } else {
+        on_call::Event::Trigger {
+            incident_key: Some(EVENT_KEY.into()),
+            description: format!(
+                "{count} crates have index files that do not match the database, see {}",
+                admin_url("index_inconsistencies")
+            ),
+        }
+    };
+
+    log_and_trigger_event(event)
+}
+
+/// Check for crate files that were missing or did not match their checksum
+/// when the `verify_crate_files` background job last checked them
+fn check_crate_file_integrity(conn: &mut PgConnection) -> Result<()> {
+    const EVENT_KEY: &str = "crate_file_integrity";
+
+    println!("Checking for crate files that are missing or corrupted");
+
+    let count: i64 = crate_file_checks::table
+        .filter(crate_file_checks::missing.or(crate_file_checks::checksum_mismatch))
+        .count()
+        .get_result(conn)?;
+
+    let event = if count == 0 {
+        on_call::Event::Resolve {
+            incident_key: EVENT_KEY.into(),
+            description: Some("Crate files match the database".into()),
+        }
+    } else {
+        on_call::Event::Trigger {
+            incident_key: Some(EVENT_KEY.into()),
+            description: format!(
+                "{count} crate files are missing or do not match their checksum, see {}",
+                admin_url("crate_file_checks")
+            ),
+        }
+    };
+
+    log_and_trigger_event(event)
+}
+
 /// Check for known spam patterns
 fn check_spam_attack(conn: &mut PgConnection) -> Result<()> {
     use cargo_registry::sql::canon_crate_name;
@@ -160,3 +219,10 @@ fn log_and_trigger_event(event: on_call::Event) -> Result<()> {
     }
     event.send()
 }
+
+/// Returns the URL of the admin endpoint that lists the details of an alert,
+/// since the alerts themselves only contain a count.
+fn admin_url(path: &str) -> String {
+    let domain_name = dotenv::var("DOMAIN_NAME").unwrap_or_else(|_| "crates.io".into());
+    format!("https://{domain_name}/api/private/admin/{path}")
+}
@@ -8,16 +8,31 @@ use crate::{env, env_optional, uploaders::Uploader, Env};
 mod balance_capacity;
 mod base;
 mod database_pools;
+mod index_config;
+mod metadata_limits;
+mod search_backend;
+mod search_ranking;
+mod sso;
 
 pub use self::base::Base;
 pub use self::database_pools::{DatabasePools, DbPoolConfig};
 pub use crate::config::balance_capacity::BalanceCapacityConfig;
+pub use crate::config::index_config::IndexConfig;
+pub use crate::config::metadata_limits::MetadataLimits;
+pub use crate::config::search_backend::SearchBackendConfig;
+pub use crate::config::search_ranking::SearchRanking;
+pub use crate::config::sso::SsoConfig;
 use http::HeaderValue;
 use std::collections::HashSet;
+use std::path::PathBuf;
 use std::time::Duration;
 
 const DEFAULT_VERSION_ID_CACHE_SIZE: u64 = 10_000;
 const DEFAULT_VERSION_ID_CACHE_TTL: u64 = 5 * 60; // 5 minutes
+const DEFAULT_SUGGESTIONS_CACHE_SIZE: u64 = 10_000;
+const DEFAULT_SUGGESTIONS_CACHE_TTL: u64 = 15 * 60; // 15 minutes
+const DEFAULT_LICENSES_CACHE_TTL: u64 = 5 * 60; // 5 minutes
+pub const DEFAULT_OWNERSHIP_INVITATIONS_EXPIRATION_DAYS: u64 = 30;
 
 pub struct Server {
     pub base: Base,
@@ -25,9 +40,16 @@ pub struct Server {
     pub session_key: cookie::Key,
     pub gh_client_id: ClientId,
     pub gh_client_secret: ClientSecret,
+    pub gitlab_client_id: Option<ClientId>,
+    pub gitlab_client_secret: Option<ClientSecret>,
+    pub sso: Option<SsoConfig>,
     pub max_upload_size: u64,
     pub max_unpack_size: u64,
+    pub metadata_limits: MetadataLimits,
+    pub search_ranking: SearchRanking,
+    pub search_backend: SearchBackendConfig,
     pub publish_rate_limit: PublishRateLimit,
+    pub publish_update_rate_limit: PublishRateLimit,
     pub new_version_rate_limit: Option<u32>,
     pub blocked_traffic: Vec<(String, Vec<String>)>,
     pub max_allowed_page_offset: u32,
@@ -35,16 +57,27 @@ pub struct Server {
     pub page_offset_cidr_blocklist: Vec<IpNetwork>,
     pub excluded_crate_names: Vec<String>,
     pub domain_name: String,
+    pub index_config: IndexConfig,
+    pub git_http_index_path: Option<PathBuf>,
     pub allowed_origins: AllowedOrigins,
     pub downloads_persist_interval_ms: usize,
+    pub downloads_counted_from_cdn_logs: bool,
+    pub downloads_journal_path: Option<PathBuf>,
     pub ownership_invitations_expiration_days: u64,
+    pub deletion_grace_period_hours: u64,
+    pub crate_transfer_waiting_period_hours: u64,
+    pub reject_duplicate_tarballs: bool,
     pub metrics_authorization_token: Option<String>,
+    pub docs_rs_callback_token: Option<String>,
     pub use_test_database_pool: bool,
     pub instance_metrics_log_every_seconds: Option<u64>,
     pub force_unconditional_redirects: bool,
     pub blocked_routes: HashSet<String>,
     pub version_id_cache_size: u64,
     pub version_id_cache_ttl: Duration,
+    pub suggestions_cache_size: u64,
+    pub suggestions_cache_ttl: Duration,
+    pub licenses_cache_ttl: Duration,
     pub cdn_user_agent: String,
     pub balance_capacity: BalanceCapacityConfig,
 }
@@ -55,18 +88,28 @@ impl Default for Server {
     /// Sets the following default values:
     ///
     /// - `Config::max_upload_size`: 10MiB
-    /// - `Config::ownership_invitations_expiration_days`: 30
     ///
     /// Pulls values from the following environment variables:
     ///
     /// - `SESSION_KEY`: The key used to sign and encrypt session cookies.
     /// - `GH_CLIENT_ID`: The client ID of the associated GitHub application.
     /// - `GH_CLIENT_SECRET`: The client secret of the associated GitHub application.
+    /// - `GITLAB_CLIENT_ID`, `GITLAB_CLIENT_SECRET`: The credentials of the associated GitLab
+    ///   application. If missing, users can't log in with GitLab.
+    /// - `SSO_CLIENT_ID`: The client ID of crates.io at the single sign-on identity provider of a
+    ///   private deployment. If missing, users can't log in with single sign-on. See `SsoConfig`
+    ///   for the other `SSO_*` variables.
     /// - `BLOCKED_TRAFFIC`: A list of headers and environment variables to use for blocking
     ///   traffic. See the `block_traffic` module for more documentation.
     /// - `DOWNLOADS_PERSIST_INTERVAL_MS`: how frequent to persist download counts (in ms).
+    /// - `DOWNLOADS_COUNTED_FROM_CDN_LOGS`: Whether downloads are counted by the background worker
+    ///   from the access logs of the CDN, in which case the download endpoint doesn't count them.
+    /// - `DOWNLOADS_JOURNAL_PATH`: A directory in which downloads are journaled until they're
+    ///   persisted, so they aren't lost if the process crashes. If missing, no journal is kept.
     /// - `METRICS_AUTHORIZATION_TOKEN`: authorization token needed to query metrics. If missing,
     ///   querying metrics will be completely disabled.
+    /// - `DOCS_RS_CALLBACK_TOKEN`: authorization token needed by docs.rs to report the outcome of
+    ///   documentation builds. If missing, the callback endpoint is disabled.
     /// - `WEB_MAX_ALLOWED_PAGE_OFFSET`: Page offsets larger than this value are rejected. Defaults
     ///   to 200.
     /// - `WEB_PAGE_OFFSET_UA_BLOCKLIST`: A comma seperated list of user-agent substrings that will
@@ -78,10 +121,41 @@ impl Default for Server {
     ///   If not set or empty, no blocking will occur.
     /// - `INSTANCE_METRICS_LOG_EVERY_SECONDS`: How frequently should instance metrics be logged.
     ///   If the environment variable is not present instance metrics are not logged.
+    /// - `REJECT_DUPLICATE_TARBALLS`: Whether publishes of tarballs that are identical to the one
+    ///   of an existing version are rejected. Defaults to `true`.
     /// - `FORCE_UNCONDITIONAL_REDIRECTS`: Whether to force unconditional redirects in the download
     ///   endpoint even with a healthy database pool.
     /// - `BLOCKED_ROUTES`: A comma separated list of HTTP route patterns that are manually blocked
     ///   by an operator (e.g. `/crates/:crate_id/:version/download`).
+    /// - `WEB_NEW_PKG_RATE_LIMIT_RATE_MINUTES`, `WEB_NEW_PKG_RATE_LIMIT_BURST`: How often a user
+    ///   gets a token to publish a new crate, and how many tokens they can save up. Defaults to
+    ///   10 minutes and 5 crates.
+    /// - `WEB_UPDATE_PKG_RATE_LIMIT_RATE_MINUTES`, `WEB_UPDATE_PKG_RATE_LIMIT_BURST`: The same
+    ///   for publishing new versions of existing crates. Defaults to 1 minute and 30 versions.
+    /// - `PUBLISH_MAX_DEPENDENCIES`, `PUBLISH_MAX_FEATURES`, `PUBLISH_MAX_FEATURE_NAME_LENGTH`,
+    ///   `PUBLISH_MAX_METADATA_SIZE`: Limits for the metadata of published crates. Default to
+    ///   500 dependencies, 300 features, 100 characters and 5 MB.
+    /// - `SEARCH_NAME_WEIGHT`, `SEARCH_TEXT_WEIGHT`, `SEARCH_POPULARITY_WEIGHT`: The weights of
+    ///   the name similarity, the full-text rank and the recent downloads when sorting search
+    ///   results by relevance. Default to 0.3, 1.0 and 0.05.
+    /// - `SEARCH_BACKEND`: Either `postgres` (the default) or `meilisearch`, in which case the
+    ///   text of search queries is matched by the Meilisearch instance at `MEILISEARCH_URL`,
+    ///   authenticated with the optional `MEILISEARCH_API_KEY`.
+    /// - `DELETION_GRACE_PERIOD_HOURS`: For how many hours after publishing the owners of a
+    ///   crate can delete it or one of its versions themselves. Defaults to 72 hours.
+    /// - `OWNERSHIP_INVITATIONS_EXPIRATION_DAYS`: After how many days crate ownership invitations
+    ///   can't be accepted anymore. Defaults to 30 days.
+    /// - `CRATE_TRANSFER_WAITING_PERIOD_HOURS`: For how many hours after the recipient accepted
+    ///   a crate ownership transfer it can still be cancelled, before the ownership is moved.
+    ///   Defaults to 72 hours.
+    /// - `SUGGESTIONS_CACHE_SIZE`, `SUGGESTIONS_CACHE_TTL`: How many crate name suggestions for
+    ///   the search box are cached, and for how many seconds. Default to 10000 and 15 minutes.
+    /// - `LICENSES_CACHE_TTL`: For how many seconds the licenses in use are cached for the license
+    ///   filter of the search. Defaults to 5 minutes.
+    /// - `INDEX_CONFIG_DL`, `INDEX_CONFIG_API`, `INDEX_AUTH_REQUIRED`: The contents of the
+    ///   `config.json` file of the index. See `IndexConfig::from_environment()`.
+    /// - `GIT_HTTP_INDEX_PATH`: The path of a local clone of the git index, which is then served
+    ///   read-only over the smart HTTP protocol at `/git/index`. If missing, the index isn't served.
     ///
     /// # Panics
     ///
@@ -106,6 +180,7 @@ impl Default for Server {
             };
 
         let base = Base::from_environment();
+        let domain_name = domain_name();
         let excluded_crate_names = match env_optional::<String>("EXCLUDED_CRATE_NAMES") {
             None => vec![],
             Some(s) if s.is_empty() => vec![],
@@ -117,16 +192,27 @@ impl Default for Server {
             session_key: cookie::Key::derive_from(env("SESSION_KEY").as_bytes()),
             gh_client_id: ClientId::new(env("GH_CLIENT_ID")),
             gh_client_secret: ClientSecret::new(env("GH_CLIENT_SECRET")),
+            gitlab_client_id: dotenv::var("GITLAB_CLIENT_ID").ok().map(ClientId::new),
+            gitlab_client_secret: dotenv::var("GITLAB_CLIENT_SECRET")
+                .ok()
+                .map(ClientSecret::new),
+            sso: SsoConfig::from_environment(&domain_name),
             max_upload_size: 10 * 1024 * 1024, // 10 MB default file upload size limit
             max_unpack_size: 512 * 1024 * 1024, // 512 MB max when decompressed
+            metadata_limits: MetadataLimits::from_environment(),
+            search_ranking: SearchRanking::from_environment(),
+            search_backend: SearchBackendConfig::from_environment(),
             publish_rate_limit: Default::default(),
+            publish_update_rate_limit: PublishRateLimit::for_updates(),
             new_version_rate_limit: env_optional("MAX_NEW_VERSIONS_DAILY"),
             blocked_traffic: blocked_traffic(),
             max_allowed_page_offset: env_optional("WEB_MAX_ALLOWED_PAGE_OFFSET").unwrap_or(200),
             page_offset_ua_blocklist,
             page_offset_cidr_blocklist,
             excluded_crate_names,
-            domain_name: domain_name(),
+            index_config: IndexConfig::from_environment(&domain_name),
+            domain_name,
+            git_http_index_path: dotenv::var("GIT_HTTP_INDEX_PATH").ok().map(PathBuf::from),
             allowed_origins,
             downloads_persist_interval_ms: dotenv::var("DOWNLOADS_PERSIST_INTERVAL_MS")
                 .map(|interval| {
@@ -135,8 +221,22 @@ impl Default for Server {
                         .expect("invalid DOWNLOADS_PERSIST_INTERVAL_MS")
                 })
                 .unwrap_or(60_000), // 1 minute
-            ownership_invitations_expiration_days: 30,
+            downloads_counted_from_cdn_logs: dotenv::var("DOWNLOADS_COUNTED_FROM_CDN_LOGS").is_ok(),
+            downloads_journal_path: dotenv::var("DOWNLOADS_JOURNAL_PATH")
+                .ok()
+                .map(PathBuf::from),
+            ownership_invitations_expiration_days: env_optional(
+                "OWNERSHIP_INVITATIONS_EXPIRATION_DAYS",
+            )
+            .unwrap_or(DEFAULT_OWNERSHIP_INVITATIONS_EXPIRATION_DAYS),
+            deletion_grace_period_hours: env_optional("DELETION_GRACE_PERIOD_HOURS").unwrap_or(72),
+            crate_transfer_waiting_period_hours: env_optional(
+                "CRATE_TRANSFER_WAITING_PERIOD_HOURS",
+            )
+            .unwrap_or(72),
+            reject_duplicate_tarballs: env_optional("REJECT_DUPLICATE_TARBALLS").unwrap_or(true),
             metrics_authorization_token: dotenv::var("METRICS_AUTHORIZATION_TOKEN").ok(),
+            docs_rs_callback_token: dotenv::var("DOCS_RS_CALLBACK_TOKEN").ok(),
             use_test_database_pool: false,
             instance_metrics_log_every_seconds: env_optional("INSTANCE_METRICS_LOG_EVERY_SECONDS"),
             force_unconditional_redirects: dotenv::var("FORCE_UNCONDITIONAL_REDIRECTS").is_ok(),
@@ -148,6 +248,14 @@ impl Default for Server {
             version_id_cache_ttl: Duration::from_secs(
                 env_optional("VERSION_ID_CACHE_TTL").unwrap_or(DEFAULT_VERSION_ID_CACHE_TTL),
             ),
+            suggestions_cache_size: env_optional("SUGGESTIONS_CACHE_SIZE")
+                .unwrap_or(DEFAULT_SUGGESTIONS_CACHE_SIZE),
+            suggestions_cache_ttl: Duration::from_secs(
+                env_optional("SUGGESTIONS_CACHE_TTL").unwrap_or(DEFAULT_SUGGESTIONS_CACHE_TTL),
+            ),
+            licenses_cache_ttl: Duration::from_secs(
+                env_optional("LICENSES_CACHE_TTL").unwrap_or(DEFAULT_LICENSES_CACHE_TTL),
+            ),
             cdn_user_agent: dotenv::var("WEB_CDN_USER_AGENT")
                 .unwrap_or_else(|_| "Amazon CloudFront".into()),
             balance_capacity: BalanceCapacityConfig::from_environment(),
@@ -7,8 +7,22 @@
 //! - `AWS_ACCESS_KEY`: The access key to interact with S3. Optional if running a mirror.
 //! - `AWS_SECRET_KEY`: The secret key to interact with S3. Optional if running a mirror.
 //! - `S3_CDN`: Optional CDN configuration for building public facing URLs.
+//! - `S3_REPLICA_BUCKETS`: Optional comma-separated list of buckets that crate files and readmes
+//!    are replicated to, each as `name` or `name@region`.
+//! - `STORAGE_BACKEND`: Where crate files are stored, `s3`, `local`, or `memory`. Defaults to
+//!    `s3` in production, and in development to `s3` if `S3_BUCKET` is set and `local` otherwise.
+//! - `LOCAL_STORAGE_PATH`: The directory that the `local` storage backend stores the files in.
+//!    Defaults to `local_uploads` in the current directory.
+//! - `S3_SIGNED_URL_TTL`: If set, crate files and readmes are served through signed S3 URLs that
+//!    expire after this many seconds instead of through public URLs, so that the bucket doesn't
+//!    have to be public. The `S3_CDN` is bypassed for these files.
+//! - `CONTENT_ADDRESSED_STORAGE`: If set, newly published crate files are stored under their
+//!    SHA-256 checksum instead of their crate name and version.
 
+use crate::storage::{InMemoryStorage, LocalStorage, S3Storage};
 use crate::{env, uploaders::Uploader, Env};
+use chrono::Duration;
+use std::path::PathBuf;
 
 pub struct Base {
     pub env: Env,
@@ -24,40 +38,57 @@ impl Base {
             Env::Development
         };
 
-        let uploader = match env {
-            Env::Production => {
+        let backend = dotenv::var("STORAGE_BACKEND").ok();
+        let backend = backend.as_deref().unwrap_or(match env {
+            Env::Production => "s3",
+            _ if dotenv::var("S3_BUCKET").is_ok() => "s3",
+            _ => "local",
+        });
+
+        let uploader = match (backend, env) {
+            ("s3", Env::Production) => {
                 // `env` panics if these vars are not set, and in production for a primary instance,
                 // that's what we want since we don't want to be able to start the server if the
                 // server doesn't know where to upload crates.
                 Self::s3_panic_if_missing_keys()
             }
-            // In Development mode, either running as a primary instance or a read-only mirror
-            _ => {
-                if dotenv::var("S3_BUCKET").is_ok() {
-                    // If we've set the `S3_BUCKET` variable to any value, use all of the values
-                    // for the related S3 environment variables and configure the app to upload to
-                    // and read from S3 like production does. All values except for bucket are
-                    // optional, like production read-only mirrors.
-                    info!("Using S3 uploader");
-                    Self::s3_maybe_read_only()
-                } else {
-                    // If we don't set the `S3_BUCKET` variable, we'll use a development-only
-                    // uploader that makes it possible to run and publish to a locally-running
-                    // crates.io instance without needing to set up an account and a bucket in S3.
-                    info!(
-                        "Using local uploader, crate files will be in the local_uploads directory"
-                    );
-                    Uploader::Local
-                }
+            ("s3", _) => {
+                // In Development mode, either running as a primary instance or a read-only
+                // mirror, use all of the values for the related S3 environment variables and
+                // configure the app to upload to and read from S3 like production does. All
+                // values except for bucket are optional, like production read-only mirrors.
+                info!("Using S3 uploader");
+                Self::s3_maybe_read_only()
+            }
+            ("local", _) => {
+                // The local uploader makes it possible to run and publish to a crates.io
+                // instance without needing to set up an account and a bucket in S3.
+                let path = dotenv::var("LOCAL_STORAGE_PATH")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|_| std::env::current_dir().unwrap().join("local_uploads"));
+                info!(path = %path.display(), "Using local uploader");
+                Uploader::new(LocalStorage::new(path))
+            }
+            ("memory", _) => {
+                warn!("Using in-memory uploader, crate files are lost on restart");
+                Uploader::new(InMemoryStorage::new())
             }
+            (backend, _) => panic!("unknown STORAGE_BACKEND `{backend}`"),
+        };
+
+        let uploader = if dotenv::var("CONTENT_ADDRESSED_STORAGE").is_ok() {
+            info!("Storing crate files under their content address");
+            uploader.with_content_addressed_layout()
+        } else {
+            uploader
         };
 
         Self { env, uploader }
     }
 
     pub fn test() -> Self {
-        let uploader = Uploader::S3 {
-            bucket: Box::new(s3::Bucket::new(
+        let uploader = Uploader::new(S3Storage::new(
+            s3::Bucket::new(
                 String::from("alexcrichton-test"),
                 None,
                 dotenv::var("AWS_ACCESS_KEY").unwrap_or_default(),
@@ -65,8 +96,8 @@ impl Base {
                 // When testing we route all API traffic over HTTP so we can
                 // sniff/record it, but everywhere else we use https
                 "http",
-            )),
-            index_bucket: Some(Box::new(s3::Bucket::new(
+            ),
+            Some(s3::Bucket::new(
                 String::from("alexcrichton-test"),
                 None,
                 dotenv::var("AWS_ACCESS_KEY").unwrap_or_default(),
@@ -74,9 +105,9 @@ impl Base {
                 // When testing we route all API traffic over HTTP so we can
                 // sniff/record it, but everywhere else we use https
                 "http",
-            ))),
-            cdn: None,
-        };
+            )),
+            None,
+        ));
         Self {
             env: Env::Test,
             uploader,
@@ -87,51 +118,94 @@ impl Base {
         &self.uploader
     }
 
+    /// Replaces the uploader, e.g. to store the files in memory in tests.
+    pub fn set_uploader(&mut self, uploader: Uploader) {
+        self.uploader = uploader;
+    }
+
     fn s3_panic_if_missing_keys() -> Uploader {
         let index_bucket = match dotenv::var("S3_INDEX_BUCKET") {
-            Ok(name) => Some(Box::new(s3::Bucket::new(
+            Ok(name) => Some(s3::Bucket::new(
                 name,
                 dotenv::var("S3_INDEX_REGION").ok(),
                 env("AWS_ACCESS_KEY"),
                 env("AWS_SECRET_KEY"),
                 "https",
-            ))),
+            )),
             Err(_) => None,
         };
-        Uploader::S3 {
-            bucket: Box::new(s3::Bucket::new(
+        let uploader = Uploader::new(Self::s3_storage(
+            s3::Bucket::new(
                 env("S3_BUCKET"),
                 dotenv::var("S3_REGION").ok(),
                 env("AWS_ACCESS_KEY"),
                 env("AWS_SECRET_KEY"),
                 "https",
-            )),
+            ),
             index_bucket,
-            cdn: dotenv::var("S3_CDN").ok(),
-        }
+        ));
+        Self::with_s3_replicas(uploader, &env("AWS_ACCESS_KEY"), &env("AWS_SECRET_KEY"))
     }
 
     fn s3_maybe_read_only() -> Uploader {
         let index_bucket = match dotenv::var("S3_INDEX_BUCKET") {
-            Ok(name) => Some(Box::new(s3::Bucket::new(
+            Ok(name) => Some(s3::Bucket::new(
                 name,
                 dotenv::var("S3_INDEX_REGION").ok(),
                 dotenv::var("AWS_ACCESS_KEY").unwrap_or_default(),
                 dotenv::var("AWS_SECRET_KEY").unwrap_or_default(),
                 "https",
-            ))),
+            )),
             Err(_) => None,
         };
-        Uploader::S3 {
-            bucket: Box::new(s3::Bucket::new(
+        let uploader = Uploader::new(Self::s3_storage(
+            s3::Bucket::new(
                 env("S3_BUCKET"),
                 dotenv::var("S3_REGION").ok(),
                 dotenv::var("AWS_ACCESS_KEY").unwrap_or_default(),
                 dotenv::var("AWS_SECRET_KEY").unwrap_or_default(),
                 "https",
-            )),
+            ),
             index_bucket,
-            cdn: dotenv::var("S3_CDN").ok(),
+        ));
+        Self::with_s3_replicas(
+            uploader,
+            &dotenv::var("AWS_ACCESS_KEY").unwrap_or_default(),
+            &dotenv::var("AWS_SECRET_KEY").unwrap_or_default(),
+        )
+    }
+
+    fn s3_storage(bucket: s3::Bucket, index_bucket: Option<s3::Bucket>) -> S3Storage {
+        let storage = S3Storage::new(bucket, index_bucket, dotenv::var("S3_CDN").ok());
+        match dotenv::var("S3_SIGNED_URL_TTL") {
+            Ok(ttl) => {
+                let ttl = ttl
+                    .parse()
+                    .expect("S3_SIGNED_URL_TTL must be a number of seconds");
+                info!(ttl, "Serving crate files through signed URLs");
+                storage.with_signed_urls(Duration::seconds(ttl))
+            }
+            Err(_) => storage,
+        }
+    }
+
+    fn with_s3_replicas(mut uploader: Uploader, access_key: &str, secret_key: &str) -> Uploader {
+        let replicas = dotenv::var("S3_REPLICA_BUCKETS").unwrap_or_default();
+        for replica in replicas.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, region) = match replica.split_once('@') {
+                Some((name, region)) => (name, Some(region.to_string())),
+                None => (replica, None),
+            };
+            let bucket = s3::Bucket::new(
+                name.to_string(),
+                region,
+                access_key.to_string(),
+                secret_key.to_string(),
+                "https",
+            );
+            info!(%replica, "Replicating crate files to S3 bucket");
+            uploader = uploader.with_replica(name, S3Storage::new(bucket, None, None));
         }
+        uploader
     }
 }
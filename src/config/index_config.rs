@@ -0,0 +1,59 @@
+use crate::env_optional;
+
+/// Contents of the `config.json` file at the root of the index, which tells
+/// cargo where crates are downloaded from and where the web API is.
+///
+/// See <https://doc.rust-lang.org/cargo/reference/registry-index.html#index-configuration>.
+#[derive(Clone, Debug, Serialize)]
+pub struct IndexConfig {
+    /// The URL that crate files are downloaded from. Cargo appends
+    /// `/{crate}/{version}/download` to it unless it contains markers like
+    /// `{crate}` or `{version}`.
+    pub dl: String,
+    /// The URL of the web API, or `None` if the registry does not support
+    /// publishing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api: Option<String>,
+    /// Whether cargo has to authenticate all requests to the registry,
+    /// including fetches of the index and crate downloads.
+    #[serde(rename = "auth-required", skip_serializing_if = "is_false")]
+    pub auth_required: bool,
+}
+
+impl IndexConfig {
+    /// Reads the configuration from the following environment variables,
+    /// defaulting to the URLs of the `domain_name`:
+    ///
+    /// - `INDEX_CONFIG_DL`: The download URL.
+    /// - `INDEX_CONFIG_API`: The API URL. Set to an empty string to disable
+    ///   publishing.
+    /// - `INDEX_AUTH_REQUIRED`: If set, cargo authenticates all requests, and
+    ///   the sparse index and crate downloads are only served to requests with
+    ///   a valid token.
+    pub fn from_environment(domain_name: &str) -> Self {
+        let defaults = Self::for_domain(domain_name);
+        let api = match env_optional::<String>("INDEX_CONFIG_API") {
+            None => defaults.api,
+            Some(s) if s.is_empty() => None,
+            Some(s) => Some(s),
+        };
+
+        Self {
+            dl: env_optional("INDEX_CONFIG_DL").unwrap_or(defaults.dl),
+            api,
+            auth_required: dotenv::var("INDEX_AUTH_REQUIRED").is_ok(),
+        }
+    }
+
+    pub fn for_domain(domain_name: &str) -> Self {
+        Self {
+            dl: format!("https://{domain_name}/api/v1/crates"),
+            api: Some(format!("https://{domain_name}")),
+            auth_required: false,
+        }
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
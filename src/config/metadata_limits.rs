@@ -0,0 +1,51 @@
+use crate::env_optional;
+
+/// Limits for the metadata of publish requests.
+///
+/// Crates with huge numbers of dependencies or features bloat the index and
+/// slow down dependency resolution for everyone using them.
+pub struct MetadataLimits {
+    pub max_dependencies: usize,
+    pub max_features: usize,
+    pub max_feature_name_length: usize,
+    /// The maximum size of the JSON metadata in bytes, including the readme.
+    pub max_metadata_size: usize,
+}
+
+impl Default for MetadataLimits {
+    /// The limits that are used in production unless they are overridden by
+    /// the environment. They leave plenty of room for the largest crates
+    /// published so far.
+    fn default() -> Self {
+        Self {
+            max_dependencies: 500,
+            max_features: 300,
+            max_feature_name_length: 100,
+            max_metadata_size: 5 * 1024 * 1024, // 5 MB
+        }
+    }
+}
+
+impl MetadataLimits {
+    pub fn from_environment() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_dependencies: env_optional("PUBLISH_MAX_DEPENDENCIES")
+                .unwrap_or(defaults.max_dependencies),
+            max_features: env_optional("PUBLISH_MAX_FEATURES").unwrap_or(defaults.max_features),
+            max_feature_name_length: env_optional("PUBLISH_MAX_FEATURE_NAME_LENGTH")
+                .unwrap_or(defaults.max_feature_name_length),
+            max_metadata_size: env_optional("PUBLISH_MAX_METADATA_SIZE")
+                .unwrap_or(defaults.max_metadata_size),
+        }
+    }
+
+    pub fn for_testing() -> Self {
+        Self {
+            max_dependencies: 500,
+            max_features: 300,
+            max_feature_name_length: 100,
+            max_metadata_size: 5 * 1024 * 1024, // 5 MB
+        }
+    }
+}
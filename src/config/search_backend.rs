@@ -0,0 +1,30 @@
+/// The backend that matches crates against the text query of a search.
+pub enum SearchBackendConfig {
+    /// Search with the full-text search and the trigram indexes of the
+    /// database.
+    Postgres,
+    /// Offload the text matching to a Meilisearch instance, which is kept in
+    /// sync with the database by background jobs.
+    Meilisearch {
+        url: String,
+        api_key: Option<String>,
+    },
+}
+
+impl SearchBackendConfig {
+    pub fn from_environment() -> Self {
+        match dotenv::var("SEARCH_BACKEND").as_deref() {
+            Err(_) | Ok("postgres") => Self::Postgres,
+            Ok("meilisearch") => Self::Meilisearch {
+                url: dotenv::var("MEILISEARCH_URL").expect("missing MEILISEARCH_URL"),
+                api_key: dotenv::var("MEILISEARCH_API_KEY").ok(),
+            },
+            Ok(backend) => panic!("unknown SEARCH_BACKEND `{backend}`"),
+        }
+    }
+
+    /// Whether the search index has to be updated when crates change.
+    pub fn needs_sync(&self) -> bool {
+        !matches!(self, Self::Postgres)
+    }
+}
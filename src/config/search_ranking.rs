@@ -0,0 +1,38 @@
+use crate::env_optional;
+
+/// Weights of the signals that are combined into the relevance of a crate
+/// when search results are sorted by relevance.
+///
+/// Exact name matches always come first, the remaining results are ordered
+/// by the weighted sum of these signals.
+#[derive(Clone)]
+pub struct SearchRanking {
+    /// Weight of the trigram similarity between the crate name and the
+    /// query, which ranges from 0 to 1.
+    pub name_weight: f64,
+    /// Weight of the full-text rank of the query in the name, keywords,
+    /// description and readme of the crate.
+    pub text_weight: f64,
+    /// Weight of the logarithm of the recent downloads of the crate.
+    pub popularity_weight: f64,
+}
+
+impl SearchRanking {
+    pub fn from_environment() -> Self {
+        let defaults = Self::for_testing();
+        Self {
+            name_weight: env_optional("SEARCH_NAME_WEIGHT").unwrap_or(defaults.name_weight),
+            text_weight: env_optional("SEARCH_TEXT_WEIGHT").unwrap_or(defaults.text_weight),
+            popularity_weight: env_optional("SEARCH_POPULARITY_WEIGHT")
+                .unwrap_or(defaults.popularity_weight),
+        }
+    }
+
+    pub fn for_testing() -> Self {
+        Self {
+            name_weight: 0.3,
+            text_weight: 1.0,
+            popularity_weight: 0.05,
+        }
+    }
+}
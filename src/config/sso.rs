@@ -0,0 +1,89 @@
+use crate::env;
+use oauth2::{ClientId, ClientSecret};
+
+/// The single sign-on identity provider of a private deployment, that users
+/// log in with via OpenID Connect. SAML identity providers are not supported.
+///
+/// Users are signed up when they log in for the first time, and become
+/// members of the teams that the groups of their identity are mapped to.
+pub struct SsoConfig {
+    pub client_id: ClientId,
+    pub client_secret: ClientSecret,
+    pub authorize_url: String,
+    pub token_url: String,
+    pub userinfo_url: String,
+    /// The URL of the JSON Web Key Set that the ID tokens are signed with.
+    pub jwks_url: String,
+    /// The `iss` claim of the ID tokens.
+    pub issuer: String,
+    pub redirect_url: String,
+    /// The claim of the userinfo response that lists the groups of the user.
+    pub groups_claim: String,
+    /// Pairs of groups at the identity provider and the names of the
+    /// `sso:<name>` teams that their members belong to.
+    pub group_teams: Vec<(String, String)>,
+}
+
+impl SsoConfig {
+    /// Reads the configuration from the environment, if `SSO_CLIENT_ID` is
+    /// set:
+    ///
+    /// - `SSO_CLIENT_ID`, `SSO_CLIENT_SECRET`: The credentials of crates.io at the identity
+    ///   provider.
+    /// - `SSO_AUTHORIZE_URL`, `SSO_TOKEN_URL`, `SSO_USERINFO_URL`, `SSO_JWKS_URL`: The OpenID
+    ///   Connect endpoints of the identity provider.
+    /// - `SSO_ISSUER`: The issuer identifier of the identity provider.
+    /// - `SSO_REDIRECT_URL`: The callback URL of the login. Defaults to the GitHub redirect page.
+    /// - `SSO_GROUPS_CLAIM`: The claim that lists the groups of the user. Defaults to `groups`.
+    /// - `SSO_GROUP_TEAMS`: A comma separated list of `group=team` pairs, which make the members of
+    ///   the group members of the `sso:team` team.
+    pub fn from_environment(domain_name: &str) -> Option<Self> {
+        let client_id = dotenv::var("SSO_CLIENT_ID").ok()?;
+
+        let group_teams = match dotenv::var("SSO_GROUP_TEAMS") {
+            Ok(pairs) => parse_group_teams(&pairs),
+            Err(_) => vec![],
+        };
+
+        Some(Self {
+            client_id: ClientId::new(client_id),
+            client_secret: ClientSecret::new(env("SSO_CLIENT_SECRET")),
+            authorize_url: env("SSO_AUTHORIZE_URL"),
+            token_url: env("SSO_TOKEN_URL"),
+            userinfo_url: env("SSO_USERINFO_URL"),
+            jwks_url: env("SSO_JWKS_URL"),
+            issuer: env("SSO_ISSUER"),
+            redirect_url: dotenv::var("SSO_REDIRECT_URL")
+                .unwrap_or_else(|_| format!("https://{domain_name}/github-redirect.html")),
+            groups_claim: dotenv::var("SSO_GROUPS_CLAIM").unwrap_or_else(|_| "groups".into()),
+            group_teams,
+        })
+    }
+
+    /// Returns the logins of the teams that the members of the groups belong
+    /// to.
+    pub fn teams_for(&self, groups: &[String]) -> Vec<String> {
+        let mut logins: Vec<_> = self
+            .group_teams
+            .iter()
+            .filter(|(group, _)| groups.contains(group))
+            .map(|(_, team)| format!("sso:{team}"))
+            .collect();
+        logins.sort();
+        logins.dedup();
+        logins
+    }
+}
+
+fn parse_group_teams(pairs: &str) -> Vec<(String, String)> {
+    pairs
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| {
+            let (group, team) = pair
+                .split_once('=')
+                .unwrap_or_else(|| panic!("invalid SSO_GROUP_TEAMS entry `{pair}`"));
+            (group.trim().to_string(), team.trim().to_lowercase())
+        })
+        .collect()
+}
@@ -64,14 +64,18 @@ mod prelude {
 pub mod helpers;
 pub mod util;
 
+pub mod admin;
 pub mod category;
-mod conduit_axum;
+pub mod conduit_axum;
 pub mod crate_owner_invitation;
+pub mod crate_transfer;
 pub mod git;
 pub mod github;
+pub mod index;
 pub mod keyword;
 pub mod krate;
 pub mod metrics;
+pub mod organization;
 pub mod site_metadata;
 pub mod team;
 pub mod token;
@@ -0,0 +1,369 @@
+//! Endpoints for the admin actions of the crates.io team.
+//!
+//! Each endpoint requires a single `AdminPermission`, which users are granted
+//! through admin roles that are managed with `crates-admin admin-roles`. Every
+//! action that changes something is recorded in the `admin_actions` table,
+//! together with the reason that the admin gave for it. `DELETE` endpoints
+//! take the reason as the `reason` query parameter.
+
+use chrono::NaiveDateTime;
+
+use crate::auth::{ensure_not_locked, AdminCheck};
+use crate::controllers::frontend_prelude::*;
+use crate::controllers::krate::delete::purge_crate;
+use crate::models::{AdminPermission, ApiToken, Crate, User};
+use crate::schema::{
+    api_tokens, crate_file_checks, crates, index_inconsistencies, reserved_crate_names, users,
+    versions,
+};
+use crate::sql::canon_crate_name;
+use crate::util::errors::not_found;
+use crate::util::rfc3339::Timestamp;
+
+/// Handles the `DELETE /api/private/admin/crates/:crate_id` route.
+///
+/// Unlike owners, admins can delete crates after the grace period and while
+/// other crates depend on them, e.g. to remove malware.
+pub async fn delete_crate(
+    app: AppState,
+    Path(crate_name): Path<String>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AdminCheck::new(AdminPermission::CratesDelete).check(&req, conn)?;
+        let reason = req.query().get("reason").cloned();
+
+        let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
+
+        conn.transaction(|conn| {
+            purge_crate(&app, conn, &krate, auth.user())?;
+
+            let target = format!("crate {}", krate.name);
+            auth.record_action(conn, &target, reason.as_deref())?;
+
+            ok_true()
+        })
+    })
+    .await
+}
+
+/// Handles the `PUT /api/private/admin/crates/:crate_id/upload_limit` route.
+///
+/// Overrides the maximum upload size of the crate, e.g. for crates with large
+/// generated bindings. A `max_upload_size` of `null` removes the override, so
+/// that the global limit applies again.
+pub async fn set_upload_limit(
+    app: AppState,
+    Path(crate_name): Path<String>,
+    req: BytesRequest,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct UploadLimitRequest {
+            max_upload_size: Option<u32>,
+            reason: Option<String>,
+        }
+
+        let limit: UploadLimitRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid upload limit request: {e}")))?;
+
+        let max_upload_size = limit
+            .max_upload_size
+            .map(i32::try_from)
+            .transpose()
+            .map_err(|_| bad_request("max_upload_size is too large"))?;
+
+        let conn = &mut *app.db_write()?;
+        let auth = AdminCheck::new(AdminPermission::CratesLimits).check(&req, conn)?;
+
+        let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
+
+        conn.transaction(|conn| {
+            diesel::update(&krate)
+                .set(crates::max_upload_size.eq(max_upload_size))
+                .execute(conn)?;
+
+            let target = format!("crate {}", krate.name);
+            auth.record_action(conn, &target, limit.reason.as_deref())?;
+
+            ok_true()
+        })
+    })
+    .await
+}
+
+/// Handles the `PUT /api/private/admin/reserved_crate_names/:name` route.
+///
+/// Reserved names can't be published by anyone. Names of existing crates
+/// can't be reserved.
+pub async fn reserve_crate_name(
+    app: AppState,
+    Path(name): Path<String>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AdminCheck::new(AdminPermission::CratesReserve).check(&req, conn)?;
+        let reason = req.query().get("reason").cloned();
+
+        let exists = diesel::select(diesel::dsl::exists(Crate::by_name(&name))).get_result(conn)?;
+        if exists {
+            return Err(bad_request(&format_args!(
+                "a crate with the name `{name}` already exists"
+            )));
+        }
+
+        conn.transaction(|conn| {
+            diesel::insert_into(reserved_crate_names::table)
+                .values(reserved_crate_names::name.eq(&name))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+
+            let target = format!("crate name {name}");
+            auth.record_action(conn, &target, reason.as_deref())?;
+
+            ok_true()
+        })
+    })
+    .await
+}
+
+/// Handles the `DELETE /api/private/admin/reserved_crate_names/:name` route.
+///
+/// Releases a reserved name, so that it can be published again.
+pub async fn release_crate_name(
+    app: AppState,
+    Path(name): Path<String>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AdminCheck::new(AdminPermission::CratesReserve).check(&req, conn)?;
+        let reason = req.query().get("reason").cloned();
+
+        conn.transaction(|conn| {
+            let reserved = reserved_crate_names::table
+                .filter(canon_crate_name(reserved_crate_names::name).eq(canon_crate_name(&name)));
+            let released: Vec<String> = diesel::delete(reserved)
+                .returning(reserved_crate_names::name)
+                .get_results(conn)?;
+
+            let Some(released) = released.first() else {
+                return Err(not_found());
+            };
+
+            let target = format!("crate name {released}");
+            auth.record_action(conn, &target, reason.as_deref())?;
+
+            ok_true()
+        })
+    })
+    .await
+}
+
+/// Handles the `PUT /api/private/admin/users/:login/suspension` route.
+///
+/// Suspended users can't log in, publish or use their API tokens until the
+/// suspension ends. They are notified by email with the reason for the
+/// suspension. Accounts that are locked for another reason, e.g. because they
+/// were deleted, can't be suspended.
+pub async fn suspend_user(
+    app: AppState,
+    Path(login): Path<String>,
+    req: BytesRequest,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct SuspensionRequest {
+            reason: String,
+            until: Option<NaiveDateTime>,
+        }
+
+        let suspension: SuspensionRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid suspension request: {e}")))?;
+
+        let reason = suspension.reason.trim();
+        if reason.is_empty() {
+            return Err(bad_request("the reason for a suspension is required"));
+        }
+
+        let conn = &mut *app.db_write()?;
+        let auth = AdminCheck::new(AdminPermission::UsersSuspend).check(&req, conn)?;
+        let user = User::find_by_login(conn, &login)?;
+        if !user.account_suspended && ensure_not_locked(&user).is_err() {
+            return Err(bad_request("this account is locked for another reason"));
+        }
+
+        conn.transaction(|conn| {
+            diesel::update(&user)
+                .set((
+                    users::account_lock_reason.eq(reason),
+                    users::account_lock_until.eq(suspension.until),
+                    users::account_suspended.eq(true),
+                ))
+                .execute(conn)?;
+
+            let target = format!("user {}", user.gh_login);
+            auth.record_action(conn, &target, Some(reason))
+        })?;
+
+        if let Some(email) = user.verified_email(conn)? {
+            let result =
+                app.emails
+                    .send_account_suspended(&email, &user.gh_login, reason, suspension.until);
+            if let Err(error) = result {
+                warn!(user = %user.gh_login, %error, "Failed to send suspension email");
+            }
+        }
+
+        ok_true()
+    })
+    .await
+}
+
+/// Handles the `DELETE /api/private/admin/users/:login/suspension` route.
+///
+/// Only lifts locks that were set by a suspension, so that e.g. deleted
+/// accounts stay locked.
+pub async fn unsuspend_user(
+    app: AppState,
+    Path(login): Path<String>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AdminCheck::new(AdminPermission::UsersSuspend).check(&req, conn)?;
+        let reason = req.query().get("reason").cloned();
+        let user = User::find_by_login(conn, &login)?;
+        if !user.account_suspended {
+            return Err(bad_request("this account is not suspended"));
+        }
+
+        conn.transaction(|conn| {
+            diesel::update(&user)
+                .set((
+                    users::account_lock_reason.eq(None::<String>),
+                    users::account_lock_until.eq(None::<NaiveDateTime>),
+                    users::account_suspended.eq(false),
+                ))
+                .execute(conn)?;
+
+            let target = format!("user {}", user.gh_login);
+            auth.record_action(conn, &target, reason.as_deref())
+        })?;
+
+        if let Some(email) = user.verified_email(conn)? {
+            let result = app.emails.send_account_unsuspended(&email, &user.gh_login);
+            if let Err(error) = result {
+                warn!(user = %user.gh_login, %error, "Failed to send unsuspension email");
+            }
+        }
+
+        ok_true()
+    })
+    .await
+}
+
+/// Handles the `DELETE /api/private/admin/tokens/:token_id` route.
+pub async fn revoke_token(app: AppState, Path(id): Path<i32>, req: Parts) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AdminCheck::new(AdminPermission::TokensRevoke).check(&req, conn)?;
+        let reason = req.query().get("reason").cloned();
+
+        let token: ApiToken = api_tokens::table.find(id).first(conn)?;
+
+        conn.transaction(|conn| {
+            diesel::update(&token)
+                .set(api_tokens::revoked.eq(true))
+                .execute(conn)?;
+
+            let target = format!("token {} of user {}", token.id, token.user_id);
+            auth.record_action(conn, &target, reason.as_deref())?;
+
+            ok_true()
+        })
+    })
+    .await
+}
+
+/// Handles the `GET /api/private/admin/index_inconsistencies` route.
+///
+/// Lists the crates whose index files did not match the database when the
+/// `check_index_consistency` background job last sampled them. The monitor
+/// links to this instead of listing the crates in its alert.
+pub async fn list_index_inconsistencies(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        AdminCheck::new(AdminPermission::IntegrityRead).check(&req, conn)?;
+
+        let inconsistencies: Vec<(String, bool, bool, NaiveDateTime)> =
+            index_inconsistencies::table
+                .inner_join(crates::table)
+                .select((
+                    crates::name,
+                    index_inconsistencies::git_mismatch,
+                    index_inconsistencies::sparse_mismatch,
+                    index_inconsistencies::detected_at,
+                ))
+                .order(crates::name)
+                .load(conn)?;
+
+        let inconsistencies = inconsistencies
+            .into_iter()
+            .map(|(name, git_mismatch, sparse_mismatch, detected_at)| {
+                json!({
+                    "crate": name,
+                    "git_mismatch": git_mismatch,
+                    "sparse_mismatch": sparse_mismatch,
+                    "detected_at": Timestamp(detected_at),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "index_inconsistencies": inconsistencies })))
+    })
+    .await
+}
+
+/// Handles the `GET /api/private/admin/crate_file_checks` route.
+///
+/// Lists the crate files that were missing or did not match their checksum
+/// when the `verify_crate_files` background job last checked them. The
+/// monitor links to this instead of listing the files in its alert.
+pub async fn list_failed_crate_file_checks(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        AdminCheck::new(AdminPermission::IntegrityRead).check(&req, conn)?;
+
+        let checks: Vec<(String, String, bool, bool, NaiveDateTime)> = crate_file_checks::table
+            .inner_join(versions::table.inner_join(crates::table))
+            .filter(crate_file_checks::missing.or(crate_file_checks::checksum_mismatch))
+            .select((
+                crates::name,
+                versions::num,
+                crate_file_checks::missing,
+                crate_file_checks::checksum_mismatch,
+                crate_file_checks::checked_at,
+            ))
+            .order((crates::name, versions::num))
+            .load(conn)?;
+
+        let checks = checks
+            .into_iter()
+            .map(|(name, num, missing, checksum_mismatch, checked_at)| {
+                json!({
+                    "crate": name,
+                    "version": num,
+                    "missing": missing,
+                    "checksum_mismatch": checksum_mismatch,
+                    "checked_at": Timestamp(checked_at),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "crate_file_checks": checks })))
+    })
+    .await
+}
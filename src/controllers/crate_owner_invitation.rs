@@ -3,15 +3,20 @@ use super::frontend_prelude::*;
 use crate::auth::AuthCheck;
 use crate::auth::Authentication;
 use crate::controllers::helpers::pagination::{Page, PaginationOptions};
-use crate::models::{Crate, CrateOwnerInvitation, Rights, User};
+use crate::middleware::session::client_info;
+use crate::models::{
+    Crate, CrateOwnerInvitation, Rights, SecurityEvent, SecurityLogEntry, User, WebhookEvent,
+};
 use crate::schema::{crate_owner_invitations, crates, users};
 use crate::util::errors::{forbidden, internal};
 use crate::views::{
     EncodableCrateOwnerInvitation, EncodableCrateOwnerInvitationV1, EncodablePublicUser,
     InvitationResponse,
 };
+use crate::worker;
 use chrono::{Duration, Utc};
 use diesel::{pg::Pg, sql_types::Bool};
+use http::HeaderMap;
 use indexmap::IndexMap;
 use std::collections::{HashMap, HashSet};
 
@@ -106,7 +111,7 @@ fn prepare_list(
                 // Only allow crate owners to query pending invitations for their crate.
                 let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
                 let owners = krate.owners(conn)?;
-                if user.rights(state, &owners)? != Rights::Full {
+                if user.rights(state, conn, &owners)? != Rights::Full {
                     return Err(forbidden());
                 }
 
@@ -274,7 +279,15 @@ pub async fn handle_invite(state: AppState, req: BytesRequest) -> AppResult<Json
 
         let invitation = CrateOwnerInvitation::find_by_id(user_id, crate_invite.crate_id, conn)?;
         if crate_invite.accepted {
+            let inviter_id = invitation.invited_by_user_id;
             invitation.accept(conn, config)?;
+            trigger_owner_added(
+                conn,
+                req.headers(),
+                crate_invite.crate_id,
+                user_id,
+                inviter_id,
+            )?;
         } else {
             invitation.decline(conn)?;
         }
@@ -288,6 +301,7 @@ pub async fn handle_invite(state: AppState, req: BytesRequest) -> AppResult<Json
 pub async fn handle_invite_with_token(
     state: AppState,
     Path(token): Path<String>,
+    req: Parts,
 ) -> AppResult<Json<Value>> {
     conduit_compat(move || {
         let config = &state.config;
@@ -295,7 +309,10 @@ pub async fn handle_invite_with_token(
 
         let invitation = CrateOwnerInvitation::find_by_token(&token, conn)?;
         let crate_id = invitation.crate_id;
+        let user_id = invitation.invited_user_id;
+        let inviter_id = invitation.invited_by_user_id;
         invitation.accept(conn, config)?;
+        trigger_owner_added(conn, &req.headers, crate_id, user_id, inviter_id)?;
 
         Ok(Json(json!({
             "crate_owner_invitation": {
@@ -306,3 +323,48 @@ pub async fn handle_invite_with_token(
     })
     .await
 }
+
+/// Notifies the webhooks of the crate that the user accepted the invitation
+/// and is now an owner, and records it in the security logs of the user and
+/// of the owner that invited them.
+fn trigger_owner_added(
+    conn: &mut PgConnection,
+    headers: &HeaderMap,
+    crate_id: i32,
+    user_id: i32,
+    inviter_id: i32,
+) -> AppResult<()> {
+    let crate_name: String = crates::table
+        .find(crate_id)
+        .select(crates::name)
+        .first(conn)?;
+    let login: String = users::table
+        .find(user_id)
+        .select(users::gh_login)
+        .first(conn)?;
+
+    let (ip_address, user_agent) = client_info(headers);
+    let details = format!("crate {crate_name}, owner {login}");
+    SecurityLogEntry::record(
+        conn,
+        user_id,
+        SecurityEvent::OwnerAdded,
+        Some(&details),
+        ip_address,
+        user_agent,
+    )?;
+    // The client of the request belongs to the new owner, not to the inviter
+    SecurityLogEntry::record(
+        conn,
+        inviter_id,
+        SecurityEvent::OwnerAdded,
+        Some(&details),
+        None,
+        None,
+    )?;
+
+    let data = json!({ "owner": login });
+    worker::trigger_webhooks(conn, crate_id, &crate_name, WebhookEvent::OwnerAdded, data)?;
+
+    Ok(())
+}
@@ -0,0 +1,297 @@
+//! Endpoints for transferring the ownership of a crate from one of its owners
+//! to another user.
+//!
+//! An individual owner starts a transfer with `POST
+//! /crates/:crate_id/transfers`, which the recipient accepts or declines with
+//! `PUT /me/crate_transfers/:transfer_id`. Accepted transfers are completed by
+//! `worker::complete_crate_transfers()` once the waiting period of
+//! `config::Server::crate_transfer_waiting_period_hours` is over, and can be
+//! cancelled by the owners of the crate until then. Both users are notified by
+//! email at every step, and completed transfers are listed publicly.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::controllers::helpers::second_factor::ensure_second_factor;
+use crate::models::token::EndpointScope;
+use crate::models::{Crate, CrateTransfer, NotificationKind, Owner, Rights, User};
+use crate::schema::crates;
+use crate::util::errors::not_found;
+use crate::views::EncodableCrateTransfer;
+use chrono::Duration;
+
+/// Handles the `GET /crates/:crate_id/transfers` route.
+///
+/// Lists the completed transfers of the crate, as a public record of its
+/// ownership changes.
+pub async fn list(app: AppState, Path(crate_name): Path<String>) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read()?;
+        let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
+
+        let transfers = CrateTransfer::completed_for_crate(conn, krate.id)?
+            .into_iter()
+            .map(|transfer| EncodableCrateTransfer::new(transfer, krate.name.clone()))
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "transfers": transfers })))
+    })
+    .await
+}
+
+/// Handles the `POST /crates/:crate_id/transfers` route.
+///
+/// Only individual owners can transfer their ownership, and every crate can
+/// only have one pending transfer at a time.
+///
+/// ## Request Body Example
+///
+/// ```json
+/// {
+///     "transfer": {
+///         "to": "foo"
+///     }
+/// }
+/// ```
+pub async fn create(
+    app: AppState,
+    Path(crate_name): Path<String>,
+    req: BytesRequest,
+) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct NewTransferRequest {
+            transfer: NewTransferQuery,
+        }
+
+        #[derive(Deserialize)]
+        struct NewTransferQuery {
+            to: String,
+        }
+
+        let new: NewTransferRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid transfer request: {e}")))?;
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::default()
+            .with_endpoint_scope(EndpointScope::ChangeOwners)
+            .for_crate(&crate_name)
+            .check(&req, conn)?;
+        let user = auth.user();
+
+        let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
+        let owners = krate.owners(conn)?;
+        let is_user_owner = |user_id| {
+            owners
+                .iter()
+                .any(|owner| matches!(owner, Owner::User(owner) if owner.id == user_id))
+        };
+        if !is_user_owner(user.id) {
+            return Err(cargo_err(
+                "only individual owners of a crate can transfer their ownership",
+            ));
+        }
+
+        let recipient = User::find_by_login(conn, &new.transfer.to)
+            .optional()?
+            .ok_or_else(|| {
+                bad_request(&format_args!(
+                    "could not find user with login `{}`",
+                    new.transfer.to
+                ))
+            })?;
+        if is_user_owner(recipient.id) {
+            return Err(bad_request(&format_args!(
+                "`{}` is already an owner of this crate",
+                recipient.gh_login
+            )));
+        }
+
+        ensure_second_factor(&app, conn, &req, user.id)?;
+
+        let transfer = CrateTransfer::create(conn, krate.id, user.id, recipient.id)?;
+
+        if let Ok(Some(email)) = recipient.notification_email(conn, NotificationKind::OwnerInvites)
+        {
+            // Swallow any error, the recipient also sees the transfer in their
+            // list of pending transfers
+            let _ = app.emails.send_crate_transfer_requested(
+                &email,
+                &user.gh_login,
+                &krate.name,
+                app.config.crate_transfer_waiting_period_hours,
+            );
+        }
+
+        Ok(Json(
+            json!({ "transfer": EncodableCrateTransfer::new(transfer, krate.name) }),
+        ))
+    })
+    .await
+}
+
+/// Handles the `DELETE /crates/:crate_id/transfers/:transfer_id` route.
+///
+/// Any owner with full rights can cancel a pending transfer of the crate,
+/// also during the waiting period after the recipient accepted it.
+pub async fn cancel(
+    app: AppState,
+    Path((crate_name, transfer_id)): Path<(String, i32)>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::default()
+            .with_endpoint_scope(EndpointScope::ChangeOwners)
+            .for_crate(&crate_name)
+            .check(&req, conn)?;
+        let user = auth.user();
+
+        let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
+        let owners = krate.owners(conn)?;
+        if user.rights(&app, conn, &owners)? < Rights::Full {
+            return Err(cargo_err("only owners have permission to modify owners"));
+        }
+
+        let transfer = CrateTransfer::find_pending(conn, transfer_id)
+            .optional()?
+            .filter(|transfer| transfer.crate_id == krate.id)
+            .ok_or_else(not_found)?;
+
+        ensure_second_factor(&app, conn, &req, user.id)?;
+
+        if !transfer.cancel(conn)? {
+            return Err(bad_request("this transfer is no longer pending"));
+        }
+        notify_cancelled(&app, conn, &transfer, &krate.name, user.id);
+
+        ok_true()
+    })
+    .await
+}
+
+/// Handles the `GET /me/crate_transfers` route.
+///
+/// Lists the pending transfers that the user initiated or received.
+pub async fn list_pending(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+
+        let transfers = CrateTransfer::pending_for_user(conn, auth.user_id())?
+            .into_iter()
+            .map(|(transfer, crate_name)| EncodableCrateTransfer::new(transfer, crate_name))
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "crate_transfers": transfers })))
+    })
+    .await
+}
+
+/// Handles the `PUT /me/crate_transfers/:transfer_id` route.
+///
+/// Accepting a transfer starts its waiting period, while declining it cancels
+/// the transfer.
+///
+/// ## Request Body Example
+///
+/// ```json
+/// {
+///     "crate_transfer": {
+///         "accepted": true
+///     }
+/// }
+/// ```
+pub async fn handle(
+    app: AppState,
+    Path(transfer_id): Path<i32>,
+    req: BytesRequest,
+) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct HandleTransferRequest {
+            crate_transfer: HandleTransferQuery,
+        }
+
+        #[derive(Deserialize)]
+        struct HandleTransferQuery {
+            accepted: bool,
+        }
+
+        let request: HandleTransferRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid transfer request: {e}")))?;
+        let accepted = request.crate_transfer.accepted;
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+
+        let transfer = CrateTransfer::find_pending(conn, transfer_id)
+            .optional()?
+            .filter(|transfer| transfer.to_user_id == user.id)
+            .ok_or_else(not_found)?;
+        if transfer.is_accepted() {
+            return Err(bad_request("this transfer was already accepted"));
+        }
+
+        let crate_name: String = crates::table
+            .find(transfer.crate_id)
+            .select(crates::name)
+            .first(conn)?;
+
+        let transfer = if accepted {
+            ensure_second_factor(&app, conn, &req, user.id)?;
+
+            let hours = app.config.crate_transfer_waiting_period_hours;
+            let transfer = transfer.accept(conn, Duration::hours(hours as i64))?;
+
+            let from_user = User::find(conn, transfer.from_user_id)?;
+            if let (Ok(Some(email)), Some(completes_at)) = (
+                from_user.notification_email(conn, NotificationKind::OwnerInvites),
+                transfer.completes_at,
+            ) {
+                let _ = app.emails.send_crate_transfer_accepted(
+                    &email,
+                    &user.gh_login,
+                    &crate_name,
+                    completes_at,
+                );
+            }
+
+            transfer
+        } else {
+            if !transfer.cancel(conn)? {
+                return Err(bad_request("this transfer is no longer pending"));
+            }
+            notify_cancelled(&app, conn, &transfer, &crate_name, user.id);
+            transfer
+        };
+
+        Ok(Json(json!({
+            "crate_transfer": EncodableCrateTransfer::new(transfer, crate_name),
+            "accepted": accepted,
+        })))
+    })
+    .await
+}
+
+/// Notifies the users of a cancelled transfer, except for the one that
+/// cancelled it. Errors are swallowed, since the transfer is cancelled either
+/// way.
+fn notify_cancelled(
+    app: &AppState,
+    conn: &mut PgConnection,
+    transfer: &CrateTransfer,
+    crate_name: &str,
+    cancelled_by: i32,
+) {
+    for user_id in [transfer.from_user_id, transfer.to_user_id] {
+        if user_id == cancelled_by {
+            continue;
+        }
+        if let Ok(Some(email)) = User::find(conn, user_id)
+            .and_then(|user| user.notification_email(conn, NotificationKind::OwnerInvites))
+        {
+            let _ = app.emails.send_crate_transfer_cancelled(&email, crate_name);
+        }
+    }
+}
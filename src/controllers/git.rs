@@ -1,13 +1,18 @@
-use axum::body::HttpBody;
+use crate::app::AppState;
+use axum::body::{HttpBody, StreamBody};
 use axum::extract::{ConnectInfo, Path};
 use axum::response::{IntoResponse, Response};
+use axum::BoxError;
 use http::header::HeaderName;
 use http::request::Parts;
 use http::{header, HeaderMap, HeaderValue, Request, StatusCode};
+use http_body::{LengthLimitError, Limited};
 use hyper::body::Buf;
-use std::io::{BufRead, Read};
+use std::io::{self, BufRead, Read, Write};
 use std::net::SocketAddr;
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, Stdio};
+use std::thread::JoinHandle;
+use tokio::sync::mpsc;
 
 pub async fn http_backend<B: HttpBody>(
     Path(path): Path<String>,
@@ -98,3 +103,250 @@ fn header(req: &Parts, name: HeaderName) -> &str {
         .map(|value| value.to_str().unwrap_or_default())
         .unwrap_or_default()
 }
+
+/// The only git service that is served by `info_refs()` and `upload_pack()`.
+/// Pushing to the index over HTTP is not supported.
+const UPLOAD_PACK_SERVICE: &str = "git-upload-pack";
+
+/// Requests to `upload_pack()` only list the commits that the client wants
+/// and has, so larger requests are rejected, both before and after they are
+/// decompressed.
+const MAX_UPLOAD_PACK_REQUEST_SIZE: usize = 10 * 1024 * 1024;
+
+/// The size of the chunks in which the pack is streamed to the client.
+const PACK_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Handles the `GET /git/index/info/refs` route of the read-only smart HTTP
+/// protocol, by advertising the refs of the repository at
+/// `GIT_HTTP_INDEX_PATH`.
+///
+/// See <https://git-scm.com/docs/http-protocol#_smart_clients>.
+pub async fn info_refs(state: AppState, req: Parts) -> Result<Response, StatusCode> {
+    let repo_path = state
+        .config
+        .git_http_index_path
+        .clone()
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let service = req
+        .uri
+        .query()
+        .and_then(|query| {
+            url::form_urlencoded::parse(query.as_bytes())
+                .find(|(key, _)| key == "service")
+                .map(|(_, value)| value.into_owned())
+        })
+        .ok_or(StatusCode::FORBIDDEN)?;
+    if service != UPLOAD_PACK_SERVICE {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let git_protocol = header(&req, HeaderName::from_static("git-protocol")).to_string();
+    let is_protocol_v2 = git_protocol.contains("version=2");
+
+    let refs = tokio::task::spawn_blocking(move || {
+        let mut cmd = Command::new("git");
+        cmd.args(["upload-pack", "--stateless-rpc", "--advertise-refs"])
+            .arg(&repo_path);
+        run_upload_pack(cmd, &git_protocol, &[])
+    })
+    .await
+    .unwrap_or(Err(StatusCode::INTERNAL_SERVER_ERROR))?;
+
+    // Clients that speak protocol version 2 get the capability advertisement
+    // without the service announcement
+    let mut body = Vec::new();
+    if !is_protocol_v2 {
+        body.extend(pkt_line(&format!("# service={UPLOAD_PACK_SERVICE}\n")));
+        body.extend(b"0000");
+    }
+    body.extend(refs);
+
+    let content_type = format!("application/x-{UPLOAD_PACK_SERVICE}-advertisement");
+    Ok(git_response(&content_type, body))
+}
+
+/// Handles the `POST /git/index/git-upload-pack` route of the read-only smart
+/// HTTP protocol, by streaming the objects that the client asked for.
+pub async fn upload_pack<B>(state: AppState, req: Request<B>) -> Result<Response, StatusCode>
+where
+    B: HttpBody,
+    B::Error: Into<BoxError>,
+{
+    let repo_path = state
+        .config
+        .git_http_index_path
+        .clone()
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let (req, body) = req.into_parts();
+    let body = hyper::body::to_bytes(Limited::new(body, MAX_UPLOAD_PACK_REQUEST_SIZE))
+        .await
+        .map_err(|error| {
+            if error.is::<LengthLimitError>() {
+                StatusCode::PAYLOAD_TOO_LARGE
+            } else {
+                StatusCode::BAD_REQUEST
+            }
+        })?;
+
+    let content_type = format!("application/x-{UPLOAD_PACK_SERVICE}-request");
+    if header(&req, header::CONTENT_TYPE) != content_type {
+        return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    // git compresses large requests, e.g. when negotiating many commits
+    let body = match header(&req, header::CONTENT_ENCODING) {
+        "" => body.to_vec(),
+        "gzip" | "x-gzip" => {
+            let mut decoded = Vec::new();
+            flate2::read::GzDecoder::new(body.reader())
+                .take(MAX_UPLOAD_PACK_REQUEST_SIZE as u64 + 1)
+                .read_to_end(&mut decoded)
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+            if decoded.len() > MAX_UPLOAD_PACK_REQUEST_SIZE {
+                return Err(StatusCode::PAYLOAD_TOO_LARGE);
+            }
+            decoded
+        }
+        _ => return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+    };
+
+    let git_protocol = header(&req, HeaderName::from_static("git-protocol")).to_string();
+
+    let mut cmd = Command::new("git");
+    cmd.args(["upload-pack", "--stateless-rpc"]).arg(&repo_path);
+    let (child, writer) = spawn_upload_pack(cmd, &git_protocol, body)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // The pack can be large, so it is streamed to the client as `git
+    // upload-pack` writes it instead of being buffered
+    let (sender, receiver) = mpsc::channel(4);
+    tokio::task::spawn_blocking(move || {
+        if let Err(error) = stream_upload_pack(child, writer, &sender) {
+            warn!(%error, "git upload-pack failed");
+            // Aborts the response, so that the client doesn't mistake the
+            // partial pack for a complete one
+            let _ = sender.blocking_send(Err(error));
+        }
+    });
+    let stream = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        let chunk = receiver.recv().await?;
+        Some((chunk, receiver))
+    });
+
+    let content_type = format!("application/x-{UPLOAD_PACK_SERVICE}-result");
+    Ok(git_response(&content_type, StreamBody::new(stream)))
+}
+
+/// Starts `git upload-pack` and writes `input` to its standard input from a
+/// separate thread, since it may start writing the pack before it has read
+/// all of its input.
+fn spawn_upload_pack(
+    mut cmd: Command,
+    git_protocol: &str,
+    input: Vec<u8>,
+) -> io::Result<(Child, JoinHandle<io::Result<()>>)> {
+    if !git_protocol.is_empty() {
+        cmd.env("GIT_PROTOCOL", git_protocol);
+    }
+
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+
+    Ok((child, writer))
+}
+
+/// Waits for the input of `git upload-pack` to be written, and checks that it
+/// exited successfully.
+fn finish_upload_pack(mut child: Child, writer: JoinHandle<io::Result<()>>) -> io::Result<()> {
+    let status = child.wait()?;
+    writer
+        .join()
+        .map_err(|_| io::Error::other("writing the input panicked"))??;
+    if !status.success() {
+        let message = format!("git upload-pack exited with {status}");
+        return Err(io::Error::other(message));
+    }
+    Ok(())
+}
+
+/// Runs `git upload-pack` with `input` on its standard input and returns its
+/// standard output.
+fn run_upload_pack(cmd: Command, git_protocol: &str, input: &[u8]) -> Result<Vec<u8>, StatusCode> {
+    let (mut child, writer) = spawn_upload_pack(cmd, git_protocol, input.to_vec())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut output = Vec::new();
+    let result = child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_end(&mut output)
+        .and_then(|_| finish_upload_pack(child, writer));
+    if let Err(error) = result {
+        warn!(%error, "git upload-pack failed");
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    Ok(output)
+}
+
+/// Sends the standard output of `git upload-pack` in chunks to `sender`,
+/// until it exits or the client goes away.
+fn stream_upload_pack(
+    mut child: Child,
+    writer: JoinHandle<io::Result<()>>,
+    sender: &mpsc::Sender<io::Result<Vec<u8>>>,
+) -> io::Result<()> {
+    let mut stdout = child.stdout.take().unwrap();
+    let mut buffer = vec![0; PACK_CHUNK_SIZE];
+    loop {
+        let read = stdout.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+
+        if sender.blocking_send(Ok(buffer[..read].to_vec())).is_err() {
+            // The client went away, so the rest of the pack isn't needed
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(());
+        }
+    }
+
+    finish_upload_pack(child, writer)
+}
+
+/// Encodes a line in the pkt-line format of the git protocol, which prefixes
+/// it with its length including the prefix as four hexadecimal digits.
+fn pkt_line(line: &str) -> Vec<u8> {
+    format!("{:04x}{line}", line.len() + 4).into_bytes()
+}
+
+fn git_response(content_type: &str, body: impl IntoResponse) -> Response {
+    let headers = [
+        (header::CONTENT_TYPE, content_type.to_string()),
+        (header::CACHE_CONTROL, "no-cache".to_string()),
+    ];
+    (headers, body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pkt_line_is_prefixed_with_length() {
+        assert_eq!(
+            pkt_line("# service=git-upload-pack\n"),
+            b"001e# service=git-upload-pack\n"
+        );
+    }
+}
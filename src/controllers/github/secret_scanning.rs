@@ -1,6 +1,6 @@
 use crate::app::AppState;
 use crate::controllers::frontend_prelude::*;
-use crate::models::{ApiToken, User};
+use crate::models::{ApiToken, NotificationKind, NotificationPreferences, User};
 use crate::schema::api_tokens;
 use crate::util::token::SecureToken;
 use anyhow::{anyhow, Context};
@@ -204,6 +204,10 @@ fn send_notification_email(
     conn: &mut PgConnection,
 ) -> anyhow::Result<()> {
     let user = User::find(conn, token.user_id).context("Failed to find user")?;
+    if !NotificationPreferences::allows(conn, user.id, NotificationKind::SecurityAlerts)? {
+        return Ok(());
+    }
+
     let Some(email) = user.email(conn)? else {
         return Err(anyhow!("No address found"));
     };
@@ -1,8 +1,12 @@
 use crate::controllers::cargo_prelude::{AppResult, Response};
+use crate::util::errors::bad_request;
 use axum::response::IntoResponse;
 use axum::Json;
+use indexmap::IndexMap;
+use std::str::FromStr;
 
 pub(crate) mod pagination;
+pub mod second_factor;
 
 pub(crate) use self::pagination::Paginate;
 
@@ -10,3 +14,18 @@ pub fn ok_true() -> AppResult<Response> {
     let json = json!({ "ok": true });
     Ok(Json(json).into_response())
 }
+
+/// Parses the query parameter `name`, if it is present.
+pub(crate) fn parse_integer_param<T: FromStr>(
+    params: &IndexMap<String, String>,
+    name: &str,
+) -> AppResult<Option<T>> {
+    params
+        .get(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| bad_request(&format_args!("invalid {name} `{value}`")))
+        })
+        .transpose()
+}
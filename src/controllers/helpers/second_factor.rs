@@ -0,0 +1,97 @@
+//! Step-up checks that confirm sensitive actions with a second factor.
+
+use crate::app::AppState;
+use crate::auth::Authentication;
+use crate::controllers::krate::publish::OTP_HEADER;
+use crate::controllers::user::webauthn::verify_assertion;
+use crate::controllers::util::RequestPartsExt;
+use crate::models::{PersistentSession, TotpCredential, WebauthnCredential};
+use crate::util::errors::{cargo_err, AppResult, SudoModeRequired};
+use diesel::prelude::*;
+use webauthn_rs::prelude::PublicKeyCredential;
+
+/// The header that contains the JSON encoded response of a security key to
+/// the challenge of `POST /me/webauthn/assertions`.
+pub const WEBAUTHN_HEADER: &str = "X-Crates-Io-Webauthn";
+
+/// Checks that a sensitive action, like changing the owners of a crate or
+/// deleting it, is confirmed with one of the second factors of the user, so
+/// that a stolen session or API token alone is not sufficient.
+///
+/// A security key response in the `X-Crates-Io-Webauthn` header, or a one-time
+/// password or recovery code in the `X-Crates-Io-Otp` header are accepted.
+/// Users without a second factor are not checked.
+pub(crate) fn ensure_second_factor<T: RequestPartsExt>(
+    app: &AppState,
+    conn: &mut PgConnection,
+    req: &T,
+    user_id: i32,
+) -> AppResult<()> {
+    let headers = req.headers();
+
+    if let Some(assertion) = headers.get(WEBAUTHN_HEADER) {
+        let assertion: PublicKeyCredential = serde_json::from_slice(assertion.as_bytes())
+            .map_err(|_| cargo_err(&format_args!("invalid `{WEBAUTHN_HEADER}` header")))?;
+        return verify_assertion(app, conn, user_id, &assertion);
+    }
+
+    let totp = TotpCredential::find(conn, user_id)?.filter(|credential| credential.enabled);
+    let code = headers
+        .get(OTP_HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    match (totp, code) {
+        (Some(credential), Some(code)) => {
+            if !credential.verify_or_redeem(conn, code)? {
+                return Err(cargo_err("invalid or already used one-time password"));
+            }
+            Ok(())
+        }
+        (Some(_), None) => Err(cargo_err(&format_args!(
+            "this action must be confirmed with a one-time password, \
+             please pass it in the `{OTP_HEADER}` header"
+        ))),
+        (None, _) if WebauthnCredential::exists_for(conn, user_id)? => {
+            Err(cargo_err(&format_args!(
+                "this action must be confirmed with a security key, \
+                 please pass its response in the `{WEBAUTHN_HEADER}` header"
+            )))
+        }
+        (None, _) => Ok(()),
+    }
+}
+
+/// Returns whether the user has set up a second factor.
+pub(crate) fn has_second_factor(conn: &mut PgConnection, user_id: i32) -> QueryResult<bool> {
+    Ok(TotpCredential::is_enabled_for(conn, user_id)?
+        || WebauthnCredential::exists_for(conn, user_id)?)
+}
+
+/// Checks that a sensitive action, like creating an API token or changing an
+/// email address, is taken by a session in sudo mode, like on GitHub.
+///
+/// Sessions are in sudo mode for a while after the login, and after the user
+/// confirmed their identity again with `PUT /api/private/session/sudo`.
+/// Requests that are authenticated with an API token can't enter sudo mode,
+/// and have to confirm the action with a second factor instead. Users without
+/// a second factor can only take these actions with a session.
+pub(crate) fn ensure_sudo_mode<T: RequestPartsExt>(
+    app: &AppState,
+    conn: &mut PgConnection,
+    req: &T,
+    auth: &Authentication,
+) -> AppResult<()> {
+    let Some(session_id) = auth.session_id() else {
+        if !has_second_factor(conn, auth.user_id())? {
+            return Err(Box::new(SudoModeRequired));
+        }
+        return ensure_second_factor(app, conn, req, auth.user_id());
+    };
+
+    let session = PersistentSession::find_active(conn, session_id, auth.user_id())?;
+    if !session.is_in_sudo_mode() {
+        return Err(Box::new(SudoModeRequired));
+    }
+
+    Ok(())
+}
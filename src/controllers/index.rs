@@ -0,0 +1,214 @@
+//! Endpoints that serve the sparse HTTP index straight from the database
+//!
+//! See <https://doc.rust-lang.org/cargo/reference/registry-index.html#sparse-protocol>
+//! for the protocol that cargo uses to fetch these files.
+
+use crate::controllers::frontend_prelude::*;
+
+use crate::config::IndexConfig;
+use crate::controllers::helpers::parse_integer_param;
+use crate::models::{Crate, IndexChange};
+use crate::schema::{crates, index_squashes};
+use crate::sql::lower;
+use crate::util::errors::not_found;
+use crate::util::rfc3339::Timestamp;
+use cargo_registry_index::Repository;
+use chrono::NaiveDateTime;
+use http::HeaderValue;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+
+const DEFAULT_CHANGES_LIMIT: i64 = 100;
+const MAX_CHANGES_LIMIT: i64 = 1000;
+
+/// The longest time that a request for changes is held open when there are
+/// no new changes.
+const MAX_CHANGES_WAIT: Duration = Duration::from_secs(30);
+const CHANGES_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Handles the `GET /index/config.json` route.
+///
+/// The file is generated from the runtime configuration, so that it does not
+/// have to be committed to the index of every deployment. If the registry
+/// requires authentication, cargo first requests this file without a token to
+/// find out that it has to send one.
+pub async fn config_json(state: AppState) -> AppResult<Json<IndexConfig>> {
+    Ok(Json(state.config.index_config.clone()))
+}
+
+/// Handles the `GET /index/*path` route.
+///
+/// The path consists of the prefix directories of the crate name, followed by
+/// the lowercased crate name itself, in the same layout as the git index.
+/// Responses carry an `ETag` derived from their content, so that cargo can
+/// revalidate its cached copies with `If-None-Match`.
+pub async fn index_file(
+    state: AppState,
+    Path(path): Path<String>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let name = path.rsplit('/').next().unwrap_or_default();
+        if name.is_empty() || Repository::relative_index_file_for_url(name) != path {
+            return Err(not_found());
+        }
+
+        let conn = &mut *state.db_read()?;
+
+        let krate: Crate = Crate::all()
+            .filter(lower(crates::name).eq(name))
+            .first(conn)
+            .optional()?
+            .ok_or_else(not_found)?;
+
+        index_file_response(conn, &krate, &req)
+    })
+    .await
+}
+
+/// Handles the `GET /api/v1/crates/:crate_id/index` route.
+///
+/// Responds with the same newline-delimited JSON entries as the index file of
+/// the crate, so that tools can fetch the metadata of all versions at once
+/// without cloning the index.
+pub async fn crate_index(
+    state: AppState,
+    Path(crate_name): Path<String>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *state.db_read()?;
+
+        let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
+
+        index_file_response(conn, &krate, &req)
+    })
+    .await
+}
+
+/// Renders the index file of the crate from the database. Responds with
+/// `304 Not Modified` instead if the client already has the current content.
+fn index_file_response(conn: &mut PgConnection, krate: &Crate, req: &Parts) -> AppResult<Response> {
+    let body = krate.index_file(conn)?.ok_or_else(not_found)?;
+
+    let etag = format!("\"{}\"", hex::encode(Sha256::digest(&body)));
+    let etag = HeaderValue::from_str(&etag).map_err(http::Error::from)?;
+    if matches_etag(req, &etag) {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+
+    let headers = [
+        (header::CONTENT_TYPE, HeaderValue::from_static("text/plain")),
+        (header::ETAG, etag),
+    ];
+    Ok((headers, body).into_response())
+}
+
+/// Handles the `GET /api/v1/index/squashes` route.
+///
+/// Returns the changelog of squashes of the git index, newest first. Mirrors
+/// poll this to find out when the history of the index was rewritten and on
+/// which branch the previous history was archived.
+pub async fn squashes(state: AppState) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *state.db_read()?;
+
+        let squashes: Vec<(String, String, NaiveDateTime)> = index_squashes::table
+            .select((
+                index_squashes::previous_head,
+                index_squashes::snapshot_branch,
+                index_squashes::squashed_at,
+            ))
+            .order(index_squashes::id.desc())
+            .load(conn)?;
+
+        let squashes = squashes
+            .into_iter()
+            .map(|(previous_head, snapshot_branch, squashed_at)| {
+                json!({
+                    "previous_head": previous_head,
+                    "snapshot_branch": snapshot_branch,
+                    "squashed_at": Timestamp(squashed_at),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "squashes": squashes })))
+    })
+    .await
+}
+
+/// Handles the `GET /api/v1/index/changes` route.
+///
+/// Returns the changes to the index with sequence numbers greater than the
+/// `since` query parameter, oldest first. Mirrors pass the `next_since` value
+/// of the response to their next request to follow the changes incrementally.
+///
+/// If there are no new changes, the request is held open for up to `wait`
+/// seconds until there are.
+pub async fn changes(state: AppState, req: Parts) -> AppResult<Json<Value>> {
+    let params = req.query();
+
+    let since = parse_integer_param(&params, "since")?.unwrap_or(0);
+    let limit = parse_integer_param(&params, "limit")?.unwrap_or(DEFAULT_CHANGES_LIMIT);
+    if !(1..=MAX_CHANGES_LIMIT).contains(&limit) {
+        return Err(bad_request(&format_args!(
+            "limit must be between 1 and {MAX_CHANGES_LIMIT}"
+        )));
+    }
+    let wait = parse_integer_param(&params, "wait")?
+        .map(Duration::from_secs)
+        .unwrap_or_default()
+        .min(MAX_CHANGES_WAIT);
+
+    let deadline = Instant::now() + wait;
+    let changes = loop {
+        let state = state.clone();
+        let changes = conduit_compat(move || {
+            let conn = &mut *state.db_read()?;
+            Ok(IndexChange::since(conn, since, limit)?)
+        })
+        .await?;
+
+        if !changes.is_empty() || Instant::now() >= deadline {
+            break changes;
+        }
+        tokio::time::sleep(CHANGES_POLL_INTERVAL).await;
+    };
+
+    let next_since = changes.last().map(|change| change.id).unwrap_or(since);
+    let changes = changes
+        .into_iter()
+        .map(|change| {
+            json!({
+                "seq": change.id,
+                "crate": change.crate_name,
+                "version": change.version,
+                "action": change.action,
+                "created_at": Timestamp(change.created_at),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(json!({
+        "changes": changes,
+        "meta": { "next_since": next_since },
+    })))
+}
+
+/// Checks whether the `If-None-Match` header of the request contains the
+/// given `ETag`, ignoring weak validator prefixes.
+fn matches_etag(req: &Parts, etag: &HeaderValue) -> bool {
+    let Ok(etag) = etag.to_str() else {
+        return false;
+    };
+
+    req.headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .map(|tag| tag.strip_prefix("W/").unwrap_or(tag))
+        .any(|tag| tag == "*" || tag == etag)
+}
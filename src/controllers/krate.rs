@@ -1,6 +1,14 @@
+pub mod delete;
+pub mod deprecation;
 pub mod downloads;
 pub mod follow;
 pub mod metadata;
 pub mod owners;
 pub mod publish;
+pub mod publish_2fa;
+pub mod publish_multipart;
 pub mod search;
+pub mod staged;
+pub mod suggest;
+pub mod upload;
+pub mod webhooks;
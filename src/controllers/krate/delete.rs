@@ -0,0 +1,204 @@
+//! Endpoints for deleting crates and versions shortly after they were published.
+//!
+//! Within a grace period after publishing (see
+//! `config::Server::deletion_grace_period_hours`), the owners of a crate can
+//! delete a version or the whole crate themselves, as long as no other crate
+//! depends on it. This covers accidental publishes; after the grace period,
+//! versions can only be yanked.
+//!
+//! Users with a second factor have to confirm deletions with it, see
+//! `helpers::second_factor`.
+//!
+//! Deleting requires a token with the `delete` endpoint scope, a `yank` scope
+//! is not enough. The crate files, rendered readmes and index entries are
+//! removed by background jobs once the deletion is committed. Every deletion
+//! is recorded in the `crate_deletions` table.
+
+use chrono::{Duration, NaiveDateTime, Utc};
+
+use crate::auth::AuthCheck;
+use crate::controllers::cargo_prelude::*;
+use crate::controllers::helpers::second_factor::ensure_second_factor;
+use crate::models::token::EndpointScope;
+use crate::models::{Crate, CrateFile, CrateVersions, NewCrateDeletion, Rights, User, Version};
+use crate::schema::{crates, dependencies, versions};
+use crate::worker;
+
+/// Handles the `DELETE /crates/:crate_id` route.
+pub async fn delete_crate(
+    app: AppState,
+    Path(crate_name): Path<String>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::default()
+            .with_endpoint_scope(EndpointScope::Delete)
+            .for_crate(&crate_name)
+            .check(&req, conn)?;
+
+        let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
+        let user = auth.user();
+        let owners = krate.owners(conn)?;
+        if user.rights(&app, conn, &owners)? < Rights::Full {
+            return Err(cargo_err("must be an owner to delete a crate"));
+        }
+
+        ensure_second_factor(&app, conn, &req, user.id)?;
+
+        ensure_within_grace_period(&app, krate.created_at)?;
+
+        if !reverse_dependency_reqs(conn, &krate)?.is_empty() {
+            return Err(cargo_err(&format_args!(
+                "cannot delete `{}` because other crates depend on it",
+                krate.name
+            )));
+        }
+
+        purge_crate(&app, conn, &krate, user)?;
+
+        ok_true()
+    })
+    .await
+}
+
+/// Handles the `DELETE /crates/:crate_id/:version` route.
+pub async fn delete_version(
+    app: AppState,
+    Path((crate_name, version)): Path<(String, String)>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let semver = semver::Version::parse(&version)
+            .map_err(|_| cargo_err(&format_args!("invalid semver: {version}")))?;
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::default()
+            .with_endpoint_scope(EndpointScope::Delete)
+            .for_crate(&crate_name)
+            .check(&req, conn)?;
+
+        let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
+        let version = krate.find_version(conn, &version)?;
+        let user = auth.user();
+        let owners = krate.owners(conn)?;
+        if user.rights(&app, conn, &owners)? < Rights::Publish {
+            return Err(cargo_err("must already be an owner to delete a version"));
+        }
+
+        ensure_second_factor(&app, conn, &req, user.id)?;
+
+        ensure_within_grace_period(&app, version.created_at)?;
+
+        let num_versions: i64 = krate.all_versions().count().get_result(conn)?;
+        if num_versions == 1 {
+            return Err(cargo_err(&format_args!(
+                "`{}@{}` is the only version of the crate, delete the crate instead",
+                krate.name, version.num
+            )));
+        }
+
+        let is_required = reverse_dependency_reqs(conn, &krate)?
+            .iter()
+            .filter_map(|req| semver::VersionReq::parse(req).ok())
+            .any(|req| req.matches(&semver));
+        if is_required {
+            return Err(cargo_err(&format_args!(
+                "cannot delete `{}@{}` because other crates depend on it",
+                krate.name, version.num
+            )));
+        }
+
+        let object_keys = CrateFile::object_keys(conn, &[version.id])?;
+
+        conn.transaction(|conn| {
+            diesel::delete(&version).execute(conn)?;
+
+            record_deletion(conn, &krate, Some(&version), user)?;
+
+            let version_nums = vec![version.num.clone()];
+            worker::delete_versions(krate.name.clone(), version_nums.clone()).enqueue(conn)?;
+            worker::delete_crate_files(krate.name.clone(), version_nums, object_keys)
+                .enqueue(conn)?;
+
+            ok_true()
+        })
+    })
+    .await
+}
+
+/// Deletes the crate with all its versions, records the deletion and enqueues
+/// the removal of its files from storage and the index.
+///
+/// This is shared with `DELETE /api/private/admin/crates/:crate_id`, which
+/// skips the checks for ownership, reverse dependencies and the grace period.
+pub(crate) fn purge_crate(
+    app: &AppState,
+    conn: &mut PgConnection,
+    krate: &Crate,
+    user: &User,
+) -> AppResult<()> {
+    let (version_ids, version_nums): (Vec<i32>, Vec<String>) = versions::table
+        .filter(versions::crate_id.eq(krate.id))
+        .select((versions::id, versions::num))
+        .load::<(i32, String)>(conn)?
+        .into_iter()
+        .unzip();
+    let object_keys = CrateFile::object_keys(conn, &version_ids)?;
+
+    conn.transaction(|conn| {
+        diesel::delete(crates::table.find(krate.id)).execute(conn)?;
+
+        record_deletion(conn, krate, None, user)?;
+
+        worker::delete_versions(krate.name.clone(), version_nums.clone()).enqueue(conn)?;
+        worker::delete_crate_files(krate.name.clone(), version_nums, object_keys).enqueue(conn)?;
+
+        if app.config.search_backend.needs_sync() {
+            worker::sync_search_index(krate.name.clone()).enqueue(conn)?;
+        }
+
+        Ok(())
+    })
+}
+
+fn ensure_within_grace_period(app: &AppState, published_at: NaiveDateTime) -> AppResult<()> {
+    let hours = app.config.deletion_grace_period_hours;
+    let deadline = published_at + Duration::hours(hours as i64);
+    if Utc::now().naive_utc() > deadline {
+        return Err(cargo_err(&format_args!(
+            "crates and versions can only be deleted within {hours} hours after \
+             publishing, consider yanking instead"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the version requirements of all dependencies of other crates on
+/// the given crate.
+fn reverse_dependency_reqs(conn: &mut PgConnection, krate: &Crate) -> QueryResult<Vec<String>> {
+    dependencies::table
+        .inner_join(versions::table)
+        .filter(dependencies::crate_id.eq(krate.id))
+        .filter(versions::crate_id.ne(krate.id))
+        .select(dependencies::req)
+        .load(conn)
+}
+
+fn record_deletion(
+    conn: &mut PgConnection,
+    krate: &Crate,
+    version: Option<&Version>,
+    user: &User,
+) -> QueryResult<()> {
+    NewCrateDeletion {
+        crate_name: &krate.name,
+        version: version.map(|version| &*version.num),
+        deleted_by: user.id,
+        published_at: version.map_or(krate.created_at, |version| version.created_at),
+    }
+    .create(conn)?;
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! Endpoint for deprecating a whole crate.
+//!
+//! Deprecation is a softer signal than yanking: the crate stays available
+//! for new dependencies, but the API and the index tell users that it is no
+//! longer maintained, why, and optionally which crate to migrate to.
+
+use crate::auth::AuthCheck;
+use crate::controllers::cargo_prelude::*;
+use crate::models::token::EndpointScope;
+use crate::models::{Crate, Rights};
+use crate::schema::crates;
+use crate::worker;
+
+/// The maximum length of the message given when deprecating a crate or a
+/// version.
+const MAX_MESSAGE_LENGTH: usize = 256;
+
+#[derive(Deserialize)]
+struct DeprecationRequest {
+    deprecated: bool,
+    message: Option<String>,
+    successor: Option<String>,
+}
+
+/// Handles the `PUT /crates/:crate_id/deprecation` route.
+///
+/// The request body contains the new `deprecated` flag and, if the crate is
+/// deprecated, an optional `message` and the name of an optional `successor`
+/// crate. Undeprecating a crate removes its message and successor.
+pub async fn update(
+    app: AppState,
+    Path(crate_name): Path<String>,
+    req: BytesRequest,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let request: DeprecationRequest = serde_json::from_slice(req.body())
+            .map_err(|e| cargo_err(&format!("invalid deprecation request: {e}")))?;
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::default()
+            .with_endpoint_scope(EndpointScope::Yank)
+            .for_crate(&crate_name)
+            .check(&req, conn)?;
+
+        let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
+        let owners = krate.owners(conn)?;
+        if auth.user().rights(&app, conn, &owners)? < Rights::Full {
+            return Err(cargo_err("must already be an owner to deprecate a crate"));
+        }
+
+        let (message, successor) = if request.deprecated {
+            let message = validate_message(request.message)?;
+            let successor = request.successor.filter(|s| !s.is_empty());
+            let successor = match successor {
+                Some(successor) => Some(find_successor(conn, &krate, &successor)?),
+                None => None,
+            };
+            (message, successor)
+        } else {
+            (None, None)
+        };
+
+        diesel::update(&krate)
+            .set((
+                crates::deprecated.eq(request.deprecated),
+                crates::deprecation_message.eq(&message),
+                crates::deprecation_successor.eq(&successor),
+            ))
+            .execute(conn)?;
+
+        worker::sync_deprecated(krate.name).enqueue(conn)?;
+
+        ok_true()
+    })
+    .await
+}
+
+/// Trims the deprecation message and checks its length.
+pub(crate) fn validate_message(message: Option<String>) -> AppResult<Option<String>> {
+    let message = message.map(|message| message.trim().to_string());
+    let message = message.filter(|message| !message.is_empty());
+    if let Some(message) = &message {
+        if message.chars().count() > MAX_MESSAGE_LENGTH {
+            return Err(cargo_err(&format_args!(
+                "the deprecation message must not be longer than {MAX_MESSAGE_LENGTH} characters"
+            )));
+        }
+    }
+
+    Ok(message)
+}
+
+/// Looks up the successor crate and returns its canonical name.
+fn find_successor(conn: &mut PgConnection, krate: &Crate, name: &str) -> AppResult<String> {
+    let successor: Option<Crate> = Crate::by_name(name).first(conn).optional()?;
+    let Some(successor) = successor else {
+        return Err(cargo_err(&format_args!(
+            "the successor crate `{name}` does not exist"
+        )));
+    };
+
+    if successor.id == krate.id {
+        return Err(cargo_err("a crate can not be its own successor"));
+    }
+
+    Ok(successor.name)
+}
@@ -4,13 +4,16 @@
 //! download counts are located in `version::downloads`.
 
 use std::cmp;
+use std::collections::HashMap;
 
 use crate::controllers::frontend_prelude::*;
 
+use crate::controllers::version::downloads::parse_date;
 use crate::models::{Crate, CrateVersions, Version, VersionDownload};
-use crate::schema::version_downloads;
-use crate::sql::to_char;
-use crate::views::EncodableVersionDownload;
+use crate::schema::{crates, recent_crate_downloads, version_cargo_downloads, version_downloads};
+use crate::sql::{canon_crate_name, to_char};
+use crate::views::{EncodableCrateDownloads, EncodableVersionDownload};
+use chrono::{Duration, Utc};
 
 /// Handles the `GET /crates/:crate_id/downloads` route.
 pub async fn downloads(state: AppState, Path(crate_name): Path<String>) -> AppResult<Json<Value>> {
@@ -60,3 +63,151 @@ pub async fn downloads(state: AppState, Path(crate_name): Path<String>) -> AppRe
     })
     .await
 }
+
+/// The default range of the cargo version statistics, in days.
+const DEFAULT_CARGO_VERSIONS_RANGE_DAYS: i64 = 90;
+
+/// Handles the `GET /crates/:crate_id/downloads/cargo_versions` route.
+///
+/// Returns the downloads of each version of the crate between the `start`
+/// and `end` dates (inclusive) by the minor version of cargo that downloaded
+/// them, which helps maintainers to choose a minimum supported Rust version.
+/// Only downloads that were counted from the CDN logs are included.
+pub async fn cargo_versions(
+    state: AppState,
+    Path(crate_name): Path<String>,
+    req: Parts,
+) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let params = req.query();
+        let end = parse_date(&params, "end")?.unwrap_or_else(|| Utc::now().date_naive());
+        let start = parse_date(&params, "start")?
+            .unwrap_or_else(|| end - Duration::days(DEFAULT_CARGO_VERSIONS_RANGE_DAYS - 1));
+        if start > end {
+            return Err(bad_request("the start date must not be after the end date"));
+        }
+
+        let conn = &mut *state.db_read()?;
+        let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
+
+        let mut versions: Vec<Version> = krate.all_versions().load(conn)?;
+        versions
+            .sort_by_cached_key(|version| cmp::Reverse(semver::Version::parse(&version.num).ok()));
+        let positions = versions
+            .iter()
+            .enumerate()
+            .map(|(position, version)| (version.id, position))
+            .collect::<HashMap<_, _>>();
+        let version_ids = versions
+            .iter()
+            .map(|version| version.id)
+            .collect::<Vec<_>>();
+
+        let mut downloads: Vec<(i32, String, Option<i64>)> = version_cargo_downloads::table
+            .filter(version_cargo_downloads::version_id.eq_any(version_ids))
+            .filter(version_cargo_downloads::date.between(start, end))
+            .group_by((
+                version_cargo_downloads::version_id,
+                version_cargo_downloads::cargo_version,
+            ))
+            .select((
+                version_cargo_downloads::version_id,
+                version_cargo_downloads::cargo_version,
+                diesel::dsl::sum(version_cargo_downloads::downloads),
+            ))
+            .load(conn)?;
+
+        // Newest crate versions first, then newest cargo versions first
+        downloads.sort_by_cached_key(|(version_id, cargo_version, _)| {
+            let cargo_version = cargo_version
+                .split('.')
+                .map(|part| part.parse::<u32>().unwrap_or_default())
+                .collect::<Vec<_>>();
+            (positions[version_id], cmp::Reverse(cargo_version))
+        });
+
+        let cargo_versions = downloads
+            .into_iter()
+            .map(|(version_id, cargo_version, downloads)| {
+                json!({
+                    "version": versions[positions[&version_id]].num,
+                    "cargo_version": cargo_version,
+                    "downloads": downloads.unwrap_or_default(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({
+            "cargo_versions": cargo_versions,
+            "meta": {
+                "start": start.to_string(),
+                "end": end.to_string(),
+            },
+        })))
+    })
+    .await
+}
+
+/// At most this many crates can be requested from the bulk downloads endpoint.
+const MAX_BULK_CRATES: usize = 100;
+
+/// Handles the `GET /crate_downloads` route.
+///
+/// Returns the total and recent download counts of the crates given by the
+/// comma-separated `ids` parameter, in the order they were requested. Crates
+/// that don't exist are left out.
+pub async fn bulk_downloads(state: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let params = req.query();
+        let ids = params
+            .get("ids")
+            .ok_or_else(|| bad_request("missing the `ids` parameter"))?;
+
+        // Crate names are compared by their canonical spelling
+        let mut names: Vec<String> = Vec::new();
+        for name in ids
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let name = name.to_lowercase().replace('-', "_");
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        if names.len() > MAX_BULK_CRATES {
+            return Err(bad_request(&format_args!(
+                "at most {MAX_BULK_CRATES} crates can be requested at once"
+            )));
+        }
+
+        let conn = &mut *state.db_read()?;
+        let downloads: Vec<(String, i32, Option<i64>)> = crates::table
+            .left_join(recent_crate_downloads::table)
+            .filter(canon_crate_name(crates::name).eq_any(names.clone()))
+            .select((
+                crates::name,
+                crates::downloads,
+                recent_crate_downloads::downloads.nullable(),
+            ))
+            .load(conn)?;
+
+        let mut downloads = downloads
+            .into_iter()
+            .map(
+                |(name, downloads, recent_downloads)| EncodableCrateDownloads {
+                    name,
+                    downloads: downloads.into(),
+                    recent_downloads: recent_downloads.unwrap_or_default(),
+                },
+            )
+            .collect::<Vec<_>>();
+        downloads.sort_by_key(|krate| {
+            let name = krate.name.to_lowercase().replace('-', "_");
+            names.iter().position(|requested| *requested == name)
+        });
+
+        Ok(Json(json!({ "crates": downloads })))
+    })
+    .await
+}
@@ -7,12 +7,19 @@
 use std::cmp::Reverse;
 use std::str::FromStr;
 
+use cargo_registry_markdown::RENDERER_VERSION;
+use chrono::NaiveDateTime;
+use diesel::dsl::sql;
+use diesel::sql_types::{Bool, Double};
+use indexmap::IndexMap;
+
 use crate::controllers::frontend_prelude::*;
-use crate::controllers::helpers::pagination::PaginationOptions;
+use crate::controllers::helpers::pagination::{encode_seek, Page, PaginationOptions};
+use crate::controllers::helpers::parse_integer_param;
 
 use crate::models::{
-    Category, Crate, CrateCategory, CrateKeyword, CrateVersions, Keyword, RecentCrateDownloads,
-    TopVersions, User, Version, VersionOwnerAction,
+    Category, Crate, CrateCategory, CrateKeyword, CrateVersions, Dependency, Keyword,
+    RecentCrateDownloads, ReverseDependency, TopVersions, User, Version, VersionOwnerAction,
 };
 use crate::schema::*;
 use crate::views::{
@@ -20,6 +27,7 @@ use crate::views::{
 };
 
 use crate::models::krate::ALL_COLUMNS;
+use crate::worker::download_trends::DOWNLOAD_TREND_WINDOWS;
 
 /// Handles the `GET /summary` route.
 pub async fn summary(state: AppState) -> AppResult<Json<Value>> {
@@ -34,33 +42,6 @@ pub async fn summary(state: AppState) -> AppResult<Json<Value>> {
             .select(metadata::total_downloads)
             .get_result(conn)?;
 
-        fn encode_crates(
-            conn: &mut PgConnection,
-            data: Vec<(Crate, Option<i64>)>,
-        ) -> AppResult<Vec<EncodableCrate>> {
-            let recent_downloads = data.iter().map(|&(_, s)| s).collect::<Vec<_>>();
-
-            let krates = data.into_iter().map(|(c, _)| c).collect::<Vec<_>>();
-
-            let versions: Vec<Version> = krates.versions().load(conn)?;
-            versions
-                .grouped_by(&krates)
-                .into_iter()
-                .map(TopVersions::from_versions)
-                .zip(krates)
-                .zip(recent_downloads)
-                .map(|((top_versions, krate), recent_downloads)| {
-                    Ok(EncodableCrate::from_minimal(
-                        krate,
-                        Some(&top_versions),
-                        None,
-                        false,
-                        recent_downloads,
-                    ))
-                })
-                .collect()
-        }
-
         let selection = (ALL_COLUMNS, recent_crate_downloads::downloads.nullable());
 
         let new_crates = crates
@@ -129,6 +110,132 @@ pub async fn summary(state: AppState) -> AppResult<Json<Value>> {
     .await
 }
 
+fn encode_crates(
+    conn: &mut PgConnection,
+    data: Vec<(Crate, Option<i64>)>,
+) -> AppResult<Vec<EncodableCrate>> {
+    let recent_downloads = data.iter().map(|&(_, s)| s).collect::<Vec<_>>();
+
+    let krates = data.into_iter().map(|(c, _)| c).collect::<Vec<_>>();
+
+    let versions: Vec<Version> = krates.versions().load(conn)?;
+    versions
+        .grouped_by(&krates)
+        .into_iter()
+        .map(TopVersions::from_versions)
+        .zip(krates)
+        .zip(recent_downloads)
+        .map(|((top_versions, krate), recent_downloads)| {
+            Ok(EncodableCrate::from_minimal(
+                krate,
+                Some(&top_versions),
+                None,
+                false,
+                recent_downloads,
+            ))
+        })
+        .collect()
+}
+
+const DEFAULT_TRENDING_LIMIT: i64 = 10;
+const MAX_TRENDING_LIMIT: i64 = 100;
+
+/// Crates with fewer downloads in the previous window are left out by
+/// default, since small numbers of downloads grow by large factors easily.
+const DEFAULT_TRENDING_MIN_BASELINE: i64 = 1000;
+
+/// Handles the `GET /summary/trending` route.
+///
+/// Returns the crates whose downloads grew the most in the most recent window
+/// of `window` days compared to the window before it. Crates with fewer than
+/// `min_baseline` downloads in the previous window are left out. The trends
+/// are computed periodically by the `update_download_trends` job.
+pub async fn trending(state: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let params = req.query();
+
+        let window = parse_integer_param(&params, "window")?.unwrap_or(DOWNLOAD_TREND_WINDOWS[0]);
+        if !DOWNLOAD_TREND_WINDOWS.contains(&window) {
+            let windows = DOWNLOAD_TREND_WINDOWS.map(|window| window.to_string());
+            return Err(bad_request(&format_args!(
+                "invalid window `{window}`, expected one of {}",
+                windows.join(", ")
+            )));
+        }
+
+        let min_baseline = parse_integer_param(&params, "min_baseline")?
+            .unwrap_or(DEFAULT_TRENDING_MIN_BASELINE)
+            // Growth can't be computed for crates without previous downloads
+            .max(1);
+
+        let limit = parse_integer_param(&params, "limit")?.unwrap_or(DEFAULT_TRENDING_LIMIT);
+        if !(1..=MAX_TRENDING_LIMIT).contains(&limit) {
+            return Err(bad_request(&format_args!(
+                "limit must be between 1 and {MAX_TRENDING_LIMIT}"
+            )));
+        }
+
+        let conn = &mut *state.db_read()?;
+
+        let mut query = crate_download_trends::table
+            .inner_join(crates::table.left_join(recent_crate_downloads::table))
+            .filter(crate_download_trends::window_days.eq(window))
+            .filter(crate_download_trends::previous_downloads.ge(min_baseline))
+            .into_boxed();
+        if !state.config.excluded_crate_names.is_empty() {
+            query = query.filter(crates::name.ne_all(&state.config.excluded_crate_names));
+        }
+
+        let trends: Vec<(Crate, Option<i64>, i64, i64)> = query
+            .order((
+                sql::<Double>(
+                    "crate_download_trends.downloads::float8 / crate_download_trends.previous_downloads",
+                )
+                .desc(),
+                crates::name.asc(),
+            ))
+            .select((
+                ALL_COLUMNS,
+                recent_crate_downloads::downloads.nullable(),
+                crate_download_trends::downloads,
+                crate_download_trends::previous_downloads,
+            ))
+            .limit(limit)
+            .load(conn)?;
+
+        let counts = trends
+            .iter()
+            .map(|&(_, _, downloads, previous_downloads)| (downloads, previous_downloads))
+            .collect::<Vec<_>>();
+        let crates = trends
+            .into_iter()
+            .map(|(krate, recent_downloads, _, _)| (krate, recent_downloads))
+            .collect();
+
+        let trending = encode_crates(conn, crates)?
+            .into_iter()
+            .zip(counts)
+            .map(|(krate, (downloads, previous_downloads))| {
+                json!({
+                    "crate": krate,
+                    "downloads": downloads,
+                    "previous_downloads": previous_downloads,
+                    "growth": (downloads - previous_downloads) as f64 / previous_downloads as f64,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({
+            "trending": trending,
+            "meta": {
+                "window": window,
+                "min_baseline": min_baseline,
+            },
+        })))
+    })
+    .await
+}
+
 /// Handles the `GET /crates/:crate_id` route.
 pub async fn show(app: AppState, Path(name): Path<String>, req: Parts) -> AppResult<Json<Value>> {
     conduit_compat(move || {
@@ -306,17 +413,37 @@ impl FromStr for ShowIncludeMode {
 }
 
 /// Handles the `GET /crates/:crate_id/:version/readme` route.
+///
+/// Redirects to the readme of the version as it was last rendered. Versions
+/// whose readme was not rendered yet are redirected to where the current
+/// renderer will store it.
 pub async fn readme(
     app: AppState,
     Path((crate_name, version)): Path<(String, String)>,
     req: Parts,
-) -> Response {
-    let redirect_url = app.config.uploader().readme_location(&crate_name, &version);
-    if req.wants_json() {
-        Json(json!({ "url": redirect_url })).into_response()
-    } else {
-        redirect(redirect_url)
-    }
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read()?;
+        let renderer_version: Option<i32> = readme_renderings::table
+            .inner_join(versions::table.inner_join(crates::table))
+            .filter(Crate::with_name(&crate_name))
+            .filter(versions::num.eq(&version))
+            .select(readme_renderings::renderer_version)
+            .first(conn)
+            .optional()?;
+        let renderer_version = renderer_version.unwrap_or(RENDERER_VERSION);
+
+        let redirect_url =
+            app.config
+                .uploader()
+                .readme_location(&crate_name, &version, renderer_version);
+        if req.wants_json() {
+            Ok(Json(json!({ "url": redirect_url })).into_response())
+        } else {
+            Ok(redirect(redirect_url))
+        }
+    })
+    .await
 }
 
 /// Handles the `GET /crates/:crate_id/versions` route.
@@ -351,19 +478,146 @@ pub async fn versions(state: AppState, Path(crate_name): Path<String>) -> AppRes
     .await
 }
 
+/// Matches only the newest non-yanked version of each crate. Prereleases are
+/// only the newest version of crates without any other versions.
+const NEWEST_VERSION_FILTER: &str = "versions.id = (\
+    SELECT newest.id FROM versions newest \
+    WHERE newest.crate_id = versions.crate_id AND NOT newest.yanked \
+    ORDER BY to_semver_no_prerelease(newest.num) DESC NULLS LAST, newest.id DESC \
+    LIMIT 1)";
+
+/// Matches only one dependency per version, since a version can depend on
+/// the same crate several times, e.g. for different targets.
+const FIRST_DEPENDENCY_FILTER: &str = "dependencies.id = (\
+    SELECT MIN(same_crate.id) FROM dependencies same_crate \
+    WHERE same_crate.version_id = dependencies.version_id \
+    AND same_crate.crate_id = dependencies.crate_id)";
+
 /// Handles the `GET /crates/:crate_id/reverse_dependencies` route.
+///
+/// Lists the crates whose newest version depends on the crate, sorted by the
+/// `sort` parameter. Pages after the first are selected by the `seek`
+/// parameter from `meta.next_page`, while offset-based pagination is still
+/// supported through the `page` parameter for existing clients.
 pub async fn reverse_dependencies(
     app: AppState,
     Path(name): Path<String>,
     req: Parts,
 ) -> AppResult<Json<Value>> {
     conduit_compat(move || {
-        let pagination_options = PaginationOptions::builder().gather(&req)?;
+        let pagination = PaginationOptions::builder()
+            .enable_seek(true)
+            .gather(&req)?;
+        let params = req.query();
+        let sort = ReverseDependencySort::from_param(params.get("sort").map(|s| &**s));
+
         let conn = &mut *app.db_read()?;
         let krate: Crate = Crate::by_name(&name).first(conn)?;
-        let (rev_deps, total) = krate.reverse_dependencies(conn, pagination_options)?;
-        let rev_deps: Vec<_> = rev_deps
+
+        let mut query = dependencies::table
+            .inner_join(versions::table)
+            // Dependencies also reference the crate they depend on
+            .inner_join(crates::table.on(crates::id.eq(versions::crate_id)))
+            .filter(dependencies::crate_id.eq(krate.id))
+            .filter(sql::<Bool>(NEWEST_VERSION_FILTER))
+            .filter(sql::<Bool>(FIRST_DEPENDENCY_FILTER))
+            .select((
+                dependencies::all_columns,
+                crates::downloads,
+                crates::name,
+                crates::updated_at,
+            ))
+            .into_boxed();
+
+        // The name is used as a tie-breaker so that the order is stable for
+        // seek-based pagination
+        query = match sort {
+            ReverseDependencySort::Downloads => {
+                query.order((crates::downloads.desc(), crates::name.asc()))
+            }
+            ReverseDependencySort::RecentUpdates => {
+                query.order((crates::updated_at.desc(), crates::name.asc()))
+            }
+            ReverseDependencySort::Name => query.order(crates::name.asc()),
+        };
+
+        let total = match &pagination.page {
+            // Counting the reverse dependencies again would be slow, so the
+            // total is passed along from the first page
+            Page::Seek(seek) => {
+                let seek = seek.decode::<ReverseDependencySeekKey>()?;
+                let total = seek.total;
+                query = match (sort, seek.value) {
+                    (
+                        ReverseDependencySort::Downloads,
+                        ReverseDependencySortValue::Downloads(downloads),
+                    ) => query.filter(
+                        crates::downloads.lt(downloads).or(crates::downloads
+                            .eq(downloads)
+                            .and(crates::name.gt(seek.name))),
+                    ),
+                    (
+                        ReverseDependencySort::RecentUpdates,
+                        ReverseDependencySortValue::RecentUpdates(updated_at),
+                    ) => query.filter(
+                        crates::updated_at.lt(updated_at).or(crates::updated_at
+                            .eq(updated_at)
+                            .and(crates::name.gt(seek.name))),
+                    ),
+                    (ReverseDependencySort::Name, ReverseDependencySortValue::Name) => {
+                        query.filter(crates::name.gt(seek.name))
+                    }
+                    _ => {
+                        return Err(bad_request(
+                            "the seek parameter does not match the sort order",
+                        ))
+                    }
+                };
+                total
+            }
+            Page::Numeric(_) | Page::Unspecified => reverse_dependencies_total(conn, krate.id)?,
+        };
+
+        if let Some(offset) = pagination.offset() {
+            query = query.offset(offset);
+        }
+
+        let rows: Vec<(Dependency, i32, String, NaiveDateTime)> =
+            query.limit(pagination.per_page).load(conn)?;
+
+        // Clients that select pages by number compute the next page themselves
+        let next_page = match rows.last() {
+            Some((_, downloads, name, updated_at))
+                if pagination.offset().is_none() && rows.len() as i64 == pagination.per_page =>
+            {
+                let value = match sort {
+                    ReverseDependencySort::Downloads => {
+                        ReverseDependencySortValue::Downloads(*downloads)
+                    }
+                    ReverseDependencySort::RecentUpdates => {
+                        ReverseDependencySortValue::RecentUpdates(*updated_at)
+                    }
+                    ReverseDependencySort::Name => ReverseDependencySortValue::Name,
+                };
+                let seek = ReverseDependencySeekKey {
+                    total,
+                    value,
+                    name: name.clone(),
+                };
+                let mut params = IndexMap::new();
+                params.insert("seek".into(), encode_seek(seek)?);
+                Some(req.query_with_params(params))
+            }
+            _ => None,
+        };
+
+        let rev_deps: Vec<_> = rows
             .into_iter()
+            .map(|(dependency, crate_downloads, name, _)| ReverseDependency {
+                dependency,
+                crate_downloads,
+                name,
+            })
             .map(|dep| EncodableDependency::from_reverse_dep(dep, &krate.name))
             .collect();
 
@@ -395,8 +649,75 @@ pub async fn reverse_dependencies(
         Ok(Json(json!({
             "dependencies": rev_deps,
             "versions": versions,
-            "meta": { "total": total },
+            "meta": {
+                "total": total,
+                "next_page": next_page,
+            },
         })))
     })
     .await
 }
+
+/// Returns the number of crates whose newest version depends on the crate.
+///
+/// The count is taken from the `reverse_dependency_counts` table if it was
+/// refreshed for the crate, and counted otherwise.
+fn reverse_dependencies_total(conn: &mut PgConnection, crate_id: i32) -> QueryResult<i64> {
+    let cached: Option<i32> = reverse_dependency_counts::table
+        .find(crate_id)
+        .select(reverse_dependency_counts::dependents)
+        .first(conn)
+        .optional()?;
+
+    match cached {
+        Some(dependents) => Ok(dependents.into()),
+        None => dependencies::table
+            .inner_join(versions::table)
+            .filter(dependencies::crate_id.eq(crate_id))
+            .filter(sql::<Bool>(NEWEST_VERSION_FILTER))
+            .filter(sql::<Bool>(FIRST_DEPENDENCY_FILTER))
+            .count()
+            .get_result(conn),
+    }
+}
+
+/// The orders of the reverse dependencies selected by the `sort` query
+/// parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReverseDependencySort {
+    /// The most downloaded crates first.
+    Downloads,
+    /// The most recently updated crates first.
+    RecentUpdates,
+    /// Alphabetically by crate name.
+    Name,
+}
+
+impl ReverseDependencySort {
+    fn from_param(sort: Option<&str>) -> Self {
+        match sort {
+            Some("recent-updates") => Self::RecentUpdates,
+            Some("name") => Self::Name,
+            _ => Self::Downloads,
+        }
+    }
+}
+
+/// The position of the last crate of a page, which is passed as the `seek`
+/// parameter of the next page.
+#[derive(Debug, Serialize, Deserialize)]
+struct ReverseDependencySeekKey {
+    /// The total number of reverse dependencies, as counted for the first page.
+    total: i64,
+    value: ReverseDependencySortValue,
+    name: String,
+}
+
+/// The value a crate is sorted by before its name.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ReverseDependencySortValue {
+    Downloads(i32),
+    RecentUpdates(NaiveDateTime),
+    Name,
+}
@@ -1,10 +1,15 @@
 //! All routes related to managing owners of a crate
 
 use crate::auth::AuthCheck;
+use crate::controllers::helpers::second_factor::{ensure_second_factor, ensure_sudo_mode};
 use crate::controllers::prelude::*;
+use crate::middleware::session::client_info;
 use crate::models::token::EndpointScope;
-use crate::models::{Crate, Owner, Rights, Team, User};
+use crate::models::{
+    Crate, Organization, Owner, Rights, SecurityEvent, SecurityLogEntry, Team, User, WebhookEvent,
+};
 use crate::views::EncodableOwner;
+use crate::worker;
 use axum::body::Bytes;
 use http::Request;
 
@@ -77,7 +82,7 @@ pub async fn remove_owners(
 /// The format is:
 ///
 /// ```json
-/// {"owners": ["username", "github:org:team", ...]}
+/// {"owners": ["username", "github:org:team", "org:name", ...]}
 /// ```
 fn parse_owners_request(req: &Request<Bytes>) -> AppResult<Vec<String>> {
     #[derive(Deserialize)]
@@ -109,12 +114,13 @@ fn modify_owners(
         .check(req, conn)?;
 
     let user = auth.user();
+    let (ip_address, user_agent) = client_info(req.headers());
 
     conn.transaction(|conn| {
         let krate: Crate = Crate::by_name(crate_name).first(conn)?;
         let owners = krate.owners(conn)?;
 
-        match user.rights(app, &owners)? {
+        match user.rights(app, conn, &owners)? {
             Rights::Full => {}
             // Yes!
             Rights::Publish => {
@@ -127,6 +133,13 @@ fn modify_owners(
             }
         }
 
+        if add {
+            ensure_second_factor(app, conn, req, user.id)?;
+        } else {
+            // Removed owners lose access to the crate right away
+            ensure_sudo_mode(app, conn, req, &auth)?;
+        }
+
         let comma_sep_msg = if add {
             let mut msgs = Vec::with_capacity(logins.len());
             for login in &logins {
@@ -137,13 +150,48 @@ fn modify_owners(
                 }
                 let msg = krate.owner_add(app, conn, user, login)?;
                 msgs.push(msg);
+
+                // Users only become owners once they accept their invitation,
+                // while teams are added immediately
+                if login.contains(':') {
+                    let details = format!("crate {}, owner {login}", krate.name);
+                    SecurityLogEntry::record(
+                        conn,
+                        user.id,
+                        SecurityEvent::OwnerAdded,
+                        Some(&details),
+                        ip_address,
+                        user_agent,
+                    )?;
+
+                    let data = json!({ "owner": login });
+                    let event = WebhookEvent::OwnerAdded;
+                    worker::trigger_webhooks(conn, krate.id, &krate.name, event, data)?;
+                }
             }
             msgs.join(",")
         } else {
             for login in &logins {
                 krate.owner_remove(app, conn, user, login)?;
+
+                let details = format!("crate {}, owner {login}", krate.name);
+                SecurityLogEntry::record(
+                    conn,
+                    user.id,
+                    SecurityEvent::OwnerRemoved,
+                    Some(&details),
+                    ip_address,
+                    user_agent,
+                )?;
+
+                let data = json!({ "owner": login });
+                let event = WebhookEvent::OwnerRemoved;
+                worker::trigger_webhooks(conn, krate.id, &krate.name, event, data)?;
             }
-            if User::owning(&krate, conn)?.is_empty() {
+            // Admins of an owning organization can still manage the crate
+            if User::owning(&krate, conn)?.is_empty()
+                && Organization::owning(&krate, conn)?.is_empty()
+            {
                 return Err(cargo_err(
                     "cannot remove all individual owners of a crate. \
                      Team member don't have permission to modify owners, so \
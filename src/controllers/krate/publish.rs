@@ -6,22 +6,28 @@ use flate2::read::GzDecoder;
 use hex::ToHex;
 use hyper::body::Buf;
 use sha2::{Digest, Sha256};
-use std::collections::BTreeMap;
 use std::io::Read;
-use std::path::Path;
+use std::path::{Component, Path};
 
 use crate::controllers::cargo_prelude::*;
 use crate::controllers::util::RequestPartsExt;
 use crate::models::{
-    insert_version_owner_action, Category, Crate, DependencyKind, Keyword, NewCrate, NewVersion,
-    Rights, VersionAction,
+    insert_version_owner_action, Category, Crate, CrateFile, CrateVersions, DependencyKind,
+    Keyword, NewCrate, NewVersion, NewVersionSignature, Organization, Owner, ReservedCratePrefix,
+    Rights, SignatureKind, StagedPublish, TotpCredential, VersionAction, WebhookEvent,
 };
 use crate::worker;
 
+use crate::config::MetadataLimits;
+use crate::controllers::krate::publish_2fa::publish_requires_2fa;
 use crate::middleware::log_request::RequestLogExt;
 use crate::models::token::EndpointScope;
+use crate::publish_rate_limit::LimitedAction;
 use crate::schema::*;
-use crate::util::errors::{cargo_err, AppResult};
+use crate::util::errors::{
+    cargo_err, conflict, AppResult, MetadataLimit, MetadataLimitExceeded, UploadTooLarge,
+};
+use crate::util::sigstore;
 use crate::util::{CargoVcsInfo, LimitErrorReader, Maximums};
 use crate::views::{
     EncodableCrate, EncodableCrateDependency, EncodableCrateUpload, GoodCrate, PublishWarnings,
@@ -33,6 +39,15 @@ pub const MISSING_RIGHTS_ERROR_MESSAGE: &str =
      to accept an invitation to be an owner before \
      publishing.";
 
+/// The maximum size of a signature that is uploaded together with a crate.
+/// Sigstore bundles, which include a certificate chain, are usually only a
+/// few kilobytes large.
+pub(super) const MAX_SIGNATURE_SIZE: usize = 64 * 1024;
+
+/// The header that carries the one-time password for crates that require
+/// publishes to be confirmed with one.
+pub const OTP_HEADER: &str = "X-Crates-Io-Otp";
+
 pub const WILDCARD_ERROR_MESSAGE: &str = "wildcard (`*`) dependency constraints are not allowed \
      on crates.io. See https://doc.rust-lang.org/cargo/faq.html#can-\
      libraries-use--as-a-version-for-their-dependencies for more \
@@ -47,34 +62,48 @@ pub const WILDCARD_ERROR_MESSAGE: &str = "wildcard (`*`) dependency constraints
 /// --status` command, via crates.io's front end, or email.
 pub async fn publish(app: AppState, req: BytesRequest) -> AppResult<Json<GoodCrate>> {
     let (req, bytes) = req.0.into_parts();
-    let (json_bytes, tarball_bytes) = split_body(bytes, &req)?;
-
-    let new_crate: EncodableCrateUpload = serde_json::from_slice(&json_bytes)
-        .map_err(|e| cargo_err(&format_args!("invalid upload request: {e}")))?;
-
-    let request_log = req.request_log();
-    request_log.add("crate_name", new_crate.name.to_string());
-    request_log.add("crate_version", new_crate.vers.to_string());
-
-    // Make sure required fields are provided
-    fn empty(s: Option<&String>) -> bool {
-        s.map_or(true, String::is_empty)
-    }
+    publish_body(app, req, bytes).await
+}
 
-    // It can have up to three elements per below conditions.
-    let mut missing = Vec::with_capacity(3);
+/// Publishes a crate from the body of a `PUT /crates/new` request.
+///
+/// This is also used to publish the body of a completed chunked upload.
+pub(crate) async fn publish_body(
+    app: AppState,
+    req: Parts,
+    bytes: Bytes,
+) -> AppResult<Json<GoodCrate>> {
+    let (new_crate, tarball_bytes, signature) =
+        parse_body(bytes, &req, &app.config.metadata_limits)?;
+
+    publish_upload(app, req, new_crate, tarball_bytes, signature, None).await
+}
 
-    if empty(new_crate.description.as_ref()) {
-        missing.push("description");
-    }
-    if empty(new_crate.license.as_ref()) && empty(new_crate.license_file.as_ref()) {
-        missing.push("license");
-    }
-    if !missing.is_empty() {
-        let message = missing_metadata_error_message(&missing);
-        return Err(cargo_err(&message));
-    }
+/// Publishes the body of a staged publish. The staged publish is deleted in
+/// the same transaction as the version is created, so that it is either
+/// promoted completely or stays staged.
+pub(super) async fn publish_staged(
+    app: AppState,
+    req: Parts,
+    staged: StagedPublish,
+) -> AppResult<Json<GoodCrate>> {
+    let bytes = Bytes::from(staged.body.clone());
+    let (new_crate, tarball_bytes, signature) =
+        parse_body(bytes, &req, &app.config.metadata_limits)?;
+
+    publish_upload(app, req, new_crate, tarball_bytes, signature, Some(staged)).await
+}
 
+/// Publishes a crate from the already parsed metadata, tarball and optional
+/// signature of a publish request.
+pub(super) async fn publish_upload(
+    app: AppState,
+    req: Parts,
+    new_crate: EncodableCrateUpload,
+    tarball_bytes: Bytes,
+    signature: Option<Bytes>,
+    staged: Option<StagedPublish>,
+) -> AppResult<Json<GoodCrate>> {
     conduit_compat(move || {
         let conn = &mut *app.primary_database.get()?;
 
@@ -107,9 +136,28 @@ pub async fn publish(app: AppState, req: BytesRequest) -> AppResult<Json<GoodCra
             ))
         })?;
 
+        // The one-time password is checked before the transaction, so that
+        // invalid codes are counted towards the lockout of the authenticator
+        // even though the publish is rolled back.
+        let mut otp_confirmed = false;
+        if let Some(existing_crate) = &existing_crate {
+            if publish_requires_2fa(conn, existing_crate.id)? {
+                ensure_otp_confirmed(conn, &req, user.id)?;
+                otp_confirmed = true;
+            }
+        }
+
         // Create a transaction on the database, if there are no errors,
         // commit the transactions to record a new or updated crate.
         conn.transaction(|conn| {
+            // Promoting the same staged publish concurrently must not publish
+            // it twice
+            if let Some(staged) = &staged {
+                if !staged.delete(conn)? {
+                    return Err(conflict("this staged publish was already promoted"));
+                }
+            }
+
             let _ = &new_crate;
             let name = new_crate.name;
             let vers = &*new_crate.vers;
@@ -142,15 +190,31 @@ pub async fn publish(app: AppState, req: BytesRequest) -> AppResult<Json<GoodCra
                 max_upload_size: None,
             };
 
+            if existing_crate.is_none() {
+                ReservedCratePrefix::ensure_can_publish(conn, &app, user, &name)?;
+            }
+
             let license_file = new_crate.license_file.as_deref();
             let krate =
                 persist.create_or_update(conn, user.id, Some(&app.config.publish_rate_limit))?;
 
+            // New crates that are published with a token of an organization
+            // are owned by the organization from the start
+            let organization_id = auth.api_token().and_then(|token| token.organization_id);
+            if let (None, Some(organization_id)) = (&existing_crate, organization_id) {
+                let organization = Organization::find(conn, organization_id)?;
+                krate.insert_owner(conn, &Owner::Organization(organization), user.id)?;
+            }
+
             let owners = krate.owners(conn)?;
-            if user.rights(&app, &owners)? < Rights::Publish {
+            if user.rights(&app, conn, &owners)? < Rights::Publish {
                 return Err(cargo_err(MISSING_RIGHTS_ERROR_MESSAGE));
             }
 
+            if !otp_confirmed && publish_requires_2fa(conn, krate.id)? {
+                ensure_otp_confirmed(conn, &req, user.id)?;
+            }
+
             if krate.name != *name {
                 return Err(cargo_err(&format_args!(
                     "crate was previously named `{}`",
@@ -158,6 +222,14 @@ pub async fn publish(app: AppState, req: BytesRequest) -> AppResult<Json<GoodCra
                 )));
             }
 
+            if existing_crate.is_some() {
+                app.config.publish_update_rate_limit.check_rate_limit(
+                    user.id,
+                    LimitedAction::PublishUpdate,
+                    conn,
+                )?;
+            }
+
             if let Some(daily_version_limit) = app.config.new_version_rate_limit {
                 let published_today = count_versions_published_today(krate.id, conn)?;
                 if published_today >= daily_version_limit as i64 {
@@ -176,10 +248,8 @@ pub async fn publish(app: AppState, req: BytesRequest) -> AppResult<Json<GoodCra
             );
 
             if content_length > maximums.max_upload_size {
-                return Err(cargo_err(&format_args!(
-                    "max upload size is: {}",
-                    maximums.max_upload_size
-                )));
+                let max_upload_size = maximums.max_upload_size;
+                return Err(Box::new(UploadTooLarge { max_upload_size }));
             }
 
             // This is only redundant for now. Eventually the duplication will be removed.
@@ -188,6 +258,23 @@ pub async fn publish(app: AppState, req: BytesRequest) -> AppResult<Json<GoodCra
             // Read tarball from request
             let hex_cksum: String = Sha256::digest(&tarball_bytes).encode_hex();
 
+            if app.config.reject_duplicate_tarballs {
+                // Publishing the exact same tarball twice is most likely a
+                // mistake in a publishing script
+                let duplicate = versions::table
+                    .inner_join(crates::table)
+                    .filter(versions::checksum.eq(&hex_cksum))
+                    .select((crates::name, versions::num))
+                    .first::<(String, String)>(conn)
+                    .optional()?;
+
+                if let Some((name, num)) = duplicate {
+                    return Err(cargo_err(&format_args!(
+                        "the uploaded tarball is identical to the one of `{name}@{num}`"
+                    )));
+                }
+            }
+
             // Persist the new version of this crate
             let version = NewVersion::new(
                 krate.id,
@@ -225,9 +312,14 @@ pub async fn publish(app: AppState, req: BytesRequest) -> AppResult<Json<GoodCra
             let top_versions = krate.top_versions(conn)?;
 
             let pkg_name = format!("{}-{}", krate.name, vers);
-            let cargo_vcs_info =
-                verify_tarball(&pkg_name, &tarball_bytes, maximums.max_unpack_size)?;
-            let pkg_path_in_vcs = cargo_vcs_info.map(|info| info.path_in_vcs);
+            let tarball_info = verify_tarball(&pkg_name, &tarball_bytes, maximums.max_unpack_size)?;
+            let pkg_path_in_vcs = tarball_info.vcs_info.map(|info| info.path_in_vcs);
+
+            if let Some(rust_version) = &tarball_info.rust_version {
+                diesel::update(&version)
+                    .set(versions::rust_version.eq(rust_version))
+                    .execute(conn)?;
+            }
 
             if let Some(readme) = new_crate.readme {
                 worker::render_and_upload_readme(
@@ -243,36 +335,64 @@ pub async fn publish(app: AppState, req: BytesRequest) -> AppResult<Json<GoodCra
             }
 
             // Upload crate tarball
-            app.config
-                .uploader()
-                .upload_crate(app.http_client(), tarball_bytes, &krate, vers)?;
-
-            let (features, features2): (BTreeMap<_, _>, BTreeMap<_, _>) =
-                features.into_iter().partition(|(_k, vals)| {
-                    !vals
-                        .iter()
-                        .any(|v| v.starts_with("dep:") || v.contains("?/"))
-                });
-            let (features2, v) = if features2.is_empty() {
-                (None, None)
-            } else {
-                (Some(features2), Some(2))
-            };
+            let uploader = app.config.uploader();
+            let object_key = uploader.upload_crate(
+                app.http_client(),
+                conn,
+                tarball_bytes,
+                &krate,
+                vers,
+                &hex_cksum,
+            )?;
+            if let Some(object_key) = object_key {
+                CrateFile::record(conn, version.id, &object_key)?;
+            }
+            uploader.replicate_crate(conn, &krate.name, &vers.to_string())?;
+
+            if let Some(signature) = signature {
+                let kind = SignatureKind::detect(&signature);
+                NewVersionSignature {
+                    version_id: version.id,
+                    kind: kind.as_str(),
+                    size: signature.len() as i32,
+                }
+                .create(conn)?;
+
+                uploader.upload_signature(
+                    app.http_client(),
+                    &krate.name,
+                    &vers.to_string(),
+                    signature,
+                    kind.content_type(),
+                )?;
+            }
+
+            let (features, features2, v) = cargo_registry_index::Crate::split_features(features);
 
             // Register this crate in our local git repo.
             let git_crate = cargo_registry_index::Crate {
                 name: name.0,
                 vers: vers.to_string(),
-                cksum: hex_cksum,
+                cksum: hex_cksum.clone(),
                 features,
                 features2,
                 deps: git_deps,
                 yanked: Some(false),
+                yank_reason: None,
+                yank_replacement: None,
+                deprecated: krate.index_deprecation(None),
                 links,
                 v,
             };
             worker::add_crate(git_crate).enqueue(conn)?;
 
+            if app.config.search_backend.needs_sync() {
+                worker::sync_search_index(krate.name.clone()).enqueue(conn)?;
+            }
+
+            let data = json!({ "version": vers.to_string(), "published_by": user.gh_login });
+            worker::trigger_webhooks(conn, krate.id, &krate.name, WebhookEvent::Publish, data)?;
+
             // The `other` field on `PublishWarnings` was introduced to handle a temporary warning
             // that is no longer needed. As such, crates.io currently does not return any `other`
             // warnings at this time, but if we need to, the field is available.
@@ -285,12 +405,43 @@ pub async fn publish(app: AppState, req: BytesRequest) -> AppResult<Json<GoodCra
             Ok(Json(GoodCrate {
                 krate: EncodableCrate::from_minimal(krate, Some(&top_versions), None, false, None),
                 warnings,
+                cksum: hex_cksum,
             }))
         })
     })
     .await
 }
 
+/// Checks the one-time password in the `X-Crates-Io-Otp` header of a publish
+/// of a crate whose owners require it.
+fn ensure_otp_confirmed(conn: &mut PgConnection, req: &Parts, user_id: i32) -> AppResult<()> {
+    let credential = TotpCredential::find(conn, user_id)?
+        .filter(|credential| credential.enabled)
+        .ok_or_else(|| {
+            cargo_err(
+                "the owners of this crate require publishes to be confirmed with a one-time \
+                 password, but you have not enabled an authenticator on your account",
+            )
+        })?;
+
+    let code = req
+        .headers
+        .get(OTP_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| {
+            cargo_err(&format_args!(
+                "the owners of this crate require publishes to be confirmed with a one-time \
+                 password, please pass it in the `{OTP_HEADER}` header"
+            ))
+        })?;
+
+    if !credential.verify_or_redeem(conn, code)? {
+        return Err(cargo_err("invalid or already used one-time password"));
+    }
+
+    Ok(())
+}
+
 /// Counts the number of versions for `krate_id` that were published within
 /// the last 24 hours.
 fn count_versions_published_today(krate_id: i32, conn: &mut PgConnection) -> QueryResult<i64> {
@@ -305,13 +456,118 @@ fn count_versions_published_today(krate_id: i32, conn: &mut PgConnection) -> Que
 }
 
 #[instrument(skip_all)]
-fn split_body<R: RequestPartsExt>(mut bytes: Bytes, req: &R) -> AppResult<(Bytes, Bytes)> {
+/// Splits the body of a publish request into the crate metadata, the tarball
+/// and the optional signature, and checks that the required metadata fields
+/// are present.
+pub(super) fn parse_body<R: RequestPartsExt>(
+    bytes: Bytes,
+    req: &R,
+    limits: &MetadataLimits,
+) -> AppResult<(EncodableCrateUpload, Bytes, Option<Bytes>)> {
+    let (json_bytes, tarball_bytes, signature) = split_body(bytes, req)?;
+    let new_crate = parse_metadata(&json_bytes, &tarball_bytes, req, limits)?;
+    Ok((new_crate, tarball_bytes, signature))
+}
+
+/// Parses the crate metadata of a publish request, and checks that the
+/// required fields are present and that the checksum matches the tarball.
+pub(super) fn parse_metadata<R: RequestPartsExt>(
+    json_bytes: &[u8],
+    tarball_bytes: &[u8],
+    req: &R,
+    limits: &MetadataLimits,
+) -> AppResult<EncodableCrateUpload> {
+    if json_bytes.len() > limits.max_metadata_size {
+        let max = limits.max_metadata_size;
+        return Err(limit_exceeded(MetadataLimit::MetadataSize, max));
+    }
+
+    let new_crate: EncodableCrateUpload = serde_json::from_slice(json_bytes)
+        .map_err(|e| cargo_err(&format_args!("invalid upload request: {e}")))?;
+
+    let request_log = req.request_log();
+    request_log.add("crate_name", new_crate.name.to_string());
+    request_log.add("crate_version", new_crate.vers.to_string());
+
+    // Make sure required fields are provided
+    fn empty(s: Option<&String>) -> bool {
+        s.map_or(true, String::is_empty)
+    }
+
+    // It can have up to three elements per below conditions.
+    let mut missing = Vec::with_capacity(3);
+
+    if empty(new_crate.description.as_ref()) {
+        missing.push("description");
+    }
+    if empty(new_crate.license.as_ref()) && empty(new_crate.license_file.as_ref()) {
+        missing.push("license");
+    }
+    if !missing.is_empty() {
+        let message = missing_metadata_error_message(&missing);
+        return Err(cargo_err(&message));
+    }
+
+    check_metadata_limits(&new_crate, limits)?;
+
+    // Detect corruption of the tarball between the client and us
+    if let Some(expected) = &new_crate.cksum {
+        let cksum: String = Sha256::digest(tarball_bytes).encode_hex();
+        if !cksum.eq_ignore_ascii_case(expected) {
+            return Err(cargo_err(&format_args!(
+                "the SHA-256 checksum of the uploaded tarball is `{cksum}`, \
+                 but the publish metadata specifies `{expected}`"
+            )));
+        }
+    }
+
+    Ok(new_crate)
+}
+
+/// Checks the dependencies and features of a crate against the configured
+/// limits, since huge numbers of them bloat the index and slow down resolvers.
+fn check_metadata_limits(
+    new_crate: &EncodableCrateUpload,
+    limits: &MetadataLimits,
+) -> AppResult<()> {
+    if new_crate.deps.len() > limits.max_dependencies {
+        let max = limits.max_dependencies;
+        return Err(limit_exceeded(MetadataLimit::Dependencies, max));
+    }
+
+    if new_crate.features.len() > limits.max_features {
+        let max = limits.max_features;
+        return Err(limit_exceeded(MetadataLimit::Features, max));
+    }
+
+    let max = limits.max_feature_name_length;
+    if new_crate
+        .features
+        .keys()
+        .any(|name| name.chars().count() > max)
+    {
+        return Err(limit_exceeded(MetadataLimit::FeatureNameLength, max));
+    }
+
+    Ok(())
+}
+
+fn limit_exceeded(limit: MetadataLimit, max: usize) -> BoxedAppError {
+    Box::new(MetadataLimitExceeded { limit, max })
+}
+
+fn split_body<R: RequestPartsExt>(
+    mut bytes: Bytes,
+    req: &R,
+) -> AppResult<(Bytes, Bytes, Option<Bytes>)> {
     // The format of the req.body() of a publish request is as follows:
     //
     // metadata length
     // metadata in JSON about the crate being published
     // .crate tarball length
     // .crate tarball file
+    // (optional) signature length
+    // (optional) detached signature or Sigstore bundle of the .crate file
 
     let json_len = bytes.get_u32_le() as usize;
     req.request_log().add("metadata_length", json_len);
@@ -333,7 +589,36 @@ fn split_body<R: RequestPartsExt>(mut bytes: Bytes, req: &R) -> AppResult<(Bytes
 
     let tarball_bytes = bytes.split_to(tarball_len);
 
-    Ok((json_bytes, tarball_bytes))
+    // Older clients don't send anything after the tarball
+    if bytes.len() < 4 {
+        return Ok((json_bytes, tarball_bytes, None));
+    }
+
+    let signature_len = bytes.get_u32_le() as usize;
+    req.request_log().add("signature_length", signature_len);
+
+    if signature_len == 0 || signature_len > bytes.len() {
+        return Err(cargo_err(&format!(
+            "invalid signature length for remaining payload: {signature_len}"
+        )));
+    }
+
+    if signature_len > MAX_SIGNATURE_SIZE {
+        return Err(cargo_err(&format_args!(
+            "max signature size is: {MAX_SIGNATURE_SIZE}"
+        )));
+    }
+
+    let signature = bytes.split_to(signature_len);
+
+    // Detached signatures can't be verified without the key of the
+    // publisher, but Sigstore bundles contain everything that is needed
+    if SignatureKind::detect(&signature) == SignatureKind::SigstoreBundle {
+        sigstore::verify_bundle(&signature, &tarball_bytes)
+            .map_err(|e| cargo_err(&format_args!("invalid Sigstore bundle: {e}")))?;
+    }
+
+    Ok((json_bytes, tarball_bytes, Some(signature)))
 }
 
 pub fn missing_metadata_error_message(missing: &[&str]) -> String {
@@ -371,6 +656,24 @@ pub fn add_dependencies(
                 if version_req == semver::VersionReq::STAR {
                     return Err(cargo_err(WILDCARD_ERROR_MESSAGE));
                 }
+
+                // Requirements that no version satisfies are most likely typos,
+                // and would make the new version impossible to build
+                let available: Vec<String> = krate
+                    .all_versions()
+                    .filter(versions::yanked.eq(false))
+                    .select(versions::num)
+                    .load(conn)?;
+                let is_satisfiable = available
+                    .iter()
+                    .filter_map(|num| semver::Version::parse(num).ok())
+                    .any(|num| version_req.matches(&num));
+                if !is_satisfiable {
+                    return Err(cargo_err(&format_args!(
+                        "no non-yanked version of `{}` matches the dependency requirement `{}`",
+                        krate.name, version_req
+                    )));
+                }
             }
 
             // If this dependency has an explicit name in `Cargo.toml` that
@@ -419,11 +722,20 @@ pub fn add_dependencies(
     Ok(git_deps)
 }
 
-fn verify_tarball(
+/// Information extracted from the tarball of a crate while verifying it.
+#[derive(Debug, Default)]
+pub(super) struct TarballInfo {
+    pub vcs_info: Option<CargoVcsInfo>,
+    /// The `rust-version` from the embedded manifest, normalized to
+    /// `major.minor.patch`.
+    pub rust_version: Option<String>,
+}
+
+pub(super) fn verify_tarball(
     pkg_name: &str,
     tarball: &[u8],
     max_unpack: u64,
-) -> AppResult<Option<CargoVcsInfo>> {
+) -> AppResult<TarballInfo> {
     // All our data is currently encoded with gzip
     let decoder = GzDecoder::new(tarball);
 
@@ -435,7 +747,9 @@ fn verify_tarball(
     let mut archive = tar::Archive::new(decoder);
 
     let vcs_info_path = Path::new(&pkg_name).join(".cargo_vcs_info.json");
-    let mut vcs_info = None;
+    let mut info = TarballInfo::default();
+
+    let manifest_path = Path::new(&pkg_name).join("Cargo.toml");
 
     for entry in archive.entries()? {
         let mut entry = entry.map_err(|err| {
@@ -444,20 +758,28 @@ fn verify_tarball(
             ))
         })?;
 
+        // Absolute paths and `..` components could be used to write outside
+        // of the package directory when the tarball is extracted, so every
+        // component of the path has to be a plain file or directory name.
+        let entry_path = entry.path()?;
+        if !entry_path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(cargo_err(&format_args!(
+                "invalid tarball uploaded: `{}` is not a relative path within the package",
+                entry_path.display()
+            )));
+        }
+
         // Verify that all entries actually start with `$name-$vers/`.
         // Historically Cargo didn't verify this on extraction so you could
         // upload a tarball that contains both `foo-0.1.0/` source code as well
         // as `bar-0.1.0/` source code, and this could overwrite other crates in
         // the registry!
-        let entry_path = entry.path()?;
         if !entry_path.starts_with(pkg_name) {
             return Err(cargo_err("invalid tarball uploaded"));
         }
-        if entry_path == vcs_info_path {
-            let mut contents = String::new();
-            entry.read_to_string(&mut contents)?;
-            vcs_info = CargoVcsInfo::from_contents(&contents).ok();
-        }
 
         // Historical versions of the `tar` crate which Cargo uses internally
         // don't properly prevent hard links and symlinks from overwriting
@@ -468,14 +790,93 @@ fn verify_tarball(
         if entry_type.is_hard_link() || entry_type.is_symlink() {
             return Err(cargo_err("invalid tarball uploaded"));
         }
+
+        // Device files and named pipes have no business in a source package.
+        if entry_type.is_character_special()
+            || entry_type.is_block_special()
+            || entry_type.is_fifo()
+        {
+            return Err(cargo_err(&format_args!(
+                "invalid tarball uploaded: `{}` is a special file",
+                entry_path.display()
+            )));
+        }
+
+        if entry_path == vcs_info_path {
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents)?;
+            info.vcs_info = CargoVcsInfo::from_contents(&contents).ok();
+        } else if entry_path == manifest_path {
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents)?;
+            info.rust_version = verify_manifest(pkg_name, &contents)?;
+        }
+    }
+    Ok(info)
+}
+
+#[derive(Deserialize)]
+struct TarballManifest {
+    package: Option<TarballManifestPackage>,
+}
+
+#[derive(Deserialize)]
+struct TarballManifestPackage {
+    name: Option<String>,
+    version: Option<String>,
+    #[serde(rename = "rust-version")]
+    rust_version: Option<toml::Value>,
+}
+
+/// Checks that the name and version in the `Cargo.toml` file embedded in the
+/// tarball match the metadata that was sent along with it, and returns the
+/// normalized `rust-version` of the package, if any.
+///
+/// Manifests that cannot be parsed are left for cargo to complain about, since
+/// they are unusable for anyone depending on the crate anyway.
+fn verify_manifest(pkg_name: &str, contents: &str) -> AppResult<Option<String>> {
+    let Ok(manifest) = toml::from_str::<TarballManifest>(contents) else { return Ok(None) };
+    let Some(package) = manifest.package else { return Ok(None) };
+
+    if let (Some(name), Some(version)) = (package.name, package.version) {
+        let embedded = format!("{name}-{version}");
+        if embedded != pkg_name {
+            return Err(cargo_err(&format_args!(
+                "the `Cargo.toml` file in the uploaded tarball is for `{embedded}`, \
+                 but the upload request is for `{pkg_name}`"
+            )));
+        }
+    }
+
+    let rust_version = package.rust_version.as_ref().and_then(|v| v.as_str());
+    Ok(rust_version.and_then(normalize_rust_version))
+}
+
+/// Normalizes a `rust-version` like `1.70` to `1.70.0`.
+///
+/// Values that are not a plain `major.minor[.patch]` version are ignored, the
+/// same way older cargo versions ignore them.
+fn normalize_rust_version(rust_version: &str) -> Option<String> {
+    let rust_version = rust_version.trim();
+    let components = rust_version.split('.').count();
+    let rust_version = match components {
+        2 => format!("{rust_version}.0"),
+        3 => rust_version.to_string(),
+        _ => return None,
+    };
+
+    let version = semver::Version::parse(&rust_version).ok()?;
+    if !version.pre.is_empty() || !version.build.is_empty() {
+        return None;
     }
-    Ok(vcs_info)
+
+    Some(version.to_string())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{missing_metadata_error_message, verify_tarball};
-    use crate::admin::render_readmes::tests::add_file;
+    use super::{missing_metadata_error_message, normalize_rust_version, verify_tarball};
+    use crate::worker::readmes::tests::add_file;
     use flate2::read::GzEncoder;
     use std::io::Read;
 
@@ -496,10 +897,9 @@ mod tests {
             .unwrap();
 
         let limit = 512 * 1024 * 1024;
-        assert_eq!(
-            verify_tarball("foo-0.0.1", &serialized_archive, limit).unwrap(),
-            None
-        );
+        let info = verify_tarball("foo-0.0.1", &serialized_archive, limit).unwrap();
+        assert_none!(info.vcs_info);
+        assert_none!(info.rust_version);
         assert_err!(verify_tarball("bar-0.0.1", &serialized_archive, limit));
     }
 
@@ -519,6 +919,7 @@ mod tests {
         let limit = 512 * 1024 * 1024;
         let vcs_info = verify_tarball("foo-0.0.1", &serialized_archive, limit)
             .unwrap()
+            .vcs_info
             .unwrap();
         assert_eq!(vcs_info.path_in_vcs, "");
     }
@@ -539,7 +940,109 @@ mod tests {
         let limit = 512 * 1024 * 1024;
         let vcs_info = verify_tarball("foo-0.0.1", &serialized_archive, limit)
             .unwrap()
+            .vcs_info
             .unwrap();
         assert_eq!(vcs_info.path_in_vcs, "path/in/vcs");
     }
+
+    fn serialize(pkg: tar::Builder<Vec<u8>>) -> Vec<u8> {
+        let mut serialized_archive = vec![];
+        GzEncoder::new(pkg.into_inner().unwrap().as_slice(), Default::default())
+            .read_to_end(&mut serialized_archive)
+            .unwrap();
+        serialized_archive
+    }
+
+    /// `tar::Builder` refuses to write unsafe paths, so the name is written
+    /// into the header directly.
+    fn add_raw_entry(
+        pkg: &mut tar::Builder<Vec<u8>>,
+        path: &str,
+        entry_type: tar::EntryType,
+        content: &[u8],
+    ) {
+        let mut header = tar::Header::new_gnu();
+        header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+        header.set_entry_type(entry_type);
+        header.set_size(content.len() as u64);
+        header.set_cksum();
+        pkg.append(&header, content).unwrap();
+    }
+
+    #[test]
+    fn verify_tarball_rejects_unsafe_paths() {
+        let limit = 512 * 1024 * 1024;
+
+        for path in ["/foo-0.0.1/Cargo.toml", "foo-0.0.1/../bar-0.0.1/lib.rs"] {
+            let mut pkg = tar::Builder::new(vec![]);
+            add_raw_entry(&mut pkg, path, tar::EntryType::Regular, b"");
+            let error = verify_tarball("foo-0.0.1", &serialize(pkg), limit).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!(
+                    "invalid tarball uploaded: `{path}` is not a relative path within the package"
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn verify_tarball_rejects_special_files() {
+        let limit = 512 * 1024 * 1024;
+
+        for entry_type in [
+            tar::EntryType::Char,
+            tar::EntryType::Block,
+            tar::EntryType::Fifo,
+        ] {
+            let mut pkg = tar::Builder::new(vec![]);
+            add_raw_entry(&mut pkg, "foo-0.0.1/dev", entry_type, b"");
+            let error = verify_tarball("foo-0.0.1", &serialize(pkg), limit).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "invalid tarball uploaded: `foo-0.0.1/dev` is a special file"
+            );
+        }
+    }
+
+    #[test]
+    fn verify_tarball_checks_embedded_manifest() {
+        let limit = 512 * 1024 * 1024;
+
+        let mut pkg = tar::Builder::new(vec![]);
+        let manifest = b"[package]\nname = \"foo\"\nversion = \"0.0.1\"\n";
+        add_file(&mut pkg, "foo-0.0.1/Cargo.toml", manifest);
+        assert_ok!(verify_tarball("foo-0.0.1", &serialize(pkg), limit));
+
+        let mut pkg = tar::Builder::new(vec![]);
+        let manifest = b"[package]\nname = \"foo\"\nversion = \"0.0.2\"\n";
+        add_file(&mut pkg, "foo-0.0.1/Cargo.toml", manifest);
+        let error = verify_tarball("foo-0.0.1", &serialize(pkg), limit).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the `Cargo.toml` file in the uploaded tarball is for `foo-0.0.2`, \
+             but the upload request is for `foo-0.0.1`"
+        );
+    }
+
+    #[test]
+    fn verify_tarball_extracts_rust_version() {
+        let limit = 512 * 1024 * 1024;
+
+        let mut pkg = tar::Builder::new(vec![]);
+        let manifest = b"[package]\nname = \"foo\"\nversion = \"0.0.1\"\nrust-version = \"1.70\"\n";
+        add_file(&mut pkg, "foo-0.0.1/Cargo.toml", manifest);
+        let info = verify_tarball("foo-0.0.1", &serialize(pkg), limit).unwrap();
+        assert_some_eq!(info.rust_version, "1.70.0");
+    }
+
+    #[test]
+    fn normalize_rust_version_test() {
+        assert_some_eq!(normalize_rust_version("1.70"), "1.70.0");
+        assert_some_eq!(normalize_rust_version("1.56.1"), "1.56.1");
+        assert_none!(normalize_rust_version("1"));
+        assert_none!(normalize_rust_version("1.70.0-beta"));
+        assert_none!(normalize_rust_version("1.70.0.1"));
+        assert_none!(normalize_rust_version("stable"));
+    }
 }
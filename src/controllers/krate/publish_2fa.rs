@@ -0,0 +1,120 @@
+//! Endpoints for requiring that publishes of a crate are confirmed with a
+//! one-time password.
+//!
+//! Once enabled, API tokens alone are no longer sufficient to publish a new
+//! version of the crate: the publishing user must also have an authenticator
+//! enabled (see `user::totp`) and pass a current code in the
+//! `X-Crates-Io-Otp` header. Other second factors, like WebAuthn, are not
+//! supported yet.
+//!
+//! Disabling the requirement again must be confirmed with a one-time password
+//! in the same header, so that a hijacked session alone can't be used to turn
+//! it off.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::controllers::krate::publish::OTP_HEADER;
+use crate::models::{Crate, Rights, TotpCredential};
+use crate::schema::crates;
+
+/// Handles the `GET /crates/:crate_id/publish_2fa` route.
+pub async fn show(
+    app: AppState,
+    Path(crate_name): Path<String>,
+    req: Parts,
+) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let auth = AuthCheck::default()
+            .for_crate(&crate_name)
+            .check(&req, conn)?;
+
+        let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
+        let owners = krate.owners(conn)?;
+        if auth.user().rights(&app, conn, &owners)? < Rights::Publish {
+            return Err(bad_request(
+                "only owners have permission to view this setting",
+            ));
+        }
+
+        let required = publish_requires_2fa(conn, krate.id)?;
+
+        Ok(Json(json!({ "publish_requires_2fa": required })))
+    })
+    .await
+}
+
+/// Handles the `PUT /crates/:crate_id/publish_2fa` route.
+pub async fn update(
+    app: AppState,
+    Path(crate_name): Path<String>,
+    req: BytesRequest,
+) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct UpdateRequest {
+            required: bool,
+        }
+
+        let request: UpdateRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid request: {e}")))?;
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+
+        let krate: Crate = Crate::by_name(&crate_name).first(conn)?;
+        let owners = krate.owners(conn)?;
+        if user.rights(&app, conn, &owners)? < Rights::Full {
+            return Err(bad_request(
+                "only owners have permission to change this setting",
+            ));
+        }
+
+        // Prevent owners from locking themselves out of publishing
+        if request.required && !TotpCredential::is_enabled_for(conn, user.id)? {
+            return Err(bad_request(
+                "you need to enable an authenticator on your account first",
+            ));
+        }
+
+        if !request.required && publish_requires_2fa(conn, krate.id)? {
+            let credential = TotpCredential::find(conn, user.id)?
+                .filter(|credential| credential.enabled)
+                .ok_or_else(|| {
+                    bad_request("you need to enable an authenticator on your account first")
+                })?;
+
+            let code = req
+                .headers()
+                .get(OTP_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| {
+                    bad_request(&format_args!(
+                        "disabling this requirement must be confirmed with a one-time \
+                         password, please pass it in the `{OTP_HEADER}` header"
+                    ))
+                })?;
+
+            if !credential.verify_or_redeem(conn, code)? {
+                return Err(bad_request("invalid or already used one-time password"));
+            }
+        }
+
+        diesel::update(crates::table.find(krate.id))
+            .set(crates::publish_requires_2fa.eq(request.required))
+            .execute(conn)?;
+
+        Ok(Json(json!({ "publish_requires_2fa": request.required })))
+    })
+    .await
+}
+
+/// Returns whether publishes of the crate must be confirmed with a one-time
+/// password.
+pub(crate) fn publish_requires_2fa(conn: &mut PgConnection, crate_id: i32) -> QueryResult<bool> {
+    crates::table
+        .find(crate_id)
+        .select(crates::publish_requires_2fa)
+        .first(conn)
+}
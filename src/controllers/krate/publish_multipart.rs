@@ -0,0 +1,162 @@
+//! Endpoint for publishing crates with a `multipart/form-data` body.
+//!
+//! Instead of the length-prefixed binary framing of `PUT /crates/new`, the
+//! metadata and the tarball are sent as separate parts of the body:
+//!
+//! - `metadata`: the JSON metadata of the crate, in the same format as for
+//!   `PUT /crates/new`
+//! - `tarball`: the `.crate` file
+//! - `signature` (optional): a detached signature or Sigstore bundle of the
+//!   `.crate` file
+//!
+//! The `metadata` part must come before the `tarball` part, so that the
+//! maximum upload size of the crate is known while the tarball is read. The
+//! parts are read as they are streamed in, so oversized parts are rejected
+//! without buffering the whole request body first. Problems with the body
+//! itself are reported with a `400 Bad Request` status that names the
+//! offending part. Once the body is read, the crate is published exactly like
+//! with `PUT /crates/new`.
+
+use axum::body::{Body, Bytes};
+use axum::extract::multipart::{Field, MultipartError};
+use axum::extract::{FromRequest, Multipart};
+
+use super::publish::{parse_metadata, publish_upload, MAX_SIGNATURE_SIZE};
+use crate::controllers::frontend_prelude::*;
+use crate::models::Crate;
+use crate::router::MAX_PUBLISH_CONTENT_LENGTH;
+use crate::schema::crates;
+use crate::util::errors::UploadTooLarge;
+use crate::util::Maximums;
+use crate::views::GoodCrate;
+
+/// Handles the `PUT /api/v2/crates/new` route.
+pub async fn publish(app: AppState, req: Request<Body>) -> AppResult<Json<GoodCrate>> {
+    // The parts of the request are needed for authentication after the body
+    // is read, so the multipart body is extracted from a request that only
+    // carries the `Content-Type` header with the boundary.
+    let (req, body) = req.into_parts();
+    let mut body = Request::new(body);
+    if let Some(content_type) = req.headers.get(header::CONTENT_TYPE) {
+        body.headers_mut()
+            .insert(header::CONTENT_TYPE, content_type.clone());
+    }
+    let mut multipart = Multipart::from_request(body, &())
+        .await
+        .map_err(|e| bad_request(&format_args!("invalid multipart body: {e}")))?;
+
+    let mut metadata: Option<Bytes> = None;
+    let mut tarball: Option<Bytes> = None;
+    let mut signature: Option<Bytes> = None;
+
+    while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+        let name = field.name().unwrap_or_default().to_string();
+        let limit = match name.as_str() {
+            "metadata" => PartLimit::Part(app.config.metadata_limits.max_metadata_size),
+            "tarball" => {
+                let metadata = metadata.as_ref().ok_or_else(|| {
+                    bad_request("the part `metadata` must come before the part `tarball`")
+                })?;
+                PartLimit::Upload(max_upload_size(&app, metadata).await?)
+            }
+            "signature" => PartLimit::Part(MAX_SIGNATURE_SIZE),
+            _ => {
+                return Err(bad_request(&format_args!(
+                    "unexpected part `{name}`, expected `metadata`, `tarball` or `signature`"
+                )))
+            }
+        };
+
+        let part = match name.as_str() {
+            "metadata" => &mut metadata,
+            "tarball" => &mut tarball,
+            _ => &mut signature,
+        };
+        if part.is_some() {
+            return Err(bad_request(&format_args!("duplicate part `{name}`")));
+        }
+
+        *part = Some(read_part(&mut field, &name, limit).await?);
+    }
+
+    let metadata = metadata.ok_or_else(|| bad_request("missing part `metadata`"))?;
+    let tarball = tarball.ok_or_else(|| bad_request("missing part `tarball`"))?;
+    if signature.as_ref().is_some_and(Bytes::is_empty) {
+        return Err(bad_request("the part `signature` must not be empty"));
+    }
+
+    let new_crate = parse_metadata(&metadata, &tarball, &req, &app.config.metadata_limits)?;
+
+    publish_upload(app, req, new_crate, tarball, signature, None).await
+}
+
+/// The maximum size of a part, and how exceeding it is reported.
+#[derive(Clone, Copy)]
+enum PartLimit {
+    Part(usize),
+    /// The maximum upload size of the crate, which is reported like for
+    /// `PUT /crates/new`.
+    Upload(u64),
+}
+
+impl PartLimit {
+    fn check(self, name: &str, size: usize) -> AppResult<()> {
+        match self {
+            Self::Part(max_size) if size > max_size => Err(bad_request(&format_args!(
+                "the part `{name}` must not be larger than {max_size} bytes"
+            ))),
+            Self::Upload(max_upload_size) if size as u64 > max_upload_size => {
+                Err(Box::new(UploadTooLarge { max_upload_size }))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns the maximum size of the tarball, which depends on the limit of
+/// the crate if it already exists.
+async fn max_upload_size(app: &AppState, metadata: &[u8]) -> AppResult<u64> {
+    #[derive(Deserialize)]
+    struct Metadata {
+        name: String,
+    }
+
+    let Metadata { name } = serde_json::from_slice(metadata)
+        .map_err(|e| cargo_err(&format_args!("invalid upload request: {e}")))?;
+
+    let app_clone = app.clone();
+    let crate_max_upload_size = conduit_compat(move || {
+        let conn = &mut *app_clone.db_read_prefer_primary()?;
+        let max_upload_size = Crate::by_name(&name)
+            .select(crates::max_upload_size)
+            .first::<Option<i32>>(conn)
+            .optional()?;
+        Ok(max_upload_size.flatten())
+    })
+    .await?;
+
+    let maximums = Maximums::new(
+        crate_max_upload_size,
+        app.config.max_upload_size,
+        app.config.max_unpack_size,
+    );
+    Ok(maximums
+        .max_upload_size
+        .min(MAX_PUBLISH_CONTENT_LENGTH as u64))
+}
+
+/// Reads the chunks of a part as they are streamed in, until it ends or until
+/// it exceeds the limit.
+async fn read_part(field: &mut Field<'_>, name: &str, limit: PartLimit) -> AppResult<Bytes> {
+    let mut buffer = Vec::new();
+    while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+        limit.check(name, buffer.len() + chunk.len())?;
+        buffer.extend_from_slice(&chunk);
+    }
+
+    Ok(Bytes::from(buffer))
+}
+
+fn multipart_error(error: MultipartError) -> BoxedAppError {
+    bad_request(&format_args!("invalid multipart body: {error}"))
+}
@@ -1,22 +1,30 @@
 //! Endpoint for searching and discovery functionality
 
 use crate::auth::AuthCheck;
+use chrono::NaiveDateTime;
 use diesel::dsl::*;
-use diesel::sql_types::Array;
-use diesel_full_text_search::*;
+use diesel::sql_types::{Array, BigInt, Bool, Double};
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 use crate::controllers::cargo_prelude::*;
 use crate::controllers::helpers::Paginate;
-use crate::models::{Crate, CrateOwner, CrateVersions, OwnerKind, TopVersions, Version};
+use crate::models::{
+    AcceptedLicenses, Crate, CrateOwner, CrateVersions, OwnerKind, SearchTerm, TopVersions, Version,
+};
 use crate::schema::*;
+use crate::search::{SearchExpression, SearchFields};
 use crate::util::errors::bad_request;
 use crate::views::EncodableCrate;
 
-use crate::controllers::helpers::pagination::{Page, Paginated, PaginationOptions};
+use crate::controllers::helpers::pagination::{encode_seek, Page, Paginated, PaginationOptions};
 use crate::models::krate::ALL_COLUMNS;
 use crate::sql::{array_agg, canon_crate_name, lower};
 
+/// Searches with fewer results than this get a "did you mean" suggestion.
+const DID_YOU_MEAN_THRESHOLD: i64 = 3;
+
 /// Handles the `GET /crates` route.
 /// Returns a list of crates. Called in a variety of scenarios in the
 /// front end, including:
@@ -40,10 +48,11 @@ use crate::sql::{array_agg, canon_crate_name, lower};
 /// for them.
 pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
     conduit_compat(move || {
-        use diesel::sql_types::{Bool, Text};
+        use diesel::sql_types::{Integer, Text};
 
         let params = req.query();
         let sort = params.get("sort").map(|s| &**s);
+        let order = Sort::from_param(sort);
         let include_yanked = params
             .get("include_yanked")
             .map(|s| s == "yes")
@@ -58,48 +67,70 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
             ALL_COLUMNS,
             false.into_sql::<Bool>(),
             recent_crate_downloads::downloads.nullable(),
+            0f64.into_sql::<Double>(),
         );
         let mut query = crates::table
             .left_join(recent_crate_downloads::table)
             .select(selection)
             .into_boxed();
 
-        let mut supports_seek = true;
+        // The exact name match and relevance the results are sorted by when searching
+        let mut exact_match = None;
+        let mut relevance = None;
 
-        if let Some(q_string) = &q_string {
-            // Searching with a query string always puts the exact match at the start of the results,
-            // so we can't support seek-based pagination with it.
-            supports_seek = false;
+        // Whether the results are filtered, in which case the total number of results can't be
+        // counted as cheaply.
+        let mut filtered = false;
 
+        if let Some(q_string) = &q_string {
             if !q_string.is_empty() {
+                filtered = true;
+
                 let sort = params.get("sort").map(|s| &**s).unwrap_or("relevance");
 
-                let q = sql::<TsQuery>("plainto_tsquery('english', ")
-                    .bind::<Text, _>(q_string)
-                    .sql(")");
-                query = query.filter(
-                    q.clone()
-                        .matches(crates::textsearchable_index_col)
-                        .or(Crate::loosly_matches_name(q_string)),
-                );
+                let fields = params
+                    .get("in")
+                    .map(|fields| SearchFields::parse(fields))
+                    .transpose()?
+                    .unwrap_or_default();
+
+                let text_relevance = match fields {
+                    SearchFields::ExactName => {
+                        query = query.filter(Crate::with_name(q_string));
+                        None
+                    }
+                    SearchFields::Text(fields) => {
+                        let text_matches = app.search.text_matches(q_string, &fields)?;
+                        query = query.filter(text_matches.filter);
+                        Some(text_matches.relevance)
+                    }
+                };
+
+                relevance = text_relevance.filter(|_| sort == "relevance");
+                let relevance_column: SearchExpression<'_, Double> = match &relevance {
+                    Some(relevance) => relevance(),
+                    None => Box::new(0f64.into_sql::<Double>()),
+                };
 
                 query = query.select((
                     ALL_COLUMNS,
                     Crate::with_name(q_string),
                     recent_crate_downloads::downloads.nullable(),
+                    relevance_column,
                 ));
                 query = query.order(Crate::with_name(q_string).desc());
+                if order == Sort::Relevance {
+                    exact_match = Some(q_string.as_str());
+                }
 
-                if sort == "relevance" {
-                    let rank = ts_rank_cd(crates::textsearchable_index_col, q);
-                    query = query.then_order_by(rank.desc())
+                if let Some(relevance) = &relevance {
+                    query = query.then_order_by(relevance().desc())
                 }
             }
         }
 
         if let Some(cat) = params.get("category") {
-            // Calculating the total number of results with filters is not supported yet.
-            supports_seek = false;
+            filtered = true;
 
             query = query.filter(
                 crates::id.eq_any(
@@ -118,8 +149,7 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
         let conn = &mut *app.db_read()?;
 
         if let Some(kws) = params.get("all_keywords") {
-            // Calculating the total number of results with filters is not supported yet.
-            supports_seek = false;
+            filtered = true;
 
             let names: Vec<_> = kws
                 .split_whitespace()
@@ -139,8 +169,7 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
                 ),
             );
         } else if let Some(kw) = params.get("keyword") {
-            // Calculating the total number of results with filters is not supported yet.
-            supports_seek = false;
+            filtered = true;
 
             query = query.filter(
                 crates::id.eq_any(
@@ -151,8 +180,7 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
                 ),
             );
         } else if let Some(letter) = params.get("letter") {
-            // Calculating the total number of results with filters is not supported yet.
-            supports_seek = false;
+            filtered = true;
 
             let pattern = format!(
                 "{}%",
@@ -165,8 +193,7 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
             );
             query = query.filter(canon_crate_name(crates::name).like(pattern));
         } else if let Some(user_id) = params.get("user_id").and_then(|s| s.parse::<i32>().ok()) {
-            // Calculating the total number of results with filters is not supported yet.
-            supports_seek = false;
+            filtered = true;
 
             query = query.filter(
                 crates::id.eq_any(
@@ -176,8 +203,7 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
                 ),
             );
         } else if let Some(team_id) = params.get("team_id").and_then(|s| s.parse::<i32>().ok()) {
-            // Calculating the total number of results with filters is not supported yet.
-            supports_seek = false;
+            filtered = true;
 
             query = query.filter(
                 crates::id.eq_any(
@@ -186,9 +212,21 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
                         .filter(crate_owners::owner_id.eq(team_id)),
                 ),
             );
+        } else if let Some(organization_id) = params
+            .get("organization_id")
+            .and_then(|s| s.parse::<i32>().ok())
+        {
+            filtered = true;
+
+            query = query.filter(
+                crates::id.eq_any(
+                    CrateOwner::by_owner_kind(OwnerKind::Organization)
+                        .select(crate_owners::crate_id)
+                        .filter(crate_owners::owner_id.eq(organization_id)),
+                ),
+            );
         } else if params.get("following").is_some() {
-            // Calculating the total number of results with filters is not supported yet.
-            supports_seek = false;
+            filtered = true;
 
             let user_id = AuthCheck::default().check(&req, conn)?.user_id();
 
@@ -200,8 +238,7 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
                 ),
             );
         } else if params.get("ids[]").is_some() {
-            // Calculating the total number of results with filters is not supported yet.
-            supports_seek = false;
+            filtered = true;
 
             let query_bytes = req.uri.query().unwrap_or("").as_bytes();
             let ids: Vec<_> = url::form_urlencoded::parse(query_bytes)
@@ -213,8 +250,7 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
         }
 
         if !include_yanked {
-            // Calculating the total number of results with filters is not supported yet.
-            supports_seek = false;
+            filtered = true;
 
             query = query.filter(exists(
                 versions::table
@@ -223,82 +259,125 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
             ));
         }
 
-        // Any sort other than 'relevance' (default) would ignore exact crate name matches
-        if sort == Some("downloads") {
-            // Custom sorting is not supported yet with seek.
-            supports_seek = false;
+        if let Some(license) = params.get("license") {
+            filtered = true;
+
+            let accepted = AcceptedLicenses::parse(license)?;
+
+            // SPDX expressions can not be evaluated by the database, so the
+            // accepted ones are picked from all distinct expressions in use.
+            let licenses = match app.licenses_cacher.get(&()) {
+                Some(licenses) => licenses,
+                None => {
+                    let licenses: Vec<Option<String>> = versions::table
+                        .select(versions::license)
+                        .distinct()
+                        .load(conn)?;
+                    let licenses = Arc::new(licenses.into_iter().flatten().collect::<Vec<_>>());
+                    app.licenses_cacher.blocking().insert((), licenses.clone());
+                    licenses
+                }
+            };
+            let licenses = licenses
+                .iter()
+                .filter(|license| accepted.accepts(license))
+                .cloned()
+                .collect::<Vec<_>>();
 
-            query = query.order(crates::downloads.desc())
-        } else if sort == Some("recent-downloads") {
-            // Custom sorting is not supported yet with seek.
-            supports_seek = false;
+            // Only the license of the newest non-yanked version is considered
+            query = query.filter(
+                sql::<Bool>("(SELECT versions.license FROM versions ")
+                    .sql("WHERE versions.crate_id = crates.id AND NOT versions.yanked ")
+                    .sql("ORDER BY versions.id DESC LIMIT 1) = ANY(")
+                    .bind::<Array<Text>, _>(licenses)
+                    .sql(")"),
+            );
+        }
 
-            query = query.order(recent_crate_downloads::downloads.desc().nulls_last())
-        } else if sort == Some("recent-updates") {
-            // Custom sorting is not supported yet with seek.
-            supports_seek = false;
+        // `?msrv<=1.70` is split at the `=`, so it ends up as a `msrv<` key.
+        if let Some(msrv) = params.get("msrv<").or_else(|| params.get("msrv")) {
+            filtered = true;
 
-            query = query.order(crates::updated_at.desc());
-        } else if sort == Some("new") {
-            // Custom sorting is not supported yet with seek.
-            supports_seek = false;
+            let msrv = parse_msrv(msrv)?;
 
-            query = query.order(crates::created_at.desc());
-        } else {
-            query = query.then_order_by(crates::name.asc())
+            // Only the newest non-yanked version is considered. Crates that
+            // don't specify a `rust-version` are assumed to be compatible,
+            // like cargo does.
+            query = query.filter(
+                sql::<Bool>("COALESCE((SELECT string_to_array(versions.rust_version, '.')::int[] ")
+                    .sql("FROM versions WHERE versions.crate_id = crates.id AND NOT versions.yanked ")
+                    .sql("ORDER BY versions.id DESC LIMIT 1) <= ")
+                    .bind::<Array<Integer>, _>(msrv)
+                    .sql(", true)"),
+            );
         }
 
+        // Any sort other than 'relevance' (default) would ignore exact crate name matches. The name is
+        // used as a tie-breaker so that the order is stable for seek-based pagination.
+        query = match order {
+            Sort::Downloads => query.order((crates::downloads.desc(), crates::name.asc())),
+            Sort::RecentDownloads => query.order((
+                recent_crate_downloads::downloads.desc().nulls_last(),
+                crates::name.asc(),
+            )),
+            Sort::RecentUpdates => query.order((crates::updated_at.desc(), crates::name.asc())),
+            Sort::New => query.order((crates::created_at.desc(), crates::name.asc())),
+            Sort::Relevance => query.then_order_by(crates::name.asc()),
+        };
+
         let pagination: PaginationOptions = PaginationOptions::builder()
             .limit_page_numbers()
-            .enable_seek(supports_seek)
+            .enable_seek(true)
             .gather(&req)?;
+        let per_page = pagination.per_page;
 
         let (explicit_page, seek) = match pagination.page.clone() {
             Page::Numeric(_) => (true, None),
-            Page::Seek(s) => (false, Some(s.decode::<i32>()?)),
+            Page::Seek(s) => (false, Some(s.decode::<SeekKey>()?)),
             Page::Unspecified => (false, None),
         };
 
         // To avoid breaking existing users, seek-based pagination is only used if an explicit page has
         // not been provided. This way clients relying on meta.next_page will use the faster seek-based
         // paginations, while client hardcoding pages handling will use the slower offset-based code.
-        let (total, next_page, prev_page, data, conn) = if supports_seek && !explicit_page {
-            // Equivalent of:
-            // `WHERE name > (SELECT name FROM crates WHERE id = $1) LIMIT $2`
-            query = query.limit(pagination.per_page);
-            if let Some(seek) = seek {
-                let crate_name: String = crates::table
-                    .find(seek)
-                    .select(crates::name)
-                    .get_result(conn)?;
-                query = query.filter(crates::name.gt(crate_name));
-            }
-
-            // This does a full index-only scan over the crates table to gather how many crates were
-            // published. Unfortunately on PostgreSQL counting the rows in a table requires scanning
-            // the table, and the `total` field is part of the stable registries API.
-            //
-            // If this becomes a problem in the future the crates count could be denormalized, at least
-            // for the filterless happy path.
-            let total: i64 = crates::table.count().get_result(conn)?;
-
-            let results: Vec<(Crate, bool, Option<i64>)> = query.load(conn)?;
+        let (total, next_page, prev_page, data, conn) = if !explicit_page {
+            let (total, data): (i64, Vec<Row>) = if let Some(seek) = seek {
+                // Counting the results again would be as slow as offset-based pagination, so the
+                // total is passed along from the first page.
+                let total = seek.total;
+                query = query.filter(seek.after(order, exact_match, relevance.as_deref())?);
+                (total, query.limit(per_page).load(conn)?)
+            } else if filtered {
+                let data: Paginated<Row> = query.pages_pagination(pagination).load(conn)?;
+                (data.total(), data.into_iter().collect())
+            } else {
+                // This does a full index-only scan over the crates table to gather how many crates
+                // were published. Unfortunately on PostgreSQL counting the rows in a table requires
+                // scanning the table, and the `total` field is part of the stable registries API.
+                //
+                // If this becomes a problem in the future the crates count could be denormalized, at
+                // least for the filterless happy path.
+                let total: i64 = crates::table.count().get_result(conn)?;
+                (total, query.limit(per_page).load(conn)?)
+            };
 
-            let next_page = if let Some(last) = results.last() {
+            let next_page = if let Some(last) = data.last() {
+                let seek = SeekKey {
+                    total,
+                    value: SortValue::new(order, last),
+                    name: last.0.name.clone(),
+                };
                 let mut params = IndexMap::new();
-                params.insert(
-                    "seek".into(),
-                    crate::controllers::helpers::pagination::encode_seek(last.0.id)?,
-                );
+                params.insert("seek".into(), encode_seek(seek)?);
                 Some(req.query_with_params(params))
             } else {
                 None
             };
 
-            (total, next_page, None, results, conn)
+            (total, next_page, None, data, conn)
         } else {
             let query = query.pages_pagination(pagination);
-            let data: Paginated<(Crate, bool, Option<i64>)> = query.load(conn)?;
+            let data: Paginated<Row> = query.load(conn)?;
             (
                 data.total(),
                 data.next_page_params().map(|p| req.query_with_params(p)),
@@ -308,12 +387,21 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
             )
         };
 
-        let perfect_matches = data.iter().map(|&(_, b, _)| b).collect::<Vec<_>>();
+        // Offer a spelling suggestion if a (probably misspelled) query
+        // didn't return anything useful.
+        let did_you_mean = match &q_string {
+            Some(q_string) if total < DID_YOU_MEAN_THRESHOLD => {
+                SearchTerm::did_you_mean(conn, q_string)?
+            }
+            _ => None,
+        };
+
+        let perfect_matches = data.iter().map(|&(_, b, _, _)| b).collect::<Vec<_>>();
         let recent_downloads = data
             .iter()
-            .map(|&(_, _, s)| s.unwrap_or(0))
+            .map(|&(_, _, s, _)| s.unwrap_or(0))
             .collect::<Vec<_>>();
-        let crates = data.into_iter().map(|(c, _, _)| c).collect::<Vec<_>>();
+        let crates = data.into_iter().map(|(c, _, _, _)| c).collect::<Vec<_>>();
 
         let versions: Vec<Version> = crates.versions().load(conn)?;
         let versions = versions
@@ -344,10 +432,178 @@ pub async fn search(app: AppState, req: Parts) -> AppResult<Json<Value>> {
                 "total": total,
                 "next_page": next_page,
                 "prev_page": prev_page,
+                "did_you_mean": did_you_mean,
             },
         })))
     })
     .await
 }
 
+/// A crate in the results, whether its name matches the query exactly, its recent downloads and its
+/// relevance for the query.
+type Row = (Crate, bool, Option<i64>, f64);
+
+/// The orders of the results selected by the `sort` query parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sort {
+    /// Exact name matches and the most relevant crates first when searching, alphabetically
+    /// otherwise.
+    Relevance,
+    Downloads,
+    RecentDownloads,
+    RecentUpdates,
+    New,
+}
+
+impl Sort {
+    fn from_param(sort: Option<&str>) -> Self {
+        match sort {
+            Some("downloads") => Self::Downloads,
+            Some("recent-downloads") => Self::RecentDownloads,
+            Some("recent-updates") => Self::RecentUpdates,
+            Some("new") => Self::New,
+            _ => Self::Relevance,
+        }
+    }
+}
+
+/// The position of the last crate of a page, which is passed as the `seek`
+/// parameter of the next page.
+#[derive(Debug, Serialize, Deserialize)]
+struct SeekKey {
+    /// The total number of results, as counted for the first page.
+    total: i64,
+    value: SortValue,
+    name: String,
+}
+
+/// The value a crate is sorted by before its name.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SortValue {
+    /// The relevance is stored as the bits of the float, since parsing it
+    /// from JSON may not give back exactly the same value, which would make
+    /// the crate its own successor.
+    Relevance {
+        exact_match: bool,
+        relevance_bits: u64,
+    },
+    Downloads(i32),
+    RecentDownloads(Option<i64>),
+    RecentUpdates(NaiveDateTime),
+    New(NaiveDateTime),
+}
+
+impl SortValue {
+    fn new(sort: Sort, (krate, exact_match, recent_downloads, relevance): &Row) -> Self {
+        match sort {
+            Sort::Relevance => Self::Relevance {
+                exact_match: *exact_match,
+                relevance_bits: relevance.to_bits(),
+            },
+            Sort::Downloads => Self::Downloads(krate.downloads),
+            Sort::RecentDownloads => Self::RecentDownloads(*recent_downloads),
+            Sort::RecentUpdates => Self::RecentUpdates(krate.updated_at),
+            Sort::New => Self::New(krate.created_at),
+        }
+    }
+}
+
+impl SeekKey {
+    /// Returns the filter for the crates that are sorted after this key.
+    ///
+    /// `exact_match` and `relevance` are only given when searching by a query
+    /// string, in which case the results are sorted by them first.
+    fn after<'a>(
+        self,
+        sort: Sort,
+        exact_match: Option<&'a str>,
+        relevance: Option<&dyn Fn() -> SearchExpression<'a, Double>>,
+    ) -> AppResult<SearchExpression<'a, Bool>> {
+        let mut after: SearchExpression<'a, Bool> = Box::new(crates::name.gt(self.name));
+
+        match (sort, self.value) {
+            (
+                Sort::Relevance,
+                SortValue::Relevance {
+                    exact_match: is_exact,
+                    relevance_bits,
+                },
+            ) => {
+                if let Some(relevance) = relevance {
+                    let value = f64::from_bits(relevance_bits);
+                    after = Box::new(relevance().lt(value).or(relevance().eq(value).and(after)));
+                }
+                if let Some(q_string) = exact_match {
+                    after = if is_exact {
+                        Box::new(
+                            not(Crate::with_name(q_string))
+                                .or(Crate::with_name(q_string).and(after)),
+                        )
+                    } else {
+                        Box::new(not(Crate::with_name(q_string)).and(after))
+                    };
+                }
+            }
+            (Sort::Downloads, SortValue::Downloads(downloads)) => {
+                after = Box::new(
+                    crates::downloads
+                        .lt(downloads)
+                        .or(crates::downloads.eq(downloads).and(after)),
+                );
+            }
+            // Crates without recent downloads are sorted last
+            (Sort::RecentDownloads, SortValue::RecentDownloads(Some(downloads))) => {
+                after = Box::new(
+                    sql::<Bool>("(recent_crate_downloads.downloads < ")
+                        .bind::<BigInt, _>(downloads)
+                        .sql(" OR recent_crate_downloads.downloads IS NULL)")
+                        .or(sql::<Bool>("recent_crate_downloads.downloads = ")
+                            .bind::<BigInt, _>(downloads)
+                            .and(after)),
+                );
+            }
+            (Sort::RecentDownloads, SortValue::RecentDownloads(None)) => {
+                after =
+                    Box::new(sql::<Bool>("recent_crate_downloads.downloads IS NULL").and(after));
+            }
+            (Sort::RecentUpdates, SortValue::RecentUpdates(updated_at)) => {
+                after = Box::new(
+                    crates::updated_at
+                        .lt(updated_at)
+                        .or(crates::updated_at.eq(updated_at).and(after)),
+                );
+            }
+            (Sort::New, SortValue::New(created_at)) => {
+                after = Box::new(
+                    crates::created_at
+                        .lt(created_at)
+                        .or(crates::created_at.eq(created_at).and(after)),
+                );
+            }
+            _ => {
+                return Err(bad_request(
+                    "the seek parameter does not match the sort order",
+                ))
+            }
+        }
+
+        Ok(after)
+    }
+}
+
 diesel::infix_operator!(Contains, "@>");
+
+/// Parses a Rust toolchain version like `1.70` into its `[major, minor, patch]`
+/// components, with missing components defaulting to zero.
+fn parse_msrv(msrv: &str) -> AppResult<Vec<i32>> {
+    let mut components = msrv
+        .split('.')
+        .map(|component| component.parse::<i32>().ok().filter(|c| *c >= 0))
+        .collect::<Option<Vec<_>>>()
+        .filter(|components| components.len() <= 3)
+        .ok_or_else(|| bad_request(&format_args!("invalid Rust version: {msrv}")))?;
+
+    components.resize(3, 0);
+    Ok(components)
+}
@@ -0,0 +1,141 @@
+//! Endpoints for staged publishes.
+//!
+//! A staged publish is validated like a regular `PUT /crates/new` request,
+//! but it is only stored and does not show up anywhere else (e.g. in the
+//! index) until it is promoted. This allows the crates of a workspace to be
+//! uploaded and checked before any of them goes live:
+//!
+//! 1. `PUT /crates/new/staged` stages a publish request. The body uses the
+//!    same format as the body of a regular `PUT /crates/new` request.
+//! 2. `POST /crates/new/staged/:staged_id/promote` publishes the staged
+//!    request exactly like `PUT /crates/new`.
+//!
+//! `GET /crates/new/staged` lists the staged publishes of the current user and
+//! `DELETE /crates/new/staged/:staged_id` discards one of them. Checks that
+//! depend on other crates, like the existence of dependencies, only happen
+//! when a staged publish is promoted, so that crates of a workspace can
+//! depend on each other.
+
+use diesel::dsl::exists;
+
+use super::publish::{parse_body, publish_staged, verify_tarball, MISSING_RIGHTS_ERROR_MESSAGE};
+use super::upload::authenticate;
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::models::{Crate, NewStagedPublish, ReservedCratePrefix, Rights, StagedPublish};
+use crate::schema::versions;
+use crate::util::errors::{conflict, UploadTooLarge};
+use crate::util::Maximums;
+use crate::views::{EncodableStagedPublish, GoodCrate};
+
+/// Handles the `GET /crates/new/staged` route.
+pub async fn list(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let user_id = AuthCheck::default().check(&req, conn)?.user_id();
+
+        let staged = StagedPublish::for_user(conn, user_id)?
+            .into_iter()
+            .map(EncodableStagedPublish::from)
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "staged_publishes": staged })))
+    })
+    .await
+}
+
+/// Handles the `PUT /crates/new/staged` route.
+pub async fn stage(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let (req, bytes) = req.0.into_parts();
+        let (new_crate, tarball_bytes, _signature) =
+            parse_body(bytes.clone(), &req, &app.config.metadata_limits)?;
+        let name = &*new_crate.name;
+        let vers = new_crate.vers.to_string();
+
+        let conn = &mut *app.db_write()?;
+        let auth = authenticate(&req, conn, name)?;
+        let user = auth.user();
+
+        let existing_crate = Crate::by_name(name).first::<Crate>(conn).optional()?;
+        match &existing_crate {
+            Some(krate) => {
+                let owners = krate.owners(conn)?;
+                if user.rights(&app, conn, &owners)? < Rights::Publish {
+                    return Err(cargo_err(MISSING_RIGHTS_ERROR_MESSAGE));
+                }
+
+                let already_uploaded = versions::table
+                    .filter(versions::crate_id.eq(krate.id))
+                    .filter(versions::num.eq(&vers));
+                if diesel::select(exists(already_uploaded)).get_result(conn)? {
+                    return Err(cargo_err(&format_args!(
+                        "crate version `{vers}` is already uploaded"
+                    )));
+                }
+            }
+            None => ReservedCratePrefix::ensure_can_publish(conn, &app, user, name)?,
+        }
+
+        let maximums = Maximums::new(
+            existing_crate.and_then(|krate| krate.max_upload_size),
+            app.config.max_upload_size,
+            app.config.max_unpack_size,
+        );
+
+        if tarball_bytes.len() as u64 > maximums.max_upload_size {
+            let max_upload_size = maximums.max_upload_size;
+            return Err(Box::new(UploadTooLarge { max_upload_size }));
+        }
+
+        let pkg_name = format!("{name}-{vers}");
+        verify_tarball(&pkg_name, &tarball_bytes, maximums.max_unpack_size)?;
+
+        let staged = NewStagedPublish {
+            user_id: user.id,
+            crate_name: name,
+            version: &vers,
+            body: &bytes,
+        }
+        .create(conn)?
+        .ok_or_else(|| conflict(&format_args!("`{name}@{vers}` is already staged")))?;
+
+        Ok(Json(
+            json!({ "staged_publish": EncodableStagedPublish::from(staged) }),
+        ))
+    })
+    .await
+}
+
+/// Handles the `POST /crates/new/staged/:staged_id/promote` route.
+pub async fn promote(
+    app: AppState,
+    Path(staged_id): Path<i32>,
+    req: Parts,
+) -> AppResult<Json<GoodCrate>> {
+    let (staged, req) = conduit_compat({
+        let app = app.clone();
+        move || {
+            let conn = &mut *app.db_write()?;
+            let user_id = AuthCheck::default().check(&req, conn)?.user_id();
+            let staged = StagedPublish::find(conn, staged_id, user_id)?;
+            Ok((staged, req))
+        }
+    })
+    .await?;
+
+    // The staged request is validated again, since other crates or the
+    // permissions of the user may have changed in the meantime
+    publish_staged(app, req, staged).await
+}
+
+/// Handles the `DELETE /crates/new/staged/:staged_id` route.
+pub async fn discard(app: AppState, Path(staged_id): Path<i32>, req: Parts) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let user_id = AuthCheck::default().check(&req, conn)?.user_id();
+        StagedPublish::find(conn, staged_id, user_id)?.delete(conn)?;
+        ok_true()
+    })
+    .await
+}
@@ -0,0 +1,94 @@
+//! Endpoint for suggesting crate names while typing a search query
+
+use std::sync::Arc;
+
+use crate::controllers::frontend_prelude::*;
+use crate::schema::crates;
+use crate::sql::canon_crate_name;
+use crate::views::EncodableCrateSuggestion;
+
+const DEFAULT_LIMIT: i64 = 10;
+const MAX_LIMIT: i64 = 25;
+
+/// Suggestions only change when crates are published or downloaded, so they
+/// can also be cached by the CDN and the browser for a while.
+const CACHE_CONTROL: &str = "public,max-age=300";
+
+/// Handles the `GET /crate_suggestions` route.
+///
+/// Returns the names and download counts of the most downloaded crates whose
+/// names start with the query, with an exact match always coming first. This
+/// is much cheaper than a full search, which is too slow to run on every
+/// keystroke in the search box.
+pub async fn suggest(app: AppState, req: Parts) -> AppResult<Response> {
+    let params = req.query();
+
+    let limit = params
+        .get("limit")
+        .map(|limit| limit.parse().map_err(|e| bad_request(&e)))
+        .transpose()?
+        .unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(bad_request(&format_args!(
+            "limit must be between 1 and {MAX_LIMIT}"
+        )));
+    }
+
+    // Suggestions are cached under the canonical spelling of the query, the
+    // same way crate names are compared.
+    let prefix = params
+        .get("q")
+        .map(|q| q.trim().to_lowercase().replace('-', "_"))
+        .unwrap_or_default();
+
+    let suggestions = if prefix.is_empty() {
+        Arc::default()
+    } else if let Some(suggestions) = app.suggestions_cacher.get(&(prefix.clone(), limit)) {
+        suggestions
+    } else {
+        conduit_compat(move || {
+            let conn = &mut *app.db_read()?;
+
+            let pattern = format!("{}%", escape_like(&prefix));
+            let suggestions: Vec<(String, i32)> = crates::table
+                .select((crates::name, crates::downloads))
+                .filter(canon_crate_name(crates::name).like(pattern))
+                .order((
+                    canon_crate_name(crates::name).eq(prefix.as_str()).desc(),
+                    crates::downloads.desc(),
+                    crates::name.asc(),
+                ))
+                .limit(limit)
+                .load(conn)?;
+
+            let suggestions = Arc::new(
+                suggestions
+                    .into_iter()
+                    .map(|(name, downloads)| EncodableCrateSuggestion {
+                        name,
+                        downloads: downloads.into(),
+                    })
+                    .collect::<Vec<_>>(),
+            );
+
+            app.suggestions_cacher
+                .blocking()
+                .insert((prefix, limit), suggestions.clone());
+
+            Ok(suggestions)
+        })
+        .await?
+    };
+
+    let body = json!({ "suggestions": &*suggestions });
+    Ok(([(header::CACHE_CONTROL, CACHE_CONTROL)], Json(body)).into_response())
+}
+
+/// Escapes the wildcards of a `LIKE` pattern, so that e.g. the `_` of
+/// canonical crate names only matches itself.
+fn escape_like(pattern: &str) -> String {
+    pattern
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
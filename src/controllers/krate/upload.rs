@@ -0,0 +1,257 @@
+//! Endpoints for uploading the body of a publish request in multiple chunks.
+//!
+//! This allows clients to resume the upload of large crates after a
+//! connection failure instead of starting from zero. The protocol consists of
+//! three steps:
+//!
+//! 1. `POST /crates/new/uploads` initiates an upload for the given crate name,
+//!    with the size and the SHA-256 checksum of the complete request body.
+//! 2. `PUT /crates/new/uploads/:upload_id` appends a chunk. The
+//!    `Upload-Offset` header must match the number of bytes received so far,
+//!    and the `Upload-Checksum` header must contain the SHA-256 hex digest of
+//!    the chunk. `GET` on the same route returns the current offset, so that
+//!    clients know where to resume after a failure.
+//! 3. `POST /crates/new/uploads/:upload_id/complete` verifies the checksum of
+//!    the complete body and publishes it exactly like `PUT /crates/new`. The
+//!    upload is removed afterwards, even if publishing fails.
+//!
+//! The body that is uploaded in chunks uses the same format as the body of a
+//! regular `PUT /crates/new` request. The chunks are stored as the parts of a
+//! multipart upload in the storage backend, so all chunks but the last one
+//! must be at least as large as the backend requires, 5 MiB for S3.
+
+use axum::body::Bytes;
+use hex::ToHex;
+use sha2::{Digest, Sha256};
+
+use super::publish::publish_body;
+use crate::auth::{AuthCheck, Authentication};
+use crate::controllers::frontend_prelude::*;
+use crate::controllers::util::RequestPartsExt;
+use crate::models::token::EndpointScope;
+use crate::models::{NewPublishUpload, PublishUpload};
+use crate::router::MAX_PUBLISH_CONTENT_LENGTH;
+use crate::uploaders::Uploader;
+use crate::util::errors::{conflict, not_found};
+use crate::views::{EncodablePublishUpload, GoodCrate};
+
+/// The maximum size of a single chunk.
+pub const MAX_CHUNK_SIZE: usize = 8 * 1024 * 1024; // 8 MB
+
+const UPLOAD_OFFSET: &str = "Upload-Offset";
+const UPLOAD_CHECKSUM: &str = "Upload-Checksum";
+
+/// Handles the `POST /crates/new/uploads` route.
+pub async fn initiate(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct NewUpload {
+            name: String,
+            size: u64,
+            cksum: String,
+        }
+
+        let new: NewUpload = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid upload request: {e}")))?;
+
+        if new.size > MAX_PUBLISH_CONTENT_LENGTH as u64 {
+            return Err(bad_request(&format_args!(
+                "max upload size is: {MAX_PUBLISH_CONTENT_LENGTH}"
+            )));
+        }
+
+        if new.cksum.len() != 64 || !new.cksum.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(bad_request("cksum must be a SHA-256 hex digest"));
+        }
+
+        let conn = &mut *app.db_write()?;
+        let auth = authenticate(&req, conn, &new.name)?;
+
+        let storage_path = Uploader::publish_upload_path();
+        let storage_upload_id = app
+            .config
+            .uploader()
+            .start_publish_upload(app.http_client(), &storage_path)?;
+
+        let upload = NewPublishUpload {
+            user_id: auth.user_id(),
+            crate_name: &new.name,
+            size: new.size as i64,
+            cksum: &new.cksum.to_lowercase(),
+            storage_path: &storage_path,
+            storage_upload_id: &storage_upload_id,
+        }
+        .create(conn)?;
+
+        Ok(Json(
+            json!({ "upload": EncodablePublishUpload::from(upload) }),
+        ))
+    })
+    .await
+}
+
+/// Handles the `GET /crates/new/uploads/:upload_id` route.
+pub async fn show(app: AppState, Path(upload_id): Path<i32>, req: Parts) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let upload = find_upload(&req, conn, upload_id)?;
+        let offset = upload.received;
+
+        let json = json!({ "upload": EncodablePublishUpload::from(upload) });
+        Ok(([(UPLOAD_OFFSET, offset.to_string())], Json(json)).into_response())
+    })
+    .await
+}
+
+/// Handles the `PUT /crates/new/uploads/:upload_id` route.
+pub async fn append(
+    app: AppState,
+    Path(upload_id): Path<i32>,
+    req: BytesRequest,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let offset = req
+            .headers()
+            .get(UPLOAD_OFFSET)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<i64>().ok())
+            .ok_or_else(|| bad_request("missing or invalid Upload-Offset header"))?;
+
+        let checksum = req
+            .headers()
+            .get(UPLOAD_CHECKSUM)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| bad_request("missing Upload-Checksum header"))?;
+
+        let chunk = req.body();
+        if chunk.is_empty() {
+            return Err(bad_request("chunk must not be empty"));
+        }
+
+        let actual_checksum: String = Sha256::digest(chunk).encode_hex();
+        if !actual_checksum.eq_ignore_ascii_case(checksum) {
+            return Err(bad_request("chunk checksum does not match Upload-Checksum"));
+        }
+
+        let conn = &mut *app.db_write()?;
+        let upload = find_upload(&req, conn, upload_id)?;
+
+        let len = chunk.len() as i64;
+        if offset + len > upload.size {
+            return Err(bad_request("chunk exceeds the announced upload size"));
+        }
+
+        let uploader = app.config.uploader();
+        let min_chunk_size = uploader.min_publish_chunk_size();
+        if offset + len < upload.size && chunk.len() < min_chunk_size {
+            return Err(bad_request(&format_args!(
+                "all chunks but the last one must be at least {min_chunk_size} bytes"
+            )));
+        }
+
+        let received = conn.transaction(|conn| {
+            let received = upload.lock_received(conn)?;
+            if received != offset {
+                return Err(conflict(&format_args!(
+                    "Upload-Offset does not match the {received} bytes received so far"
+                )));
+            }
+
+            let part_number = upload.parts(conn)?.len() as i32 + 1;
+            let tag = uploader.upload_publish_chunk(
+                app.http_client(),
+                &upload,
+                part_number,
+                chunk.to_vec(),
+            )?;
+            Ok(upload.add_part(conn, part_number, offset, len, &tag)?)
+        })?;
+
+        Ok((
+            [(UPLOAD_OFFSET, received.to_string())],
+            StatusCode::NO_CONTENT,
+        )
+            .into_response())
+    })
+    .await
+}
+
+/// Handles the `POST /crates/new/uploads/:upload_id/complete` route.
+pub async fn complete(
+    app: AppState,
+    Path(upload_id): Path<i32>,
+    req: Parts,
+) -> AppResult<Json<GoodCrate>> {
+    let (req, bytes) = conduit_compat({
+        let app = app.clone();
+        move || {
+            let conn = &mut *app.db_write()?;
+            let upload = find_upload(&req, conn, upload_id)?;
+
+            if upload.received != upload.size {
+                return Err(bad_request(&format_args!(
+                    "upload is incomplete, received {} of {} bytes",
+                    upload.received, upload.size
+                )));
+            }
+
+            let parts = upload.parts(conn)?;
+            let bytes = app.config.uploader().complete_publish_upload(
+                app.http_client(),
+                &upload,
+                &parts,
+            )?;
+
+            // Assembling the body consumes the stored chunks
+            upload.delete(conn)?;
+
+            let cksum: String = Sha256::digest(&bytes).encode_hex();
+            if cksum != upload.cksum {
+                return Err(bad_request("upload checksum does not match"));
+            }
+
+            Ok((req, Bytes::from(bytes)))
+        }
+    })
+    .await?;
+
+    // The authentication and crate scope checks are repeated by the regular publish logic
+    publish_body(app, req, bytes).await
+}
+
+/// Authenticates the user for a new upload of the given crate.
+///
+/// Since the upload is not yet associated with a crate version, API tokens
+/// with either the `publish-new` or the `publish-update` scope are accepted.
+/// The exact scope is checked again when the upload is completed.
+pub(super) fn authenticate(
+    req: &impl RequestPartsExt,
+    conn: &mut PgConnection,
+    crate_name: &str,
+) -> AppResult<Authentication> {
+    let auth = AuthCheck::default().for_crate(crate_name);
+    auth.with_endpoint_scope(EndpointScope::PublishNew)
+        .check(req, conn)
+        .or_else(|_| {
+            auth.with_endpoint_scope(EndpointScope::PublishUpdate)
+                .check(req, conn)
+        })
+}
+
+/// Loads the upload and authenticates the user who initiated it.
+///
+/// The same API token scopes are accepted as when the upload was initiated,
+/// so that uploads which were started with a scoped token can be continued
+/// with it.
+fn find_upload(
+    req: &impl RequestPartsExt,
+    conn: &mut PgConnection,
+    upload_id: i32,
+) -> AppResult<PublishUpload> {
+    let upload = PublishUpload::find(conn, upload_id)?;
+    let auth = authenticate(req, conn, &upload.crate_name)?;
+    if auth.user_id() != upload.user_id {
+        return Err(not_found());
+    }
+    Ok(upload)
+}
@@ -0,0 +1,157 @@
+//! Endpoints for managing the webhooks of a crate.
+//!
+//! Webhooks are HTTPS endpoints that receive a signed JSON payload whenever a
+//! version of the crate is published, yanked or unyanked, and whenever an owner
+//! is added or removed. The secret is only shown once, when the webhook is
+//! created, and only its SHA-256 hash is stored. The signatures are therefore
+//! keyed with the SHA-256 hash of the secret, which receivers have to compute
+//! as well to verify them. URLs that point to private or
+//! local addresses are rejected, see `util::webhooks`. See `worker::trigger_webhooks()`
+//! for the format of the payloads.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::models::{Crate, NewWebhook, Rights, User, Webhook, WebhookDelivery};
+use crate::util::webhooks::validate_url;
+use crate::views::{EncodableWebhook, EncodableWebhookDelivery, EncodableWebhookWithSecret};
+
+const MAX_WEBHOOKS_PER_CRATE: i64 = 10;
+
+/// The number of deliveries that are returned by the delivery log.
+const MAX_DELIVERIES: i64 = 100;
+
+/// Handles the `GET /crates/:crate_id/webhooks` route.
+pub async fn list(
+    app: AppState,
+    Path(crate_name): Path<String>,
+    req: Parts,
+) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let auth = AuthCheck::default()
+            .for_crate(&crate_name)
+            .check(&req, conn)?;
+        let krate = find_crate(&app, conn, auth.user(), &crate_name, Rights::Publish)?;
+
+        let webhooks = Webhook::for_crate(conn, krate.id)?
+            .into_iter()
+            .map(EncodableWebhook::from)
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "webhooks": webhooks })))
+    })
+    .await
+}
+
+/// Handles the `POST /crates/:crate_id/webhooks` route.
+pub async fn create(
+    app: AppState,
+    Path(crate_name): Path<String>,
+    req: BytesRequest,
+) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct NewWebhookRequest {
+            webhook: NewWebhookUrl,
+        }
+
+        #[derive(Deserialize)]
+        struct NewWebhookUrl {
+            url: String,
+        }
+
+        let new: NewWebhookRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid new webhook request: {e}")))?;
+
+        let url = new.webhook.url;
+        validate_url(&url).map_err(|e| bad_request(&e))?;
+
+        let conn = &mut *app.db_write()?;
+        // The secret of the new webhook must not be handed out to API tokens
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+        let krate = find_crate(&app, conn, user, &crate_name, Rights::Full)?;
+
+        let count: i64 = Webhook::belonging_to(&krate).count().get_result(conn)?;
+        if count >= MAX_WEBHOOKS_PER_CRATE {
+            return Err(bad_request(&format_args!(
+                "maximum webhooks per crate is: {MAX_WEBHOOKS_PER_CRATE}"
+            )));
+        }
+
+        let webhook = NewWebhook {
+            crate_id: krate.id,
+            url: &url,
+            created_by: user.id,
+        }
+        .create(conn)?;
+
+        Ok(Json(
+            json!({ "webhook": EncodableWebhookWithSecret::from(webhook) }),
+        ))
+    })
+    .await
+}
+
+/// Handles the `DELETE /crates/:crate_id/webhooks/:webhook_id` route.
+pub async fn delete(
+    app: AppState,
+    Path((crate_name, webhook_id)): Path<(String, i32)>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let krate = find_crate(&app, conn, auth.user(), &crate_name, Rights::Full)?;
+
+        Webhook::find(conn, webhook_id, krate.id)?.delete(conn)?;
+
+        ok_true()
+    })
+    .await
+}
+
+/// Handles the `GET /crates/:crate_id/webhooks/:webhook_id/deliveries` route.
+pub async fn deliveries(
+    app: AppState,
+    Path((crate_name, webhook_id)): Path<(String, i32)>,
+    req: Parts,
+) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let auth = AuthCheck::default()
+            .for_crate(&crate_name)
+            .check(&req, conn)?;
+        let krate = find_crate(&app, conn, auth.user(), &crate_name, Rights::Publish)?;
+
+        let webhook = Webhook::find(conn, webhook_id, krate.id)?;
+        let deliveries = WebhookDelivery::recent(conn, webhook.id, MAX_DELIVERIES)?
+            .into_iter()
+            .map(EncodableWebhookDelivery::from)
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "deliveries": deliveries })))
+    })
+    .await
+}
+
+/// Loads the crate and checks that the user has at least the given rights.
+fn find_crate(
+    app: &AppState,
+    conn: &mut PgConnection,
+    user: &User,
+    crate_name: &str,
+    required: Rights,
+) -> AppResult<Crate> {
+    let krate: Crate = Crate::by_name(crate_name).first(conn)?;
+    let owners = krate.owners(conn)?;
+    if user.rights(app, conn, &owners)? < required {
+        let message = match required {
+            Rights::Full => "only owners have permission to manage webhooks",
+            _ => "only owners have permission to view webhooks",
+        };
+        return Err(bad_request(message));
+    }
+
+    Ok(krate)
+}
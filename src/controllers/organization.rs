@@ -0,0 +1,342 @@
+//! Endpoints for organizations, which are accounts that are shared by several
+//! users and can own crates directly.
+//!
+//! Members have one of three roles: viewers can see the other members,
+//! publishers can publish the crates of the organization, and admins manage
+//! the members and the owners of its crates. New members are invited by an
+//! admin and have to accept the invitation, see `GET
+//! /me/organization_invitations`. Organizations are added as crate owners
+//! like teams, with `org:` followed by their name.
+//!
+//! Admins can delete an organization once it no longer owns any crates.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::controllers::helpers::second_factor::ensure_second_factor;
+use crate::models::{
+    NewOrganization, NotificationKind, Organization, OrganizationInvitation, OrganizationRole, User,
+};
+use crate::util::errors::not_found;
+use crate::views::{
+    EncodableOrganization, EncodableOrganizationInvitation, EncodableOrganizationMember,
+};
+
+const MAX_DESCRIPTION_LENGTH: usize = 256;
+
+/// Handles the `POST /organizations` route.
+///
+/// The user that creates the organization becomes its first admin.
+pub async fn create(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct NewOrganizationRequest {
+            organization: NewOrganizationQuery,
+        }
+
+        #[derive(Deserialize)]
+        struct NewOrganizationQuery {
+            name: String,
+            description: Option<String>,
+        }
+
+        let new: NewOrganizationRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid new organization request: {e}")))?;
+
+        let description = new
+            .organization
+            .description
+            .as_deref()
+            .map(str::trim)
+            .filter(|description| !description.is_empty());
+        if description.is_some_and(|description| description.len() > MAX_DESCRIPTION_LENGTH) {
+            return Err(bad_request(&format_args!(
+                "the description of an organization must not be longer than \
+                 {MAX_DESCRIPTION_LENGTH} characters"
+            )));
+        }
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+
+        let organization = NewOrganization::new(new.organization.name.trim(), description)
+            .create(conn, auth.user_id())?;
+
+        Ok(Json(
+            json!({ "organization": EncodableOrganization::from(organization) }),
+        ))
+    })
+    .await
+}
+
+/// Handles the `GET /organizations/:name` route.
+pub async fn show(app: AppState, Path(name): Path<String>) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read()?;
+        let organization = Organization::find_by_name(conn, &name)?;
+
+        Ok(Json(
+            json!({ "organization": EncodableOrganization::from(organization) }),
+        ))
+    })
+    .await
+}
+
+/// Handles the `DELETE /organizations/:name` route.
+///
+/// The organization has to be removed from the owners of its crates first,
+/// so that no crate is left without the owners that were managing it. Its
+/// members, invitations and tokens are deleted with it.
+pub async fn delete(app: AppState, Path(name): Path<String>, req: Parts) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user_id = auth.user_id();
+
+        let organization = Organization::find_by_name(conn, &name)?;
+        organization.ensure_role(
+            conn,
+            user_id,
+            OrganizationRole::Admin,
+            "only admins of an organization can delete it",
+        )?;
+
+        ensure_second_factor(&app, conn, &req, user_id)?;
+
+        organization.delete(conn)?;
+
+        ok_true()
+    })
+    .await
+}
+
+/// Handles the `GET /organizations/:name/members` route.
+///
+/// Only members of the organization can see its other members.
+pub async fn members(
+    app: AppState,
+    Path(name): Path<String>,
+    req: Parts,
+) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+
+        let organization = Organization::find_by_name(conn, &name)?;
+        organization.ensure_role(
+            conn,
+            auth.user_id(),
+            OrganizationRole::Viewer,
+            "only members of an organization can see its members",
+        )?;
+
+        let members = organization
+            .members(conn)?
+            .into_iter()
+            .map(|(user, role)| EncodableOrganizationMember::new(user, role))
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "members": members })))
+    })
+    .await
+}
+
+/// Handles the `PUT /organizations/:name/members` route.
+///
+/// Invites a user to the organization, or changes the role of a member.
+///
+/// ## Request Body Example
+///
+/// ```json
+/// {
+///     "member": {
+///         "login": "foo",
+///         "role": "publisher"
+///     }
+/// }
+/// ```
+pub async fn update_member(
+    app: AppState,
+    Path(name): Path<String>,
+    req: BytesRequest,
+) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct UpdateMemberRequest {
+            member: UpdateMemberQuery,
+        }
+
+        #[derive(Deserialize)]
+        struct UpdateMemberQuery {
+            login: String,
+            role: String,
+        }
+
+        let update: UpdateMemberRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid member request: {e}")))?;
+        let role = OrganizationRole::from_param(&update.member.role).ok_or_else(|| {
+            bad_request(&format_args!(
+                "invalid role `{}`, must be one of `viewer`, `publisher` or `admin`",
+                update.member.role
+            ))
+        })?;
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user_id = auth.user_id();
+
+        let organization = Organization::find_by_name(conn, &name)?;
+        organization.ensure_role(
+            conn,
+            user_id,
+            OrganizationRole::Admin,
+            "only admins of an organization can manage its members",
+        )?;
+
+        let member = find_user(conn, &update.member.login)?;
+
+        ensure_second_factor(&app, conn, &req, user_id)?;
+
+        let msg = if organization.role_of(conn, member.id)?.is_some() {
+            organization.update_member(conn, member.id, Some(role))?;
+            format!(
+                "user {} now has the {} role in organization {}",
+                member.gh_login,
+                role.as_str(),
+                organization.name
+            )
+        } else {
+            OrganizationInvitation::create(conn, organization.id, member.id, user_id, role)?;
+
+            if let Ok(Some(email)) = member.notification_email(conn, NotificationKind::OwnerInvites)
+            {
+                // Swallow any error, the user also sees the invitation in their
+                // list of pending invitations
+                let _ = app.emails.send_organization_invite(
+                    &email,
+                    &auth.user().gh_login,
+                    &organization.name,
+                    role.as_str(),
+                );
+            }
+
+            format!(
+                "user {} has been invited to join organization {}",
+                member.gh_login, organization.name
+            )
+        };
+
+        Ok(Json(json!({ "ok": true, "msg": msg })))
+    })
+    .await
+}
+
+/// Handles the `DELETE /organizations/:name/members/:login` route.
+///
+/// Admins can remove any member, while other members can only leave the
+/// organization themselves.
+pub async fn remove_member(
+    app: AppState,
+    Path((name, login)): Path<(String, String)>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user_id = auth.user_id();
+
+        let organization = Organization::find_by_name(conn, &name)?;
+        let member = find_user(conn, &login)?;
+        if member.id != user_id {
+            organization.ensure_role(
+                conn,
+                user_id,
+                OrganizationRole::Admin,
+                "only admins of an organization can manage its members",
+            )?;
+        }
+
+        ensure_second_factor(&app, conn, &req, user_id)?;
+
+        organization.update_member(conn, member.id, None)?;
+
+        ok_true()
+    })
+    .await
+}
+
+/// Handles the `GET /me/organization_invitations` route.
+pub async fn list_invitations(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+
+        let invitations = OrganizationInvitation::for_user(conn, auth.user_id())?
+            .into_iter()
+            .map(|(invitation, organization)| {
+                EncodableOrganizationInvitation::new(invitation, organization)
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "organization_invitations": invitations })))
+    })
+    .await
+}
+
+/// Handles the `PUT /me/organization_invitations/:organization_id` route.
+///
+/// ## Request Body Example
+///
+/// ```json
+/// {
+///     "organization_invitation": {
+///         "accepted": true
+///     }
+/// }
+/// ```
+pub async fn handle_invitation(
+    app: AppState,
+    Path(organization_id): Path<i32>,
+    req: BytesRequest,
+) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct HandleInvitationRequest {
+            organization_invitation: HandleInvitationQuery,
+        }
+
+        #[derive(Deserialize)]
+        struct HandleInvitationQuery {
+            accepted: bool,
+        }
+
+        let request: HandleInvitationRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid invitation request: {e}")))?;
+        let accepted = request.organization_invitation.accepted;
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+
+        let invitation = OrganizationInvitation::find(conn, organization_id, auth.user_id())
+            .optional()?
+            .ok_or_else(not_found)?;
+        if accepted {
+            invitation.accept(conn)?;
+        } else {
+            invitation.decline(conn)?;
+        }
+
+        Ok(Json(json!({
+            "organization_invitation": {
+                "organization_id": organization_id,
+                "accepted": accepted,
+            }
+        })))
+    })
+    .await
+}
+
+fn find_user(conn: &mut PgConnection, login: &str) -> AppResult<User> {
+    User::find_by_login(conn, login)
+        .optional()?
+        .ok_or_else(|| bad_request(&format_args!("could not find user with login `{login}`")))
+}
@@ -1,10 +1,12 @@
 use super::frontend_prelude::*;
 
-use crate::models::ApiToken;
+use crate::middleware::session::client_info;
+use crate::models::{ApiToken, Organization, OrganizationRole, SecurityEvent, SecurityLogEntry};
 use crate::schema::api_tokens;
 use crate::views::EncodableApiTokenWithToken;
 
 use crate::auth::AuthCheck;
+use crate::controllers::helpers::second_factor::ensure_sudo_mode;
 use crate::models::token::{CrateScope, EndpointScope};
 use axum::response::IntoResponse;
 use serde_json as json;
@@ -35,6 +37,8 @@ pub async fn new(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
             name: String,
             crate_scopes: Option<Vec<String>>,
             endpoint_scopes: Option<Vec<String>>,
+            /// The name of the organization that the token acts for
+            organization: Option<String>,
         }
 
         /// The incoming serialization format for the `ApiToken` model.
@@ -60,6 +64,8 @@ pub async fn new(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
             ));
         }
 
+        ensure_sudo_mode(&app, conn, &req, &auth)?;
+
         let user = auth.user();
 
         let max_token_per_user = 500;
@@ -94,8 +100,43 @@ pub async fn new(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
             .transpose()
             .map_err(|_err| bad_request("invalid endpoint scope"))?;
 
-        let api_token =
-            ApiToken::insert_with_scopes(conn, user.id, name, crate_scopes, endpoint_scopes)?;
+        let organization_id = match &new.api_token.organization {
+            Some(name) => {
+                let organization = Organization::find_by_name(conn, name)
+                    .optional()?
+                    .ok_or_else(|| {
+                        bad_request(&format_args!("could not find organization `{name}`"))
+                    })?;
+                organization.ensure_role(
+                    conn,
+                    user.id,
+                    OrganizationRole::Publisher,
+                    "only publishers and admins of an organization can create tokens for it",
+                )?;
+                Some(organization.id)
+            }
+            None => None,
+        };
+
+        let api_token = ApiToken::insert_for_organization(
+            conn,
+            user.id,
+            name,
+            crate_scopes,
+            endpoint_scopes,
+            organization_id,
+        )?;
+
+        let (ip_address, user_agent) = client_info(req.headers());
+        SecurityLogEntry::record(
+            conn,
+            user.id,
+            SecurityEvent::TokenCreated,
+            Some(name.as_str()),
+            ip_address,
+            user_agent,
+        )?;
+
         let api_token = EncodableApiTokenWithToken::from(api_token);
 
         Ok(Json(json!({ "api_token": api_token })))
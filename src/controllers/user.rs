@@ -1,3 +1,13 @@
+pub mod account_deletion;
+pub mod emails;
+pub mod follow_notifications;
+pub mod linked_accounts;
 pub mod me;
+pub mod notification_preferences;
 pub mod other;
+pub mod saved_searches;
+pub mod security_log;
 pub mod session;
+pub mod sessions;
+pub mod totp;
+pub mod webauthn;
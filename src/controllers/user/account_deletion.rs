@@ -0,0 +1,74 @@
+//! Endpoints for users to delete their own account.
+//!
+//! The deletion is requested with `POST /me/account_deletion`, which emails a
+//! confirmation link to the verified email address of the user. Confirming
+//! the deletion with `PUT /me/account_deletion/:token` locks the account right
+//! away, and `worker::delete_account()` then removes its personal data.
+//!
+//! Crates are never deleted with an account, so that nobody depending on them
+//! is broken. The user is removed as an owner of all their crates, and crates
+//! that they were the only owner of are left without owners. Those crates are
+//! listed in the confirmation email and the response of the request, so that
+//! the user can add another owner first. The crates.io team can transfer
+//! ownerless crates to a new maintainer on request. Users that are the last
+//! admin of an organization have to make another member an admin first.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::controllers::helpers::second_factor::ensure_second_factor;
+use crate::models::AccountDeletion;
+use crate::util::errors::not_found;
+use crate::worker;
+
+/// Handles the `POST /me/account_deletion` route.
+pub async fn request(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+
+        let Some(email) = user.verified_email(conn)? else {
+            return Err(bad_request(
+                "please verify your email address first, the deletion of your \
+                 account has to be confirmed by email",
+            ));
+        };
+
+        ensure_second_factor(&app, conn, &req, user.id)?;
+
+        let token = AccountDeletion::request(conn, user.id)?;
+        let sole_owned_crates = AccountDeletion::sole_owned_crates(conn, user.id)?;
+
+        app.emails.send_account_deletion_confirmation(
+            &email,
+            &user.gh_login,
+            &token,
+            &sole_owned_crates,
+        )?;
+
+        Ok(Json(
+            json!({ "ok": true, "sole_owned_crates": sole_owned_crates }),
+        ))
+    })
+    .await
+}
+
+/// Handles the `PUT /me/account_deletion/:token` route.
+pub async fn confirm(app: AppState, Path(token): Path<String>, req: Parts) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user_id = auth.user_id();
+
+        let deletion = AccountDeletion::find_unconfirmed(conn, user_id, &token)
+            .optional()?
+            .ok_or_else(not_found)?;
+
+        conn.transaction(|conn| {
+            deletion.confirm(conn)?;
+            worker::delete_account(user_id).enqueue(conn)?;
+            ok_true()
+        })
+    })
+    .await
+}
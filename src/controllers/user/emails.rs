@@ -0,0 +1,275 @@
+//! Endpoints for managing the email addresses of the authenticated user.
+//!
+//! Users can add several email addresses, for example a work and a personal
+//! one. Each address has to be verified separately. Notifications are sent to
+//! a single verified address, and users can optionally choose one verified
+//! address to be shown on their public profile.
+//!
+//! Like changing the address on the profile, moving the notifications away
+//! from a verified address or removing it notifies that address with a link
+//! to revert the change, in case the account was taken over.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::controllers::helpers::second_factor::ensure_sudo_mode;
+use crate::middleware::session::client_info;
+use crate::models::{Email, EmailChange, NewEmail, SecurityEvent, SecurityLogEntry, User};
+use crate::schema::emails;
+use crate::views::EncodableEmail;
+
+const MAX_EMAILS_PER_USER: i64 = 10;
+
+/// Handles the `GET /me/emails` route.
+pub async fn list(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let user_id = AuthCheck::only_cookie().check(&req, conn)?.user_id();
+
+        let emails = Email::for_user(conn, user_id)?
+            .into_iter()
+            .map(EncodableEmail::from)
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "emails": emails })))
+    })
+    .await
+}
+
+/// Handles the `POST /me/emails` route.
+///
+/// The first address of a user receives their notifications once it is
+/// verified.
+pub async fn create(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct NewEmailRequest {
+            email: NewEmailAddress,
+        }
+
+        #[derive(Deserialize)]
+        struct NewEmailAddress {
+            email: String,
+        }
+
+        let new: NewEmailRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid new email request: {e}")))?;
+
+        let address = new.email.email.trim();
+        if address.is_empty() {
+            return Err(bad_request("empty email rejected"));
+        }
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        ensure_sudo_mode(&app, conn, &req, &auth)?;
+        let user = auth.user();
+
+        let existing = Email::for_user(conn, user.id)?;
+        if existing.len() as i64 >= MAX_EMAILS_PER_USER {
+            return Err(bad_request(&format_args!(
+                "maximum email addresses per user is: {MAX_EMAILS_PER_USER}"
+            )));
+        }
+        if existing.iter().any(|email| email.email == address) {
+            return Err(bad_request(&format_args!(
+                "the email address {address} is already added to your account"
+            )));
+        }
+
+        let new_email = NewEmail {
+            user_id: user.id,
+            email: address,
+            send_notifications: !existing.iter().any(|email| email.send_notifications),
+        };
+        let email: Email = diesel::insert_into(emails::table)
+            .values(&new_email)
+            .get_result(conn)?;
+
+        let (ip_address, user_agent) = client_info(req.headers());
+        SecurityLogEntry::record(
+            conn,
+            user.id,
+            SecurityEvent::EmailAdded,
+            Some(&email.email),
+            ip_address,
+            user_agent,
+        )?;
+
+        // Like during sign up, this swallows any errors from sending the email,
+        // the user can ask for the verification email to be resent.
+        let _ = app
+            .emails
+            .send_user_confirm(&email.email, &user.gh_login, &email.token);
+
+        Ok(Json(json!({ "email": EncodableEmail::from(email) })))
+    })
+    .await
+}
+
+/// Handles the `PUT /me/emails/:id` route.
+///
+/// Makes the address the notification address of the user, or shows or hides
+/// it on their public profile. Both require the address to be verified.
+pub async fn update(
+    app: AppState,
+    Path(id): Path<i32>,
+    req: BytesRequest,
+) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct EmailUpdateRequest {
+            email: EmailUpdate,
+        }
+
+        #[derive(Deserialize)]
+        struct EmailUpdate {
+            send_notifications: Option<bool>,
+            public: Option<bool>,
+        }
+
+        let update: EmailUpdateRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid email update request: {e}")))?;
+        let update = update.email;
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+        let user_id = user.id;
+
+        let email = Email::find(conn, id, user_id)?;
+
+        if !email.verified
+            && (update.send_notifications == Some(true) || update.public == Some(true))
+        {
+            return Err(bad_request("please verify this email address first"));
+        }
+
+        match update.send_notifications {
+            Some(true) if !email.send_notifications => {
+                ensure_sudo_mode(&app, conn, &req, &auth)?;
+                let previous = Email::for_user(conn, user_id)?
+                    .into_iter()
+                    .find(|email| email.send_notifications);
+                email.make_notification_address(conn)?;
+
+                if let Some(previous) = previous {
+                    notify_previous_address(&app, conn, user, &previous, &email.email)?;
+                }
+
+                let (ip_address, user_agent) = client_info(req.headers());
+                SecurityLogEntry::record(
+                    conn,
+                    user_id,
+                    SecurityEvent::NotificationEmailChanged,
+                    Some(&email.email),
+                    ip_address,
+                    user_agent,
+                )?;
+            }
+            Some(false) if email.send_notifications => {
+                return Err(bad_request(
+                    "please choose another address to send notifications to instead",
+                ));
+            }
+            _ => {}
+        }
+
+        if let Some(public) = update.public {
+            email.set_public(conn, public)?;
+        }
+
+        let email = Email::find(conn, id, user_id)?;
+        Ok(Json(json!({ "email": EncodableEmail::from(email) })))
+    })
+    .await
+}
+
+/// Handles the `PUT /me/emails/:id/resend` route.
+pub async fn resend(app: AppState, Path(id): Path<i32>, req: Parts) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+
+        let email = Email::find(conn, id, user.id)?;
+        if email.verified {
+            return Err(bad_request("this email address is already verified"));
+        }
+
+        conn.transaction(|conn| {
+            let email = email.regenerate_token(conn)?;
+            app.emails
+                .send_user_confirm(&email.email, &user.gh_login, &email.token)
+        })?;
+
+        ok_true()
+    })
+    .await
+}
+
+/// Handles the `DELETE /me/emails/:id` route.
+pub async fn delete(app: AppState, Path(id): Path<i32>, req: Parts) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+        let user_id = user.id;
+
+        let email = Email::find(conn, id, user_id)?;
+        if email.send_notifications {
+            return Err(bad_request(
+                "notifications are sent to this address, please choose another one first",
+            ));
+        }
+        ensure_sudo_mode(&app, conn, &req, &auth)?;
+
+        diesel::delete(&email).execute(conn)?;
+
+        let notification_address = Email::for_user(conn, user_id)?
+            .into_iter()
+            .find(|email| email.send_notifications);
+        if let Some(notification_address) = notification_address {
+            notify_previous_address(&app, conn, user, &email, &notification_address.email)?;
+        }
+
+        let (ip_address, user_agent) = client_info(&req.headers);
+        SecurityLogEntry::record(
+            conn,
+            user_id,
+            SecurityEvent::EmailRemoved,
+            Some(&email.email),
+            ip_address,
+            user_agent,
+        )?;
+
+        ok_true()
+    })
+    .await
+}
+
+/// Notifies a verified address that the notifications of the user are no
+/// longer sent to it, with a link to make it the notification address again.
+fn notify_previous_address(
+    app: &AppState,
+    conn: &mut PgConnection,
+    user: &User,
+    previous: &Email,
+    new_email: &str,
+) -> AppResult<()> {
+    if !previous.verified || previous.email == new_email {
+        return Ok(());
+    }
+
+    let change = EmailChange::create(conn, user.id, &previous.email, new_email)?;
+
+    // Like for the verification emails, errors from sending the notification
+    // are swallowed
+    let _ = app.emails.send_email_change_notification(
+        &previous.email,
+        &user.gh_login,
+        new_email,
+        &change.revert_token,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! Endpoints for choosing how the authenticated user is notified about the
+//! crates they follow.
+//!
+//! Users can be notified daily or weekly about the versions of their followed
+//! crates that were published or yanked in the meantime, by email or, if a
+//! webhook URL is given, by a JSON payload sent to that URL. See
+//! `worker::check_followed_crates()` for the format of the notifications.
+//!
+//! The payloads are signed like those of crate webhooks, with a secret that is
+//! only returned by the update that sets a new webhook URL.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::models::{FollowNotificationFrequency, FollowNotificationSettings};
+use crate::util::webhooks::validate_url;
+use crate::views::EncodableFollowNotificationSettings;
+
+/// Handles the `GET /me/follow_notifications` route.
+pub async fn show(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let user_id = AuthCheck::only_cookie().check(&req, conn)?.user_id();
+
+        let settings = FollowNotificationSettings::find(conn, user_id)?;
+        let settings = EncodableFollowNotificationSettings::from(settings);
+
+        Ok(Json(json!({ "follow_notifications": settings })))
+    })
+    .await
+}
+
+/// Handles the `PUT /me/follow_notifications` route.
+pub async fn update(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct UpdateRequest {
+            follow_notifications: SettingsUpdate,
+        }
+
+        #[derive(Deserialize)]
+        struct SettingsUpdate {
+            frequency: String,
+            webhook_url: Option<String>,
+        }
+
+        let update: UpdateRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid follow notification settings: {e}")))?;
+        let update = update.follow_notifications;
+
+        let frequency = FollowNotificationFrequency::from_param(&update.frequency)
+            .ok_or_else(|| bad_request("the frequency must be `never`, `daily` or `weekly`"))?;
+
+        let webhook_url = update.webhook_url;
+        if let Some(url) = &webhook_url {
+            validate_url(url).map_err(|e| bad_request(&e))?;
+        }
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+
+        let notified_by_email =
+            frequency != FollowNotificationFrequency::Never && webhook_url.is_none();
+        if notified_by_email && user.verified_email(conn)?.is_none() {
+            return Err(bad_request(
+                "a verified email address is required to be notified about followed crates",
+            ));
+        }
+
+        let (settings, webhook_secret) =
+            FollowNotificationSettings::save(conn, user.id, frequency, webhook_url.as_deref())?;
+        let mut settings = EncodableFollowNotificationSettings::from(Some(settings));
+        settings.webhook_secret = webhook_secret;
+
+        Ok(Json(json!({ "follow_notifications": settings })))
+    })
+    .await
+}
@@ -0,0 +1,80 @@
+//! Endpoints for managing the identity provider accounts of a user.
+//!
+//! Accounts are linked by logging in with them while already being logged in,
+//! see `GET /api/private/session/begin?link=true`. Every linked account can be
+//! used to log in, and its team memberships count for the crates of the user.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::controllers::helpers::second_factor::ensure_second_factor;
+use crate::models::{AccountProvider, LinkedAccount, User};
+use crate::util::errors::not_found;
+use crate::views::EncodableLinkedAccount;
+
+/// Handles the `GET /me/linked_accounts` route.
+///
+/// Lists the GitHub account that the user signed up with, if any, followed by
+/// the accounts they linked later.
+pub async fn list(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+
+        let linked_accounts = EncodableLinkedAccount::primary(user)
+            .into_iter()
+            .chain(
+                LinkedAccount::for_user(conn, user.id)?
+                    .into_iter()
+                    .map(EncodableLinkedAccount::from),
+            )
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "linked_accounts": linked_accounts })))
+    })
+    .await
+}
+
+/// Handles the `DELETE /me/linked_accounts/:provider/:account_id` route.
+///
+/// Unlinking is confirmed with a second factor, like other changes to the
+/// ways of logging in. The account that the user signed up with, and the last
+/// account that the user can log in with, can't be unlinked.
+pub async fn delete(
+    app: AppState,
+    Path((provider, account_id)): Path<(String, i32)>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+
+        let provider = AccountProvider::from_param(&provider).ok_or_else(not_found)?;
+        if provider == AccountProvider::GitHub && account_id == user.gh_id {
+            return Err(bad_request(
+                "the GitHub account that you signed up with can't be unlinked",
+            ));
+        }
+
+        let account = LinkedAccount::find(conn, user.id, provider, account_id)
+            .optional()?
+            .ok_or_else(not_found)?;
+
+        let linked = LinkedAccount::for_user(conn, user.id)?.len();
+        let has_github = user.gh_id != User::NO_GITHUB_ID;
+        if linked + usize::from(has_github) <= 1 {
+            return Err(bad_request(
+                "this is the only account that you can log in with, \
+                 please link another account first",
+            ));
+        }
+
+        ensure_second_factor(&app, conn, &req, user.id)?;
+
+        account.delete(conn)?;
+
+        ok_true()
+    })
+    .await
+}
@@ -3,11 +3,14 @@ use std::collections::HashMap;
 
 use crate::controllers::frontend_prelude::*;
 
+use crate::controllers::helpers::second_factor::ensure_sudo_mode;
 use crate::controllers::helpers::*;
 
 use crate::controllers::helpers::pagination::{Paginated, PaginationOptions};
+use crate::middleware::session::client_info;
 use crate::models::{
-    CrateOwner, Email, Follow, NewEmail, OwnerKind, User, Version, VersionOwnerAction,
+    CrateOwner, Email, EmailChange, Follow, NewEmail, OwnerKind, SecurityEvent, SecurityLogEntry,
+    User, Version, VersionOwnerAction,
 };
 use crate::schema::{crate_owners, crates, emails, follows, users, versions};
 use crate::views::{EncodableMe, EncodablePrivateUser, EncodableVersion, OwnedCrate};
@@ -21,7 +24,11 @@ pub async fn me(app: AppState, req: Parts) -> AppResult<Json<EncodableMe>> {
         let (user, verified, email, verification_sent): (User, Option<bool>, Option<String>, bool) =
             users::table
                 .find(user_id)
-                .left_join(emails::table)
+                .left_join(
+                    emails::table.on(emails::user_id
+                        .eq(users::id)
+                        .and(emails::send_notifications.eq(true))),
+                )
                 .select((
                     users::all_columns,
                     emails::verified.nullable(),
@@ -103,8 +110,9 @@ pub async fn update_user(
     req: BytesRequest,
 ) -> AppResult<Response> {
     conduit_compat(move || {
-        use self::emails::user_id;
+        use diesel::dsl::sql;
         use diesel::insert_into;
+        use diesel::sql_types::Integer;
 
         let state = app.clone();
         let conn = &mut state.db_write()?;
@@ -117,6 +125,8 @@ pub async fn update_user(
             return Err(bad_request("current user does not match requested user"));
         }
 
+        ensure_sudo_mode(&app, conn, &req, &auth)?;
+
         #[derive(Deserialize)]
         struct UserUpdate {
             user: User,
@@ -139,21 +149,36 @@ pub async fn update_user(
             return Err(bad_request("empty email rejected"));
         }
 
+        let old_email = user.verified_email(conn)?;
+
         conn.transaction::<_, BoxedAppError, _>(|conn| {
             let new_email = NewEmail {
                 user_id: user.id,
                 email: user_email,
+                send_notifications: true,
             };
 
+            // This changes the address that notifications are sent to, any other addresses of
+            // the user are managed through the `/me/emails` routes
             let token: String = insert_into(emails::table)
                 .values(&new_email)
-                .on_conflict(user_id)
+                .on_conflict(sql::<Integer>("(user_id) WHERE send_notifications"))
                 .do_update()
                 .set(&new_email)
                 .returning(emails::token)
                 .get_result(conn)
                 .map_err(|_| server_error("Error in creating token"))?;
 
+            let (ip_address, user_agent) = client_info(req.headers());
+            SecurityLogEntry::record(
+                conn,
+                user.id,
+                SecurityEvent::NotificationEmailChanged,
+                Some(user_email),
+                ip_address,
+                user_agent,
+            )?;
+
             // This swallows any errors that occur while attempting to send the email. Some users have
             // an invalid email set in their GitHub profile, and we should let them sign in even though
             // we're trying to silently use their invalid address during signup and can't send them an
@@ -162,6 +187,18 @@ pub async fn update_user(
                 .emails
                 .send_user_confirm(user_email, &user.gh_login, &token);
 
+            // Notify the previous address, so that the change can be reverted if the account
+            // was taken over, e.g. through a compromised GitHub account.
+            if let Some(old_email) = old_email.filter(|old_email| old_email != user_email) {
+                let change = EmailChange::create(conn, user.id, &old_email, user_email)?;
+                let _ = state.emails.send_email_change_notification(
+                    &old_email,
+                    &user.gh_login,
+                    user_email,
+                    &change.revert_token,
+                );
+            }
+
             Ok(())
         })?;
 
@@ -190,6 +227,38 @@ pub async fn confirm_user_email(state: AppState, Path(token): Path<String>) -> A
     .await
 }
 
+/// Handles the `PUT /revert_email_change/:token` route
+pub async fn revert_email_change(
+    state: AppState,
+    Path(token): Path<String>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *state.db_write()?;
+
+        let change = EmailChange::find_revertable(conn, &token)
+            .optional()?
+            .ok_or_else(|| bad_request("Email change belonging to token not found."))?;
+
+        conn.transaction(|conn| {
+            change.revert(conn)?;
+
+            let (ip_address, user_agent) = client_info(&req.headers);
+            SecurityLogEntry::record(
+                conn,
+                change.user_id,
+                SecurityEvent::NotificationEmailChanged,
+                Some(&change.old_email),
+                ip_address,
+                user_agent,
+            )
+        })?;
+
+        ok_true()
+    })
+    .await
+}
+
 /// Handles `PUT /user/:user_id/resend` route
 pub async fn regenerate_token_and_send(
     state: AppState,
@@ -211,10 +280,11 @@ pub async fn regenerate_token_and_send(
         }
 
         conn.transaction(|conn| {
-            let email: Email = update(Email::belonging_to(user))
-                .set(emails::token.eq(sql("DEFAULT")))
-                .get_result(conn)
-                .map_err(|_| bad_request("Email could not be found"))?;
+            let email: Email =
+                update(Email::belonging_to(user).filter(emails::send_notifications.eq(true)))
+                    .set(emails::token.eq(sql("DEFAULT")))
+                    .get_result(conn)
+                    .map_err(|_| bad_request("Email could not be found"))?;
 
             state
                 .emails
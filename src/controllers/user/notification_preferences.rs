@@ -0,0 +1,68 @@
+//! Endpoints for choosing which kinds of emails the authenticated user
+//! receives.
+//!
+//! Emails that are required to use an account, like the confirmation of an
+//! email address, are always sent. See `NotificationKind` for the kinds of
+//! emails that users can opt out of.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::models::NotificationPreferences;
+use crate::views::EncodableNotificationPreferences;
+
+/// Handles the `GET /me/notification_preferences` route.
+pub async fn show(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let user_id = AuthCheck::only_cookie().check(&req, conn)?.user_id();
+
+        let preferences = NotificationPreferences::for_user(conn, user_id)?;
+        let preferences = EncodableNotificationPreferences::from(preferences);
+
+        Ok(Json(json!({ "notification_preferences": preferences })))
+    })
+    .await
+}
+
+/// Handles the `PUT /me/notification_preferences` route.
+///
+/// Preferences that are missing from the request are left unchanged.
+pub async fn update(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct UpdateRequest {
+            notification_preferences: PreferencesUpdate,
+        }
+
+        #[derive(Deserialize)]
+        struct PreferencesUpdate {
+            owner_invites: Option<bool>,
+            security_alerts: Option<bool>,
+            digests: Option<bool>,
+        }
+
+        let update: UpdateRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid notification preferences: {e}")))?;
+        let update = update.notification_preferences;
+
+        let conn = &mut *app.db_write()?;
+        let user_id = AuthCheck::only_cookie().check(&req, conn)?.user_id();
+
+        let mut preferences = NotificationPreferences::for_user(conn, user_id)?;
+        if let Some(owner_invites) = update.owner_invites {
+            preferences.owner_invites = owner_invites;
+        }
+        if let Some(security_alerts) = update.security_alerts {
+            preferences.security_alerts = security_alerts;
+        }
+        if let Some(digests) = update.digests {
+            preferences.digests = digests;
+        }
+
+        let preferences = preferences.save(conn)?;
+        let preferences = EncodableNotificationPreferences::from(preferences);
+
+        Ok(Json(json!({ "notification_preferences": preferences })))
+    })
+    .await
+}
@@ -17,7 +17,13 @@ pub async fn show(state: AppState, Path(user_name): Path<String>) -> AppResult<J
             .order(id.desc())
             .first(conn)?;
 
-        Ok(Json(json!({ "user": EncodablePublicUser::from(user) })))
+        let email = user.public_email(conn)?;
+        let user = EncodablePublicUser {
+            email,
+            ..EncodablePublicUser::from(user)
+        };
+
+        Ok(Json(json!({ "user": user })))
     })
     .await
 }
@@ -0,0 +1,107 @@
+//! Endpoints for managing the saved searches of the authenticated user.
+//!
+//! Saved searches are periodically checked for newly published crates and
+//! versions that match their query, and the user is notified about them by
+//! email or, if a webhook URL is given, by a JSON payload sent to that URL.
+//! See `worker::check_saved_searches()` for the format of the notifications.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::models::{NewSavedSearch, SavedSearch};
+use crate::util::webhooks::validate_url;
+use crate::views::EncodableSavedSearch;
+
+const MAX_SAVED_SEARCHES_PER_USER: i64 = 20;
+const MAX_QUERY_LENGTH: usize = 256;
+
+/// Handles the `GET /me/saved_searches` route.
+pub async fn list(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let user_id = AuthCheck::default().check(&req, conn)?.user_id();
+
+        let saved_searches = SavedSearch::for_user(conn, user_id)?
+            .into_iter()
+            .map(EncodableSavedSearch::from)
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "saved_searches": saved_searches })))
+    })
+    .await
+}
+
+/// Handles the `POST /me/saved_searches` route.
+pub async fn create(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct NewSavedSearchRequest {
+            saved_search: NewSavedSearchQuery,
+        }
+
+        #[derive(Deserialize)]
+        struct NewSavedSearchQuery {
+            query: String,
+            webhook_url: Option<String>,
+        }
+
+        let new: NewSavedSearchRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid new saved search request: {e}")))?;
+
+        let query = new.saved_search.query.trim();
+        if query.is_empty() {
+            return Err(bad_request("the query of a saved search must not be empty"));
+        }
+        if query.len() > MAX_QUERY_LENGTH {
+            return Err(bad_request(&format_args!(
+                "the query of a saved search must not be longer than {MAX_QUERY_LENGTH} characters"
+            )));
+        }
+
+        let webhook_url = new.saved_search.webhook_url;
+        if let Some(url) = &webhook_url {
+            validate_url(url).map_err(|e| bad_request(&e))?;
+        }
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user = auth.user();
+
+        if webhook_url.is_none() && user.verified_email(conn)?.is_none() {
+            return Err(bad_request(
+                "a verified email address is required to be notified about saved searches",
+            ));
+        }
+
+        let count: i64 = SavedSearch::belonging_to(user).count().get_result(conn)?;
+        if count >= MAX_SAVED_SEARCHES_PER_USER {
+            return Err(bad_request(&format_args!(
+                "maximum saved searches per user is: {MAX_SAVED_SEARCHES_PER_USER}"
+            )));
+        }
+
+        let saved_search = NewSavedSearch {
+            user_id: user.id,
+            query,
+            webhook_url: webhook_url.as_deref(),
+        }
+        .create(conn)?;
+
+        Ok(Json(
+            json!({ "saved_search": EncodableSavedSearch::from(saved_search) }),
+        ))
+    })
+    .await
+}
+
+/// Handles the `DELETE /me/saved_searches/:id` route.
+pub async fn delete(app: AppState, Path(id): Path<i32>, req: Parts) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let user_id = AuthCheck::default().check(&req, conn)?.user_id();
+
+        SavedSearch::find(conn, id, user_id)?.delete(conn)?;
+
+        ok_true()
+    })
+    .await
+}
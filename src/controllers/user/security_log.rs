@@ -0,0 +1,41 @@
+//! Endpoint for reviewing the security log of the authenticated user.
+//!
+//! Logins, created API tokens, changes of crate owners and changes of email
+//! addresses are recorded together with the IP address and user agent of the
+//! request, so that users can audit their account after suspicious activity.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::controllers::helpers::pagination::{Paginated, PaginationOptions};
+use crate::controllers::helpers::Paginate;
+use crate::models::SecurityLogEntry;
+use crate::schema::security_log_entries;
+use crate::views::EncodableSecurityLogEntry;
+
+/// Handles the `GET /me/security_log` route.
+///
+/// Returns the entries of the security log, newest first.
+pub async fn list(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let user_id = AuthCheck::only_cookie().check(&req, conn)?.user_id();
+
+        let data: Paginated<SecurityLogEntry> = security_log_entries::table
+            .filter(security_log_entries::user_id.eq(user_id))
+            .order(security_log_entries::id.desc())
+            .pages_pagination(PaginationOptions::builder().gather(&req)?)
+            .load(conn)?;
+
+        let more = data.next_page_params().is_some();
+        let entries = data
+            .into_iter()
+            .map(EncodableSecurityLogEntry::from)
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({
+            "security_log": entries,
+            "meta": { "more": more },
+        })))
+    })
+    .await
+}
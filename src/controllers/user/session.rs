@@ -1,23 +1,61 @@
+use crate::auth::{ensure_not_locked, AuthCheck};
 use crate::controllers::frontend_prelude::*;
 
+use chrono::Utc;
+use http::HeaderMap;
+use oauth2::basic::BasicClient;
 use oauth2::reqwest::http_client;
 use oauth2::{AuthorizationCode, Scope, TokenResponse};
+use webauthn_rs::prelude::PublicKeyCredential;
 
+use crate::config::SsoConfig;
+use crate::controllers::helpers::second_factor::{ensure_second_factor, has_second_factor};
+use crate::controllers::user::webauthn::{request_options, verify_assertion};
 use crate::email::Emails;
 use crate::github::GithubUser;
-use crate::middleware::session::SessionExtension;
-use crate::models::{NewUser, User};
+use crate::middleware::session::{client_info, SessionExtension, SESSION_TOKEN_KEY};
+use crate::models::{
+    AccountProvider, NewLinkedAccount, NewUser, PersistentSession, SecurityEvent, SecurityLogEntry,
+    SsoIdentity, TotpCredential, User, WebauthnCredential,
+};
 use crate::schema::users;
-use crate::util::errors::ReadOnlyMode;
-use crate::views::EncodableMe;
+use crate::sso::{self, SsoClient};
+use crate::util::errors::{forbidden, ReadOnlyMode};
+use crate::views::{EncodableMe, EncodablePersistentSession};
+
+/// The session key of the login that still has to be confirmed with a second
+/// factor, see [`PendingLogin`].
+const PENDING_LOGIN_KEY: &str = "pending_login_user_id";
+
+/// The number of seconds within which a login has to be confirmed with a
+/// second factor.
+const PENDING_LOGIN_TTL_SECONDS: i64 = 5 * 60;
+
+/// The number of invalid second factors after which the login has to be
+/// started again.
+const MAX_PENDING_LOGIN_ATTEMPTS: u32 = 5;
+
+/// The session key of the user that the account of the pending OAuth flow
+/// will be linked to, instead of logging in with it.
+const LINK_USER_KEY: &str = "link_user_id";
+
+/// The session key of the `nonce` that the ID token of a pending single
+/// sign-on login has to carry.
+const SSO_NONCE_KEY: &str = "sso_oauth_nonce";
 
 /// Handles the `GET /api/private/session/begin` route.
 ///
-/// This route will return an authorization URL for the GitHub OAuth flow including the crates.io
-/// `client_id` and a randomly generated `state` secret.
+/// This route will return an authorization URL for the OAuth flow of the identity provider
+/// including the crates.io `client_id` and a randomly generated `state` secret.
 ///
 /// see <https://developer.github.com/v3/oauth/#redirect-users-to-request-github-access>
 ///
+/// ## Query Parameters
+///
+/// - `provider` – either `github` (the default), `gitlab` or `sso`
+/// - `link` – `true` to link the account to the user that is logged in, instead of logging in
+///   with it. This has to be confirmed with a second factor, if the user has one.
+///
 /// ## Response Body Example
 ///
 /// ```json
@@ -26,32 +64,82 @@ use crate::views::EncodableMe;
 ///     "url": "https://github.com/login/oauth/authorize?client_id=...&state=...&scope=read%3Aorg"
 /// }
 /// ```
-pub async fn begin(app: AppState, session: SessionExtension) -> Json<Value> {
-    let (url, state) = app
-        .github_oauth
-        .authorize_url(oauth2::CsrfToken::new_random)
-        .add_scope(Scope::new("read:org".to_string()))
-        .url();
+pub async fn begin(app: AppState, session: SessionExtension, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let provider = provider_param(&req)?;
+
+        if req.query().get("link").map(String::as_str) == Some("true") {
+            let conn = &mut *app.db_write()?;
+            let auth = AuthCheck::only_cookie().check(&req, conn)?;
+            let user_id = auth.user_id();
 
-    let state = state.secret().to_string();
-    session.insert("github_oauth_state".to_string(), state.clone());
+            // Linking adds a way to log in, so a stolen session is not enough
+            ensure_second_factor(&app, conn, &req, user_id)?;
 
-    Json(json!({ "url": url.to_string(), "state": state }))
+            session.insert(LINK_USER_KEY.to_string(), user_id.to_string());
+        } else {
+            session.remove(LINK_USER_KEY);
+        }
+
+        let (url, state) = match provider {
+            AccountProvider::GitHub => app
+                .github_oauth
+                .authorize_url(oauth2::CsrfToken::new_random)
+                .add_scope(Scope::new("read:org".to_string()))
+                .url(),
+            // `read_api` is needed to check group memberships
+            AccountProvider::GitLab => gitlab_oauth(&app)?
+                .authorize_url(oauth2::CsrfToken::new_random)
+                .add_scope(Scope::new("read_user".to_string()))
+                .add_scope(Scope::new("read_api".to_string()))
+                .url(),
+            AccountProvider::Sso => {
+                // The ID token has to carry the nonce of this login
+                let nonce = oauth2::CsrfToken::new_random().secret().to_string();
+                session.insert(SSO_NONCE_KEY.to_string(), nonce.clone());
+
+                sso_oauth(&app)?
+                    .0
+                    .authorize_url(oauth2::CsrfToken::new_random)
+                    .add_scope(Scope::new("openid".to_string()))
+                    .add_scope(Scope::new("profile".to_string()))
+                    .add_scope(Scope::new("email".to_string()))
+                    .add_extra_param("nonce", nonce)
+                    .url()
+            }
+        };
+
+        let state = state.secret().to_string();
+        session.insert(state_key(provider), state.clone());
+
+        Ok(Json(json!({ "url": url.to_string(), "state": state })))
+    })
+    .await
 }
 
 /// Handles the `GET /api/private/session/authorize` route.
 ///
-/// This route is called from the GitHub API OAuth flow after the user accepted or rejected
-/// the data access permissions. It will check the `state` parameter and then call the GitHub API
-/// to exchange the temporary `code` for an API token. The API token is returned together with
-/// the corresponding user information.
+/// This route is called from the OAuth flow of the identity provider after the user accepted or
+/// rejected the data access permissions. It will check the `state` parameter and then call the API
+/// of the provider to exchange the temporary `code` for an API token. The API token is returned
+/// together with the corresponding user information.
+///
+/// Accounts that were linked to a user log in as that user. Flows that were started with
+/// `link=true` link the account to the current user instead, and return that user.
 ///
 /// see <https://developer.github.com/v3/oauth/#github-redirects-back-to-your-site>
 ///
 /// ## Query Parameters
 ///
-/// - `code` – temporary code received from the GitHub API  **(Required)**
-/// - `state` – state parameter received from the GitHub API  **(Required)**
+/// - `code` – temporary code received from the provider  **(Required)**
+/// - `state` – state parameter received from the provider  **(Required)**
+/// - `provider` – either `github` (the default), `gitlab` or `sso`
+///
+/// Users that have enabled a second factor are not logged in yet. Instead, the
+/// response lists the second factors of the user, like
+/// `{"second_factor": {"totp": true, "webauthn": false}}`, and the login has to
+/// be confirmed with one of them, see [`confirm_totp`] and
+/// [`confirm_webauthn`].
 ///
 /// ## Response Body Example
 ///
@@ -71,10 +159,12 @@ pub async fn authorize(
     app: AppState,
     session: SessionExtension,
     req: Parts,
-) -> AppResult<Json<EncodableMe>> {
+) -> AppResult<Response> {
     let app_clone = app.clone();
 
     let req = conduit_compat(move || {
+        let provider = provider_param(&req)?;
+
         // Parse the url query
         let mut query = req.query();
         let code = query.remove("code").unwrap_or_default();
@@ -83,37 +173,409 @@ pub async fn authorize(
         // Make sure that the state we just got matches the session state that we
         // should have issued earlier.
         {
-            let session_state = session.remove("github_oauth_state");
+            let session_state = session.remove(&state_key(provider));
             let session_state = session_state.as_deref();
             if Some(&state[..]) != session_state {
                 return Err(bad_request("invalid state parameter"));
             }
         }
 
-        // Fetch the access token from GitHub using the code we just got
+        let link_user = session
+            .remove(LINK_USER_KEY)
+            .and_then(|user_id| user_id.parse::<i32>().ok());
+        if let Some(user_id) = link_user {
+            // The user could have logged out, or in as someone else, meanwhile
+            let conn = &mut *app.db_write()?;
+            let current_session = match session.get(SESSION_TOKEN_KEY) {
+                Some(token) => PersistentSession::find_by_token(conn, &token)?,
+                None => None,
+            };
+            if current_session.map(|session| session.user_id) != Some(user_id) {
+                return Err(bad_request("please log in again to link this account"));
+            }
+        }
+
         let code = AuthorizationCode::new(code);
-        let token = app
-            .github_oauth
-            .exchange_code(code)
-            .request(http_client)
-            .map_err(|err| err.chain(server_error("Error obtaining token")))?;
-        let token = token.access_token();
-
-        // Fetch the user info from GitHub using the access token we just got and create a user record
-        let ghuser = app.github.current_user(token)?;
-        let user =
-            save_user_to_database(&ghuser, token.secret(), &app.emails, &mut *app.db_write()?)?;
-
-        // Log in by setting a cookie and the middleware authentication
-        session.insert("user_id".to_string(), user.id.to_string());
-
-        Ok(req)
+        let user = match provider {
+            AccountProvider::GitHub => {
+                // Fetch the access token from GitHub using the code we just got
+                let token = app
+                    .github_oauth
+                    .exchange_code(code)
+                    .request(http_client)
+                    .map_err(|err| err.chain(server_error("Error obtaining token")))?;
+                let token = token.access_token();
+
+                // Fetch the user info from GitHub using the access token we just got and
+                // create a user record
+                let ghuser = app.github.current_user(token)?;
+                let account = NewLinkedAccount::new(
+                    AccountProvider::GitHub,
+                    ghuser.id,
+                    &ghuser.login,
+                    ghuser.avatar_url.as_deref(),
+                    token.secret(),
+                );
+
+                let conn = &mut *app.db_write()?;
+                if let Some(user_id) = link_user {
+                    link_account(conn, user_id, &account)?;
+                    return Ok(Ok(req));
+                }
+                match account.login(conn)? {
+                    Some(user_id) => User::find(conn, user_id)?,
+                    None => save_user_to_database(&ghuser, token.secret(), &app.emails, conn)?,
+                }
+            }
+            AccountProvider::GitLab => {
+                let token = gitlab_oauth(&app)?
+                    .exchange_code(code)
+                    .request(http_client)
+                    .map_err(|err| err.chain(server_error("Error obtaining token")))?;
+                let token = token.access_token();
+
+                let gitlab_user = app.gitlab.current_user(token)?;
+                let account = NewLinkedAccount::new(
+                    AccountProvider::GitLab,
+                    gitlab_user.id,
+                    &gitlab_user.username,
+                    gitlab_user.avatar_url.as_deref(),
+                    token.secret(),
+                );
+
+                let conn = &mut *app.db_write()?;
+                if let Some(user_id) = link_user {
+                    link_account(conn, user_id, &account)?;
+                    return Ok(Ok(req));
+                }
+                match account.login(conn)? {
+                    Some(user_id) => User::find(conn, user_id)?,
+                    None => account.create_user(
+                        gitlab_user.name.as_deref(),
+                        gitlab_user.email.as_deref(),
+                        &app.emails,
+                        conn,
+                    )?,
+                }
+            }
+            AccountProvider::Sso => {
+                let (oauth, config) = sso_oauth(&app)?;
+                let nonce = session
+                    .remove(SSO_NONCE_KEY)
+                    .ok_or_else(|| bad_request("invalid state parameter"))?;
+                let token = oauth
+                    .exchange_code(code)
+                    .request(http_client)
+                    .map_err(|err| err.chain(server_error("Error obtaining token")))?;
+
+                // The user is only provisioned once the ID token is validated,
+                // see the `sso` module
+                let id_token = &token.extra_fields().id_token;
+                let subject = sso::validate_id_token(app.http_client(), config, id_token, &nonce)?;
+                let sso_user =
+                    sso::current_user(app.http_client(), config, token.access_token(), &subject)?;
+
+                let conn = &mut *app.db_write()?;
+                if let Some(user_id) = link_user {
+                    SsoIdentity::link(conn, &sso_user, config, user_id)?;
+                    return Ok(Ok(req));
+                }
+                SsoIdentity::log_in(conn, &sso_user, config, &app.emails)?
+            }
+        };
+
+        // Suspended users can't log in until their suspension ends
+        ensure_not_locked(&user)?;
+
+        let conn = &mut *app.db_write()?;
+        let totp = TotpCredential::is_enabled_for(conn, user.id)?;
+        let webauthn = WebauthnCredential::exists_for(conn, user.id)?;
+        if totp || webauthn {
+            PendingLogin::start(&session, user.id);
+            return Ok(Err(
+                json!({ "second_factor": { "totp": totp, "webauthn": webauthn } }),
+            ));
+        }
+
+        log_in(conn, &session, &req.headers, user.id)?;
+
+        Ok(Ok(req))
+    })
+    .await?;
+
+    match req {
+        Ok(req) => Ok(super::me::me(app_clone, req).await?.into_response()),
+        Err(second_factor) => Ok(Json(second_factor).into_response()),
+    }
+}
+
+/// Handles the `POST /api/private/session/totp` route.
+///
+/// Completes the login of a user with an authenticator, after the GitHub OAuth
+/// flow asked for a second factor. Recovery codes are accepted instead of a
+/// one-time password as well.
+///
+/// ## Request Body Example
+///
+/// ```json
+/// {
+///     "code": "123456"
+/// }
+/// ```
+pub async fn confirm_totp(
+    app: AppState,
+    session: SessionExtension,
+    req: BytesRequest,
+) -> AppResult<Json<EncodableMe>> {
+    let app_clone = app.clone();
+
+    let req = conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct ConfirmRequest {
+            code: String,
+        }
+
+        let request: ConfirmRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid request: {e}")))?;
+
+        let pending_login = PendingLogin::get(&session, "a one-time password")?;
+        let user_id = pending_login.user_id;
+
+        let conn = &mut *app.db_write()?;
+        let credential = TotpCredential::find(conn, user_id)?
+            .filter(|credential| credential.enabled)
+            .ok_or_else(|| bad_request("no login is waiting for a one-time password"))?;
+
+        if !credential.verify_or_redeem(conn, &request.code)? {
+            pending_login.record_failed_attempt(&session);
+            return Err(bad_request("invalid one-time password"));
+        }
+
+        session.remove(PENDING_LOGIN_KEY);
+        log_in(conn, &session, req.headers(), user_id)?;
+
+        Ok(req.0.into_parts().0)
     })
     .await?;
 
     super::me::me(app_clone, req).await
 }
 
+/// Handles the `POST /api/private/session/webauthn` route.
+///
+/// Starts the confirmation of a login with a security key, after the GitHub
+/// OAuth flow asked for a second factor, and returns the options for
+/// `navigator.credentials.get()`.
+pub async fn begin_webauthn(app: AppState, session: SessionExtension) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let user_id = PendingLogin::get(&session, "a security key")?.user_id;
+
+        let conn = &mut *app.db_write()?;
+        Ok(Json(request_options(&app, conn, user_id)?))
+    })
+    .await
+}
+
+/// Handles the `PUT /api/private/session/webauthn` route.
+///
+/// Completes the login of a user with the response of their security key.
+///
+/// ## Request Body Example
+///
+/// ```json
+/// {
+///     "id": "...",
+///     "rawId": "...",
+///     "type": "public-key",
+///     "response": {
+///         "authenticatorData": "...",
+///         "clientDataJSON": "...",
+///         "signature": "...",
+///         "userHandle": "..."
+///     }
+/// }
+/// ```
+pub async fn confirm_webauthn(
+    app: AppState,
+    session: SessionExtension,
+    req: BytesRequest,
+) -> AppResult<Json<EncodableMe>> {
+    let app_clone = app.clone();
+
+    let req = conduit_compat(move || {
+        let assertion: PublicKeyCredential = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid request: {e}")))?;
+
+        let pending_login = PendingLogin::get(&session, "a security key")?;
+        let user_id = pending_login.user_id;
+
+        let conn = &mut *app.db_write()?;
+        if let Err(error) = verify_assertion(&app, conn, user_id, &assertion) {
+            pending_login.record_failed_attempt(&session);
+            return Err(error);
+        }
+
+        session.remove(PENDING_LOGIN_KEY);
+        log_in(conn, &session, req.headers(), user_id)?;
+
+        Ok(req.0.into_parts().0)
+    })
+    .await?;
+
+    super::me::me(app_clone, req).await
+}
+
+/// Handles the `PUT /api/private/session/sudo` route.
+///
+/// Puts the current session into sudo mode, which sensitive actions require,
+/// after the user confirmed their identity with a second factor in the
+/// `X-Crates-Io-Otp` or `X-Crates-Io-Webauthn` header. Users without a second
+/// factor have to log in again instead, which starts a session in sudo mode.
+pub async fn enter_sudo_mode(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user_id = auth.user_id();
+        let session_id = auth.session_id().ok_or_else(forbidden)?;
+
+        if !has_second_factor(conn, user_id)? {
+            return Err(bad_request("please log in again to enter sudo mode"));
+        }
+        ensure_second_factor(&app, conn, &req, user_id)?;
+
+        let session = PersistentSession::find_active(conn, session_id, user_id)?;
+        let session = session.enter_sudo_mode(conn)?;
+        let session = EncodablePersistentSession::new(session, Some(session_id));
+
+        Ok(Json(json!({ "session": session })))
+    })
+    .await
+}
+
+/// Returns the identity provider of the `provider` query parameter, which
+/// defaults to GitHub.
+fn provider_param(req: &Parts) -> AppResult<AccountProvider> {
+    match req.query().get("provider") {
+        None => Ok(AccountProvider::GitHub),
+        Some(provider) => AccountProvider::from_param(provider)
+            .ok_or_else(|| bad_request(&format_args!("unknown identity provider `{provider}`"))),
+    }
+}
+
+/// The session key of the pending OAuth `state` of the provider.
+fn state_key(provider: AccountProvider) -> String {
+    format!("{}_oauth_state", provider.as_str())
+}
+
+fn gitlab_oauth(app: &AppState) -> AppResult<&BasicClient> {
+    app.gitlab_oauth
+        .as_ref()
+        .ok_or_else(|| bad_request("logging in with GitLab is not enabled"))
+}
+
+fn sso_oauth(app: &AppState) -> AppResult<(&SsoClient, &SsoConfig)> {
+    match (&app.sso_oauth, &app.config.sso) {
+        (Some(oauth), Some(config)) => Ok((oauth, config)),
+        _ => Err(bad_request("logging in with single sign-on is not enabled")),
+    }
+}
+
+/// Links the account to the user, unless it is the GitHub account that the
+/// user signed up with. GitHub accounts that another user signed up with can't
+/// be linked, since they already log in as that user.
+fn link_account(
+    conn: &mut PgConnection,
+    user_id: i32,
+    account: &NewLinkedAccount<'_>,
+) -> AppResult<()> {
+    if account.provider == AccountProvider::GitHub as i16 {
+        let owner = users::table
+            .filter(users::gh_id.eq(account.account_id))
+            .select(users::id)
+            .first::<i32>(conn)
+            .optional()?;
+        match owner {
+            Some(owner_id) if owner_id == user_id => return Ok(()),
+            Some(_) => return Err(account.linked_to_another_user()),
+            None => {}
+        }
+    }
+
+    account.link(conn, user_id)
+}
+
+/// A login that is waiting for the confirmation with a second factor.
+///
+/// It is stored in the session as `{user_id}:{started_at}:{failed_attempts}`,
+/// and expires after `PENDING_LOGIN_TTL_SECONDS` or once there were
+/// `MAX_PENDING_LOGIN_ATTEMPTS` invalid second factors.
+#[derive(Debug, PartialEq, Eq)]
+struct PendingLogin {
+    user_id: i32,
+    started_at: i64,
+    failed_attempts: u32,
+}
+
+impl PendingLogin {
+    fn start(session: &SessionExtension, user_id: i32) {
+        let pending_login = Self {
+            user_id,
+            started_at: Utc::now().timestamp(),
+            failed_attempts: 0,
+        };
+        session.insert(PENDING_LOGIN_KEY.to_string(), pending_login.encode());
+    }
+
+    /// Returns the pending login of the session, which is discarded if it
+    /// expired.
+    fn get(session: &SessionExtension, second_factor: &str) -> AppResult<Self> {
+        let pending_login = session
+            .get(PENDING_LOGIN_KEY)
+            .and_then(|value| Self::parse(&value))
+            .ok_or_else(|| bad_request(&format_args!("no login is waiting for {second_factor}")))?;
+
+        if pending_login.is_expired(Utc::now().timestamp()) {
+            session.remove(PENDING_LOGIN_KEY);
+            return Err(bad_request("the login has expired, please log in again"));
+        }
+
+        Ok(pending_login)
+    }
+
+    /// Counts an invalid second factor, and discards the login once there
+    /// were too many.
+    fn record_failed_attempt(mut self, session: &SessionExtension) {
+        self.failed_attempts += 1;
+        if self.failed_attempts >= MAX_PENDING_LOGIN_ATTEMPTS {
+            session.remove(PENDING_LOGIN_KEY);
+        } else {
+            session.insert(PENDING_LOGIN_KEY.to_string(), self.encode());
+        }
+    }
+
+    fn is_expired(&self, now: i64) -> bool {
+        now - self.started_at > PENDING_LOGIN_TTL_SECONDS
+            || self.failed_attempts >= MAX_PENDING_LOGIN_ATTEMPTS
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split(':');
+        let pending_login = Self {
+            user_id: parts.next()?.parse().ok()?,
+            started_at: parts.next()?.parse().ok()?,
+            failed_attempts: parts.next()?.parse().ok()?,
+        };
+        parts.next().is_none().then_some(pending_login)
+    }
+
+    fn encode(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.user_id, self.started_at, self.failed_attempts
+        )
+    }
+}
+
 fn save_user_to_database(
     user: &GithubUser,
     access_token: &str,
@@ -144,10 +606,47 @@ fn save_user_to_database(
     })
 }
 
+/// Logs the user in by storing a new persistent session in the session
+/// cookie, which the middleware authentication then picks up.
+fn log_in(
+    conn: &mut PgConnection,
+    session: &SessionExtension,
+    headers: &HeaderMap,
+    user_id: i32,
+) -> QueryResult<()> {
+    let (ip_address, user_agent) = client_info(headers);
+    let persistent_session = PersistentSession::create(conn, user_id, ip_address, user_agent)?;
+    SecurityLogEntry::record(
+        conn,
+        user_id,
+        SecurityEvent::Login,
+        None,
+        ip_address,
+        user_agent,
+    )?;
+
+    session.insert(SESSION_TOKEN_KEY.to_string(), persistent_session.plaintext);
+    Ok(())
+}
+
 /// Handles the `DELETE /api/private/session` route.
-pub async fn logout(session: SessionExtension) -> Json<bool> {
-    session.remove("user_id");
-    Json(true)
+pub async fn logout(app: AppState, session: SessionExtension) -> AppResult<Json<bool>> {
+    conduit_compat(move || {
+        let token = session.remove(SESSION_TOKEN_KEY);
+        session.remove(PENDING_LOGIN_KEY);
+
+        // Revoke the persistent session, so that copies of the cookie can't be
+        // used anymore either
+        if let Some(token) = token {
+            let conn = &mut *app.db_write()?;
+            if let Some(session) = PersistentSession::find_by_token(conn, &token)? {
+                session.revoke(conn)?;
+            }
+        }
+
+        Ok(Json(true))
+    })
+    .await
 }
 
 #[cfg(test)]
@@ -178,4 +677,28 @@ mod tests {
             "Creating a User from a GitHub user failed when it shouldn't have, {result:?}"
         );
     }
+
+    #[test]
+    fn pending_logins_expire() {
+        let pending_login = PendingLogin {
+            user_id: 42,
+            started_at: 1_681_000_000,
+            failed_attempts: 2,
+        };
+        assert_eq!(pending_login.encode(), "42:1681000000:2");
+        assert_some_eq!(PendingLogin::parse("42:1681000000:2"), pending_login);
+
+        // Values of the sessions that were started before the expiry was added
+        assert_none!(PendingLogin::parse("42"));
+        assert_none!(PendingLogin::parse("42:1681000000:2:1"));
+
+        assert!(!pending_login.is_expired(1_681_000_000 + PENDING_LOGIN_TTL_SECONDS));
+        assert!(pending_login.is_expired(1_681_000_001 + PENDING_LOGIN_TTL_SECONDS));
+
+        let failed = PendingLogin {
+            failed_attempts: MAX_PENDING_LOGIN_ATTEMPTS,
+            ..pending_login
+        };
+        assert!(failed.is_expired(1_681_000_000));
+    }
 }
@@ -0,0 +1,55 @@
+//! Endpoints for managing the login sessions of the authenticated user.
+//!
+//! Every login creates a persistent session, whose ID is stored in the signed
+//! session cookie. Revoking a session logs out the browser that uses it.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::models::PersistentSession;
+use crate::views::EncodablePersistentSession;
+
+/// Handles the `GET /me/sessions` route.
+pub async fn list(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let current_id = auth.session_id();
+
+        let sessions = PersistentSession::active_for_user(conn, auth.user_id())?
+            .into_iter()
+            .map(|session| EncodablePersistentSession::new(session, current_id))
+            .collect::<Vec<_>>();
+
+        Ok(Json(json!({ "sessions": sessions })))
+    })
+    .await
+}
+
+/// Handles the `DELETE /me/sessions` route.
+///
+/// Revokes all sessions of the user, except for the one that the request was
+/// made with.
+pub async fn revoke_others(app: AppState, req: Parts) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+
+        PersistentSession::revoke_all_for_user(conn, auth.user_id(), auth.session_id())?;
+
+        ok_true()
+    })
+    .await
+}
+
+/// Handles the `DELETE /me/sessions/:id` route.
+pub async fn revoke(app: AppState, Path(id): Path<i64>, req: Parts) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let user_id = AuthCheck::only_cookie().check(&req, conn)?.user_id();
+
+        PersistentSession::find_active(conn, id, user_id)?.revoke(conn)?;
+
+        ok_true()
+    })
+    .await
+}
@@ -0,0 +1,136 @@
+//! Endpoints for managing the one-time password authenticator of a user.
+//!
+//! The authenticator is used to confirm logins, sensitive actions like owner
+//! changes, and publishes of crates whose owners require it, see
+//! `krate::publish_2fa`. Enrolling is a two step process: the secret is
+//! generated first, and the authenticator is only enabled once the user proves
+//! that they added it to their app by sending a valid code.
+//!
+//! When the authenticator is enabled, the user receives a set of recovery
+//! codes, which are accepted instead of a one-time password in case the
+//! authenticator is lost. Users that lost both can ask the crates.io team to
+//! reset their authenticator, see `admin::reset_totp`. Security keys are an
+//! alternative second factor, see `user::webauthn`.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::controllers::helpers::second_factor::ensure_sudo_mode;
+use crate::models::{TotpCredential, TotpRecoveryCode};
+
+#[derive(Deserialize)]
+struct CodeRequest {
+    code: String,
+}
+
+/// Handles the `POST /me/totp` route.
+///
+/// The session has to be in sudo mode, so that a stolen session can't add an
+/// authenticator of its own.
+pub async fn enroll(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        ensure_sudo_mode(&app, conn, &req, &auth)?;
+        let user = auth.user();
+
+        if TotpCredential::is_enabled_for(conn, user.id)? {
+            return Err(bad_request(
+                "an authenticator is already enabled, disable it before adding a new one",
+            ));
+        }
+
+        let credential = TotpCredential::enroll(conn, user.id)?;
+
+        Ok(Json(json!({
+            "totp": {
+                "provisioning_uri": credential.provisioning_uri(&user.gh_login),
+            }
+        })))
+    })
+    .await
+}
+
+/// Handles the `PUT /me/totp` route.
+///
+/// The response contains the recovery codes of the user, which are not shown
+/// again later.
+pub async fn enable(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let request: CodeRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid request: {e}")))?;
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+
+        let credential = TotpCredential::find(conn, auth.user_id())?
+            .ok_or_else(|| bad_request("no authenticator was added yet"))?;
+        if credential.enabled {
+            return Err(bad_request("the authenticator is already enabled"));
+        }
+
+        if !credential.verify(conn, &request.code)? {
+            return Err(bad_request("invalid one-time password"));
+        }
+
+        let recovery_codes = conn.transaction(|conn| {
+            credential.enable(conn)?;
+            TotpRecoveryCode::regenerate(conn, credential.user_id)
+        })?;
+
+        Ok(Json(
+            json!({ "ok": true, "recovery_codes": recovery_codes }),
+        ))
+    })
+    .await
+}
+
+/// Handles the `DELETE /me/totp` route.
+pub async fn disable(app: AppState, req: BytesRequest) -> AppResult<Response> {
+    conduit_compat(move || {
+        let request: CodeRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid request: {e}")))?;
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+
+        let credential = TotpCredential::find(conn, auth.user_id())?
+            .filter(|credential| credential.enabled)
+            .ok_or_else(|| bad_request("no authenticator is enabled"))?;
+
+        if !credential.verify_or_redeem(conn, &request.code)? {
+            return Err(bad_request("invalid one-time password"));
+        }
+
+        credential.delete(conn)?;
+
+        ok_true()
+    })
+    .await
+}
+
+/// Handles the `POST /me/totp/recovery_codes` route.
+///
+/// Replaces the recovery codes of the user with new ones, for example after
+/// some of them were used up.
+pub async fn regenerate_recovery_codes(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let request: CodeRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid request: {e}")))?;
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+
+        let credential = TotpCredential::find(conn, auth.user_id())?
+            .filter(|credential| credential.enabled)
+            .ok_or_else(|| bad_request("no authenticator is enabled"))?;
+
+        if !credential.verify(conn, &request.code)? {
+            return Err(bad_request("invalid one-time password"));
+        }
+
+        let recovery_codes = TotpRecoveryCode::regenerate(conn, credential.user_id)?;
+
+        Ok(Json(json!({ "recovery_codes": recovery_codes })))
+    })
+    .await
+}
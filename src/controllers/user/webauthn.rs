@@ -0,0 +1,238 @@
+//! Endpoints for managing the security keys of a user.
+//!
+//! WebAuthn credentials are an alternative second factor to the one-time
+//! password authenticator (see `user::totp`), and are used to confirm logins
+//! and sensitive actions, see `helpers::second_factor`. Every ceremony starts
+//! with a `POST` request that returns the options for the browser API,
+//! including a new challenge, and is completed with a `PUT` request that
+//! contains the credential that the browser API returned, in the JSON format
+//! of `PublicKeyCredential.toJSON()`. The ceremonies are verified by the
+//! `webauthn-rs` crate.
+
+use crate::auth::AuthCheck;
+use crate::controllers::frontend_prelude::*;
+use crate::controllers::helpers::second_factor::{ensure_second_factor, ensure_sudo_mode};
+use crate::models::{WebauthnCeremony, WebauthnChallenge, WebauthnCredential};
+use crate::util::errors::{internal, not_found};
+use webauthn_rs::prelude::{
+    PublicKeyCredential, RegisterPublicKeyCredential, SecurityKeyAuthentication,
+    SecurityKeyRegistration, Url, Uuid, Webauthn, WebauthnBuilder, WebauthnError,
+};
+
+const CLONED_SECURITY_KEY: &str = "invalid security key response: the signature counter did \
+    not increase, the authenticator may have been cloned";
+
+/// Returns the relying party that credentials are scoped to, which is the
+/// domain of crates.io.
+fn webauthn(app: &AppState) -> AppResult<Webauthn> {
+    let rp_id = &app.config.domain_name;
+    let rp_origin = Url::parse(&format!("https://{rp_id}")).map_err(internal)?;
+    WebauthnBuilder::new(rp_id, &rp_origin)
+        .and_then(|builder| builder.rp_name("crates.io").build())
+        .map_err(internal)
+}
+
+/// The WebAuthn user handle of the user, which is derived from their ID.
+fn user_handle(user_id: i32) -> Uuid {
+    Uuid::from_u128(user_id as u128)
+}
+
+/// Handles the `POST /me/webauthn/registrations` route.
+///
+/// The session has to be in sudo mode, so that a stolen session can't add a
+/// security key of its own.
+pub async fn begin_registration(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        ensure_sudo_mode(&app, conn, &req, &auth)?;
+        let user = auth.user();
+
+        let exclude_credentials = WebauthnCredential::for_user(conn, user.id)?
+            .into_iter()
+            .map(|credential| credential.credential_id.into())
+            .collect();
+
+        let (options, state) = webauthn(&app)?
+            .start_securitykey_registration(
+                user_handle(user.id),
+                &user.gh_login,
+                user.name.as_deref().unwrap_or(&user.gh_login),
+                Some(exclude_credentials),
+                None,
+                None,
+            )
+            .map_err(internal)?;
+
+        WebauthnChallenge::issue(conn, user.id, WebauthnCeremony::Registration, &state)?;
+
+        Ok(Json(json!(options)))
+    })
+    .await
+}
+
+/// Handles the `PUT /me/webauthn/registrations` route.
+///
+/// ## Request Body Example
+///
+/// ```json
+/// {
+///     "name": "My security key",
+///     "credential": {
+///         "id": "...",
+///         "rawId": "...",
+///         "type": "public-key",
+///         "response": { "attestationObject": "...", "clientDataJSON": "..." }
+///     }
+/// }
+/// ```
+pub async fn finish_registration(app: AppState, req: BytesRequest) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        #[derive(Deserialize)]
+        struct RegistrationRequest {
+            name: String,
+            credential: RegisterPublicKeyCredential,
+        }
+
+        let request: RegistrationRequest = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid request: {e}")))?;
+
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Err(bad_request("name must have a value"));
+        }
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user_id = auth.user_id();
+
+        let state: SecurityKeyRegistration =
+            WebauthnChallenge::take(conn, user_id, WebauthnCeremony::Registration)?
+                .ok_or_else(|| bad_request("the registration has expired, please try again"))?;
+
+        let credential_id = &request.credential.raw_id.0;
+        if WebauthnCredential::find_by_credential_id(conn, user_id, credential_id)?.is_some() {
+            return Err(bad_request("this credential is already registered"));
+        }
+
+        let security_key = webauthn(&app)?
+            .finish_securitykey_registration(&request.credential, &state)
+            .map_err(|e| bad_request(&format_args!("invalid credential: {e}")))?;
+
+        let credential = WebauthnCredential::create(conn, user_id, name, &security_key)?;
+
+        Ok(Json(json!({ "credential": credential })))
+    })
+    .await
+}
+
+/// Handles the `GET /me/webauthn/credentials` route.
+pub async fn list(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read_prefer_primary()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+
+        let credentials = WebauthnCredential::for_user(conn, auth.user_id())?;
+
+        Ok(Json(json!({ "credentials": credentials })))
+    })
+    .await
+}
+
+/// Handles the `DELETE /me/webauthn/credentials/:id` route.
+///
+/// Removing a second factor is itself confirmed with a second factor.
+pub async fn delete(app: AppState, Path(id): Path<i32>, req: Parts) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+        let user_id = auth.user_id();
+
+        let credential = WebauthnCredential::find(conn, user_id, id)
+            .optional()?
+            .ok_or_else(not_found)?;
+
+        ensure_second_factor(&app, conn, &req, user_id)?;
+
+        credential.delete(conn)?;
+
+        ok_true()
+    })
+    .await
+}
+
+/// Handles the `POST /me/webauthn/assertions` route.
+///
+/// Starts an authentication ceremony to confirm a sensitive action. The
+/// response of the authenticator is then passed along with the action, see
+/// `helpers::second_factor`.
+pub async fn begin_assertion(app: AppState, req: Parts) -> AppResult<Json<Value>> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::only_cookie().check(&req, conn)?;
+
+        Ok(Json(request_options(&app, conn, auth.user_id())?))
+    })
+    .await
+}
+
+/// Issues an authentication challenge for the user and returns the options for
+/// `navigator.credentials.get()`.
+pub(crate) fn request_options(
+    app: &AppState,
+    conn: &mut PgConnection,
+    user_id: i32,
+) -> AppResult<Value> {
+    let security_keys = WebauthnCredential::for_user(conn, user_id)?
+        .iter()
+        .map(WebauthnCredential::security_key)
+        .collect::<Result<Vec<_>, _>>()?;
+    if security_keys.is_empty() {
+        return Err(bad_request("no security key is registered"));
+    }
+
+    let (options, state) = webauthn(app)?
+        .start_securitykey_authentication(&security_keys)
+        .map_err(internal)?;
+
+    WebauthnChallenge::issue(conn, user_id, WebauthnCeremony::Authentication, &state)?;
+
+    Ok(json!(options))
+}
+
+/// Verifies the response to the pending authentication challenge of the user,
+/// and records the new signature counter of the credential.
+///
+/// Authenticators that support counters increase them with every assertion,
+/// so a counter that did not increase indicates that the credential was
+/// cloned.
+pub(crate) fn verify_assertion(
+    app: &AppState,
+    conn: &mut PgConnection,
+    user_id: i32,
+    response: &PublicKeyCredential,
+) -> AppResult<()> {
+    let state: SecurityKeyAuthentication =
+        WebauthnChallenge::take(conn, user_id, WebauthnCeremony::Authentication)?.ok_or_else(
+            || bad_request("the security key challenge has expired, please try again"),
+        )?;
+
+    let credential =
+        WebauthnCredential::find_by_credential_id(conn, user_id, response.get_credential_id())?
+            .ok_or_else(|| bad_request("unknown security key"))?;
+
+    let result = webauthn(app)?
+        .finish_securitykey_authentication(response, &state)
+        .map_err(|e| match e {
+            WebauthnError::CredentialPossibleCompromise => bad_request(CLONED_SECURITY_KEY),
+            e => bad_request(&format_args!("invalid security key response: {e}")),
+        })?;
+
+    let mut security_key = credential.security_key()?;
+    security_key.update_credential(&result);
+    if !credential.record_use(conn, &security_key, result.counter())? {
+        return Err(bad_request(CLONED_SECURITY_KEY));
+    }
+
+    Ok(())
+}
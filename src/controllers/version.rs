@@ -1,6 +1,9 @@
 pub mod deprecated;
+pub mod deprecation;
+pub mod docs_rs;
 pub mod downloads;
 pub mod metadata;
+pub mod signature;
 pub mod yank;
 
 use super::prelude::*;
@@ -0,0 +1,67 @@
+//! Endpoint for deprecating a specific version of a crate.
+
+use super::version_and_crate;
+use crate::auth::AuthCheck;
+use crate::controllers::cargo_prelude::*;
+use crate::controllers::krate::deprecation::validate_message;
+use crate::models::token::EndpointScope;
+use crate::models::Rights;
+use crate::schema::versions;
+use crate::worker;
+
+#[derive(Deserialize)]
+struct DeprecationRequest {
+    deprecated: bool,
+    message: Option<String>,
+}
+
+/// Handles the `PUT /crates/:crate_id/:version/deprecation` route.
+///
+/// Unlike yanked versions, deprecated versions can still be used by new
+/// dependencies. The request body contains the new `deprecated` flag and, if
+/// the version is deprecated, an optional `message` that takes precedence
+/// over the message of the crate.
+pub async fn update(
+    app: AppState,
+    Path((crate_name, version)): Path<(String, String)>,
+    req: BytesRequest,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let request: DeprecationRequest = serde_json::from_slice(req.body())
+            .map_err(|e| cargo_err(&format!("invalid deprecation request: {e}")))?;
+
+        if semver::Version::parse(&version).is_err() {
+            return Err(cargo_err(&format_args!("invalid semver: {version}")));
+        }
+
+        let conn = &mut *app.db_write()?;
+        let auth = AuthCheck::default()
+            .with_endpoint_scope(EndpointScope::Yank)
+            .for_crate(&crate_name)
+            .check(&req, conn)?;
+
+        let (version, krate) = version_and_crate(conn, &crate_name, &version)?;
+        let owners = krate.owners(conn)?;
+        if auth.user().rights(&app, conn, &owners)? < Rights::Publish {
+            return Err(cargo_err("must already be an owner to deprecate a version"));
+        }
+
+        let message = if request.deprecated {
+            validate_message(request.message)?
+        } else {
+            None
+        };
+
+        diesel::update(&version)
+            .set((
+                versions::deprecated.eq(request.deprecated),
+                versions::deprecation_message.eq(&message),
+            ))
+            .execute(conn)?;
+
+        worker::sync_deprecated(krate.name).enqueue(conn)?;
+
+        ok_true()
+    })
+    .await
+}
@@ -0,0 +1,73 @@
+//! Endpoint for docs.rs to report the outcome of documentation builds
+
+use chrono::Utc;
+
+use super::version_and_crate;
+use crate::controllers::frontend_prelude::*;
+use crate::schema::versions;
+use crate::util::errors::forbidden;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum BuildStatus {
+    Success,
+    Failure,
+}
+
+impl BuildStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+        }
+    }
+}
+
+/// Handles the `PUT /api/private/docs_rs/crates/:crate_id/:version` route.
+///
+/// The request must carry the configured `DOCS_RS_CALLBACK_TOKEN` as a bearer
+/// token, and the body must be `{"status": "success"}` or
+/// `{"status": "failure"}`.
+pub async fn update_status(
+    app: AppState,
+    Path((crate_name, version)): Path<(String, String)>,
+    req: BytesRequest,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        // The endpoint is disabled unless a token is configured
+        let Some(expected_token) = &app.config.docs_rs_callback_token else {
+            return Err(forbidden());
+        };
+
+        let provided_token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        if provided_token != Some(expected_token.as_str()) {
+            return Err(forbidden());
+        }
+
+        #[derive(Deserialize)]
+        struct StatusUpdate {
+            status: BuildStatus,
+        }
+
+        let update: StatusUpdate = serde_json::from_slice(req.body())
+            .map_err(|e| bad_request(&format!("invalid docs.rs status update: {e}")))?;
+
+        let conn = &mut *app.db_write()?;
+        let (version, _) = version_and_crate(conn, &crate_name, &version)?;
+
+        diesel::update(&version)
+            .set((
+                versions::docs_rs_status.eq(update.status.as_str()),
+                versions::docs_rs_updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+
+        Ok(StatusCode::NO_CONTENT.into_response())
+    })
+    .await
+}
@@ -6,10 +6,14 @@ use super::version_and_crate;
 use crate::controllers::prelude::*;
 use crate::db::PoolError;
 use crate::middleware::log_request::RequestLogExt;
-use crate::models::{Crate, VersionDownload};
+use crate::models::{Crate, CrateFile, VersionDownload};
 use crate::schema::*;
+use crate::util::errors::bad_request;
+use crate::util::hyperloglog::HyperLogLog;
 use crate::views::EncodableVersionDownload;
-use chrono::{Duration, NaiveDate, Utc};
+use crate::App;
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use indexmap::IndexMap;
 
 /// Handles the `GET /crates/:crate_id/:version/download` route.
 /// This returns a URL to the location where the crate is stored.
@@ -26,7 +30,7 @@ pub async fn download(
 
         // The increment does not happen instantly, but it's deferred to be executed in a batch
         // along with other downloads. See crate::downloads_counter for the implementation.
-        app.downloads_counter.increment(version_id);
+        count_download(&app, version_id);
 
         (crate_name, version)
     } else {
@@ -67,7 +71,7 @@ pub async fn download(
 
                 // The increment does not happen instantly, but it's deferred to be executed in a batch
                 // along with other downloads. See crate::downloads_counter for the implementation.
-                app.downloads_counter.increment(version_id);
+                count_download(&app, version_id);
 
                 if canonical_crate_name != crate_name {
                     app.instance_metrics
@@ -115,7 +119,13 @@ pub async fn download(
         .await?
     };
 
-    let redirect_url = app.config.uploader().crate_location(&crate_name, &version);
+    let redirect_url = if app.config.uploader().is_content_addressed() {
+        let app = app.clone();
+        conduit_compat(move || crate_file_location(&app, &crate_name, &version)).await?
+    } else {
+        app.config.uploader().crate_location(&crate_name, &version)
+    };
+
     if wants_json {
         Ok(Json(json!({ "url": redirect_url })).into_response())
     } else {
@@ -123,7 +133,115 @@ pub async fn download(
     }
 }
 
+/// Returns the location of the crate file of a version in the
+/// content-addressed layout.
+///
+/// Crate files of versions without a recorded key, which were published before
+/// the layout was enabled, are still stored under their crate name and
+/// version. That's also where requests are redirected to if the database is
+/// unavailable, or unconditional redirects are forced.
+fn crate_file_location(app: &App, crate_name: &str, version: &str) -> AppResult<String> {
+    let uploader = app.config.uploader();
+    let object_key = if app.config.force_unconditional_redirects {
+        None
+    } else {
+        match app.db_read_prefer_primary() {
+            Ok(mut conn) => CrateFile::object_key_by_name(&mut conn, crate_name, version)?,
+            Err(PoolError::UnhealthyPool) => None,
+            Err(err) => return Err(err.into()),
+        }
+    };
+
+    Ok(match object_key {
+        Some(object_key) => uploader.crate_object_location(&object_key),
+        None => uploader.crate_location(crate_name, version),
+    })
+}
+
+/// Counts a download of the version, unless downloads are counted from the
+/// access logs of the CDN instead. The redirected request shows up in these
+/// logs as well, so counting it here would count it twice.
+fn count_download(app: &App, version_id: i32) {
+    if !app.config.downloads_counted_from_cdn_logs {
+        app.downloads_counter.increment(version_id);
+    }
+}
+
+/// Ranges of up to this many days are returned as daily counts by default.
+const MAX_DAILY_RANGE_DAYS: i64 = 90;
+
+/// Ranges of up to this many days are aggregated by week by default, longer
+/// ranges by month.
+const MAX_WEEKLY_RANGE_DAYS: i64 = 731;
+
+/// The length of the buckets that download counts are aggregated into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Interval {
+    Day,
+    Week,
+    Month,
+}
+
+impl Interval {
+    fn parse(interval: &str) -> AppResult<Self> {
+        match interval {
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            _ => Err(bad_request(&format_args!(
+                "invalid interval `{interval}`, expected `day`, `week` or `month`"
+            ))),
+        }
+    }
+
+    /// Picks an interval that keeps the number of buckets manageable for
+    /// long ranges.
+    fn for_range(start: NaiveDate, end: NaiveDate) -> Self {
+        let days = (end - start).num_days() + 1;
+        if days <= MAX_DAILY_RANGE_DAYS {
+            Self::Day
+        } else if days <= MAX_WEEKLY_RANGE_DAYS {
+            Self::Week
+        } else {
+            Self::Month
+        }
+    }
+
+    /// Returns the first day of the bucket containing `date`. Weeks start on
+    /// Mondays, like `date_trunc('week', ...)` in PostgreSQL.
+    fn bucket_start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => date,
+            Self::Week => date - Duration::days(date.weekday().num_days_from_monday().into()),
+            Self::Month => date.with_day(1).expect("every month has a first day"),
+        }
+    }
+}
+
+pub(crate) fn parse_date(
+    params: &IndexMap<String, String>,
+    name: &str,
+) -> AppResult<Option<NaiveDate>> {
+    params
+        .get(name)
+        .map(|date| {
+            NaiveDate::parse_from_str(date, "%F").map_err(|_| {
+                bad_request(&format_args!(
+                    "invalid {name} date `{date}`, expected the format YYYY-MM-DD"
+                ))
+            })
+        })
+        .transpose()
+}
+
 /// Handles the `GET /crates/:crate_id/:version/downloads` route.
+///
+/// Returns the download counts of the version between the `start` and `end`
+/// dates (inclusive), which default to the last 90 days. The counts are
+/// aggregated into buckets of the requested `interval`, or of an interval
+/// chosen by the length of the range. The `date` of each bucket is its first
+/// day, and buckets without downloads are omitted.
 pub async fn downloads(
     app: AppState,
     Path((crate_name, version)): Path<(String, String)>,
@@ -134,25 +252,125 @@ pub async fn downloads(
             return Err(cargo_err(&format_args!("invalid semver: {version}")));
         }
 
+        let params = req.query();
+
+        // `before_date` is the previous name of `end`, which is still used
+        // by older clients and silently ignored if invalid
+        let end = match parse_date(&params, "end")? {
+            Some(end) => end,
+            None => params
+                .get("before_date")
+                .and_then(|d| NaiveDate::parse_from_str(d, "%F").ok())
+                .unwrap_or_else(|| Utc::now().date_naive()),
+        };
+        let start = parse_date(&params, "start")?
+            .unwrap_or_else(|| end - Duration::days(MAX_DAILY_RANGE_DAYS - 1));
+        if start > end {
+            return Err(bad_request("the start date must not be after the end date"));
+        }
+
+        let interval = match params.get("interval") {
+            Some(interval) => Interval::parse(interval)?,
+            None => Interval::for_range(start, end),
+        };
+
         let conn = &mut *app.db_read()?;
         let (version, _) = version_and_crate(conn, &crate_name, &version)?;
 
-        let cutoff_end_date = req
-            .query()
-            .get("before_date")
-            .and_then(|d| NaiveDate::parse_from_str(d, "%F").ok())
-            .unwrap_or_else(|| Utc::now().date_naive());
-        let cutoff_start_date = cutoff_end_date - Duration::days(89);
-
-        let downloads = VersionDownload::belonging_to(&version)
-            .filter(version_downloads::date.between(cutoff_start_date, cutoff_end_date))
+        let downloads: Vec<VersionDownload> = VersionDownload::belonging_to(&version)
+            .filter(version_downloads::date.between(start, end))
             .order(version_downloads::date)
-            .load(conn)?
+            .load(conn)?;
+
+        // The unique clients of a bucket are estimated from the union of the
+        // sketches of its days, since the same client may download the
+        // version on several days
+        let mut buckets: Vec<(NaiveDate, i32, Option<HyperLogLog>)> = Vec::new();
+        for download in downloads {
+            let bucket_start = interval.bucket_start(download.date);
+            let sketch = download
+                .unique_clients
+                .as_deref()
+                .and_then(HyperLogLog::from_bytes);
+            match buckets.last_mut() {
+                Some((date, count, bucket_sketch)) if *date == bucket_start => {
+                    *count = count.saturating_add(download.downloads);
+                    if let Some(sketch) = sketch {
+                        match bucket_sketch {
+                            Some(bucket_sketch) => bucket_sketch.merge(&sketch),
+                            None => *bucket_sketch = Some(sketch),
+                        }
+                    }
+                }
+                _ => buckets.push((bucket_start, download.downloads, sketch)),
+            }
+        }
+
+        let downloads = buckets
             .into_iter()
-            .map(VersionDownload::into)
-            .collect::<Vec<EncodableVersionDownload>>();
+            .map(|(date, downloads, sketch)| EncodableVersionDownload {
+                version: version.id,
+                downloads,
+                date: date.to_string(),
+                unique_clients: sketch.map(|sketch| sketch.estimate()),
+            })
+            .collect::<Vec<_>>();
 
-        Ok(Json(json!({ "version_downloads": downloads })))
+        Ok(Json(json!({
+            "version_downloads": downloads,
+            "meta": {
+                "start": start.to_string(),
+                "end": end.to_string(),
+                "interval": interval,
+            },
+        })))
     })
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%F").unwrap()
+    }
+
+    #[test]
+    fn interval_for_range() {
+        let start = date("2023-01-01");
+        assert_eq!(
+            Interval::for_range(start, date("2023-01-01")),
+            Interval::Day
+        );
+        assert_eq!(
+            Interval::for_range(start, date("2023-03-31")),
+            Interval::Day
+        );
+        assert_eq!(
+            Interval::for_range(start, date("2023-04-01")),
+            Interval::Week
+        );
+        assert_eq!(
+            Interval::for_range(start, date("2024-12-31")),
+            Interval::Week
+        );
+        assert_eq!(
+            Interval::for_range(start, date("2025-01-02")),
+            Interval::Month
+        );
+    }
+
+    #[test]
+    fn bucket_start() {
+        // 2023-03-15 is a Wednesday
+        let wednesday = date("2023-03-15");
+        assert_eq!(Interval::Day.bucket_start(wednesday), wednesday);
+        assert_eq!(Interval::Week.bucket_start(wednesday), date("2023-03-13"));
+        assert_eq!(
+            Interval::Week.bucket_start(date("2023-03-13")),
+            date("2023-03-13")
+        );
+        assert_eq!(Interval::Month.bucket_start(wednesday), date("2023-03-01"));
+    }
+}
@@ -0,0 +1,41 @@
+//! Endpoint for the signatures that were uploaded together with versions.
+//!
+//! Publishers can append a detached signature or a Sigstore bundle of the
+//! crate file to the publish request (see `krate::publish`). The signature is
+//! stored next to the crate file, so that verifiers can check the provenance
+//! of a download.
+
+use super::version_and_crate;
+use crate::controllers::prelude::*;
+use crate::models::VersionSignature;
+use crate::util::errors::not_found;
+use crate::views::EncodableVersionSignature;
+
+/// Handles the `GET /crates/:crate_id/:version/signature` route.
+///
+/// Redirects to the stored signature, or returns its metadata if JSON is
+/// requested.
+pub async fn show(
+    app: AppState,
+    Path((crate_name, version)): Path<(String, String)>,
+    req: Parts,
+) -> AppResult<Response> {
+    conduit_compat(move || {
+        let conn = &mut *app.db_read()?;
+        let (version, krate) = version_and_crate(conn, &crate_name, &version)?;
+        let signature = VersionSignature::find(conn, version.id)?.ok_or_else(not_found)?;
+
+        let url = app
+            .config
+            .uploader()
+            .signature_location(&krate.name, &version.num);
+
+        if req.wants_json() {
+            let signature = EncodableVersionSignature::new(signature, url);
+            Ok(Json(json!({ "signature": signature })).into_response())
+        } else {
+            Ok(redirect(url))
+        }
+    })
+    .await
+}
@@ -4,9 +4,10 @@ use crate::auth::AuthCheck;
 
 use super::version_and_crate;
 use crate::controllers::cargo_prelude::*;
+use crate::controllers::util::RequestPartsExt;
 use crate::models::token::EndpointScope;
-use crate::models::Rights;
-use crate::models::{insert_version_owner_action, VersionAction};
+use crate::models::{insert_version_owner_action, VersionAction, WebhookEvent};
+use crate::models::{Crate, Rights};
 use crate::schema::versions;
 use crate::worker;
 
@@ -15,6 +16,11 @@ use crate::worker;
 /// version accessible only to crates that already have a
 /// `Cargo.lock` containing this version.
 ///
+/// The request body is optional. If present, it may contain a `reason` for
+/// the yank and a suggested `replacement`, which is either a version of the
+/// same crate or `<crate>@<version>`. Both are shown in the version API and
+/// the index entry of the yanked version.
+///
 /// Notes:
 /// Crate deletion is not implemented to avoid breaking builds,
 /// and the goal of yanking a crate is to prevent crates
@@ -22,9 +28,13 @@ use crate::worker;
 pub async fn yank(
     app: AppState,
     Path((crate_name, version)): Path<(String, String)>,
-    req: Parts,
+    req: BytesRequest,
 ) -> AppResult<Response> {
-    conduit_compat(move || modify_yank(&crate_name, &version, &app, &req, true)).await
+    conduit_compat(move || {
+        let details = YankDetails::from_body(req.body())?;
+        modify_yank(&crate_name, &version, &app, &req, true, details)
+    })
+    .await
 }
 
 /// Handles the `PUT /crates/:crate_id/:version/unyank` route.
@@ -33,7 +43,67 @@ pub async fn unyank(
     Path((crate_name, version)): Path<(String, String)>,
     req: Parts,
 ) -> AppResult<Response> {
-    conduit_compat(move || modify_yank(&crate_name, &version, &app, &req, false)).await
+    conduit_compat(move || {
+        let details = YankDetails::default();
+        modify_yank(&crate_name, &version, &app, &req, false, details)
+    })
+    .await
+}
+
+/// The maximum length of the `reason` given when yanking a version.
+const MAX_REASON_LENGTH: usize = 256;
+
+/// The optional body of a yank request.
+#[derive(Default, Deserialize)]
+struct YankDetails {
+    reason: Option<String>,
+    replacement: Option<String>,
+}
+
+impl YankDetails {
+    fn from_body(body: &[u8]) -> AppResult<Self> {
+        if body.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let details: Self = serde_json::from_slice(body)
+            .map_err(|e| cargo_err(&format!("invalid yank request: {e}")))?;
+
+        let reason = details.reason.map(|reason| reason.trim().to_string());
+        let reason = reason.filter(|reason| !reason.is_empty());
+        if let Some(reason) = &reason {
+            if reason.chars().count() > MAX_REASON_LENGTH {
+                return Err(cargo_err(&format_args!(
+                    "the yank reason must not be longer than {MAX_REASON_LENGTH} characters"
+                )));
+            }
+        }
+
+        let replacement = details.replacement.filter(|r| !r.is_empty());
+        if let Some(replacement) = &replacement {
+            if !is_valid_replacement(replacement) {
+                return Err(cargo_err(&format_args!(
+                    "invalid yank replacement `{replacement}`, expected \
+                     `<version>` or `<crate>@<version>`"
+                )));
+            }
+        }
+
+        Ok(Self {
+            reason,
+            replacement,
+        })
+    }
+}
+
+fn is_valid_replacement(replacement: &str) -> bool {
+    let version = match replacement.split_once('@') {
+        Some((name, version)) if Crate::valid_name(name) => version,
+        Some(_) => return false,
+        None => replacement,
+    };
+
+    semver::Version::parse(version).is_ok()
 }
 
 /// Changes `yanked` flag on a crate version record
@@ -41,8 +111,9 @@ fn modify_yank(
     crate_name: &str,
     version: &str,
     state: &AppState,
-    req: &Parts,
+    req: &impl RequestPartsExt,
     yanked: bool,
+    details: YankDetails,
 ) -> AppResult<Response> {
     // FIXME: Should reject bad requests before authentication, but can't due to
     // lifetime issues with `req`.
@@ -63,27 +134,54 @@ fn modify_yank(
     let user = auth.user();
     let owners = krate.owners(conn)?;
 
-    if user.rights(state, &owners)? < Rights::Publish {
+    if user.rights(state, conn, &owners)? < Rights::Publish {
         return Err(cargo_err("must already be an owner to yank or unyank"));
     }
 
-    if version.yanked == yanked {
+    // Yanking an already yanked version again only updates the reason or
+    // replacement that was given, and keeps the other one
+    let details = if yanked && version.yanked {
+        YankDetails {
+            reason: details.reason.or_else(|| version.yank_reason.clone()),
+            replacement: details
+                .replacement
+                .or_else(|| version.yank_replacement.clone()),
+        }
+    } else {
+        details
+    };
+
+    let details_unchanged =
+        version.yank_reason == details.reason && version.yank_replacement == details.replacement;
+
+    if version.yanked == yanked && details_unchanged {
         // The crate is already in the state requested, nothing to do
         return ok_true();
     }
 
     diesel::update(&version)
-        .set(versions::yanked.eq(yanked))
+        .set((
+            versions::yanked.eq(yanked),
+            versions::yank_reason.eq(&details.reason),
+            versions::yank_replacement.eq(&details.replacement),
+        ))
         .execute(conn)?;
 
-    let action = if yanked {
-        VersionAction::Yank
+    let (action, event) = if yanked {
+        (VersionAction::Yank, WebhookEvent::Yank)
     } else {
-        VersionAction::Unyank
+        (VersionAction::Unyank, WebhookEvent::Unyank)
     };
 
     insert_version_owner_action(conn, version.id, user.id, api_token_id, action)?;
 
+    let data = json!({
+        "version": version.num,
+        "reason": details.reason,
+        "replacement": details.replacement,
+    });
+    worker::trigger_webhooks(conn, krate.id, &krate.name, event, data)?;
+
     worker::sync_yanked(krate.name, version.num).enqueue(conn)?;
 
     ok_true()
@@ -1,10 +1,20 @@
 use crate::App;
 use anyhow::Error;
-use dashmap::{DashMap, SharedValue};
+use dashmap::DashMap;
 use diesel::{pg::upsert::excluded, prelude::*};
 use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
 
+use self::journal::{read_journal_file, DownloadsJournal};
+
+mod journal;
+
+/// The pending downloads of a shard, together with the journal file that recorded them.
+type TakenShard = (Vec<(i32, usize)>, Option<PathBuf>);
+
 /// crates.io receives a lot of download requests, and we can't execute a write query to the
 /// database during each connection for performance reasons. To reduce the write load, this struct
 /// collects the pending updates from the current process and writes in batch.
@@ -19,9 +29,9 @@ use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
 /// persists a single shard at the time.
 ///
 /// The disadvantage of this approach is that download counts are stored in memory until they're
-/// persisted, so it's possible to lose some of them if the process exits ungracefully. While
-/// that's far from ideal, the advantage of batching database updates far outweights potentially
-/// losing some download counts.
+/// persisted, so it's possible to lose some of them if the process exits ungracefully. To avoid
+/// that, the downloads can also be recorded in a write-ahead journal on the local disk (see
+/// `with_journal`), which is read again by the next process.
 #[derive(Debug)]
 pub struct DownloadsCounter {
     /// Inner storage for the download counts.
@@ -31,6 +41,8 @@ pub struct DownloadsCounter {
     /// Number of downloads that are not yet persisted on the database. This is just used as a
     /// metric included in log lines, and it's not guaranteed to be accurate.
     pending_count: AtomicI64,
+    /// Journal of the downloads that are not yet persisted on the database, if enabled.
+    journal: Option<DownloadsJournal>,
 }
 
 impl DownloadsCounter {
@@ -39,10 +51,48 @@ impl DownloadsCounter {
             inner: DashMap::new(),
             shard_idx: AtomicUsize::new(0),
             pending_count: AtomicI64::new(0),
+            journal: None,
+        }
+    }
+
+    /// Creates a counter that records every download in a journal in `dir` before counting it.
+    ///
+    /// The downloads in journal files that were left behind by previous processes are counted
+    /// again, and the files are removed.
+    pub(crate) fn with_journal(dir: &Path) -> io::Result<Self> {
+        let mut counter = Self::new();
+        let (journal, leftover_files) = DownloadsJournal::open(dir, counter.shards_count())?;
+        counter.journal = Some(journal);
+
+        for path in leftover_files {
+            let version_ids = read_journal_file(&path)?;
+            info!(
+                ?path,
+                downloads = version_ids.len(),
+                "Recovering downloads from journal"
+            );
+            for version_id in version_ids {
+                counter.increment(version_id);
+            }
+            fs::remove_file(&path)?;
         }
+
+        Ok(counter)
     }
 
     pub(crate) fn increment(&self, version_id: i32) {
+        match &self.journal {
+            Some(journal) => {
+                // The download is counted while the journal file of the shard is locked, so that
+                // persisting the shard can't take the count without also rotating the journal.
+                let shard = self.inner.determine_map(&version_id);
+                journal.append(shard, version_id, || self.increment_in_memory(version_id))
+            }
+            None => self.increment_in_memory(version_id),
+        }
+    }
+
+    fn increment_in_memory(&self, version_id: i32) {
         self.pending_count.fetch_add(1, Ordering::SeqCst);
 
         if let Some(counter) = self.inner.get(&version_id) {
@@ -76,9 +126,10 @@ impl DownloadsCounter {
 
     fn persist_all_shards_with_conn(&self, conn: &mut PgConnection) -> Result<PersistStats, Error> {
         let mut stats = PersistStats::default();
-        for shard in self.inner.shards() {
-            let shard = std::mem::take(&mut *shard.write());
-            stats = stats.merge(self.persist_shard(conn, shard.iter())?);
+        for idx in 0..self.shards_count() {
+            let (shard, journal_file) = self.take_shard(idx)?;
+            stats = stats.merge(self.persist_shard(conn, shard)?);
+            remove_journal_file(journal_file)?;
         }
 
         Ok(stats)
@@ -88,19 +139,42 @@ impl DownloadsCounter {
         // Replace the next shard in the ring with an empty HashMap (clearing it), and return the
         // previous contents for processing. The fetch_add method wraps around on overflow, so it's
         // fine to keep incrementing it without resetting.
-        let shards = self.inner.shards();
-        let idx = self.shard_idx.fetch_add(1, Ordering::SeqCst) % shards.len();
-        let shard = std::mem::take(&mut *shards[idx].write());
+        let idx = self.shard_idx.fetch_add(1, Ordering::SeqCst) % self.shards_count();
+        let (shard, journal_file) = self.take_shard(idx)?;
 
-        let mut stats = self.persist_shard(conn, shard.iter())?;
+        let mut stats = self.persist_shard(conn, shard)?;
         stats.shard = Some(idx);
+        remove_journal_file(journal_file)?;
         Ok(stats)
     }
 
-    fn persist_shard<'a, Iter: Iterator<Item = (&'a i32, &'a SharedValue<AtomicUsize>)>>(
+    /// Replaces the shard with an empty HashMap (clearing it) and returns the previous contents,
+    /// together with the journal file containing them.
+    ///
+    /// If persisting the contents fails, the journal file is kept so the downloads are counted
+    /// again by the next process.
+    fn take_shard(&self, idx: usize) -> io::Result<TakenShard> {
+        let take = || {
+            let shard = std::mem::take(&mut *self.inner.shards()[idx].write());
+            shard
+                .iter()
+                .map(|(id, atomic)| (*id, atomic.get().load(Ordering::SeqCst)))
+                .collect()
+        };
+
+        match &self.journal {
+            Some(journal) => {
+                let (shard, journal_file) = journal.rotate(idx, take)?;
+                Ok((shard, Some(journal_file)))
+            }
+            None => Ok((take(), None)),
+        }
+    }
+
+    fn persist_shard(
         &self,
         conn: &mut PgConnection,
-        shard: Iter,
+        mut to_insert: Vec<(i32, usize)>,
     ) -> Result<PersistStats, Error> {
         use crate::schema::{version_downloads, versions};
 
@@ -108,10 +182,6 @@ impl DownloadsCounter {
         let mut counted_downloads = 0;
         let mut counted_versions = 0;
 
-        let mut to_insert = shard
-            .map(|(id, atomic)| (*id, atomic.get().load(Ordering::SeqCst)))
-            .collect::<Vec<_>>();
-
         if !to_insert.is_empty() {
             // The rows we're about to insert need to be sorted to avoid deadlocks when multiple
             // instances of crates.io are running at the same time.
@@ -204,6 +274,14 @@ impl DownloadsCounter {
     }
 }
 
+/// Removes a journal file after its downloads were persisted.
+fn remove_journal_file(path: Option<PathBuf>) -> io::Result<()> {
+    match path {
+        Some(path) => fs::remove_file(path),
+        None => Ok(()),
+    }
+}
+
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
 pub struct PersistStats {
     shard: Option<usize>,
@@ -290,6 +368,37 @@ mod tests {
         state.assert_downloads_count(conn, v3, 0);
     }
 
+    #[test]
+    fn test_recover_downloads_from_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = &mut crate::db::test_conn();
+        let mut state = State::new(conn);
+
+        let v1 = state.new_version(conn);
+        let v2 = state.new_version(conn);
+
+        let counter = DownloadsCounter::with_journal(dir.path()).unwrap();
+        counter.increment(v1);
+        counter.increment(v1);
+        counter.increment(v2);
+        drop(counter);
+
+        // The downloads that weren't persisted by the previous counter are counted again
+        let counter = DownloadsCounter::with_journal(dir.path()).unwrap();
+        assert_eq!(3, counter.pending_count());
+
+        counter
+            .persist_all_shards_with_conn(conn)
+            .expect("failed to persist all shards");
+        state.assert_downloads_count(conn, v1, 2);
+        state.assert_downloads_count(conn, v2, 1);
+        drop(counter);
+
+        // Persisted downloads are removed from the journal
+        let counter = DownloadsCounter::with_journal(dir.path()).unwrap();
+        assert_eq!(0, counter.pending_count());
+    }
+
     #[test]
     fn test_increment_and_persist_shard() {
         let counter = DownloadsCounter::new();
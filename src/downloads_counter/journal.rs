@@ -0,0 +1,187 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+const EXTENSION: &str = "journal";
+
+/// Size of a single record, which is the little endian version ID of a download.
+const RECORD_SIZE: usize = std::mem::size_of::<i32>();
+
+/// A write-ahead journal of the downloads that are not persisted yet.
+///
+/// Every shard of the counter has its own journal file, to which the version
+/// ID of each download is appended before it's counted in memory. When a
+/// shard is persisted, its file is swapped for a new one, and the old file is
+/// removed once the downloads were written to the database.
+///
+/// If the process exits before that, the files are left behind and the next
+/// process counts their downloads again. The records are written without
+/// calling `fsync`, so they survive crashes of the process but not of the
+/// machine.
+#[derive(Debug)]
+pub(super) struct DownloadsJournal {
+    dir: PathBuf,
+    /// Prefix of the file names, unique to this process.
+    prefix: String,
+    /// Counter used to give every rotated file a new name.
+    generation: AtomicU64,
+    files: Vec<Mutex<JournalFile>>,
+}
+
+#[derive(Debug)]
+struct JournalFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl DownloadsJournal {
+    /// Opens a journal with one file per shard in `dir`.
+    ///
+    /// Also returns the files that were left behind by previous processes.
+    pub(super) fn open(dir: &Path, shards: usize) -> io::Result<(Self, Vec<PathBuf>)> {
+        fs::create_dir_all(dir)?;
+
+        let mut leftover_files = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == EXTENSION) {
+                leftover_files.push(path);
+            }
+        }
+        leftover_files.sort();
+
+        let prefix = format!(
+            "downloads-{}-{}",
+            chrono::Utc::now().timestamp_nanos(),
+            std::process::id()
+        );
+        let mut journal = Self {
+            dir: dir.to_path_buf(),
+            prefix,
+            generation: AtomicU64::new(0),
+            files: Vec::with_capacity(shards),
+        };
+        for shard in 0..shards {
+            let file = journal.create_file(shard)?;
+            journal.files.push(Mutex::new(file));
+        }
+
+        Ok((journal, leftover_files))
+    }
+
+    /// Records a download in the journal file of the shard, and calls
+    /// `count` while the file is still locked.
+    pub(super) fn append<T>(&self, shard: usize, version_id: i32, count: impl FnOnce() -> T) -> T {
+        let mut journal_file = self.lock(shard);
+        if let Err(error) = journal_file.file.write_all(&version_id.to_le_bytes()) {
+            // The download is still counted, but it would be lost if the process crashed
+            error!(?error, path = ?journal_file.path, "Failed to write to the downloads journal");
+        }
+        count()
+    }
+
+    /// Calls `take` to take the downloads of the shard, and replaces the
+    /// journal file of the shard with a new one at the same time.
+    ///
+    /// Returns the path of the previous file, which should be removed once
+    /// the downloads were persisted.
+    pub(super) fn rotate<T>(
+        &self,
+        shard: usize,
+        take: impl FnOnce() -> T,
+    ) -> io::Result<(T, PathBuf)> {
+        let new_file = self.create_file(shard)?;
+
+        let mut journal_file = self.lock(shard);
+        let taken = take();
+        let old_file = std::mem::replace(&mut *journal_file, new_file);
+
+        Ok((taken, old_file.path))
+    }
+
+    fn lock(&self, shard: usize) -> std::sync::MutexGuard<'_, JournalFile> {
+        self.files[shard]
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn create_file(&self, shard: usize) -> io::Result<JournalFile> {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst);
+        let path = self
+            .dir
+            .join(format!("{}-{shard}-{generation}.{EXTENSION}", self.prefix));
+        let file = OpenOptions::new()
+            .append(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(JournalFile { path, file })
+    }
+}
+
+/// Reads the version IDs of the downloads recorded in a journal file.
+///
+/// A record that was only partially written when the process exited is
+/// ignored.
+pub(super) fn read_journal_file(path: &Path) -> io::Result<Vec<i32>> {
+    let content = fs::read(path)?;
+    Ok(content
+        .chunks_exact(RECORD_SIZE)
+        .map(|record| i32::from_le_bytes(record.try_into().unwrap()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_and_rotate() {
+        let dir = tempfile::tempdir().unwrap();
+        let (journal, leftover_files) = DownloadsJournal::open(dir.path(), 2).unwrap();
+        assert!(leftover_files.is_empty());
+
+        assert_eq!(journal.append(0, 1, || "counted"), "counted");
+        journal.append(0, 2, || ());
+        journal.append(1, 3, || ());
+
+        let (taken, path) = journal.rotate(0, || "taken").unwrap();
+        assert_eq!(taken, "taken");
+        assert_eq!(read_journal_file(&path).unwrap(), vec![1, 2]);
+
+        // Downloads after the rotation go to the new file
+        journal.append(0, 4, || ());
+        let (_, new_path) = journal.rotate(0, || ()).unwrap();
+        assert_ne!(path, new_path);
+        assert_eq!(read_journal_file(&new_path).unwrap(), vec![4]);
+    }
+
+    #[test]
+    fn leftover_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (journal, _) = DownloadsJournal::open(dir.path(), 2).unwrap();
+        journal.append(1, 42, || ());
+        drop(journal);
+
+        let (_, leftover_files) = DownloadsJournal::open(dir.path(), 2).unwrap();
+        assert_eq!(leftover_files.len(), 2);
+
+        let version_ids = leftover_files
+            .iter()
+            .flat_map(|path| read_journal_file(path).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(version_ids, vec![42]);
+    }
+
+    #[test]
+    fn partial_records_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("partial.journal");
+        let mut content = 7i32.to_le_bytes().to_vec();
+        content.extend_from_slice(&[1, 2]);
+        fs::write(&path, content).unwrap();
+
+        assert_eq!(read_journal_file(&path).unwrap(), vec![7]);
+    }
+}
@@ -5,6 +5,7 @@ use crate::util::errors::{server_error, AppResult};
 
 use crate::config;
 use crate::Env;
+use chrono::NaiveDateTime;
 use lettre::message::header::ContentType;
 use lettre::transport::file::FileTransport;
 use lettre::transport::smtp::authentication::{Credentials, Mechanism};
@@ -72,6 +73,28 @@ https://{}/confirm/{}",
         self.send(email, subject, &body)
     }
 
+    /// Attempts to notify the previous address of a user that their email
+    /// address was changed, with a link to revert the change.
+    pub fn send_email_change_notification(
+        &self,
+        email: &str,
+        user_name: &str,
+        new_email: &str,
+        revert_token: &str,
+    ) -> AppResult<()> {
+        let subject = "Your crates.io email address was changed";
+        let body = format!(
+            "Hello {user_name}! The email address of your crates.io account was changed to {new_email}.\n
+If you did not make this change, your account may have been compromised.
+Please click the link below to restore this email address, and review your API tokens
+at https://{domain}/settings/tokens:\n
+https://{domain}/revert-email-change/{revert_token}",
+            domain = crate::config::domain_name()
+        );
+
+        self.send(email, subject, &body)
+    }
+
     /// Attempts to send an ownership invitation.
     pub fn send_owner_invite(
         &self,
@@ -91,6 +114,46 @@ or go to https://{domain}/me/pending-invites to manage all of your crate ownersh
         self.send(email, subject, &body)
     }
 
+    /// Attempts to remind a user of an ownership invitation that is about to expire.
+    pub fn send_owner_invite_reminder(
+        &self,
+        email: &str,
+        user_name: &str,
+        crate_name: &str,
+        token: &str,
+        expires_at: NaiveDateTime,
+    ) -> AppResult<()> {
+        let subject = "Crate ownership invitation expires soon";
+        let body = format!(
+            "{user_name} has invited you to become an owner of the crate {crate_name}.
+This invitation expires on {expires_at} UTC.\n
+Visit https://{domain}/accept-invite/{token} to accept this invitation,
+or go to https://{domain}/me/pending-invites to manage all of your crate ownership invitations.",
+            expires_at = expires_at.format("%Y-%m-%d %H:%M"),
+            domain = crate::config::domain_name()
+        );
+
+        self.send(email, subject, &body)
+    }
+
+    /// Attempts to send an invitation to join an organization.
+    pub fn send_organization_invite(
+        &self,
+        email: &str,
+        user_name: &str,
+        organization_name: &str,
+        role: &str,
+    ) -> AppResult<()> {
+        let subject = "Organization invitation";
+        let body = format!(
+            "{user_name} has invited you to join the organization {organization_name} as a {role}!\n
+Go to https://{domain}/me/pending-invites to accept or decline this invitation.",
+            domain = crate::config::domain_name()
+        );
+
+        self.send(email, subject, &body)
+    }
+
     /// Attempts to send an API token exposure notification email
     pub fn send_token_exposed_notification(
         &self,
@@ -118,6 +181,243 @@ Source type: {source}\n",
         self.send(email, subject, &body)
     }
 
+    /// Attempts to send a notification about new versions matching a saved search.
+    ///
+    /// `versions` is a list of crate name and version number pairs, of which
+    /// `total` were published in total.
+    pub fn send_saved_search_alert(
+        &self,
+        email: &str,
+        query: &str,
+        versions: &[(String, String)],
+        total: usize,
+    ) -> AppResult<()> {
+        let subject = format!("New crates matching \"{query}\" on crates.io");
+        let mut body = format!(
+            "The following versions matching your saved search \"{query}\" were published:\n\n"
+        );
+        for (krate, version) in versions {
+            body.push_str(&format!("{krate} {version}\n"));
+        }
+        if total > versions.len() {
+            body.push_str(&format!("and {} more\n", total - versions.len()));
+        }
+        let encoded_query: String =
+            url::form_urlencoded::byte_serialize(query.as_bytes()).collect();
+        body.push_str(&format!(
+            "\nVisit https://{domain}/search?q={encoded_query} to see all matching crates,
+or go to https://{domain}/me to manage your saved searches.",
+            domain = crate::config::domain_name()
+        ));
+        self.send(email, &subject, &body)
+    }
+
+    /// Attempts to send a digest of the new and yanked versions of the crates
+    /// that a user follows.
+    ///
+    /// `events` is a list of crate name, version number and action
+    /// (`published` or `yanked`) triples, of which there were `total` in total.
+    pub fn send_followed_crates_digest(
+        &self,
+        email: &str,
+        events: &[(String, String, &str)],
+        total: usize,
+    ) -> AppResult<()> {
+        let subject = "Updates of the crates you follow on crates.io";
+        let mut body = String::from(
+            "The following versions of crates you follow were published or yanked:\n\n",
+        );
+        for (krate, version, action) in events {
+            body.push_str(&format!("{krate} {version} ({action})\n"));
+        }
+        if total > events.len() {
+            body.push_str(&format!("and {} more\n", total - events.len()));
+        }
+        body.push_str(&format!(
+            "\nGo to https://{domain}/me to change how often you receive these emails.",
+            domain = crate::config::domain_name()
+        ));
+        self.send(email, subject, &body)
+    }
+
+    /// Attempts to notify a user that they are no longer a member of a GitHub
+    /// team, and therefore can't publish the crates owned by the team anymore.
+    pub fn send_team_membership_removed(
+        &self,
+        email: &str,
+        team_login: &str,
+        crate_names: &[String],
+    ) -> AppResult<()> {
+        let subject = format!("Your membership of {team_login} on crates.io was removed");
+        let mut body = format!(
+            "We noticed that you are no longer a member of the GitHub team {team_login}.
+You can therefore no longer publish the following crates, which are owned by the team:\n\n"
+        );
+        for krate in crate_names {
+            body.push_str(&format!("{krate}\n"));
+        }
+        body.push_str(
+            "\nIf you believe this is a mistake, please make sure that you are an active member
+of the team and that crates.io is allowed to see your membership on GitHub.",
+        );
+        self.send(email, &subject, &body)
+    }
+
+    /// Attempts to notify a user that an owner of a crate wants to transfer
+    /// their ownership of the crate to them.
+    pub fn send_crate_transfer_requested(
+        &self,
+        email: &str,
+        user_name: &str,
+        crate_name: &str,
+        waiting_period_hours: u64,
+    ) -> AppResult<()> {
+        let subject = "Crate ownership transfer";
+        let body = format!(
+            "{user_name} would like to transfer their ownership of the crate {crate_name} to you.\n
+Go to https://{domain}/me/pending-invites to accept or decline this transfer.
+Once you accept it, the ownership is moved to you after a waiting period of
+{waiting_period_hours} hours, during which the transfer can still be cancelled.",
+            domain = crate::config::domain_name()
+        );
+
+        self.send(email, subject, &body)
+    }
+
+    /// Attempts to notify the initiator of an ownership transfer that the
+    /// recipient accepted it.
+    pub fn send_crate_transfer_accepted(
+        &self,
+        email: &str,
+        user_name: &str,
+        crate_name: &str,
+        completes_at: NaiveDateTime,
+    ) -> AppResult<()> {
+        let subject = "Crate ownership transfer accepted";
+        let body = format!(
+            "{user_name} has accepted your transfer of the ownership of the crate {crate_name}.
+The ownership will be moved to them on {completes_at} UTC.\n
+If you did not request this transfer, go to https://{domain}/me/pending-invites
+to cancel it before then.",
+            completes_at = completes_at.format("%Y-%m-%d %H:%M"),
+            domain = crate::config::domain_name()
+        );
+
+        self.send(email, subject, &body)
+    }
+
+    /// Attempts to notify a user that an ownership transfer they were part of
+    /// was cancelled or declined.
+    pub fn send_crate_transfer_cancelled(&self, email: &str, crate_name: &str) -> AppResult<()> {
+        let subject = "Crate ownership transfer cancelled";
+        let body = format!(
+            "The transfer of the ownership of the crate {crate_name} was cancelled.
+The owners of the crate did not change."
+        );
+
+        self.send(email, subject, &body)
+    }
+
+    /// Attempts to notify a user that the ownership of a crate was moved from
+    /// `from_user` to `to_user`.
+    pub fn send_crate_transfer_completed(
+        &self,
+        email: &str,
+        crate_name: &str,
+        from_user: &str,
+        to_user: &str,
+    ) -> AppResult<()> {
+        let subject = "Crate ownership transfer completed";
+        let body = format!(
+            "The ownership of the crate {crate_name} was transferred from {from_user} to {to_user}.
+{from_user} is no longer an owner of the crate.\n
+The transfer is publicly listed at https://{domain}/api/v1/crates/{crate_name}/transfers.",
+            domain = crate::config::domain_name()
+        );
+
+        self.send(email, subject, &body)
+    }
+
+    /// Attempts to send the link to confirm the deletion of an account.
+    ///
+    /// `sole_owned_crates` are the crates that will be left without owners.
+    pub fn send_account_deletion_confirmation(
+        &self,
+        email: &str,
+        user_name: &str,
+        token: &str,
+        sole_owned_crates: &[String],
+    ) -> AppResult<()> {
+        let subject = "Please confirm the deletion of your crates.io account";
+        let mut body = format!(
+            "Hello {user_name}! We received a request to delete your crates.io account.
+Please click the link below within the next 24 hours to confirm the deletion:\n
+https://{domain}/confirm-account-deletion/{token}\n
+Your personal data will be removed, and you will be removed as an owner of all your crates.
+The crates and versions you published are kept, but no longer show who published them.\n",
+            domain = crate::config::domain_name()
+        );
+        if !sole_owned_crates.is_empty() {
+            body.push_str(
+                "\nYou are the only owner of the following crates, which will be left without owners:\n\n",
+            );
+            for krate in sole_owned_crates {
+                body.push_str(&format!("{krate}\n"));
+            }
+        }
+        body.push_str("\nIf you did not request this, you can ignore this email.");
+
+        self.send(email, subject, &body)
+    }
+
+    /// Attempts to notify a user that their account was deleted.
+    pub fn send_account_deleted(&self, email: &str, user_name: &str) -> AppResult<()> {
+        let subject = "Your crates.io account was deleted";
+        let body = format!(
+            "Hello {user_name}! Your crates.io account and its personal data were deleted.
+This is the last email we send to this address."
+        );
+
+        self.send(email, subject, &body)
+    }
+
+    /// Attempts to notify a user that their account was suspended by the
+    /// crates.io team.
+    ///
+    /// `until` is `None` for suspensions without an end date.
+    pub fn send_account_suspended(
+        &self,
+        email: &str,
+        user_name: &str,
+        reason: &str,
+        until: Option<NaiveDateTime>,
+    ) -> AppResult<()> {
+        let subject = "Your crates.io account was suspended";
+        let duration = match until {
+            Some(until) => format!("until {} UTC", until.format("%Y-%m-%d %H:%M")),
+            None => "until further notice".to_string(),
+        };
+        let body = format!(
+            "Hello {user_name}! Your crates.io account was suspended {duration} for the following reason:\n
+{reason}\n
+While your account is suspended, you can't log in, publish crates or use your API tokens.
+If you believe this is a mistake, you can appeal the suspension by emailing help@crates.io."
+        );
+
+        self.send(email, subject, &body)
+    }
+
+    /// Attempts to notify a user that the suspension of their account ended.
+    pub fn send_account_unsuspended(&self, email: &str, user_name: &str) -> AppResult<()> {
+        let subject = "Your crates.io account is no longer suspended";
+        let body = format!(
+            "Hello {user_name}! The suspension of your crates.io account was lifted, \
+you can use it again."
+        );
+
+        self.send(email, subject, &body)
+    }
+
     /// This is supposed to be used only during tests, to retrieve the messages stored in the
     /// "memory" backend. It's not cfg'd away because our integration tests need to access this.
     pub fn mails_in_memory(&self) -> Option<Vec<StoredEmail>> {
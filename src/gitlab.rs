@@ -0,0 +1,132 @@
+//! This module implements functionality for interacting with GitLab.
+
+use oauth2::AccessToken;
+use reqwest::{self, header};
+
+use serde::de::DeserializeOwned;
+
+use crate::util::errors::{cargo_err, internal, not_found, AppResult, BoxedAppError};
+use reqwest::blocking::Client;
+
+/// The GitLab instance that users can log in with.
+pub const GITLAB_URL: &str = "https://gitlab.com";
+
+/// Only group members with at least this access level are considered members
+/// of the team, so that guests can't publish the crates of their group, see
+/// <https://docs.gitlab.com/ee/api/members.html#valid-access-levels>.
+pub const DEVELOPER_ACCESS_LEVEL: i32 = 30;
+
+pub trait GitLabClient: Send + Sync {
+    fn current_user(&self, auth: &AccessToken) -> AppResult<GitLabUser>;
+    fn group_by_path(&self, path: &str, auth: &AccessToken) -> AppResult<GitLabGroup>;
+    /// Returns the membership of the user in the group, including
+    /// memberships that are inherited from parent groups.
+    fn group_membership(
+        &self,
+        group_id: i32,
+        user_id: i32,
+        auth: &AccessToken,
+    ) -> AppResult<GitLabGroupMembership>;
+}
+
+#[derive(Debug)]
+pub struct RealGitLabClient {
+    client: Option<Client>,
+}
+
+impl RealGitLabClient {
+    pub fn new(client: Option<Client>) -> Self {
+        Self { client }
+    }
+
+    /// Sends a GET to GitLab using OAuth access token authentication
+    pub fn request<T>(&self, url: &str, auth: &AccessToken) -> AppResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let url = format!("{GITLAB_URL}/api/v4{url}");
+        info!("GITLAB HTTP: {url}");
+
+        self.client()
+            .get(&url)
+            .header(header::AUTHORIZATION, format!("Bearer {}", auth.secret()))
+            .header(header::USER_AGENT, "crates.io (https://crates.io)")
+            .send()?
+            .error_for_status()
+            .map_err(|e| handle_error_response(&e))?
+            .json()
+            .map_err(Into::into)
+    }
+
+    fn client(&self) -> &Client {
+        self.client
+            .as_ref()
+            .expect("No HTTP client is configured.  In tests, use `TestApp::with_proxy()`.")
+    }
+}
+
+impl GitLabClient for RealGitLabClient {
+    fn current_user(&self, auth: &AccessToken) -> AppResult<GitLabUser> {
+        self.request("/user", auth)
+    }
+
+    fn group_by_path(&self, path: &str, auth: &AccessToken) -> AppResult<GitLabGroup> {
+        // Groups can be looked up by their URL-encoded full path instead of their ID
+        let url = format!("/groups/{}", path.replace('/', "%2F"));
+        self.request(&url, auth)
+    }
+
+    fn group_membership(
+        &self,
+        group_id: i32,
+        user_id: i32,
+        auth: &AccessToken,
+    ) -> AppResult<GitLabGroupMembership> {
+        let url = format!("/groups/{group_id}/members/all/{user_id}");
+        self.request(&url, auth)
+    }
+}
+
+fn handle_error_response(error: &reqwest::Error) -> BoxedAppError {
+    use reqwest::StatusCode as Status;
+
+    match error.status() {
+        Some(Status::UNAUTHORIZED) | Some(Status::FORBIDDEN) => cargo_err(
+            "It looks like you don't have permission \
+             to query a necessary property from GitLab \
+             to complete this request. \
+             You may need to log in to crates.io \
+             with GitLab again.",
+        ),
+        Some(Status::NOT_FOUND) => not_found(),
+        _ => internal(format!("didn't get a 200 result from gitlab: {error}")),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabUser {
+    pub id: i32,
+    pub username: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabGroup {
+    pub id: i32,
+    pub name: String,
+    pub full_path: String,
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabGroupMembership {
+    pub state: String,
+    pub access_level: i32,
+}
+
+pub fn team_url(login: &str) -> String {
+    let path = login.trim_start_matches("gitlab:");
+    format!("{GITLAB_URL}/{path}")
+}
@@ -8,6 +8,9 @@
 // `diesel` macros are currently generating code that breaks this rule, so
 // we have to disable it for now.
 #![allow(clippy::extra_unused_lifetimes)]
+// The `table!` macro of `diesel` needs a higher limit for the many columns of
+// the `crates` table.
+#![recursion_limit = "256"]
 
 #[cfg(test)]
 #[macro_use]
@@ -43,13 +46,17 @@ pub mod db;
 mod downloads_counter;
 pub mod email;
 pub mod github;
+pub mod gitlab;
 pub mod headers;
 pub mod metrics;
 pub mod middleware;
-mod publish_rate_limit;
+pub mod publish_rate_limit;
 pub mod schema;
+pub mod search;
 pub mod sql;
 pub mod ssh;
+pub mod sso;
+pub mod storage;
 pub mod swirl;
 mod test_util;
 pub mod uploaders;
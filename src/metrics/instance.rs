@@ -56,6 +56,11 @@ metrics! {
         pub version_id_cache_hits: IntCounter,
         /// Number of version ID cache misses on the download endpoint.
         pub version_id_cache_misses: IntCounter,
+
+        /// Number of static files served from the in-memory cache.
+        pub static_files_cache_hits: IntCounter,
+        /// Number of static file requests that could not be served from the in-memory cache.
+        pub static_files_cache_misses: IntCounter,
     }
 
     // All instance metrics will be prefixed with this namespace.
@@ -10,9 +10,11 @@
 //! As a rule of thumb, if the metric is not straight up fetched from the database it's probably an
 //! instance-level metric, and you should add it to `src/metrics/instance.rs`.
 
-use crate::schema::{background_jobs, crates, versions};
+use crate::schema::{background_jobs, crates, file_replications, versions};
 use crate::util::errors::AppResult;
-use diesel::{dsl::count_star, prelude::*, PgConnection};
+use chrono::{NaiveDateTime, Utc};
+use diesel::dsl::count_star;
+use diesel::{prelude::*, PgConnection};
 use prometheus::{proto::MetricFamily, IntGauge};
 
 metrics! {
@@ -23,6 +25,10 @@ metrics! {
         versions_total: IntGauge,
         /// Number of queued up background jobs
         background_jobs: IntGauge,
+        /// Number of crate files and readmes that are not copied to a replica yet
+        file_replications_pending: IntGauge,
+        /// Seconds since the oldest file that is not copied to a replica yet was uploaded
+        file_replication_lag_seconds: IntGauge,
     }
 
     // All service metrics will be prefixed with this namespace.
@@ -38,6 +44,17 @@ impl ServiceMetrics {
         self.background_jobs
             .set(background_jobs::table.select(count_star()).first(conn)?);
 
+        let pending = file_replications::table.filter(file_replications::replicated_at.is_null());
+        self.file_replications_pending
+            .set(pending.select(count_star()).first(conn)?);
+        let oldest_pending: Option<NaiveDateTime> = pending
+            .select(diesel::dsl::min(file_replications::created_at))
+            .first(conn)?;
+        let lag = oldest_pending.map_or(0, |created_at| {
+            (Utc::now().naive_utc() - created_at).num_seconds()
+        });
+        self.file_replication_lag_seconds.set(lag);
+
         Ok(self.registry.gather())
     }
 }
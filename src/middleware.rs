@@ -6,29 +6,46 @@ mod ember_html;
 mod head;
 pub mod log_request;
 pub mod normalize_path;
+mod registry_access;
 mod require_user_agent;
 mod sentry;
 pub mod session;
-mod static_or_continue;
+pub mod static_or_continue;
 mod update_metrics;
 
 use app::add_app_state_extension;
+use static_or_continue::{FileCache, Static};
 
 use ::sentry::integrations::tower as sentry_tower;
 use axum::middleware::{from_fn, from_fn_with_state};
 use axum::Router;
 use axum_extra::either::Either;
 use axum_extra::middleware::option_layer;
+use std::path::Path;
+use std::sync::Arc;
 use tower::layer::util::Identity;
 
 use crate::app::AppState;
 use crate::Env;
 
+/// Total size of the frontend assets that are kept in memory.
+const STATIC_CACHE_CAPACITY: u64 = 64 * 1024 * 1024;
+/// Frontend assets larger than this are always read from disk.
+const STATIC_CACHE_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
 pub fn apply_axum_middleware(state: AppState, router: Router) -> Router {
     type Request = http::Request<axum::body::Body>;
 
     let env = state.config.env();
+    // Files of the local storage backend are served by the application itself.
+    let local_uploads = state
+        .config
+        .uploader()
+        .storage()
+        .local_directory()
+        .map(Path::to_path_buf);
 
+    let auth_required = state.config.index_config.auth_required;
     let capacity = state.config.db.primary.pool_size;
     if capacity >= 10 {
         info!(?capacity, "Enabling BalanceCapacity middleware");
@@ -63,22 +80,46 @@ pub fn apply_axum_middleware(state: AppState, router: Router) -> Router {
             state.clone(),
             block_traffic::block_routes,
         ))
-        .layer(from_fn(head::support_head_requests))
-        .layer(conditional_layer(env == Env::Development, || {
-            from_fn(static_or_continue::serve_local_uploads)
+        .layer(from_fn_with_state(state.clone(), add_app_state_extension))
+        // Private registries don't serve any metadata to anonymous users. This includes the
+        // files of the local storage backend, so the check runs before they are served.
+        .layer(conditional_layer(auth_required, || {
+            from_fn_with_state(state.clone(), registry_access::require_registry_access)
         }))
+        // Static files are served before HEAD requests are proxied into GET requests, so that
+        // they are answered from the file metadata without opening the file itself.
+        .layer(option_layer(local_uploads.map(|root| {
+            let config = Static::new(root)
+                .mime_type("crate", "application/gzip")
+                .attachment("/**.crate");
+            from_fn_with_state(Arc::new(config), static_or_continue::serve_static)
+        })))
         // Serve the static files in the *dist* directory, which are the frontend assets.
         // Not needed for the backend tests.
         .layer(conditional_layer(env != Env::Test, || {
-            from_fn(static_or_continue::serve_dist)
+            let metrics = &state.instance_metrics;
+            let cache = FileCache::new(STATIC_CACHE_CAPACITY, STATIC_CACHE_MAX_FILE_SIZE)
+                .with_counters(
+                    metrics.static_files_cache_hits.clone(),
+                    metrics.static_files_cache_misses.clone(),
+                );
+
+            let config = Static::new("dist")
+                .with_index_file()
+                .precompressed()
+                .in_memory_cache(cache)
+                .charset("utf-8")
+                .exclude(".*")
+                .fingerprinted(r"-(?P<hash>[0-9a-f]{32})\.\w+$")
+                .cache_control("/assets/**", "public, max-age=31536000, immutable");
+            from_fn_with_state(Arc::new(config), static_or_continue::serve_static)
         }))
+        .layer(from_fn(head::support_head_requests))
         .layer(conditional_layer(env != Env::Test, || {
             from_fn_with_state(state.clone(), ember_html::serve_html)
         }))
-        .layer(from_fn_with_state(state.clone(), add_app_state_extension))
-        // This is currently the final middleware to run. If a middleware layer requires a database
-        // connection, it should be run after this middleware so that the potential pool usage can be
-        // tracked here.
+        // This middleware should run before all middleware layers that require a database
+        // connection, so that the potential pool usage can be tracked here.
         //
         // In production we currently have 2 equally sized pools (primary and a read-only replica).
         // Because such a large portion of production traffic is for download requests (which update
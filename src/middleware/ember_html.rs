@@ -25,8 +25,9 @@ pub async fn serve_html<B: Send + 'static>(
 ) -> Response {
     let path = &request.uri().path();
 
-    // The "/git/" prefix is only used in development (when within a docker container)
-    if path.starts_with("/api/") || path.starts_with("/git/") {
+    // The "/git/" prefix is used in development (when within a docker container) and by
+    // deployments serving the git index over HTTP
+    if path.starts_with("/api/") || path.starts_with("/git/") || path.starts_with("/index/") {
         next.run(request).await
     } else {
         if let Some(client) = &state.fastboot_client {
@@ -0,0 +1,56 @@
+//! Middleware that restricts the registry to authenticated users, if it is
+//! configured with `auth-required`.
+//!
+//! All routes of the public API and the sparse index are covered, so that no
+//! crate, version or owner metadata is served to anonymous users. The same
+//! applies to the crate files, readmes and index files that are served from
+//! the local storage backend. The private routes of the frontend stay
+//! reachable, since they are needed to log in, and check the credentials of
+//! their requests themselves.
+
+use crate::app::AppState;
+use crate::auth::check_registry_access;
+use crate::controllers::conduit_axum::conduit_compat;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use http::Request;
+
+const RESTRICTED_PATH_PREFIXES: &[&str] =
+    &["/api/v1/", "/api/v2/", "/index/", "/readmes/", "/uploads/"];
+
+/// The crate files share their `/crates/` prefix with the pages of the
+/// frontend, so they are recognized by their extension.
+const RESTRICTED_CRATE_FILE_EXTENSIONS: &[&str] = &[".crate", ".crate.sig"];
+
+fn is_restricted(path: &str) -> bool {
+    RESTRICTED_PATH_PREFIXES
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+        || path.starts_with("/crates/")
+            && RESTRICTED_CRATE_FILE_EXTENSIONS
+                .iter()
+                .any(|extension| path.ends_with(extension))
+}
+
+pub async fn require_registry_access<B>(
+    state: AppState,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if !is_restricted(req.uri().path()) {
+        return next.run(req).await;
+    }
+
+    let (parts, body) = req.into_parts();
+    let result = conduit_compat(move || {
+        let conn = &mut *state.db_read_prefer_primary()?;
+        check_registry_access(&state.config, &parts, conn)?;
+        Ok(parts)
+    })
+    .await;
+
+    match result {
+        Ok(parts) => next.run(Request::from_parts(parts, body)).await,
+        Err(error) => error.into_response(),
+    }
+}
@@ -1,11 +1,13 @@
 use crate::controllers::util::RequestPartsExt;
+use crate::headers::XRealIp;
 use axum::extract::{Extension, FromRequestParts};
+use axum::headers::Header;
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
 use axum_extra::extract::SignedCookieJar;
 use cookie::time::Duration;
 use cookie::{Cookie, SameSite};
-use http::Request;
+use http::{header, HeaderMap, HeaderName, Request};
 use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::ops::Deref;
@@ -14,6 +16,10 @@ use std::sync::Arc;
 static COOKIE_NAME: &str = "cargo_session";
 static MAX_AGE_DAYS: i64 = 90;
 
+/// The session key of the token of the `PersistentSession` that the user is
+/// logged in with.
+pub const SESSION_TOKEN_KEY: &str = "session_token";
+
 #[derive(Clone, FromRequestParts)]
 #[from_request(via(Extension))]
 pub struct SessionExtension(Arc<RwLock<Session>>);
@@ -108,6 +114,13 @@ impl<T: RequestPartsExt> RequestSession for T {
     }
 }
 
+/// Returns the IP address and `User-Agent` of a request, which are recorded
+/// for the persistent sessions of users.
+pub fn client_info(headers: &HeaderMap) -> (Option<&str>, Option<&str>) {
+    let get = |name: &HeaderName| headers.get(name).and_then(|value| value.to_str().ok());
+    (get(XRealIp::name()), get(&header::USER_AGENT))
+}
+
 pub fn decode(cookie: Cookie<'_>) -> HashMap<String, String> {
     let mut ret = HashMap::new();
     let bytes = base64::decode(cookie.value().as_bytes()).unwrap_or_default();
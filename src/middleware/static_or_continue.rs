@@ -1,34 +1,1877 @@
 //! This module implements middleware to serve static files from the
-//! specified directory.
+//! specified directory, falling through to the remaining middleware layers if
+//! no matching file exists.
+//!
+//! Since it is implemented as a middleware instead of a handler, missing files
+//! never result in an empty `404 Not Found` response. Instead the wrapped
+//! router handles the request, which means that the middleware can be mounted
+//! in front of the API routes, and the router's fallback handler decides how
+//! to answer requests that match neither a file nor a route.
+//!
+//! With [`Static::charset()`], a `charset` parameter is added to the
+//! `Content-Type` of text files, since some user agents don't default to
+//! UTF-8 for HTML and CSS files served without one.
+//!
+//! Fingerprinted files, whose name contains a hash of their content, can be
+//! recognized with [`Static::fingerprinted()`]. They are cached forever by
+//! browsers and proxies, and are served with a strong `ETag` derived from the
+//! hash, so that revalidation doesn't require reading the file.
+//!
+//! Files matching one of the patterns registered with [`Static::exclude()`]
+//! are never served. Requests for them are answered with `404 Not Found`,
+//! instead of falling through to the next layer.
+//!
+//! With [`Static::at()`], the files are mounted below a path prefix, which is
+//! stripped from the request path before resolving it against the root
+//! directory.
+//!
+//! A [`StaticMetrics`] hook can be registered with [`Static::metrics()`] to
+//! record the status, size and duration of each response served from the
+//! static files.
+//!
+//! Multiple root directories can be overlaid with [`Static::new_multi()`], in
+//! which case each request is served from the first root directory that
+//! contains the requested path.
+//!
+//! Symbolic links within the root directory are followed by default. This
+//! can be restricted with [`Static::symlinks()`] and a [`SymlinkPolicy`],
+//! which is applied to the file that is actually served (including
+//! precompressed variants, index files and the SPA fallback) and to the
+//! entries of directory listings.
+//!
+//! Files are never read on the request thread: `ServeDir` opens them with
+//! `tokio::fs`, which moves the blocking file system calls to a dedicated
+//! thread pool, and streams the body in chunks of 64 KiB. A slow disk thus only
+//! delays the affected responses, instead of stalling the async workers.
+//!
+//! Conditional requests are handled by `ServeDir`: responses carry a
+//! `Last-Modified` header and requests with a matching `If-Modified-Since`
+//! header are answered with an empty `304 Not Modified` response. Since HTTP
+//! dates only have a resolution of one second, the sub-second part of the file
+//! modification time is ignored for this comparison.
+//!
+//! `Range: bytes=...` requests are supported as well to allow resuming
+//! downloads of large files. Satisfiable ranges are answered with
+//! `206 Partial Content` and a `Content-Range` header, while unsatisfiable
+//! ones result in `416 Range Not Satisfiable`.
+//!
+//! `HEAD` requests receive the same headers as `GET` requests (including
+//! `Content-Length` and `Last-Modified`), but the file is never read.
+//!
+//! If enabled with [`Static::precompressed()`], precompressed `.br` and `.gz`
+//! variants of the requested file are served to clients that accept these
+//! encodings. The `Content-Type` is still derived from the original file
+//! name, and all responses carry a `Vary: Accept-Encoding` header so that
+//! caches keep the variants apart.
+//!
+//! The `Content-Type` of the responses is derived from the file extension by
+//! `ServeDir`. Additional extensions can be registered, or the defaults can be
+//! overridden, with [`Static::mime_type()`].
+//!
+//! `Cache-Control` headers can be configured per path pattern with
+//! [`Static::cache_control()`], e.g. to cache fingerprinted assets forever
+//! while HTML files are revalidated on every request.
+//!
+//! Requests for directories fall through to the next layer, unless
+//! [`Static::with_index_file()`] is used. In that case the `index.html` file
+//! of the directory is served, and requests without a trailing slash are
+//! redirected to the path with a trailing slash, so that relative links in the
+//! HTML file work as expected.
+//!
+//! For single-page applications, [`Static::spa_fallback()`] can be used to
+//! answer `GET` requests for unknown paths with the configured HTML file, as
+//! long as the client accepts `text/html`. This allows client-side routes to
+//! be loaded directly. Other requests for unknown paths still fall through.
+//!
+//! Small files can be kept in memory with [`Static::in_memory_cache()`], see
+//! the [`cache`] module for details.
+//!
+//! Directory listings can be enabled with [`Static::directory_listing()`], see
+//! the [`listing`] module for details.
 
+mod cache;
+mod listing;
+
+pub use cache::FileCache;
+
+use axum::body::{boxed, Bytes};
+use axum::extract::State;
 use axum::middleware::Next;
-use axum::response::Response;
-use http::{Method, Request, StatusCode};
-use std::path::Path;
+use axum::response::{IntoResponse, Response};
+use http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode, Uri};
+use http_body::Body as _;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tower::ServiceExt;
-use tower_http::services::ServeDir;
+use tower_http::services::{ServeDir, ServeFile};
+
+/// Controls whether files are served if their path contains symbolic links.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Follow all symbolic links, even if they point outside of the root
+    /// directory.
+    #[default]
+    FollowAll,
+    /// Follow symbolic links only if the canonicalized path of the file is
+    /// still within the root directory.
+    FollowWithinRoot,
+    /// Never serve files if any component of their path below the root
+    /// directory is a symbolic link.
+    Deny,
+}
+
+/// Information about a response served by [`Static`], which is passed to the
+/// [`StaticMetrics`] hook.
+#[derive(Debug)]
+pub struct ServedFile<'a> {
+    /// Request path, without the mount point.
+    pub path: &'a str,
+    pub status: StatusCode,
+    /// Size of the response body, if known in advance.
+    pub bytes: Option<u64>,
+    /// Time it took to prepare the response, not including the time it takes
+    /// to stream the body to the client.
+    pub duration: Duration,
+}
+
+/// Hook that is called for every response served by [`Static`], e.g. to
+/// record metrics.
+pub trait StaticMetrics: Send + Sync + 'static {
+    fn record(&self, served: &ServedFile<'_>);
+}
+
+impl<F> StaticMetrics for F
+where
+    F: Fn(&ServedFile<'_>) + Send + Sync + 'static,
+{
+    fn record(&self, served: &ServedFile<'_>) {
+        self(served)
+    }
+}
+
+#[derive(Clone)]
+struct MetricsHook(Arc<dyn StaticMetrics>);
+
+impl fmt::Debug for MetricsHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MetricsHook")
+    }
+}
+
+/// `Cache-Control` value for fingerprinted files.
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Configuration for serving the static files of one or more directories.
+#[derive(Clone, Debug)]
+pub struct Static {
+    roots: Vec<PathBuf>,
+    precompressed: bool,
+    index_file: bool,
+    spa_fallback: Option<PathBuf>,
+    directory_listing: bool,
+    listing_exclusions: Vec<String>,
+    listing_disabled: Vec<String>,
+    cache_control: Vec<(String, HeaderValue)>,
+    cache: Option<FileCache>,
+    mime_types: HashMap<String, HeaderValue>,
+    symlinks: SymlinkPolicy,
+    exclusions: Vec<String>,
+    attachments: Vec<String>,
+    charset: Option<String>,
+    mount_point: Option<String>,
+    metrics: Option<MetricsHook>,
+    fingerprint: Option<Regex>,
+}
+
+impl Static {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self::new_multi(vec![root.into()])
+    }
+
+    /// Serves the files of multiple root directories, as if they were
+    /// overlaid on top of each other. Each request is resolved against the
+    /// roots in the given order, and the first root containing the requested
+    /// path is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `roots` is empty.
+    pub fn new_multi(roots: Vec<PathBuf>) -> Self {
+        assert!(!roots.is_empty(), "at least one root directory is required");
+
+        Self {
+            roots,
+            precompressed: false,
+            index_file: false,
+            spa_fallback: None,
+            directory_listing: false,
+            listing_exclusions: Vec::new(),
+            listing_disabled: Vec::new(),
+            cache_control: Vec::new(),
+            cache: None,
+            mime_types: HashMap::new(),
+            symlinks: SymlinkPolicy::default(),
+            exclusions: Vec::new(),
+            attachments: Vec::new(),
+            charset: None,
+            mount_point: None,
+            metrics: None,
+            fingerprint: None,
+        }
+    }
+
+    /// Serve precompressed `.br` and `.gz` variants of the requested files,
+    /// if they exist and the client accepts the encoding.
+    pub fn precompressed(mut self) -> Self {
+        self.precompressed = true;
+        self
+    }
+
+    /// Serve the `index.html` file for requests to a directory.
+    pub fn with_index_file(mut self) -> Self {
+        self.index_file = true;
+        self
+    }
+
+    /// Serve `file` (relative to the root directory) with a `200 OK` status
+    /// for `GET` requests that accept HTML and don't match any other file.
+    pub fn spa_fallback(mut self, file: impl Into<PathBuf>) -> Self {
+        self.spa_fallback = Some(file.into());
+        self
+    }
+
+    /// Serve small files from the given in-memory cache.
+    pub fn in_memory_cache(mut self, cache: FileCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Render a listing of the directory contents for requests to a
+    /// directory without an index file.
+    pub fn directory_listing(mut self) -> Self {
+        self.directory_listing = true;
+        self
+    }
+
+    /// Hide all files and directories with a request path matching `pattern`
+    /// from the directory listings. See [`Static::cache_control()`] for the
+    /// pattern syntax.
+    pub fn exclude_from_listing(mut self, pattern: &str) -> Self {
+        self.listing_exclusions.push(pattern.to_string());
+        self
+    }
+
+    /// Disable the directory listings of all directories with a request path
+    /// matching `pattern`.
+    pub fn disable_listing(mut self, pattern: &str) -> Self {
+        self.listing_disabled.push(pattern.to_string());
+        self
+    }
+
+    /// Use `mime_type` as the `Content-Type` of all files with the given
+    /// extension (without the leading dot, matched case-insensitively),
+    /// overriding the built-in default for the extension, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mime_type` is not a valid header value.
+    pub fn mime_type(mut self, extension: &str, mime_type: &str) -> Self {
+        let value = HeaderValue::from_str(mime_type).expect("invalid MIME type");
+        self.mime_types
+            .insert(extension.to_ascii_lowercase(), value);
+        self
+    }
+
+    /// Never serve files with a path matching `pattern`, e.g. `.*` to hide
+    /// all dotfiles, or `private/**` to hide a whole directory.
+    ///
+    /// Patterns containing a `/` are matched against the file path relative
+    /// to the root directory, while patterns without a `/` are matched
+    /// against each path segment individually. See [`Static::cache_control()`]
+    /// for the pattern syntax.
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclusions.push(pattern.to_string());
+        self
+    }
+
+    /// Serve all files with a request path matching `pattern` with a
+    /// `Content-Disposition: attachment` header, so that browsers download
+    /// them instead of displaying them. See [`Static::cache_control()`] for
+    /// the pattern syntax.
+    pub fn attachment(mut self, pattern: &str) -> Self {
+        self.attachments.push(pattern.to_string());
+        self
+    }
+
+    /// Append a `charset` parameter to the `Content-Type` of all text files,
+    /// unless it already contains one.
+    pub fn charset(mut self, charset: &str) -> Self {
+        self.charset = Some(charset.to_string());
+        self
+    }
+
+    /// Only serve requests with a path below `mount_point` (e.g. `/assets`),
+    /// and strip it from the path before resolving it against the root
+    /// directory. Requests with other paths fall through to the next layer.
+    ///
+    /// All path patterns are matched against the stripped path.
+    pub fn at(mut self, mount_point: &str) -> Self {
+        let mount_point = mount_point.trim_end_matches('/');
+        self.mount_point = (!mount_point.is_empty()).then(|| mount_point.to_string());
+        self
+    }
+
+    /// Call `metrics` for every response served from this configuration.
+    /// Requests that fall through to the next layer are not recorded.
+    pub fn metrics(mut self, metrics: impl StaticMetrics) -> Self {
+        self.metrics = Some(MetricsHook(Arc::new(metrics)));
+        self
+    }
+
+    /// Treat all files with a name matching the regular expression `pattern`
+    /// as fingerprinted, i.e. their name changes whenever their content
+    /// changes. These files are served with an `immutable` `Cache-Control`
+    /// header and a strong `ETag`, which is derived from the `hash` capture
+    /// group of the pattern (or the whole match, if there is no such group).
+    ///
+    /// All other files are served with `Cache-Control: no-cache`, unless a
+    /// different value was configured with [`Static::cache_control()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` is not a valid regular expression.
+    pub fn fingerprinted(mut self, pattern: &str) -> Self {
+        let pattern = Regex::new(pattern).expect("invalid fingerprint pattern");
+        self.fingerprint = Some(pattern);
+        self
+    }
+
+    /// Use the given policy for paths containing symbolic links.
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Use the given `Cache-Control` header value for all files with a
+    /// request path matching `pattern`.
+    ///
+    /// In the pattern, `*` matches any characters except `/`, and `**`
+    /// matches any characters including `/`. If multiple patterns match, the
+    /// one that was registered first is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not a valid header value.
+    pub fn cache_control(mut self, pattern: &str, value: &str) -> Self {
+        let value = HeaderValue::from_str(value).expect("invalid Cache-Control value");
+        self.cache_control.push((pattern.to_string(), value));
+        self
+    }
+
+    fn cache_control_for(&self, path: &str) -> Option<&HeaderValue> {
+        self.cache_control
+            .iter()
+            .find(|(pattern, _)| glob_matches(pattern.as_bytes(), path.as_bytes()))
+            .map(|(_, value)| value)
+    }
+
+    /// Removes the mount point from the request URI, or returns `None` if the
+    /// path is not below the mount point.
+    fn strip_mount_point(&self, uri: &Uri) -> Option<Uri> {
+        let Some(mount_point) = &self.mount_point else {
+            return Some(uri.clone());
+        };
+
+        let path = match uri.path().strip_prefix(mount_point.as_str())? {
+            "" => "/",
+            path if path.starts_with('/') => path,
+            _ => return None,
+        };
+
+        let path_and_query = match uri.query() {
+            Some(query) => format!("{path}?{query}"),
+            None => path.to_string(),
+        };
+
+        path_and_query.parse().ok()
+    }
+
+    /// Returns the first root directory that contains the requested path, or
+    /// the first root directory if none of them does.
+    async fn root_for(&self, request_path: &str) -> &Path {
+        for root in &self.roots {
+            if let Some(path) = resolve_path(root, request_path) {
+                if tokio::fs::symlink_metadata(&path).await.is_ok() {
+                    return root;
+                }
+            }
+        }
+
+        &self.roots[0]
+    }
+
+    /// Checks whether the file at `path` (as returned by [`resolve_path()`]
+    /// for `root`) may be served according to the configured
+    /// [`SymlinkPolicy`].
+    ///
+    /// Paths that don't exist are allowed, since they won't be served anyway.
+    /// Existing paths that can't be resolved, e.g. dangling symbolic links,
+    /// are rejected.
+    async fn is_allowed_by_symlink_policy(&self, root: &Path, path: &Path) -> bool {
+        match self.symlinks {
+            SymlinkPolicy::FollowAll => true,
+            SymlinkPolicy::FollowWithinRoot => {
+                let Ok(root) = tokio::fs::canonicalize(root).await else {
+                    return false;
+                };
+                match tokio::fs::canonicalize(path).await {
+                    Ok(target) => target.starts_with(root),
+                    Err(_) => tokio::fs::symlink_metadata(path).await.is_err(),
+                }
+            }
+            SymlinkPolicy::Deny => {
+                let Ok(relative_path) = path.strip_prefix(root) else {
+                    return false;
+                };
+
+                let mut current = root.to_path_buf();
+                for component in relative_path.components() {
+                    current.push(component);
+                    match tokio::fs::symlink_metadata(&current).await {
+                        Ok(metadata) if metadata.file_type().is_symlink() => return false,
+                        Ok(_) => {}
+                        Err(_) => return true,
+                    }
+                }
+
+                true
+            }
+        }
+    }
+
+    /// Returns the path of the file that a response with the given `headers`
+    /// was served from, which is the `index.html` file for directories, and
+    /// the precompressed variant of the file if the response is encoded.
+    async fn served_path(&self, file_path: &Path, headers: &HeaderMap) -> PathBuf {
+        let mut path = file_path.to_path_buf();
+        if self.index_file && tokio::fs::metadata(&path).await.is_ok_and(|m| m.is_dir()) {
+            path.push("index.html");
+        }
+
+        let encoding = headers.get(header::CONTENT_ENCODING);
+        let extension = match encoding.and_then(|encoding| encoding.to_str().ok()) {
+            Some("br") => ".br",
+            Some("gzip") => ".gz",
+            _ => return path,
+        };
+
+        let mut path = path.into_os_string();
+        path.push(extension);
+        path.into()
+    }
+
+    fn mime_type_for(&self, path: &str) -> Option<&HeaderValue> {
+        let file_name = path.rsplit('/').next()?;
+        let (_, extension) = file_name.rsplit_once('.')?;
+        self.mime_types.get(&extension.to_ascii_lowercase())
+    }
+
+    /// Adds the headers that depend on the requested file itself, instead of
+    /// the way it was served.
+    fn add_file_headers(&self, path: &str, headers: &mut HeaderMap) {
+        if let Some(mime_type) = self.mime_type_for(path) {
+            headers.insert(header::CONTENT_TYPE, mime_type.clone());
+        }
+
+        let path_bytes = path.as_bytes();
+        let mut attachments = self.attachments.iter();
+        if attachments.any(|pattern| glob_matches(pattern.as_bytes(), path_bytes)) {
+            let file_name = path.rsplit('/').next().unwrap_or_default();
+            let file_name = percent_decode_str(file_name).decode_utf8_lossy();
+            headers.insert(header::CONTENT_DISPOSITION, attachment(&file_name));
+        }
+    }
+
+    /// Checks whether the file at `path` (as returned by [`resolve_path()`]
+    /// for `root`) matches any of the patterns registered with
+    /// [`Static::exclude()`].
+    fn is_excluded(&self, root: &Path, path: &Path) -> bool {
+        let Ok(relative_path) = path.strip_prefix(root) else {
+            return true;
+        };
+
+        let segments = relative_path
+            .iter()
+            .map(|segment| segment.to_string_lossy())
+            .collect::<Vec<_>>();
+        let relative_path = segments.join("/");
+
+        self.exclusions
+            .iter()
+            .any(|pattern| match pattern.strip_prefix('/') {
+                Some(pattern) => glob_matches(pattern.as_bytes(), relative_path.as_bytes()),
+                None if pattern.contains('/') => {
+                    glob_matches(pattern.as_bytes(), relative_path.as_bytes())
+                }
+                None => segments
+                    .iter()
+                    .any(|segment| glob_matches(pattern.as_bytes(), segment.as_bytes())),
+            })
+    }
+
+    fn is_excluded_from_listing(&self, path: &str) -> bool {
+        let path = path.as_bytes();
+        let mut patterns = self.listing_exclusions.iter();
+        patterns.any(|pattern| glob_matches(pattern.as_bytes(), path))
+    }
+
+    fn is_listing_disabled(&self, path: &str) -> bool {
+        let path = path.as_bytes();
+        let mut patterns = self.listing_disabled.iter();
+        patterns.any(|pattern| glob_matches(pattern.as_bytes(), path))
+    }
+
+    fn finish_response<B>(&self, path: &str, response: Response<B>) -> Response
+    where
+        B: http_body::Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<axum::BoxError>,
+    {
+        let mut response = response.map(boxed);
+        if let Some(mount_point) = &self.mount_point {
+            // Redirects (e.g. to add a trailing slash) are relative to the
+            // stripped path, so the mount point has to be added back.
+            let location = response.headers().get(header::LOCATION);
+            let location = location.and_then(|location| location.to_str().ok());
+            if let Some(location) = location.filter(|location| location.starts_with('/')) {
+                let location = format!("{mount_point}{location}");
+                if let Ok(location) = HeaderValue::from_str(&location) {
+                    response.headers_mut().insert(header::LOCATION, location);
+                }
+            }
+        }
+        if let Some(charset) = &self.charset {
+            add_charset(response.headers_mut(), charset);
+        }
+        if self.precompressed {
+            let accept_encoding = HeaderValue::from_static("accept-encoding");
+            response.headers_mut().append(header::VARY, accept_encoding);
+        }
+        let fingerprint = self.fingerprint_of(path);
+        let status = response.status();
+        if let Some(fingerprint) = fingerprint.filter(|_| status.is_success()) {
+            let headers = response.headers_mut();
+            if !headers.contains_key(header::ETAG) {
+                let encoding = headers.get(header::CONTENT_ENCODING);
+                let encoding = encoding.and_then(|encoding| encoding.to_str().ok());
+                if let Ok(etag) = HeaderValue::from_str(&etag(fingerprint, encoding)) {
+                    headers.insert(header::ETAG, etag);
+                }
+            }
+        }
+        if fingerprint.is_some() && (status.is_success() || status == StatusCode::NOT_MODIFIED) {
+            let value = HeaderValue::from_static(IMMUTABLE);
+            response.headers_mut().insert(header::CACHE_CONTROL, value);
+        } else if let Some(value) = self.cache_control_for(path) {
+            response
+                .headers_mut()
+                .insert(header::CACHE_CONTROL, value.clone());
+        } else if self.fingerprint.is_some() {
+            let value = HeaderValue::from_static("no-cache");
+            response.headers_mut().insert(header::CACHE_CONTROL, value);
+        }
+        response
+    }
+
+    /// Returns the content hash of a fingerprinted file, as matched by the
+    /// pattern registered with [`Static::fingerprinted()`].
+    fn fingerprint_of<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let pattern = self.fingerprint.as_ref()?;
+        let file_name = path.rsplit('/').next()?;
+        let captures = pattern.captures(file_name)?;
+        let fingerprint = captures.name("hash").or_else(|| captures.get(0))?;
+        Some(fingerprint.as_str())
+    }
+
+    /// Answers requests for existing fingerprinted files with
+    /// `304 Not Modified` if the `If-None-Match` header of the request
+    /// contains the `ETag` of one of the variants of the file.
+    async fn not_modified(
+        &self,
+        path: &str,
+        file_path: &Path,
+        request: &Request<()>,
+    ) -> Option<Response> {
+        let fingerprint = self.fingerprint_of(path)?;
+
+        let candidates = [None, Some("br"), Some("gzip")].map(|enc| etag(fingerprint, enc));
+        let if_none_match = request.headers().get_all(header::IF_NONE_MATCH);
+        let matched = if_none_match
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|tag| tag.trim())
+            .map(|tag| tag.strip_prefix("W/").unwrap_or(tag))
+            .find(|tag| candidates.iter().any(|candidate| candidate == tag))?;
+        let matched = HeaderValue::from_str(matched).ok()?;
+
+        let metadata = tokio::fs::metadata(file_path).await.ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+
+        Some((StatusCode::NOT_MODIFIED, [(header::ETAG, matched)]).into_response())
+    }
+
+    fn serve_dir(&self, root: &Path) -> ServeDir {
+        let serve_dir = ServeDir::new(root).append_index_html_on_directories(self.index_file);
+        if self.precompressed {
+            serve_dir.precompressed_br().precompressed_gzip()
+        } else {
+            serve_dir
+        }
+    }
+}
+
+pub async fn serve_static<B>(
+    State(config): State<Arc<Static>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let start = Instant::now();
+
+    let Some(uri) = config.strip_mount_point(request.uri()) else {
+        return next.run(request).await;
+    };
+
+    let static_req = clone_request(&request, &uri);
+    match try_serve(&config, &static_req).await {
+        Some(response) => {
+            if let Some(metrics) = &config.metrics {
+                metrics.0.record(&ServedFile {
+                    path: uri.path(),
+                    status: response.status(),
+                    bytes: body_size(&response),
+                    duration: start.elapsed(),
+                });
+            }
+            response
+        }
+        None => next.run(request).await,
+    }
+}
+
+/// Returns the size of the response body, either from the body itself, or
+/// from the `Content-Length` header for streamed files.
+fn body_size(response: &Response) -> Option<u64> {
+    response.body().size_hint().exact().or_else(|| {
+        let content_length = response.headers().get(header::CONTENT_LENGTH)?;
+        content_length.to_str().ok()?.parse().ok()
+    })
+}
+
+/// Tries to answer the (already stripped) request with a static file, or
+/// returns `None` if the request should be passed on to the next layer.
+async fn try_serve(config: &Static, request: &Request<()>) -> Option<Response> {
+    let path = request.uri().path();
+
+    // Paths that can't be safely mapped to a file below the root directory
+    // are never served, but might still be valid for the next layer.
+    let is_read_request = request.method() == Method::GET || request.method() == Method::HEAD;
+    let root = config.root_for(path).await;
+    let file_path = resolve_path(root, path)?;
+    if !is_read_request {
+        return None;
+    }
+    if config.is_excluded(root, &file_path) {
+        return Some(StatusCode::NOT_FOUND.into_response());
+    }
+    if !config.is_allowed_by_symlink_policy(root, &file_path).await {
+        return None;
+    }
+
+    if let Some(response) = config.not_modified(path, &file_path, request).await {
+        return Some(config.finish_response(path, response));
+    }
+
+    let static_req = clone_request(request, request.uri());
+    let response = match &config.cache {
+        Some(cache) => cache.serve(config, root, static_req).await,
+        None => {
+            let response = config.serve_dir(root).oneshot(static_req).await;
+            response.map(|response| response.map(boxed))
+        }
+    };
+    let Ok(mut response) = response;
+    // `ServeDir` may have opened a different file than the requested one,
+    // which has to be checked against the policy as well. Files that must not
+    // be served are treated as if they didn't exist.
+    let is_found = response.status() != StatusCode::NOT_FOUND && {
+        let served_path = config.served_path(&file_path, response.headers()).await;
+        config
+            .is_allowed_by_symlink_policy(root, &served_path)
+            .await
+    };
+    if is_found {
+        if response.status().is_success() {
+            config.add_file_headers(path, response.headers_mut());
+        }
+        return Some(config.finish_response(path, response));
+    }
+
+    if config.directory_listing {
+        if let Some(response) = listing::list_directory(config, root, request).await {
+            return Some(config.finish_response(path, response));
+        }
+    }
+
+    if let Some(file) = &config.spa_fallback {
+        if request.method() == Method::GET && accepts_html(request) {
+            for root in &config.roots {
+                let file_path = root.join(file);
+                if !config.is_allowed_by_symlink_policy(root, &file_path).await {
+                    continue;
+                }
+                let static_req = clone_request(request, request.uri());
+                let serve_file = ServeFile::new(file_path);
+                let Ok(response) = serve_file.oneshot(static_req).await;
+                if response.status() != StatusCode::NOT_FOUND {
+                    return Some(config.finish_response(path, response));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Creates a body-less copy of the request with the given URI, which can be
+/// passed to `ServeDir` while the original request is kept around for the
+/// next layer.
+fn clone_request<B>(request: &Request<B>, uri: &Uri) -> Request<()> {
+    let mut static_req = Request::new(());
+    *static_req.method_mut() = request.method().clone();
+    *static_req.uri_mut() = uri.clone();
+    *static_req.headers_mut() = request.headers().clone();
+    static_req
+}
+
+/// Maps the path of a request to a path within the `root` directory.
+///
+/// Returns `None` if the path is not valid percent-encoded UTF-8, contains NUL
+/// bytes, or contains components that could be used to escape the `root`
+/// directory. Backslashes are treated as path separators, so that encoded
+/// Windows-style `..\` segments are rejected as well.
+fn resolve_path(root: &Path, request_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode_str(request_path).decode_utf8().ok()?;
+    if decoded.contains('\0') {
+        return None;
+    }
+
+    let normalized = decoded.replace('\\', "/");
+
+    let mut path = root.to_path_buf();
+    for component in Path::new(normalized.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(segment) => path.push(segment),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    Some(path)
+}
+
+/// Builds the strong `ETag` of a fingerprinted file, which has to differ
+/// between the precompressed variants of the file.
+fn etag(fingerprint: &str, encoding: Option<&str>) -> String {
+    match encoding {
+        Some(encoding) => format!("\"{fingerprint}-{encoding}\""),
+        None => format!("\"{fingerprint}\""),
+    }
+}
+
+/// Appends the `charset` parameter to the `Content-Type` header, if it is a
+/// text type without an explicit charset.
+fn add_charset(headers: &mut HeaderMap, charset: &str) {
+    let Some(content_type) = headers.get(header::CONTENT_TYPE) else {
+        return;
+    };
+    let Ok(content_type) = content_type.to_str() else {
+        return;
+    };
+
+    let mut parts = content_type.split(';');
+    let essence = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+    if parts.any(|param| param.trim().to_ascii_lowercase().starts_with("charset=")) {
+        return;
+    }
+
+    let is_text = essence.starts_with("text/")
+        || essence.ends_with("+xml")
+        || essence.ends_with("+json")
+        || matches!(
+            essence.as_str(),
+            "application/javascript" | "application/json" | "application/xml"
+        );
+
+    if is_text {
+        let value = format!("{content_type}; charset={charset}");
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(header::CONTENT_TYPE, value);
+        }
+    }
+}
+
+/// Builds a `Content-Disposition: attachment` header value for the given file
+/// name, with an additional RFC 5987 encoded variant for non-ASCII names.
+fn attachment(file_name: &str) -> HeaderValue {
+    const ATTR_CHAR: &AsciiSet = &NON_ALPHANUMERIC
+        .remove(b'!')
+        .remove(b'#')
+        .remove(b'$')
+        .remove(b'&')
+        .remove(b'+')
+        .remove(b'-')
+        .remove(b'.')
+        .remove(b'^')
+        .remove(b'_')
+        .remove(b'`')
+        .remove(b'|')
+        .remove(b'~');
+
+    let fallback = file_name
+        .chars()
+        .map(|c| match c {
+            ' ' | '!' | '#'..='[' | ']'..='~' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+
+    let value = if fallback == file_name {
+        format!("attachment; filename=\"{file_name}\"")
+    } else {
+        let encoded = utf8_percent_encode(file_name, ATTR_CHAR);
+        format!("attachment; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+    };
+
+    HeaderValue::from_str(&value).unwrap_or_else(|_| HeaderValue::from_static("attachment"))
+}
 
-pub async fn serve_local_uploads<B>(request: Request<B>, next: Next<B>) -> Response {
-    serve("local_uploads", request, next).await
+fn accepts_html<B>(request: &Request<B>) -> bool {
+    request
+        .headers()
+        .get_all(header::ACCEPT)
+        .iter()
+        .any(|val| val.to_str().unwrap_or_default().contains("text/html"))
 }
 
-pub async fn serve_dist<B>(request: Request<B>, next: Next<B>) -> Response {
-    serve("dist", request, next).await
+/// Matches a path against a glob pattern, where `*` matches any characters
+/// except `/`, `**` matches any characters including `/` and `?` matches a
+/// single character except `/`.
+fn glob_matches(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=path.len()).any(|skip| glob_matches(rest, &path[skip..])),
+        [b'*', rest @ ..] => {
+            let segment_len = path.iter().position(|&b| b == b'/').unwrap_or(path.len());
+            (0..=segment_len).any(|skip| glob_matches(rest, &path[skip..]))
+        }
+        [b'?', rest @ ..] => match path {
+            [first, path_rest @ ..] if *first != b'/' => glob_matches(rest, path_rest),
+            _ => false,
+        },
+        [first, rest @ ..] => match path {
+            [path_first, path_rest @ ..] if path_first == first => glob_matches(rest, path_rest),
+            _ => false,
+        },
+    }
 }
 
-async fn serve<P: AsRef<Path>, B>(path: P, request: Request<B>, next: Next<B>) -> Response {
-    if request.method() == Method::GET || request.method() == Method::HEAD {
-        let mut static_req = Request::new(());
-        *static_req.method_mut() = request.method().clone();
-        *static_req.uri_mut() = request.uri().clone();
-        *static_req.headers_mut() = request.headers().clone();
+#[cfg(test)]
+mod tests {
+    use super::{
+        add_charset, attachment, glob_matches, resolve_path, serve_static, FileCache, Static,
+        SymlinkPolicy,
+    };
+    use axum::body::Body;
+    use axum::middleware::from_fn_with_state;
+    use axum::Router;
+    use http::{header, Request, StatusCode};
+    use std::path::Path;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn router(root: &Path) -> Router {
+        router_with_config(Static::new(root))
+    }
+
+    fn router_with_config(config: Static) -> Router {
+        Router::new()
+            .fallback(|| async { StatusCode::IM_A_TEAPOT })
+            .layer(from_fn_with_state(Arc::new(config), serve_static))
+    }
+
+    #[tokio::test]
+    async fn if_modified_since() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("foo.txt"), "hello").unwrap();
+        let router = router(root.path());
+
+        let req = Request::get("/foo.txt").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let last_modified = response.headers()[header::LAST_MODIFIED].clone();
+
+        // The `Last-Modified` value is truncated to whole seconds, but must
+        // still be considered fresh compared to the sub-second file mtime.
+        let req = Request::get("/foo.txt")
+            .header(header::IF_MODIFIED_SINCE, last_modified)
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(body.is_empty());
+
+        let req = Request::get("/foo.txt")
+            .header(header::IF_MODIFIED_SINCE, "Thu, 01 Jan 1970 00:00:00 GMT")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let req = Request::get("/missing.txt").body(Body::empty()).unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+    }
+
+    #[tokio::test]
+    async fn range_requests() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("foo.txt"), "hello world").unwrap();
+        let router = router(root.path());
+
+        let req = Request::get("/foo.txt").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+
+        let req = Request::get("/foo.txt")
+            .header(header::RANGE, "bytes=6-")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 6-10/11");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"world");
+
+        let req = Request::get("/foo.txt")
+            .header(header::RANGE, "bytes=20-30")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */11");
+    }
+
+    #[tokio::test]
+    async fn head_requests() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("foo.txt"), "hello world").unwrap();
+        let router = router(root.path());
+
+        let req = Request::head("/foo.txt").body(Body::empty()).unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "11");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain");
+        assert!(response.headers().contains_key(header::LAST_MODIFIED));
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn precompressed_variants() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("style.css"), "plain").unwrap();
+        std::fs::write(root.path().join("style.css.br"), "brotli").unwrap();
+        std::fs::write(root.path().join("style.css.gz"), "gzip").unwrap();
+        let router = router_with_config(Static::new(root.path()).precompressed());
+
+        let req = Request::get("/style.css")
+            .header(header::ACCEPT_ENCODING, "gzip, br")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "br");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/css");
+        assert_eq!(response.headers()[header::VARY], "accept-encoding");
+
+        let req = Request::get("/style.css")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"gzip");
+
+        let req = Request::get("/style.css").body(Body::empty()).unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(response.headers()[header::VARY], "accept-encoding");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"plain");
+    }
+
+    #[test]
+    fn glob_patterns() {
+        let matches = |pattern: &str, path: &str| glob_matches(pattern.as_bytes(), path.as_bytes());
+
+        assert!(matches("/assets/**", "/assets/app.js"));
+        assert!(matches("/assets/**", "/assets/fonts/font.woff2"));
+        assert!(!matches("/assets/**", "/index.html"));
+        assert!(matches("/*.html", "/index.html"));
+        assert!(!matches("/*.html", "/docs/index.html"));
+        assert!(matches("/**/*.html", "/docs/index.html"));
+        assert!(matches("/**.map", "/assets/app.js.map"));
+        assert!(matches("/app-?.js", "/app-1.js"));
+        assert!(!matches("/app-?.js", "/app-12.js"));
+    }
+
+    #[tokio::test]
+    async fn cache_control() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("assets")).unwrap();
+        std::fs::write(root.path().join("assets/app-1234.css"), "").unwrap();
+        std::fs::write(root.path().join("index.html"), "").unwrap();
+        std::fs::write(root.path().join("robots.txt"), "").unwrap();
+
+        let config = Static::new(root.path())
+            .cache_control("/assets/**", "public, max-age=31536000, immutable")
+            .cache_control("/*.html", "no-cache");
+        let router = router_with_config(config);
+
+        let req = Request::get("/assets/app-1234.css")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "public, max-age=31536000, immutable"
+        );
+
+        let req = Request::get("/index.html").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-cache");
+
+        let req = Request::get("/robots.txt").body(Body::empty()).unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert!(!response.headers().contains_key(header::CACHE_CONTROL));
+    }
+
+    #[tokio::test]
+    async fn index_file() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("docs")).unwrap();
+        std::fs::write(root.path().join("docs/index.html"), "docs").unwrap();
+
+        let req = Request::get("/docs/").body(Body::empty()).unwrap();
+        let response = router(root.path()).oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+
+        let router = router_with_config(Static::new(root.path()).with_index_file());
+
+        let req = Request::get("/docs").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert!(response.status().is_redirection());
+        assert_eq!(response.headers()[header::LOCATION], "/docs/");
+
+        let req = Request::get("/docs/").body(Body::empty()).unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/html");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"docs");
+    }
+
+    #[tokio::test]
+    async fn spa_fallback() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("index.html"), "app").unwrap();
+        std::fs::write(root.path().join("robots.txt"), "robots").unwrap();
+        let router = router_with_config(Static::new(root.path()).spa_fallback("index.html"));
+
+        let req = Request::get("/crates/foo")
+            .header(header::ACCEPT, "text/html,application/xhtml+xml")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/html");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"app");
+
+        let req = Request::get("/robots.txt")
+            .header(header::ACCEPT, "text/html")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"robots");
+
+        let req = Request::get("/crates/foo")
+            .header(header::ACCEPT, "application/json")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+
+        let req = Request::post("/crates/foo")
+            .header(header::ACCEPT, "text/html")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+    }
+
+    #[test]
+    fn resolve_paths() {
+        let root = Path::new("/srv/static");
+        assert_eq!(
+            resolve_path(root, "/docs/a%20b.txt").unwrap(),
+            Path::new("/srv/static/docs/a b.txt")
+        );
+        assert_eq!(resolve_path(root, "/").unwrap(), root);
+        assert_eq!(
+            resolve_path(root, "/docs%5Cindex.html").unwrap(),
+            Path::new("/srv/static/docs/index.html")
+        );
+        assert_eq!(
+            resolve_path(root, "/./docs//index.html").unwrap(),
+            Path::new("/srv/static/docs/index.html")
+        );
+        assert_none!(resolve_path(root, "/%ff"));
+
+        let payloads = [
+            "/../etc/passwd",
+            "/docs/../../etc/passwd",
+            "/%2e%2e/etc/passwd",
+            "/%2E%2E/etc/passwd",
+            "/%2e%2e%2fetc%2fpasswd",
+            "/..%2fetc%2fpasswd",
+            "/..%5c..%5cwindows%5cwin.ini",
+            "/%5c..%5cetc%5cpasswd",
+            "/docs/..\\..\\etc\\passwd",
+            "/index.html%00.txt",
+            "/%00",
+        ];
+        for payload in payloads {
+            assert_none!(resolve_path(root, payload), "{payload}");
+        }
+
+        // Paths are only decoded once, so double-encoded dots are literal
+        assert_eq!(
+            resolve_path(root, "/%252e%252e/etc/passwd").unwrap(),
+            Path::new("/srv/static/%2e%2e/etc/passwd")
+        );
+    }
+
+    #[tokio::test]
+    async fn path_traversal() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(root.join("index.html"), "index").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "secret").unwrap();
+        let router = router(&root);
+
+        let payloads = [
+            "/../secret.txt",
+            "/%2e%2e/secret.txt",
+            "/%2e%2e%2fsecret.txt",
+            "/..%5csecret.txt",
+            "/index.html/../../secret.txt",
+            "/index.html%00",
+        ];
+        for payload in payloads {
+            let req = Request::get(payload).body(Body::empty()).unwrap();
+            let response = router.clone().oneshot(req).await.unwrap();
+            assert_eq!(response.status(), StatusCode::IM_A_TEAPOT, "{payload}");
+        }
+    }
+
+    #[tokio::test]
+    async fn directory_listing() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("docs/nested")).unwrap();
+        std::fs::create_dir(root.path().join("private")).unwrap();
+        std::fs::write(root.path().join("docs/a <b>.txt"), "hello").unwrap();
+        std::fs::write(root.path().join("docs/.secret"), "").unwrap();
+
+        let req = Request::get("/docs/").body(Body::empty()).unwrap();
+        let response = router(root.path()).oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+
+        let config = Static::new(root.path())
+            .directory_listing()
+            .exclude_from_listing("/**/.*")
+            .disable_listing("/private/");
+        let router = router_with_config(config);
+
+        let req = Request::get("/docs").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], "/docs/");
+
+        let req = Request::get("/docs/")
+            .header(header::ACCEPT, "text/html")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains(r#"<a href="a%20%3Cb%3E.txt">a &lt;b&gt;.txt</a>"#));
+        assert!(html.contains(r#"<a href="nested/">nested/</a>"#));
+        assert!(!html.contains(".secret"));
+
+        let req = Request::get("/docs/")
+            .header(header::ACCEPT, "application/json")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["path"], "/docs/");
+        let entries = json["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["name"], "nested");
+        assert_eq!(entries[0]["is_dir"], true);
+        assert_eq!(entries[0]["size"], serde_json::Value::Null);
+        assert_eq!(entries[1]["name"], "a <b>.txt");
+        assert_eq!(entries[1]["size"], 5);
+        assert!(entries[1]["modified"].is_string());
+
+        let req = Request::get("/private/").body(Body::empty()).unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+    }
+
+    #[tokio::test]
+    async fn falls_through_to_router() {
+        use axum::routing::get;
+
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("foo.txt"), "file").unwrap();
+
+        let router = Router::new()
+            .route(
+                "/api/foo",
+                get(|| async { "api" }).post(|| async { "post" }),
+            )
+            .route(
+                "/foo.txt",
+                get(|| async { "route" }).post(|| async { "post" }),
+            )
+            .fallback(|| async { StatusCode::IM_A_TEAPOT })
+            .layer(from_fn_with_state(
+                Arc::new(Static::new(root.path())),
+                serve_static,
+            ));
+
+        let req = Request::get("/api/foo").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"api");
+
+        // Existing files take precedence over routes for `GET` requests ...
+        let req = Request::get("/foo.txt").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"file");
+
+        // ... but not for other methods
+        let req = Request::post("/foo.txt").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"post");
+
+        let req = Request::get("/missing.txt").body(Body::empty()).unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+    }
+
+    #[tokio::test]
+    async fn in_memory_cache() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("small.txt"), "small").unwrap();
+        std::fs::write(root.path().join("large.txt"), "large file").unwrap();
+
+        let cache = FileCache::new(1024, 5);
+        let config = Static::new(root.path()).in_memory_cache(cache.clone());
+        let router = router_with_config(config);
+
+        let get = |path: &str| Request::get(path).body(Body::empty()).unwrap();
+
+        let response = router.clone().oneshot(get("/small.txt")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!((cache.hits(), cache.misses()), (0, 1));
+
+        let response = router.clone().oneshot(get("/small.txt")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain");
+        assert!(response.headers().contains_key(header::LAST_MODIFIED));
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"small");
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        // Changing the file invalidates the cache entry
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        std::fs::write(root.path().join("small.txt"), "SMALL").unwrap();
+        let response = router.clone().oneshot(get("/small.txt")).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"SMALL");
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+
+        // Files above the size limit and missing files are never cached
+        let response = router.clone().oneshot(get("/large.txt")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router.oneshot(get("/missing.txt")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn in_memory_cache_with_precompressed_variants() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("style.css"), "plain").unwrap();
+        std::fs::write(root.path().join("style.css.gz"), "gzip").unwrap();
+
+        let cache = FileCache::new(1024, 5);
+        let config = Static::new(root.path())
+            .precompressed()
+            .in_memory_cache(cache.clone());
+        let router = router_with_config(config);
+
+        let get = |accept_encoding: &str| {
+            Request::get("/style.css")
+                .header(header::ACCEPT_ENCODING, accept_encoding)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // Requests that negotiate the same encoding share a cache entry
+        for accept_encoding in ["gzip", "gzip, deflate", "br;q=0.5, gzip"] {
+            let response = router.clone().oneshot(get(accept_encoding)).await.unwrap();
+            assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            assert_eq!(&body[..], b"gzip");
+        }
+        assert_eq!((cache.hits(), cache.misses()), (2, 1));
+
+        let response = router.clone().oneshot(get("gzip;q=0")).await.unwrap();
+        assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"plain");
+        assert_eq!((cache.hits(), cache.misses()), (2, 2));
+
+        // Changing the precompressed variant invalidates its cache entry
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        std::fs::write(root.path().join("style.css.gz"), "GZIP").unwrap();
+        let response = router.clone().oneshot(get("gzip")).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"GZIP");
+        assert_eq!((cache.hits(), cache.misses()), (2, 3));
+
+        // A newly added variant is preferred over the cached one
+        std::fs::write(root.path().join("style.css.br"), "br").unwrap();
+        let response = router.oneshot(get("gzip, br")).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "br");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"br");
+        assert_eq!((cache.hits(), cache.misses()), (2, 4));
+    }
+
+    #[tokio::test]
+    async fn streams_large_files_in_chunks() {
+        use http_body::Body as _;
+
+        let root = tempfile::tempdir().unwrap();
+        let content = vec![b'x'; 256 * 1024];
+        std::fs::write(root.path().join("large.bin"), &content).unwrap();
+
+        let req = Request::get("/large.bin").body(Body::empty()).unwrap();
+        let response = router(root.path()).oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut body = response.into_body();
+        let mut chunks = 0;
+        let mut received = Vec::new();
+        while let Some(chunk) = body.data().await {
+            chunks += 1;
+            received.extend_from_slice(&chunk.unwrap());
+        }
+
+        assert!(chunks > 1);
+        assert_eq!(received, content);
+    }
+
+    #[tokio::test]
+    async fn mime_type_overrides() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("foo-1.0.0.crate"), "").unwrap();
+        std::fs::write(root.path().join("style.CSS"), "").unwrap();
+        std::fs::write(root.path().join("notes.txt"), "").unwrap();
+
+        let config = Static::new(root.path())
+            .mime_type("crate", "application/gzip")
+            .mime_type("css", "text/x-custom-css");
+        let router = router_with_config(config);
+
+        let req = Request::get("/foo-1.0.0.crate")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/gzip");
+
+        let req = Request::get("/style.CSS").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/x-custom-css"
+        );
+
+        let req = Request::get("/notes.txt").body(Body::empty()).unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain");
+    }
 
-        if let Ok(response) = ServeDir::new(path).oneshot(static_req).await {
-            if response.status() != StatusCode::NOT_FOUND {
-                return response.map(axum::body::boxed);
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn symlink_policy() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs/file.txt"), "inside").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "outside").unwrap();
+        symlink(root.join("docs/file.txt"), root.join("inside.txt")).unwrap();
+        symlink(dir.path().join("secret.txt"), root.join("outside.txt")).unwrap();
+        symlink(root.join("docs"), root.join("linked-docs")).unwrap();
+
+        let status = |policy, path: &'static str| {
+            let router = router_with_config(Static::new(&root).symlinks(policy));
+            async move {
+                let req = Request::get(path).body(Body::empty()).unwrap();
+                router.oneshot(req).await.unwrap().status()
             }
+        };
+
+        let ok = StatusCode::OK;
+        let fallthrough = StatusCode::IM_A_TEAPOT;
+
+        assert_eq!(status(SymlinkPolicy::FollowAll, "/inside.txt").await, ok);
+        assert_eq!(status(SymlinkPolicy::FollowAll, "/outside.txt").await, ok);
+        assert_eq!(
+            status(SymlinkPolicy::FollowAll, "/linked-docs/file.txt").await,
+            ok
+        );
+
+        let policy = SymlinkPolicy::FollowWithinRoot;
+        assert_eq!(status(policy, "/docs/file.txt").await, ok);
+        assert_eq!(status(policy, "/inside.txt").await, ok);
+        assert_eq!(status(policy, "/outside.txt").await, fallthrough);
+        assert_eq!(status(policy, "/linked-docs/file.txt").await, ok);
+
+        let policy = SymlinkPolicy::Deny;
+        assert_eq!(status(policy, "/docs/file.txt").await, ok);
+        assert_eq!(status(policy, "/inside.txt").await, fallthrough);
+        assert_eq!(status(policy, "/outside.txt").await, fallthrough);
+        assert_eq!(status(policy, "/linked-docs/file.txt").await, fallthrough);
+        assert_eq!(status(policy, "/missing.txt").await, fallthrough);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn symlink_policy_applies_to_served_files() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs/file.txt"), "inside").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "outside").unwrap();
+        // Only the precompressed variant and the index file are symlinks
+        symlink(
+            dir.path().join("secret.txt"),
+            root.join("compressed.txt.gz"),
+        )
+        .unwrap();
+        symlink(dir.path().join("secret.txt"), root.join("docs/index.html")).unwrap();
+        symlink(dir.path().join("missing.txt"), root.join("dangling.txt")).unwrap();
+        symlink(dir.path().join("secret.txt"), root.join("docs/outside.txt")).unwrap();
+        symlink(dir.path().join("secret.txt"), root.join("spa.html")).unwrap();
+
+        for policy in [SymlinkPolicy::FollowWithinRoot, SymlinkPolicy::Deny] {
+            let config = Static::new(&root)
+                .symlinks(policy)
+                .precompressed()
+                .with_index_file()
+                .directory_listing()
+                .spa_fallback("spa.html");
+            let router = router_with_config(config);
+
+            let req = Request::get("/compressed.txt")
+                .header(header::ACCEPT_ENCODING, "gzip")
+                .body(Body::empty())
+                .unwrap();
+            let response = router.clone().oneshot(req).await.unwrap();
+            assert_eq!(response.status(), StatusCode::IM_A_TEAPOT, "{policy:?}");
+
+            let req = Request::get("/dangling.txt").body(Body::empty()).unwrap();
+            let response = router.clone().oneshot(req).await.unwrap();
+            assert_eq!(response.status(), StatusCode::IM_A_TEAPOT, "{policy:?}");
+
+            let req = Request::get("/unknown")
+                .header(header::ACCEPT, "text/html")
+                .body(Body::empty())
+                .unwrap();
+            let response = router.clone().oneshot(req).await.unwrap();
+            assert_eq!(response.status(), StatusCode::IM_A_TEAPOT, "{policy:?}");
+
+            // The index file is skipped, and the listing hides the symlinks
+            let req = Request::get("/docs/")
+                .header(header::ACCEPT, "application/json")
+                .body(Body::empty())
+                .unwrap();
+            let response = router.clone().oneshot(req).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{policy:?}");
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            let names = json["entries"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|entry| entry["name"].as_str().unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["file.txt"], "{policy:?}");
         }
     }
 
-    next.run(request).await
+    #[tokio::test]
+    async fn exclusions() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("private/nested")).unwrap();
+        std::fs::create_dir_all(root.path().join("assets")).unwrap();
+        std::fs::write(root.path().join(".env"), "SECRET=1").unwrap();
+        std::fs::write(root.path().join("assets/app.js"), "").unwrap();
+        std::fs::write(root.path().join("assets/app.js.map"), "").unwrap();
+        std::fs::write(root.path().join("private/nested/key.pem"), "").unwrap();
+
+        let config = Static::new(root.path())
+            .exclude(".*")
+            .exclude("*.map")
+            .exclude("private/**")
+            .directory_listing();
+        let router = router_with_config(config);
+
+        let status = |path: &str| {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            let router = router.clone();
+            async move { router.oneshot(req).await.unwrap().status() }
+        };
+
+        assert_eq!(status("/.env").await, StatusCode::NOT_FOUND);
+        assert_eq!(status("/%2eenv").await, StatusCode::NOT_FOUND);
+        assert_eq!(status("/assets/.env").await, StatusCode::NOT_FOUND);
+        assert_eq!(status("/assets/app.js.map").await, StatusCode::NOT_FOUND);
+        assert_eq!(
+            status("/private/nested/key.pem").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(status("/assets/app.js").await, StatusCode::OK);
+
+        let req = Request::get("/assets/")
+            .header(header::ACCEPT, "application/json")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let entries = json["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["name"], "app.js");
+
+        // Other methods still fall through to the next layer
+        let req = Request::post("/.env").body(Body::empty()).unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+    }
+
+    #[test]
+    fn attachment_header_values() {
+        assert_eq!(
+            attachment("foo-1.0.0.crate"),
+            r#"attachment; filename="foo-1.0.0.crate""#
+        );
+        assert_eq!(
+            attachment(r#"a "quoted" name.zip"#),
+            r#"attachment; filename="a _quoted_ name.zip"; filename*=UTF-8''a%20%22quoted%22%20name.zip"#
+        );
+        assert_eq!(
+            attachment("grüße.zip"),
+            r#"attachment; filename="gr__e.zip"; filename*=UTF-8''gr%C3%BC%C3%9Fe.zip"#
+        );
+    }
+
+    #[tokio::test]
+    async fn attachments() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("foo-1.0.0.crate"), "").unwrap();
+        std::fs::write(root.path().join("readme.txt"), "").unwrap();
+
+        let config = Static::new(root.path()).attachment("/**.crate");
+        let router = router_with_config(config);
+
+        let req = Request::get("/foo-1.0.0.crate")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            r#"attachment; filename="foo-1.0.0.crate""#
+        );
+
+        let req = Request::get("/readme.txt").body(Body::empty()).unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert!(!response.headers().contains_key(header::CONTENT_DISPOSITION));
+    }
+
+    #[test]
+    fn charset_parameter() {
+        let with_charset = |content_type: &'static str| {
+            let mut headers = http::HeaderMap::new();
+            headers.insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+            add_charset(&mut headers, "utf-8");
+            headers[header::CONTENT_TYPE].to_str().unwrap().to_string()
+        };
+
+        assert_eq!(with_charset("text/html"), "text/html; charset=utf-8");
+        assert_eq!(with_charset("text/css"), "text/css; charset=utf-8");
+        assert_eq!(
+            with_charset("application/javascript"),
+            "application/javascript; charset=utf-8"
+        );
+        assert_eq!(
+            with_charset("image/svg+xml"),
+            "image/svg+xml; charset=utf-8"
+        );
+        assert_eq!(
+            with_charset("text/plain; charset=iso-8859-1"),
+            "text/plain; charset=iso-8859-1"
+        );
+        assert_eq!(with_charset("image/png"), "image/png");
+        assert_eq!(with_charset("application/gzip"), "application/gzip");
+    }
+
+    #[tokio::test]
+    async fn charset() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("index.html"), "").unwrap();
+        std::fs::write(root.path().join("logo.png"), "").unwrap();
+        let router = router_with_config(Static::new(root.path()).charset("utf-8"));
+
+        let req = Request::get("/index.html").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/html; charset=utf-8"
+        );
+
+        let req = Request::get("/logo.png").body(Body::empty()).unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+    }
+
+    #[tokio::test]
+    async fn multiple_roots() {
+        let generated = tempfile::tempdir().unwrap();
+        let public = tempfile::tempdir().unwrap();
+        std::fs::create_dir(generated.path().join("assets")).unwrap();
+        std::fs::create_dir(public.path().join("assets")).unwrap();
+        std::fs::write(generated.path().join("assets/app.js"), "generated").unwrap();
+        std::fs::write(generated.path().join("robots.txt"), "generated").unwrap();
+        std::fs::write(public.path().join("assets/logo.svg"), "public").unwrap();
+        std::fs::write(public.path().join("robots.txt"), "public").unwrap();
+
+        let roots = vec![generated.path().to_path_buf(), public.path().to_path_buf()];
+        let router = router_with_config(Static::new_multi(roots));
+
+        let body = |path: &str| {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(req).await.unwrap();
+                let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
+        };
+
+        assert_eq!(body("/assets/app.js").await, "generated");
+        assert_eq!(body("/assets/logo.svg").await, "public");
+        assert_eq!(body("/robots.txt").await, "generated");
+
+        let req = Request::get("/missing.txt").body(Body::empty()).unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+    }
+
+    #[tokio::test]
+    async fn mount_point() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("docs")).unwrap();
+        std::fs::write(root.path().join("docs/index.html"), "docs").unwrap();
+        std::fs::write(root.path().join("app.js"), "app").unwrap();
+
+        let config = Static::new(root.path()).at("/assets/").with_index_file();
+        let router = router_with_config(config);
+
+        let req = Request::get("/assets/app.js?v=1")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"app");
+
+        let req = Request::get("/assets/docs").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert!(response.status().is_redirection());
+        assert_eq!(response.headers()[header::LOCATION], "/assets/docs/");
+
+        let req = Request::get("/assets/docs/").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for path in ["/app.js", "/assetsapp.js", "/other/assets/app.js"] {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            let response = router.clone().oneshot(req).await.unwrap();
+            assert_eq!(response.status(), StatusCode::IM_A_TEAPOT, "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn metrics_hook() {
+        use super::ServedFile;
+        use std::sync::Mutex;
+
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("foo.txt"), "hello").unwrap();
+
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let hook = {
+            let recorded = recorded.clone();
+            move |served: &ServedFile<'_>| {
+                let entry = (served.path.to_string(), served.status, served.bytes);
+                recorded.lock().unwrap().push(entry);
+            }
+        };
+
+        let config = Static::new(root.path()).at("/static").metrics(hook);
+        let router = router_with_config(config);
+
+        for path in ["/static/foo.txt", "/static/missing.txt", "/other"] {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            router.clone().oneshot(req).await.unwrap();
+        }
+
+        let req = Request::get("/static/foo.txt")
+            .header(header::RANGE, "bytes=1-2")
+            .body(Body::empty())
+            .unwrap();
+        router.oneshot(req).await.unwrap();
+
+        let recorded = recorded.lock().unwrap();
+        assert_eq!(
+            *recorded,
+            vec![
+                ("/foo.txt".to_string(), StatusCode::OK, Some(5)),
+                ("/foo.txt".to_string(), StatusCode::PARTIAL_CONTENT, Some(2)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn fingerprinted_assets() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("assets")).unwrap();
+        let hash = "0123456789abcdef0123456789abcdef";
+        std::fs::write(root.path().join(format!("assets/app-{hash}.css")), "app").unwrap();
+        std::fs::write(root.path().join(format!("assets/app-{hash}.css.br")), "br").unwrap();
+        std::fs::write(root.path().join("index.html"), "").unwrap();
+
+        let config = Static::new(root.path())
+            .precompressed()
+            .fingerprinted(r"-(?P<hash>[0-9a-f]{32})\.\w+$");
+        let router = router_with_config(config);
+
+        let path = format!("/assets/app-{hash}.css");
+        let etag = format!("\"{hash}\"");
+        let br_etag = format!("\"{hash}-br\"");
+
+        let req = Request::get(&path).body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "public, max-age=31536000, immutable"
+        );
+        assert_eq!(response.headers()[header::ETAG], etag.as_str());
+
+        let req = Request::get(&path)
+            .header(header::ACCEPT_ENCODING, "br")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.headers()[header::ETAG], br_etag.as_str());
+
+        let req = Request::get(&path)
+            .header(header::IF_NONE_MATCH, format!("\"other\", {br_etag}"))
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], br_etag.as_str());
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "public, max-age=31536000, immutable"
+        );
+
+        let req = Request::get("/index.html").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-cache");
+        assert!(!response.headers().contains_key(header::ETAG));
+
+        // The `ETag` of missing files is never considered a match
+        let missing = "/assets/missing-ffffffffffffffffffffffffffffffff.css";
+        let req = Request::get(missing)
+            .header(
+                header::IF_NONE_MATCH,
+                "\"ffffffffffffffffffffffffffffffff\"",
+            )
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+    }
 }
@@ -0,0 +1,248 @@
+//! In-memory cache for small static files, used by [`Static`] if enabled with
+//! [`Static::in_memory_cache()`].
+//!
+//! Only plain `GET` requests are answered from the cache. Conditional and
+//! `Range` requests are always passed on to `ServeDir`, which knows how to
+//! handle them. Cached entries are invalidated when the modification time of
+//! the file on disk changes, which still requires a `stat` call per request,
+//! but avoids opening and reading the file.
+//!
+//! If precompressed variants are served, the encoding is negotiated the same
+//! way as by `ServeDir`, and each variant is cached once and invalidated by
+//! the modification time of its own file.
+
+use super::{resolve_path, Static};
+use axum::body::{boxed, Bytes};
+use axum::response::{IntoResponse, Response};
+use http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode};
+use moka::future::{Cache, CacheBuilder};
+use prometheus::IntCounter;
+use std::convert::Infallible;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tower::ServiceExt;
+
+/// The file path, and the content coding of the precompressed variant that
+/// is served, if any.
+type CacheKey = (PathBuf, Option<&'static str>);
+
+/// The content codings of the precompressed variants and the extensions of
+/// their files, in the order that `ServeDir` prefers them in if the client
+/// accepts them equally.
+const PRECOMPRESSED: [(&str, &str); 2] = [("br", ".br"), ("gzip", ".gz")];
+
+#[derive(Clone)]
+pub struct FileCache {
+    files: Cache<CacheKey, Arc<CachedFile>>,
+    max_file_size: u64,
+    hits: IntCounter,
+    misses: IntCounter,
+}
+
+struct CachedFile {
+    modified: SystemTime,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl FileCache {
+    /// Creates a cache for files up to `max_file_size` bytes, using at most
+    /// `capacity` bytes in total. If the cache is full, the least frequently
+    /// and recently used files are evicted first.
+    pub fn new(capacity: u64, max_file_size: u64) -> Self {
+        let files = CacheBuilder::new(capacity)
+            .weigher(|_key, file: &Arc<CachedFile>| file.body.len().try_into().unwrap_or(u32::MAX))
+            .build();
+
+        let hits = IntCounter::new("static_files_cache_hits", "Number of cache hits").unwrap();
+        let misses =
+            IntCounter::new("static_files_cache_misses", "Number of cache misses").unwrap();
+
+        Self {
+            files,
+            max_file_size,
+            hits,
+            misses,
+        }
+    }
+
+    /// Count cache hits and misses with the given (usually registered)
+    /// counters, instead of the internal ones.
+    pub fn with_counters(mut self, hits: IntCounter, misses: IntCounter) -> Self {
+        self.hits = hits;
+        self.misses = misses;
+        self
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.get()
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.get()
+    }
+
+    pub(super) async fn serve(
+        &self,
+        config: &Static,
+        root: &Path,
+        request: Request<()>,
+    ) -> Result<Response, Infallible> {
+        let Some((key, modified)) = self.cache_key(config, root, &request).await else {
+            let response = config.serve_dir(root).oneshot(request).await?;
+            return Ok(response.map(boxed));
+        };
+
+        if let Some(file) = self.files.get(&key) {
+            if file.modified == modified {
+                self.hits.inc();
+                return Ok(file.to_response());
+            }
+        }
+
+        self.misses.inc();
+
+        let response = config.serve_dir(root).oneshot(request).await?;
+        let encoding = response.headers().get(header::CONTENT_ENCODING);
+        if response.status() != StatusCode::OK
+            || encoding.map(HeaderValue::as_bytes) != key.1.map(str::as_bytes)
+        {
+            return Ok(response.map(boxed));
+        }
+
+        let (parts, body) = response.into_parts();
+        let body = match hyper::body::to_bytes(body).await {
+            Ok(body) => body,
+            Err(error) => {
+                error!(%error, "Failed to read static file");
+                return Ok(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+            }
+        };
+
+        let file = Arc::new(CachedFile {
+            modified,
+            headers: parts.headers,
+            body,
+        });
+
+        let response = file.to_response();
+        self.files.insert(key, file).await;
+        Ok(response)
+    }
+
+    /// Returns the cache key and the current modification time of the file
+    /// that is served for the request, or `None` if the request can't be
+    /// served from the cache.
+    async fn cache_key(
+        &self,
+        config: &Static,
+        root: &Path,
+        request: &Request<()>,
+    ) -> Option<(CacheKey, SystemTime)> {
+        let headers = request.headers();
+        if request.method() != Method::GET
+            || headers.contains_key(header::RANGE)
+            || headers.contains_key(header::IF_MODIFIED_SINCE)
+            || headers.contains_key(header::IF_UNMODIFIED_SINCE)
+        {
+            return None;
+        }
+
+        let path = resolve_path(root, request.uri().path())?;
+        let mut metadata = tokio::fs::metadata(&path).await.ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+
+        let mut encoding = None;
+        if config.precompressed {
+            for (accepted, extension) in accepted_encodings(headers) {
+                let mut variant = path.clone().into_os_string();
+                variant.push(extension);
+                if let Ok(variant) = tokio::fs::metadata(variant).await {
+                    if variant.is_file() {
+                        encoding = Some(accepted);
+                        metadata = variant;
+                        break;
+                    }
+                }
+            }
+        }
+
+        if metadata.len() > self.max_file_size {
+            return None;
+        }
+
+        let modified = metadata.modified().ok()?;
+        Some(((path, encoding), modified))
+    }
+}
+
+impl fmt::Debug for FileCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileCache")
+            .field("entries", &self.files.entry_count())
+            .field("max_file_size", &self.max_file_size)
+            .field("hits", &self.hits())
+            .field("misses", &self.misses())
+            .finish()
+    }
+}
+
+/// Returns the precompressed variants that the client accepts according to
+/// the `Accept-Encoding` header, most preferred first.
+///
+/// Like `ServeDir`, codings with a higher quality value are preferred, ties
+/// are broken by the order of [`PRECOMPRESSED`], and an `identity` coding
+/// with a higher quality value than the remaining ones ends the list.
+fn accepted_encodings(headers: &HeaderMap) -> Vec<(&'static str, &'static str)> {
+    let mut accepted = headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|coding| {
+            let mut parts = coding.splitn(2, ';');
+            let name = parts.next()?.trim();
+            let quality = match parts.next() {
+                Some(param) => quality(param.trim())?,
+                None => 1000,
+            };
+            let rank = match PRECOMPRESSED
+                .iter()
+                .position(|(e, _)| name.eq_ignore_ascii_case(e))
+            {
+                Some(position) => PRECOMPRESSED.len() - position,
+                None if name.eq_ignore_ascii_case("identity") => 0,
+                None => return None,
+            };
+            Some((quality, rank))
+        })
+        .filter(|(quality, _)| *quality > 0)
+        .collect::<Vec<_>>();
+    accepted.sort_by(|a, b| b.cmp(a));
+
+    accepted
+        .into_iter()
+        .map_while(|(_, rank)| (rank > 0).then(|| PRECOMPRESSED[PRECOMPRESSED.len() - rank]))
+        .collect()
+}
+
+/// Parses a `q=...` parameter into thousandths.
+fn quality(param: &str) -> Option<u16> {
+    let value = param
+        .strip_prefix("q=")
+        .or_else(|| param.strip_prefix("Q="))?;
+    let value: f32 = value.parse().ok()?;
+    (0.0..=1.0)
+        .contains(&value)
+        .then_some((value * 1000.0).round() as u16)
+}
+
+impl CachedFile {
+    fn to_response(&self) -> Response {
+        (StatusCode::OK, self.headers.clone(), self.body.clone()).into_response()
+    }
+}
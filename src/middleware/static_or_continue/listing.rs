@@ -0,0 +1,151 @@
+//! Generated listings of the files in a directory, which are served by
+//! [`Static`] if enabled with [`Static::directory_listing()`].
+//!
+//! Listings are rendered as HTML by default, and as JSON for clients that
+//! prefer `application/json` over `text/html` in their `Accept` header.
+
+use super::{resolve_path, Static};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::Json;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use http::{header, Request, StatusCode};
+use minijinja::Environment;
+use once_cell::sync::Lazy;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use std::path::Path;
+
+/// Characters that need to be escaped in a single path segment.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+static TEMPLATES: Lazy<Environment<'static>> = Lazy::new(|| {
+    let mut env = Environment::new();
+    // The `.html` extension enables auto-escaping for the template.
+    env.add_template(
+        "directory-listing.html",
+        include_str!("directory-listing.html.j2"),
+    )
+    .expect("invalid directory listing template");
+    env
+});
+
+#[derive(Debug, Serialize)]
+struct Listing {
+    path: String,
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize)]
+struct Entry {
+    name: String,
+    href: String,
+    is_dir: bool,
+    size: Option<u64>,
+    #[serde(with = "crate::util::rfc3339::option")]
+    modified: Option<NaiveDateTime>,
+}
+
+/// Renders a listing of the directory that the request path points to.
+///
+/// Returns `None` if the path does not point to a directory, or if listings
+/// are disabled for it.
+pub(super) async fn list_directory<B>(
+    config: &Static,
+    root: &Path,
+    request: &Request<B>,
+) -> Option<Response> {
+    let path = request.uri().path();
+    if config.is_listing_disabled(path) {
+        return None;
+    }
+
+    let dir = resolve_path(root, path)?;
+    let mut read_dir = tokio::fs::read_dir(&dir).await.ok()?;
+
+    // Relative links in the listing only work with a trailing slash.
+    if !path.ends_with('/') {
+        let location = match request.uri().query() {
+            Some(query) => format!("{path}/?{query}"),
+            None => format!("{path}/"),
+        };
+        return Some(Redirect::temporary(&location).into_response());
+    }
+
+    let mut entries = Vec::new();
+    while let Ok(Some(dir_entry)) = read_dir.next_entry().await {
+        let Ok(name) = dir_entry.file_name().into_string() else {
+            continue;
+        };
+        let entry_path = dir.join(&name);
+        if config.is_excluded(root, &entry_path)
+            || config.is_excluded_from_listing(&format!("{path}{name}"))
+        {
+            continue;
+        }
+        // The metadata is read through symbolic links, so entries that can't
+        // be served must not reveal their targets
+        if !config.is_allowed_by_symlink_policy(root, &entry_path).await {
+            continue;
+        }
+        let Ok(metadata) = dir_entry.metadata().await else {
+            continue;
+        };
+
+        let is_dir = metadata.is_dir();
+        entries.push(Entry {
+            href: utf8_percent_encode(&name, PATH_SEGMENT).to_string(),
+            name,
+            is_dir,
+            size: (!is_dir).then_some(metadata.len()),
+            modified: metadata
+                .modified()
+                .ok()
+                .map(|time| DateTime::<Utc>::from(time).naive_utc()),
+        });
+    }
+
+    // Directories first, then files, both sorted by name
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+    let listing = Listing {
+        path: path.to_string(),
+        entries,
+    };
+
+    if prefers_json(request) {
+        return Some(Json(listing).into_response());
+    }
+
+    let template = TEMPLATES.get_template("directory-listing.html").unwrap();
+    let response = match template.render(&listing) {
+        Ok(html) => Html(html).into_response(),
+        Err(error) => {
+            error!(%error, "Failed to render directory listing");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    };
+
+    Some(response)
+}
+
+fn prefers_json<B>(request: &Request<B>) -> bool {
+    let accept = request
+        .headers()
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    accept.contains("application/json") && !accept.contains("text/html")
+}
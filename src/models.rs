@@ -1,33 +1,79 @@
+pub use self::account_deletion::AccountDeletion;
 pub use self::action::{insert_version_owner_action, VersionAction, VersionOwnerAction};
+pub use self::admin::{AdminAction, AdminPermission, AdminRole, NewAdminAction};
 pub use self::category::{Category, CrateCategory, NewCategory};
+pub use self::crate_deletion::{CrateDeletion, NewCrateDeletion};
+pub use self::crate_file::CrateFile;
 pub use self::crate_owner_invitation::{CrateOwnerInvitation, NewCrateOwnerInvitationOutcome};
+pub use self::crate_transfer::{CrateTransfer, TransferCompletion};
 pub use self::dependency::{Dependency, DependencyKind, ReverseDependency};
 pub use self::download::VersionDownload;
-pub use self::email::{Email, NewEmail};
-pub use self::follow::Follow;
+pub use self::email::{Email, EmailChange, NewEmail};
+pub use self::file_replication::FileReplication;
+pub use self::follow::{Follow, FollowNotificationFrequency, FollowNotificationSettings};
+pub use self::index_change::{IndexChange, IndexChangeAction};
 pub use self::keyword::{CrateKeyword, Keyword};
 pub use self::krate::{Crate, CrateVersions, NewCrate, RecentCrateDownloads};
+pub use self::linked_account::{AccountProvider, LinkedAccount, NewLinkedAccount};
+pub use self::notification_preferences::{NotificationKind, NotificationPreferences};
+pub use self::organization::{
+    NewOrganization, Organization, OrganizationInvitation, OrganizationRole,
+};
 pub use self::owner::{CrateOwner, Owner, OwnerKind};
+pub use self::persistent_session::{CreatedPersistentSession, PersistentSession};
+pub use self::publish_upload::{NewPublishUpload, PublishUpload};
+pub use self::reserved_prefix::ReservedCratePrefix;
 pub use self::rights::Rights;
-pub use self::team::{NewTeam, Team};
+pub use self::saved_search::{NewSavedSearch, SavedSearch};
+pub use self::search_term::SearchTerm;
+pub use self::security_log::{SecurityEvent, SecurityLogEntry};
+pub use self::sso_identity::SsoIdentity;
+pub use self::staged_publish::{NewStagedPublish, StagedPublish};
+pub use self::team::{NewTeam, Team, SSO_MEMBERSHIP_VALID_DAYS};
 pub use self::token::{ApiToken, CreatedApiToken};
+pub use self::totp::{TotpCredential, TotpRecoveryCode};
 pub use self::user::{NewUser, User};
-pub use self::version::{NewVersion, TopVersions, Version};
+pub use self::version::{AcceptedLicenses, NewVersion, TopVersions, Version};
+pub use self::version_signature::{NewVersionSignature, SignatureKind, VersionSignature};
+pub use self::webauthn::{WebauthnCeremony, WebauthnChallenge, WebauthnCredential};
+pub use self::webhook::{CreatedWebhook, NewWebhook, Webhook, WebhookDelivery, WebhookEvent};
 
 pub mod helpers;
 
+pub mod account_deletion;
 mod action;
+mod admin;
 pub mod category;
+mod crate_deletion;
+mod crate_file;
 mod crate_owner_invitation;
+mod crate_transfer;
 pub mod dependency;
 mod download;
 mod email;
+mod file_replication;
 mod follow;
+mod index_change;
 mod keyword;
 pub mod krate;
+mod linked_account;
+mod notification_preferences;
+pub mod organization;
 mod owner;
+mod persistent_session;
+mod publish_upload;
+mod reserved_prefix;
 mod rights;
+mod saved_search;
+mod search_term;
+mod security_log;
+mod sso_identity;
+mod staged_publish;
 mod team;
 pub mod token;
+mod totp;
 pub mod user;
 mod version;
+mod version_signature;
+mod webauthn;
+mod webhook;
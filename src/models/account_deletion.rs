@@ -0,0 +1,140 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::dsl::{count_star, now};
+use diesel::prelude::*;
+
+use crate::models::{CrateOwner, Organization, OwnerKind};
+use crate::schema::{account_deletions, crate_owners, crates, users};
+use crate::util::errors::{bad_request, AppResult, BoxedAppError};
+use crate::util::token::{SecureToken, SecureTokenKind};
+
+/// The account lock reason of accounts that are being deleted or were deleted.
+pub const DELETED_ACCOUNT_LOCK_REASON: &str =
+    "This account was deleted at the request of its owner";
+
+/// A request of a user to delete their account.
+///
+/// The deletion has to be confirmed with the token that is emailed to the
+/// user, of which only the hash is stored, after which their account is locked and its personal data is removed
+/// by `worker::delete_account()`.
+#[derive(Clone, Debug, PartialEq, Eq, Identifiable, Queryable)]
+#[diesel(primary_key(user_id))]
+pub struct AccountDeletion {
+    pub user_id: i32,
+    token: SecureToken,
+    pub requested_at: NaiveDateTime,
+    pub confirmed_at: Option<NaiveDateTime>,
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+impl AccountDeletion {
+    /// For how many hours after the request the deletion can be confirmed.
+    pub const CONFIRMATION_VALID_HOURS: i64 = 24;
+
+    /// Requests the deletion of the account with a new confirmation token,
+    /// replacing any earlier request that wasn't confirmed, and returns the
+    /// plaintext token.
+    ///
+    /// Fails if the user is the last admin of an organization, which would
+    /// leave its members and crates without anyone to manage them.
+    pub fn request(conn: &mut PgConnection, user_id: i32) -> AppResult<String> {
+        ensure_not_last_admin(conn, user_id)?;
+
+        let token = SecureToken::generate(SecureTokenKind::AccountDeletion);
+        diesel::insert_into(account_deletions::table)
+            .values((
+                account_deletions::user_id.eq(user_id),
+                account_deletions::token.eq(&*token),
+            ))
+            .on_conflict(account_deletions::user_id)
+            .do_update()
+            .set((
+                account_deletions::token.eq(&*token),
+                account_deletions::requested_at.eq(now),
+            ))
+            .execute(conn)?;
+
+        Ok(token.plaintext().into())
+    }
+
+    /// Finds the unconfirmed request of the user with the given token, unless
+    /// it's too old to be confirmed.
+    pub fn find_unconfirmed(
+        conn: &mut PgConnection,
+        user_id: i32,
+        token: &str,
+    ) -> QueryResult<Self> {
+        let token =
+            SecureToken::parse(SecureTokenKind::AccountDeletion, token).ok_or(diesel::NotFound)?;
+
+        let valid_after = Utc::now().naive_utc() - Duration::hours(Self::CONFIRMATION_VALID_HOURS);
+        account_deletions::table
+            .find(user_id)
+            .filter(account_deletions::token.eq(token))
+            .filter(account_deletions::confirmed_at.is_null())
+            .filter(account_deletions::requested_at.gt(valid_after))
+            .first(conn)
+    }
+
+    /// Confirms the deletion and locks the account, which signs the user out
+    /// of all sessions and stops their API tokens from working right away.
+    pub fn confirm(&self, conn: &mut PgConnection) -> AppResult<()> {
+        ensure_not_last_admin(conn, self.user_id)?;
+
+        conn.transaction::<_, BoxedAppError, _>(|conn| {
+            diesel::update(self)
+                .set(account_deletions::confirmed_at.eq(now))
+                .execute(conn)?;
+
+            diesel::update(users::table.find(self.user_id))
+                .set((
+                    users::account_lock_reason.eq(DELETED_ACCOUNT_LOCK_REASON),
+                    users::account_lock_until.eq(None::<NaiveDateTime>),
+                ))
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+
+    /// Returns the names of the crates that the user is the only owner of.
+    ///
+    /// These crates are kept with their whole history when the account is
+    /// deleted, but are left without owners.
+    pub fn sole_owned_crates(conn: &mut PgConnection, user_id: i32) -> QueryResult<Vec<String>> {
+        let owned_crate_ids: Vec<i32> = CrateOwner::by_owner_kind(OwnerKind::User)
+            .filter(crate_owners::owner_id.eq(user_id))
+            .select(crate_owners::crate_id)
+            .load(conn)?;
+
+        let sole_owned_crate_ids: Vec<i32> = crate_owners::table
+            .filter(crate_owners::deleted.eq(false))
+            .filter(crate_owners::crate_id.eq_any(owned_crate_ids))
+            .group_by(crate_owners::crate_id)
+            .having(count_star().eq(1))
+            .select(crate_owners::crate_id)
+            .load(conn)?;
+
+        crates::table
+            .filter(crates::id.eq_any(sole_owned_crate_ids))
+            .select(crates::name)
+            .order(crates::name)
+            .load(conn)
+    }
+}
+
+fn ensure_not_last_admin(conn: &mut PgConnection, user_id: i32) -> AppResult<()> {
+    let organizations = Organization::last_admin_of(conn, user_id)?;
+    if organizations.is_empty() {
+        return Ok(());
+    }
+
+    let names = organizations
+        .iter()
+        .map(|organization| organization.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(bad_request(&format_args!(
+        "you are the last admin of the organizations {names}, \
+         please make another member an admin first"
+    )))
+}
@@ -0,0 +1,181 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::models::User;
+use crate::schema::{admin_actions, admin_role_assignments, admin_roles, users};
+
+/// A permission for an admin action of the crates.io team.
+///
+/// Permissions are granted by assigning users an `AdminRole` that contains
+/// them, and every action taken with a permission is recorded as an
+/// `AdminAction`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdminPermission {
+    /// Can delete any crate, regardless of its owners and age.
+    CratesDelete,
+    /// Can override the maximum upload size of crates.
+    CratesLimits,
+    /// Can reserve crate names, so that nobody can publish them, and release
+    /// them again.
+    CratesReserve,
+    /// Can list the problems that the integrity checks of the index and the
+    /// crate files found.
+    IntegrityRead,
+    /// Can revoke the API tokens of any user.
+    TokensRevoke,
+    /// Can lock and unlock the accounts of users.
+    UsersSuspend,
+}
+
+impl AdminPermission {
+    pub const ALL: [AdminPermission; 6] = [
+        AdminPermission::CratesDelete,
+        AdminPermission::CratesLimits,
+        AdminPermission::CratesReserve,
+        AdminPermission::IntegrityRead,
+        AdminPermission::TokensRevoke,
+        AdminPermission::UsersSuspend,
+    ];
+
+    pub fn from_param(param: &str) -> Option<Self> {
+        match param {
+            "crates:delete" => Some(AdminPermission::CratesDelete),
+            "crates:limits" => Some(AdminPermission::CratesLimits),
+            "crates:reserve" => Some(AdminPermission::CratesReserve),
+            "integrity:read" => Some(AdminPermission::IntegrityRead),
+            "tokens:revoke" => Some(AdminPermission::TokensRevoke),
+            "users:suspend" => Some(AdminPermission::UsersSuspend),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminPermission::CratesDelete => "crates:delete",
+            AdminPermission::CratesLimits => "crates:limits",
+            AdminPermission::CratesReserve => "crates:reserve",
+            AdminPermission::IntegrityRead => "integrity:read",
+            AdminPermission::TokensRevoke => "tokens:revoke",
+            AdminPermission::UsersSuspend => "users:suspend",
+        }
+    }
+
+    /// Returns whether any role of the user grants this permission.
+    pub fn is_granted_to(&self, conn: &mut PgConnection, user_id: i32) -> QueryResult<bool> {
+        let query = admin_role_assignments::table
+            .inner_join(admin_roles::table)
+            .filter(admin_role_assignments::user_id.eq(user_id))
+            .filter(admin_roles::permissions.contains(vec![self.as_str()]));
+
+        diesel::select(diesel::dsl::exists(query)).get_result(conn)
+    }
+}
+
+/// A named set of admin permissions, e.g. `support` with `users:suspend` and
+/// `tokens:revoke`.
+#[derive(Clone, Debug, Identifiable, Queryable)]
+pub struct AdminRole {
+    pub id: i32,
+    pub name: String,
+    pub permissions: Vec<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl AdminRole {
+    pub fn create(
+        conn: &mut PgConnection,
+        name: &str,
+        permissions: &[AdminPermission],
+    ) -> QueryResult<Self> {
+        let permissions: Vec<_> = permissions.iter().map(|p| p.as_str()).collect();
+
+        diesel::insert_into(admin_roles::table)
+            .values((
+                admin_roles::name.eq(name),
+                admin_roles::permissions.eq(permissions),
+            ))
+            .get_result(conn)
+    }
+
+    pub fn find_by_name(conn: &mut PgConnection, name: &str) -> QueryResult<Self> {
+        admin_roles::table
+            .filter(admin_roles::name.eq(name))
+            .first(conn)
+    }
+
+    pub fn all(conn: &mut PgConnection) -> QueryResult<Vec<Self>> {
+        admin_roles::table.order(admin_roles::name).load(conn)
+    }
+
+    /// Returns the logins of the users that the role is assigned to.
+    pub fn member_logins(&self, conn: &mut PgConnection) -> QueryResult<Vec<String>> {
+        admin_role_assignments::table
+            .inner_join(users::table)
+            .filter(admin_role_assignments::role_id.eq(self.id))
+            .select(users::gh_login)
+            .order(users::gh_login)
+            .load(conn)
+    }
+
+    pub fn assign(&self, conn: &mut PgConnection, user: &User) -> QueryResult<()> {
+        diesel::insert_into(admin_role_assignments::table)
+            .values((
+                admin_role_assignments::user_id.eq(user.id),
+                admin_role_assignments::role_id.eq(self.id),
+            ))
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Removes the role from the user. Returns whether the user had the role.
+    pub fn unassign(&self, conn: &mut PgConnection, user: &User) -> QueryResult<bool> {
+        let assignment = admin_role_assignments::table
+            .filter(admin_role_assignments::user_id.eq(user.id))
+            .filter(admin_role_assignments::role_id.eq(self.id));
+
+        Ok(diesel::delete(assignment).execute(conn)? > 0)
+    }
+}
+
+/// An entry of the audit log of admin actions.
+#[derive(Clone, Debug, Identifiable, Queryable, Associations)]
+#[diesel(belongs_to(User))]
+pub struct AdminAction {
+    pub id: i64,
+    /// The admin that took the action, or `None` for actions taken with the
+    /// `crates-admin` command line tool.
+    pub user_id: Option<i32>,
+    pub permission: String,
+    pub target: String,
+    pub reason: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = admin_actions)]
+pub struct NewAdminAction<'a> {
+    pub user_id: Option<i32>,
+    pub permission: &'a str,
+    pub target: &'a str,
+    pub reason: Option<&'a str>,
+}
+
+impl<'a> NewAdminAction<'a> {
+    /// An action taken with the `crates-admin` command line tool, which is
+    /// recorded with the name of the `command` instead of a permission.
+    pub fn command_line(command: &'a str, target: &'a str, reason: Option<&'a str>) -> Self {
+        Self {
+            user_id: None,
+            permission: command,
+            target,
+            reason,
+        }
+    }
+
+    pub fn create(&self, conn: &mut PgConnection) -> QueryResult<AdminAction> {
+        diesel::insert_into(admin_actions::table)
+            .values(self)
+            .get_result(conn)
+    }
+}
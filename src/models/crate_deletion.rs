@@ -0,0 +1,36 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::models::User;
+use crate::schema::crate_deletions;
+
+/// An audit record of a crate or a single version that was deleted by one of
+/// its owners within the deletion grace period.
+#[derive(Clone, Identifiable, Queryable, Associations, Debug)]
+#[diesel(belongs_to(User, foreign_key = deleted_by))]
+pub struct CrateDeletion {
+    pub id: i32,
+    pub crate_name: String,
+    /// The deleted version, or `None` if the whole crate was deleted.
+    pub version: Option<String>,
+    pub deleted_by: i32,
+    pub published_at: NaiveDateTime,
+    pub deleted_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate_deletions)]
+pub struct NewCrateDeletion<'a> {
+    pub crate_name: &'a str,
+    pub version: Option<&'a str>,
+    pub deleted_by: i32,
+    pub published_at: NaiveDateTime,
+}
+
+impl NewCrateDeletion<'_> {
+    pub fn create(&self, conn: &mut PgConnection) -> QueryResult<CrateDeletion> {
+        diesel::insert_into(crate_deletions::table)
+            .values(self)
+            .get_result(conn)
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+
+use crate::models::{Crate, Version};
+use crate::schema::{crate_files, crates, versions};
+
+/// The storage key of a crate file that is stored under its content address.
+///
+/// Versions that were published before the content-addressed layout was
+/// enabled have no entry, their crate files are stored under their crate name
+/// and version.
+#[derive(Clone, Debug, Identifiable, Queryable, Associations)]
+#[diesel(primary_key(version_id))]
+#[diesel(belongs_to(Version))]
+pub struct CrateFile {
+    pub version_id: i32,
+    pub object_key: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl CrateFile {
+    pub fn record(conn: &mut PgConnection, version_id: i32, object_key: &str) -> QueryResult<()> {
+        diesel::insert_into(crate_files::table)
+            .values((
+                crate_files::version_id.eq(version_id),
+                crate_files::object_key.eq(object_key),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Returns the storage key of the crate file of a version, or `None` if it
+    /// is not stored under its content address.
+    pub fn object_key(conn: &mut PgConnection, version_id: i32) -> QueryResult<Option<String>> {
+        crate_files::table
+            .find(version_id)
+            .select(crate_files::object_key)
+            .first(conn)
+            .optional()
+    }
+
+    /// Like [`CrateFile::object_key()`], but looks up the version by the name
+    /// of its crate and its number.
+    pub fn object_key_by_name(
+        conn: &mut PgConnection,
+        crate_name: &str,
+        version: &str,
+    ) -> QueryResult<Option<String>> {
+        crate_files::table
+            .inner_join(versions::table.inner_join(crates::table))
+            .filter(Crate::with_name(crate_name))
+            .filter(versions::num.eq(version))
+            .select(crate_files::object_key)
+            .first(conn)
+            .optional()
+    }
+
+    /// Returns the storage keys of the crate files of the given versions.
+    pub fn object_keys(conn: &mut PgConnection, version_ids: &[i32]) -> QueryResult<Vec<String>> {
+        crate_files::table
+            .filter(crate_files::version_id.eq_any(version_ids))
+            .select(crate_files::object_key)
+            .distinct()
+            .load(conn)
+    }
+
+    /// Locks the crate file stored at `object_key` until the end of the
+    /// current transaction.
+    ///
+    /// Publishes hold the lock from the upload of a crate file until the
+    /// version referring to it is committed, and deletions hold it from the
+    /// check whether a crate file is still referenced until it is deleted, so
+    /// that a deletion can't remove an identical crate file that was just
+    /// uploaded again.
+    pub fn lock(conn: &mut PgConnection, object_key: &str) -> QueryResult<()> {
+        diesel::sql_query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind::<Text, _>(object_key)
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Whether any version still refers to the crate file stored at
+    /// `object_key`. Identical crate files of different versions share the
+    /// same key.
+    pub fn is_referenced(conn: &mut PgConnection, object_key: &str) -> QueryResult<bool> {
+        diesel::select(diesel::dsl::exists(
+            crate_files::table.filter(crate_files::object_key.eq(object_key)),
+        ))
+        .get_result(conn)
+    }
+}
@@ -22,6 +22,7 @@ pub struct CrateOwnerInvitation {
     pub created_at: NaiveDateTime,
     pub token: String,
     pub token_created_at: Option<NaiveDateTime>,
+    pub reminder_sent_at: Option<NaiveDateTime>,
 }
 
 impl CrateOwnerInvitation {
@@ -0,0 +1,204 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::dsl::now;
+use diesel::pg::Pg;
+use diesel::prelude::*;
+
+use crate::models::{CrateOwner, OwnerKind};
+use crate::schema::{crate_owners, crate_transfers, crates};
+use crate::util::errors::{bad_request, AppResult};
+
+type BoxedQuery<'a> = crate_transfers::BoxedQuery<'a, Pg, crate_transfers::SqlType>;
+
+/// A transfer of the ownership of a crate from one of its owners to another
+/// user.
+///
+/// The recipient has to accept the transfer first. Accepted transfers are
+/// completed by the background worker once their waiting period is over, and
+/// can be cancelled until then. Completed transfers are kept as a public
+/// record of the ownership changes of the crate.
+#[derive(Clone, Debug, PartialEq, Eq, Identifiable, Queryable)]
+pub struct CrateTransfer {
+    pub id: i32,
+    pub crate_id: i32,
+    pub from_user_id: i32,
+    pub to_user_id: i32,
+    pub created_at: NaiveDateTime,
+    pub accepted_at: Option<NaiveDateTime>,
+    pub completes_at: Option<NaiveDateTime>,
+    pub completed_at: Option<NaiveDateTime>,
+    pub cancelled_at: Option<NaiveDateTime>,
+}
+
+impl CrateTransfer {
+    /// Returns a query of the transfers that were neither completed nor
+    /// cancelled yet.
+    pub fn pending() -> BoxedQuery<'static> {
+        crate_transfers::table
+            .filter(crate_transfers::completed_at.is_null())
+            .filter(crate_transfers::cancelled_at.is_null())
+            .into_boxed()
+    }
+
+    /// Starts a transfer of the crate. Every crate can only have one pending
+    /// transfer at a time.
+    pub fn create(
+        conn: &mut PgConnection,
+        crate_id: i32,
+        from_user_id: i32,
+        to_user_id: i32,
+    ) -> AppResult<Self> {
+        diesel::insert_into(crate_transfers::table)
+            .values((
+                crate_transfers::crate_id.eq(crate_id),
+                crate_transfers::from_user_id.eq(from_user_id),
+                crate_transfers::to_user_id.eq(to_user_id),
+            ))
+            .on_conflict_do_nothing()
+            .get_result(conn)
+            .optional()?
+            .ok_or_else(|| {
+                bad_request("there already is a pending ownership transfer of this crate")
+            })
+    }
+
+    pub fn find_pending(conn: &mut PgConnection, id: i32) -> QueryResult<Self> {
+        Self::pending()
+            .filter(crate_transfers::id.eq(id))
+            .first(conn)
+    }
+
+    /// Returns the pending transfers that the user initiated or received,
+    /// with the names of their crates.
+    pub fn pending_for_user(
+        conn: &mut PgConnection,
+        user_id: i32,
+    ) -> QueryResult<Vec<(Self, String)>> {
+        crate_transfers::table
+            .inner_join(crates::table)
+            .filter(crate_transfers::completed_at.is_null())
+            .filter(crate_transfers::cancelled_at.is_null())
+            .filter(
+                crate_transfers::from_user_id
+                    .eq(user_id)
+                    .or(crate_transfers::to_user_id.eq(user_id)),
+            )
+            .select((crate_transfers::all_columns, crates::name))
+            .order(crate_transfers::created_at)
+            .load(conn)
+    }
+
+    /// Returns the completed transfers of the crate, oldest first.
+    pub fn completed_for_crate(conn: &mut PgConnection, crate_id: i32) -> QueryResult<Vec<Self>> {
+        crate_transfers::table
+            .filter(crate_transfers::crate_id.eq(crate_id))
+            .filter(crate_transfers::completed_at.is_not_null())
+            .order(crate_transfers::completed_at)
+            .load(conn)
+    }
+
+    /// Returns the accepted transfers whose waiting period is over.
+    pub fn due(conn: &mut PgConnection) -> QueryResult<Vec<Self>> {
+        Self::pending()
+            .filter(crate_transfers::completes_at.le(Utc::now().naive_utc()))
+            .order(crate_transfers::completes_at)
+            .load(conn)
+    }
+
+    pub fn is_accepted(&self) -> bool {
+        self.accepted_at.is_some()
+    }
+
+    /// Accepts the transfer on behalf of the recipient, which starts its
+    /// waiting period.
+    ///
+    /// Fails if the transfer was accepted, cancelled or completed since it
+    /// was loaded.
+    pub fn accept(&self, conn: &mut PgConnection, waiting_period: Duration) -> AppResult<Self> {
+        let accepted_at = Utc::now().naive_utc();
+        diesel::update(self)
+            .filter(crate_transfers::accepted_at.is_null())
+            .filter(crate_transfers::completed_at.is_null())
+            .filter(crate_transfers::cancelled_at.is_null())
+            .set((
+                crate_transfers::accepted_at.eq(accepted_at),
+                crate_transfers::completes_at.eq(accepted_at + waiting_period),
+            ))
+            .get_result(conn)
+            .optional()?
+            .ok_or_else(|| bad_request("this transfer is no longer pending"))
+    }
+
+    /// Cancels the transfer, and returns whether it was still pending.
+    pub fn cancel(&self, conn: &mut PgConnection) -> QueryResult<bool> {
+        let cancelled = diesel::update(self)
+            .filter(crate_transfers::completed_at.is_null())
+            .filter(crate_transfers::cancelled_at.is_null())
+            .set(crate_transfers::cancelled_at.eq(now))
+            .execute(conn)?;
+        Ok(cancelled > 0)
+    }
+
+    /// Moves the ownership of the crate from the initiator to the recipient
+    /// of the transfer, in a single transaction.
+    ///
+    /// If the initiator isn't an owner of the crate anymore, the transfer is
+    /// cancelled instead. Transfers that were cancelled or completed since
+    /// they were loaded are left alone.
+    pub fn complete(&self, conn: &mut PgConnection) -> QueryResult<TransferCompletion> {
+        conn.transaction(|conn| {
+            let pending = crate_transfers::table
+                .find(self.id)
+                .filter(crate_transfers::completed_at.is_null())
+                .filter(crate_transfers::cancelled_at.is_null())
+                .select(crate_transfers::id)
+                .for_update()
+                .first::<i32>(conn)
+                .optional()?;
+            if pending.is_none() {
+                return Ok(TransferCompletion::NoLongerPending);
+            }
+
+            let from_owner = crate_owners::table
+                .find((self.crate_id, self.from_user_id, OwnerKind::User as i32))
+                .filter(crate_owners::deleted.eq(false));
+            let removed = diesel::update(from_owner)
+                .set(crate_owners::deleted.eq(true))
+                .execute(conn)?;
+            if removed == 0 {
+                self.cancel(conn)?;
+                return Ok(TransferCompletion::Cancelled);
+            }
+
+            diesel::insert_into(crate_owners::table)
+                .values(&CrateOwner {
+                    crate_id: self.crate_id,
+                    owner_id: self.to_user_id,
+                    created_by: self.from_user_id,
+                    owner_kind: OwnerKind::User as i32,
+                    email_notifications: true,
+                })
+                .on_conflict(crate_owners::table.primary_key())
+                .do_update()
+                .set(crate_owners::deleted.eq(false))
+                .execute(conn)?;
+
+            diesel::update(self)
+                .set(crate_transfers::completed_at.eq(now))
+                .execute(conn)?;
+
+            Ok(TransferCompletion::Completed)
+        })
+    }
+}
+
+/// The outcome of `CrateTransfer::complete`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferCompletion {
+    /// The ownership of the crate was moved to the recipient.
+    Completed,
+    /// The initiator isn't an owner of the crate anymore, so the transfer
+    /// was cancelled instead.
+    Cancelled,
+    /// The transfer was cancelled or completed in the meantime.
+    NoLongerPending,
+}
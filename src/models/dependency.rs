@@ -1,6 +1,6 @@
 use diesel::deserialize::{self, FromSql};
 use diesel::pg::Pg;
-use diesel::sql_types::{Integer, Text};
+use diesel::sql_types::Integer;
 
 use crate::models::{Crate, Version};
 use crate::schema::*;
@@ -23,14 +23,10 @@ pub struct Dependency {
     pub explicit_name: Option<String>,
 }
 
-#[derive(Debug, QueryableByName)]
+#[derive(Debug)]
 pub struct ReverseDependency {
-    #[diesel(embed)]
     pub dependency: Dependency,
-    #[diesel(sql_type = Integer)]
     pub crate_downloads: i32,
-    #[diesel(sql_type = Text)]
-    #[diesel(column_name = crate_name)]
     pub name: String,
 }
 
@@ -2,7 +2,7 @@ use crate::models::Version;
 use crate::schema::version_downloads;
 use chrono::NaiveDate;
 
-#[derive(Queryable, Identifiable, Associations, Debug, Clone, Copy)]
+#[derive(Queryable, Identifiable, Associations, Debug, Clone)]
 #[diesel(belongs_to(Version))]
 #[diesel(primary_key(version_id, date))]
 pub struct VersionDownload {
@@ -11,4 +11,7 @@ pub struct VersionDownload {
     pub counted: i32,
     pub date: NaiveDate,
     pub processed: bool,
+    /// HyperLogLog registers of the clients that downloaded the version on
+    /// this date, if they are counted from the CDN logs.
+    pub unique_clients: Option<Vec<u8>>,
 }
@@ -1,7 +1,9 @@
-use chrono::NaiveDateTime;
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::dsl::{now, sql};
+use diesel::prelude::*;
 
 use crate::models::User;
-use crate::schema::emails;
+use crate::schema::{email_changes, emails};
 
 #[derive(Debug, Queryable, AsChangeset, Identifiable, Associations)]
 #[diesel(belongs_to(User))]
@@ -12,6 +14,65 @@ pub struct Email {
     pub verified: bool,
     pub token: String,
     pub token_generated_at: Option<NaiveDateTime>,
+    pub send_notifications: bool,
+    pub public: bool,
+}
+
+impl Email {
+    /// Returns all email addresses of the user, oldest first.
+    pub fn for_user(conn: &mut PgConnection, user_id: i32) -> QueryResult<Vec<Self>> {
+        emails::table
+            .filter(emails::user_id.eq(user_id))
+            .order(emails::id)
+            .load(conn)
+    }
+
+    /// Finds an email address of the user by its ID.
+    pub fn find(conn: &mut PgConnection, id: i32, user_id: i32) -> QueryResult<Self> {
+        emails::table
+            .find(id)
+            .filter(emails::user_id.eq(user_id))
+            .first(conn)
+    }
+
+    /// Generates a new verification token for this address.
+    pub fn regenerate_token(&self, conn: &mut PgConnection) -> QueryResult<Self> {
+        diesel::update(self)
+            .set(emails::token.eq(sql("DEFAULT")))
+            .get_result(conn)
+    }
+
+    /// Sends all future notifications of the user to this address instead of
+    /// their current notification address.
+    pub fn make_notification_address(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        conn.transaction(|conn| {
+            diesel::update(emails::table.filter(emails::user_id.eq(self.user_id)))
+                .filter(emails::send_notifications.eq(true))
+                .set(emails::send_notifications.eq(false))
+                .execute(conn)?;
+            diesel::update(self)
+                .set(emails::send_notifications.eq(true))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Shows or hides this address on the public profile of the user, hiding
+    /// any other address that was shown.
+    pub fn set_public(&self, conn: &mut PgConnection, public: bool) -> QueryResult<()> {
+        conn.transaction(|conn| {
+            if public {
+                diesel::update(emails::table.filter(emails::user_id.eq(self.user_id)))
+                    .filter(emails::public.eq(true))
+                    .set(emails::public.eq(false))
+                    .execute(conn)?;
+            }
+            diesel::update(self)
+                .set(emails::public.eq(public))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
 }
 
 #[derive(Debug, Insertable, AsChangeset)]
@@ -19,4 +80,84 @@ pub struct Email {
 pub struct NewEmail<'a> {
     pub user_id: i32,
     pub email: &'a str,
+    pub send_notifications: bool,
+}
+
+/// A change of a verified email address, which can be reverted from the
+/// previous address in case the account was taken over.
+#[derive(Debug, Queryable, Identifiable, Associations)]
+#[diesel(belongs_to(User))]
+pub struct EmailChange {
+    pub id: i32,
+    pub user_id: i32,
+    pub old_email: String,
+    pub new_email: String,
+    pub revert_token: String,
+    pub created_at: NaiveDateTime,
+    pub reverted_at: Option<NaiveDateTime>,
+}
+
+impl EmailChange {
+    /// For how many days after the change it can be reverted.
+    pub const REVERT_VALID_DAYS: i64 = 30;
+
+    pub fn create(
+        conn: &mut PgConnection,
+        user_id: i32,
+        old_email: &str,
+        new_email: &str,
+    ) -> QueryResult<Self> {
+        diesel::insert_into(email_changes::table)
+            .values((
+                email_changes::user_id.eq(user_id),
+                email_changes::old_email.eq(old_email),
+                email_changes::new_email.eq(new_email),
+            ))
+            .get_result(conn)
+    }
+
+    /// Finds the change with the given revert token, unless it was already
+    /// reverted or is too old to be reverted.
+    pub fn find_revertable(conn: &mut PgConnection, token: &str) -> QueryResult<Self> {
+        let valid_after = Utc::now().naive_utc() - Duration::days(Self::REVERT_VALID_DAYS);
+        email_changes::table
+            .filter(email_changes::revert_token.eq(token))
+            .filter(email_changes::reverted_at.is_null())
+            .filter(email_changes::created_at.gt(valid_after))
+            .first(conn)
+    }
+
+    /// Restores the previous address as the verified notification address of
+    /// the user, adding it again if it was changed or removed since.
+    pub fn revert(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        conn.transaction(|conn| {
+            let previous = emails::table
+                .filter(emails::user_id.eq(self.user_id))
+                .filter(emails::email.eq(&self.old_email))
+                .first::<Email>(conn)
+                .optional()?;
+
+            let email = match previous {
+                Some(email) => email,
+                None => diesel::insert_into(emails::table)
+                    .values(&NewEmail {
+                        user_id: self.user_id,
+                        email: &self.old_email,
+                        send_notifications: false,
+                    })
+                    .get_result(conn)?,
+            };
+
+            diesel::update(&email)
+                .set(emails::verified.eq(true))
+                .execute(conn)?;
+            email.make_notification_address(conn)?;
+
+            diesel::update(self)
+                .set(email_changes::reverted_at.eq(now))
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
 }
@@ -0,0 +1,77 @@
+use chrono::NaiveDateTime;
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+
+use crate::schema::file_replications;
+
+/// The replication of a file in the primary bucket to a replica bucket.
+#[derive(Clone, Debug, Queryable)]
+pub struct FileReplication {
+    pub path: String,
+    pub replica: String,
+    pub created_at: NaiveDateTime,
+    pub replicated_at: Option<NaiveDateTime>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+}
+
+impl FileReplication {
+    /// Queues the replication of the file at `path` to the `replicas`. Files
+    /// that are uploaded again are replicated again.
+    pub fn queue(conn: &mut PgConnection, path: &str, replicas: &[String]) -> QueryResult<()> {
+        let rows = replicas
+            .iter()
+            .map(|replica| {
+                (
+                    file_replications::path.eq(path),
+                    file_replications::replica.eq(replica),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        diesel::insert_into(file_replications::table)
+            .values(&rows)
+            .on_conflict((file_replications::path, file_replications::replica))
+            .do_update()
+            .set((
+                file_replications::created_at.eq(excluded(file_replications::created_at)),
+                file_replications::replicated_at.eq(None::<NaiveDateTime>),
+                file_replications::attempts.eq(0),
+                file_replications::last_error.eq(None::<String>),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Returns up to `limit` files that were not replicated yet, oldest first.
+    pub fn pending(conn: &mut PgConnection, limit: i64) -> QueryResult<Vec<Self>> {
+        file_replications::table
+            .filter(file_replications::replicated_at.is_null())
+            .order(file_replications::created_at)
+            .limit(limit)
+            .load(conn)
+    }
+
+    pub fn mark_replicated(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        diesel::update(file_replications::table.find((&self.path, &self.replica)))
+            .set(file_replications::replicated_at.eq(now))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn record_failure(&self, conn: &mut PgConnection, error: &str) -> QueryResult<()> {
+        diesel::update(file_replications::table.find((&self.path, &self.replica)))
+            .set((
+                file_replications::attempts.eq(file_replications::attempts + 1),
+                file_replications::last_error.eq(error),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn delete(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        diesel::delete(file_replications::table.find((&self.path, &self.replica))).execute(conn)?;
+        Ok(())
+    }
+}
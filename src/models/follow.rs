@@ -1,5 +1,13 @@
+use chrono::NaiveDateTime;
+use diesel::deserialize::{self, FromSql};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::SmallInt;
+
 use crate::models::User;
-use crate::schema::follows;
+use crate::schema::{follow_notification_settings, follows};
+use crate::util::token::{SecureToken, SecureTokenKind};
 
 #[derive(Insertable, Queryable, Identifiable, Associations, Clone, Copy, Debug)]
 #[diesel(belongs_to(User))]
@@ -9,3 +17,170 @@ pub struct Follow {
     pub user_id: i32,
     pub crate_id: i32,
 }
+
+/// How often a user is notified about new and yanked versions of the crates
+/// they follow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromSqlRow, AsExpression)]
+#[repr(i16)]
+#[diesel(sql_type = SmallInt)]
+pub enum FollowNotificationFrequency {
+    Never = 0,
+    Daily = 1,
+    Weekly = 2,
+}
+
+impl FollowNotificationFrequency {
+    pub fn from_param(param: &str) -> Option<Self> {
+        match param {
+            "never" => Some(FollowNotificationFrequency::Never),
+            "daily" => Some(FollowNotificationFrequency::Daily),
+            "weekly" => Some(FollowNotificationFrequency::Weekly),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FollowNotificationFrequency::Never => "never",
+            FollowNotificationFrequency::Daily => "daily",
+            FollowNotificationFrequency::Weekly => "weekly",
+        }
+    }
+
+    /// The minimum time between two notifications, or `None` if the user
+    /// isn't notified at all.
+    pub fn interval(&self) -> Option<chrono::Duration> {
+        match self {
+            FollowNotificationFrequency::Never => None,
+            FollowNotificationFrequency::Daily => Some(chrono::Duration::days(1)),
+            FollowNotificationFrequency::Weekly => Some(chrono::Duration::weeks(1)),
+        }
+    }
+}
+
+impl FromSql<SmallInt, Pg> for FollowNotificationFrequency {
+    fn from_sql(bytes: diesel::pg::PgValue<'_>) -> deserialize::Result<Self> {
+        match <i16 as FromSql<SmallInt, Pg>>::from_sql(bytes)? {
+            0 => Ok(FollowNotificationFrequency::Never),
+            1 => Ok(FollowNotificationFrequency::Daily),
+            2 => Ok(FollowNotificationFrequency::Weekly),
+            n => Err(format!("unknown follow notification frequency: {n}").into()),
+        }
+    }
+}
+
+impl ToSql<SmallInt, Pg> for FollowNotificationFrequency {
+    fn to_sql(&self, out: &mut Output<'_, '_, Pg>) -> serialize::Result {
+        ToSql::<SmallInt, Pg>::to_sql(&(*self as i16), &mut out.reborrow())
+    }
+}
+
+/// How a user wants to be notified about the crates they follow. Users
+/// without settings are not notified.
+#[derive(Clone, Identifiable, Queryable, Associations)]
+#[diesel(table_name = follow_notification_settings, belongs_to(User), primary_key(user_id))]
+pub struct FollowNotificationSettings {
+    pub user_id: i32,
+    pub frequency: FollowNotificationFrequency,
+    pub webhook_url: Option<String>,
+    pub last_notified_at: NaiveDateTime,
+    webhook_secret: Option<String>,
+    /// The user was notified about all version actions up to this time.
+    pub notified_until: NaiveDateTime,
+}
+
+impl FollowNotificationSettings {
+    pub fn find(conn: &mut PgConnection, user_id: i32) -> QueryResult<Option<Self>> {
+        follow_notification_settings::table
+            .find(user_id)
+            .first(conn)
+            .optional()
+    }
+
+    /// Returns the key that is used to sign the payloads sent to the webhook
+    /// URL, which is the secret that was shown when the URL was set.
+    pub(crate) fn signing_key(&self) -> Option<&[u8]> {
+        self.webhook_secret.as_deref().map(str::as_bytes)
+    }
+
+    /// Stores the settings of the user. Newly enabled notifications only
+    /// cover versions that are published or yanked afterwards.
+    ///
+    /// A new webhook secret is generated whenever the webhook URL changes,
+    /// and returned in plaintext together with the settings.
+    pub fn save(
+        conn: &mut PgConnection,
+        user_id: i32,
+        frequency: FollowNotificationFrequency,
+        webhook_url: Option<&str>,
+    ) -> QueryResult<(Self, Option<String>)> {
+        let existing = Self::find(conn, user_id)?;
+
+        let notified_until = existing
+            .as_ref()
+            .filter(|settings| settings.frequency != FollowNotificationFrequency::Never)
+            .map(|settings| settings.notified_until)
+            .unwrap_or_else(|| chrono::Utc::now().naive_utc());
+
+        let existing_secret = existing
+            .filter(|settings| settings.webhook_url.as_deref() == webhook_url)
+            .and_then(|settings| settings.webhook_secret);
+        let new_secret = webhook_url
+            .filter(|_| existing_secret.is_none())
+            .map(|_| SecureToken::generate(SecureTokenKind::WebhookSecret));
+        let webhook_secret = match &new_secret {
+            Some(secret) => Some(secret.plaintext()),
+            None => existing_secret.as_deref(),
+        };
+
+        let settings = diesel::insert_into(follow_notification_settings::table)
+            .values((
+                follow_notification_settings::user_id.eq(user_id),
+                follow_notification_settings::frequency.eq(frequency),
+                follow_notification_settings::webhook_url.eq(webhook_url),
+                follow_notification_settings::webhook_secret.eq(webhook_secret),
+                follow_notification_settings::notified_until.eq(notified_until),
+            ))
+            .on_conflict(follow_notification_settings::user_id)
+            .do_update()
+            .set((
+                follow_notification_settings::frequency.eq(frequency),
+                follow_notification_settings::webhook_url.eq(webhook_url),
+                follow_notification_settings::webhook_secret.eq(webhook_secret),
+                follow_notification_settings::notified_until.eq(notified_until),
+            ))
+            .get_result(conn)?;
+
+        let new_secret = new_secret.map(|secret| secret.plaintext().to_string());
+        Ok((settings, new_secret))
+    }
+
+    /// Records that the user was notified about all actions up to
+    /// `notified_until`.
+    pub fn mark_notified(
+        &self,
+        conn: &mut PgConnection,
+        notified_until: NaiveDateTime,
+    ) -> QueryResult<()> {
+        diesel::update(self)
+            .set((
+                follow_notification_settings::notified_until.eq(notified_until),
+                follow_notification_settings::last_notified_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+}
+
+// Use a custom implementation of Debug to hide the webhook secret.
+impl std::fmt::Debug for FollowNotificationSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FollowNotificationSettings")
+            .field("user_id", &self.user_id)
+            .field("frequency", &self.frequency)
+            .field("webhook_url", &self.webhook_url)
+            .field("last_notified_at", &self.last_notified_at)
+            .field("notified_until", &self.notified_until)
+            .finish()
+    }
+}
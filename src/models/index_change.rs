@@ -0,0 +1,82 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+
+use crate::schema::index_changes;
+
+/// Key of the transaction-level advisory lock that serializes the recording
+/// of index changes.
+const RECORD_LOCK_KEY: i64 = 0x696e_6465_785f_6368; // "index_ch"
+
+/// The kinds of changes to the index file of a crate that are recorded in the
+/// changelog of the index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexChangeAction {
+    Publish,
+    Yank,
+    Unyank,
+    Delete,
+    Deprecate,
+}
+
+impl IndexChangeAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Publish => "publish",
+            Self::Yank => "yank",
+            Self::Unyank => "unyank",
+            Self::Delete => "delete",
+            Self::Deprecate => "deprecate",
+        }
+    }
+}
+
+/// An entry of the append-only changelog of the index. Its `id` is the
+/// sequence number that mirrors use to follow the changelog.
+///
+/// Changes are recorded while holding a lock until the recording transaction
+/// ends, so that they become visible in the order of their sequence numbers.
+/// Otherwise a change of a long transaction could become visible after a
+/// mirror already followed the changelog past its sequence number.
+#[derive(Clone, Debug, Identifiable, Queryable)]
+pub struct IndexChange {
+    pub id: i64,
+    pub crate_name: String,
+    pub version: Option<String>,
+    pub action: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl IndexChange {
+    pub fn record(
+        conn: &mut PgConnection,
+        crate_name: &str,
+        version: Option<&str>,
+        action: IndexChangeAction,
+    ) -> QueryResult<()> {
+        conn.transaction(|conn| {
+            diesel::sql_query("SELECT pg_advisory_xact_lock($1)")
+                .bind::<BigInt, _>(RECORD_LOCK_KEY)
+                .execute(conn)?;
+
+            diesel::insert_into(index_changes::table)
+                .values((
+                    index_changes::crate_name.eq(crate_name),
+                    index_changes::version.eq(version),
+                    index_changes::action.eq(action.as_str()),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Returns up to `limit` changes with sequence numbers greater than
+    /// `since`, oldest first.
+    pub fn since(conn: &mut PgConnection, since: i64, limit: i64) -> QueryResult<Vec<Self>> {
+        index_changes::table
+            .filter(index_changes::id.gt(since))
+            .order(index_changes::id)
+            .limit(limit)
+            .load(conn)
+    }
+}
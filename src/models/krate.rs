@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+
+use cargo_registry_index::Deprecation;
 use chrono::NaiveDateTime;
 use diesel::associations::Identifiable;
 use diesel::pg::Pg;
@@ -6,16 +9,14 @@ use diesel::sql_types::{Bool, Text};
 use url::Url;
 
 use crate::app::App;
-use crate::controllers::helpers::pagination::*;
 use crate::models::version::TopVersions;
 use crate::models::{
-    CrateOwner, CrateOwnerInvitation, NewCrateOwnerInvitationOutcome, Owner, OwnerKind,
-    ReverseDependency, User, Version,
+    CrateOwner, CrateOwnerInvitation, Dependency, NewCrateOwnerInvitationOutcome, NotificationKind,
+    Organization, OrganizationRole, Owner, OwnerKind, User, Version,
 };
-use crate::util::errors::{cargo_err, AppResult};
+use crate::util::errors::{cargo_err, AppResult, ReservedCrateName};
 
-use crate::models::helpers::with_count::*;
-use crate::publish_rate_limit::PublishRateLimit;
+use crate::publish_rate_limit::{LimitedAction, PublishRateLimit};
 use crate::schema::*;
 use crate::sql::canon_crate_name;
 
@@ -41,6 +42,9 @@ pub struct Crate {
     pub documentation: Option<String>,
     pub repository: Option<String>,
     pub max_upload_size: Option<i32>,
+    pub deprecated: bool,
+    pub deprecation_message: Option<String>,
+    pub deprecation_successor: Option<String>,
 }
 
 /// We literally never want to select `textsearchable_index_col`
@@ -56,6 +60,9 @@ type AllColumns = (
     crates::documentation,
     crates::repository,
     crates::max_upload_size,
+    crates::deprecated,
+    crates::deprecation_message,
+    crates::deprecation_successor,
 );
 
 pub const ALL_COLUMNS: AllColumns = (
@@ -69,6 +76,9 @@ pub const ALL_COLUMNS: AllColumns = (
     crates::documentation,
     crates::repository,
     crates::max_upload_size,
+    crates::deprecated,
+    crates::deprecation_message,
+    crates::deprecation_successor,
 );
 
 pub const MAX_NAME_LENGTH: usize = 64;
@@ -110,7 +120,7 @@ impl<'a> NewCrate<'a> {
             // first so we know whether to add an owner
             if let Some(krate) = self.save_new_crate(conn, uploader)? {
                 if let Some(rate_limit) = rate_limit {
-                    rate_limit.check_rate_limit(uploader, conn)?;
+                    rate_limit.check_rate_limit(uploader, LimitedAction::PublishNew, conn)?;
                 }
                 return Ok(krate);
             }
@@ -161,7 +171,7 @@ impl<'a> NewCrate<'a> {
         ))
         .get_result(conn)?;
         if reserved_name {
-            Err(cargo_err("cannot upload a crate with a reserved name"))
+            Err(Box::new(ReservedCrateName))
         } else {
             Ok(())
         }
@@ -248,6 +258,113 @@ impl Crate {
             })
     }
 
+    /// Returns the deprecation that is recorded in the index entry of a
+    /// version of this crate, if the version or the whole crate is deprecated.
+    ///
+    /// The message of a deprecated version takes precedence over the message
+    /// of the crate, while the successor is always taken from the crate.
+    pub fn index_deprecation(&self, version: Option<&Version>) -> Option<Deprecation> {
+        let version = version.filter(|version| version.deprecated);
+        if !self.deprecated && version.is_none() {
+            return None;
+        }
+
+        let crate_message = self.deprecated.then(|| self.deprecation_message.clone());
+        let message = version
+            .and_then(|version| version.deprecation_message.clone())
+            .or_else(|| crate_message.flatten());
+        let successor = self
+            .deprecated
+            .then(|| self.deprecation_successor.clone())
+            .flatten();
+
+        Some(Deprecation { message, successor })
+    }
+
+    /// Renders the entries of the index file of this crate from the
+    /// database, one per version in the order they were published.
+    pub fn index_metadata(
+        &self,
+        conn: &mut PgConnection,
+    ) -> QueryResult<Vec<cargo_registry_index::Crate>> {
+        let versions: Vec<Version> = self.all_versions().order(versions::id).load(conn)?;
+
+        let deps: Vec<(Dependency, String)> = Dependency::belonging_to(&versions)
+            .inner_join(crates::table)
+            .select((dependencies::all_columns, crates::name))
+            .load(conn)?;
+        let deps = deps.grouped_by(&versions);
+
+        let entries = versions
+            .into_iter()
+            .zip(deps)
+            .map(|(version, deps)| {
+                let mut deps = deps
+                    .into_iter()
+                    .map(|(dep, name)| {
+                        // The index lists dependencies by the name used in
+                        // `Cargo.toml`, with the crate name as `package`.
+                        let (name, package) = match dep.explicit_name {
+                            Some(explicit_name) => (explicit_name, Some(name)),
+                            None => (name, None),
+                        };
+
+                        cargo_registry_index::Dependency {
+                            name,
+                            req: dep.req,
+                            features: dep.features,
+                            optional: dep.optional,
+                            default_features: dep.default_features,
+                            target: dep.target,
+                            kind: Some(dep.kind.into()),
+                            package,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                deps.sort();
+
+                let features: BTreeMap<String, Vec<String>> =
+                    serde_json::from_value(version.features.clone()).unwrap_or_default();
+                let (features, features2, v) =
+                    cargo_registry_index::Crate::split_features(features);
+
+                cargo_registry_index::Crate {
+                    name: self.name.clone(),
+                    deprecated: self.index_deprecation(Some(&version)),
+                    vers: version.num,
+                    cksum: version.checksum,
+                    features,
+                    features2,
+                    deps,
+                    yanked: Some(version.yanked),
+                    yank_reason: version.yank_reason,
+                    yank_replacement: version.yank_replacement,
+                    links: version.links,
+                    v,
+                }
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    /// Renders the index file of this crate from the database, or returns
+    /// `None` if the crate has no versions and thus no index file.
+    pub fn index_file(&self, conn: &mut PgConnection) -> QueryResult<Option<String>> {
+        let entries = self.index_metadata(conn)?;
+        if entries.is_empty() {
+            return Ok(None);
+        }
+
+        let mut contents = String::new();
+        for entry in entries {
+            let line = serde_json::to_string(&entry).expect("index entries are valid JSON");
+            contents.push_str(&line);
+            contents.push('\n');
+        }
+        Ok(Some(contents))
+    }
+
     pub fn valid_name(name: &str) -> bool {
         let under_max_length = name.chars().take(MAX_NAME_LENGTH + 1).count() <= MAX_NAME_LENGTH;
         Crate::valid_ident(name) && under_max_length
@@ -329,8 +446,9 @@ impl Crate {
             .load(conn)?
             .into_iter()
             .map(Owner::Team);
+        let organizations = Organization::owning(self, conn)?;
 
-        Ok(users.chain(teams).collect())
+        Ok(users.chain(teams).chain(organizations).collect())
     }
 
     pub fn owner_add(
@@ -340,8 +458,6 @@ impl Crate {
         req_user: &User,
         login: &str,
     ) -> AppResult<String> {
-        use diesel::insert_into;
-
         let owner = Owner::find_or_create_by_login(app, conn, req_user, login)?;
 
         match owner {
@@ -350,7 +466,9 @@ impl Crate {
                 let config = &app.config;
                 match CrateOwnerInvitation::create(user.id, req_user.id, self.id, conn, config)? {
                     NewCrateOwnerInvitationOutcome::InviteCreated { plaintext_token } => {
-                        if let Ok(Some(email)) = user.verified_email(conn) {
+                        if let Ok(Some(email)) =
+                            user.notification_email(conn, NotificationKind::OwnerInvites)
+                        {
                             // Swallow any error. Whether or not the email is sent, the invitation
                             // entry will be created in the database and the user will see the
                             // invitation when they visit https://crates.io/me/pending-invites/.
@@ -375,18 +493,7 @@ impl Crate {
             }
             // Teams are added as owners immediately
             owner @ Owner::Team(_) => {
-                insert_into(crate_owners::table)
-                    .values(&CrateOwner {
-                        crate_id: self.id,
-                        owner_id: owner.id(),
-                        created_by: req_user.id,
-                        owner_kind: OwnerKind::Team as i32,
-                        email_notifications: true,
-                    })
-                    .on_conflict(crate_owners::table.primary_key())
-                    .do_update()
-                    .set(crate_owners::deleted.eq(false))
-                    .execute(conn)?;
+                self.insert_owner(conn, &owner, req_user.id)?;
 
                 Ok(format!(
                     "team {} has been added as an owner of crate {}",
@@ -394,9 +501,48 @@ impl Crate {
                     self.name
                 ))
             }
+            // Organizations are added immediately as well, but only by their
+            // admins, since they can manage the crate from then on
+            Owner::Organization(ref organization) => {
+                organization.ensure_role(
+                    conn,
+                    req_user.id,
+                    OrganizationRole::Admin,
+                    "only admins of an organization can add it as an owner",
+                )?;
+                self.insert_owner(conn, &owner, req_user.id)?;
+
+                Ok(format!(
+                    "organization {} has been added as an owner of crate {}",
+                    organization.name, self.name
+                ))
+            }
         }
     }
 
+    /// Adds a team or an organization as an owner, or restores it if it was
+    /// removed before.
+    pub fn insert_owner(
+        &self,
+        conn: &mut PgConnection,
+        owner: &Owner,
+        created_by: i32,
+    ) -> QueryResult<()> {
+        diesel::insert_into(crate_owners::table)
+            .values(&CrateOwner {
+                crate_id: self.id,
+                owner_id: owner.id(),
+                created_by,
+                owner_kind: owner.kind(),
+                email_notifications: true,
+            })
+            .on_conflict(crate_owners::table.primary_key())
+            .do_update()
+            .set(crate_owners::deleted.eq(false))
+            .execute(conn)?;
+        Ok(())
+    }
+
     pub fn owner_remove(
         &self,
         app: &App,
@@ -412,26 +558,6 @@ impl Crate {
             .execute(conn)?;
         Ok(())
     }
-
-    /// Returns (dependency, dependent crate name, dependent crate downloads)
-    pub(crate) fn reverse_dependencies(
-        &self,
-        conn: &mut PgConnection,
-        options: PaginationOptions,
-    ) -> AppResult<(Vec<ReverseDependency>, i64)> {
-        use diesel::sql_query;
-        use diesel::sql_types::{BigInt, Integer};
-
-        let offset = options.offset().unwrap_or_default();
-        let rows: Vec<WithCount<ReverseDependency>> =
-            sql_query(include_str!("krate_reverse_dependencies.sql"))
-                .bind::<Integer, _>(self.id)
-                .bind::<BigInt, _>(offset)
-                .bind::<BigInt, _>(options.per_page)
-                .load(conn)?;
-
-        Ok(rows.records_and_total())
-    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,207 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::email::Emails;
+use crate::models::{NewUser, User};
+use crate::schema::linked_accounts;
+use crate::util::errors::{bad_request, AppResult, BoxedAppError};
+
+/// The OAuth identity providers that users can log in with, and whose teams
+/// can own crates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i16)]
+pub enum AccountProvider {
+    GitHub = 0,
+    GitLab = 1,
+    /// The single sign-on identity provider of a private deployment, whose
+    /// identities are stored in the `sso_identities` table instead.
+    Sso = 2,
+}
+
+impl AccountProvider {
+    /// Parses the `provider` parameter of the session and linked account
+    /// routes.
+    pub fn from_param(param: &str) -> Option<Self> {
+        match param {
+            "github" => Some(AccountProvider::GitHub),
+            "gitlab" => Some(AccountProvider::GitLab),
+            "sso" => Some(AccountProvider::Sso),
+            _ => None,
+        }
+    }
+
+    pub fn from_i16(provider: i16) -> Option<Self> {
+        match provider {
+            0 => Some(AccountProvider::GitHub),
+            1 => Some(AccountProvider::GitLab),
+            2 => Some(AccountProvider::Sso),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountProvider::GitHub => "github",
+            AccountProvider::GitLab => "gitlab",
+            AccountProvider::Sso => "sso",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            AccountProvider::GitHub => "GitHub",
+            AccountProvider::GitLab => "GitLab",
+            AccountProvider::Sso => "single sign-on",
+        }
+    }
+}
+
+/// An additional account at an identity provider, that the user can log in
+/// with, and whose team memberships count for the crates they own.
+///
+/// The GitHub account that a user signed up with is stored in the `users`
+/// table itself, since it used to be the only way to log in.
+#[derive(Clone, Debug, Queryable, Associations)]
+#[diesel(belongs_to(User))]
+pub struct LinkedAccount {
+    pub user_id: i32,
+    pub provider: i16,
+    pub account_id: i32,
+    pub login: String,
+    pub avatar: Option<String>,
+    pub access_token: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl LinkedAccount {
+    /// Returns all accounts that the user linked, oldest first.
+    pub fn for_user(conn: &mut PgConnection, user_id: i32) -> QueryResult<Vec<Self>> {
+        linked_accounts::table
+            .filter(linked_accounts::user_id.eq(user_id))
+            .order((linked_accounts::created_at, linked_accounts::account_id))
+            .load(conn)
+    }
+
+    /// Returns the accounts that the user linked at the identity provider.
+    pub fn for_provider(
+        conn: &mut PgConnection,
+        user_id: i32,
+        provider: AccountProvider,
+    ) -> QueryResult<Vec<Self>> {
+        linked_accounts::table
+            .filter(linked_accounts::user_id.eq(user_id))
+            .filter(linked_accounts::provider.eq(provider as i16))
+            .order((linked_accounts::created_at, linked_accounts::account_id))
+            .load(conn)
+    }
+
+    pub fn find(
+        conn: &mut PgConnection,
+        user_id: i32,
+        provider: AccountProvider,
+        account_id: i32,
+    ) -> QueryResult<Self> {
+        linked_accounts::table
+            .find((provider as i16, account_id))
+            .filter(linked_accounts::user_id.eq(user_id))
+            .first(conn)
+    }
+
+    pub fn delete(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        diesel::delete(linked_accounts::table.find((self.provider, self.account_id)))
+            .execute(conn)?;
+        Ok(())
+    }
+}
+
+/// An account as returned by an identity provider after an OAuth login.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = linked_accounts)]
+pub struct NewLinkedAccount<'a> {
+    pub provider: i16,
+    pub account_id: i32,
+    pub login: &'a str,
+    pub avatar: Option<&'a str>,
+    pub access_token: &'a str,
+}
+
+impl<'a> NewLinkedAccount<'a> {
+    pub fn new(
+        provider: AccountProvider,
+        account_id: i32,
+        login: &'a str,
+        avatar: Option<&'a str>,
+        access_token: &'a str,
+    ) -> Self {
+        NewLinkedAccount {
+            provider: provider as i16,
+            account_id,
+            login,
+            avatar,
+            access_token,
+        }
+    }
+
+    /// Updates the details of the account if it is linked, and returns the
+    /// ID of the user that logs in with it.
+    pub fn login(&self, conn: &mut PgConnection) -> QueryResult<Option<i32>> {
+        diesel::update(linked_accounts::table.find((self.provider, self.account_id)))
+            .set((
+                linked_accounts::login.eq(self.login),
+                linked_accounts::avatar.eq(self.avatar),
+                linked_accounts::access_token.eq(self.access_token),
+            ))
+            .returning(linked_accounts::user_id)
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Links the account to the user. Accounts that are already linked to
+    /// another user are rejected instead of being moved.
+    pub fn link(&self, conn: &mut PgConnection, user_id: i32) -> AppResult<()> {
+        conn.transaction::<_, BoxedAppError, _>(|conn| match self.login(conn)? {
+            Some(owner_id) if owner_id == user_id => Ok(()),
+            Some(_) => Err(self.linked_to_another_user()),
+            None => {
+                diesel::insert_into(linked_accounts::table)
+                    .values((linked_accounts::user_id.eq(user_id), self))
+                    .execute(conn)?;
+                Ok(())
+            }
+        })
+    }
+
+    /// Signs up a new user that logs in with this account, and has no GitHub
+    /// account of its own.
+    pub fn create_user(
+        &self,
+        name: Option<&'a str>,
+        email: Option<&'a str>,
+        emails: &Emails,
+        conn: &mut PgConnection,
+    ) -> AppResult<User> {
+        conn.transaction::<_, BoxedAppError, _>(|conn| {
+            if User::login_is_taken(conn, self.login)? {
+                return Err(bad_request(&format_args!(
+                    "the username `{}` is already taken, please log in with \
+                     your other account first to link this one",
+                    self.login
+                )));
+            }
+
+            let user = NewUser::new(User::NO_GITHUB_ID, self.login, name, self.avatar, "")
+                .create_or_update(email, emails, conn)?;
+            self.link(conn, user.id)?;
+            Ok(user)
+        })
+    }
+
+    pub fn linked_to_another_user(&self) -> BoxedAppError {
+        let provider = AccountProvider::from_i16(self.provider)
+            .map(|provider| provider.display_name())
+            .unwrap_or("identity provider");
+        bad_request(&format_args!(
+            "this {provider} account is already linked to another crates.io account"
+        ))
+    }
+}
@@ -0,0 +1,95 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+
+use crate::models::User;
+use crate::schema::notification_preferences;
+
+/// The kinds of emails that users can opt out of.
+///
+/// Emails that are required to use an account, like the confirmation of an
+/// email address or of an account deletion, are always sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotificationKind {
+    /// Invitations to become an owner of a crate, and updates about crate
+    /// transfers and team ownership.
+    OwnerInvites,
+    /// Alerts about exposed API tokens.
+    SecurityAlerts,
+    /// Periodic summaries, like the alerts of saved searches.
+    Digests,
+}
+
+/// The kinds of emails that a user wants to receive.
+///
+/// Users without stored preferences receive all of them.
+#[derive(Clone, Debug, PartialEq, Eq, Identifiable, Queryable, Associations)]
+#[diesel(table_name = notification_preferences, belongs_to(User), primary_key(user_id))]
+pub struct NotificationPreferences {
+    pub user_id: i32,
+    pub owner_invites: bool,
+    pub security_alerts: bool,
+    pub digests: bool,
+    pub updated_at: NaiveDateTime,
+}
+
+impl NotificationPreferences {
+    pub fn for_user(conn: &mut PgConnection, user_id: i32) -> QueryResult<Self> {
+        let preferences = notification_preferences::table
+            .find(user_id)
+            .first(conn)
+            .optional()?;
+
+        Ok(preferences.unwrap_or_else(|| Self::default_for(user_id)))
+    }
+
+    fn default_for(user_id: i32) -> Self {
+        Self {
+            user_id,
+            owner_invites: true,
+            security_alerts: true,
+            digests: true,
+            updated_at: Utc::now().naive_utc(),
+        }
+    }
+
+    /// Returns whether the user wants to receive emails of the given kind.
+    pub fn allows(
+        conn: &mut PgConnection,
+        user_id: i32,
+        kind: NotificationKind,
+    ) -> QueryResult<bool> {
+        Ok(Self::for_user(conn, user_id)?.is_enabled(kind))
+    }
+
+    pub fn is_enabled(&self, kind: NotificationKind) -> bool {
+        match kind {
+            NotificationKind::OwnerInvites => self.owner_invites,
+            NotificationKind::SecurityAlerts => self.security_alerts,
+            NotificationKind::Digests => self.digests,
+        }
+    }
+
+    pub fn save(&self, conn: &mut PgConnection) -> QueryResult<Self> {
+        use diesel::dsl::now;
+        use diesel::upsert::excluded;
+
+        diesel::insert_into(notification_preferences::table)
+            .values((
+                notification_preferences::user_id.eq(self.user_id),
+                notification_preferences::owner_invites.eq(self.owner_invites),
+                notification_preferences::security_alerts.eq(self.security_alerts),
+                notification_preferences::digests.eq(self.digests),
+            ))
+            .on_conflict(notification_preferences::user_id)
+            .do_update()
+            .set((
+                notification_preferences::owner_invites
+                    .eq(excluded(notification_preferences::owner_invites)),
+                notification_preferences::security_alerts
+                    .eq(excluded(notification_preferences::security_alerts)),
+                notification_preferences::digests.eq(excluded(notification_preferences::digests)),
+                notification_preferences::updated_at.eq(now),
+            ))
+            .get_result(conn)
+    }
+}
@@ -0,0 +1,425 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::models::{Crate, CrateOwner, Owner, OwnerKind, User};
+use crate::schema::{
+    crate_owners, crates, organization_invitations, organization_members, organizations, users,
+};
+use crate::sql::lower;
+use crate::util::errors::{bad_request, cargo_err, AppResult, BoxedAppError};
+
+/// The maximum length of the name of an organization.
+pub const MAX_NAME_LENGTH: usize = 39;
+
+/// The role of a member of an organization.
+/// NOTE: The order of these variants matters, every role includes the
+/// permissions of the roles before it!
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+#[repr(i16)]
+pub enum OrganizationRole {
+    /// Can see the members of the organization.
+    Viewer = 0,
+    /// Can publish and yank the crates of the organization, and create
+    /// organization tokens to do so.
+    Publisher = 1,
+    /// Can manage the members of the organization and the owners of its
+    /// crates.
+    Admin = 2,
+}
+
+impl OrganizationRole {
+    pub fn from_param(param: &str) -> Option<Self> {
+        match param {
+            "viewer" => Some(OrganizationRole::Viewer),
+            "publisher" => Some(OrganizationRole::Publisher),
+            "admin" => Some(OrganizationRole::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn from_i16(role: i16) -> Option<Self> {
+        match role {
+            0 => Some(OrganizationRole::Viewer),
+            1 => Some(OrganizationRole::Publisher),
+            2 => Some(OrganizationRole::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrganizationRole::Viewer => "viewer",
+            OrganizationRole::Publisher => "publisher",
+            OrganizationRole::Admin => "admin",
+        }
+    }
+}
+
+/// An account that is shared by several users, and can own crates directly.
+#[derive(Clone, Debug, PartialEq, Eq, Queryable, Identifiable)]
+pub struct Organization {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl Organization {
+    /// The prefix of organizations in the list of owners of a crate, like
+    /// `org:rust-lang`.
+    pub const OWNER_PREFIX: &'static str = "org:";
+
+    pub fn find(conn: &mut PgConnection, id: i32) -> QueryResult<Self> {
+        organizations::table.find(id).first(conn)
+    }
+
+    pub fn find_by_name(conn: &mut PgConnection, name: &str) -> QueryResult<Self> {
+        organizations::table
+            .filter(lower(organizations::name).eq(name.to_lowercase()))
+            .first(conn)
+    }
+
+    /// The name of the organization as a crate owner.
+    pub fn login(&self) -> String {
+        format!("{}{}", Self::OWNER_PREFIX, self.name)
+    }
+
+    /// Returns the role of the user in the organization, if they are a member.
+    pub fn role_of(
+        &self,
+        conn: &mut PgConnection,
+        user_id: i32,
+    ) -> QueryResult<Option<OrganizationRole>> {
+        role_of(conn, self.id, user_id)
+    }
+
+    /// Fails with `error` unless the user has at least the given role.
+    pub fn ensure_role(
+        &self,
+        conn: &mut PgConnection,
+        user_id: i32,
+        role: OrganizationRole,
+        error: &str,
+    ) -> AppResult<()> {
+        match self.role_of(conn, user_id)? {
+            Some(actual) if actual >= role => Ok(()),
+            _ => Err(cargo_err(error)),
+        }
+    }
+
+    /// Returns the members of the organization with their roles, ordered by
+    /// their login.
+    pub fn members(&self, conn: &mut PgConnection) -> QueryResult<Vec<(User, i16)>> {
+        organization_members::table
+            .inner_join(users::table)
+            .filter(organization_members::organization_id.eq(self.id))
+            .select((users::all_columns, organization_members::role))
+            .order(lower(users::gh_login))
+            .load(conn)
+    }
+
+    /// Changes the role of a member, or removes them if `role` is `None`.
+    ///
+    /// Organizations always keep at least one admin, so that their members
+    /// and crates can still be managed.
+    pub fn update_member(
+        &self,
+        conn: &mut PgConnection,
+        user_id: i32,
+        role: Option<OrganizationRole>,
+    ) -> AppResult<()> {
+        conn.transaction::<_, BoxedAppError, _>(|conn| {
+            let member = organization_members::table.find((self.id, user_id));
+            let current = member
+                .select(organization_members::role)
+                .for_update()
+                .first::<i16>(conn)
+                .optional()?
+                .ok_or_else(|| bad_request("this user is not a member of the organization"))?;
+
+            if current == OrganizationRole::Admin as i16 && role != Some(OrganizationRole::Admin) {
+                let admins: i64 = organization_members::table
+                    .filter(organization_members::organization_id.eq(self.id))
+                    .filter(organization_members::role.eq(OrganizationRole::Admin as i16))
+                    .count()
+                    .get_result(conn)?;
+                if admins <= 1 {
+                    return Err(bad_request(
+                        "an organization needs at least one admin, \
+                         please make another member an admin first",
+                    ));
+                }
+            }
+
+            match role {
+                Some(role) => {
+                    diesel::update(member)
+                        .set(organization_members::role.eq(role as i16))
+                        .execute(conn)?;
+                }
+                None => {
+                    diesel::delete(member).execute(conn)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Deletes the organization, unless it still owns crates.
+    pub fn delete(&self, conn: &mut PgConnection) -> AppResult<()> {
+        conn.transaction::<_, BoxedAppError, _>(|conn| {
+            // Prevents the organization from being added as an owner while
+            // it is deleted
+            organizations::table
+                .find(self.id)
+                .for_update()
+                .execute(conn)?;
+
+            let owns_crates = diesel::select(diesel::dsl::exists(
+                CrateOwner::by_owner_kind(OwnerKind::Organization)
+                    .filter(crate_owners::owner_id.eq(self.id)),
+            ))
+            .get_result::<bool>(conn)?;
+            if owns_crates {
+                return Err(bad_request(
+                    "this organization still owns crates, \
+                     please remove it from their owners first",
+                ));
+            }
+
+            diesel::delete(self).execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Returns the organizations that the user is the only admin of.
+    pub fn last_admin_of(conn: &mut PgConnection, user_id: i32) -> QueryResult<Vec<Self>> {
+        let administered: Vec<Organization> = organizations::table
+            .inner_join(organization_members::table)
+            .filter(organization_members::user_id.eq(user_id))
+            .filter(organization_members::role.eq(OrganizationRole::Admin as i16))
+            .select(organizations::all_columns)
+            .order(organizations::name)
+            .load(conn)?;
+
+        let mut last_admin_of = Vec::new();
+        for organization in administered {
+            let admins: i64 = organization_members::table
+                .filter(organization_members::organization_id.eq(organization.id))
+                .filter(organization_members::role.eq(OrganizationRole::Admin as i16))
+                .count()
+                .get_result(conn)?;
+            if admins <= 1 {
+                last_admin_of.push(organization);
+            }
+        }
+        Ok(last_admin_of)
+    }
+
+    pub fn owning(krate: &Crate, conn: &mut PgConnection) -> QueryResult<Vec<Owner>> {
+        let organizations = organizations::table
+            .filter(
+                organizations::id.eq_any(
+                    CrateOwner::by_owner_kind(OwnerKind::Organization)
+                        .select(crate_owners::owner_id)
+                        .filter(crate_owners::crate_id.eq(krate.id)),
+                ),
+            )
+            .order(organizations::name)
+            .load(conn)?
+            .into_iter()
+            .map(Owner::Organization);
+
+        Ok(organizations.collect())
+    }
+
+    /// Checks whether a token of the organization may be used for the crate:
+    /// its user has to be allowed to publish for the organization, and the
+    /// crate has to be owned by the organization, or not exist yet.
+    pub fn token_may_access(
+        conn: &mut PgConnection,
+        organization_id: i32,
+        user_id: i32,
+        crate_name: &str,
+    ) -> QueryResult<bool> {
+        let role = role_of(conn, organization_id, user_id)?;
+        if role < Some(OrganizationRole::Publisher) {
+            return Ok(false);
+        }
+
+        let krate = Crate::by_name(crate_name)
+            .select(crates::id)
+            .first::<i32>(conn)
+            .optional()?;
+        let Some(crate_id) = krate else {
+            return Ok(true);
+        };
+
+        diesel::select(diesel::dsl::exists(
+            CrateOwner::by_owner_kind(OwnerKind::Organization)
+                .filter(crate_owners::crate_id.eq(crate_id))
+                .filter(crate_owners::owner_id.eq(organization_id)),
+        ))
+        .get_result(conn)
+    }
+}
+
+fn role_of(
+    conn: &mut PgConnection,
+    organization_id: i32,
+    user_id: i32,
+) -> QueryResult<Option<OrganizationRole>> {
+    let role = organization_members::table
+        .find((organization_id, user_id))
+        .select(organization_members::role)
+        .first::<i16>(conn)
+        .optional()?;
+    Ok(role.and_then(OrganizationRole::from_i16))
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = organizations)]
+pub struct NewOrganization<'a> {
+    pub name: &'a str,
+    pub description: Option<&'a str>,
+}
+
+impl<'a> NewOrganization<'a> {
+    pub fn new(name: &'a str, description: Option<&'a str>) -> Self {
+        NewOrganization { name, description }
+    }
+
+    /// Creates the organization, with the user as its first admin.
+    pub fn create(&self, conn: &mut PgConnection, admin_id: i32) -> AppResult<Organization> {
+        fn is_allowed_char(c: char) -> bool {
+            matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_')
+        }
+
+        if self.name.is_empty()
+            || self.name.len() > MAX_NAME_LENGTH
+            || !self.name.chars().all(is_allowed_char)
+        {
+            return Err(bad_request(&format_args!(
+                "invalid organization name `{}`, names can only contain \
+                 alphanumeric characters, `-` and `_`, and must be at most \
+                 {MAX_NAME_LENGTH} characters long",
+                self.name
+            )));
+        }
+
+        conn.transaction::<_, BoxedAppError, _>(|conn| {
+            let organization: Organization = diesel::insert_into(organizations::table)
+                .values(self)
+                .on_conflict_do_nothing()
+                .get_result(conn)
+                .optional()?
+                .ok_or_else(|| {
+                    bad_request(&format_args!(
+                        "the organization name `{}` is already taken",
+                        self.name
+                    ))
+                })?;
+
+            diesel::insert_into(organization_members::table)
+                .values((
+                    organization_members::organization_id.eq(organization.id),
+                    organization_members::user_id.eq(admin_id),
+                    organization_members::role.eq(OrganizationRole::Admin as i16),
+                ))
+                .execute(conn)?;
+
+            Ok(organization)
+        })
+    }
+}
+
+/// The model representing a row in the `organization_invitations` database
+/// table.
+#[derive(Clone, Debug, PartialEq, Eq, Identifiable, Queryable)]
+#[diesel(primary_key(organization_id, invited_user_id))]
+pub struct OrganizationInvitation {
+    pub organization_id: i32,
+    pub invited_user_id: i32,
+    pub invited_by_user_id: i32,
+    pub role: i16,
+    pub created_at: NaiveDateTime,
+}
+
+impl OrganizationInvitation {
+    /// Invites the user to the organization, or updates the role of their
+    /// pending invitation.
+    pub fn create(
+        conn: &mut PgConnection,
+        organization_id: i32,
+        invited_user_id: i32,
+        invited_by_user_id: i32,
+        role: OrganizationRole,
+    ) -> AppResult<Self> {
+        if role_of(conn, organization_id, invited_user_id)?.is_some() {
+            return Err(bad_request(
+                "this user is already a member of the organization",
+            ));
+        }
+
+        Ok(diesel::insert_into(organization_invitations::table)
+            .values((
+                organization_invitations::organization_id.eq(organization_id),
+                organization_invitations::invited_user_id.eq(invited_user_id),
+                organization_invitations::invited_by_user_id.eq(invited_by_user_id),
+                organization_invitations::role.eq(role as i16),
+            ))
+            .on_conflict((
+                organization_invitations::organization_id,
+                organization_invitations::invited_user_id,
+            ))
+            .do_update()
+            .set((
+                organization_invitations::invited_by_user_id.eq(invited_by_user_id),
+                organization_invitations::role.eq(role as i16),
+            ))
+            .get_result(conn)?)
+    }
+
+    pub fn find(
+        conn: &mut PgConnection,
+        organization_id: i32,
+        invited_user_id: i32,
+    ) -> QueryResult<Self> {
+        organization_invitations::table
+            .find((organization_id, invited_user_id))
+            .first(conn)
+    }
+
+    /// Returns the pending invitations of the user, with their organizations.
+    pub fn for_user(
+        conn: &mut PgConnection,
+        user_id: i32,
+    ) -> QueryResult<Vec<(Self, Organization)>> {
+        organization_invitations::table
+            .inner_join(organizations::table)
+            .filter(organization_invitations::invited_user_id.eq(user_id))
+            .order(organization_invitations::created_at)
+            .load(conn)
+    }
+
+    pub fn accept(self, conn: &mut PgConnection) -> QueryResult<()> {
+        conn.transaction(|conn| {
+            diesel::insert_into(organization_members::table)
+                .values((
+                    organization_members::organization_id.eq(self.organization_id),
+                    organization_members::user_id.eq(self.invited_user_id),
+                    organization_members::role.eq(self.role),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+
+            diesel::delete(&self).execute(conn)?;
+            Ok(())
+        })
+    }
+
+    pub fn decline(self, conn: &mut PgConnection) -> QueryResult<()> {
+        diesel::delete(&self).execute(conn)?;
+        Ok(())
+    }
+}
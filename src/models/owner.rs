@@ -1,10 +1,11 @@
 use diesel::pg::Pg;
 use diesel::prelude::*;
+use std::borrow::Cow;
 
 use crate::app::App;
 use crate::util::errors::{cargo_err, AppResult};
 
-use crate::models::{Crate, Team, User};
+use crate::models::{Crate, Organization, Team, User};
 use crate::schema::{crate_owners, users};
 use crate::sql::lower;
 
@@ -42,13 +43,15 @@ impl CrateOwner {
 pub enum OwnerKind {
     User = 0,
     Team = 1,
+    Organization = 2,
 }
 
-/// Unifies the notion of a User or a Team.
+/// Unifies the notion of a User, a Team or an Organization.
 #[derive(Debug)]
 pub enum Owner {
     User(User),
     Team(Team),
+    Organization(Organization),
 }
 
 impl Owner {
@@ -56,15 +59,19 @@ impl Owner {
     /// up-to-date GitHub ID. Fails out if the user isn't found in the
     /// database, the team isn't found on GitHub, or if the user isn't a member
     /// of the team on GitHub.
-    /// May be a user's GH login or a full team name. This is case
-    /// sensitive.
+    /// May be a user's GH login, a full team name or `org:` followed by the
+    /// name of an organization. This is case sensitive.
     pub fn find_or_create_by_login(
         app: &App,
         conn: &mut PgConnection,
         req_user: &User,
         name: &str,
     ) -> AppResult<Owner> {
-        if name.contains(':') {
+        if let Some(org_name) = name.strip_prefix(Organization::OWNER_PREFIX) {
+            Organization::find_by_name(conn, org_name)
+                .map(Owner::Organization)
+                .map_err(|_| cargo_err(&format_args!("could not find organization `{org_name}`")))
+        } else if name.contains(':') {
             Ok(Owner::Team(Team::create_or_update(
                 app, conn, name, req_user,
             )?))
@@ -83,13 +90,15 @@ impl Owner {
         match *self {
             Owner::User(_) => OwnerKind::User as i32,
             Owner::Team(_) => OwnerKind::Team as i32,
+            Owner::Organization(_) => OwnerKind::Organization as i32,
         }
     }
 
-    pub fn login(&self) -> &str {
+    pub fn login(&self) -> Cow<'_, str> {
         match *self {
-            Owner::User(ref user) => &user.gh_login,
-            Owner::Team(ref team) => &team.login,
+            Owner::User(ref user) => Cow::Borrowed(&user.gh_login),
+            Owner::Team(ref team) => Cow::Borrowed(&team.login),
+            Owner::Organization(ref organization) => Cow::Owned(organization.login()),
         }
     }
 
@@ -97,6 +106,7 @@ impl Owner {
         match *self {
             Owner::User(ref user) => user.id,
             Owner::Team(ref team) => team.id,
+            Owner::Organization(ref organization) => organization.id,
         }
     }
 }
@@ -0,0 +1,189 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+
+use crate::models::User;
+use crate::schema::persistent_sessions;
+use crate::util::token::{SecureToken, SecureTokenKind};
+
+type BoxedQuery<'a> = persistent_sessions::BoxedQuery<'a, Pg, persistent_sessions::SqlType>;
+
+/// A login session of a user.
+///
+/// The session cookie only contains an opaque token, which is looked up in the
+/// database for every request. Sessions stop authenticating requests once they
+/// are revoked, or once they weren't used for `IDLE_TIMEOUT_DAYS`.
+///
+/// Sensitive actions additionally require the session to be in sudo mode,
+/// which it is for `SUDO_DURATION_MINUTES` after the login and after the user
+/// confirmed their identity again.
+#[derive(Clone, Debug, Identifiable, Queryable, Associations)]
+#[diesel(belongs_to(User))]
+pub struct PersistentSession {
+    pub id: i64,
+    pub user_id: i32,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: NaiveDateTime,
+    pub revoked: bool,
+    pub last_ip_address: Option<String>,
+    pub last_user_agent: Option<String>,
+    #[allow(dead_code)] // Only the hash, which sessions are looked up by in SQL
+    token: SecureToken,
+    pub sudo_until: Option<NaiveDateTime>,
+}
+
+impl PersistentSession {
+    /// How often the last use of a session is recorded at most, to avoid a
+    /// write for every request.
+    const LAST_USED_PRECISION_SECONDS: i64 = 60;
+
+    /// After how many days without a request a session expires.
+    pub const IDLE_TIMEOUT_DAYS: i64 = 30;
+
+    /// For how long sessions stay in sudo mode, like on GitHub.
+    pub const SUDO_DURATION_MINUTES: i64 = 120;
+
+    pub fn create(
+        conn: &mut PgConnection,
+        user_id: i32,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> QueryResult<CreatedPersistentSession> {
+        let token = SecureToken::generate(SecureTokenKind::Session);
+
+        // Logging in confirms the identity of the user
+        let model = diesel::insert_into(persistent_sessions::table)
+            .values((
+                persistent_sessions::user_id.eq(user_id),
+                persistent_sessions::last_ip_address.eq(ip_address),
+                persistent_sessions::last_user_agent.eq(user_agent),
+                persistent_sessions::token.eq(&*token),
+                persistent_sessions::sudo_until.eq(Self::sudo_deadline()),
+            ))
+            .get_result(conn)?;
+
+        Ok(CreatedPersistentSession {
+            model,
+            plaintext: token.plaintext().into(),
+        })
+    }
+
+    /// Finds the session of the token from a session cookie, unless it was
+    /// revoked or expired.
+    pub fn find_by_token(conn: &mut PgConnection, token: &str) -> QueryResult<Option<Self>> {
+        let Some(token) = SecureToken::parse(SecureTokenKind::Session, token) else {
+            return Ok(None);
+        };
+
+        Self::active()
+            .filter(persistent_sessions::token.eq(token))
+            .first(conn)
+            .optional()
+    }
+
+    /// Finds the session of the user, unless it was revoked or expired.
+    pub fn find_active(conn: &mut PgConnection, id: i64, user_id: i32) -> QueryResult<Self> {
+        Self::active()
+            .filter(persistent_sessions::id.eq(id))
+            .filter(persistent_sessions::user_id.eq(user_id))
+            .first(conn)
+    }
+
+    /// Returns the sessions of the user that weren't revoked and didn't
+    /// expire, most recently used first.
+    pub fn active_for_user(conn: &mut PgConnection, user_id: i32) -> QueryResult<Vec<Self>> {
+        Self::active()
+            .filter(persistent_sessions::user_id.eq(user_id))
+            .order(persistent_sessions::last_used_at.desc())
+            .load(conn)
+    }
+
+    /// Returns a query of the sessions that were neither revoked nor expired.
+    fn active() -> BoxedQuery<'static> {
+        let idle_since = Utc::now().naive_utc() - Duration::days(Self::IDLE_TIMEOUT_DAYS);
+
+        persistent_sessions::table
+            .filter(persistent_sessions::revoked.eq(false))
+            .filter(persistent_sessions::last_used_at.gt(idle_since))
+            .into_boxed()
+    }
+
+    /// Records that the session was used for a request, unless that was
+    /// already recorded recently.
+    pub fn record_use(
+        &self,
+        conn: &mut PgConnection,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> QueryResult<()> {
+        let now = Utc::now().naive_utc();
+        if now - self.last_used_at < Duration::seconds(Self::LAST_USED_PRECISION_SECONDS) {
+            return Ok(());
+        }
+
+        diesel::update(self)
+            .set((
+                persistent_sessions::last_used_at.eq(now),
+                persistent_sessions::last_ip_address.eq(ip_address),
+                persistent_sessions::last_user_agent.eq(user_agent),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn is_in_sudo_mode(&self) -> bool {
+        self.sudo_until
+            .is_some_and(|sudo_until| sudo_until > Utc::now().naive_utc())
+    }
+
+    /// Puts the session into sudo mode for `SUDO_DURATION_MINUTES`, after the
+    /// user confirmed their identity.
+    pub fn enter_sudo_mode(&self, conn: &mut PgConnection) -> QueryResult<Self> {
+        diesel::update(self)
+            .set(persistent_sessions::sudo_until.eq(Self::sudo_deadline()))
+            .get_result(conn)
+    }
+
+    fn sudo_deadline() -> NaiveDateTime {
+        Utc::now().naive_utc() + Duration::minutes(Self::SUDO_DURATION_MINUTES)
+    }
+
+    pub fn revoke(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        diesel::update(self)
+            .set(persistent_sessions::revoked.eq(true))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Revokes all sessions of the user, except for the given one. Returns the
+    /// number of revoked sessions.
+    pub fn revoke_all_for_user(
+        conn: &mut PgConnection,
+        user_id: i32,
+        except_id: Option<i64>,
+    ) -> QueryResult<usize> {
+        let sessions = persistent_sessions::table
+            .filter(persistent_sessions::user_id.eq(user_id))
+            .filter(persistent_sessions::revoked.eq(false))
+            .filter(persistent_sessions::id.ne(except_id.unwrap_or_default()));
+        diesel::update(sessions)
+            .set(persistent_sessions::revoked.eq(true))
+            .execute(conn)
+    }
+}
+
+pub struct CreatedPersistentSession {
+    pub model: PersistentSession,
+    /// The token for the session cookie.
+    pub plaintext: String,
+}
+
+// Use a custom implementation of Debug to hide the plaintext token.
+impl std::fmt::Debug for CreatedPersistentSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CreatedPersistentSession")
+            .field("model", &self.model)
+            .field("plaintext", &"(sensitive)")
+            .finish()
+    }
+}
@@ -0,0 +1,113 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::models::User;
+use crate::schema::{publish_upload_parts, publish_uploads};
+
+/// Uploads that have not been completed within this many hours are deleted
+/// by the daily database maintenance.
+const STALE_UPLOAD_HOURS: i32 = 24;
+
+/// A publish request body that is uploaded in multiple chunks.
+///
+/// The chunks are stored as the parts of a multipart upload in the storage
+/// backend, which assembles them at `storage_path` when the upload is
+/// completed.
+#[derive(Clone, Identifiable, Queryable, Associations, Debug)]
+#[diesel(belongs_to(User))]
+pub struct PublishUpload {
+    pub id: i32,
+    pub user_id: i32,
+    pub crate_name: String,
+    pub size: i64,
+    pub received: i64,
+    pub cksum: String,
+    pub created_at: NaiveDateTime,
+    pub storage_path: String,
+    pub storage_upload_id: String,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = publish_uploads)]
+pub struct NewPublishUpload<'a> {
+    pub user_id: i32,
+    pub crate_name: &'a str,
+    pub size: i64,
+    pub cksum: &'a str,
+    pub storage_path: &'a str,
+    pub storage_upload_id: &'a str,
+}
+
+impl NewPublishUpload<'_> {
+    pub fn create(&self, conn: &mut PgConnection) -> QueryResult<PublishUpload> {
+        diesel::insert_into(publish_uploads::table)
+            .values(self)
+            .get_result(conn)
+    }
+}
+
+impl PublishUpload {
+    pub fn find(conn: &mut PgConnection, id: i32) -> QueryResult<Self> {
+        publish_uploads::table.find(id).first(conn)
+    }
+
+    /// Returns the number of bytes received so far, and locks the upload
+    /// until the end of the transaction, so that concurrent requests can't
+    /// append chunks at the same offset.
+    pub fn lock_received(&self, conn: &mut PgConnection) -> QueryResult<i64> {
+        publish_uploads::table
+            .find(self.id)
+            .select(publish_uploads::received)
+            .for_update()
+            .first(conn)
+    }
+
+    /// Returns the part numbers and tags of the stored chunks, in order.
+    pub fn parts(&self, conn: &mut PgConnection) -> QueryResult<Vec<(i32, String)>> {
+        publish_upload_parts::table
+            .filter(publish_upload_parts::upload_id.eq(self.id))
+            .order(publish_upload_parts::part_number.asc())
+            .select((publish_upload_parts::part_number, publish_upload_parts::tag))
+            .load(conn)
+    }
+
+    /// Records a chunk of `len` bytes at `offset` that was stored as part
+    /// `part_number`, and returns the new number of received bytes.
+    pub fn add_part(
+        &self,
+        conn: &mut PgConnection,
+        part_number: i32,
+        offset: i64,
+        len: i64,
+        tag: &str,
+    ) -> QueryResult<i64> {
+        diesel::insert_into(publish_upload_parts::table)
+            .values((
+                publish_upload_parts::upload_id.eq(self.id),
+                publish_upload_parts::part_number.eq(part_number),
+                publish_upload_parts::position.eq(offset),
+                publish_upload_parts::tag.eq(tag),
+            ))
+            .execute(conn)?;
+
+        diesel::update(publish_uploads::table.find(self.id))
+            .set(publish_uploads::received.eq(offset + len))
+            .returning(publish_uploads::received)
+            .get_result(conn)
+    }
+
+    pub fn delete(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        diesel::delete(self).execute(conn)?;
+        Ok(())
+    }
+
+    /// Returns all uploads that have been started more than
+    /// `STALE_UPLOAD_HOURS` ago.
+    pub fn stale(conn: &mut PgConnection) -> QueryResult<Vec<Self>> {
+        use diesel::dsl::{now, IntervalDsl};
+
+        publish_uploads::table
+            .filter(publish_uploads::created_at.lt(now - STALE_UPLOAD_HOURS.hours()))
+            .load(conn)
+    }
+}
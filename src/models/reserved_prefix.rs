@@ -0,0 +1,61 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::app::App;
+use crate::models::{Team, User};
+use crate::schema::{reserved_crate_prefixes, teams};
+use crate::sql::{canon_crate_name, starts_with};
+use crate::util::errors::{cargo_err, AppResult};
+
+/// A crate name prefix (e.g. `mycorp-`) that is reserved for the members of
+/// a team.
+///
+/// Reservations only apply to crates that do not exist yet. Crates that
+/// already match the prefix when it is reserved keep their current owners, and
+/// publishing new versions of a crate is always governed by its owners. If
+/// several reservations match a crate name, the longest prefix wins.
+#[derive(Queryable, Identifiable, Debug, Clone)]
+#[diesel(table_name = reserved_crate_prefixes, primary_key(prefix))]
+pub struct ReservedCratePrefix {
+    pub prefix: String,
+    pub team_id: i32,
+    pub created_at: NaiveDateTime,
+}
+
+impl ReservedCratePrefix {
+    /// Returns the reservation that applies to the given crate name, if any.
+    pub fn for_crate_name(conn: &mut PgConnection, name: &str) -> QueryResult<Option<Self>> {
+        let reservations: Vec<Self> = reserved_crate_prefixes::table
+            .filter(starts_with(
+                canon_crate_name(name),
+                canon_crate_name(reserved_crate_prefixes::prefix),
+            ))
+            .load(conn)?;
+
+        Ok(reservations
+            .into_iter()
+            .max_by_key(|reservation| reservation.prefix.len()))
+    }
+
+    /// Checks that `user` is allowed to publish a new crate called `name`.
+    pub fn ensure_can_publish(
+        conn: &mut PgConnection,
+        app: &App,
+        user: &User,
+        name: &str,
+    ) -> AppResult<()> {
+        let Some(reservation) = Self::for_crate_name(conn, name)? else {
+            return Ok(());
+        };
+
+        let team: Team = teams::table.find(reservation.team_id).first(conn)?;
+        if team.contains_user(app, conn, user)? {
+            return Ok(());
+        }
+
+        Err(cargo_err(&format_args!(
+            "the crate name prefix `{}` is reserved for members of the `{}` team",
+            reservation.prefix, team.login
+        )))
+    }
+}
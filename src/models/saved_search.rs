@@ -0,0 +1,65 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::models::User;
+use crate::schema::{saved_searches, versions};
+
+/// A search query of a user that is periodically checked for newly published
+/// crates and versions.
+#[derive(Clone, Debug, Identifiable, Queryable, Associations)]
+#[diesel(table_name = saved_searches, belongs_to(User))]
+pub struct SavedSearch {
+    pub id: i32,
+    pub user_id: i32,
+    pub query: String,
+    pub webhook_url: Option<String>,
+    pub last_version_id: i32,
+    pub created_at: NaiveDateTime,
+}
+
+impl SavedSearch {
+    pub fn for_user(conn: &mut PgConnection, user_id: i32) -> QueryResult<Vec<Self>> {
+        saved_searches::table
+            .filter(saved_searches::user_id.eq(user_id))
+            .order(saved_searches::id)
+            .load(conn)
+    }
+
+    pub fn find(conn: &mut PgConnection, id: i32, user_id: i32) -> QueryResult<Self> {
+        saved_searches::table
+            .find(id)
+            .filter(saved_searches::user_id.eq(user_id))
+            .first(conn)
+    }
+
+    pub fn delete(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        diesel::delete(self).execute(conn)?;
+        Ok(())
+    }
+}
+
+pub struct NewSavedSearch<'a> {
+    pub user_id: i32,
+    pub query: &'a str,
+    pub webhook_url: Option<&'a str>,
+}
+
+impl NewSavedSearch<'_> {
+    /// Stores the saved search. Only versions that are published afterwards
+    /// are notified about.
+    pub fn create(&self, conn: &mut PgConnection) -> QueryResult<SavedSearch> {
+        let last_version_id = versions::table
+            .select(diesel::dsl::max(versions::id))
+            .first::<Option<i32>>(conn)?
+            .unwrap_or_default();
+
+        diesel::insert_into(saved_searches::table)
+            .values((
+                saved_searches::user_id.eq(self.user_id),
+                saved_searches::query.eq(self.query),
+                saved_searches::webhook_url.eq(self.webhook_url),
+                saved_searches::last_version_id.eq(last_version_id),
+            ))
+            .get_result(conn)
+    }
+}
@@ -0,0 +1,64 @@
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::Text;
+
+/// Maximum number of words in a search query that we try to correct.
+const MAX_CORRECTED_WORDS: usize = 5;
+
+/// A crate name or keyword from the `search_terms` materialized view.
+#[derive(QueryableByName, Debug)]
+pub struct SearchTerm {
+    #[diesel(sql_type = Text)]
+    pub term: String,
+}
+
+impl SearchTerm {
+    /// Refreshes the `search_terms` view from the current crate names and
+    /// keywords.
+    ///
+    /// This can not be run from within a transaction.
+    pub fn refresh(conn: &mut PgConnection) -> QueryResult<()> {
+        sql_query("REFRESH MATERIALIZED VIEW CONCURRENTLY search_terms").execute(conn)?;
+        Ok(())
+    }
+
+    /// Returns the closest known crate name or keyword for the given word,
+    /// preferring more popular terms if several are equally similar.
+    pub fn closest(conn: &mut PgConnection, word: &str) -> QueryResult<Option<String>> {
+        sql_query(
+            "SELECT term FROM search_terms \
+             WHERE term % $1 \
+             ORDER BY similarity(term, $1) DESC, weight DESC \
+             LIMIT 1",
+        )
+        .bind::<Text, _>(word)
+        .get_result::<SearchTerm>(conn)
+        .optional()
+        .map(|term| term.map(|term| term.term))
+    }
+
+    /// Builds a "did you mean" suggestion for a search query by replacing
+    /// each word with the closest known crate name or keyword.
+    ///
+    /// Returns `None` if no word of the query could be corrected.
+    pub fn did_you_mean(conn: &mut PgConnection, query: &str) -> QueryResult<Option<String>> {
+        let words = query.split_whitespace().collect::<Vec<_>>();
+        if words.is_empty() || words.len() > MAX_CORRECTED_WORDS {
+            return Ok(None);
+        }
+
+        let mut corrected = false;
+        let mut suggestion = Vec::with_capacity(words.len());
+        for word in words {
+            match Self::closest(conn, word)? {
+                Some(term) if !term.eq_ignore_ascii_case(word) => {
+                    corrected = true;
+                    suggestion.push(term);
+                }
+                _ => suggestion.push(word.to_string()),
+            }
+        }
+
+        Ok(corrected.then(|| suggestion.join(" ")))
+    }
+}
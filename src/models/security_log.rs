@@ -0,0 +1,103 @@
+use chrono::NaiveDateTime;
+use diesel::deserialize::{self, FromSql};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::SmallInt;
+
+use crate::models::User;
+use crate::schema::security_log_entries;
+
+/// A security relevant event of a user account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromSqlRow, AsExpression)]
+#[repr(i16)]
+#[diesel(sql_type = SmallInt)]
+pub enum SecurityEvent {
+    Login = 0,
+    TokenCreated = 1,
+    OwnerAdded = 2,
+    OwnerRemoved = 3,
+    EmailAdded = 4,
+    EmailRemoved = 5,
+    /// The address that notifications are sent to was changed.
+    NotificationEmailChanged = 6,
+    /// The authenticator was removed by the crates.io team, after the user
+    /// lost access to it.
+    TotpReset = 7,
+}
+
+impl SecurityEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecurityEvent::Login => "login",
+            SecurityEvent::TokenCreated => "token_created",
+            SecurityEvent::OwnerAdded => "owner_added",
+            SecurityEvent::OwnerRemoved => "owner_removed",
+            SecurityEvent::EmailAdded => "email_added",
+            SecurityEvent::EmailRemoved => "email_removed",
+            SecurityEvent::NotificationEmailChanged => "notification_email_changed",
+            SecurityEvent::TotpReset => "totp_reset",
+        }
+    }
+}
+
+impl FromSql<SmallInt, Pg> for SecurityEvent {
+    fn from_sql(bytes: diesel::pg::PgValue<'_>) -> deserialize::Result<Self> {
+        match <i16 as FromSql<SmallInt, Pg>>::from_sql(bytes)? {
+            0 => Ok(SecurityEvent::Login),
+            1 => Ok(SecurityEvent::TokenCreated),
+            2 => Ok(SecurityEvent::OwnerAdded),
+            3 => Ok(SecurityEvent::OwnerRemoved),
+            4 => Ok(SecurityEvent::EmailAdded),
+            5 => Ok(SecurityEvent::EmailRemoved),
+            6 => Ok(SecurityEvent::NotificationEmailChanged),
+            7 => Ok(SecurityEvent::TotpReset),
+            n => Err(format!("unknown security event: {n}").into()),
+        }
+    }
+}
+
+impl ToSql<SmallInt, Pg> for SecurityEvent {
+    fn to_sql(&self, out: &mut Output<'_, '_, Pg>) -> serialize::Result {
+        ToSql::<SmallInt, Pg>::to_sql(&(*self as i16), &mut out.reborrow())
+    }
+}
+
+/// An entry of the security log of a user, which users can review to audit
+/// their account after suspicious activity.
+#[derive(Clone, Debug, Identifiable, Queryable, Associations)]
+#[diesel(belongs_to(User), table_name = security_log_entries)]
+pub struct SecurityLogEntry {
+    pub id: i64,
+    pub user_id: i32,
+    pub event: SecurityEvent,
+    pub details: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl SecurityLogEntry {
+    /// Records an event in the security log of the user, together with the
+    /// client that caused it.
+    pub fn record(
+        conn: &mut PgConnection,
+        user_id: i32,
+        event: SecurityEvent,
+        details: Option<&str>,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> QueryResult<()> {
+        diesel::insert_into(security_log_entries::table)
+            .values((
+                security_log_entries::user_id.eq(user_id),
+                security_log_entries::event.eq(event),
+                security_log_entries::details.eq(details),
+                security_log_entries::ip_address.eq(ip_address),
+                security_log_entries::user_agent.eq(user_agent),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::config::SsoConfig;
+use crate::email::Emails;
+use crate::models::{NewUser, Team, User};
+use crate::schema::sso_identities;
+use crate::sso::SsoUser;
+use crate::util::errors::{bad_request, AppResult, BoxedAppError};
+
+/// An identity at the single sign-on identity provider of a private
+/// deployment, that a user logs in with.
+#[derive(Clone, Debug, Queryable, Associations)]
+#[diesel(table_name = sso_identities, belongs_to(User))]
+pub struct SsoIdentity {
+    pub subject: String,
+    pub user_id: i32,
+    pub created_at: NaiveDateTime,
+}
+
+impl SsoIdentity {
+    /// Returns the ID of the user that logs in with the identity, if any.
+    pub fn find_user_id(conn: &mut PgConnection, subject: &str) -> QueryResult<Option<i32>> {
+        sso_identities::table
+            .find(subject)
+            .select(sso_identities::user_id)
+            .first(conn)
+            .optional()
+    }
+
+    /// Logs in with the identity. Users are signed up when they log in for
+    /// the first time, and their team memberships are updated to match their
+    /// groups at the identity provider on every login.
+    pub fn log_in(
+        conn: &mut PgConnection,
+        sso_user: &SsoUser,
+        config: &SsoConfig,
+        emails: &Emails,
+    ) -> AppResult<User> {
+        conn.transaction::<_, BoxedAppError, _>(|conn| {
+            let user = match Self::find_user_id(conn, &sso_user.subject)? {
+                Some(user_id) => User::find(conn, user_id)?,
+                None => Self::create_user(conn, sso_user, emails)?,
+            };
+
+            Team::sync_sso_memberships(conn, user.id, &config.teams_for(&sso_user.groups))?;
+            Ok(user)
+        })
+    }
+
+    /// Links the identity to the user. Identities that are already linked to
+    /// another user are rejected instead of being moved.
+    pub fn link(
+        conn: &mut PgConnection,
+        sso_user: &SsoUser,
+        config: &SsoConfig,
+        user_id: i32,
+    ) -> AppResult<()> {
+        conn.transaction::<_, BoxedAppError, _>(|conn| {
+            match Self::find_user_id(conn, &sso_user.subject)? {
+                Some(owner_id) if owner_id == user_id => {}
+                Some(_) => {
+                    return Err(bad_request(
+                        "this single sign-on identity is already linked to \
+                         another crates.io account",
+                    ));
+                }
+                None => Self::insert(conn, &sso_user.subject, user_id)?,
+            }
+
+            Team::sync_sso_memberships(conn, user_id, &config.teams_for(&sso_user.groups))?;
+            Ok(())
+        })
+    }
+
+    /// Signs up a new user that logs in with the identity, and has no GitHub
+    /// account of its own.
+    fn create_user(
+        conn: &mut PgConnection,
+        sso_user: &SsoUser,
+        emails: &Emails,
+    ) -> AppResult<User> {
+        if User::login_is_taken(conn, &sso_user.username)? {
+            return Err(bad_request(&format_args!(
+                "the username `{}` is already taken, please log in with \
+                 your other account first to link this one",
+                sso_user.username
+            )));
+        }
+
+        let user = NewUser::new(
+            User::NO_GITHUB_ID,
+            &sso_user.username,
+            sso_user.name.as_deref(),
+            sso_user.avatar.as_deref(),
+            "",
+        )
+        .create_or_update(sso_user.email.as_deref(), emails, conn)?;
+
+        Self::insert(conn, &sso_user.subject, user.id)?;
+        Ok(user)
+    }
+
+    fn insert(conn: &mut PgConnection, subject: &str, user_id: i32) -> QueryResult<()> {
+        diesel::insert_into(sso_identities::table)
+            .values((
+                sso_identities::subject.eq(subject),
+                sso_identities::user_id.eq(user_id),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+}
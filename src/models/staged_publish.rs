@@ -0,0 +1,80 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::models::User;
+use crate::schema::staged_publishes;
+use crate::sql::canon_crate_name;
+
+/// Staged publishes that have not been promoted within this many days are
+/// deleted by the daily database maintenance.
+const STALE_STAGED_PUBLISH_DAYS: i32 = 7;
+
+/// A validated publish request that is stored until it is promoted to a
+/// regular version of the crate.
+#[derive(Clone, Identifiable, Queryable, Associations, Debug)]
+#[diesel(table_name = staged_publishes, belongs_to(User))]
+pub struct StagedPublish {
+    pub id: i32,
+    pub user_id: i32,
+    pub crate_name: String,
+    pub version: String,
+    pub body: Vec<u8>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = staged_publishes)]
+pub struct NewStagedPublish<'a> {
+    pub user_id: i32,
+    pub crate_name: &'a str,
+    pub version: &'a str,
+    pub body: &'a [u8],
+}
+
+impl NewStagedPublish<'_> {
+    /// Stores the staged publish, or returns `None` if this version of the
+    /// crate has already been staged.
+    pub fn create(&self, conn: &mut PgConnection) -> QueryResult<Option<StagedPublish>> {
+        diesel::insert_into(staged_publishes::table)
+            .values(self)
+            .on_conflict_do_nothing()
+            .get_result(conn)
+            .optional()
+    }
+}
+
+impl StagedPublish {
+    pub fn find(conn: &mut PgConnection, id: i32, user_id: i32) -> QueryResult<Self> {
+        staged_publishes::table
+            .find(id)
+            .filter(staged_publishes::user_id.eq(user_id))
+            .first(conn)
+    }
+
+    pub fn for_user(conn: &mut PgConnection, user_id: i32) -> QueryResult<Vec<Self>> {
+        staged_publishes::table
+            .filter(staged_publishes::user_id.eq(user_id))
+            .order((
+                canon_crate_name(staged_publishes::crate_name),
+                staged_publishes::id,
+            ))
+            .load(conn)
+    }
+
+    /// Deletes the staged publish. Returns whether it still existed.
+    pub fn delete(&self, conn: &mut PgConnection) -> QueryResult<bool> {
+        Ok(diesel::delete(self).execute(conn)? > 0)
+    }
+
+    /// Deletes all staged publishes that are older than
+    /// `STALE_STAGED_PUBLISH_DAYS`, returning the number of deleted rows.
+    pub fn delete_stale(conn: &mut PgConnection) -> QueryResult<usize> {
+        use diesel::dsl::{now, IntervalDsl};
+
+        diesel::delete(
+            staged_publishes::table
+                .filter(staged_publishes::created_at.lt(now - STALE_STAGED_PUBLISH_DAYS.days())),
+        )
+        .execute(conn)
+    }
+}
@@ -1,15 +1,23 @@
+use diesel::dsl::{now, sql};
 use diesel::prelude::*;
+use diesel::sql_types::Integer;
 
 use crate::app::App;
 use crate::util::errors::{cargo_err, AppResult, NotFound};
 
 use oauth2::AccessToken;
 
-use crate::models::{Crate, CrateOwner, Owner, OwnerKind, User};
-use crate::schema::{crate_owners, teams};
+use crate::github::GitHubClient;
+use crate::gitlab::DEVELOPER_ACCESS_LEVEL;
+use crate::models::{AccountProvider, Crate, CrateOwner, LinkedAccount, Owner, OwnerKind, User};
+use crate::schema::{crate_owners, team_members, teams};
 
-/// For now, just a Github Team. Can be upgraded to other teams
-/// later if desirable.
+/// Memberships of single sign-on teams are only refreshed when the user logs
+/// in, so they expire once the last login was this long ago.
+pub const SSO_MEMBERSHIP_VALID_DAYS: i32 = 7;
+
+/// A GitHub team, a GitLab group or a team of the single sign-on identity
+/// provider.
 #[derive(Queryable, Identifiable, Serialize, Deserialize, Debug)]
 pub struct Team {
     /// Unique table id
@@ -27,6 +35,10 @@ pub struct Team {
     pub avatar: Option<String>,
     /// The GitHub Organization ID this team sits under
     pub org_id: Option<i32>,
+    /// The `AccountProvider` of the team. For GitLab groups, `github_id` is
+    /// the ID of the group. Single sign-on teams get an ID of the
+    /// `sso_team_ids` sequence instead.
+    pub provider: i16,
 }
 
 #[derive(Insertable, AsChangeset, Debug)]
@@ -37,6 +49,7 @@ pub struct NewTeam<'a> {
     pub name: Option<String>,
     pub avatar: Option<String>,
     pub org_id: i32,
+    pub provider: i16,
 }
 
 impl<'a> NewTeam<'a> {
@@ -53,6 +66,25 @@ impl<'a> NewTeam<'a> {
             name,
             avatar,
             org_id,
+            provider: AccountProvider::GitHub as i16,
+        }
+    }
+
+    /// A GitLab group, which has no organization but may be nested in other
+    /// groups.
+    pub fn gitlab(
+        login: &'a str,
+        group_id: i32,
+        name: Option<String>,
+        avatar: Option<String>,
+    ) -> Self {
+        NewTeam {
+            login,
+            github_id: group_id,
+            name,
+            avatar,
+            org_id: group_id,
+            provider: AccountProvider::GitLab as i16,
         }
     }
 
@@ -62,7 +94,7 @@ impl<'a> NewTeam<'a> {
 
         insert_into(teams)
             .values(self)
-            .on_conflict(github_id)
+            .on_conflict((provider, github_id))
             .do_update()
             .set(self)
             .get_result(conn)
@@ -104,9 +136,28 @@ impl Team {
                     req_user,
                 )
             }
+            // gitlab:rust-lang/owners
+            "gitlab" => {
+                // unwrap is documented above as part of the calling contract
+                let path = chunks.next().unwrap();
+                if chunks.next().is_some() {
+                    return Err(cargo_err(
+                        "too many colons in the gitlab group; format is gitlab:group",
+                    ));
+                }
+                Team::create_or_update_gitlab_group(
+                    app,
+                    conn,
+                    &login.to_lowercase(),
+                    path,
+                    req_user,
+                )
+            }
+            // sso:engineering
+            "sso" => Team::find_sso_team(app, conn, &login.to_lowercase(), req_user),
             _ => Err(cargo_err(
                 "unknown organization handler, \
-                 only 'github:org:team' is supported",
+                 only 'github:org:team' and 'gitlab:group' are supported",
             )),
         }
     }
@@ -137,10 +188,13 @@ impl Team {
             )));
         }
 
-        let token = AccessToken::new(req_user.gh_access_token.clone());
+        let identities = github_identities(conn, req_user)?;
+        let (_, token) = identities.first().ok_or_else(|| {
+            cargo_err("only users who logged in with GitHub can add a github team as an owner")
+        })?;
         let team = app
             .github
-            .team_by_name(org_name, team_name, &token)
+            .team_by_name(org_name, team_name, token)
             .map_err(|_| {
                 cargo_err(&format_args!(
                     "could not find the github team {org_name}/{team_name}"
@@ -149,13 +203,13 @@ impl Team {
 
         let org_id = team.organization.id;
 
-        if !can_add_team(app, org_id, team.id, req_user)? {
+        if !can_add_team(&*app.github, org_id, team.id, &identities)? {
             return Err(cargo_err(
                 "only members of a team or organization owners can add it as an owner",
             ));
         }
 
-        let org = app.github.org_by_name(org_name, &token)?;
+        let org = app.github.org_by_name(org_name, token)?;
 
         NewTeam::new(
             &login.to_lowercase(),
@@ -168,13 +222,183 @@ impl Team {
         .map_err(Into::into)
     }
 
+    /// Tries to create or update a GitLab group. Subgroups are separated by
+    /// slashes in `path`, like on GitLab itself.
+    fn create_or_update_gitlab_group(
+        app: &App,
+        conn: &mut PgConnection,
+        login: &str,
+        path: &str,
+        req_user: &User,
+    ) -> AppResult<Self> {
+        fn is_allowed_char(c: char) -> bool {
+            matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' | '/')
+        }
+
+        if let Some(c) = path.chars().find(|c| !is_allowed_char(*c)) {
+            return Err(cargo_err(&format_args!(
+                "group cannot contain special characters like {c}"
+            )));
+        }
+        if path
+            .split('/')
+            .any(|part| part.is_empty() || part.starts_with('.'))
+        {
+            return Err(cargo_err(&format_args!("invalid gitlab group `{path}`")));
+        }
+
+        let accounts = LinkedAccount::for_provider(conn, req_user.id, AccountProvider::GitLab)?;
+        let account = accounts.first().ok_or_else(|| {
+            cargo_err("only users who logged in with GitLab can add a gitlab group as an owner")
+        })?;
+
+        let token = AccessToken::new(account.access_token.clone());
+        let group = app
+            .gitlab
+            .group_by_path(path, &token)
+            .map_err(|_| cargo_err(&format_args!("could not find the gitlab group {path}")))?;
+
+        if !gitlab_group_contains_any(app, group.id, &accounts)? {
+            return Err(cargo_err("only members of a group can add it as an owner"));
+        }
+
+        NewTeam::gitlab(login, group.id, Some(group.name), group.avatar_url)
+            .create_or_update(conn)
+            .map_err(Into::into)
+    }
+
+    /// Looks up a single sign-on team. These teams are created when their
+    /// members log in, and only their members can add them as owners.
+    fn find_sso_team(
+        app: &App,
+        conn: &mut PgConnection,
+        login: &str,
+        req_user: &User,
+    ) -> AppResult<Self> {
+        if app.config.sso.is_none() {
+            return Err(cargo_err("single sign-on is not enabled"));
+        }
+
+        let team: Option<Team> = teams::table
+            .filter(teams::login.eq(login))
+            .filter(teams::provider.eq(AccountProvider::Sso as i16))
+            .first(conn)
+            .optional()?;
+
+        match team {
+            Some(team) if team.has_member(conn, req_user.id)? => Ok(team),
+            _ => Err(cargo_err(&format_args!(
+                "only members of the team {login} can add it as an owner"
+            ))),
+        }
+    }
+
+    /// Makes the user a member of exactly the given single sign-on teams,
+    /// which are created if they don't exist yet.
+    pub fn sync_sso_memberships(
+        conn: &mut PgConnection,
+        user_id: i32,
+        logins: &[String],
+    ) -> QueryResult<()> {
+        conn.transaction(|conn| {
+            let mut team_ids = Vec::with_capacity(logins.len());
+            for login in logins {
+                let name = login.trim_start_matches("sso:");
+                let team_id = diesel::insert_into(teams::table)
+                    .values((
+                        teams::login.eq(login),
+                        teams::github_id.eq(sql::<Integer>("nextval('sso_team_ids')::integer")),
+                        teams::name.eq(name),
+                        teams::provider.eq(AccountProvider::Sso as i16),
+                    ))
+                    .on_conflict(teams::login)
+                    .do_update()
+                    .set(teams::name.eq(name))
+                    .returning(teams::id)
+                    .get_result::<i32>(conn)?;
+                team_ids.push(team_id);
+            }
+
+            let sso_teams = teams::table
+                .filter(teams::provider.eq(AccountProvider::Sso as i16))
+                .select(teams::id);
+            diesel::delete(team_members::table)
+                .filter(team_members::user_id.eq(user_id))
+                .filter(team_members::team_id.eq_any(sso_teams))
+                .filter(team_members::team_id.ne_all(&team_ids))
+                .execute(conn)?;
+
+            for team_id in team_ids {
+                diesel::insert_into(team_members::table)
+                    .values((
+                        team_members::team_id.eq(team_id),
+                        team_members::user_id.eq(user_id),
+                    ))
+                    .on_conflict((team_members::team_id, team_members::user_id))
+                    .do_update()
+                    .set(team_members::verified_at.eq(now))
+                    .execute(conn)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Returns whether the user was a member of the single sign-on team when
+    /// they last logged in, unless that membership has expired.
+    fn has_member(&self, conn: &mut PgConnection, user_id: i32) -> QueryResult<bool> {
+        use diesel::dsl::IntervalDsl;
+
+        diesel::select(diesel::dsl::exists(
+            team_members::table
+                .find((self.id, user_id))
+                .filter(team_members::verified_at.gt(now - SSO_MEMBERSHIP_VALID_DAYS.days())),
+        ))
+        .get_result(conn)
+    }
+
     /// Phones home to Github to ask if this User is a member of the given team.
+    /// Every account that the user linked at the provider of the team is
+    /// checked, so membership through any of them counts.
     /// Note that we're assuming that the given user is the one interested in
     /// the answer. If this is not the case, then we could accidentally leak
     /// private membership information here.
-    pub fn contains_user(&self, app: &App, user: &User) -> AppResult<bool> {
+    pub fn contains_user(
+        &self,
+        app: &App,
+        conn: &mut PgConnection,
+        user: &User,
+    ) -> AppResult<bool> {
+        if self.provider == AccountProvider::Sso as i16 {
+            // The memberships are updated whenever the user logs in with
+            // single sign-on, and don't count once it's disabled
+            return Ok(app.config.sso.is_some() && self.has_member(conn, user.id)?);
+        }
+
+        if self.provider == AccountProvider::GitLab as i16 {
+            // Users that never logged in with GitLab can't be members of a group
+            let accounts = LinkedAccount::for_provider(conn, user.id, AccountProvider::GitLab)?;
+            return gitlab_group_contains_any(app, self.github_id, &accounts);
+        }
+
+        let is_member = self.github_team_contains_user(&*app.github, conn, user)?;
+        self.record_membership(conn, user.id, is_member);
+        Ok(is_member)
+    }
+
+    /// Asks GitHub if any of the GitHub accounts of the user is a member of
+    /// this team.
+    pub fn github_team_contains_user(
+        &self,
+        github: &dyn GitHubClient,
+        conn: &mut PgConnection,
+        user: &User,
+    ) -> AppResult<bool> {
         match self.org_id {
-            Some(org_id) => team_with_gh_id_contains_user(app, org_id, self.github_id, user),
+            Some(org_id) => {
+                let identities = github_identities(conn, user)?;
+                team_contains_any(github, org_id, self.github_id, &identities)
+            }
             // This means we don't have an org_id on file for the `self` team. It much
             // probably was deleted from github by the time we backfilled the database.
             // Short-circuiting to false since a non-existent team cannot contain any
@@ -183,6 +407,34 @@ impl Team {
         }
     }
 
+    /// Remembers the outcome of a membership check in the `team_members`
+    /// table, so that `worker::sync_team_memberships()` can validate the
+    /// membership again later on.
+    ///
+    /// Failing to record the membership doesn't fail the action that checked
+    /// it, e.g. when it runs on a read-only replica.
+    fn record_membership(&self, conn: &mut PgConnection, user_id: i32, is_member: bool) {
+        let result = conn.transaction(|conn| {
+            if is_member {
+                diesel::insert_into(team_members::table)
+                    .values((
+                        team_members::team_id.eq(self.id),
+                        team_members::user_id.eq(user_id),
+                    ))
+                    .on_conflict((team_members::team_id, team_members::user_id))
+                    .do_update()
+                    .set(team_members::verified_at.eq(now))
+                    .execute(conn)
+            } else {
+                diesel::delete(team_members::table.find((self.id, user_id))).execute(conn)
+            }
+        });
+
+        if let Err(error) = result {
+            warn!(team_id = self.id, user_id, %error, "Failed to record team membership");
+        }
+    }
+
     pub fn owning(krate: &Crate, conn: &mut PgConnection) -> QueryResult<Vec<Owner>> {
         let base_query = CrateOwner::belonging_to(krate).filter(crate_owners::deleted.eq(false));
         let teams = base_query
@@ -197,14 +449,67 @@ impl Team {
     }
 }
 
-fn can_add_team(app: &App, org_id: i32, team_id: i32, user: &User) -> AppResult<bool> {
-    Ok(team_with_gh_id_contains_user(app, org_id, team_id, user)?
-        || is_gh_org_owner(app, org_id, user)?)
+/// The logins and access tokens of all GitHub accounts of the user: the one
+/// they signed up with, if any, followed by the ones they linked later.
+fn github_identities(
+    conn: &mut PgConnection,
+    user: &User,
+) -> QueryResult<Vec<(String, AccessToken)>> {
+    let mut identities = Vec::new();
+    if user.gh_id != User::NO_GITHUB_ID {
+        identities.push((
+            user.gh_login.clone(),
+            AccessToken::new(user.gh_access_token.clone()),
+        ));
+    }
+
+    let linked = LinkedAccount::for_provider(conn, user.id, AccountProvider::GitHub)?;
+    identities.extend(
+        linked
+            .into_iter()
+            .map(|account| (account.login, AccessToken::new(account.access_token))),
+    );
+
+    Ok(identities)
 }
 
-fn is_gh_org_owner(app: &App, org_id: i32, user: &User) -> AppResult<bool> {
-    let token = AccessToken::new(user.gh_access_token.clone());
-    match app.github.org_membership(org_id, &user.gh_login, &token) {
+fn can_add_team(
+    github: &dyn GitHubClient,
+    org_id: i32,
+    team_id: i32,
+    identities: &[(String, AccessToken)],
+) -> AppResult<bool> {
+    for (login, token) in identities {
+        if team_with_gh_id_contains_user(github, org_id, team_id, login, token)?
+            || is_gh_org_owner(github, org_id, login, token)?
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn team_contains_any(
+    github: &dyn GitHubClient,
+    org_id: i32,
+    team_id: i32,
+    identities: &[(String, AccessToken)],
+) -> AppResult<bool> {
+    for (login, token) in identities {
+        if team_with_gh_id_contains_user(github, org_id, team_id, login, token)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn is_gh_org_owner(
+    github: &dyn GitHubClient,
+    org_id: i32,
+    login: &str,
+    token: &AccessToken,
+) -> AppResult<bool> {
+    match github.org_membership(org_id, login, token) {
         Ok(membership) => Ok(membership.state == "active" && membership.role == "admin"),
         Err(e) if e.is::<NotFound>() => Ok(false),
         Err(e) => Err(e),
@@ -212,26 +517,55 @@ fn is_gh_org_owner(app: &App, org_id: i32, user: &User) -> AppResult<bool> {
 }
 
 fn team_with_gh_id_contains_user(
-    app: &App,
+    github: &dyn GitHubClient,
     github_org_id: i32,
     github_team_id: i32,
-    user: &User,
+    login: &str,
+    token: &AccessToken,
 ) -> AppResult<bool> {
     // GET /organizations/:org_id/team/:team_id/memberships/:username
     // check that "state": "active"
 
-    let token = AccessToken::new(user.gh_access_token.clone());
-    let membership =
-        match app
-            .github
-            .team_membership(github_org_id, github_team_id, &user.gh_login, &token)
-        {
-            // Officially how `false` is returned
-            Err(ref e) if e.is::<NotFound>() => return Ok(false),
-            x => x?,
-        };
+    let membership = match github.team_membership(github_org_id, github_team_id, login, token) {
+        // Officially how `false` is returned
+        Err(ref e) if e.is::<NotFound>() => return Ok(false),
+        x => x?,
+    };
 
     // There is also `state: pending` for which we could possibly give
     // some feedback, but it's not obvious how that should work.
     Ok(membership.state == "active")
 }
+
+fn gitlab_group_contains_any(
+    app: &App,
+    group_id: i32,
+    accounts: &[LinkedAccount],
+) -> AppResult<bool> {
+    for account in accounts {
+        if gitlab_group_contains_account(app, group_id, account)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn gitlab_group_contains_account(
+    app: &App,
+    group_id: i32,
+    account: &LinkedAccount,
+) -> AppResult<bool> {
+    // GET /groups/:group_id/members/all/:user_id
+    // check that "state": "active" and that the user is at least a developer
+
+    let token = AccessToken::new(account.access_token.clone());
+    let membership = match app
+        .gitlab
+        .group_membership(group_id, account.account_id, &token)
+    {
+        Err(ref e) if e.is::<NotFound>() => return Ok(false),
+        x => x?,
+    };
+
+    Ok(membership.state == "active" && membership.access_level >= DEVELOPER_ACCESS_LEVEL)
+}
@@ -32,6 +32,10 @@ pub struct ApiToken {
     /// A list of endpoint scopes or `None` for the `legacy` endpoint scope (see RFC #2947)
     #[serde(skip)]
     pub endpoint_scopes: Option<Vec<EndpointScope>>,
+    /// The organization that the token acts for, which limits it to the
+    /// crates of the organization
+    #[serde(skip)]
+    pub organization_id: Option<i32>,
 }
 
 impl ApiToken {
@@ -46,6 +50,19 @@ impl ApiToken {
         name: &str,
         crate_scopes: Option<Vec<CrateScope>>,
         endpoint_scopes: Option<Vec<EndpointScope>>,
+    ) -> AppResult<CreatedApiToken> {
+        Self::insert_for_organization(conn, user_id, name, crate_scopes, endpoint_scopes, None)
+    }
+
+    /// Generates a new named API token for a user, that can only be used for
+    /// the crates of the organization, if one is given.
+    pub fn insert_for_organization(
+        conn: &mut PgConnection,
+        user_id: i32,
+        name: &str,
+        crate_scopes: Option<Vec<CrateScope>>,
+        endpoint_scopes: Option<Vec<EndpointScope>>,
+        organization_id: Option<i32>,
     ) -> AppResult<CreatedApiToken> {
         let token = SecureToken::generate(SecureTokenKind::Api);
 
@@ -56,6 +73,7 @@ impl ApiToken {
                 api_tokens::token.eq(&*token),
                 api_tokens::crate_scopes.eq(crate_scopes),
                 api_tokens::endpoint_scopes.eq(endpoint_scopes),
+                api_tokens::organization_id.eq(organization_id),
             ))
             .get_result(conn)?;
 
@@ -129,14 +147,15 @@ mod tests {
             .unwrap(),
             crate_scopes: None,
             endpoint_scopes: None,
+            organization_id: None,
         };
         let json = serde_json::to_string(&tok).unwrap();
         assert_some!(json
             .as_str()
-            .find(r#""created_at":"2017-01-06T14:23:11+00:00""#));
+            .find(r#""created_at":"2017-01-06T14:23:11.000Z""#));
         assert_some!(json
             .as_str()
-            .find(r#""last_used_at":"2017-01-06T14:23:12+00:00""#));
+            .find(r#""last_used_at":"2017-01-06T14:23:12.000Z""#));
     }
 
     #[test]
@@ -160,9 +179,9 @@ mod tests {
         let json = serde_json::to_string(&tok).unwrap();
         assert_some!(json
             .as_str()
-            .find(r#""created_at":"2017-01-06T14:23:11+00:00""#));
+            .find(r#""created_at":"2017-01-06T14:23:11.000Z""#));
         assert_some!(json
             .as_str()
-            .find(r#""last_used_at":"2017-01-06T14:23:12+00:00""#));
+            .find(r#""last_used_at":"2017-01-06T14:23:12.000Z""#));
     }
 }
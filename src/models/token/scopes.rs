@@ -12,6 +12,7 @@ pub enum EndpointScope {
     PublishUpdate,
     Yank,
     ChangeOwners,
+    Delete,
 }
 
 impl From<&EndpointScope> for &[u8] {
@@ -21,6 +22,7 @@ impl From<&EndpointScope> for &[u8] {
             EndpointScope::PublishUpdate => b"publish-update",
             EndpointScope::Yank => b"yank",
             EndpointScope::ChangeOwners => b"change-owners",
+            EndpointScope::Delete => b"delete",
         }
     }
 }
@@ -41,6 +43,7 @@ impl TryFrom<&[u8]> for EndpointScope {
             b"publish-update" => Ok(EndpointScope::PublishUpdate),
             b"yank" => Ok(EndpointScope::Yank),
             b"change-owners" => Ok(EndpointScope::ChangeOwners),
+            b"delete" => Ok(EndpointScope::Delete),
             _ => Err("Unrecognized enum variant".to_string()),
         }
     }
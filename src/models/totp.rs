@@ -0,0 +1,253 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::schema::{totp_credentials, totp_recovery_codes};
+use crate::util::errors::{AppResult, TotpLocked};
+use crate::util::token::{SecureToken, SecureTokenKind};
+use crate::util::totp;
+
+/// The number of recovery codes that are generated for a user.
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// The authenticator is locked for `LOCKOUT_MINUTES` after this many invalid
+/// codes in a row, so that the six digit codes can't be guessed.
+const MAX_FAILED_ATTEMPTS: i32 = 5;
+const LOCKOUT_MINUTES: i64 = 15;
+
+/// The time-based one-time password authenticator of a user.
+///
+/// A credential is created when the user starts the enrollment, but it is
+/// only used once it was enabled with a first valid code.
+#[derive(Clone, Identifiable, Queryable)]
+#[diesel(primary_key(user_id))]
+pub struct TotpCredential {
+    pub user_id: i32,
+    secret: Vec<u8>,
+    pub enabled: bool,
+    pub last_used_step: Option<i64>,
+    pub failed_attempts: i32,
+    pub locked_until: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl TotpCredential {
+    pub fn find(conn: &mut PgConnection, user_id: i32) -> QueryResult<Option<Self>> {
+        totp_credentials::table.find(user_id).first(conn).optional()
+    }
+
+    /// Returns whether the user has an enabled authenticator.
+    pub fn is_enabled_for(conn: &mut PgConnection, user_id: i32) -> QueryResult<bool> {
+        let query = totp_credentials::table
+            .filter(totp_credentials::user_id.eq(user_id))
+            .filter(totp_credentials::enabled);
+        diesel::select(diesel::dsl::exists(query)).get_result(conn)
+    }
+
+    /// Starts the enrollment of a new authenticator, replacing any previous
+    /// credential of the user.
+    pub fn enroll(conn: &mut PgConnection, user_id: i32) -> QueryResult<Self> {
+        let secret = totp::generate_secret();
+
+        diesel::insert_into(totp_credentials::table)
+            .values((
+                totp_credentials::user_id.eq(user_id),
+                totp_credentials::secret.eq(&secret),
+            ))
+            .on_conflict(totp_credentials::user_id)
+            .do_update()
+            .set((
+                totp_credentials::secret.eq(&secret),
+                totp_credentials::enabled.eq(false),
+                totp_credentials::last_used_step.eq(None::<i64>),
+                totp_credentials::failed_attempts.eq(0),
+                totp_credentials::locked_until.eq(None::<NaiveDateTime>),
+                totp_credentials::created_at.eq(diesel::dsl::now),
+            ))
+            .get_result(conn)
+    }
+
+    /// Returns the `otpauth://` URI that is used to add the secret to an
+    /// authenticator app.
+    pub fn provisioning_uri(&self, account: &str) -> String {
+        totp::provisioning_uri(&self.secret, account)
+    }
+
+    /// Checks the code and marks it as used.
+    ///
+    /// Every code is only accepted once, so codes that were observed by
+    /// someone else can not be replayed. Too many invalid codes in a row lock
+    /// the authenticator for a while, in which all codes are rejected with an
+    /// error.
+    ///
+    /// The failed attempts are recorded on the connection, so they are rolled
+    /// back together with a surrounding transaction. Callers should check the
+    /// code before starting a transaction, where possible.
+    pub fn verify(&self, conn: &mut PgConnection, code: &str) -> AppResult<bool> {
+        self.ensure_not_locked()?;
+        let valid = self.check_code(conn, code)?;
+        self.record_attempt(conn, valid)?;
+        Ok(valid)
+    }
+
+    /// Checks the code like `verify()`, but also accepts recovery codes,
+    /// which are used up in the process.
+    pub fn verify_or_redeem(&self, conn: &mut PgConnection, code: &str) -> AppResult<bool> {
+        self.ensure_not_locked()?;
+        let valid = match SecureToken::parse(SecureTokenKind::TotpRecoveryCode, code.trim()) {
+            Some(recovery_code) => TotpRecoveryCode::redeem(conn, self.user_id, &recovery_code)?,
+            None => self.check_code(conn, code)?,
+        };
+        self.record_attempt(conn, valid)?;
+        Ok(valid)
+    }
+
+    fn ensure_not_locked(&self) -> AppResult<()> {
+        match self.locked_until {
+            Some(until) if until > Utc::now().naive_utc() => Err(Box::new(TotpLocked { until })),
+            _ => Ok(()),
+        }
+    }
+
+    /// Resets the failed attempts after a valid code, or counts an invalid
+    /// one and locks the authenticator once there were too many.
+    fn record_attempt(&self, conn: &mut PgConnection, valid: bool) -> QueryResult<()> {
+        if valid {
+            diesel::update(self)
+                .set((
+                    totp_credentials::failed_attempts.eq(0),
+                    totp_credentials::locked_until.eq(None::<NaiveDateTime>),
+                ))
+                .execute(conn)?;
+            return Ok(());
+        }
+
+        let failed_attempts: i32 = diesel::update(self)
+            .set(totp_credentials::failed_attempts.eq(totp_credentials::failed_attempts + 1))
+            .returning(totp_credentials::failed_attempts)
+            .get_result(conn)?;
+
+        if failed_attempts >= MAX_FAILED_ATTEMPTS {
+            let until = Utc::now().naive_utc() + Duration::minutes(LOCKOUT_MINUTES);
+            diesel::update(self)
+                .set((
+                    totp_credentials::failed_attempts.eq(0),
+                    totp_credentials::locked_until.eq(until),
+                ))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_code(&self, conn: &mut PgConnection, code: &str) -> QueryResult<bool> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        let Some(step) = totp::verify(&self.secret, code, now) else {
+            return Ok(false);
+        };
+
+        let step = step as i64;
+        let updated = diesel::update(self)
+            .filter(
+                totp_credentials::last_used_step
+                    .is_null()
+                    .or(totp_credentials::last_used_step.lt(step)),
+            )
+            .set(totp_credentials::last_used_step.eq(step))
+            .execute(conn)?;
+
+        Ok(updated > 0)
+    }
+
+    pub fn enable(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        diesel::update(self)
+            .set(totp_credentials::enabled.eq(true))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Removes the authenticator together with the recovery codes of the
+    /// user.
+    pub fn delete(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        TotpRecoveryCode::delete_all(conn, self.user_id)?;
+        diesel::delete(self).execute(conn)?;
+        Ok(())
+    }
+}
+
+/// Single-use codes that can be used instead of a one-time password, in case
+/// the user lost access to their authenticator.
+///
+/// Like API tokens, only the hashes of the codes are stored, so the plaintext
+/// codes are only shown once when they are generated.
+pub struct TotpRecoveryCode;
+
+impl TotpRecoveryCode {
+    /// Replaces the recovery codes of the user with new ones, and returns
+    /// the plaintext codes.
+    pub fn regenerate(conn: &mut PgConnection, user_id: i32) -> QueryResult<Vec<String>> {
+        Self::delete_all(conn, user_id)?;
+
+        let codes: Vec<_> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| SecureToken::generate(SecureTokenKind::TotpRecoveryCode))
+            .collect();
+        let rows: Vec<_> = codes
+            .iter()
+            .map(|code| {
+                (
+                    totp_recovery_codes::user_id.eq(user_id),
+                    totp_recovery_codes::code.eq(&**code),
+                )
+            })
+            .collect();
+
+        diesel::insert_into(totp_recovery_codes::table)
+            .values(&rows)
+            .execute(conn)?;
+
+        Ok(codes.iter().map(|code| code.plaintext().into()).collect())
+    }
+
+    /// Returns the number of recovery codes the user has left.
+    pub fn count(conn: &mut PgConnection, user_id: i32) -> QueryResult<i64> {
+        totp_recovery_codes::table
+            .filter(totp_recovery_codes::user_id.eq(user_id))
+            .count()
+            .get_result(conn)
+    }
+
+    /// Deletes the recovery code and returns whether it was a valid code of
+    /// the user.
+    fn redeem(conn: &mut PgConnection, user_id: i32, code: &SecureToken) -> QueryResult<bool> {
+        let deleted = diesel::delete(totp_recovery_codes::table)
+            .filter(totp_recovery_codes::user_id.eq(user_id))
+            .filter(totp_recovery_codes::code.eq(code))
+            .execute(conn)?;
+
+        Ok(deleted > 0)
+    }
+
+    fn delete_all(conn: &mut PgConnection, user_id: i32) -> QueryResult<()> {
+        diesel::delete(totp_recovery_codes::table)
+            .filter(totp_recovery_codes::user_id.eq(user_id))
+            .execute(conn)?;
+        Ok(())
+    }
+}
+
+// Use a custom implementation of Debug to hide the secret.
+impl std::fmt::Debug for TotpCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TotpCredential")
+            .field("user_id", &self.user_id)
+            .field("enabled", &self.enabled)
+            .field("last_used_step", &self.last_used_step)
+            .field("locked_until", &self.locked_until)
+            .field("created_at", &self.created_at)
+            .finish()
+    }
+}
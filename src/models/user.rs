@@ -6,8 +6,12 @@ use crate::app::App;
 use crate::email::Emails;
 use crate::util::errors::AppResult;
 
-use crate::models::{ApiToken, Crate, CrateOwner, Email, NewEmail, Owner, OwnerKind, Rights};
+use crate::models::{
+    ApiToken, Crate, CrateOwner, Email, NewEmail, NotificationKind, NotificationPreferences,
+    OrganizationRole, Owner, OwnerKind, Rights,
+};
 use crate::schema::{crate_owners, emails, users};
+use crate::sql::lower;
 
 /// The model representing a row in the `users` database table.
 #[derive(Clone, Debug, PartialEq, Eq, Queryable, Identifiable, AsChangeset)]
@@ -20,6 +24,9 @@ pub struct User {
     pub gh_id: i32,
     pub account_lock_reason: Option<String>,
     pub account_lock_until: Option<NaiveDateTime>,
+    /// Whether the account lock was set by a suspension, which the crates.io
+    /// team can lift again.
+    pub account_suspended: bool,
 }
 
 /// Represents a new user record insertable to the `users` table
@@ -89,6 +96,7 @@ impl<'a> NewUser<'a> {
                 let new_email = NewEmail {
                     user_id: user.id,
                     email: user_email,
+                    send_notifications: true,
                 };
 
                 let token: Option<String> = insert_into(emails::table)
@@ -110,10 +118,34 @@ impl<'a> NewUser<'a> {
 }
 
 impl User {
+    /// The `gh_id` of users that signed up with another identity provider, and
+    /// have no GitHub account.
+    pub const NO_GITHUB_ID: i32 = 0;
+
     pub fn find(conn: &mut PgConnection, id: i32) -> QueryResult<User> {
         users::table.find(id).first(conn)
     }
 
+    /// Queries the database for the user with a certain login, ignoring case.
+    /// If several accounts used the login over time, the most recent one wins.
+    pub fn find_by_login(conn: &mut PgConnection, login: &str) -> QueryResult<User> {
+        users::table
+            .filter(lower(users::gh_login).eq(login.to_lowercase()))
+            .filter(users::gh_id.ne(-1))
+            .order(users::gh_id.desc())
+            .first(conn)
+    }
+
+    /// Returns whether a user already has the login, ignoring case. Logins are
+    /// shared by all identity providers, e.g. on the user pages and when
+    /// adding owners, so they can't be taken twice.
+    pub fn login_is_taken(conn: &mut PgConnection, login: &str) -> QueryResult<bool> {
+        diesel::select(diesel::dsl::exists(
+            users::table.filter(lower(users::gh_login).eq(login.to_lowercase())),
+        ))
+        .get_result(conn)
+    }
+
     /// Queries the database for a user with a certain `api_token` value.
     pub fn find_by_api_token(conn: &mut PgConnection, token: &str) -> AppResult<User> {
         let api_token = ApiToken::find_by_api_token(conn, token)?;
@@ -141,7 +173,15 @@ impl User {
     /// `Publish` as well, but this is a non-obvious invariant so we don't bother.
     /// Sweet free optimization if teams are proving burdensome to check.
     /// More than one team isn't really expected, though.
-    pub fn rights(&self, app: &App, owners: &[Owner]) -> AppResult<Rights> {
+    ///
+    /// Admins of an owning organization have the same rights as individual
+    /// owners, while its publishers have the rights of team members.
+    pub fn rights(
+        &self,
+        app: &App,
+        conn: &mut PgConnection,
+        owners: &[Owner],
+    ) -> AppResult<Rights> {
         let mut best = Rights::None;
         for owner in owners {
             match *owner {
@@ -151,29 +191,63 @@ impl User {
                     }
                 }
                 Owner::Team(ref team) => {
-                    if team.contains_user(app, self)? {
+                    if team.contains_user(app, conn, self)? {
                         best = Rights::Publish;
                     }
                 }
+                Owner::Organization(ref organization) => {
+                    match organization.role_of(conn, self.id)? {
+                        Some(OrganizationRole::Admin) => return Ok(Rights::Full),
+                        Some(OrganizationRole::Publisher) => best = Rights::Publish,
+                        Some(OrganizationRole::Viewer) | None => {}
+                    }
+                }
             }
         }
         Ok(best)
     }
 
-    /// Queries the database for the verified emails
-    /// belonging to a given user
+    /// Queries the database for the verified email address that notifications
+    /// of a given user are sent to
     pub fn verified_email(&self, conn: &mut PgConnection) -> QueryResult<Option<String>> {
         Email::belonging_to(self)
             .select(emails::email)
+            .filter(emails::send_notifications.eq(true))
             .filter(emails::verified.eq(true))
             .first(conn)
             .optional()
     }
 
-    /// Queries for the email belonging to a particular user
+    /// Queries for the verified notification email of the user, unless they
+    /// opted out of emails of the given kind.
+    pub fn notification_email(
+        &self,
+        conn: &mut PgConnection,
+        kind: NotificationKind,
+    ) -> QueryResult<Option<String>> {
+        if !NotificationPreferences::allows(conn, self.id, kind)? {
+            return Ok(None);
+        }
+
+        self.verified_email(conn)
+    }
+
+    /// Queries for the notification email belonging to a particular user
     pub fn email(&self, conn: &mut PgConnection) -> QueryResult<Option<String>> {
         Email::belonging_to(self)
             .select(emails::email)
+            .filter(emails::send_notifications.eq(true))
+            .first(conn)
+            .optional()
+    }
+
+    /// Queries for the verified email that a particular user chose to show on
+    /// their public profile
+    pub fn public_email(&self, conn: &mut PgConnection) -> QueryResult<Option<String>> {
+        Email::belonging_to(self)
+            .select(emails::email)
+            .filter(emails::public.eq(true))
+            .filter(emails::verified.eq(true))
             .first(conn)
             .optional()
     }
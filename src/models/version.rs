@@ -3,7 +3,8 @@ use std::collections::BTreeMap;
 use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
-use crate::util::errors::{cargo_err, AppResult};
+use crate::util::errors::{bad_request, cargo_err, AppResult};
+use crate::util::rfc3339;
 
 use crate::models::{Crate, Dependency, User};
 use crate::schema::*;
@@ -15,7 +16,9 @@ pub struct Version {
     pub id: i32,
     pub crate_id: i32,
     pub num: String,
+    #[serde(with = "rfc3339")]
     pub updated_at: NaiveDateTime,
+    #[serde(with = "rfc3339")]
     pub created_at: NaiveDateTime,
     pub downloads: i32,
     pub features: serde_json::Value,
@@ -25,6 +28,14 @@ pub struct Version {
     pub published_by: Option<i32>,
     pub checksum: String,
     pub links: Option<String>,
+    pub yank_reason: Option<String>,
+    pub yank_replacement: Option<String>,
+    pub docs_rs_status: Option<String>,
+    #[serde(with = "rfc3339::option")]
+    pub docs_rs_updated_at: Option<NaiveDateTime>,
+    pub deprecated: bool,
+    pub deprecation_message: Option<String>,
+    pub rust_version: Option<String>,
 }
 
 #[derive(Insertable, Debug)]
@@ -102,18 +113,24 @@ impl Version {
             .load(conn)
     }
 
+    /// Records that the readme of a version was rendered by the current
+    /// version of the renderer.
     pub fn record_readme_rendering(
         version_id_: i32,
         conn: &mut PgConnection,
     ) -> QueryResult<usize> {
         use crate::schema::readme_renderings::dsl::*;
+        use cargo_registry_markdown::RENDERER_VERSION;
         use diesel::dsl::now;
 
         diesel::insert_into(readme_renderings)
-            .values(version_id.eq(version_id_))
+            .values((
+                version_id.eq(version_id_),
+                renderer_version.eq(RENDERER_VERSION),
+            ))
             .on_conflict(version_id)
             .do_update()
-            .set(rendered_at.eq(now))
+            .set((rendered_at.eq(now), renderer_version.eq(RENDERER_VERSION)))
             .execute(conn)
     }
 
@@ -189,7 +206,15 @@ impl NewVersion {
 
     fn validate_license(&mut self, license_file: Option<&str>) -> AppResult<()> {
         if let Some(ref license) = self.license {
-            validate_license_expr(license)?;
+            let expression = validate_license_expr(license)?;
+            if license_file.is_none() {
+                if let Some(license_ref) = license_refs(&expression).next() {
+                    return Err(cargo_err(&format_args!(
+                        "the license expression references `LicenseRef-{license_ref}`, \
+                         which requires the license text to be uploaded via `license-file`"
+                    )));
+                }
+            }
         } else if license_file.is_some() {
             // If no license is given, but a license file is given, flag this
             // crate as having a nonstandard license. Note that we don't
@@ -200,7 +225,9 @@ impl NewVersion {
     }
 }
 
-fn validate_license_expr(s: &str) -> AppResult<()> {
+const LICENSE_EXPR_ERROR: &str = "unknown or invalid license expression; see http://opensource.org/licenses for options, and http://spdx.org/licenses/ for their identifiers";
+
+fn validate_license_expr(s: &str) -> AppResult<spdx::Expression> {
     pub const PARSE_MODE: spdx::ParseMode = spdx::ParseMode {
         allow_lower_case_operators: false,
         allow_slash_as_or_operator: true,
@@ -208,16 +235,103 @@ fn validate_license_expr(s: &str) -> AppResult<()> {
         allow_postfix_plus_on_gpl: true,
     };
 
-    spdx::Expression::parse_mode(s, PARSE_MODE).map_err(|_| {
-        cargo_err("unknown or invalid license expression; see http://opensource.org/licenses for options, and http://spdx.org/licenses/ for their identifiers")
-    })?;
+    spdx::Expression::parse_mode(s, PARSE_MODE).map_err(|error| {
+        let suggestion = match error.reason {
+            spdx::error::Reason::UnknownTerm => {
+                let term = &error.original[error.span.clone()];
+                suggest_license_id(term).map(|id| (term, id))
+            }
+            _ => None,
+        };
+
+        match suggestion {
+            Some((term, id)) => cargo_err(&format_args!(
+                "{LICENSE_EXPR_ERROR}; did you mean `{id}` instead of `{term}`?"
+            )),
+            None => cargo_err(LICENSE_EXPR_ERROR),
+        }
+    })
+}
+
+/// Finds the SPDX identifier that an unknown license term most likely refers
+/// to, e.g. `Apache-2.0` for `apache-2.0` or `apache2`.
+fn suggest_license_id(term: &str) -> Option<&'static str> {
+    spdx::identifiers::LICENSES
+        .iter()
+        .map(|license| license.0)
+        .find(|name| name.eq_ignore_ascii_case(term))
+        .or_else(|| {
+            spdx::imprecise_license_id(term)
+                .filter(|(_, len)| *len == term.len())
+                .map(|(id, _)| id.name)
+        })
+}
+
+/// Returns the `LicenseRef-*` identifiers used in the expression.
+fn license_refs(expression: &spdx::Expression) -> impl Iterator<Item = &str> {
+    expression
+        .requirements()
+        .filter_map(|req| match &req.req.license {
+            spdx::LicenseItem::Other { lic_ref, .. } => Some(lic_ref.as_str()),
+            spdx::LicenseItem::Spdx { .. } => None,
+        })
+}
+
+/// The licenses a user is willing to accept, e.g. when filtering search
+/// results, given as an SPDX expression like `MIT OR Apache-2.0`.
+#[derive(Debug)]
+pub struct AcceptedLicenses(Vec<spdx::Licensee>);
+
+impl AcceptedLicenses {
+    pub fn parse(s: &str) -> AppResult<Self> {
+        let expression = spdx::Expression::parse_mode(s, spdx::ParseMode::LAX)
+            .map_err(|_| bad_request(LICENSE_EXPR_ERROR))?;
+
+        let uses_and = expression.iter().any(|node| {
+            matches!(
+                node,
+                spdx::expression::ExprNode::Op(spdx::expression::Operator::And)
+            )
+        });
+        if uses_and {
+            return Err(bad_request(
+                "accepted licenses can only be combined with `OR`",
+            ));
+        }
+
+        let licensees = expression
+            .requirements()
+            .map(|req| {
+                // A licensee is always a specific version of a license
+                let mut license = req.req.license.clone();
+                if let spdx::LicenseItem::Spdx { or_later, .. } = &mut license {
+                    *or_later = false;
+                }
+                spdx::Licensee::new(license, req.req.exception)
+            })
+            .collect();
+
+        Ok(Self(licensees))
+    }
 
-    Ok(())
+    /// Returns whether code under the license expression of a version can be
+    /// used under the accepted licenses.
+    ///
+    /// For example, `MIT OR GPL-3.0` is accepted if `MIT` is accepted, while
+    /// `MIT AND GPL-3.0` also requires `GPL-3.0` to be accepted. Expressions
+    /// that can not be parsed, including `non-standard`, are never accepted.
+    pub fn accepts(&self, license: &str) -> bool {
+        let Ok(expression) = spdx::Expression::parse_mode(license, spdx::ParseMode::LAX) else {
+            return false;
+        };
+
+        expression.evaluate(|req| self.0.iter().any(|licensee| licensee.satisfies(req)))
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{validate_license_expr, TopVersions};
+    use super::{license_refs, validate_license_expr, AcceptedLicenses, TopVersions};
     use chrono::NaiveDateTime;
 
     #[track_caller]
@@ -301,4 +415,46 @@ mod tests {
         let error = format!("{error}");
         assert!(error.starts_with("unknown or invalid license expression; see http"));
     }
+
+    #[test]
+    fn license_suggestions() {
+        let error = assert_err!(validate_license_expr("mit OR Apache-2.0")).to_string();
+        assert!(error.starts_with("unknown or invalid license expression; see http"));
+        assert!(error.ends_with("did you mean `MIT` instead of `mit`?"));
+
+        let error = assert_err!(validate_license_expr("MIT OR apache-2.0")).to_string();
+        assert!(error.ends_with("did you mean `Apache-2.0` instead of `apache-2.0`?"));
+
+        let error = assert_err!(validate_license_expr("MIT OR Foobar-1.0")).to_string();
+        assert!(!error.contains("did you mean"));
+    }
+
+    #[test]
+    fn license_refs_in_expression() {
+        let expression = assert_ok!(validate_license_expr("MIT OR LicenseRef-Proprietary"));
+        let refs = license_refs(&expression).collect::<Vec<_>>();
+        assert_eq!(refs, vec!["Proprietary"]);
+
+        let expression = assert_ok!(validate_license_expr("MIT OR Apache-2.0"));
+        assert_none!(license_refs(&expression).next());
+    }
+
+    #[test]
+    fn accepted_licenses() {
+        let accepted = assert_ok!(AcceptedLicenses::parse("MIT OR Apache-2.0"));
+        assert!(accepted.accepts("MIT"));
+        assert!(accepted.accepts("MIT OR GPL-3.0"));
+        assert!(accepted.accepts("MIT/Apache-2.0"));
+        assert!(accepted.accepts("MIT AND Apache-2.0"));
+        assert!(!accepted.accepts("MIT AND GPL-3.0"));
+        assert!(!accepted.accepts("GPL-3.0"));
+        assert!(!accepted.accepts("non-standard"));
+
+        let accepted = assert_ok!(AcceptedLicenses::parse("Apache-2.0 WITH LLVM-exception"));
+        assert!(accepted.accepts("Apache-2.0 WITH LLVM-exception"));
+        assert!(!accepted.accepts("MIT"));
+
+        assert_err!(AcceptedLicenses::parse("MIT AND Apache-2.0"));
+        assert_err!(AcceptedLicenses::parse("not a license"));
+    }
 }
@@ -0,0 +1,108 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::models::Version;
+use crate::schema::version_signatures;
+
+/// A signature of the crate file of a version, which was uploaded together
+/// with the version.
+///
+/// Only the metadata is stored in the database, the signature itself is
+/// stored next to the crate file.
+#[derive(Clone, Debug, Identifiable, Queryable, Associations)]
+#[diesel(primary_key(version_id))]
+#[diesel(belongs_to(Version))]
+pub struct VersionSignature {
+    pub version_id: i32,
+    pub kind: String,
+    pub size: i32,
+    pub created_at: NaiveDateTime,
+}
+
+impl VersionSignature {
+    pub fn find(conn: &mut PgConnection, version_id: i32) -> QueryResult<Option<Self>> {
+        version_signatures::table
+            .find(version_id)
+            .first(conn)
+            .optional()
+    }
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = version_signatures)]
+pub struct NewVersionSignature<'a> {
+    pub version_id: i32,
+    pub kind: &'a str,
+    pub size: i32,
+}
+
+impl NewVersionSignature<'_> {
+    pub fn create(&self, conn: &mut PgConnection) -> QueryResult<VersionSignature> {
+        diesel::insert_into(version_signatures::table)
+            .values(self)
+            .get_result(conn)
+    }
+}
+
+/// The formats of signatures that can be uploaded with a version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureKind {
+    /// A detached signature of the crate file in any format, for example
+    /// OpenPGP, minisign or SSH signatures.
+    Detached,
+    /// A Sigstore bundle, which contains the signature together with the
+    /// certificate and the transparency log entry needed to verify it.
+    SigstoreBundle,
+}
+
+impl SignatureKind {
+    /// Detects the format of the uploaded signature.
+    pub fn detect(signature: &[u8]) -> Self {
+        #[derive(Deserialize)]
+        struct Bundle {
+            #[serde(rename = "mediaType")]
+            media_type: String,
+        }
+
+        match serde_json::from_slice::<Bundle>(signature) {
+            Ok(bundle) if bundle.media_type.starts_with(SIGSTORE_BUNDLE_MEDIA_TYPE) => {
+                Self::SigstoreBundle
+            }
+            _ => Self::Detached,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Detached => "detached",
+            Self::SigstoreBundle => "sigstore_bundle",
+        }
+    }
+
+    /// The content type that the signature is served with.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Detached => "application/octet-stream",
+            Self::SigstoreBundle => "application/vnd.dev.sigstore.bundle+json",
+        }
+    }
+}
+
+const SIGSTORE_BUNDLE_MEDIA_TYPE: &str = "application/vnd.dev.sigstore.bundle";
+
+#[cfg(test)]
+mod tests {
+    use super::SignatureKind;
+
+    #[test]
+    fn detect_signature_kind() {
+        let bundle = br#"{"mediaType":"application/vnd.dev.sigstore.bundle+json;version=0.1"}"#;
+        assert_eq!(SignatureKind::detect(bundle), SignatureKind::SigstoreBundle);
+
+        let other_json = br#"{"mediaType":"application/json"}"#;
+        assert_eq!(SignatureKind::detect(other_json), SignatureKind::Detached);
+
+        let pgp = b"-----BEGIN PGP SIGNATURE-----\n\n-----END PGP SIGNATURE-----\n";
+        assert_eq!(SignatureKind::detect(pgp), SignatureKind::Detached);
+    }
+}
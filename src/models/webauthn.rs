@@ -0,0 +1,206 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use webauthn_rs::prelude::SecurityKey;
+
+use crate::models::User;
+use crate::schema::{webauthn_challenges, webauthn_credentials};
+use crate::util::errors::AppResult;
+
+/// Challenges that have not been answered within this many minutes are no
+/// longer accepted.
+const CHALLENGE_TTL_MINUTES: i64 = 5;
+
+/// A security key that a user registered as a second factor.
+#[derive(Clone, Debug, Identifiable, Queryable, Associations, Serialize)]
+#[diesel(belongs_to(User))]
+pub struct WebauthnCredential {
+    pub id: i32,
+    #[serde(skip)]
+    pub user_id: i32,
+    #[serde(serialize_with = "serialize_credential_id")]
+    pub credential_id: Vec<u8>,
+    #[serde(skip)]
+    pub sign_count: i64,
+    pub name: String,
+    #[serde(with = "crate::util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(with = "crate::util::rfc3339::option")]
+    pub last_used_at: Option<NaiveDateTime>,
+    #[serde(skip)]
+    security_key: Value,
+}
+
+/// Serializes the credential ID as unpadded base64url, the encoding that the
+/// WebAuthn browser APIs use for binary data in JSON.
+fn serialize_credential_id<S: serde::Serializer>(id: &[u8], s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&base64::encode_config(id, base64::URL_SAFE_NO_PAD))
+}
+
+impl WebauthnCredential {
+    pub fn for_user(conn: &mut PgConnection, user_id: i32) -> QueryResult<Vec<Self>> {
+        webauthn_credentials::table
+            .filter(webauthn_credentials::user_id.eq(user_id))
+            .order(webauthn_credentials::id)
+            .load(conn)
+    }
+
+    /// Returns whether the user has registered any credential.
+    pub fn exists_for(conn: &mut PgConnection, user_id: i32) -> QueryResult<bool> {
+        let query = webauthn_credentials::table.filter(webauthn_credentials::user_id.eq(user_id));
+        diesel::select(diesel::dsl::exists(query)).get_result(conn)
+    }
+
+    pub fn find(conn: &mut PgConnection, user_id: i32, id: i32) -> QueryResult<Self> {
+        webauthn_credentials::table
+            .find(id)
+            .filter(webauthn_credentials::user_id.eq(user_id))
+            .first(conn)
+    }
+
+    pub fn find_by_credential_id(
+        conn: &mut PgConnection,
+        user_id: i32,
+        credential_id: &[u8],
+    ) -> QueryResult<Option<Self>> {
+        webauthn_credentials::table
+            .filter(webauthn_credentials::user_id.eq(user_id))
+            .filter(webauthn_credentials::credential_id.eq(credential_id))
+            .first(conn)
+            .optional()
+    }
+
+    pub fn create(
+        conn: &mut PgConnection,
+        user_id: i32,
+        name: &str,
+        security_key: &SecurityKey,
+    ) -> AppResult<Self> {
+        let credential = diesel::insert_into(webauthn_credentials::table)
+            .values((
+                webauthn_credentials::user_id.eq(user_id),
+                webauthn_credentials::credential_id.eq(&security_key.cred_id().0),
+                webauthn_credentials::sign_count.eq(0),
+                webauthn_credentials::name.eq(name),
+                webauthn_credentials::security_key.eq(serde_json::to_value(security_key)?),
+            ))
+            .get_result(conn)?;
+
+        Ok(credential)
+    }
+
+    pub fn security_key(&self) -> serde_json::Result<SecurityKey> {
+        serde_json::from_value(self.security_key.clone())
+    }
+
+    /// Stores the credential with the signature counter of a successful
+    /// assertion.
+    ///
+    /// The counter is compared again in the query, so that concurrent
+    /// assertions with the same counter can't both succeed.
+    pub fn record_use(
+        &self,
+        conn: &mut PgConnection,
+        security_key: &SecurityKey,
+        sign_count: u32,
+    ) -> AppResult<bool> {
+        let sign_count = sign_count as i64;
+        let updated = diesel::update(self)
+            .filter(
+                webauthn_credentials::sign_count
+                    .lt(sign_count)
+                    .or(webauthn_credentials::sign_count.eq(0)),
+            )
+            .set((
+                webauthn_credentials::sign_count.eq(sign_count),
+                webauthn_credentials::security_key.eq(serde_json::to_value(security_key)?),
+                webauthn_credentials::last_used_at.eq(diesel::dsl::now.nullable()),
+            ))
+            .execute(conn)?;
+
+        Ok(updated > 0)
+    }
+
+    pub fn delete(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        diesel::delete(self).execute(conn)?;
+        Ok(())
+    }
+}
+
+/// The WebAuthn ceremonies that challenges are issued for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebauthnCeremony {
+    /// Registering a new credential.
+    Registration,
+    /// Proving the possession of a registered credential, when logging in or
+    /// before sensitive actions.
+    Authentication,
+}
+
+impl WebauthnCeremony {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WebauthnCeremony::Registration => "registration",
+            WebauthnCeremony::Authentication => "authentication",
+        }
+    }
+}
+
+/// The pending ceremonies of users, whose state contains the random challenge
+/// that the authenticator has to sign, so that its responses can't be
+/// replayed.
+pub struct WebauthnChallenge;
+
+impl WebauthnChallenge {
+    /// Stores the state of a new ceremony, replacing the pending ceremony of
+    /// the user of the same kind.
+    pub fn issue<T: Serialize>(
+        conn: &mut PgConnection,
+        user_id: i32,
+        ceremony: WebauthnCeremony,
+        state: &T,
+    ) -> AppResult<()> {
+        let state = serde_json::to_value(state)?;
+
+        diesel::insert_into(webauthn_challenges::table)
+            .values((
+                webauthn_challenges::user_id.eq(user_id),
+                webauthn_challenges::ceremony.eq(ceremony.as_str()),
+                webauthn_challenges::state.eq(&state),
+            ))
+            .on_conflict((webauthn_challenges::user_id, webauthn_challenges::ceremony))
+            .do_update()
+            .set((
+                webauthn_challenges::state.eq(&state),
+                webauthn_challenges::created_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Removes the pending ceremony of the user of the given kind, and returns
+    /// its state if it has not expired yet.
+    pub fn take<T: DeserializeOwned>(
+        conn: &mut PgConnection,
+        user_id: i32,
+        ceremony: WebauthnCeremony,
+    ) -> AppResult<Option<T>> {
+        let state: Option<(Value, NaiveDateTime)> = diesel::delete(webauthn_challenges::table)
+            .filter(webauthn_challenges::user_id.eq(user_id))
+            .filter(webauthn_challenges::ceremony.eq(ceremony.as_str()))
+            .returning((webauthn_challenges::state, webauthn_challenges::created_at))
+            .get_result(conn)
+            .optional()?;
+
+        let expires_after = Utc::now().naive_utc() - Duration::minutes(CHALLENGE_TTL_MINUTES);
+        match state {
+            Some((state, created_at)) if created_at > expires_after => {
+                Ok(Some(serde_json::from_value(state)?))
+            }
+            _ => Ok(None),
+        }
+    }
+}
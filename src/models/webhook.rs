@@ -0,0 +1,172 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::models::Crate;
+use crate::schema::{webhook_deliveries, webhooks};
+use crate::util::token::{SecureToken, SecureTokenKind};
+
+/// An HTTPS endpoint that is notified about the events of a crate.
+#[derive(Clone, Debug, Identifiable, Queryable, Associations)]
+#[diesel(belongs_to(Crate))]
+pub struct Webhook {
+    pub id: i32,
+    pub crate_id: i32,
+    pub url: String,
+    secret: SecureToken,
+    pub created_by: i32,
+    pub created_at: NaiveDateTime,
+}
+
+impl Webhook {
+    pub fn for_crate(conn: &mut PgConnection, crate_id: i32) -> QueryResult<Vec<Self>> {
+        webhooks::table
+            .filter(webhooks::crate_id.eq(crate_id))
+            .order(webhooks::id)
+            .load(conn)
+    }
+
+    pub fn find(conn: &mut PgConnection, id: i32, crate_id: i32) -> QueryResult<Self> {
+        webhooks::table
+            .find(id)
+            .filter(webhooks::crate_id.eq(crate_id))
+            .first(conn)
+    }
+
+    /// Returns the key that is used to sign the payloads sent to this webhook.
+    ///
+    /// Only the SHA-256 hash of the secret is stored, so the hash is used as
+    /// the HMAC key. Receivers compute it from the secret they were given.
+    pub(crate) fn signing_key(&self) -> &[u8] {
+        self.secret.sha256()
+    }
+
+    pub fn delete(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        diesel::delete(self).execute(conn)?;
+        Ok(())
+    }
+}
+
+pub struct NewWebhook<'a> {
+    pub crate_id: i32,
+    pub url: &'a str,
+    pub created_by: i32,
+}
+
+impl NewWebhook<'_> {
+    /// Stores the webhook with a newly generated secret.
+    pub fn create(&self, conn: &mut PgConnection) -> QueryResult<CreatedWebhook> {
+        let secret = SecureToken::generate(SecureTokenKind::WebhookSecret);
+
+        let model: Webhook = diesel::insert_into(webhooks::table)
+            .values((
+                webhooks::crate_id.eq(self.crate_id),
+                webhooks::url.eq(self.url),
+                webhooks::secret.eq(&*secret),
+                webhooks::created_by.eq(self.created_by),
+            ))
+            .get_result(conn)?;
+
+        Ok(CreatedWebhook {
+            plaintext_secret: secret.plaintext().into(),
+            model,
+        })
+    }
+}
+
+pub struct CreatedWebhook {
+    pub model: Webhook,
+    pub plaintext_secret: String,
+}
+
+// Use a custom implementation of Debug to hide the plaintext secret.
+impl std::fmt::Debug for CreatedWebhook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CreatedWebhook")
+            .field("model", &self.model)
+            .field("plaintext_secret", &"(sensitive)")
+            .finish()
+    }
+}
+
+/// The events of a crate that webhooks are notified about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookEvent {
+    Publish,
+    Yank,
+    Unyank,
+    OwnerAdded,
+    OwnerRemoved,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Publish => "publish",
+            Self::Yank => "yank",
+            Self::Unyank => "unyank",
+            Self::OwnerAdded => "owner_added",
+            Self::OwnerRemoved => "owner_removed",
+        }
+    }
+}
+
+/// An event that was sent, or is still to be sent, to a webhook.
+#[derive(Clone, Debug, Identifiable, Queryable, Associations)]
+#[diesel(belongs_to(Webhook))]
+#[diesel(table_name = webhook_deliveries)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub webhook_id: i32,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl WebhookDelivery {
+    /// Creates a delivery of the event for every webhook of the crate and
+    /// returns their IDs.
+    pub fn create_for_crate(
+        conn: &mut PgConnection,
+        crate_id: i32,
+        event: WebhookEvent,
+        payload: &serde_json::Value,
+    ) -> QueryResult<Vec<i32>> {
+        let webhook_ids: Vec<i32> = webhooks::table
+            .filter(webhooks::crate_id.eq(crate_id))
+            .select(webhooks::id)
+            .load(conn)?;
+
+        if webhook_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let new_deliveries = webhook_ids
+            .into_iter()
+            .map(|webhook_id| {
+                (
+                    webhook_deliveries::webhook_id.eq(webhook_id),
+                    webhook_deliveries::event.eq(event.as_str()),
+                    webhook_deliveries::payload.eq(payload),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        diesel::insert_into(webhook_deliveries::table)
+            .values(&new_deliveries)
+            .returning(webhook_deliveries::id)
+            .get_results(conn)
+    }
+
+    /// Returns the most recent deliveries of the webhook.
+    pub fn recent(conn: &mut PgConnection, webhook_id: i32, limit: i64) -> QueryResult<Vec<Self>> {
+        webhook_deliveries::table
+            .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+            .order(webhook_deliveries::id.desc())
+            .limit(limit)
+            .load(conn)
+    }
+}
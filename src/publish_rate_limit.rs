@@ -8,6 +8,28 @@ use crate::schema::{publish_limit_buckets, publish_rate_overrides};
 use crate::sql::{date_part, floor, greatest, interval_part, least};
 use crate::util::errors::{AppResult, TooManyRequests};
 
+/// The actions that are rate limited. Each user has a separate bucket for
+/// every action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i16)]
+pub enum LimitedAction {
+    PublishNew = 0,
+    PublishUpdate = 1,
+}
+
+impl LimitedAction {
+    pub fn error_message(&self) -> &'static str {
+        match self {
+            LimitedAction::PublishNew => {
+                "You have published too many crates in a short period of time."
+            }
+            LimitedAction::PublishUpdate => {
+                "You have published too many versions of existing crates in a short period of time."
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct PublishRateLimit {
     pub rate: Duration,
@@ -16,20 +38,7 @@ pub struct PublishRateLimit {
 
 impl Default for PublishRateLimit {
     fn default() -> Self {
-        let minutes = dotenv::var("WEB_NEW_PKG_RATE_LIMIT_RATE_MINUTES")
-            .unwrap_or_default()
-            .parse()
-            .ok()
-            .unwrap_or(10);
-        let burst = dotenv::var("WEB_NEW_PKG_RATE_LIMIT_BURST")
-            .unwrap_or_default()
-            .parse()
-            .ok()
-            .unwrap_or(5);
-        Self {
-            rate: Duration::from_secs(60) * minutes,
-            burst,
-        }
+        Self::from_environment("WEB_NEW_PKG_RATE_LIMIT", 10, 5)
     }
 }
 
@@ -40,15 +49,49 @@ struct Bucket {
     user_id: i32,
     tokens: i32,
     last_refill: NaiveDateTime,
+    action: i16,
 }
 
 impl PublishRateLimit {
-    pub fn check_rate_limit(&self, uploader: i32, conn: &mut PgConnection) -> AppResult<()> {
-        let bucket = self.take_token(uploader, Utc::now().naive_utc(), conn)?;
+    /// Limits publishing new versions of existing crates.
+    ///
+    /// The burst is much larger than for new crates, so that all crates of a
+    /// workspace can be released at once.
+    pub fn for_updates() -> Self {
+        Self::from_environment("WEB_UPDATE_PKG_RATE_LIMIT", 1, 30)
+    }
+
+    /// Reads the refill rate from `{prefix}_RATE_MINUTES` and the burst from
+    /// `{prefix}_BURST`.
+    fn from_environment(prefix: &str, default_minutes: u32, default_burst: i32) -> Self {
+        let minutes = dotenv::var(format!("{prefix}_RATE_MINUTES"))
+            .unwrap_or_default()
+            .parse()
+            .ok()
+            .unwrap_or(default_minutes);
+        let burst = dotenv::var(format!("{prefix}_BURST"))
+            .unwrap_or_default()
+            .parse()
+            .ok()
+            .unwrap_or(default_burst);
+        Self {
+            rate: Duration::from_secs(60) * minutes,
+            burst,
+        }
+    }
+
+    pub fn check_rate_limit(
+        &self,
+        uploader: i32,
+        action: LimitedAction,
+        conn: &mut PgConnection,
+    ) -> AppResult<()> {
+        let bucket = self.take_token(uploader, action, Utc::now().naive_utc(), conn)?;
         if bucket.tokens >= 1 {
             Ok(())
         } else {
             Err(Box::new(TooManyRequests {
+                action,
                 retry_after: bucket.last_refill + chrono::Duration::from_std(self.rate).unwrap(),
             }))
         }
@@ -65,13 +108,14 @@ impl PublishRateLimit {
     fn take_token(
         &self,
         uploader: i32,
+        performed_action: LimitedAction,
         now: NaiveDateTime,
         conn: &mut PgConnection,
     ) -> QueryResult<Bucket> {
         use self::publish_limit_buckets::dsl::*;
 
         let burst: i32 = publish_rate_overrides::table
-            .find(uploader)
+            .find((uploader, performed_action as i16))
             .filter(
                 publish_rate_overrides::expires_at
                     .is_null()
@@ -91,8 +135,13 @@ impl PublishRateLimit {
         );
 
         diesel::insert_into(publish_limit_buckets)
-            .values((user_id.eq(uploader), tokens.eq(burst), last_refill.eq(now)))
-            .on_conflict(user_id)
+            .values((
+                user_id.eq(uploader),
+                action.eq(performed_action as i16),
+                tokens.eq(burst),
+                last_refill.eq(now),
+            ))
+            .on_conflict((user_id, action))
             .do_update()
             .set((
                 tokens.eq(least(burst, greatest(0, tokens - 1) + tokens_to_add)),
@@ -123,11 +172,17 @@ mod tests {
             rate: Duration::from_secs(1),
             burst: 10,
         };
-        let bucket = rate.take_token(new_user(conn, "user1")?, now, conn)?;
+        let bucket = rate.take_token(
+            new_user(conn, "user1")?,
+            LimitedAction::PublishNew,
+            now,
+            conn,
+        )?;
         let expected = Bucket {
             user_id: bucket.user_id,
             tokens: 10,
             last_refill: now,
+            action: LimitedAction::PublishNew as i16,
         };
         assert_eq!(expected, bucket);
 
@@ -135,11 +190,17 @@ mod tests {
             rate: Duration::from_millis(50),
             burst: 20,
         };
-        let bucket = rate.take_token(new_user(conn, "user2")?, now, conn)?;
+        let bucket = rate.take_token(
+            new_user(conn, "user2")?,
+            LimitedAction::PublishNew,
+            now,
+            conn,
+        )?;
         let expected = Bucket {
             user_id: bucket.user_id,
             tokens: 20,
             last_refill: now,
+            action: LimitedAction::PublishNew as i16,
         };
         assert_eq!(expected, bucket);
         Ok(())
@@ -155,11 +216,12 @@ mod tests {
             burst: 10,
         };
         let user_id = new_user_bucket(conn, 5, now)?.user_id;
-        let bucket = rate.take_token(user_id, now, conn)?;
+        let bucket = rate.take_token(user_id, LimitedAction::PublishNew, now, conn)?;
         let expected = Bucket {
             user_id,
             tokens: 4,
             last_refill: now,
+            action: LimitedAction::PublishNew as i16,
         };
         assert_eq!(expected, bucket);
         Ok(())
@@ -176,11 +238,12 @@ mod tests {
         };
         let user_id = new_user_bucket(conn, 5, now)?.user_id;
         let refill_time = now + chrono::Duration::seconds(2);
-        let bucket = rate.take_token(user_id, refill_time, conn)?;
+        let bucket = rate.take_token(user_id, LimitedAction::PublishNew, refill_time, conn)?;
         let expected = Bucket {
             user_id,
             tokens: 6,
             last_refill: refill_time,
+            action: LimitedAction::PublishNew as i16,
         };
         assert_eq!(expected, bucket);
         Ok(())
@@ -201,11 +264,12 @@ mod tests {
         };
         let user_id = new_user_bucket(conn, 5, now)?.user_id;
         let refill_time = now + chrono::Duration::milliseconds(300);
-        let bucket = rate.take_token(user_id, refill_time, conn)?;
+        let bucket = rate.take_token(user_id, LimitedAction::PublishNew, refill_time, conn)?;
         let expected = Bucket {
             user_id,
             tokens: 7,
             last_refill: refill_time,
+            action: LimitedAction::PublishNew as i16,
         };
         assert_eq!(expected, bucket);
         Ok(())
@@ -221,12 +285,18 @@ mod tests {
             burst: 10,
         };
         let user_id = new_user_bucket(conn, 5, now)?.user_id;
-        let bucket = rate.take_token(user_id, now + chrono::Duration::milliseconds(250), conn)?;
+        let bucket = rate.take_token(
+            user_id,
+            LimitedAction::PublishNew,
+            now + chrono::Duration::milliseconds(250),
+            conn,
+        )?;
         let expected_refill_time = now + chrono::Duration::milliseconds(200);
         let expected = Bucket {
             user_id,
             tokens: 6,
             last_refill: expected_refill_time,
+            action: LimitedAction::PublishNew as i16,
         };
         assert_eq!(expected, bucket);
         Ok(())
@@ -242,15 +312,16 @@ mod tests {
             burst: 10,
         };
         let user_id = new_user_bucket(conn, 1, now)?.user_id;
-        let bucket = rate.take_token(user_id, now, conn)?;
+        let bucket = rate.take_token(user_id, LimitedAction::PublishNew, now, conn)?;
         let expected = Bucket {
             user_id,
             tokens: 0,
             last_refill: now,
+            action: LimitedAction::PublishNew as i16,
         };
         assert_eq!(expected, bucket);
 
-        let bucket = rate.take_token(user_id, now, conn)?;
+        let bucket = rate.take_token(user_id, LimitedAction::PublishNew, now, conn)?;
         assert_eq!(expected, bucket);
         Ok(())
     }
@@ -266,11 +337,12 @@ mod tests {
         };
         let user_id = new_user_bucket(conn, 0, now)?.user_id;
         let refill_time = now + chrono::Duration::seconds(1);
-        let bucket = rate.take_token(user_id, refill_time, conn)?;
+        let bucket = rate.take_token(user_id, LimitedAction::PublishNew, refill_time, conn)?;
         let expected = Bucket {
             user_id,
             tokens: 1,
             last_refill: refill_time,
+            action: LimitedAction::PublishNew as i16,
         };
         assert_eq!(expected, bucket);
 
@@ -288,11 +360,12 @@ mod tests {
         };
         let user_id = new_user_bucket(conn, 8, now)?.user_id;
         let refill_time = now + chrono::Duration::seconds(4);
-        let bucket = rate.take_token(user_id, refill_time, conn)?;
+        let bucket = rate.take_token(user_id, LimitedAction::PublishNew, refill_time, conn)?;
         let expected = Bucket {
             user_id,
             tokens: 10,
             last_refill: refill_time,
+            action: LimitedAction::PublishNew as i16,
         };
         assert_eq!(expected, bucket);
 
@@ -318,8 +391,8 @@ mod tests {
             ))
             .execute(conn)?;
 
-        let bucket = rate.take_token(user_id, now, conn)?;
-        let other_bucket = rate.take_token(other_user_id, now, conn)?;
+        let bucket = rate.take_token(user_id, LimitedAction::PublishNew, now, conn)?;
+        let other_bucket = rate.take_token(other_user_id, LimitedAction::PublishNew, now, conn)?;
 
         assert_eq!(20, bucket.tokens);
         assert_eq!(10, other_bucket.tokens);
@@ -346,8 +419,8 @@ mod tests {
             ))
             .execute(conn)?;
 
-        let bucket = rate.take_token(user_id, now, conn)?;
-        let other_bucket = rate.take_token(other_user_id, now, conn)?;
+        let bucket = rate.take_token(user_id, LimitedAction::PublishNew, now, conn)?;
+        let other_bucket = rate.take_token(other_user_id, LimitedAction::PublishNew, now, conn)?;
 
         assert_eq!(20, bucket.tokens);
         assert_eq!(10, other_bucket.tokens);
@@ -358,8 +431,8 @@ mod tests {
             .filter(publish_rate_overrides::user_id.eq(user_id))
             .execute(conn)?;
 
-        let bucket = rate.take_token(user_id, now, conn)?;
-        let other_bucket = rate.take_token(other_user_id, now, conn)?;
+        let bucket = rate.take_token(user_id, LimitedAction::PublishNew, now, conn)?;
+        let other_bucket = rate.take_token(other_user_id, LimitedAction::PublishNew, now, conn)?;
 
         // The number of tokens of user_id is 10 and not 9 because when the new burst limit is
         // lower than the amount of available tokens, the number of available tokens is reset to
@@ -370,6 +443,31 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn actions_have_separate_buckets() -> QueryResult<()> {
+        let conn = &mut pg_connection();
+        let now = now();
+
+        let rate = PublishRateLimit {
+            rate: Duration::from_secs(1),
+            burst: 10,
+        };
+        let user_id = new_user_bucket(conn, 5, now)?.user_id;
+
+        let bucket = rate.take_token(user_id, LimitedAction::PublishUpdate, now, conn)?;
+        let expected = Bucket {
+            user_id,
+            tokens: 10,
+            last_refill: now,
+            action: LimitedAction::PublishUpdate as i16,
+        };
+        assert_eq!(expected, bucket);
+
+        let bucket = rate.take_token(user_id, LimitedAction::PublishNew, now, conn)?;
+        assert_eq!(4, bucket.tokens);
+        Ok(())
+    }
+
     fn new_user(conn: &mut PgConnection, gh_login: &str) -> QueryResult<i32> {
         use crate::models::NewUser;
 
@@ -391,6 +489,7 @@ mod tests {
                 user_id: new_user(conn, "new_user")?,
                 tokens,
                 last_refill: now,
+                action: LimitedAction::PublishNew as i16,
             })
             .get_result(conn)
     }
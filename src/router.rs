@@ -8,17 +8,59 @@ use crate::controllers::*;
 use crate::util::errors::not_found;
 use crate::Env;
 
-const MAX_PUBLISH_CONTENT_LENGTH: usize = 128 * 1024 * 1024; // 128 MB
+pub const MAX_PUBLISH_CONTENT_LENGTH: usize = 128 * 1024 * 1024; // 128 MB
 
 pub fn build_axum_router(state: AppState) -> Router {
     let mut router = Router::new()
         // Route used by both `cargo search` and the frontend
         .route("/api/v1/crates", get(krate::search::search))
+        // Suggestions for the search box of the frontend, outside of
+        // `/crates` so that they don't shadow a crate named `suggest`
+        .route("/api/v1/crate_suggestions", get(krate::suggest::suggest))
+        // Download counts of several crates at once, e.g. for dashboards,
+        // outside of `/crates` for the same reason
+        .route(
+            "/api/v1/crate_downloads",
+            get(krate::downloads::bulk_downloads),
+        )
         // Routes used by `cargo`
         .route(
             "/api/v1/crates/new",
             put(krate::publish::publish).layer(DefaultBodyLimit::max(MAX_PUBLISH_CONTENT_LENGTH)),
         )
+        // Publishes with a `multipart/form-data` body
+        .route(
+            "/api/v2/crates/new",
+            put(krate::publish_multipart::publish)
+                .layer(DefaultBodyLimit::max(MAX_PUBLISH_CONTENT_LENGTH)),
+        )
+        // Resumable uploads of publish requests for large crates
+        .route("/api/v1/crates/new/uploads", post(krate::upload::initiate))
+        .route(
+            "/api/v1/crates/new/uploads/:upload_id",
+            get(krate::upload::show)
+                .put(krate::upload::append)
+                .layer(DefaultBodyLimit::max(krate::upload::MAX_CHUNK_SIZE)),
+        )
+        .route(
+            "/api/v1/crates/new/uploads/:upload_id/complete",
+            post(krate::upload::complete),
+        )
+        // Staged publishes that only go live once they are promoted
+        .route(
+            "/api/v1/crates/new/staged",
+            get(krate::staged::list)
+                .put(krate::staged::stage)
+                .layer(DefaultBodyLimit::max(MAX_PUBLISH_CONTENT_LENGTH)),
+        )
+        .route(
+            "/api/v1/crates/new/staged/:staged_id",
+            delete(krate::staged::discard),
+        )
+        .route(
+            "/api/v1/crates/new/staged/:staged_id/promote",
+            post(krate::staged::promote),
+        )
         .route(
             "/api/v1/crates/:crate_id/owners",
             get(krate::owners::owners)
@@ -33,10 +75,18 @@ pub fn build_axum_router(state: AppState) -> Router {
             "/api/v1/crates/:crate_id/:version/unyank",
             put(version::yank::unyank),
         )
+        .route(
+            "/api/v1/crates/:crate_id/:version/deprecation",
+            put(version::deprecation::update),
+        )
         .route(
             "/api/v1/crates/:crate_id/:version/download",
             get(version::downloads::download),
         )
+        .route(
+            "/api/v1/crates/:crate_id/:version/signature",
+            get(version::signature::show),
+        )
         // Routes that appear to be unused
         .route("/api/v1/versions", get(version::deprecated::index))
         .route(
@@ -44,10 +94,13 @@ pub fn build_axum_router(state: AppState) -> Router {
             get(version::deprecated::show_by_id),
         )
         // Routes used by the frontend
-        .route("/api/v1/crates/:crate_id", get(krate::metadata::show))
+        .route(
+            "/api/v1/crates/:crate_id",
+            get(krate::metadata::show).delete(krate::delete::delete_crate),
+        )
         .route(
             "/api/v1/crates/:crate_id/:version",
-            get(version::metadata::show),
+            get(version::metadata::show).delete(krate::delete::delete_version),
         )
         .route(
             "/api/v1/crates/:crate_id/:version/readme",
@@ -69,10 +122,15 @@ pub fn build_axum_router(state: AppState) -> Router {
             "/api/v1/crates/:crate_id/downloads",
             get(krate::downloads::downloads),
         )
+        .route(
+            "/api/v1/crates/:crate_id/downloads/cargo_versions",
+            get(krate::downloads::cargo_versions),
+        )
         .route(
             "/api/v1/crates/:crate_id/versions",
             get(krate::metadata::versions),
         )
+        .route("/api/v1/crates/:crate_id/index", get(index::crate_index))
         .route(
             "/api/v1/crates/:crate_id/follow",
             put(krate::follow::follow).delete(krate::follow::unfollow),
@@ -93,6 +151,34 @@ pub fn build_axum_router(state: AppState) -> Router {
             "/api/v1/crates/:crate_id/reverse_dependencies",
             get(krate::metadata::reverse_dependencies),
         )
+        .route(
+            "/api/v1/crates/:crate_id/publish_2fa",
+            get(krate::publish_2fa::show).put(krate::publish_2fa::update),
+        )
+        .route(
+            "/api/v1/crates/:crate_id/deprecation",
+            put(krate::deprecation::update),
+        )
+        .route(
+            "/api/v1/crates/:crate_id/transfers",
+            get(crate_transfer::list).post(crate_transfer::create),
+        )
+        .route(
+            "/api/v1/crates/:crate_id/transfers/:transfer_id",
+            delete(crate_transfer::cancel),
+        )
+        .route(
+            "/api/v1/crates/:crate_id/webhooks",
+            get(krate::webhooks::list).post(krate::webhooks::create),
+        )
+        .route(
+            "/api/v1/crates/:crate_id/webhooks/:webhook_id",
+            delete(krate::webhooks::delete),
+        )
+        .route(
+            "/api/v1/crates/:crate_id/webhooks/:webhook_id/deliveries",
+            get(krate::webhooks::deliveries),
+        )
         .route("/api/v1/keywords", get(keyword::index))
         .route("/api/v1/keywords/:keyword_id", get(keyword::show))
         .route("/api/v1/categories", get(category::index))
@@ -104,11 +190,55 @@ pub fn build_axum_router(state: AppState) -> Router {
         )
         .route("/api/v1/users/:user_id/stats", get(user::other::stats))
         .route("/api/v1/teams/:team_id", get(team::show_team))
+        .route("/api/v1/organizations", post(organization::create))
+        .route(
+            "/api/v1/organizations/:name",
+            get(organization::show).delete(organization::delete),
+        )
+        .route(
+            "/api/v1/organizations/:name/members",
+            get(organization::members).put(organization::update_member),
+        )
+        .route(
+            "/api/v1/organizations/:name/members/:login",
+            delete(organization::remove_member),
+        )
         .route("/api/v1/me", get(user::me::me))
         .route("/api/v1/me/updates", get(user::me::updates))
         .route("/api/v1/me/tokens", get(token::list).put(token::new))
         .route("/api/v1/me/tokens/:id", delete(token::revoke))
         .route("/api/v1/tokens/current", delete(token::revoke_current))
+        .route(
+            "/api/v1/me/totp",
+            post(user::totp::enroll)
+                .put(user::totp::enable)
+                .delete(user::totp::disable),
+        )
+        .route(
+            "/api/v1/me/totp/recovery_codes",
+            post(user::totp::regenerate_recovery_codes),
+        )
+        .route(
+            "/api/v1/me/webauthn/registrations",
+            post(user::webauthn::begin_registration).put(user::webauthn::finish_registration),
+        )
+        .route("/api/v1/me/webauthn/credentials", get(user::webauthn::list))
+        .route(
+            "/api/v1/me/webauthn/credentials/:id",
+            delete(user::webauthn::delete),
+        )
+        .route(
+            "/api/v1/me/webauthn/assertions",
+            post(user::webauthn::begin_assertion),
+        )
+        .route(
+            "/api/v1/me/linked_accounts",
+            get(user::linked_accounts::list),
+        )
+        .route(
+            "/api/v1/me/linked_accounts/:provider/:account_id",
+            delete(user::linked_accounts::delete),
+        )
         .route(
             "/api/v1/me/crate_owner_invitations",
             get(crate_owner_invitation::list),
@@ -121,15 +251,77 @@ pub fn build_axum_router(state: AppState) -> Router {
             "/api/v1/me/crate_owner_invitations/accept/:token",
             put(crate_owner_invitation::handle_invite_with_token),
         )
+        .route(
+            "/api/v1/me/crate_transfers",
+            get(crate_transfer::list_pending),
+        )
+        .route(
+            "/api/v1/me/crate_transfers/:transfer_id",
+            put(crate_transfer::handle),
+        )
+        .route(
+            "/api/v1/me/emails",
+            get(user::emails::list).post(user::emails::create),
+        )
+        .route(
+            "/api/v1/me/emails/:id",
+            put(user::emails::update).delete(user::emails::delete),
+        )
+        .route("/api/v1/me/emails/:id/resend", put(user::emails::resend))
         .route(
             "/api/v1/me/email_notifications",
             put(user::me::update_email_notifications),
         )
+        .route(
+            "/api/v1/me/organization_invitations",
+            get(organization::list_invitations),
+        )
+        .route(
+            "/api/v1/me/organization_invitations/:organization_id",
+            put(organization::handle_invitation),
+        )
+        .route(
+            "/api/v1/me/follow_notifications",
+            get(user::follow_notifications::show).put(user::follow_notifications::update),
+        )
+        .route(
+            "/api/v1/me/notification_preferences",
+            get(user::notification_preferences::show).put(user::notification_preferences::update),
+        )
+        .route(
+            "/api/v1/me/sessions",
+            get(user::sessions::list).delete(user::sessions::revoke_others),
+        )
+        .route("/api/v1/me/sessions/:id", delete(user::sessions::revoke))
+        .route("/api/v1/me/security_log", get(user::security_log::list))
+        .route(
+            "/api/v1/me/saved_searches",
+            get(user::saved_searches::list).post(user::saved_searches::create),
+        )
+        .route(
+            "/api/v1/me/saved_searches/:id",
+            delete(user::saved_searches::delete),
+        )
+        .route(
+            "/api/v1/me/account_deletion",
+            post(user::account_deletion::request),
+        )
+        .route(
+            "/api/v1/me/account_deletion/:token",
+            put(user::account_deletion::confirm),
+        )
+        .route("/api/v1/index/squashes", get(index::squashes))
+        .route("/api/v1/index/changes", get(index::changes))
         .route("/api/v1/summary", get(krate::metadata::summary))
+        .route("/api/v1/summary/trending", get(krate::metadata::trending))
         .route(
             "/api/v1/confirm/:email_token",
             put(user::me::confirm_user_email),
         )
+        .route(
+            "/api/v1/revert_email_change/:token",
+            put(user::me::revert_email_change),
+        )
         .route(
             "/api/v1/users/:user_id/resend",
             put(user::me::regenerate_token_and_send),
@@ -144,24 +336,80 @@ pub fn build_axum_router(state: AppState) -> Router {
             "/api/private/session/authorize",
             get(user::session::authorize),
         )
+        .route(
+            "/api/private/session/totp",
+            post(user::session::confirm_totp),
+        )
+        .route(
+            "/api/private/session/webauthn",
+            post(user::session::begin_webauthn).put(user::session::confirm_webauthn),
+        )
+        .route(
+            "/api/private/session/sudo",
+            put(user::session::enter_sudo_mode),
+        )
         .route("/api/private/session", delete(user::session::logout))
         // Metrics
         .route("/api/private/metrics/:kind", get(metrics::prometheus))
+        .route(
+            "/api/private/docs_rs/crates/:crate_id/:version",
+            put(version::docs_rs::update_status),
+        )
+        // Admin actions of the crates.io team
+        .route(
+            "/api/private/admin/crates/:crate_id",
+            delete(admin::delete_crate),
+        )
+        .route(
+            "/api/private/admin/crates/:crate_id/upload_limit",
+            put(admin::set_upload_limit),
+        )
+        .route(
+            "/api/private/admin/reserved_crate_names/:name",
+            put(admin::reserve_crate_name).delete(admin::release_crate_name),
+        )
+        .route(
+            "/api/private/admin/users/:login/suspension",
+            put(admin::suspend_user).delete(admin::unsuspend_user),
+        )
+        .route(
+            "/api/private/admin/tokens/:token_id",
+            delete(admin::revoke_token),
+        )
+        .route(
+            "/api/private/admin/index_inconsistencies",
+            get(admin::list_index_inconsistencies),
+        )
+        .route(
+            "/api/private/admin/crate_file_checks",
+            get(admin::list_failed_crate_file_checks),
+        )
         // Crate ownership invitations management in the frontend
         .route(
             "/api/private/crate_owner_invitations",
             get(crate_owner_invitation::private_list),
         )
+        // The sparse HTTP index, rendered from the database
+        .route("/index/config.json", get(index::config_json))
+        .route("/index/*path", get(index::index_file))
         // Alerts from GitHub scanning for exposed API tokens
         .route(
             "/api/github/secret-scanning/verify",
             post(github::secret_scanning::verify),
         );
 
-    // Only serve the local checkout of the git index in development mode.
-    // In production, for crates.io, cargo gets the index from
-    // https://github.com/rust-lang/crates.io-index directly.
-    if state.config.env() == Env::Development {
+    // Self-hosted deployments can serve a clone of the git index read-only,
+    // unless the registry requires authentication, which cargo doesn't support
+    // for git indexes. Otherwise, only serve the local checkout of the git index
+    // in development mode. In production, for crates.io, cargo gets the index
+    // from https://github.com/rust-lang/crates.io-index directly.
+    if state.config.git_http_index_path.is_some() {
+        if !state.config.index_config.auth_required {
+            router = router
+                .route("/git/index/info/refs", get(git::info_refs))
+                .route("/git/index/git-upload-pack", post(git::upload_pack));
+        }
+    } else if state.config.env() == Env::Development {
         router = router.route(
             "/git/index/*path",
             get(git::http_backend).post(git::http_backend),
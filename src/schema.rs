@@ -17,6 +17,146 @@ pub mod sql_types {
     pub use diesel_full_text_search::Tsvector;
 }
 
+diesel::table! {
+    /// Representation of the `account_deletions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    account_deletions (user_id) {
+        /// The `user_id` column of the `account_deletions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `token` column of the `account_deletions` table.
+        ///
+        /// Its SQL type is `Bytea`.
+        ///
+        /// (Automatically generated by Diesel.)
+        token -> Bytea,
+        /// The `requested_at` column of the `account_deletions` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        requested_at -> Timestamp,
+        /// The `confirmed_at` column of the `account_deletions` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        confirmed_at -> Nullable<Timestamp>,
+        /// The `completed_at` column of the `account_deletions` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        completed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `admin_actions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    admin_actions (id) {
+        /// The `id` column of the `admin_actions` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int8,
+        /// The `user_id` column of the `admin_actions` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Nullable<Int4>,
+        /// The `permission` column of the `admin_actions` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        permission -> Varchar,
+        /// The `target` column of the `admin_actions` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        target -> Varchar,
+        /// The `reason` column of the `admin_actions` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        reason -> Nullable<Varchar>,
+        /// The `created_at` column of the `admin_actions` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `admin_role_assignments` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    admin_role_assignments (user_id, role_id) {
+        /// The `user_id` column of the `admin_role_assignments` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `role_id` column of the `admin_role_assignments` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        role_id -> Int4,
+        /// The `created_at` column of the `admin_role_assignments` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `admin_roles` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    admin_roles (id) {
+        /// The `id` column of the `admin_roles` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `name` column of the `admin_roles` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        name -> Varchar,
+        /// The `permissions` column of the `admin_roles` table.
+        ///
+        /// Its SQL type is `Array<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        permissions -> Array<Text>,
+        /// The `created_at` column of the `admin_roles` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     /// Representation of the `api_tokens` table.
     ///
@@ -76,6 +216,12 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         endpoint_scopes -> Nullable<Array<Text>>,
+        /// The `organization_id` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        organization_id -> Nullable<Int4>,
     }
 }
 
@@ -120,6 +266,12 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         created_at -> Timestamp,
+        /// The `priority` column of the `background_jobs` table.
+        ///
+        /// Its SQL type is `Int2`.
+        ///
+        /// (Automatically generated by Diesel.)
+        priority -> Int2,
     }
 }
 
@@ -196,6 +348,172 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    /// Representation of the `cdn_invalidations` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    cdn_invalidations (id) {
+        /// The `id` column of the `cdn_invalidations` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int8,
+        /// The `path` column of the `cdn_invalidations` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        path -> Varchar,
+        /// The `created_at` column of the `cdn_invalidations` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `crate_deletions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    crate_deletions (id) {
+        /// The `id` column of the `crate_deletions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `crate_name` column of the `crate_deletions` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        crate_name -> Varchar,
+        /// The `version` column of the `crate_deletions` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version -> Nullable<Varchar>,
+        /// The `deleted_by` column of the `crate_deletions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        deleted_by -> Int4,
+        /// The `published_at` column of the `crate_deletions` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        published_at -> Timestamp,
+        /// The `deleted_at` column of the `crate_deletions` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        deleted_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `crate_download_trends` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    crate_download_trends (crate_id, window_days) {
+        /// The `crate_id` column of the `crate_download_trends` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        crate_id -> Int4,
+        /// The `window_days` column of the `crate_download_trends` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        window_days -> Int4,
+        /// The `downloads` column of the `crate_download_trends` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        downloads -> Int8,
+        /// The `previous_downloads` column of the `crate_download_trends` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        previous_downloads -> Int8,
+        /// The `computed_at` column of the `crate_download_trends` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        computed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `crate_file_checks` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    crate_file_checks (version_id) {
+        /// The `version_id` column of the `crate_file_checks` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version_id -> Int4,
+        /// The `checked_at` column of the `crate_file_checks` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        checked_at -> Timestamp,
+        /// The `missing` column of the `crate_file_checks` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        missing -> Bool,
+        /// The `checksum_mismatch` column of the `crate_file_checks` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        checksum_mismatch -> Bool,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `crate_files` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    crate_files (version_id) {
+        /// The `version_id` column of the `crate_files` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version_id -> Int4,
+        /// The `object_key` column of the `crate_files` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        object_key -> Varchar,
+        /// The `created_at` column of the `crate_files` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     /// Representation of the `crate_owner_invitations` table.
     ///
@@ -237,6 +555,12 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         token_generated_at -> Nullable<Timestamp>,
+        /// The `reminder_sent_at` column of the `crate_owner_invitations` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        reminder_sent_at -> Nullable<Timestamp>,
     }
 }
 
@@ -297,32 +621,94 @@ diesel::table! {
 }
 
 diesel::table! {
-    use diesel::sql_types::*;
-    use super::sql_types::Tsvector;
-
-    /// Representation of the `crates` table.
+    /// Representation of the `crate_transfers` table.
     ///
     /// (Automatically generated by Diesel.)
-    crates (id) {
-        /// The `id` column of the `crates` table.
+    crate_transfers (id) {
+        /// The `id` column of the `crate_transfers` table.
         ///
         /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
         id -> Int4,
-        /// The `name` column of the `crates` table.
+        /// The `crate_id` column of the `crate_transfers` table.
         ///
-        /// Its SQL type is `Varchar`.
+        /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
-        name -> Varchar,
-        /// The `updated_at` column of the `crates` table.
+        crate_id -> Int4,
+        /// The `from_user_id` column of the `crate_transfers` table.
         ///
-        /// Its SQL type is `Timestamp`.
+        /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
-        updated_at -> Timestamp,
-        /// The `created_at` column of the `crates` table.
+        from_user_id -> Int4,
+        /// The `to_user_id` column of the `crate_transfers` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        to_user_id -> Int4,
+        /// The `created_at` column of the `crate_transfers` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `accepted_at` column of the `crate_transfers` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        accepted_at -> Nullable<Timestamp>,
+        /// The `completes_at` column of the `crate_transfers` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        completes_at -> Nullable<Timestamp>,
+        /// The `completed_at` column of the `crate_transfers` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        completed_at -> Nullable<Timestamp>,
+        /// The `cancelled_at` column of the `crate_transfers` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        cancelled_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Tsvector;
+
+    /// Representation of the `crates` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    crates (id) {
+        /// The `id` column of the `crates` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `name` column of the `crates` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        name -> Varchar,
+        /// The `updated_at` column of the `crates` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamp,
+        /// The `created_at` column of the `crates` table.
         ///
         /// Its SQL type is `Timestamp`.
         ///
@@ -376,6 +762,30 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         max_upload_size -> Nullable<Int4>,
+        /// The `publish_requires_2fa` column of the `crates` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        publish_requires_2fa -> Bool,
+        /// The `deprecated` column of the `crates` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        deprecated -> Bool,
+        /// The `deprecation_message` column of the `crates` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        deprecation_message -> Nullable<Varchar>,
+        /// The `deprecation_successor` column of the `crates` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        deprecation_successor -> Nullable<Varchar>,
     }
 }
 
@@ -487,6 +897,56 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    /// Representation of the `email_changes` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    email_changes (id) {
+        /// The `id` column of the `email_changes` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `user_id` column of the `email_changes` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `old_email` column of the `email_changes` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        old_email -> Varchar,
+        /// The `new_email` column of the `email_changes` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        new_email -> Varchar,
+        /// The `revert_token` column of the `email_changes` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        revert_token -> Text,
+        /// The `created_at` column of the `email_changes` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `reverted_at` column of the `email_changes` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        reverted_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     /// Representation of the `emails` table.
     ///
@@ -528,220 +988,1152 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         token_generated_at -> Nullable<Timestamp>,
+        /// The `send_notifications` column of the `emails` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        send_notifications -> Bool,
+        /// The `public` column of the `emails` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        public -> Bool,
     }
 }
 
 diesel::table! {
-    /// Representation of the `follows` table.
+    /// Representation of the `file_replications` table.
     ///
     /// (Automatically generated by Diesel.)
-    follows (user_id, crate_id) {
-        /// The `user_id` column of the `follows` table.
+    file_replications (path, replica) {
+        /// The `path` column of the `file_replications` table.
         ///
-        /// Its SQL type is `Int4`.
+        /// Its SQL type is `Varchar`.
         ///
         /// (Automatically generated by Diesel.)
-        user_id -> Int4,
-        /// The `crate_id` column of the `follows` table.
+        path -> Varchar,
+        /// The `replica` column of the `file_replications` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        replica -> Varchar,
+        /// The `created_at` column of the `file_replications` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `replicated_at` column of the `file_replications` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        replicated_at -> Nullable<Timestamp>,
+        /// The `attempts` column of the `file_replications` table.
         ///
         /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
-        crate_id -> Int4,
+        attempts -> Int4,
+        /// The `last_error` column of the `file_replications` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        last_error -> Nullable<Varchar>,
     }
 }
 
 diesel::table! {
-    /// Representation of the `keywords` table.
+    /// Representation of the `follow_notification_settings` table.
     ///
     /// (Automatically generated by Diesel.)
-    keywords (id) {
-        /// The `id` column of the `keywords` table.
+    follow_notification_settings (user_id) {
+        /// The `user_id` column of the `follow_notification_settings` table.
         ///
         /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
-        id -> Int4,
-        /// The `keyword` column of the `keywords` table.
+        user_id -> Int4,
+        /// The `frequency` column of the `follow_notification_settings` table.
         ///
-        /// Its SQL type is `Text`.
+        /// Its SQL type is `Int2`.
         ///
         /// (Automatically generated by Diesel.)
-        keyword -> Text,
-        /// The `crates_cnt` column of the `keywords` table.
+        frequency -> Int2,
+        /// The `webhook_url` column of the `follow_notification_settings` table.
         ///
-        /// Its SQL type is `Int4`.
+        /// Its SQL type is `Nullable<Varchar>`.
         ///
         /// (Automatically generated by Diesel.)
-        crates_cnt -> Int4,
-        /// The `created_at` column of the `keywords` table.
+        webhook_url -> Nullable<Varchar>,
+        /// The `last_notified_at` column of the `follow_notification_settings` table.
         ///
         /// Its SQL type is `Timestamp`.
         ///
         /// (Automatically generated by Diesel.)
-        created_at -> Timestamp,
-    }
-}
-
-diesel::table! {
-    /// Representation of the `metadata` table.
-    ///
-    /// (Automatically generated by Diesel.)
-    metadata (total_downloads) {
-        /// The `total_downloads` column of the `metadata` table.
+        last_notified_at -> Timestamp,
+        /// The `webhook_secret` column of the `follow_notification_settings` table.
         ///
-        /// Its SQL type is `Int8`.
+        /// Its SQL type is `Nullable<Varchar>`.
         ///
         /// (Automatically generated by Diesel.)
-        total_downloads -> Int8,
+        webhook_secret -> Nullable<Varchar>,
+        /// The `notified_until` column of the `follow_notification_settings` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        notified_until -> Timestamp,
     }
 }
 
 diesel::table! {
-    /// Representation of the `publish_limit_buckets` table.
+    /// Representation of the `follows` table.
     ///
     /// (Automatically generated by Diesel.)
-    publish_limit_buckets (user_id) {
-        /// The `user_id` column of the `publish_limit_buckets` table.
+    follows (user_id, crate_id) {
+        /// The `user_id` column of the `follows` table.
         ///
         /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
         user_id -> Int4,
-        /// The `tokens` column of the `publish_limit_buckets` table.
+        /// The `crate_id` column of the `follows` table.
         ///
         /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
-        tokens -> Int4,
-        /// The `last_refill` column of the `publish_limit_buckets` table.
-        ///
-        /// Its SQL type is `Timestamp`.
-        ///
-        /// (Automatically generated by Diesel.)
-        last_refill -> Timestamp,
+        crate_id -> Int4,
     }
 }
 
 diesel::table! {
-    /// Representation of the `publish_rate_overrides` table.
+    /// Representation of the `index_changes` table.
     ///
     /// (Automatically generated by Diesel.)
-    publish_rate_overrides (user_id) {
-        /// The `user_id` column of the `publish_rate_overrides` table.
+    index_changes (id) {
+        /// The `id` column of the `index_changes` table.
         ///
-        /// Its SQL type is `Int4`.
+        /// Its SQL type is `Int8`.
         ///
         /// (Automatically generated by Diesel.)
-        user_id -> Int4,
-        /// The `burst` column of the `publish_rate_overrides` table.
+        id -> Int8,
+        /// The `crate_name` column of the `index_changes` table.
         ///
-        /// Its SQL type is `Int4`.
+        /// Its SQL type is `Varchar`.
         ///
         /// (Automatically generated by Diesel.)
-        burst -> Int4,
-        /// The `expires_at` column of the `publish_rate_overrides` table.
+        crate_name -> Varchar,
+        /// The `version` column of the `index_changes` table.
         ///
-        /// Its SQL type is `Nullable<Timestamp>`.
+        /// Its SQL type is `Nullable<Varchar>`.
         ///
         /// (Automatically generated by Diesel.)
-        expires_at -> Nullable<Timestamp>,
+        version -> Nullable<Varchar>,
+        /// The `action` column of the `index_changes` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        action -> Varchar,
+        /// The `created_at` column of the `index_changes` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
     }
 }
 
 diesel::table! {
-    /// Representation of the `readme_renderings` table.
+    /// Representation of the `index_inconsistencies` table.
     ///
     /// (Automatically generated by Diesel.)
-    readme_renderings (version_id) {
-        /// The `version_id` column of the `readme_renderings` table.
+    index_inconsistencies (crate_id) {
+        /// The `crate_id` column of the `index_inconsistencies` table.
         ///
         /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
-        version_id -> Int4,
-        /// The `rendered_at` column of the `readme_renderings` table.
+        crate_id -> Int4,
+        /// The `git_mismatch` column of the `index_inconsistencies` table.
         ///
-        /// Its SQL type is `Timestamp`.
+        /// Its SQL type is `Bool`.
         ///
         /// (Automatically generated by Diesel.)
-        rendered_at -> Timestamp,
-    }
-}
-
-diesel::table! {
-    /// Representation of the `recent_crate_downloads` view.
-    ///
-    /// This data represents the downloads in the last 90 days.
-    /// This view does not contain realtime data.
-    /// It is refreshed by the `update-downloads` script.
-    recent_crate_downloads (crate_id) {
-        /// The `crate_id` column of the `recent_crate_downloads` view.
+        git_mismatch -> Bool,
+        /// The `sparse_mismatch` column of the `index_inconsistencies` table.
         ///
-        /// Its SQL type is `Integer`.
-        crate_id -> Integer,
-        /// The `downloads` column of the `recent_crate_downloads` table.
+        /// Its SQL type is `Bool`.
         ///
-        /// Its SQL type is `BigInt`.
-        downloads -> BigInt,
-    }
-}
-
-diesel::table! {
-    /// Representation of the `reserved_crate_names` table.
-    ///
-    /// (Automatically generated by Diesel.)
-    reserved_crate_names (name) {
-        /// The `name` column of the `reserved_crate_names` table.
+        /// (Automatically generated by Diesel.)
+        sparse_mismatch -> Bool,
+        /// The `detected_at` column of the `index_inconsistencies` table.
         ///
-        /// Its SQL type is `Text`.
+        /// Its SQL type is `Timestamp`.
         ///
         /// (Automatically generated by Diesel.)
-        name -> Text,
+        detected_at -> Timestamp,
     }
 }
 
 diesel::table! {
-    /// Representation of the `teams` table.
+    /// Representation of the `index_squashes` table.
     ///
     /// (Automatically generated by Diesel.)
-    teams (id) {
-        /// The `id` column of the `teams` table.
+    index_squashes (id) {
+        /// The `id` column of the `index_squashes` table.
         ///
         /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
         id -> Int4,
-        /// The `login` column of the `teams` table.
+        /// The `previous_head` column of the `index_squashes` table.
         ///
         /// Its SQL type is `Varchar`.
         ///
         /// (Automatically generated by Diesel.)
-        login -> Varchar,
-        /// The `github_id` column of the `teams` table.
+        previous_head -> Varchar,
+        /// The `snapshot_branch` column of the `index_squashes` table.
         ///
-        /// Its SQL type is `Int4`.
+        /// Its SQL type is `Varchar`.
         ///
         /// (Automatically generated by Diesel.)
-        github_id -> Int4,
-        /// The `name` column of the `teams` table.
+        snapshot_branch -> Varchar,
+        /// The `squashed_at` column of the `index_squashes` table.
         ///
-        /// Its SQL type is `Nullable<Varchar>`.
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        squashed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `keywords` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    keywords (id) {
+        /// The `id` column of the `keywords` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `keyword` column of the `keywords` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        keyword -> Text,
+        /// The `crates_cnt` column of the `keywords` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        crates_cnt -> Int4,
+        /// The `created_at` column of the `keywords` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `linked_accounts` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    linked_accounts (provider, account_id) {
+        /// The `user_id` column of the `linked_accounts` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `provider` column of the `linked_accounts` table.
+        ///
+        /// Its SQL type is `Int2`.
+        ///
+        /// (Automatically generated by Diesel.)
+        provider -> Int2,
+        /// The `account_id` column of the `linked_accounts` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        account_id -> Int4,
+        /// The `login` column of the `linked_accounts` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        login -> Varchar,
+        /// The `avatar` column of the `linked_accounts` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        avatar -> Nullable<Varchar>,
+        /// The `access_token` column of the `linked_accounts` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        access_token -> Varchar,
+        /// The `created_at` column of the `linked_accounts` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `metadata` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    metadata (total_downloads) {
+        /// The `total_downloads` column of the `metadata` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        total_downloads -> Int8,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `notification_preferences` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    notification_preferences (user_id) {
+        /// The `user_id` column of the `notification_preferences` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `owner_invites` column of the `notification_preferences` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        owner_invites -> Bool,
+        /// The `security_alerts` column of the `notification_preferences` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        security_alerts -> Bool,
+        /// The `digests` column of the `notification_preferences` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        digests -> Bool,
+        /// The `updated_at` column of the `notification_preferences` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `organization_invitations` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    organization_invitations (organization_id, invited_user_id) {
+        /// The `organization_id` column of the `organization_invitations` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        organization_id -> Int4,
+
+        /// The `invited_user_id` column of the `organization_invitations` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        invited_user_id -> Int4,
+
+        /// The `invited_by_user_id` column of the `organization_invitations` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        invited_by_user_id -> Int4,
+
+        /// The `role` column of the `organization_invitations` table.
+        ///
+        /// Its SQL type is `Int2`.
+        ///
+        /// (Automatically generated by Diesel.)
+        role -> Int2,
+
+        /// The `created_at` column of the `organization_invitations` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `organization_members` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    organization_members (organization_id, user_id) {
+        /// The `organization_id` column of the `organization_members` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        organization_id -> Int4,
+
+        /// The `user_id` column of the `organization_members` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+
+        /// The `role` column of the `organization_members` table.
+        ///
+        /// Its SQL type is `Int2`.
+        ///
+        /// (Automatically generated by Diesel.)
+        role -> Int2,
+
+        /// The `created_at` column of the `organization_members` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `organizations` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    organizations (id) {
+        /// The `id` column of the `organizations` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+
+        /// The `name` column of the `organizations` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        name -> Varchar,
+
+        /// The `description` column of the `organizations` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        description -> Nullable<Varchar>,
+
+        /// The `created_at` column of the `organizations` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `persistent_sessions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    persistent_sessions (id) {
+        /// The `id` column of the `persistent_sessions` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int8,
+        /// The `user_id` column of the `persistent_sessions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `created_at` column of the `persistent_sessions` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `last_used_at` column of the `persistent_sessions` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        last_used_at -> Timestamp,
+        /// The `revoked` column of the `persistent_sessions` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        revoked -> Bool,
+        /// The `last_ip_address` column of the `persistent_sessions` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        last_ip_address -> Nullable<Varchar>,
+        /// The `last_user_agent` column of the `persistent_sessions` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        last_user_agent -> Nullable<Varchar>,
+        /// The `token` column of the `persistent_sessions` table.
+        ///
+        /// Its SQL type is `Bytea`.
+        ///
+        /// (Automatically generated by Diesel.)
+        token -> Bytea,
+        /// The `sudo_until` column of the `persistent_sessions` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        sudo_until -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `processed_cdn_log_files` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    processed_cdn_log_files (path) {
+        /// The `path` column of the `processed_cdn_log_files` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        path -> Varchar,
+        /// The `downloads` column of the `processed_cdn_log_files` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        downloads -> Int4,
+        /// The `processed_at` column of the `processed_cdn_log_files` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        processed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `publish_limit_buckets` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    publish_limit_buckets (user_id, action) {
+        /// The `user_id` column of the `publish_limit_buckets` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `tokens` column of the `publish_limit_buckets` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        tokens -> Int4,
+        /// The `last_refill` column of the `publish_limit_buckets` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        last_refill -> Timestamp,
+        /// The `action` column of the `publish_limit_buckets` table.
+        ///
+        /// Its SQL type is `Int2`.
+        ///
+        /// (Automatically generated by Diesel.)
+        action -> Int2,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `publish_rate_overrides` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    publish_rate_overrides (user_id, action) {
+        /// The `user_id` column of the `publish_rate_overrides` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `burst` column of the `publish_rate_overrides` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        burst -> Int4,
+        /// The `expires_at` column of the `publish_rate_overrides` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        expires_at -> Nullable<Timestamp>,
+        /// The `action` column of the `publish_rate_overrides` table.
+        ///
+        /// Its SQL type is `Int2`.
+        ///
+        /// (Automatically generated by Diesel.)
+        action -> Int2,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `publish_upload_parts` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    publish_upload_parts (upload_id, part_number) {
+        /// The `upload_id` column of the `publish_upload_parts` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        upload_id -> Int4,
+        /// The `part_number` column of the `publish_upload_parts` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        part_number -> Int4,
+        /// The `position` column of the `publish_upload_parts` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        position -> Int8,
+        /// The `tag` column of the `publish_upload_parts` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        tag -> Varchar,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `publish_uploads` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    publish_uploads (id) {
+        /// The `id` column of the `publish_uploads` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `user_id` column of the `publish_uploads` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `crate_name` column of the `publish_uploads` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        crate_name -> Varchar,
+        /// The `size` column of the `publish_uploads` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        size -> Int8,
+        /// The `received` column of the `publish_uploads` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        received -> Int8,
+        /// The `cksum` column of the `publish_uploads` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        cksum -> Varchar,
+        /// The `created_at` column of the `publish_uploads` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `storage_path` column of the `publish_uploads` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        storage_path -> Varchar,
+        /// The `storage_upload_id` column of the `publish_uploads` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        storage_upload_id -> Varchar,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `readme_renderings` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    readme_renderings (version_id) {
+        /// The `version_id` column of the `readme_renderings` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version_id -> Int4,
+        /// The `rendered_at` column of the `readme_renderings` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        rendered_at -> Timestamp,
+        /// The `renderer_version` column of the `readme_renderings` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        renderer_version -> Int4,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `recent_crate_downloads` view.
+    ///
+    /// This data represents the downloads in the last 90 days.
+    /// This view does not contain realtime data.
+    /// It is refreshed by the `update-downloads` script.
+    recent_crate_downloads (crate_id) {
+        /// The `crate_id` column of the `recent_crate_downloads` view.
+        ///
+        /// Its SQL type is `Integer`.
+        crate_id -> Integer,
+        /// The `downloads` column of the `recent_crate_downloads` table.
+        ///
+        /// Its SQL type is `BigInt`.
+        downloads -> BigInt,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `reserved_crate_names` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    reserved_crate_names (name) {
+        /// The `name` column of the `reserved_crate_names` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `reserved_crate_prefixes` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    reserved_crate_prefixes (prefix) {
+        /// The `prefix` column of the `reserved_crate_prefixes` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        prefix -> Varchar,
+        /// The `team_id` column of the `reserved_crate_prefixes` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        team_id -> Int4,
+        /// The `created_at` column of the `reserved_crate_prefixes` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `reverse_dependency_counts` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    reverse_dependency_counts (crate_id) {
+        /// The `crate_id` column of the `reverse_dependency_counts` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        crate_id -> Int4,
+        /// The `dependents` column of the `reverse_dependency_counts` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        dependents -> Int4,
+        /// The `refreshed_at` column of the `reverse_dependency_counts` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        refreshed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `saved_searches` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    saved_searches (id) {
+        /// The `id` column of the `saved_searches` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `user_id` column of the `saved_searches` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `query` column of the `saved_searches` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        query -> Varchar,
+        /// The `webhook_url` column of the `saved_searches` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        webhook_url -> Nullable<Varchar>,
+        /// The `last_version_id` column of the `saved_searches` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        last_version_id -> Int4,
+        /// The `created_at` column of the `saved_searches` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `security_log_entries` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    security_log_entries (id) {
+        /// The `id` column of the `security_log_entries` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int8,
+        /// The `user_id` column of the `security_log_entries` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `event` column of the `security_log_entries` table.
+        ///
+        /// Its SQL type is `Int2`.
+        ///
+        /// (Automatically generated by Diesel.)
+        event -> Int2,
+        /// The `details` column of the `security_log_entries` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        details -> Nullable<Varchar>,
+        /// The `ip_address` column of the `security_log_entries` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        ip_address -> Nullable<Varchar>,
+        /// The `user_agent` column of the `security_log_entries` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_agent -> Nullable<Varchar>,
+        /// The `created_at` column of the `security_log_entries` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `sso_identities` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    sso_identities (subject) {
+        /// The `subject` column of the `sso_identities` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        subject -> Varchar,
+        /// The `user_id` column of the `sso_identities` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `created_at` column of the `sso_identities` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `staged_publishes` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    staged_publishes (id) {
+        /// The `id` column of the `staged_publishes` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `user_id` column of the `staged_publishes` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `crate_name` column of the `staged_publishes` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        crate_name -> Varchar,
+        /// The `version` column of the `staged_publishes` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version -> Varchar,
+        /// The `body` column of the `staged_publishes` table.
+        ///
+        /// Its SQL type is `Bytea`.
+        ///
+        /// (Automatically generated by Diesel.)
+        body -> Bytea,
+        /// The `created_at` column of the `staged_publishes` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `team_members` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    team_members (team_id, user_id) {
+        /// The `team_id` column of the `team_members` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        team_id -> Int4,
+        /// The `user_id` column of the `team_members` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `verified_at` column of the `team_members` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        verified_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `teams` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    teams (id) {
+        /// The `id` column of the `teams` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `login` column of the `teams` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        login -> Varchar,
+        /// The `github_id` column of the `teams` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        github_id -> Int4,
+        /// The `name` column of the `teams` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
         ///
         /// (Automatically generated by Diesel.)
         name -> Nullable<Varchar>,
         /// The `avatar` column of the `teams` table.
         ///
-        /// Its SQL type is `Nullable<Varchar>`.
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        avatar -> Nullable<Varchar>,
+        /// The `org_id` column of the `teams` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        org_id -> Nullable<Int4>,
+        /// The `provider` column of the `teams` table.
+        ///
+        /// Its SQL type is `Int2`.
+        ///
+        /// (Automatically generated by Diesel.)
+        provider -> Int2,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `totp_credentials` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    totp_credentials (user_id) {
+        /// The `user_id` column of the `totp_credentials` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `secret` column of the `totp_credentials` table.
+        ///
+        /// Its SQL type is `Bytea`.
+        ///
+        /// (Automatically generated by Diesel.)
+        secret -> Bytea,
+        /// The `enabled` column of the `totp_credentials` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        enabled -> Bool,
+        /// The `last_used_step` column of the `totp_credentials` table.
+        ///
+        /// Its SQL type is `Nullable<Int8>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        last_used_step -> Nullable<Int8>,
+        /// The `failed_attempts` column of the `totp_credentials` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        failed_attempts -> Int4,
+        /// The `locked_until` column of the `totp_credentials` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        locked_until -> Nullable<Timestamp>,
+        /// The `created_at` column of the `totp_credentials` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `totp_recovery_codes` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    totp_recovery_codes (id) {
+        /// The `id` column of the `totp_recovery_codes` table.
+        ///
+        /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
-        avatar -> Nullable<Varchar>,
-        /// The `org_id` column of the `teams` table.
+        id -> Int4,
+        /// The `user_id` column of the `totp_recovery_codes` table.
         ///
-        /// Its SQL type is `Nullable<Int4>`.
+        /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
-        org_id -> Nullable<Int4>,
+        user_id -> Int4,
+        /// The `code` column of the `totp_recovery_codes` table.
+        ///
+        /// Its SQL type is `Bytea`.
+        ///
+        /// (Automatically generated by Diesel.)
+        code -> Bytea,
+        /// The `created_at` column of the `totp_recovery_codes` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
     }
 }
 
@@ -798,6 +2190,44 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         account_lock_until -> Nullable<Timestamp>,
+        /// The `account_suspended` column of the `users` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        account_suspended -> Bool,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `version_cargo_downloads` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    version_cargo_downloads (version_id, date, cargo_version) {
+        /// The `version_id` column of the `version_cargo_downloads` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version_id -> Int4,
+        /// The `date` column of the `version_cargo_downloads` table.
+        ///
+        /// Its SQL type is `Date`.
+        ///
+        /// (Automatically generated by Diesel.)
+        date -> Date,
+        /// The `cargo_version` column of the `version_cargo_downloads` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        cargo_version -> Varchar,
+        /// The `downloads` column of the `version_cargo_downloads` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        downloads -> Int4,
     }
 }
 
@@ -836,6 +2266,12 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         processed -> Bool,
+        /// The `unique_clients` column of the `version_downloads` table.
+        ///
+        /// Its SQL type is `Nullable<Bytea>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        unique_clients -> Nullable<Bytea>,
     }
 }
 
@@ -883,6 +2319,38 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    /// Representation of the `version_signatures` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    version_signatures (version_id) {
+        /// The `version_id` column of the `version_signatures` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        version_id -> Int4,
+        /// The `kind` column of the `version_signatures` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        kind -> Varchar,
+        /// The `size` column of the `version_signatures` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        size -> Int4,
+        /// The `created_at` column of the `version_signatures` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     /// Representation of the `versions` table.
     ///
@@ -966,6 +2434,48 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         links -> Nullable<Varchar>,
+        /// The `yank_reason` column of the `versions` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        yank_reason -> Nullable<Varchar>,
+        /// The `yank_replacement` column of the `versions` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        yank_replacement -> Nullable<Varchar>,
+        /// The `docs_rs_status` column of the `versions` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        docs_rs_status -> Nullable<Varchar>,
+        /// The `docs_rs_updated_at` column of the `versions` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        docs_rs_updated_at -> Nullable<Timestamp>,
+        /// The `deprecated` column of the `versions` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        deprecated -> Bool,
+        /// The `deprecation_message` column of the `versions` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        deprecation_message -> Nullable<Varchar>,
+        /// The `rust_version` column of the `versions` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        rust_version -> Nullable<Varchar>,
     }
 }
 
@@ -989,57 +2499,329 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    /// Representation of the `webauthn_challenges` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    webauthn_challenges (user_id, ceremony) {
+        /// The `user_id` column of the `webauthn_challenges` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `ceremony` column of the `webauthn_challenges` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        ceremony -> Varchar,
+        /// The `created_at` column of the `webauthn_challenges` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `state` column of the `webauthn_challenges` table.
+        ///
+        /// Its SQL type is `Jsonb`.
+        ///
+        /// (Automatically generated by Diesel.)
+        state -> Jsonb,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `webauthn_credentials` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    webauthn_credentials (id) {
+        /// The `id` column of the `webauthn_credentials` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `user_id` column of the `webauthn_credentials` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `credential_id` column of the `webauthn_credentials` table.
+        ///
+        /// Its SQL type is `Bytea`.
+        ///
+        /// (Automatically generated by Diesel.)
+        credential_id -> Bytea,
+        /// The `sign_count` column of the `webauthn_credentials` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        sign_count -> Int8,
+        /// The `name` column of the `webauthn_credentials` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        name -> Varchar,
+        /// The `created_at` column of the `webauthn_credentials` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `last_used_at` column of the `webauthn_credentials` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        last_used_at -> Nullable<Timestamp>,
+        /// The `security_key` column of the `webauthn_credentials` table.
+        ///
+        /// Its SQL type is `Jsonb`.
+        ///
+        /// (Automatically generated by Diesel.)
+        security_key -> Jsonb,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `webhook_deliveries` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    webhook_deliveries (id) {
+        /// The `id` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `webhook_id` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        webhook_id -> Int4,
+        /// The `event` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        event -> Varchar,
+        /// The `payload` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Jsonb`.
+        ///
+        /// (Automatically generated by Diesel.)
+        payload -> Jsonb,
+        /// The `attempts` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        attempts -> Int4,
+        /// The `response_status` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        response_status -> Nullable<Int4>,
+        /// The `error` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Nullable<Varchar>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        error -> Nullable<Varchar>,
+        /// The `delivered_at` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        delivered_at -> Nullable<Timestamp>,
+        /// The `created_at` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `webhooks` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    webhooks (id) {
+        /// The `id` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `crate_id` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        crate_id -> Int4,
+        /// The `url` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Varchar`.
+        ///
+        /// (Automatically generated by Diesel.)
+        url -> Varchar,
+        /// The `secret` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Bytea`.
+        ///
+        /// (Automatically generated by Diesel.)
+        secret -> Bytea,
+        /// The `created_by` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_by -> Int4,
+        /// The `created_at` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(account_deletions -> users (user_id));
+diesel::joinable!(admin_actions -> users (user_id));
+diesel::joinable!(admin_role_assignments -> admin_roles (role_id));
+diesel::joinable!(admin_role_assignments -> users (user_id));
+diesel::joinable!(api_tokens -> organizations (organization_id));
 diesel::joinable!(api_tokens -> users (user_id));
 diesel::joinable!(badges -> crates (crate_id));
+diesel::joinable!(crate_deletions -> users (deleted_by));
+diesel::joinable!(crate_download_trends -> crates (crate_id));
+diesel::joinable!(crate_file_checks -> versions (version_id));
+diesel::joinable!(crate_files -> versions (version_id));
 diesel::joinable!(crate_owner_invitations -> crates (crate_id));
 diesel::joinable!(crate_owners -> crates (crate_id));
 diesel::joinable!(crate_owners -> teams (owner_id));
 diesel::joinable!(crate_owners -> users (owner_id));
+diesel::joinable!(crate_transfers -> crates (crate_id));
 diesel::joinable!(crates_categories -> categories (category_id));
 diesel::joinable!(crates_categories -> crates (crate_id));
 diesel::joinable!(crates_keywords -> crates (crate_id));
 diesel::joinable!(crates_keywords -> keywords (keyword_id));
 diesel::joinable!(dependencies -> crates (crate_id));
 diesel::joinable!(dependencies -> versions (version_id));
+diesel::joinable!(email_changes -> users (user_id));
 diesel::joinable!(emails -> users (user_id));
+diesel::joinable!(follow_notification_settings -> users (user_id));
 diesel::joinable!(follows -> crates (crate_id));
 diesel::joinable!(follows -> users (user_id));
+diesel::joinable!(index_inconsistencies -> crates (crate_id));
+diesel::joinable!(linked_accounts -> users (user_id));
+diesel::joinable!(notification_preferences -> users (user_id));
+diesel::joinable!(organization_invitations -> organizations (organization_id));
+diesel::joinable!(organization_members -> organizations (organization_id));
+diesel::joinable!(organization_members -> users (user_id));
+diesel::joinable!(persistent_sessions -> users (user_id));
 diesel::joinable!(publish_limit_buckets -> users (user_id));
 diesel::joinable!(publish_rate_overrides -> users (user_id));
+diesel::joinable!(publish_upload_parts -> publish_uploads (upload_id));
+diesel::joinable!(publish_uploads -> users (user_id));
 diesel::joinable!(readme_renderings -> versions (version_id));
 diesel::joinable!(recent_crate_downloads -> crates (crate_id));
+diesel::joinable!(reserved_crate_prefixes -> teams (team_id));
+diesel::joinable!(reverse_dependency_counts -> crates (crate_id));
+diesel::joinable!(saved_searches -> users (user_id));
+diesel::joinable!(security_log_entries -> users (user_id));
+diesel::joinable!(sso_identities -> users (user_id));
+diesel::joinable!(staged_publishes -> users (user_id));
+diesel::joinable!(team_members -> teams (team_id));
+diesel::joinable!(team_members -> users (user_id));
+diesel::joinable!(totp_credentials -> users (user_id));
+diesel::joinable!(totp_recovery_codes -> users (user_id));
+diesel::joinable!(version_cargo_downloads -> versions (version_id));
 diesel::joinable!(version_downloads -> versions (version_id));
 diesel::joinable!(version_owner_actions -> api_tokens (api_token_id));
 diesel::joinable!(version_owner_actions -> users (user_id));
 diesel::joinable!(version_owner_actions -> versions (version_id));
+diesel::joinable!(version_signatures -> versions (version_id));
 diesel::joinable!(versions -> crates (crate_id));
 diesel::joinable!(versions -> users (published_by));
 diesel::joinable!(versions_published_by -> versions (version_id));
+diesel::joinable!(webauthn_challenges -> users (user_id));
+diesel::joinable!(webauthn_credentials -> users (user_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
+diesel::joinable!(webhooks -> crates (crate_id));
+diesel::joinable!(webhooks -> users (created_by));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    account_deletions,
+    admin_actions,
+    admin_role_assignments,
+    admin_roles,
     api_tokens,
     background_jobs,
     badges,
     categories,
+    cdn_invalidations,
+    crate_deletions,
+    crate_download_trends,
+    crate_file_checks,
+    crate_files,
     crate_owner_invitations,
     crate_owners,
+    crate_transfers,
     crates,
     crates_categories,
     crates_keywords,
     dependencies,
+    email_changes,
     emails,
+    file_replications,
+    follow_notification_settings,
     follows,
+    index_changes,
+    index_inconsistencies,
+    index_squashes,
     keywords,
+    linked_accounts,
     metadata,
+    notification_preferences,
+    organization_invitations,
+    organization_members,
+    organizations,
+    persistent_sessions,
+    processed_cdn_log_files,
     publish_limit_buckets,
     publish_rate_overrides,
+    publish_upload_parts,
+    publish_uploads,
     readme_renderings,
     recent_crate_downloads,
     reserved_crate_names,
+    reserved_crate_prefixes,
+    reverse_dependency_counts,
+    saved_searches,
+    security_log_entries,
+    sso_identities,
+    staged_publishes,
+    team_members,
     teams,
+    totp_credentials,
+    totp_recovery_codes,
     users,
+    version_cargo_downloads,
     version_downloads,
     version_owner_actions,
+    version_signatures,
     versions,
     versions_published_by,
+    webauthn_challenges,
+    webauthn_credentials,
+    webhook_deliveries,
+    webhooks,
 );
@@ -0,0 +1,59 @@
+//! Backends for the full-text search of crates.
+//!
+//! Crates are searched directly in the database by default. The text matching
+//! can instead be offloaded to a Meilisearch instance, which is kept in sync
+//! with the database by the `sync_search_index` background job.
+
+use diesel::helper_types::LeftJoinQuerySource;
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Double};
+use reqwest::blocking::Client;
+
+use crate::config::{self, SearchBackendConfig};
+use crate::schema::{crates, recent_crate_downloads};
+use crate::util::errors::AppResult;
+
+mod fields;
+mod meilisearch;
+mod postgres;
+
+pub use self::fields::{SearchFields, TextFields};
+pub use self::meilisearch::{CrateDocument, Meilisearch};
+pub use self::postgres::PostgresSearch;
+
+/// The tables that crates are searched in.
+pub type SearchSource = LeftJoinQuerySource<crates::table, recent_crate_downloads::table>;
+
+/// An SQL expression that can be used in the query of a crate search.
+pub type SearchExpression<'a, ST> = Box<dyn BoxableExpression<SearchSource, Pg, SqlType = ST> + 'a>;
+
+/// The crates matching a text query.
+pub struct TextMatches<'a> {
+    /// Whether a crate matches the query.
+    pub filter: SearchExpression<'a, Bool>,
+    /// Builds the expression of how relevant a crate is for the query, higher
+    /// is better. It is needed more than once to sort and seek the results.
+    pub relevance: Box<dyn Fn() -> SearchExpression<'a, Double> + 'a>,
+}
+
+pub trait SearchBackend: Send + Sync {
+    /// Returns the expressions that match and rank the crates for the
+    /// non-empty text query `q` in the given `fields`.
+    ///
+    /// The expressions are combined with the other filters and the
+    /// pagination of the search, and exact name matches are always put first.
+    fn text_matches<'a>(&self, q: &'a str, fields: &TextFields) -> AppResult<TextMatches<'a>>;
+}
+
+/// Creates the search backend that is selected in the configuration.
+pub fn from_config(config: &config::Server, http_client: Option<Client>) -> Box<dyn SearchBackend> {
+    match &config.search_backend {
+        SearchBackendConfig::Postgres => {
+            Box::new(PostgresSearch::new(config.search_ranking.clone()))
+        }
+        SearchBackendConfig::Meilisearch { url, api_key } => {
+            Box::new(Meilisearch::new(url, api_key.clone(), http_client))
+        }
+    }
+}
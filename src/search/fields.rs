@@ -0,0 +1,107 @@
+use crate::util::errors::{bad_request, AppResult};
+
+/// The fields of a crate that the text query of a search is matched against,
+/// as selected by the `in` query parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchFields {
+    /// Only match crates whose canonical name is equal to the query, for
+    /// tooling that needs deterministic lookups.
+    ExactName,
+    /// Match the query against the text of the given fields.
+    Text(TextFields),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextFields {
+    pub name: bool,
+    pub keywords: bool,
+    pub description: bool,
+    pub readme: bool,
+}
+
+impl SearchFields {
+    /// Parses a comma separated list of fields like `description,readme`, or
+    /// `exact_name` on its own.
+    pub fn parse(fields: &str) -> AppResult<Self> {
+        if fields == "exact_name" {
+            return Ok(Self::ExactName);
+        }
+
+        let mut text_fields = TextFields::NONE;
+        for field in fields.split(',').map(str::trim) {
+            match field {
+                "name" => text_fields.name = true,
+                "keywords" => text_fields.keywords = true,
+                "description" => text_fields.description = true,
+                "readme" => text_fields.readme = true,
+                "exact_name" => {
+                    return Err(bad_request(
+                        "`exact_name` can not be combined with other fields",
+                    ))
+                }
+                field => {
+                    return Err(bad_request(&format_args!(
+                        "invalid search field `{field}`, expected one of `name`, `keywords`, \
+                         `description`, `readme` or `exact_name`"
+                    )))
+                }
+            }
+        }
+
+        Ok(Self::Text(text_fields))
+    }
+}
+
+impl Default for SearchFields {
+    fn default() -> Self {
+        Self::Text(TextFields::ALL)
+    }
+}
+
+impl TextFields {
+    pub const ALL: Self = Self {
+        name: true,
+        keywords: true,
+        description: true,
+        readme: true,
+    };
+
+    const NONE: Self = Self {
+        name: false,
+        keywords: false,
+        description: false,
+        readme: false,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SearchFields, TextFields};
+
+    #[test]
+    fn parse_search_fields() {
+        assert_eq!(
+            SearchFields::parse("exact_name").unwrap(),
+            SearchFields::ExactName
+        );
+        assert_eq!(
+            SearchFields::parse("name,keywords,description,readme").unwrap(),
+            SearchFields::Text(TextFields::ALL)
+        );
+
+        let fields = TextFields {
+            name: false,
+            keywords: false,
+            description: true,
+            readme: true,
+        };
+        assert_eq!(
+            SearchFields::parse("description, readme").unwrap(),
+            SearchFields::Text(fields)
+        );
+
+        assert_err!(SearchFields::parse(""));
+        assert_err!(SearchFields::parse("name,exact_name"));
+        assert_err!(SearchFields::parse("license"));
+    }
+}
@@ -0,0 +1,180 @@
+use anyhow::Context;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, Double, Text};
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::Method;
+
+use super::{SearchBackend, SearchExpression, TextFields, TextMatches};
+use crate::config::SearchBackendConfig;
+use crate::schema::crates;
+use crate::util::errors::{bad_request, AppResult};
+
+/// The name of the Meilisearch index that contains the crates.
+const INDEX: &str = "crates";
+
+/// The maximum number of matches that are requested from Meilisearch for a
+/// query. Crates beyond this limit don't show up in the search results.
+const MAX_MATCHES: usize = 1000;
+
+/// Offloads the text matching of the crate search to a Meilisearch instance.
+///
+/// Crates are stored as [`CrateDocument`]s with their name as the primary
+/// key. Meilisearch tolerates typos and ranks the matches itself, so the
+/// database only has to filter and sort the returned crate names.
+#[derive(Clone)]
+pub struct Meilisearch {
+    url: String,
+    api_key: Option<String>,
+    client: Option<Client>,
+}
+
+/// The searchable content of a crate in the Meilisearch index.
+#[derive(Debug, Serialize)]
+pub struct CrateDocument {
+    pub name: String,
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    pub downloads: i32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchRequest<'a> {
+    q: &'a str,
+    limit: usize,
+    attributes_to_retrieve: &'a [&'a str],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attributes_to_search_on: Option<Vec<&'a str>>,
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    hits: Vec<SearchHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchHit {
+    name: String,
+}
+
+impl Meilisearch {
+    pub fn new(url: &str, api_key: Option<String>, client: Option<Client>) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            api_key,
+            client,
+        }
+    }
+
+    /// Returns the Meilisearch client if it is selected in the configuration.
+    pub fn from_config(config: &SearchBackendConfig, client: Client) -> Option<Self> {
+        match config {
+            SearchBackendConfig::Postgres => None,
+            SearchBackendConfig::Meilisearch { url, api_key } => {
+                Some(Self::new(url, api_key.clone(), Some(client)))
+            }
+        }
+    }
+
+    fn client(&self) -> &Client {
+        self.client
+            .as_ref()
+            .expect("No HTTP client is configured.  In tests, use `TestApp::with_proxy()`.")
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}/indexes/{INDEX}/{path}", self.url);
+        let request = self.client().request(method, url);
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    /// Adds a crate to the index, or replaces its existing document.
+    #[instrument(skip_all, fields(krate.name = %document.name))]
+    pub fn index_crate(&self, document: &CrateDocument) -> anyhow::Result<()> {
+        self.request(Method::POST, "documents?primaryKey=name")
+            .json(&[document])
+            .send()
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to index crate `{}`", document.name))?;
+        Ok(())
+    }
+
+    /// Removes a crate from the index.
+    #[instrument(skip(self))]
+    pub fn remove_crate(&self, name: &str) -> anyhow::Result<()> {
+        self.request(Method::DELETE, &format!("documents/{name}"))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to remove crate `{name}` from the index"))?;
+        Ok(())
+    }
+}
+
+impl SearchBackend for Meilisearch {
+    fn text_matches<'a>(&self, q: &'a str, fields: &TextFields) -> AppResult<TextMatches<'a>> {
+        let request = SearchRequest {
+            q,
+            limit: MAX_MATCHES,
+            attributes_to_retrieve: &["name"],
+            attributes_to_search_on: searchable_attributes(fields)?,
+        };
+        let response: SearchResponse = self
+            .request(Method::POST, "search")
+            .json(&request)
+            .send()?
+            .error_for_status()?
+            .json()?;
+        let names = response
+            .hits
+            .into_iter()
+            .map(|hit| hit.name)
+            .collect::<Vec<_>>();
+
+        // Meilisearch returns the best matches first
+        let relevance = {
+            let names = names.clone();
+            move || -> SearchExpression<'a, Double> {
+                Box::new(
+                    sql::<Double>("-array_position(")
+                        .bind::<Array<Text>, _>(names.clone())
+                        .sql(", crates.name::text)::float8"),
+                )
+            }
+        };
+
+        Ok(TextMatches {
+            filter: Box::new(crates::name.eq_any(names)),
+            relevance: Box::new(relevance),
+        })
+    }
+}
+
+/// Maps the selected fields to the attributes of the indexed documents.
+///
+/// Returns `None` if all fields are selected, so that Meilisearch searches
+/// all attributes. Readmes are not part of the index and can't be searched.
+fn searchable_attributes(fields: &TextFields) -> AppResult<Option<Vec<&'static str>>> {
+    if *fields == TextFields::ALL {
+        return Ok(None);
+    }
+
+    let attributes = [
+        (fields.name, "name"),
+        (fields.keywords, "keywords"),
+        (fields.description, "description"),
+    ]
+    .into_iter()
+    .filter(|(selected, _)| *selected)
+    .map(|(_, attribute)| attribute)
+    .collect::<Vec<_>>();
+
+    if attributes.is_empty() {
+        return Err(bad_request("searching readmes is not supported"));
+    }
+
+    Ok(Some(attributes))
+}
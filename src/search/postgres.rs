@@ -0,0 +1,130 @@
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Double, Text};
+
+use super::{SearchBackend, SearchExpression, SearchSource, TextFields, TextMatches};
+use crate::config::SearchRanking;
+use crate::models::Crate;
+use crate::util::errors::AppResult;
+
+/// Searches crates with the full-text search and the trigram indexes of the
+/// database.
+pub struct PostgresSearch {
+    ranking: SearchRanking,
+}
+
+impl PostgresSearch {
+    pub fn new(ranking: SearchRanking) -> Self {
+        Self { ranking }
+    }
+}
+
+impl SearchBackend for PostgresSearch {
+    fn text_matches<'a>(
+        &self,
+        q_string: &'a str,
+        fields: &TextFields,
+    ) -> AppResult<TextMatches<'a>> {
+        let vector = search_vector(fields);
+        let text_matches = sql::<Bool>(&vector)
+            .sql(" @@ plainto_tsquery('english', ")
+            .bind::<Text, _>(q_string)
+            .sql(")");
+        let filter: SearchExpression<'a, Bool> = if fields.name {
+            Box::new(
+                text_matches
+                    .or(Crate::loosly_matches_name::<SearchSource>(q_string))
+                    .or(fuzzily_matches_name(q_string, &vector)),
+            )
+        } else {
+            Box::new(text_matches)
+        };
+
+        // Combines the trigram similarity of the name, the full-text rank
+        // of the name, keywords, description and readme, and a boost for
+        // popular crates, so that crates with slightly different names and
+        // widely used crates are not buried below crates that happen to
+        // mention the query more often.
+        let name_weight = if fields.name {
+            self.ranking.name_weight
+        } else {
+            0.
+        };
+        let text_weight = self.ranking.text_weight;
+        let popularity_weight = self.ranking.popularity_weight;
+        let relevance = move || -> SearchExpression<'a, Double> {
+            Box::new(
+                sql::<Double>("")
+                    .bind::<Double, _>(name_weight)
+                    .sql(" * similarity(canon_crate_name(crates.name), canon_crate_name(")
+                    .bind::<Text, _>(q_string)
+                    .sql(")) + ")
+                    .bind::<Double, _>(text_weight)
+                    .sql(&format!(" * ts_rank_cd({vector}, "))
+                    .sql("plainto_tsquery('english', ")
+                    .bind::<Text, _>(q_string)
+                    .sql(")) + ")
+                    .bind::<Double, _>(popularity_weight)
+                    .sql(" * ln(1 + coalesce(recent_crate_downloads.downloads, 0))"),
+            )
+        };
+
+        Ok(TextMatches {
+            filter,
+            relevance: Box::new(relevance),
+        })
+    }
+}
+
+/// Returns the SQL of the full-text search vector of a crate, restricted to
+/// the given fields.
+///
+/// The vector is maintained by the `trigger_crates_name_search` trigger, which
+/// gives the name, keywords, description and readme the weights A to D.
+/// Restricting the fields can't use the index on the vector, so the whole
+/// vector is used if all fields are selected.
+fn search_vector(fields: &TextFields) -> String {
+    if *fields == TextFields::ALL {
+        return "crates.textsearchable_index_col".into();
+    }
+
+    let weights = [
+        (fields.name, "a"),
+        (fields.keywords, "b"),
+        (fields.description, "c"),
+        (fields.readme, "d"),
+    ]
+    .into_iter()
+    .filter(|(selected, _)| *selected)
+    .map(|(_, weight)| weight)
+    .collect::<Vec<_>>()
+    .join(",");
+
+    format!("ts_filter(crates.textsearchable_index_col, '{{{weights}}}')")
+}
+
+/// Matches crate names that are similar to the query according to their
+/// trigrams, so that typos like `tokoi` still find `tokio`.
+///
+/// This only kicks in if nothing matches the query itself, otherwise the
+/// results would be flooded with crates that merely have a similar name.
+/// Queries that are too short for meaningful trigrams are never matched
+/// fuzzily.
+fn fuzzily_matches_name<'a>(q_string: &'a str, vector: &str) -> SearchExpression<'a, Bool> {
+    if q_string.len() <= 2 {
+        return Box::new(false.into_sql::<Bool>());
+    }
+
+    // `%` uses the `pg_trgm.similarity_threshold` setting, which defaults to
+    // 0.3, and can use the trigram index on the canonical crate name.
+    Box::new(
+        sql::<Bool>("(canon_crate_name(crates.name) % canon_crate_name(")
+            .bind::<Text, _>(q_string)
+            .sql(") AND NOT EXISTS (SELECT 1 FROM crates ")
+            .sql(&format!("WHERE {vector} @@ plainto_tsquery('english', "))
+            .bind::<Text, _>(q_string)
+            .sql(") OR canon_crate_name(crates.name) LIKE canon_crate_name(")
+            .bind::<Text, _>(format!("%{q_string}%"))
+            .sql(")))"),
+    )
+}
@@ -4,6 +4,7 @@ sql_function!(#[aggregate] fn array_agg<T: SingleValue>(x: T) -> Array<T>);
 sql_function!(fn canon_crate_name(x: Text) -> Text);
 sql_function!(fn to_char(a: Date, b: Text) -> Text);
 sql_function!(fn lower(x: Text) -> Text);
+sql_function!(fn starts_with(x: Text, y: Text) -> Bool);
 sql_function!(fn date_part(x: Text, y: Timestamp) -> Double);
 sql_function! {
     #[sql_name = "date_part"]
@@ -0,0 +1,418 @@
+//! This module implements functionality for interacting with the single
+//! sign-on identity provider of a private deployment via OpenID Connect.
+//!
+//! Only the authorization code flow is supported. The ID token that is
+//! returned together with the access token is validated before the user is
+//! logged in: its signature has to verify with a key of the JSON Web Key Set
+//! of the identity provider, and its issuer, audience, expiry and nonce have to
+//! match the login. The profile of the user is then read from the userinfo
+//! endpoint, and has to belong to the subject of the ID token.
+//!
+//! SAML identity providers are not supported.
+
+use chrono::Utc;
+use oauth2::basic::{
+    BasicErrorResponse, BasicRevocationErrorResponse, BasicTokenIntrospectionResponse,
+    BasicTokenType,
+};
+use oauth2::{AccessToken, ExtraTokenFields, StandardRevocableToken, StandardTokenResponse};
+use reqwest::blocking::Client;
+use reqwest::header;
+use ring::signature::{
+    RsaPublicKeyComponents, UnparsedPublicKey, ECDSA_P256_SHA256_FIXED, RSA_PKCS1_2048_8192_SHA256,
+};
+use serde_json::Value;
+
+use crate::config::SsoConfig;
+use crate::util::errors::{bad_request, internal, AppResult};
+
+/// How far the clocks of crates.io and the identity provider may differ when
+/// checking the expiry of ID tokens, in seconds.
+const CLOCK_SKEW: i64 = 60;
+
+/// The OAuth client of the identity provider, whose token responses include
+/// the ID token.
+pub type SsoClient = oauth2::Client<
+    BasicErrorResponse,
+    SsoTokenResponse,
+    BasicTokenType,
+    BasicTokenIntrospectionResponse,
+    StandardRevocableToken,
+    BasicRevocationErrorResponse,
+>;
+
+pub type SsoTokenResponse = StandardTokenResponse<IdTokenFields, BasicTokenType>;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IdTokenFields {
+    pub id_token: String,
+}
+
+impl ExtraTokenFields for IdTokenFields {}
+
+/// The identity of the user that logged in, as returned by the userinfo
+/// endpoint of the identity provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SsoUser {
+    /// The `sub` claim, which never changes for an identity.
+    pub subject: String,
+    pub username: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub avatar: Option<String>,
+    pub groups: Vec<String>,
+}
+
+impl SsoUser {
+    /// Reads the standard claims of the identity. The username is taken from
+    /// `preferred_username`, or else from the local part of `email`.
+    pub fn from_claims(claims: &Value, groups_claim: &str) -> AppResult<Self> {
+        let claim = |name: &str| claims[name].as_str().map(String::from);
+
+        let subject = claim("sub").ok_or_else(|| internal("missing `sub` claim"))?;
+        let email = claim("email");
+        let username = claim("preferred_username")
+            .or_else(|| {
+                let email = email.as_deref()?;
+                Some(email.split('@').next()?.to_string())
+            })
+            .filter(|username| !username.is_empty())
+            .ok_or_else(|| bad_request("the identity provider did not return a username"))?;
+
+        let groups = claims[groups_claim]
+            .as_array()
+            .map(|groups| {
+                groups
+                    .iter()
+                    .filter_map(|group| group.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            subject,
+            username,
+            name: claim("name"),
+            email,
+            avatar: claim("picture"),
+            groups,
+        })
+    }
+}
+
+/// Fetches the identity of the user that the access token belongs to from the
+/// userinfo endpoint. It has to be the `subject` of the validated ID token.
+pub fn current_user(
+    client: &Client,
+    config: &SsoConfig,
+    auth: &AccessToken,
+    subject: &str,
+) -> AppResult<SsoUser> {
+    info!("SSO HTTP: {}", config.userinfo_url);
+
+    let claims: Value = client
+        .get(&config.userinfo_url)
+        .header(header::AUTHORIZATION, format!("Bearer {}", auth.secret()))
+        .header(header::USER_AGENT, "crates.io (https://crates.io)")
+        .send()?
+        .error_for_status()
+        .map_err(|e| {
+            internal(format!(
+                "didn't get a 200 result from the identity provider: {e}"
+            ))
+        })?
+        .json()?;
+
+    let user = SsoUser::from_claims(&claims, &config.groups_claim)?;
+    if user.subject != subject {
+        return Err(bad_request("the userinfo does not belong to the ID token"));
+    }
+
+    Ok(user)
+}
+
+/// Validates the ID token of a login with the keys of the identity provider,
+/// and returns its `sub` claim.
+pub fn validate_id_token(
+    client: &Client,
+    config: &SsoConfig,
+    id_token: &str,
+    nonce: &str,
+) -> AppResult<String> {
+    info!("SSO HTTP: {}", config.jwks_url);
+
+    let jwks: Value = client
+        .get(&config.jwks_url)
+        .header(header::USER_AGENT, "crates.io (https://crates.io)")
+        .send()?
+        .error_for_status()
+        .map_err(|e| {
+            internal(format!(
+                "didn't get a 200 result from the identity provider: {e}"
+            ))
+        })?
+        .json()?;
+
+    let claims = verify_id_token(&jwks, config, id_token, nonce, Utc::now().timestamp())?;
+    claims["sub"]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| bad_request("invalid ID token: missing `sub` claim"))
+}
+
+/// Checks the signature of the ID token against the key set, and its `iss`,
+/// `aud`, `exp` and `nonce` claims. Returns the claims of the token.
+fn verify_id_token(
+    jwks: &Value,
+    config: &SsoConfig,
+    id_token: &str,
+    nonce: &str,
+    now: i64,
+) -> AppResult<Value> {
+    let invalid = |reason: &str| bad_request(&format_args!("invalid ID token: {reason}"));
+
+    let (message, signature) = id_token
+        .rsplit_once('.')
+        .ok_or_else(|| invalid("malformed token"))?;
+    let (header, payload) = message
+        .split_once('.')
+        .ok_or_else(|| invalid("malformed token"))?;
+
+    let decode_json =
+        |part: &str| -> Option<Value> { serde_json::from_slice(&decode_base64(part)?).ok() };
+    let header = decode_json(header).ok_or_else(|| invalid("malformed header"))?;
+    let claims = decode_json(payload).ok_or_else(|| invalid("malformed claims"))?;
+    let signature = decode_base64(signature).ok_or_else(|| invalid("malformed signature"))?;
+
+    // Keys are matched by their ID, if the token names one
+    let alg = header["alg"].as_str().unwrap_or_default();
+    let kid = header["kid"].as_str();
+    let verified = jwks["keys"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|key| kid.is_none() || key["kid"].as_str() == kid)
+        .any(|key| verify_signature(alg, key, message.as_bytes(), &signature));
+    if !verified {
+        return Err(invalid(
+            "the signature does not match a key of the identity provider",
+        ));
+    }
+
+    if claims["iss"].as_str() != Some(&config.issuer) {
+        return Err(invalid("unexpected issuer"));
+    }
+
+    let client_id = config.client_id.as_str();
+    let audience = match &claims["aud"] {
+        Value::String(audience) => audience == client_id,
+        Value::Array(audiences) => audiences.iter().any(|audience| audience == client_id),
+        _ => false,
+    };
+    if !audience {
+        return Err(invalid("unexpected audience"));
+    }
+
+    match claims["exp"].as_i64() {
+        Some(exp) if exp + CLOCK_SKEW > now => {}
+        _ => return Err(invalid("the token has expired")),
+    }
+
+    if claims["nonce"].as_str() != Some(nonce) {
+        return Err(invalid("unexpected nonce"));
+    }
+
+    Ok(claims)
+}
+
+/// Verifies a JWS signature with a JSON Web Key. Only the `RS256` and `ES256`
+/// algorithms are supported.
+fn verify_signature(alg: &str, key: &Value, message: &[u8], signature: &[u8]) -> bool {
+    let param = |name: &str| key[name].as_str().and_then(decode_base64);
+
+    match (alg, key["kty"].as_str()) {
+        ("RS256", Some("RSA")) => {
+            let (Some(n), Some(e)) = (param("n"), param("e")) else {
+                return false;
+            };
+            RsaPublicKeyComponents { n, e }
+                .verify(&RSA_PKCS1_2048_8192_SHA256, message, signature)
+                .is_ok()
+        }
+        ("ES256", Some("EC")) if key["crv"] == "P-256" => {
+            let (Some(x), Some(y)) = (param("x"), param("y")) else {
+                return false;
+            };
+            // The uncompressed point format expected by ring
+            let public_key = [&[0x04][..], &x, &y].concat();
+            UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, public_key)
+                .verify(message, signature)
+                .is_ok()
+        }
+        _ => false,
+    }
+}
+
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    base64::decode_config(input, base64::URL_SAFE_NO_PAD).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_id_token, SsoUser};
+    use crate::config::SsoConfig;
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+    use serde_json::Value;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn config() -> SsoConfig {
+        SsoConfig {
+            client_id: oauth2::ClientId::new("sso-client".into()),
+            client_secret: oauth2::ClientSecret::new("sso-secret".into()),
+            authorize_url: "https://sso.example.com/authorize".into(),
+            token_url: "https://sso.example.com/token".into(),
+            userinfo_url: "https://sso.example.com/userinfo".into(),
+            jwks_url: "https://sso.example.com/keys".into(),
+            issuer: "https://sso.example.com".into(),
+            redirect_url: "https://crates.example.com/github-redirect.html".into(),
+            groups_claim: "groups".into(),
+            group_teams: vec![],
+        }
+    }
+
+    fn encode(bytes: &[u8]) -> String {
+        base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+    }
+
+    /// Returns a new key pair and the key set that contains its public key.
+    fn new_key() -> (EcdsaKeyPair, Value) {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref()).unwrap();
+
+        // The public key is `0x04 || x || y`
+        let public_key = key_pair.public_key().as_ref();
+        let jwks = json!({ "keys": [{
+            "kty": "EC",
+            "crv": "P-256",
+            "kid": "key-1",
+            "x": encode(&public_key[1..33]),
+            "y": encode(&public_key[33..]),
+        }] });
+
+        (key_pair, jwks)
+    }
+
+    fn sign(key_pair: &EcdsaKeyPair, header: &Value, claims: &Value) -> String {
+        let message = format!(
+            "{}.{}",
+            encode(header.to_string().as_bytes()),
+            encode(claims.to_string().as_bytes())
+        );
+        let signature = key_pair
+            .sign(&SystemRandom::new(), message.as_bytes())
+            .unwrap();
+        format!("{message}.{}", encode(signature.as_ref()))
+    }
+
+    fn valid_claims() -> Value {
+        json!({
+            "iss": "https://sso.example.com",
+            "sub": "248289761001",
+            "aud": "sso-client",
+            "exp": NOW + 300,
+            "nonce": "n-0S6_WzA2Mj",
+        })
+    }
+
+    #[test]
+    fn id_tokens_are_validated() {
+        let config = config();
+        let (key_pair, jwks) = new_key();
+        let header = json!({ "alg": "ES256", "kid": "key-1" });
+        let verify = |claims: &Value| {
+            let token = sign(&key_pair, &header, claims);
+            verify_id_token(&jwks, &config, &token, "n-0S6_WzA2Mj", NOW)
+        };
+
+        let claims = verify(&valid_claims()).unwrap();
+        assert_eq!(claims["sub"], "248289761001");
+
+        let mut audiences = valid_claims();
+        audiences["aud"] = json!(["other-client", "sso-client"]);
+        assert!(verify(&audiences).is_ok());
+
+        let invalid = [
+            ("iss", json!("https://evil.example.com")),
+            ("aud", json!("other-client")),
+            ("exp", json!(NOW - 300)),
+            ("nonce", json!("replayed")),
+        ];
+        for (claim, value) in invalid {
+            let mut claims = valid_claims();
+            claims[claim] = value;
+            assert!(verify(&claims).is_err(), "{claim} was not checked");
+        }
+
+        let mut claims = valid_claims();
+        claims.as_object_mut().unwrap().remove("nonce");
+        assert!(verify(&claims).is_err());
+    }
+
+    #[test]
+    fn id_tokens_need_a_valid_signature() {
+        let config = config();
+        let (key_pair, jwks) = new_key();
+        let (other_key_pair, _) = new_key();
+        let claims = valid_claims();
+        let verify = |token: &str| verify_id_token(&jwks, &config, token, "n-0S6_WzA2Mj", NOW);
+
+        let header = json!({ "alg": "ES256", "kid": "key-1" });
+        assert!(verify(&sign(&key_pair, &header, &claims)).is_ok());
+        assert!(verify(&sign(&other_key_pair, &header, &claims)).is_err());
+
+        // Tokens may omit the key ID, but have to name a supported algorithm
+        let header = json!({ "alg": "ES256" });
+        assert!(verify(&sign(&key_pair, &header, &claims)).is_ok());
+        let header = json!({ "alg": "ES256", "kid": "key-2" });
+        assert!(verify(&sign(&key_pair, &header, &claims)).is_err());
+        let header = json!({ "alg": "none" });
+        assert!(verify(&sign(&key_pair, &header, &claims)).is_err());
+
+        // The claims are covered by the signature
+        let token = sign(&key_pair, &json!({ "alg": "ES256" }), &claims);
+        let mut parts: Vec<_> = token.split('.').map(String::from).collect();
+        let mut tampered = claims.clone();
+        tampered["sub"] = json!("1");
+        parts[1] = encode(tampered.to_string().as_bytes());
+        assert!(verify(&parts.join(".")).is_err());
+
+        assert!(verify("not a token").is_err());
+    }
+
+    #[test]
+    fn username_falls_back_to_email() {
+        let claims = json!({
+            "sub": "248289761001",
+            "email": "jane@example.com",
+            "groups": ["engineering", 42],
+        });
+
+        let user = SsoUser::from_claims(&claims, "groups").unwrap();
+        assert_eq!(user.subject, "248289761001");
+        assert_eq!(user.username, "jane");
+        assert_eq!(user.email.as_deref(), Some("jane@example.com"));
+        assert_eq!(user.groups, vec!["engineering"]);
+
+        let claims = json!({ "sub": "248289761001", "preferred_username": "j.doe" });
+        let user = SsoUser::from_claims(&claims, "roles").unwrap();
+        assert_eq!(user.username, "j.doe");
+        assert!(user.groups.is_empty());
+
+        let claims = json!({ "sub": "248289761001" });
+        assert!(SsoUser::from_claims(&claims, "groups").is_err());
+    }
+}
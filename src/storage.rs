@@ -0,0 +1,155 @@
+//! Backends that crate files, readmes, and the files of the HTTP-based index
+//! are stored in.
+//!
+//! Production stores the files in S3 buckets. Development and self-hosted
+//! deployments can store them on the local filesystem instead, and the
+//! in-memory backend keeps them in the process for tests.
+
+use anyhow::{anyhow, Result};
+use reqwest::blocking::{Body, Client};
+use reqwest::header::HeaderMap;
+use std::fmt::Debug;
+use std::panic::RefUnwindSafe;
+use std::path::Path;
+
+mod local;
+mod memory;
+mod s3;
+
+pub use self::local::LocalStorage;
+pub use self::memory::InMemoryStorage;
+pub use self::s3::S3Storage;
+
+/// The buckets that files are stored in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UploadBucket {
+    /// Crate files, readmes, and database dumps.
+    Default,
+    /// The files of the HTTP-based index.
+    Index,
+}
+
+/// Background jobs catch panics, so the backends have to be unwind safe.
+pub trait Storage: Send + Sync + RefUnwindSafe + Debug {
+    /// Returns the public URL of the file at `path` in the default bucket.
+    ///
+    /// The function doesn't check for the existence of the file.
+    fn url(&self, path: &str) -> String;
+
+    /// Stores `content` at `path`, replacing the file if it already exists.
+    ///
+    /// The `extra_headers`, like `Cache-Control`, are served along with the
+    /// file by backends that serve the files over HTTP themselves.
+    fn put(
+        &self,
+        client: &Client,
+        path: &str,
+        content: Body,
+        content_type: &str,
+        extra_headers: HeaderMap,
+        bucket: UploadBucket,
+    ) -> Result<()>;
+
+    /// Returns the contents of the file at `path`, or `None` if the file
+    /// does not exist.
+    fn get(&self, client: &Client, path: &str, bucket: UploadBucket) -> Result<Option<Vec<u8>>>;
+
+    /// Deletes the file at `path`. Deleting a file that does not exist is not
+    /// an error.
+    fn delete(&self, client: &Client, path: &str, bucket: UploadBucket) -> Result<()>;
+
+    /// Starts an upload of the file at `path` in the default bucket in
+    /// multiple parts, and returns the ID of the upload.
+    ///
+    /// The default implementation stores each part as a separate file next to
+    /// `path` and concatenates them when the upload is completed, so it
+    /// doesn't need an ID.
+    fn start_multipart_upload(&self, _client: &Client, _path: &str) -> Result<String> {
+        Ok(String::new())
+    }
+
+    /// Stores part `part_number` of a multipart upload, and returns the tag
+    /// that identifies the part when the upload is completed.
+    fn upload_part(
+        &self,
+        client: &Client,
+        path: &str,
+        _upload_id: &str,
+        part_number: i32,
+        content: Vec<u8>,
+    ) -> Result<String> {
+        let part_path = format!("{path}.part{part_number}");
+        let content_type = "application/octet-stream";
+        let bucket = UploadBucket::Default;
+        self.put(
+            client,
+            &part_path,
+            content.into(),
+            content_type,
+            HeaderMap::new(),
+            bucket,
+        )?;
+        Ok(part_path)
+    }
+
+    /// Completes a multipart upload by concatenating the `parts`, given as
+    /// part numbers and tags in ascending order, into the file at `path`.
+    fn complete_multipart_upload(
+        &self,
+        client: &Client,
+        path: &str,
+        upload_id: &str,
+        parts: &[(i32, String)],
+    ) -> Result<()> {
+        let mut content = Vec::new();
+        for (_, part_path) in parts {
+            let part = self
+                .get(client, part_path, UploadBucket::Default)?
+                .ok_or_else(|| anyhow!("part `{part_path}` does not exist"))?;
+            content.extend(part);
+        }
+
+        let content_type = "application/octet-stream";
+        let bucket = UploadBucket::Default;
+        self.put(
+            client,
+            path,
+            content.into(),
+            content_type,
+            HeaderMap::new(),
+            bucket,
+        )?;
+        self.abort_multipart_upload(client, path, upload_id, parts)
+    }
+
+    /// Discards the `parts` of a multipart upload that was not completed, or
+    /// that are not needed anymore after it was completed.
+    fn abort_multipart_upload(
+        &self,
+        client: &Client,
+        _path: &str,
+        _upload_id: &str,
+        parts: &[(i32, String)],
+    ) -> Result<()> {
+        for (_, part_path) in parts {
+            self.delete(client, part_path, UploadBucket::Default)?;
+        }
+        Ok(())
+    }
+
+    /// The minimum size of all parts of a multipart upload but the last one.
+    fn min_part_size(&self) -> usize {
+        1
+    }
+
+    /// Returns `true` if the backend stores the files of the HTTP-based index.
+    fn has_index_bucket(&self) -> bool {
+        true
+    }
+
+    /// Returns the directory that the files of the default bucket are stored
+    /// in, if they have to be served by the application itself.
+    fn local_directory(&self) -> Option<&Path> {
+        None
+    }
+}
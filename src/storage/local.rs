@@ -0,0 +1,73 @@
+use super::{Storage, UploadBucket};
+use anyhow::Result;
+use reqwest::blocking::{Body, Client};
+use reqwest::header::HeaderMap;
+use std::fs::{self, File};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Stores the files in a directory on the local filesystem, which the
+/// application serves the crate files and readmes from.
+///
+/// The files of the HTTP-based index are stored in its `index` subdirectory.
+#[derive(Clone, Debug)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns the absolute path to the locally stored file.
+    fn file_path(&self, path: &str, bucket: UploadBucket) -> PathBuf {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        match bucket {
+            UploadBucket::Index => self.root.join("index").join(path),
+            UploadBucket::Default => self.root.join(path),
+        }
+    }
+}
+
+impl Storage for LocalStorage {
+    fn url(&self, path: &str) -> String {
+        format!("/{path}")
+    }
+
+    fn put(
+        &self,
+        _client: &Client,
+        path: &str,
+        mut content: Body,
+        _content_type: &str,
+        _extra_headers: HeaderMap,
+        bucket: UploadBucket,
+    ) -> Result<()> {
+        let filename = self.file_path(path, bucket);
+        fs::create_dir_all(filename.parent().unwrap())?;
+        let mut file = File::create(&filename)?;
+        let mut buffer = content.buffer()?;
+        std::io::copy(&mut buffer, &mut file)?;
+        Ok(())
+    }
+
+    fn get(&self, _client: &Client, path: &str, bucket: UploadBucket) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.file_path(path, bucket)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn delete(&self, _client: &Client, path: &str, bucket: UploadBucket) -> Result<()> {
+        match fs::remove_file(self.file_path(path, bucket)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn local_directory(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
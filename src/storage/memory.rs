@@ -0,0 +1,150 @@
+use super::{Storage, UploadBucket};
+use anyhow::Result;
+use reqwest::blocking::{Body, Client};
+use reqwest::header::HeaderMap;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+type Files = HashMap<(UploadBucket, String), Vec<u8>>;
+
+/// Keeps the files in memory, for tests that don't want to record the
+/// requests to S3.
+///
+/// Clones share the same files, so a test can inspect what the application
+/// stored through its own handle.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryStorage {
+    files: Arc<Mutex<Files>>,
+}
+
+impl InMemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the paths of all stored files of the `bucket`, sorted.
+    pub fn paths(&self, bucket: UploadBucket) -> Vec<String> {
+        let files = self.files.lock().unwrap();
+        let mut paths: Vec<_> = files
+            .keys()
+            .filter(|(b, _)| *b == bucket)
+            .map(|(_, path)| path.clone())
+            .collect();
+        paths.sort();
+        paths
+    }
+}
+
+impl Storage for InMemoryStorage {
+    fn url(&self, path: &str) -> String {
+        format!("/{path}")
+    }
+
+    fn put(
+        &self,
+        _client: &Client,
+        path: &str,
+        mut content: Body,
+        _content_type: &str,
+        _extra_headers: HeaderMap,
+        bucket: UploadBucket,
+    ) -> Result<()> {
+        let content = content.buffer()?.to_vec();
+        let key = (bucket, path.to_string());
+        self.files.lock().unwrap().insert(key, content);
+        Ok(())
+    }
+
+    fn get(&self, _client: &Client, path: &str, bucket: UploadBucket) -> Result<Option<Vec<u8>>> {
+        let key = (bucket, path.to_string());
+        Ok(self.files.lock().unwrap().get(&key).cloned())
+    }
+
+    fn delete(&self, _client: &Client, path: &str, bucket: UploadBucket) -> Result<()> {
+        let key = (bucket, path.to_string());
+        self.files.lock().unwrap().remove(&key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn put_get_delete() {
+        let client = Client::new();
+        let storage = InMemoryStorage::new();
+        let put = |path: &str, content: &'static str, bucket| {
+            storage
+                .put(
+                    &client,
+                    path,
+                    content.into(),
+                    "text/plain",
+                    HeaderMap::new(),
+                    bucket,
+                )
+                .unwrap()
+        };
+
+        put("crates/foo/foo-1.0.0.crate", "crate", UploadBucket::Default);
+        put("3/f/foo", "index", UploadBucket::Index);
+
+        let get = |path: &str, bucket| storage.get(&client, path, bucket).unwrap();
+        assert_some_eq!(
+            get("crates/foo/foo-1.0.0.crate", UploadBucket::Default),
+            b"crate"
+        );
+        assert_none!(get("3/f/foo", UploadBucket::Default));
+        assert_some_eq!(get("3/f/foo", UploadBucket::Index), b"index");
+        assert_eq!(storage.paths(UploadBucket::Index), vec!["3/f/foo"]);
+
+        // Clones share the stored files.
+        storage
+            .clone()
+            .delete(&client, "3/f/foo", UploadBucket::Index)
+            .unwrap();
+        assert_none!(get("3/f/foo", UploadBucket::Index));
+        assert_eq!(storage.paths(UploadBucket::Index), Vec::<String>::new());
+    }
+
+    #[test]
+    fn multipart_upload() {
+        let client = Client::new();
+        let storage = InMemoryStorage::new();
+        let path = "crates/foo/foo-1.0.0.crate";
+
+        let upload_id = storage.start_multipart_upload(&client, path).unwrap();
+        let parts = [(1, "foo-"), (2, "bar")]
+            .into_iter()
+            .map(|(part_number, content)| {
+                let tag = storage
+                    .upload_part(&client, path, &upload_id, part_number, content.into())
+                    .unwrap();
+                (part_number, tag)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(storage.paths(UploadBucket::Default).len(), 2);
+
+        storage
+            .complete_multipart_upload(&client, path, &upload_id, &parts)
+            .unwrap();
+        assert_some_eq!(
+            storage.get(&client, path, UploadBucket::Default).unwrap(),
+            b"foo-bar"
+        );
+        // The parts are removed once they are concatenated.
+        assert_eq!(storage.paths(UploadBucket::Default), vec![path]);
+
+        let other = "crates/bar/bar-1.0.0.crate";
+        let upload_id = storage.start_multipart_upload(&client, other).unwrap();
+        let tag = storage
+            .upload_part(&client, other, &upload_id, 1, b"bar".to_vec())
+            .unwrap();
+        storage
+            .abort_multipart_upload(&client, other, &upload_id, &[(1, tag)])
+            .unwrap();
+        assert_eq!(storage.paths(UploadBucket::Default), vec![path]);
+    }
+}
@@ -0,0 +1,160 @@
+use super::{Storage, UploadBucket};
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use reqwest::blocking::{Body, Client};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+
+/// Stores the files in S3 buckets and serves them from there, or from a CDN
+/// in front of the default bucket.
+///
+/// For test usage with `TestApp::with_proxy()`, the recording proxy is used.
+#[derive(Clone, Debug)]
+pub struct S3Storage {
+    bucket: ::s3::Bucket,
+    index_bucket: Option<::s3::Bucket>,
+    cdn: Option<String>,
+    signed_urls: Option<Duration>,
+}
+
+impl S3Storage {
+    /// Without an `index_bucket`, the files of the HTTP-based index are not
+    /// stored at all.
+    pub fn new(
+        bucket: ::s3::Bucket,
+        index_bucket: Option<::s3::Bucket>,
+        cdn: Option<String>,
+    ) -> Self {
+        Self {
+            bucket,
+            index_bucket,
+            cdn,
+            signed_urls: None,
+        }
+    }
+
+    /// Serves the files of the default bucket through URLs that are signed
+    /// with the bucket's credentials and expire after `ttl`, so that the
+    /// bucket doesn't have to be public.
+    ///
+    /// The CDN is bypassed, since its URLs can't be signed with the bucket's
+    /// credentials.
+    pub fn with_signed_urls(mut self, ttl: Duration) -> Self {
+        self.signed_urls = Some(ttl);
+        self
+    }
+
+    fn bucket(&self, bucket: UploadBucket) -> Option<&::s3::Bucket> {
+        match bucket {
+            UploadBucket::Default => Some(&self.bucket),
+            UploadBucket::Index => self.index_bucket.as_ref(),
+        }
+    }
+}
+
+impl Storage for S3Storage {
+    fn url(&self, path: &str) -> String {
+        if let Some(ttl) = self.signed_urls {
+            let expires = (Utc::now() + ttl).timestamp();
+            return self.bucket.presigned_url(path, expires);
+        }
+
+        let host = match &self.cdn {
+            Some(cdn) => cdn.clone(),
+            None => self.bucket.host(),
+        };
+        format!("https://{host}/{path}")
+    }
+
+    fn put(
+        &self,
+        client: &Client,
+        path: &str,
+        content: Body,
+        content_type: &str,
+        extra_headers: HeaderMap,
+        bucket: UploadBucket,
+    ) -> Result<()> {
+        if let Some(bucket) = self.bucket(bucket) {
+            bucket.put(client, path, content, content_type, extra_headers)?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, client: &Client, path: &str, bucket: UploadBucket) -> Result<Option<Vec<u8>>> {
+        let response = match bucket {
+            // The default bucket is public, unless URLs are signed, so its
+            // files are downloaded through the same URLs that users download
+            // them from.
+            UploadBucket::Default if self.signed_urls.is_some() => self.bucket.get(client, path),
+            UploadBucket::Default => client.get(self.url(path)).send()?.error_for_status(),
+            UploadBucket::Index => match &self.index_bucket {
+                Some(index_bucket) => index_bucket.get(client, path),
+                None => return Ok(None),
+            },
+        };
+
+        match response {
+            Ok(response) => Ok(Some(response.bytes()?.to_vec())),
+            Err(e) if e.status() == Some(StatusCode::NOT_FOUND) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn delete(&self, client: &Client, path: &str, bucket: UploadBucket) -> Result<()> {
+        if let Some(bucket) = self.bucket(bucket) {
+            bucket.delete(client, path)?;
+        }
+        Ok(())
+    }
+
+    fn start_multipart_upload(&self, client: &Client, path: &str) -> Result<String> {
+        Ok(self.bucket.initiate_multipart_upload(client, path)?)
+    }
+
+    fn upload_part(
+        &self,
+        client: &Client,
+        path: &str,
+        upload_id: &str,
+        part_number: i32,
+        content: Vec<u8>,
+    ) -> Result<String> {
+        Ok(self
+            .bucket
+            .upload_part(client, path, upload_id, part_number, content)?)
+    }
+
+    fn complete_multipart_upload(
+        &self,
+        client: &Client,
+        path: &str,
+        upload_id: &str,
+        parts: &[(i32, String)],
+    ) -> Result<()> {
+        self.bucket
+            .complete_multipart_upload(client, path, upload_id, parts)?;
+        Ok(())
+    }
+
+    fn abort_multipart_upload(
+        &self,
+        client: &Client,
+        path: &str,
+        upload_id: &str,
+        _parts: &[(i32, String)],
+    ) -> Result<()> {
+        self.bucket
+            .abort_multipart_upload(client, path, upload_id)?;
+        Ok(())
+    }
+
+    fn min_part_size(&self) -> usize {
+        // Enforced by S3 when the upload is completed
+        5 * 1024 * 1024
+    }
+
+    fn has_index_bucket(&self) -> bool {
+        self.index_bucket.is_some()
+    }
+}
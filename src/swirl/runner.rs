@@ -309,6 +309,22 @@ mod tests {
         runner.wait_for_jobs().unwrap();
     }
 
+    #[test]
+    fn jobs_with_a_higher_priority_are_run_first() {
+        let _guard = TestGuard::lock();
+
+        let runner = runner();
+        create_dummy_job(&runner);
+        let urgent_job_id = create_dummy_job_with_priority(&runner, 10).id;
+
+        runner.get_single_job(dummy_sender(), move |job, _| {
+            assert_eq!(urgent_job_id, job.id);
+            Ok(())
+        });
+        runner.wait_for_jobs().unwrap();
+        runner.check_for_failed_jobs().unwrap();
+    }
+
     #[test]
     fn jobs_are_deleted_when_successfully_run() {
         let _guard = TestGuard::lock();
@@ -419,8 +435,19 @@ mod tests {
     }
 
     fn create_dummy_job(runner: &Runner) -> storage::BackgroundJob {
+        create_dummy_job_with_priority(runner, 0)
+    }
+
+    fn create_dummy_job_with_priority(
+        runner: &Runner,
+        job_priority: i16,
+    ) -> storage::BackgroundJob {
         diesel::insert_into(background_jobs)
-            .values((job_type.eq("Foo"), data.eq(serde_json::json!(null))))
+            .values((
+                job_type.eq("Foo"),
+                data.eq(serde_json::json!(null)),
+                priority.eq(job_priority),
+            ))
             .returning((id, job_type, data))
             .get_result(&mut *runner.connection().unwrap())
             .unwrap()
@@ -22,15 +22,16 @@ fn retriable() -> Box<dyn BoxableExpression<background_jobs::table, Pg, SqlType
     Box::new(last_retry.lt(now - 1.minute().into_sql::<Interval>() * power(2, retries)))
 }
 
-/// Finds the next job that is unlocked, and ready to be retried. If a row is
-/// found, it will be locked.
+/// Finds the next job that is unlocked, and ready to be retried. Jobs with a
+/// higher priority are found first, and jobs of the same priority in the order
+/// that they were enqueued. If a row is found, it will be locked.
 pub(super) fn find_next_unlocked_job(conn: &mut PgConnection) -> QueryResult<BackgroundJob> {
     use schema::background_jobs::dsl::*;
 
     background_jobs
         .select((id, job_type, data))
         .filter(retriable())
-        .order(id)
+        .order((priority.desc(), id))
         .for_update()
         .skip_locked()
         .first::<BackgroundJob>(conn)
@@ -0,0 +1,196 @@
+use crate::builders::CrateBuilder;
+use crate::util::{MockCookieUser, MockRequestExt, RequestHelper, Response, TestApp};
+use cargo_registry::models::{AccountDeletion, CrateOwner, OwnerKind};
+use cargo_registry::schema::{account_deletions, crate_owners, emails, users};
+use diesel::prelude::*;
+use http::StatusCode;
+use serde_json::Value;
+
+fn request_deletion(user: &MockCookieUser) -> Response<Value> {
+    let request = user.post_request("/api/v1/me/account_deletion");
+    user.run(request)
+}
+
+fn confirm_deletion(user: &MockCookieUser, token: &str) -> Response<Value> {
+    let url = format!("/api/v1/me/account_deletion/{token}");
+    user.put(&url, b"")
+}
+
+/// Returns the token of the latest confirmation email, since only its hash
+/// is stored.
+fn deletion_token(app: &TestApp) -> String {
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    let body = &emails.last().unwrap().body;
+    let (_, rest) = body.split_once("/confirm-account-deletion/").unwrap();
+    rest.split_whitespace().next().unwrap().to_string()
+}
+
+fn email_subjects(app: &TestApp) -> Vec<String> {
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    emails.into_iter().map(|email| email.subject).collect()
+}
+
+#[test]
+fn account_is_deleted_after_confirmation() {
+    let (app, anon, user) = TestApp::full().with_user();
+    let user_id = user.as_model().id;
+    let other = app.db_new_user("bar");
+    app.db(|conn| {
+        CrateBuilder::new("foo_sole_owned", user_id).expect_build(conn);
+        let krate = CrateBuilder::new("foo_shared", user_id).expect_build(conn);
+        let crate_owner = CrateOwner {
+            crate_id: krate.id,
+            owner_id: other.as_model().id,
+            created_by: user_id,
+            owner_kind: OwnerKind::User as i32,
+            email_notifications: true,
+        };
+        diesel::insert_into(crate_owners::table)
+            .values(&crate_owner)
+            .execute(conn)
+            .unwrap();
+    });
+
+    let json = request_deletion(&user).good();
+    assert_eq!(json["sole_owned_crates"], json!(["foo_sole_owned"]));
+    assert_eq!(
+        email_subjects(&app),
+        ["Please confirm the deletion of your crates.io account"]
+    );
+
+    // The deletion can only be confirmed with the emailed token
+    let response = confirm_deletion(&user, "invalid-token");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let token = deletion_token(&app);
+    let stored_token: Vec<u8> = app.db(|conn| {
+        account_deletions::table
+            .find(user_id)
+            .select(account_deletions::token)
+            .first(conn)
+            .unwrap()
+    });
+    assert_ne!(stored_token, token.as_bytes());
+    confirm_deletion(&user, &token).good();
+
+    // The account is locked right away
+    let response = user.get::<()>("/api/v1/me");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    app.run_pending_background_jobs();
+
+    let (login, name, email_count) = app.db(|conn| {
+        let (login, name) = users::table
+            .find(user_id)
+            .select((users::gh_login, users::name))
+            .first::<(String, Option<String>)>(conn)
+            .unwrap();
+        let email_count: i64 = emails::table
+            .filter(emails::user_id.eq(user_id))
+            .count()
+            .get_result(conn)
+            .unwrap();
+        (login, name, email_count)
+    });
+    assert_eq!(login, format!("deleted-user-{user_id}"));
+    assert_eq!(name, None);
+    assert_eq!(email_count, 0);
+
+    // The crates are kept, but the user is no longer one of their owners
+    let json = anon.show_crate_owners("foo_sole_owned");
+    assert!(json.users.is_empty());
+    let logins: Vec<_> = anon
+        .show_crate_owners("foo_shared")
+        .users
+        .into_iter()
+        .map(|owner| owner.login)
+        .collect();
+    assert_eq!(logins, ["bar"]);
+    anon.get::<Value>("/api/v1/crates/foo_sole_owned").good();
+
+    assert_eq!(
+        email_subjects(&app),
+        [
+            "Please confirm the deletion of your crates.io account",
+            "Your crates.io account was deleted",
+        ]
+    );
+}
+
+#[test]
+fn unconfirmed_deletion_is_not_performed() {
+    let (app, _, user) = TestApp::full().with_user();
+    let user_id = user.as_model().id;
+
+    request_deletion(&user).good();
+    let first_token = deletion_token(&app);
+
+    // A new request replaces the token of the earlier one
+    request_deletion(&user).good();
+    let response = confirm_deletion(&user, &first_token);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    app.db(|conn| {
+        cargo_registry::worker::delete_account(user_id)
+            .enqueue(conn)
+            .unwrap()
+    });
+    app.run_pending_background_jobs();
+
+    user.get::<Value>("/api/v1/me").good();
+    let deletion = app.db(|conn| {
+        account_deletions::table
+            .find(user_id)
+            .first::<AccountDeletion>(conn)
+            .unwrap()
+    });
+    assert_eq!(deletion.completed_at, None);
+}
+
+#[test]
+fn last_organization_admin_cannot_delete_account() {
+    let (_, _, user) = TestApp::init().with_user();
+
+    let body = json!({ "organization": { "name": "rust-lang" } });
+    let mut request = user.post_request("/api/v1/organizations");
+    request.with_body(body.to_string().as_bytes());
+    user.run::<Value>(request).good();
+
+    let response = request_deletion(&user);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "you are the last admin of the organizations rust-lang, please make another member an admin first" }] })
+    );
+}
+
+/// Test that the identity of a single sign-on user is removed, so that it can
+/// be provisioned again
+#[test]
+fn sso_identity_is_deleted() {
+    use crate::team::{sso_config, sso_log_in};
+    use cargo_registry::schema::sso_identities;
+
+    let (app, _) = TestApp::full()
+        .with_config(|config| config.sso = Some(sso_config()))
+        .empty();
+    let user = sso_log_in(&app, "alice", &["Engineering"]);
+    let user_id = user.as_model().id;
+
+    request_deletion(&user).good();
+    let token = deletion_token(&app);
+    confirm_deletion(&user, &token).good();
+    app.run_pending_background_jobs();
+
+    let identities: i64 = app.db(|conn| {
+        sso_identities::table
+            .filter(sso_identities::user_id.eq(user_id))
+            .count()
+            .get_result(conn)
+            .unwrap()
+    });
+    assert_eq!(identities, 0);
+
+    let again = sso_log_in(&app, "alice", &["Engineering"]);
+    assert_ne!(again.as_model().id, user_id);
+}
@@ -0,0 +1,328 @@
+use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::util::{MockCookieUser, RequestHelper, TestApp};
+use cargo_registry::models::account_deletion::DELETED_ACCOUNT_LOCK_REASON;
+use cargo_registry::models::{AdminAction, AdminPermission, AdminRole, Crate, User};
+use cargo_registry::schema::{admin_actions, api_tokens, index_inconsistencies, users};
+use diesel::prelude::*;
+use http::StatusCode;
+use serde_json::Value;
+
+fn grant_role(app: &TestApp, user: &MockCookieUser, permissions: &[AdminPermission]) {
+    app.db(|conn| {
+        let name = format!("{}-role", user.as_model().gh_login);
+        let role = AdminRole::create(conn, &name, permissions).unwrap();
+        role.assign(conn, user.as_model()).unwrap();
+    });
+}
+
+fn admin_actions(app: &TestApp) -> Vec<AdminAction> {
+    app.db(|conn| {
+        admin_actions::table
+            .order(admin_actions::id)
+            .load(conn)
+            .unwrap()
+    })
+}
+
+#[test]
+fn admin_deletes_crate() {
+    let (app, anon, _, token) = TestApp::full().with_token();
+    token.publish_crate(PublishBuilder::new("malware")).good();
+
+    let admin = app.db_new_user("admin");
+    grant_role(&app, &admin, &[AdminPermission::CratesDelete]);
+
+    admin
+        .delete::<Value>("/api/private/admin/crates/malware?reason=malware")
+        .good();
+    app.run_pending_background_jobs();
+
+    anon.get::<()>("/api/v1/crates/malware").assert_not_found();
+
+    let actions = admin_actions(&app);
+    assert_eq!(actions.len(), 1);
+    assert_some_eq!(actions[0].user_id, admin.as_model().id);
+    assert_eq!(actions[0].permission, "crates:delete");
+    assert_eq!(actions[0].target, "crate malware");
+    assert_some_eq!(actions[0].reason.as_deref(), "malware");
+}
+
+#[test]
+fn admin_action_requires_permission() {
+    let (app, anon, user, token) = TestApp::full().with_token();
+    token.publish_crate(PublishBuilder::new("foo")).good();
+
+    // Other permissions don't grant the action
+    let admin = app.db_new_user("admin");
+    grant_role(&app, &admin, &[AdminPermission::UsersSuspend]);
+
+    for requester in [&user, &admin] {
+        let response = requester.delete::<()>("/api/private/admin/crates/foo");
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            response.into_json(),
+            json!({ "errors": [{ "detail": "this action requires the `crates:delete` admin permission" }] })
+        );
+    }
+
+    let response = anon.delete::<()>("/api/private/admin/crates/foo");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Admin actions can't be taken with API tokens
+    grant_role(&app, &user, &[AdminPermission::CratesDelete]);
+    let response = token.delete::<()>("/api/private/admin/crates/foo");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    anon.get::<Value>("/api/v1/crates/foo").good();
+    assert!(admin_actions(&app).is_empty());
+}
+
+#[test]
+fn admin_sets_upload_limit() {
+    let (app, _, _, token) = TestApp::full().with_token();
+    token.publish_crate(PublishBuilder::new("bindings")).good();
+
+    let admin = app.db_new_user("admin");
+    grant_role(&app, &admin, &[AdminPermission::CratesLimits]);
+
+    let max_upload_size = |app: &TestApp| {
+        app.db(|conn| {
+            Crate::by_name("bindings")
+                .first::<Crate>(conn)
+                .unwrap()
+                .max_upload_size
+        })
+    };
+
+    let body = json!({ "max_upload_size": 50_000_000, "reason": "generated bindings" });
+    admin
+        .put::<Value>(
+            "/api/private/admin/crates/bindings/upload_limit",
+            body.to_string().as_bytes(),
+        )
+        .good();
+    assert_some_eq!(max_upload_size(&app), 50_000_000);
+
+    // Removing the override makes the global limit apply again
+    let body = json!({ "max_upload_size": null });
+    admin
+        .put::<Value>(
+            "/api/private/admin/crates/bindings/upload_limit",
+            body.to_string().as_bytes(),
+        )
+        .good();
+    assert_none!(max_upload_size(&app));
+
+    let actions = admin_actions(&app);
+    assert_eq!(actions.len(), 2);
+    assert_eq!(actions[0].permission, "crates:limits");
+    assert_eq!(actions[0].target, "crate bindings");
+    assert_some_eq!(actions[0].reason.as_deref(), "generated bindings");
+}
+
+#[test]
+fn admin_reserves_and_releases_crate_name() {
+    let (app, _, _, token) = TestApp::full().with_token();
+    let admin = app.db_new_user("admin");
+    grant_role(&app, &admin, &[AdminPermission::CratesReserve]);
+
+    admin
+        .put::<Value>(
+            "/api/private/admin/reserved_crate_names/my-reserved?reason=trademark",
+            b"",
+        )
+        .good();
+
+    let response = token.publish_crate(PublishBuilder::new("my_reserved"));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json()["errors"][0]["code"],
+        json!("reserved_name")
+    );
+
+    // Names of existing crates can't be reserved
+    token.publish_crate(PublishBuilder::new("foo")).good();
+    let response = admin.put::<()>("/api/private/admin/reserved_crate_names/foo", b"");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    admin
+        .delete::<Value>("/api/private/admin/reserved_crate_names/my_reserved")
+        .good();
+    token
+        .publish_crate(PublishBuilder::new("my_reserved"))
+        .good();
+
+    let response = admin.delete::<()>("/api/private/admin/reserved_crate_names/my_reserved");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let actions = admin_actions(&app);
+    assert_eq!(actions.len(), 2);
+    assert_eq!(actions[0].permission, "crates:reserve");
+    assert_eq!(actions[0].target, "crate name my-reserved");
+    assert_some_eq!(actions[0].reason.as_deref(), "trademark");
+    assert_eq!(actions[1].target, "crate name my-reserved");
+}
+
+#[test]
+fn admin_suspends_and_unsuspends_user() {
+    let (app, _, user, token) = TestApp::full().with_token();
+    let admin = app.db_new_user("admin");
+    grant_role(&app, &admin, &[AdminPermission::UsersSuspend]);
+
+    let body = json!({ "reason": "spam" });
+    admin
+        .put::<Value>(
+            "/api/private/admin/users/foo/suspension",
+            body.to_string().as_bytes(),
+        )
+        .good();
+
+    // Neither the session nor the API token of the user can be used anymore,
+    // e.g. to publish
+    let expected =
+        json!({ "errors": [{ "detail": "This account is indefinitely locked. Reason: spam" }] });
+    let response = user.get::<()>("/api/v1/me");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(response.into_json(), expected);
+    let response = token.publish_crate(PublishBuilder::new("foo_suspended"));
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(response.into_json(), expected);
+
+    admin
+        .delete::<Value>("/api/private/admin/users/foo/suspension")
+        .good();
+    user.get::<Value>("/api/v1/me").good();
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 2);
+    assert_eq!(emails[0].to, "something@example.com");
+    assert_eq!(emails[0].subject, "Your crates.io account was suspended");
+    assert!(emails[0].body.contains("until further notice"));
+    assert!(emails[0].body.contains("spam"));
+    assert_eq!(
+        emails[1].subject,
+        "Your crates.io account is no longer suspended"
+    );
+
+    let actions = admin_actions(&app);
+    assert_eq!(actions.len(), 2);
+    assert_eq!(actions[0].permission, "users:suspend");
+    assert_eq!(actions[0].target, "user foo");
+    assert_some_eq!(actions[0].reason.as_deref(), "spam");
+    assert_none!(&actions[1].reason);
+}
+
+#[test]
+fn suspension_requires_reason() {
+    let (app, _, user) = TestApp::init().with_user();
+    let admin = app.db_new_user("admin");
+    grant_role(&app, &admin, &[AdminPermission::UsersSuspend]);
+
+    let body = json!({ "reason": " " });
+    let response = admin.put::<()>(
+        "/api/private/admin/users/foo/suspension",
+        body.to_string().as_bytes(),
+    );
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    user.get::<Value>("/api/v1/me").good();
+    assert!(admin_actions(&app).is_empty());
+}
+
+#[test]
+fn deleted_accounts_are_not_suspended_or_unsuspended() {
+    let (app, _, user) = TestApp::init().with_user();
+    let admin = app.db_new_user("admin");
+    grant_role(&app, &admin, &[AdminPermission::UsersSuspend]);
+
+    app.db(|conn| {
+        diesel::update(users::table.find(user.as_model().id))
+            .set(users::account_lock_reason.eq(DELETED_ACCOUNT_LOCK_REASON))
+            .execute(conn)
+            .unwrap();
+    });
+
+    let url = "/api/private/admin/users/foo/suspension";
+    let response = admin.delete::<()>(url);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this account is not suspended" }] })
+    );
+
+    let body = json!({ "reason": "spam" });
+    let response = admin.put::<()>(url, body.to_string().as_bytes());
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this account is locked for another reason" }] })
+    );
+
+    let user = app.db(|conn| {
+        users::table
+            .find(user.as_model().id)
+            .first::<User>(conn)
+            .unwrap()
+    });
+    assert_some_eq!(user.account_lock_reason, DELETED_ACCOUNT_LOCK_REASON);
+    assert!(!user.account_suspended);
+    assert!(admin_actions(&app).is_empty());
+}
+
+#[test]
+fn admin_revokes_token() {
+    let (app, _, _, token) = TestApp::init().with_token();
+    let admin = app.db_new_user("admin");
+    grant_role(&app, &admin, &[AdminPermission::TokensRevoke]);
+
+    let token_id = token.as_model().id;
+    let url = format!("/api/private/admin/tokens/{token_id}?reason=leaked");
+    admin.delete::<Value>(&url).good();
+
+    let revoked: bool = app.db(|conn| {
+        api_tokens::table
+            .find(token_id)
+            .select(api_tokens::revoked)
+            .first(conn)
+            .unwrap()
+    });
+    assert!(revoked);
+
+    let actions = admin_actions(&app);
+    assert_eq!(actions.len(), 1);
+    assert_some_eq!(actions[0].user_id, admin.as_model().id);
+    assert_eq!(actions[0].permission, "tokens:revoke");
+}
+
+#[test]
+fn admin_lists_index_inconsistencies() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        let krate = CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+        diesel::insert_into(index_inconsistencies::table)
+            .values((
+                index_inconsistencies::crate_id.eq(krate.id),
+                index_inconsistencies::git_mismatch.eq(true),
+                index_inconsistencies::sparse_mismatch.eq(false),
+            ))
+            .execute(conn)
+            .unwrap();
+    });
+
+    let url = "/api/private/admin/index_inconsistencies";
+    let response = user.get::<()>(url);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let admin = app.db_new_user("admin");
+    grant_role(&app, &admin, &[AdminPermission::IntegrityRead]);
+
+    let json = admin.get::<Value>(url).good();
+    let inconsistencies = json["index_inconsistencies"].as_array().unwrap();
+    assert_eq!(inconsistencies.len(), 1);
+    assert_eq!(inconsistencies[0]["crate"], "foo");
+    assert_eq!(inconsistencies[0]["git_mismatch"], true);
+    assert_eq!(inconsistencies[0]["sparse_mismatch"], false);
+
+    // Listing doesn't change anything, so it isn't recorded
+    assert!(admin_actions(&app).is_empty());
+}
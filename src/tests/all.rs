@@ -11,7 +11,7 @@ extern crate tracing;
 
 use crate::util::{RequestHelper, TestApp};
 use cargo_registry::{
-    models::{Crate, CrateOwner, NewCategory, NewTeam, NewUser, Team, User},
+    models::{AccountProvider, Crate, CrateOwner, NewCategory, NewTeam, NewUser, Team, User},
     schema::crate_owners,
     views::{
         EncodableCategory, EncodableCategoryWithSubcategories, EncodableCrate, EncodableKeyword,
@@ -25,22 +25,34 @@ use std::{
 
 use diesel::prelude::*;
 
+mod account_deletion;
 mod account_lock;
+mod admin_roles;
 mod authentication;
 mod blocked_routes;
 mod builders;
 mod categories;
+mod cdn_invalidations;
+mod checksums;
+mod content_addressed_storage;
+mod crate_file_integrity;
+mod crate_transfer;
 mod dump_db;
 mod github_secret_scanning;
+mod index_signing;
 mod krate;
 mod middleware;
 mod not_found_error;
+mod organization;
 mod owners;
 mod pagination;
 mod read_only_mode;
+mod readmes;
 mod record;
+mod replication;
 mod routes;
 mod schema_details;
+mod search_backend;
 mod server;
 mod server_binary;
 mod team;
@@ -60,6 +72,7 @@ struct CrateMeta {
     total: i32,
     next_page: Option<String>,
     prev_page: Option<String>,
+    did_you_mean: Option<String>,
 }
 #[derive(Deserialize)]
 pub struct CrateResponse {
@@ -108,7 +121,8 @@ fn env(var: &str) -> String {
     }
 }
 
-static NEXT_GH_ID: AtomicUsize = AtomicUsize::new(0);
+// A `gh_id` of 0 is reserved for users without a GitHub account
+static NEXT_GH_ID: AtomicUsize = AtomicUsize::new(1);
 
 fn new_user(login: &str) -> NewUser<'_> {
     NewUser {
@@ -127,6 +141,7 @@ fn new_team(login: &str) -> NewTeam<'_> {
         login,
         name: None,
         avatar: None,
+        provider: AccountProvider::GitHub as i16,
     }
 }
 
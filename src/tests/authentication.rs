@@ -1,8 +1,9 @@
 use crate::util::{MockRequestExt, RequestHelper, Response};
 use crate::TestApp;
 
-use crate::util::encode_session_header;
+use crate::util::{encode_session_data, encode_session_header};
 use http::{header, Method, StatusCode};
+use std::collections::HashMap;
 
 static URL: &str = "/api/v1/me/updates";
 static MUST_LOGIN: &[u8] = br#"{"errors":[{"detail":"must be logged in to perform that action"}]}"#;
@@ -27,19 +28,35 @@ fn token_auth_cannot_find_token() {
     assert_eq!(response.into_json().to_string().as_bytes(), MUST_LOGIN);
 }
 
-// Ensure that an unexpected authentication error is available for logging.  The user would see
-// status 500 instead of 403 as in other authentication tests.  Due to foreign-key constraints in
-// the database, it is not possible to implement this same test for a token.
 #[test]
-fn cookie_auth_cannot_find_user() {
+fn cookie_auth_cannot_find_session() {
     let (app, anon) = TestApp::init().empty();
 
     let session_key = app.as_inner().session_key();
-    let cookie = encode_session_header(session_key, -1);
+    let cookie = encode_session_header(session_key, "csefake-session");
 
     let mut request = anon.request_builder(Method::GET, URL);
     request.header(header::COOKIE, &cookie);
+    let response: Response<()> = anon.run(request);
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(response.into_json().to_string().as_bytes(), MUST_LOGIN);
+}
+
+/// Cookies from before sessions were stored in the database only contain the
+/// ID of the user, and can't be revoked.
+#[test]
+fn cookie_auth_rejects_legacy_sessions() {
+    let (app, anon, user) = TestApp::init().with_user();
 
-    let error = anon.run::<()>(request);
-    assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let session_key = app.as_inner().session_key();
+    let session = HashMap::from([("user_id".to_string(), user.as_model().id.to_string())]);
+    let cookie = encode_session_data(session_key, &session);
+
+    let mut request = anon.request_builder(Method::GET, URL);
+    request.header(header::COOKIE, &cookie);
+    let response: Response<()> = anon.run(request);
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(response.into_json().to_string().as_bytes(), MUST_LOGIN);
 }
@@ -32,7 +32,9 @@ pub struct PublishBuilder {
     pub krate_name: String,
     license: Option<String>,
     license_file: Option<String>,
+    cksum: Option<String>,
     readme: Option<String>,
+    signature: Option<Vec<u8>>,
     tarball: Vec<u8>,
     version: semver::Version,
     features: BTreeMap<u::EncodableFeatureName, Vec<u::EncodableFeature>>,
@@ -51,7 +53,9 @@ impl PublishBuilder {
             krate_name: krate_name.into(),
             license: Some("MIT".to_string()),
             license_file: None,
+            cksum: None,
             readme: None,
+            signature: None,
             tarball: EMPTY_TARBALL_BYTES.to_vec(),
             version: semver::Version::parse("1.0.0").unwrap(),
             features: BTreeMap::new(),
@@ -148,6 +152,12 @@ impl PublishBuilder {
         self
     }
 
+    /// Set the license expression for this crate
+    pub fn license(mut self, license: &str) -> Self {
+        self.license = Some(license.into());
+        self
+    }
+
     /// Remove the license from this crate. Publish will fail unless license or license file is set.
     pub fn unset_license(mut self) -> Self {
         self.license = None;
@@ -160,6 +170,18 @@ impl PublishBuilder {
         self
     }
 
+    /// Set the tarball checksum that is sent along with the publish metadata
+    pub fn cksum(mut self, cksum: &str) -> Self {
+        self.cksum = Some(cksum.into());
+        self
+    }
+
+    /// Set the signature of the tarball that is appended to the publish body
+    pub fn signature(mut self, signature: &[u8]) -> Self {
+        self.signature = Some(signature.to_vec());
+        self
+    }
+
     // Adds a feature.
     pub fn feature(mut self, name: &str, values: &[&str]) -> Self {
         let values = values
@@ -195,15 +217,22 @@ impl PublishBuilder {
             license_file: self.license_file,
             repository: None,
             links: None,
+            cksum: self.cksum,
         };
 
         (serde_json::to_string(&new_crate).unwrap(), self.tarball)
     }
 
     /// Consume this builder to make the Put request body
-    pub fn body(self) -> Vec<u8> {
+    pub fn body(mut self) -> Vec<u8> {
+        let signature = self.signature.take();
         let (json, tarball) = self.build();
-        PublishBuilder::create_publish_body(&json, &tarball)
+        let mut body = PublishBuilder::create_publish_body(&json, &tarball);
+        if let Some(signature) = signature {
+            body.extend((signature.len() as u32).to_le_bytes());
+            body.extend(signature);
+        }
+        body
     }
 
     pub fn create_publish_body(json: &str, tarball: &[u8]) -> Vec<u8> {
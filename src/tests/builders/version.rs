@@ -20,6 +20,7 @@ pub struct VersionBuilder<'a> {
     yanked: bool,
     checksum: String,
     links: Option<String>,
+    rust_version: Option<&'a str>,
 }
 
 impl<'a> VersionBuilder<'a> {
@@ -45,6 +46,7 @@ impl<'a> VersionBuilder<'a> {
             yanked: false,
             checksum: String::new(),
             links: None,
+            rust_version: None,
         }
     }
 
@@ -60,6 +62,12 @@ impl<'a> VersionBuilder<'a> {
         self
     }
 
+    /// Sets the version's `rust_version` value.
+    pub fn rust_version(mut self, rust_version: &'a str) -> Self {
+        self.rust_version = Some(rust_version);
+        self
+    }
+
     /// Sets the version's `checksum` value.
     pub fn checksum(mut self, checksum: &str) -> Self {
         self.checksum = checksum.to_string();
@@ -112,6 +120,12 @@ impl<'a> VersionBuilder<'a> {
                 .get_result(connection)?;
         }
 
+        if let Some(rust_version) = self.rust_version {
+            vers = update(&vers)
+                .set(versions::rust_version.eq(rust_version))
+                .get_result(connection)?;
+        }
+
         if let Some(created_at) = self.created_at {
             vers = update(&vers)
                 .set(versions::created_at.eq(created_at))
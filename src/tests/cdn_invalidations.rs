@@ -0,0 +1,41 @@
+use crate::util::TestApp;
+use cargo_registry::schema::cdn_invalidations;
+use cargo_registry::worker::cdn::Cdn;
+use cargo_registry::worker::queue_cdn_invalidations;
+use diesel::prelude::*;
+use reqwest::blocking::Client;
+use std::sync::{Arc, Mutex};
+
+/// Records the batches of invalidated paths.
+#[derive(Clone, Default)]
+struct RecordingCdn(Arc<Mutex<Vec<Vec<String>>>>);
+
+impl Cdn for RecordingCdn {
+    fn name(&self) -> &'static str {
+        "Recording"
+    }
+
+    fn invalidate(&self, _client: &Client, paths: &[String]) -> anyhow::Result<()> {
+        self.0.lock().unwrap().push(paths.to_vec());
+        Ok(())
+    }
+}
+
+#[test]
+fn queued_invalidations_are_batched() {
+    let cdn = RecordingCdn::default();
+    let (app, _) = TestApp::full().with_cdn(cdn.clone()).empty();
+
+    app.db(|conn| {
+        let paths = ["re/ge/regex".to_string(), "/3/f/foo".to_string()];
+        queue_cdn_invalidations(conn, &paths).unwrap();
+        queue_cdn_invalidations(conn, &["re/ge/regex".to_string()]).unwrap();
+    });
+    app.run_pending_background_jobs();
+
+    let batches = cdn.0.lock().unwrap().clone();
+    assert_eq!(batches, vec![vec!["3/f/foo", "re/ge/regex"]]);
+
+    let queued: i64 = app.db(|conn| cdn_invalidations::table.count().get_result(conn).unwrap());
+    assert_eq!(queued, 0);
+}
@@ -0,0 +1,54 @@
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
+use cargo_registry::schema::{crates, versions};
+use cargo_registry::storage::{InMemoryStorage, Storage, UploadBucket};
+use cargo_registry::{worker, Uploader};
+use diesel::prelude::*;
+use reqwest::blocking::Client;
+
+#[test]
+fn missing_checksums_are_backfilled() {
+    let client = Client::new();
+    let storage = InMemoryStorage::new();
+    let uploader = Uploader::new(storage.clone());
+
+    let (app, _, _, token) = TestApp::full()
+        .with_config(|config| config.base.set_uploader(uploader))
+        .with_token();
+
+    for name in ["foo", "bar"] {
+        token.publish_crate(PublishBuilder::new(name)).good();
+    }
+    app.run_pending_background_jobs();
+
+    let checksum = |name: &str| {
+        app.db(|conn| {
+            versions::table
+                .inner_join(crates::table)
+                .filter(crates::name.eq(name))
+                .select(versions::checksum)
+                .first::<String>(conn)
+                .unwrap()
+        })
+    };
+    let foo_checksum = checksum("foo");
+    assert!(!foo_checksum.is_empty());
+
+    // The crate file of `bar` is missing, so its checksum can't be backfilled
+    storage
+        .delete(&client, "crates/bar/bar-1.0.0.crate", UploadBucket::Default)
+        .unwrap();
+
+    app.db(|conn| {
+        diesel::update(versions::table)
+            .set(versions::checksum.eq(""))
+            .execute(conn)
+            .unwrap();
+        worker::backfill_checksums(0).enqueue(conn).unwrap();
+    });
+    app.run_pending_background_jobs();
+
+    assert_eq!(checksum("foo"), foo_checksum);
+    // The column is a fixed length `CHAR`, so the empty checksum is padded
+    assert_eq!(checksum("bar").trim(), "");
+}
@@ -0,0 +1,45 @@
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
+use cargo_registry::schema::crate_files;
+use cargo_registry::storage::{InMemoryStorage, Storage, UploadBucket};
+use cargo_registry::Uploader;
+use diesel::prelude::*;
+use reqwest::blocking::Client;
+use serde_json::Value;
+
+#[test]
+fn crate_files_are_stored_under_their_checksum() {
+    let client = Client::new();
+    let storage = InMemoryStorage::new();
+    let uploader = Uploader::new(storage.clone()).with_content_addressed_layout();
+
+    let (app, anon, _, token) = TestApp::full()
+        .with_config(|config| config.base.set_uploader(uploader))
+        .with_token();
+
+    token.publish_crate(PublishBuilder::new("foo")).good();
+
+    let object_key: String = app.db(|conn| {
+        crate_files::table
+            .select(crate_files::object_key)
+            .first(conn)
+            .unwrap()
+    });
+    assert!(object_key.starts_with("crates/sha256/"));
+    assert_some!(storage
+        .get(&client, &object_key, UploadBucket::Default)
+        .unwrap());
+    assert_none!(storage
+        .get(&client, "crates/foo/foo-1.0.0.crate", UploadBucket::Default)
+        .unwrap());
+
+    anon.get::<()>("/api/v1/crates/foo/1.0.0/download")
+        .assert_redirect_ends_with(&object_key);
+
+    token.delete::<Value>("/api/v1/crates/foo").good();
+    app.run_pending_background_jobs();
+
+    assert_none!(storage
+        .get(&client, &object_key, UploadBucket::Default)
+        .unwrap());
+}
@@ -0,0 +1,85 @@
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
+use cargo_registry::models::{AdminPermission, AdminRole};
+use cargo_registry::schema::{crate_file_checks, crates, versions};
+use cargo_registry::storage::{InMemoryStorage, Storage, UploadBucket};
+use cargo_registry::{worker, Uploader};
+use diesel::prelude::*;
+use reqwest::blocking::Client;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+
+#[test]
+fn missing_and_corrupted_crate_files_are_recorded() {
+    let client = Client::new();
+    let storage = InMemoryStorage::new();
+    let uploader = Uploader::new(storage.clone());
+
+    let (app, _, _, token) = TestApp::full()
+        .with_config(|config| config.base.set_uploader(uploader))
+        .with_token();
+
+    for name in ["foo", "bar", "baz"] {
+        token.publish_crate(PublishBuilder::new(name)).good();
+    }
+
+    storage
+        .put(
+            &client,
+            "crates/bar/bar-1.0.0.crate",
+            "corrupted".into(),
+            "application/gzip",
+            HeaderMap::new(),
+            UploadBucket::Default,
+        )
+        .unwrap();
+    storage
+        .delete(&client, "crates/baz/baz-1.0.0.crate", UploadBucket::Default)
+        .unwrap();
+
+    app.db(|conn| worker::verify_crate_files(10).enqueue(conn).unwrap());
+    app.run_pending_background_jobs();
+
+    let checks: Vec<(String, bool, bool)> = app.db(|conn| {
+        crate_file_checks::table
+            .inner_join(versions::table.inner_join(crates::table))
+            .select((
+                crates::name,
+                crate_file_checks::missing,
+                crate_file_checks::checksum_mismatch,
+            ))
+            .order(crates::name)
+            .load(conn)
+            .unwrap()
+    });
+    assert_eq!(
+        checks,
+        vec![
+            ("bar".to_string(), false, true),
+            ("baz".to_string(), true, false),
+            ("foo".to_string(), false, false),
+        ]
+    );
+
+    let admin = app.db_new_user("admin");
+    app.db(|conn| {
+        let role = AdminRole::create(conn, "integrity", &[AdminPermission::IntegrityRead]).unwrap();
+        role.assign(conn, admin.as_model()).unwrap();
+    });
+
+    let json = admin
+        .get::<Value>("/api/private/admin/crate_file_checks")
+        .good();
+    let failed = json["crate_file_checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|check| {
+            (
+                check["crate"].as_str().unwrap(),
+                check["version"].as_str().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(failed, vec![("bar", "1.0.0"), ("baz", "1.0.0")]);
+}
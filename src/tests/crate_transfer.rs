@@ -0,0 +1,205 @@
+use crate::builders::CrateBuilder;
+use crate::util::{MockCookieUser, MockRequestExt, RequestHelper, Response, TestApp};
+use cargo_registry::models::{CrateTransfer, TransferCompletion};
+use cargo_registry::schema::crate_transfers;
+use cargo_registry::worker;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use http::StatusCode;
+use serde_json::Value;
+
+fn request_transfer(user: &MockCookieUser, crate_name: &str, to: &str) -> Response<Value> {
+    let body = json!({ "transfer": { "to": to } });
+    let mut request = user.post_request(&format!("/api/v1/crates/{crate_name}/transfers"));
+    request.with_body(body.to_string().as_bytes());
+    user.run(request)
+}
+
+fn handle_transfer(user: &MockCookieUser, transfer_id: i64, accepted: bool) -> Value {
+    let body = json!({ "crate_transfer": { "accepted": accepted } });
+    let url = format!("/api/v1/me/crate_transfers/{transfer_id}");
+    user.put::<Value>(&url, &serde_json::to_vec(&body).unwrap())
+        .good()
+}
+
+fn owner_logins(user: &MockCookieUser, crate_name: &str) -> Vec<String> {
+    user.show_crate_owners(crate_name)
+        .users
+        .into_iter()
+        .map(|owner| owner.login)
+        .collect()
+}
+
+fn email_subjects(app: &TestApp) -> Vec<String> {
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    emails.into_iter().map(|email| email.subject).collect()
+}
+
+#[test]
+fn transfer_is_completed_after_the_waiting_period() {
+    let (app, anon, user) = TestApp::full().with_user();
+    let recipient = app.db_new_user("bar");
+    app.db(|conn| {
+        CrateBuilder::new("foo_transfer", user.as_model().id).expect_build(conn);
+    });
+
+    let json = request_transfer(&user, "foo_transfer", "bar").good();
+    let transfer_id = json["transfer"]["id"].as_i64().unwrap();
+    assert_eq!(email_subjects(&app), ["Crate ownership transfer"]);
+
+    // Both users see the pending transfer
+    for user in [&user, &recipient] {
+        let json = user.get::<Value>("/api/v1/me/crate_transfers").good();
+        assert_eq!(json["crate_transfers"][0]["crate_name"], "foo_transfer");
+    }
+
+    // Only the recipient can accept the transfer
+    let body = json!({ "crate_transfer": { "accepted": true } });
+    let url = format!("/api/v1/me/crate_transfers/{transfer_id}");
+    let response = user.put::<()>(&url, &serde_json::to_vec(&body).unwrap());
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let json = handle_transfer(&recipient, transfer_id, true);
+    assert!(json["crate_transfer"]["completes_at"].is_string());
+
+    let complete_transfers = || {
+        app.db(|conn| worker::complete_crate_transfers().enqueue(conn).unwrap());
+        app.run_pending_background_jobs();
+    };
+
+    // Nothing changes during the waiting period
+    complete_transfers();
+    assert_eq!(owner_logins(&user, "foo_transfer"), ["foo"]);
+
+    app.db(|conn| {
+        let completes_at = (Utc::now() - Duration::minutes(1)).naive_utc();
+        diesel::update(crate_transfers::table)
+            .set(crate_transfers::completes_at.eq(completes_at))
+            .execute(conn)
+            .unwrap();
+    });
+    complete_transfers();
+    assert_eq!(owner_logins(&user, "foo_transfer"), ["bar"]);
+
+    let json = anon
+        .get::<Value>("/api/v1/crates/foo_transfer/transfers")
+        .good();
+    let transfers = json["transfers"].as_array().unwrap();
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(
+        transfers[0]["to_user_id"].as_i64().unwrap(),
+        recipient.as_model().id as i64
+    );
+    assert!(transfers[0]["completed_at"].is_string());
+
+    assert_eq!(
+        email_subjects(&app),
+        [
+            "Crate ownership transfer",
+            "Crate ownership transfer accepted",
+            "Crate ownership transfer completed",
+            "Crate ownership transfer completed",
+        ]
+    );
+}
+
+#[test]
+fn invalid_transfers_are_rejected() {
+    let (app, _, user) = TestApp::init().with_user();
+    let other = app.db_new_user("bar");
+    app.db_new_user("baz");
+    app.db(|conn| {
+        CrateBuilder::new("foo_transfer", user.as_model().id).expect_build(conn);
+    });
+
+    let response = request_transfer(&other, "foo_transfer", "baz");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only individual owners of a crate can transfer their ownership" }] })
+    );
+
+    let response = request_transfer(&user, "foo_transfer", "foo");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "`foo` is already an owner of this crate" }] })
+    );
+
+    let response = request_transfer(&user, "foo_transfer", "missing");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    request_transfer(&user, "foo_transfer", "bar").good();
+    let response = request_transfer(&user, "foo_transfer", "baz");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "there already is a pending ownership transfer of this crate" }] })
+    );
+}
+
+#[test]
+fn declined_and_cancelled_transfers_keep_the_owners() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let recipient = app.db_new_user("bar");
+    app.db(|conn| {
+        CrateBuilder::new("foo_transfer", user.as_model().id).expect_build(conn);
+    });
+
+    let json = request_transfer(&user, "foo_transfer", "bar").good();
+    let transfer_id = json["transfer"]["id"].as_i64().unwrap();
+    handle_transfer(&recipient, transfer_id, false);
+
+    // Declined transfers can't be accepted anymore
+    let body = json!({ "crate_transfer": { "accepted": true } });
+    let url = format!("/api/v1/me/crate_transfers/{transfer_id}");
+    let response = recipient.put::<()>(&url, &serde_json::to_vec(&body).unwrap());
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // The owners can cancel accepted transfers during the waiting period
+    let json = request_transfer(&user, "foo_transfer", "bar").good();
+    let transfer_id = json["transfer"]["id"].as_i64().unwrap();
+    handle_transfer(&recipient, transfer_id, true);
+
+    let url = format!("/api/v1/crates/foo_transfer/transfers/{transfer_id}");
+    let response = recipient.delete::<()>(&url);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only owners have permission to modify owners" }] })
+    );
+    user.delete::<Value>(&url).good();
+
+    let json = recipient.get::<Value>("/api/v1/me/crate_transfers").good();
+    assert_eq!(json["crate_transfers"].as_array().unwrap().len(), 0);
+
+    let json = anon
+        .get::<Value>("/api/v1/crates/foo_transfer/transfers")
+        .good();
+    assert_eq!(json["transfers"].as_array().unwrap().len(), 0);
+    assert_eq!(owner_logins(&user, "foo_transfer"), ["foo"]);
+}
+
+#[test]
+fn transfers_that_are_no_longer_pending_are_not_changed() {
+    let (app, _, user) = TestApp::init().with_user();
+    let recipient = app.db_new_user("bar");
+    app.db(|conn| {
+        CrateBuilder::new("foo_transfer", user.as_model().id).expect_build(conn);
+    });
+
+    let json = request_transfer(&user, "foo_transfer", "bar").good();
+    let transfer_id = json["transfer"]["id"].as_i64().unwrap() as i32;
+    let transfer = app.db(|conn| assert_ok!(CrateTransfer::find_pending(conn, transfer_id)));
+    handle_transfer(&recipient, transfer_id.into(), false);
+
+    // A concurrent request or the worker may still hold the transfer from
+    // before it was declined
+    app.db(|conn| {
+        assert_err!(transfer.accept(conn, Duration::hours(1)));
+        assert!(!assert_ok!(transfer.cancel(conn)));
+        assert_eq!(
+            assert_ok!(transfer.complete(conn)),
+            TransferCompletion::NoLongerPending
+        );
+    });
+    assert_eq!(owner_logins(&user, "foo_transfer"), ["foo"]);
+}
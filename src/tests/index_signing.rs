@@ -0,0 +1,104 @@
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
+use cargo_registry::storage::InMemoryStorage;
+use cargo_registry::Uploader;
+use cargo_registry_index::testing::UpstreamIndex;
+use cargo_registry_index::{
+    Credentials, Repository, RepositoryConfig, SigningFormat, SigningKey, VerifyingKey,
+};
+use std::fs;
+use std::process::Command;
+
+/// Generates an SSH key pair and returns the private and the public key.
+fn ssh_key_pair() -> (String, String) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("key");
+    let status = Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-C", "index", "-f"])
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let private_key = fs::read_to_string(&path).unwrap();
+    let public_key = fs::read_to_string(path.with_extension("pub")).unwrap();
+    (private_key, public_key)
+}
+
+fn verifying_key(key: String) -> VerifyingKey {
+    VerifyingKey {
+        format: SigningFormat::Ssh,
+        key,
+    }
+}
+
+fn clone_index() -> Repository {
+    Repository::open(&RepositoryConfig {
+        index_location: UpstreamIndex::url(),
+        credentials: Credentials::Missing,
+        signing_key: None,
+    })
+    .unwrap()
+}
+
+/// Publishes two crates and returns a clone of the upstream index, and the
+/// number of commits that the background jobs created in it.
+fn publish_and_clone_index(app: &TestApp, token: &impl RequestHelper) -> (Repository, usize) {
+    let initial_commits = clone_index().commit_count().unwrap();
+
+    token.publish_crate(PublishBuilder::new("foo")).good();
+    token.publish_crate(PublishBuilder::new("bar")).good();
+    app.run_pending_background_jobs();
+
+    let repo = clone_index();
+    let new_commits = repo.commit_count().unwrap() - initial_commits;
+    assert!(new_commits >= 2);
+    (repo, new_commits)
+}
+
+#[test]
+fn index_commits_are_signed() {
+    let (private_key, public_key) = ssh_key_pair();
+    let (_, other_public_key) = ssh_key_pair();
+
+    let (app, _, _, token) = TestApp::full()
+        .with_config(|config| {
+            config
+                .base
+                .set_uploader(Uploader::new(InMemoryStorage::new()))
+        })
+        .with_index_signing_key(SigningKey {
+            format: SigningFormat::Ssh,
+            key: private_key,
+        })
+        .with_token();
+
+    let (repo, new_commits) = publish_and_clone_index(&app, &token);
+
+    let key = verifying_key(public_key);
+    assert_eq!(repo.unverified_commits(&key, new_commits).unwrap(), vec![]);
+
+    let other_key = verifying_key(other_public_key);
+    let unverified = repo.unverified_commits(&other_key, new_commits).unwrap();
+    assert_eq!(unverified.len(), new_commits);
+}
+
+#[test]
+fn unsigned_index_commits_are_unverified() {
+    let (_, public_key) = ssh_key_pair();
+
+    let (app, _, _, token) = TestApp::full()
+        .with_config(|config| {
+            config
+                .base
+                .set_uploader(Uploader::new(InMemoryStorage::new()))
+        })
+        .with_token();
+
+    let (repo, new_commits) = publish_and_clone_index(&app, &token);
+
+    let unverified = repo
+        .unverified_commits(&verifying_key(public_key), new_commits)
+        .unwrap();
+    assert_eq!(unverified.len(), new_commits);
+}
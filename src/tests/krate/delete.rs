@@ -0,0 +1,194 @@
+use crate::builders::{CrateBuilder, PublishBuilder, VersionBuilder};
+use crate::util::{RequestHelper, TestApp};
+use cargo_registry::models::token::EndpointScope;
+use cargo_registry::models::CrateDeletion;
+use cargo_registry::schema::crate_deletions;
+use cargo_registry::storage::{InMemoryStorage, UploadBucket};
+use cargo_registry::Uploader;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use http::StatusCode;
+use serde_json::Value;
+
+#[test]
+fn delete_version() {
+    let (app, anon, _, token) = TestApp::full().with_token();
+
+    token.publish_crate(PublishBuilder::new("fyk_max")).good();
+    let crate_to_publish = PublishBuilder::new("fyk_max").version("2.0.0");
+    token.publish_crate(crate_to_publish).good();
+
+    token.delete::<Value>("/api/v1/crates/fyk_max/2.0.0").good();
+    app.run_pending_background_jobs();
+
+    let response = anon.get::<()>("/api/v1/crates/fyk_max/2.0.0");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "crate `fyk_max` does not have a version `2.0.0`" }] })
+    );
+    let json = anon.show_crate("fyk_max");
+    assert_eq!(json.krate.max_version, "1.0.0");
+
+    let crates = app.crates_from_index_head("fyk_max");
+    assert_eq!(crates.len(), 1);
+    assert_eq!(crates[0].vers, "1.0.0");
+
+    let deletions: Vec<CrateDeletion> = app.db(|conn| crate_deletions::table.load(conn).unwrap());
+    assert_eq!(deletions.len(), 1);
+    assert_eq!(deletions[0].crate_name, "fyk_max");
+    assert_some_eq!(deletions[0].version.as_deref(), "2.0.0");
+    assert_eq!(deletions[0].deleted_by, token.as_model().user_id);
+}
+
+#[test]
+fn delete_crate() {
+    let (app, anon, _, token) = TestApp::full().with_token();
+
+    token.publish_crate(PublishBuilder::new("fyk_max")).good();
+
+    token.delete::<Value>("/api/v1/crates/fyk_max").good();
+    app.run_pending_background_jobs();
+
+    anon.get::<()>("/api/v1/crates/fyk_max").assert_not_found();
+    assert_err!(app.upstream_index().crates_from_index_head("fyk_max"));
+
+    let deletions: Vec<CrateDeletion> = app.db(|conn| crate_deletions::table.load(conn).unwrap());
+    assert_eq!(deletions.len(), 1);
+    assert_eq!(deletions[0].crate_name, "fyk_max");
+    assert_none!(&deletions[0].version);
+}
+
+#[test]
+fn delete_version_removes_files_after_commit() {
+    let storage = InMemoryStorage::new();
+    let uploader = Uploader::new(storage.clone());
+
+    let (app, _, _, token) = TestApp::full()
+        .with_config(|config| config.base.set_uploader(uploader))
+        .with_token();
+
+    token.publish_crate(PublishBuilder::new("foo")).good();
+    token
+        .publish_crate(PublishBuilder::new("foo").version("2.0.0"))
+        .good();
+
+    let crate_file = "crates/foo/foo-2.0.0.crate".to_string();
+    assert!(storage.paths(UploadBucket::Default).contains(&crate_file));
+
+    token.delete::<Value>("/api/v1/crates/foo/2.0.0").good();
+    assert!(storage.paths(UploadBucket::Default).contains(&crate_file));
+
+    app.run_pending_background_jobs();
+    let paths = storage.paths(UploadBucket::Default);
+    assert!(!paths.contains(&crate_file));
+    assert!(paths.contains(&"crates/foo/foo-1.0.0.crate".to_string()));
+}
+
+#[test]
+fn delete_requires_delete_scope() {
+    let (app, _, user, _) = TestApp::full().with_token();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id)
+            .version("1.0.0")
+            .version("1.1.0")
+            .expect_build(conn);
+    });
+
+    let yank_token = user.db_new_scoped_token("yank", None, Some(vec![EndpointScope::Yank]));
+    let response = yank_token.delete::<()>("/api/v1/crates/foo/1.1.0");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = yank_token.delete::<()>("/api/v1/crates/foo");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let delete_token = user.db_new_scoped_token("delete", None, Some(vec![EndpointScope::Delete]));
+    delete_token.delete::<Value>("/api/v1/crates/foo/1.1.0").good();
+}
+
+#[test]
+fn delete_version_after_grace_period() {
+    let (app, _, user, token) = TestApp::full().with_token();
+
+    let published_at = (Utc::now() - Duration::days(4)).naive_utc();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id)
+            .version("1.0.0")
+            .version(VersionBuilder::new("1.1.0").created_at(published_at))
+            .expect_build(conn);
+    });
+
+    let response = token.delete::<()>("/api/v1/crates/foo/1.1.0");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "crates and versions can only be deleted within 72 hours after publishing, consider yanking instead" }] })
+    );
+}
+
+#[test]
+fn delete_only_version() {
+    let (app, _, user, token) = TestApp::full().with_token();
+
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id)
+            .version("1.0.0")
+            .expect_build(conn);
+    });
+
+    let response = token.delete::<()>("/api/v1/crates/foo/1.0.0");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "`foo@1.0.0` is the only version of the crate, delete the crate instead" }] })
+    );
+}
+
+#[test]
+fn delete_with_reverse_dependencies() {
+    let (app, _, user, token) = TestApp::full().with_token();
+    let user_id = user.as_model().id;
+
+    app.db(|conn| {
+        let foo = CrateBuilder::new("foo", user_id)
+            .version("1.0.0")
+            .version("1.1.0")
+            .expect_build(conn);
+        CrateBuilder::new("bar", user_id)
+            .version(VersionBuilder::new("1.0.0").dependency(&foo, None))
+            .expect_build(conn);
+    });
+
+    let response = token.delete::<()>("/api/v1/crates/foo/1.1.0");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "cannot delete `foo@1.1.0` because other crates depend on it" }] })
+    );
+
+    let response = token.delete::<()>("/api/v1/crates/foo");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "cannot delete `foo` because other crates depend on it" }] })
+    );
+}
+
+#[test]
+fn delete_by_a_non_owner() {
+    let (app, _, _, token) = TestApp::full().with_token();
+
+    let another_user = app.db_new_user("bar");
+    app.db(|conn| {
+        CrateBuilder::new("foo", another_user.as_model().id)
+            .version("1.0.0")
+            .version("1.1.0")
+            .expect_build(conn);
+    });
+
+    let response = token.delete::<()>("/api/v1/crates/foo/1.1.0");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "must already be an owner to delete a version" }] })
+    );
+
+    let response = token.delete::<()>("/api/v1/crates/foo");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "must be an owner to delete a crate" }] })
+    );
+}
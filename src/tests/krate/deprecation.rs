@@ -0,0 +1,155 @@
+use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::util::{RequestHelper, Response, TestApp};
+use cargo_registry_index::Deprecation;
+use http::StatusCode;
+use serde_json::Value;
+
+fn deprecate_crate(user: &impl RequestHelper, crate_name: &str, body: Value) -> Response<Value> {
+    let path = format!("/api/v1/crates/{crate_name}/deprecation");
+    user.put(&path, body.to_string().as_bytes())
+}
+
+fn deprecate_version(
+    user: &impl RequestHelper,
+    crate_name: &str,
+    version: &str,
+    body: Value,
+) -> Response<Value> {
+    let path = format!("/api/v1/crates/{crate_name}/{version}/deprecation");
+    user.put(&path, body.to_string().as_bytes())
+}
+
+#[test]
+fn deprecate_crate_and_version() {
+    let (app, anon, user, token) = TestApp::full().with_token();
+    app.db(|conn| {
+        CrateBuilder::new("fyk-ng", user.as_model().id).expect_build(conn);
+    });
+
+    token.publish_crate(PublishBuilder::new("fyk")).good();
+
+    let body =
+        json!({ "deprecated": true, "message": "use fyk-ng instead", "successor": "fyk_ng" });
+    deprecate_crate(&token, "fyk", body).good();
+    app.run_pending_background_jobs();
+
+    let json = anon.show_crate("fyk");
+    assert!(json.krate.deprecated);
+    assert_some_eq!(json.krate.deprecation_message, "use fyk-ng instead");
+    assert_some_eq!(json.krate.deprecation_successor, "fyk-ng");
+
+    let crates = app.crates_from_index_head("fyk");
+    let deprecation = Deprecation {
+        message: Some("use fyk-ng instead".into()),
+        successor: Some("fyk-ng".into()),
+    };
+    assert_eq!(crates[0].deprecated, Some(deprecation));
+
+    // The message of a version takes precedence over the message of the crate
+    let body = json!({ "deprecated": true, "message": "contains a soundness bug" });
+    deprecate_version(&token, "fyk", "1.0.0", body).good();
+    app.run_pending_background_jobs();
+
+    let json = anon.show_version("fyk", "1.0.0");
+    assert!(json.version.deprecated);
+    assert_some_eq!(json.version.deprecation_message, "contains a soundness bug");
+
+    let crates = app.crates_from_index_head("fyk");
+    assert_some_eq!(
+        crates[0].deprecated.as_ref().unwrap().message.as_deref(),
+        "contains a soundness bug"
+    );
+
+    // Undeprecating the crate keeps the deprecation of the version
+    deprecate_crate(&token, "fyk", json!({ "deprecated": false })).good();
+    app.run_pending_background_jobs();
+
+    let json = anon.show_crate("fyk");
+    assert!(!json.krate.deprecated);
+    assert_none!(json.krate.deprecation_message);
+    assert_none!(json.krate.deprecation_successor);
+
+    let crates = app.crates_from_index_head("fyk");
+    let deprecation = Deprecation {
+        message: Some("contains a soundness bug".into()),
+        successor: None,
+    };
+    assert_eq!(crates[0].deprecated, Some(deprecation));
+
+    deprecate_version(&token, "fyk", "1.0.0", json!({ "deprecated": false })).good();
+    app.run_pending_background_jobs();
+
+    let json = anon.show_version("fyk", "1.0.0");
+    assert!(!json.version.deprecated);
+    assert_none!(json.version.deprecation_message);
+
+    let crates = app.crates_from_index_head("fyk");
+    assert_none!(&crates[0].deprecated);
+}
+
+#[test]
+fn successor_must_exist() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+
+    let body = json!({ "deprecated": true, "successor": "bar" });
+    let response = deprecate_crate(&user, "foo", body);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the successor crate `bar` does not exist" }] })
+    );
+
+    let body = json!({ "deprecated": true, "successor": "foo" });
+    let response = deprecate_crate(&user, "foo", body);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "a crate can not be its own successor" }] })
+    );
+
+    let json = user.show_crate("foo");
+    assert!(!json.krate.deprecated);
+}
+
+#[test]
+fn deprecation_message_is_limited() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+
+    let body = json!({ "deprecated": true, "message": "a".repeat(257) });
+    let response = deprecate_version(&user, "foo", "0.99.0", body);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the deprecation message must not be longer than 256 characters" }] })
+    );
+}
+
+#[test]
+fn only_owners_can_deprecate() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+
+    let other = app.db_new_user("other");
+
+    let response = deprecate_crate(&other, "foo", json!({ "deprecated": true }));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "must already be an owner to deprecate a crate" }] })
+    );
+
+    let response = deprecate_version(&other, "foo", "0.99.0", json!({ "deprecated": true }));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "must already be an owner to deprecate a version" }] })
+    );
+}
@@ -1,4 +1,11 @@
+mod delete;
+mod deprecation;
 mod following;
 mod publish;
+mod publish_2fa;
+mod publish_multipart;
+mod staged;
+mod upload;
 mod versions;
+mod webhooks;
 mod yanking;
@@ -1,4 +1,4 @@
-use crate::builders::{CrateBuilder, DependencyBuilder, PublishBuilder};
+use crate::builders::{CrateBuilder, DependencyBuilder, PublishBuilder, VersionBuilder};
 use crate::new_category;
 use crate::util::{RequestHelper, TestApp};
 use cargo_registry::controllers::krate::publish::{
@@ -112,6 +112,29 @@ fn invalid_names() {
     bad_name("coMpiLer_Rt", error_message);
 }
 
+#[test]
+fn new_krate_with_newly_reserved_name() {
+    use cargo_registry::schema::reserved_crate_names;
+    use diesel::insert_into;
+
+    let (app, _, user) = TestApp::full().with_user();
+
+    app.db(|conn| {
+        insert_into(reserved_crate_names::table)
+            .values(reserved_crate_names::name.eq("my-reserved"))
+            .execute(conn)
+            .unwrap();
+    });
+
+    let crate_to_publish = PublishBuilder::new("My_Reserved").version("1.0.0");
+    let response = user.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "cannot upload a crate with a reserved name", "code": "reserved_name" }] })
+    );
+}
+
 #[test]
 fn new_krate() {
     let (_, _, user) = TestApp::full().with_user();
@@ -134,6 +157,53 @@ fn new_krate_with_token() {
     assert_eq!(json.krate.max_version, "1.0.0");
 }
 
+#[test]
+fn new_krate_with_matching_cksum() {
+    let (_, _, _, token) = TestApp::full().with_token();
+
+    let cksum = "acb5604b126ac894c1eb11c4575bf2072fea61232a888e453770c79d7ed56419";
+    let crate_to_publish = PublishBuilder::new("foo_new")
+        .version("1.0.0")
+        .cksum(&cksum.to_uppercase());
+    let json: GoodCrate = token.publish_crate(crate_to_publish).good();
+
+    assert_eq!(json.krate.name, "foo_new");
+    assert_eq!(json.cksum, cksum);
+}
+
+#[test]
+fn new_krate_with_duplicate_tarball() {
+    let (_, _, _, token) = TestApp::full()
+        .with_config(|config| config.reject_duplicate_tarballs = true)
+        .with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo_new").version("1.0.0");
+    token.publish_crate(crate_to_publish).good();
+
+    // The same (empty) tarball is uploaded again for a different crate
+    let crate_to_publish = PublishBuilder::new("foo_other").version("1.0.0");
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the uploaded tarball is identical to the one of `foo_new@1.0.0`" }] })
+    );
+}
+
+#[test]
+fn new_krate_with_mismatched_cksum() {
+    let (_, _, _, token) = TestApp::full().with_token();
+
+    let cksum = "0000000000000000000000000000000000000000000000000000000000000000";
+    let crate_to_publish = PublishBuilder::new("foo_new").cksum(cksum);
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the SHA-256 checksum of the uploaded tarball is `acb5604b126ac894c1eb11c4575bf2072fea61232a888e453770c79d7ed56419`, but the publish metadata specifies `0000000000000000000000000000000000000000000000000000000000000000`" }] })
+    );
+}
+
 #[test]
 fn new_krate_weird_version() {
     let (_, _, _, token) = TestApp::full().with_token();
@@ -151,7 +221,9 @@ fn new_with_renamed_dependency() {
 
     app.db(|conn| {
         // Insert a crate directly into the database so that new-krate can depend on it
-        CrateBuilder::new("package-name", user.as_model().id).expect_build(conn);
+        CrateBuilder::new("package-name", user.as_model().id)
+            .version("1.0.0")
+            .expect_build(conn);
     });
 
     let dependency = DependencyBuilder::new("package-name").rename("my-name");
@@ -176,7 +248,9 @@ fn new_with_underscore_renamed_dependency() {
 
     app.db(|conn| {
         // Insert a crate directly into the database so that new-krate can depend on it
-        CrateBuilder::new("package-name", user.as_model().id).expect_build(conn);
+        CrateBuilder::new("package-name", user.as_model().id)
+            .version("1.0.0")
+            .expect_build(conn);
     });
 
     let dependency = DependencyBuilder::new("package-name").rename("_my-name");
@@ -206,7 +280,9 @@ fn new_krate_with_dependency() {
         // The name choice of `foo-dep` is important! It has the property of
         // name != canon_crate_name(name) and is a regression test for
         // https://github.com/rust-lang/crates.io/issues/651
-        CrateBuilder::new("foo-dep", user.as_model().id).expect_build(conn);
+        CrateBuilder::new("foo-dep", user.as_model().id)
+            .version("1.0.0")
+            .expect_build(conn);
     });
 
     let dependency = DependencyBuilder::new("foo-dep").version_req("1.0.0");
@@ -261,6 +337,31 @@ fn new_krate_with_broken_dependency_requirement() {
     );
 }
 
+#[test]
+fn new_krate_with_unsatisfiable_dependency_requirement() {
+    let (app, _, user, token) = TestApp::full().with_token();
+
+    app.db(|conn| {
+        CrateBuilder::new("foo-dep", user.as_model().id)
+            .version("0.99.0")
+            .version(VersionBuilder::new("1.0.0").yanked(true))
+            .expect_build(conn);
+    });
+
+    let dependency = DependencyBuilder::new("foo-dep").version_req("1.0.0");
+
+    let crate_to_publish = PublishBuilder::new("new_dep")
+        .version("1.0.0")
+        .dependency(dependency);
+
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "no non-yanked version of `foo-dep` matches the dependency requirement `^1.0.0`" }] })
+    );
+}
+
 #[test]
 fn reject_new_krate_with_non_exact_dependency() {
     let (app, _, user, token) = TestApp::full().with_token();
@@ -289,7 +390,9 @@ fn new_crate_allow_empty_alternative_registry_dependency() {
     let (app, _, user, token) = TestApp::full().with_token();
 
     app.db(|conn| {
-        CrateBuilder::new("foo-dep", user.as_model().id).expect_build(conn);
+        CrateBuilder::new("foo-dep", user.as_model().id)
+            .version("1.0.0")
+            .expect_build(conn);
     });
 
     let dependency = DependencyBuilder::new("foo-dep").registry("");
@@ -822,6 +925,22 @@ fn license_and_description_required() {
     );
 }
 
+#[test]
+fn license_ref_requires_license_file() {
+    let (_, _, _, token) = TestApp::full().with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo_license_ref")
+        .version("1.0.0")
+        .license("MIT OR LicenseRef-Proprietary");
+
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the license expression references `LicenseRef-Proprietary`, which requires the license text to be uploaded via `license-file`" }] })
+    );
+}
+
 #[test]
 fn new_krate_tarball_with_hard_links() {
     let (_, _, _, token) = TestApp::full().with_token();
@@ -918,7 +1037,46 @@ fn tarball_bigger_than_max_upload_size() {
     assert_eq!(response.status(), StatusCode::OK);
     assert_eq!(
         response.into_json(),
-        json!({ "errors": [{ "detail": format!("max upload size is: {max_upload_size}") }] })
+        json!({ "errors": [{
+            "detail": format!("max upload size is: {max_upload_size}"),
+            "max_upload_size": max_upload_size,
+        }] })
+    );
+}
+
+#[test]
+fn tarball_bigger_than_crate_specific_max_upload_size() {
+    let (app, _, user, token) = TestApp::full().with_token();
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_limited", user.as_model().id)
+            .max_upload_size(1000)
+            .expect_build(conn);
+    });
+
+    let mut tarball = Vec::new();
+    {
+        // We explicitly disable compression to be able to influence the final tarball size
+        let data = &[b'a'; 2000] as &[_];
+
+        let mut ar = tar::Builder::new(GzEncoder::new(&mut tarball, Compression::none()));
+        let mut header = tar::Header::new_gnu();
+        assert_ok!(header.set_path("foo_limited-1.1.0/Cargo.toml"));
+        header.set_size(data.len() as u64);
+        header.set_cksum();
+        assert_ok!(ar.append(&header, data));
+        assert_ok!(ar.finish());
+    }
+
+    let crate_to_publish = PublishBuilder::new("foo_limited")
+        .version("1.1.0")
+        .tarball(tarball);
+
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "max upload size is: 1000", "max_upload_size": 1000 }] })
     );
 }
 
@@ -964,13 +1122,46 @@ fn publish_rate_limit_doesnt_affect_existing_crates() {
     token.publish_crate(new_version).good();
 }
 
+#[test]
+fn publish_existing_crate_rate_limited() {
+    let (app, _, user, token) = TestApp::full()
+        .with_publish_update_rate_limit(Duration::from_secs(60), 0)
+        .with_token();
+
+    app.db(|conn| {
+        CrateBuilder::new("rate_limited_update", user.as_model().id)
+            .version("1.0.0")
+            .expect_build(conn);
+    });
+
+    let crate_to_publish = PublishBuilder::new("rate_limited_update").version("1.0.1");
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let json = response.into_json();
+    let detail = json["errors"][0]["detail"].as_str().unwrap();
+    assert!(
+        detail.starts_with(
+            "You have published too many versions of existing crates in a short period of time."
+        ),
+        "{detail:?}"
+    );
+
+    // The limit is also available in machine-readable form
+    assert_some!(json["errors"][0]["retry_after"].as_str());
+    let retry_after_seconds = json["errors"][0]["retry_after_seconds"].as_i64().unwrap();
+    assert!((0..=60).contains(&retry_after_seconds));
+}
+
 #[test]
 fn features_version_2() {
     let (app, _, user, token) = TestApp::full().with_token();
 
     app.db(|conn| {
         // Insert a crate directly into the database so that foo_new can depend on it
-        CrateBuilder::new("bar", user.as_model().id).expect_build(conn);
+        CrateBuilder::new("bar", user.as_model().id)
+            .version("1.0.0")
+            .expect_build(conn);
     });
 
     let dependency = DependencyBuilder::new("bar");
@@ -995,3 +1186,92 @@ fn features_version_2() {
     )]);
     assert_eq!(crates[0].features2, Some(features2));
 }
+
+#[test]
+fn too_many_features() {
+    let (_, _, _, token) = TestApp::full()
+        .with_config(|config| config.metadata_limits.max_features = 1)
+        .with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo")
+        .version("1.0.0")
+        .feature("one", &[])
+        .feature("two", &[]);
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{
+            "detail": "crates may not have more than 1 features",
+            "code": "metadata_limit_exceeded",
+            "limit": "max_features",
+            "max": 1,
+        }] })
+    );
+}
+
+#[test]
+fn feature_name_too_long() {
+    let (_, _, _, token) = TestApp::full()
+        .with_config(|config| config.metadata_limits.max_feature_name_length = 5)
+        .with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo")
+        .version("1.0.0")
+        .feature("too_long", &[]);
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{
+            "detail": "feature names may not be longer than 5 characters",
+            "code": "metadata_limit_exceeded",
+            "limit": "max_feature_name_length",
+            "max": 5,
+        }] })
+    );
+}
+
+#[test]
+fn too_many_dependencies() {
+    let (_, _, _, token) = TestApp::full()
+        .with_config(|config| config.metadata_limits.max_dependencies = 1)
+        .with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo")
+        .version("1.0.0")
+        .dependency(DependencyBuilder::new("bar"))
+        .dependency(DependencyBuilder::new("baz"));
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{
+            "detail": "crates may not have more than 1 dependencies",
+            "code": "metadata_limit_exceeded",
+            "limit": "max_dependencies",
+            "max": 1,
+        }] })
+    );
+}
+
+#[test]
+fn metadata_too_large() {
+    let (_, _, _, token) = TestApp::full()
+        .with_config(|config| config.metadata_limits.max_metadata_size = 1000)
+        .with_token();
+
+    let readme = "a".repeat(1000);
+    let crate_to_publish = PublishBuilder::new("foo").version("1.0.0").readme(&readme);
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{
+            "detail": "max metadata size is: 1000",
+            "code": "metadata_limit_exceeded",
+            "limit": "max_metadata_size",
+            "max": 1000,
+        }] })
+    );
+}
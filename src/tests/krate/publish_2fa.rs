@@ -0,0 +1,354 @@
+use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::util::{MockRequestExt, RequestHelper, Response, TestApp};
+use cargo_registry::controllers::krate::publish::OTP_HEADER;
+use cargo_registry::schema::totp_credentials;
+use cargo_registry::util::totp;
+use cargo_registry::views::GoodCrate;
+use diesel::prelude::*;
+use http::{Method, StatusCode};
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Returns the code of the authenticator of the user, `offset` time steps
+/// after the current one.
+fn code(app: &TestApp, user_id: i32, offset: u64) -> String {
+    let secret: Vec<u8> = app.db(|conn| {
+        totp_credentials::table
+            .find(user_id)
+            .select(totp_credentials::secret)
+            .first(conn)
+            .unwrap()
+    });
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    totp::code(&secret, totp::time_step(now.as_secs()) + offset)
+}
+
+fn enroll_totp(user: &impl RequestHelper) -> Response<Value> {
+    user.run(user.post_request("/api/v1/me/totp"))
+}
+
+/// Enables an authenticator for the user and returns the recovery codes.
+fn enable_totp(app: &TestApp, user: &impl RequestHelper, user_id: i32) -> Vec<String> {
+    let json = enroll_totp(user).good();
+    let uri = json["totp"]["provisioning_uri"].as_str().unwrap();
+    assert!(uri.starts_with("otpauth://totp/crates.io%3A"));
+
+    let body = json!({ "code": code(app, user_id, 0) }).to_string();
+    let json = user.put::<Value>("/api/v1/me/totp", body.as_bytes()).good();
+    serde_json::from_value(json["recovery_codes"].clone()).unwrap()
+}
+
+fn require_2fa(user: &impl RequestHelper, crate_name: &str, required: bool) -> Response<Value> {
+    let path = format!("/api/v1/crates/{crate_name}/publish_2fa");
+    let body = json!({ "required": required }).to_string();
+    user.put(&path, body.as_bytes())
+}
+
+fn publish_with_otp(
+    user: &impl RequestHelper,
+    publish_builder: PublishBuilder,
+    otp: &str,
+) -> Response<GoodCrate> {
+    let mut request = user.request_builder(Method::PUT, "/api/v1/crates/new");
+    request.with_body(&publish_builder.body());
+    request.header(OTP_HEADER, otp);
+    let response = user.run(request);
+    user.app().run_pending_background_jobs();
+    response
+}
+
+#[test]
+fn enroll_and_disable_totp() {
+    let (app, _, user) = TestApp::init().with_user();
+    let user_id = user.as_model().id;
+
+    let json = enroll_totp(&user).good();
+    assert!(json["totp"]["provisioning_uri"]
+        .as_str()
+        .unwrap()
+        .starts_with("otpauth://totp/"));
+
+    let body = json!({ "code": "abcdef" }).to_string();
+    let response = user.put::<Value>("/api/v1/me/totp", body.as_bytes());
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid one-time password" }] })
+    );
+
+    let body = json!({ "code": code(&app, user_id, 0) }).to_string();
+    user.put::<Value>("/api/v1/me/totp", body.as_bytes()).good();
+
+    // A second authenticator can only be added after disabling the first one
+    let response = enroll_totp(&user);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Codes can only be used once
+    let response = user.delete_with_body::<Value>("/api/v1/me/totp", body.as_bytes());
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = json!({ "code": code(&app, user_id, 1) }).to_string();
+    let json = user
+        .delete_with_body::<Value>("/api/v1/me/totp", body.as_bytes())
+        .good();
+    assert_eq!(json, json!({ "ok": true }));
+}
+
+#[test]
+fn requiring_2fa_needs_an_authenticator() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+
+    let response = require_2fa(&user, "foo", true);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "you need to enable an authenticator on your account first" }] })
+    );
+
+    let json = user.get::<Value>("/api/v1/crates/foo/publish_2fa").good();
+    assert_eq!(json, json!({ "publish_requires_2fa": false }));
+}
+
+#[test]
+fn only_owners_can_require_2fa() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+
+    let other = app.db_new_user("other");
+    enable_totp(&app, &other, other.as_model().id);
+
+    let response = require_2fa(&other, "foo", true);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only owners have permission to change this setting" }] })
+    );
+}
+
+#[test]
+fn api_tokens_cannot_change_2fa_requirement() {
+    let (app, _, user, token) = TestApp::init().with_token();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+
+    let response = require_2fa(&token, "foo", false);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn publish_requires_otp() {
+    let (app, _, user, token) = TestApp::full().with_token();
+    let user_id = user.as_model().id;
+    app.db(|conn| {
+        CrateBuilder::new("foo_twice", user_id).expect_build(conn);
+    });
+
+    enable_totp(&app, &user, user_id);
+    let json = require_2fa(&user, "foo_twice", true).good();
+    assert_eq!(json, json!({ "publish_requires_2fa": true }));
+
+    let crate_to_publish = || {
+        PublishBuilder::new("foo_twice")
+            .version("2.0.0")
+            .description("2.0.0 description")
+    };
+
+    let response = token.publish_crate(crate_to_publish());
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the owners of this crate require publishes to be confirmed with a one-time password, please pass it in the `X-Crates-Io-Otp` header" }] })
+    );
+
+    let response = publish_with_otp(&token, crate_to_publish(), "abcdef");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid or already used one-time password" }] })
+    );
+
+    let otp = code(&app, user_id, 1);
+    let json = publish_with_otp(&token, crate_to_publish(), &otp).good();
+    assert_eq!(json.krate.name, "foo_twice");
+    assert_eq!(json.krate.max_version, "2.0.0");
+}
+
+#[test]
+fn publish_requires_an_authenticator() {
+    let (app, _, user, token) = TestApp::full().with_token();
+    let user_id = user.as_model().id;
+    app.db(|conn| {
+        CrateBuilder::new("foo", user_id).expect_build(conn);
+    });
+
+    enable_totp(&app, &user, user_id);
+    require_2fa(&user, "foo", true).good();
+
+    let body = json!({ "code": code(&app, user_id, 1) }).to_string();
+    user.delete_with_body::<Value>("/api/v1/me/totp", body.as_bytes())
+        .good();
+
+    let response = token.publish_crate(PublishBuilder::new("foo").version("2.0.0"));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the owners of this crate require publishes to be confirmed with a one-time password, but you have not enabled an authenticator on your account" }] })
+    );
+}
+
+#[test]
+fn disabling_2fa_requirement_requires_otp() {
+    let (app, _, user) = TestApp::init().with_user();
+    let user_id = user.as_model().id;
+    app.db(|conn| {
+        CrateBuilder::new("foo", user_id).expect_build(conn);
+    });
+
+    enable_totp(&app, &user, user_id);
+    require_2fa(&user, "foo", true).good();
+
+    let response = require_2fa(&user, "foo", false);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "disabling this requirement must be confirmed with a one-time password, please pass it in the `X-Crates-Io-Otp` header" }] })
+    );
+
+    let body = json!({ "required": false }).to_string();
+    let mut request = user.request_builder(Method::PUT, "/api/v1/crates/foo/publish_2fa");
+    request.with_body(body.as_bytes());
+    request.header(OTP_HEADER, &code(&app, user_id, 1));
+    let json = user.run::<Value>(request).good();
+    assert_eq!(json, json!({ "publish_requires_2fa": false }));
+}
+
+#[test]
+fn too_many_invalid_otps_lock_the_authenticator() {
+    let (app, _, user, token) = TestApp::init().with_token();
+    let user_id = user.as_model().id;
+    app.db(|conn| {
+        CrateBuilder::new("foo", user_id).expect_build(conn);
+    });
+
+    enable_totp(&app, &user, user_id);
+    require_2fa(&user, "foo", true).good();
+
+    let publish = |otp: &str| {
+        let mut request = token.request_builder(Method::PUT, "/api/v1/crates/new");
+        request.with_body(&PublishBuilder::new("foo").version("2.0.0").body());
+        request.header(OTP_HEADER, otp);
+        token.run::<Value>(request)
+    };
+
+    for _ in 0..5 {
+        assert_eq!(
+            publish("abcdef").into_json(),
+            json!({ "errors": [{ "detail": "invalid or already used one-time password" }] })
+        );
+    }
+
+    let response = publish(&code(&app, user_id, 1));
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let json = response.into_json();
+    let detail = json["errors"][0]["detail"].as_str().unwrap();
+    assert!(detail.starts_with("too many invalid one-time passwords were entered"));
+}
+
+fn add_owner_with_otp(user: &impl RequestHelper, otp: Option<&str>) -> Response<Value> {
+    let body = json!({ "owners": ["other"] }).to_string();
+    let mut request = user.request_builder(Method::PUT, "/api/v1/crates/foo/owners");
+    request.with_body(body.as_bytes());
+    if let Some(otp) = otp {
+        request.header(OTP_HEADER, otp);
+    }
+    user.run(request)
+}
+
+#[test]
+fn owner_changes_require_otp() {
+    let (app, _, user) = TestApp::init().with_user();
+    let user_id = user.as_model().id;
+    app.db(|conn| {
+        CrateBuilder::new("foo", user_id).expect_build(conn);
+    });
+    app.db_new_user("other");
+
+    enable_totp(&app, &user, user_id);
+
+    let response = add_owner_with_otp(&user, None);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this action must be confirmed with a one-time password, please pass it in the `X-Crates-Io-Otp` header" }] })
+    );
+
+    let response = add_owner_with_otp(&user, Some("abcdef"));
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid or already used one-time password" }] })
+    );
+
+    let otp = code(&app, user_id, 1);
+    let json = add_owner_with_otp(&user, Some(&otp)).good();
+    assert_eq!(json["ok"], true);
+}
+
+#[test]
+fn recovery_codes_can_only_be_used_once() {
+    let (app, _, user) = TestApp::init().with_user();
+    let user_id = user.as_model().id;
+    app.db(|conn| {
+        CrateBuilder::new("foo", user_id).expect_build(conn);
+    });
+    app.db_new_user("other");
+
+    let recovery_codes = enable_totp(&app, &user, user_id);
+    assert_eq!(recovery_codes.len(), 10);
+
+    let json = add_owner_with_otp(&user, Some(&recovery_codes[0])).good();
+    assert_eq!(json["ok"], true);
+
+    let body = json!({ "code": recovery_codes[0] }).to_string();
+    let response = user.delete_with_body::<Value>("/api/v1/me/totp", body.as_bytes());
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Regenerating the codes invalidates the previous ones
+    let body = json!({ "code": code(&app, user_id, 1) }).to_string();
+    let mut request = user.post_request("/api/v1/me/totp/recovery_codes");
+    request.with_body(body.as_bytes());
+    let json = user.run::<Value>(request).good();
+    let new_codes: Vec<String> = serde_json::from_value(json["recovery_codes"].clone()).unwrap();
+    assert_eq!(new_codes.len(), 10);
+
+    let body = json!({ "code": recovery_codes[1] }).to_string();
+    let response = user.delete_with_body::<Value>("/api/v1/me/totp", body.as_bytes());
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = json!({ "code": new_codes[0] }).to_string();
+    let json = user
+        .delete_with_body::<Value>("/api/v1/me/totp", body.as_bytes())
+        .good();
+    assert_eq!(json, json!({ "ok": true }));
+}
+
+#[test]
+fn confirming_a_login_needs_a_pending_login() {
+    let (_, anon) = TestApp::init().empty();
+
+    let body = json!({ "code": "123456" }).to_string();
+    let mut request = anon.post_request("/api/private/session/totp");
+    request.with_body(body.as_bytes());
+    let response = anon.run::<Value>(request);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "no login is waiting for a one-time password" }] })
+    );
+}
@@ -0,0 +1,134 @@
+use crate::builders::PublishBuilder;
+use crate::util::{MockRequestExt, RequestHelper, Response, TestApp};
+use cargo_registry::views::GoodCrate;
+use http::{header, Method, StatusCode};
+
+const BOUNDARY: &str = "crates-io-test-boundary";
+
+fn multipart_body(parts: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (name, content) in parts {
+        body.extend(format!("--{BOUNDARY}\r\n").as_bytes());
+        body.extend(format!("Content-Disposition: form-data; name=\"{name}\"\r\n\r\n").as_bytes());
+        body.extend(*content);
+        body.extend(b"\r\n");
+    }
+    body.extend(format!("--{BOUNDARY}--\r\n").as_bytes());
+    body
+}
+
+fn publish_multipart(user: &impl RequestHelper, parts: &[(&str, &[u8])]) -> Response<GoodCrate> {
+    let mut request = user.request_builder(Method::PUT, "/api/v2/crates/new");
+    let content_type = format!("multipart/form-data; boundary={BOUNDARY}");
+    request.header(header::CONTENT_TYPE, &content_type);
+    request.with_body(&multipart_body(parts));
+    let response = user.run(request);
+    user.app().run_pending_background_jobs();
+    response
+}
+
+#[test]
+fn publish_new_crate() {
+    let (_, _, _, token) = TestApp::full().with_token();
+
+    let (json, tarball) = PublishBuilder::new("foo_new").version("1.0.0").build();
+    let parts = [
+        ("metadata", json.as_bytes()),
+        ("tarball", tarball.as_slice()),
+    ];
+    let json = publish_multipart(&token, &parts).good();
+
+    assert_eq!(json.krate.name, "foo_new");
+    assert_eq!(json.krate.max_version, "1.0.0");
+}
+
+#[test]
+fn missing_tarball() {
+    let (_, _, _, token) = TestApp::full().with_token();
+
+    let (json, _) = PublishBuilder::new("foo_new").build();
+    let response = publish_multipart(&token, &[("metadata", json.as_bytes())]);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "missing part `tarball`" }] })
+    );
+}
+
+#[test]
+fn unexpected_part() {
+    let (_, _, _, token) = TestApp::full().with_token();
+
+    let (json, tarball) = PublishBuilder::new("foo_new").build();
+    let parts = [
+        ("metadata", json.as_bytes()),
+        ("tarball", tarball.as_slice()),
+        ("readme", b"# foo".as_slice()),
+    ];
+    let response = publish_multipart(&token, &parts);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "unexpected part `readme`, expected `metadata`, `tarball` or `signature`" }] })
+    );
+}
+
+#[test]
+fn metadata_part_too_large() {
+    let (_, _, _, token) = TestApp::full()
+        .with_config(|config| config.metadata_limits.max_metadata_size = 100)
+        .with_token();
+
+    let (json, tarball) = PublishBuilder::new("foo_new").build();
+    let parts = [
+        ("metadata", json.as_bytes()),
+        ("tarball", tarball.as_slice()),
+    ];
+    let response = publish_multipart(&token, &parts);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the part `metadata` must not be larger than 100 bytes" }] })
+    );
+}
+
+#[test]
+fn metadata_after_tarball() {
+    let (_, _, _, token) = TestApp::full().with_token();
+
+    let (json, tarball) = PublishBuilder::new("foo_new").build();
+    let parts = [
+        ("tarball", tarball.as_slice()),
+        ("metadata", json.as_bytes()),
+    ];
+    let response = publish_multipart(&token, &parts);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the part `metadata` must come before the part `tarball`" }] })
+    );
+}
+
+#[test]
+fn tarball_bigger_than_max_upload_size() {
+    let max_upload_size = 1000;
+    let (_, _, _, token) = TestApp::full()
+        .with_config(|config| config.max_upload_size = max_upload_size)
+        .with_token();
+
+    let (json, _) = PublishBuilder::new("foo_new").build();
+    let tarball = vec![0; max_upload_size as usize + 1];
+    let parts = [
+        ("metadata", json.as_bytes()),
+        ("tarball", tarball.as_slice()),
+    ];
+    let response = publish_multipart(&token, &parts);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{
+            "detail": format!("max upload size is: {max_upload_size}"),
+            "max_upload_size": max_upload_size,
+        }] })
+    );
+}
@@ -0,0 +1,102 @@
+use crate::builders::{DependencyBuilder, PublishBuilder};
+use crate::util::{RequestHelper, Response, TestApp};
+use cargo_registry::views::GoodCrate;
+use http::StatusCode;
+use serde_json::Value;
+
+#[test]
+fn staged_publish() {
+    let (app, anon, _, token) = TestApp::full().with_token();
+
+    let body = PublishBuilder::new("foo_new").version("1.0.0").body();
+    let response: Response<Value> = token.put("/api/v1/crates/new/staged", &body);
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response.into_json();
+    let staged_id = json["staged_publish"]["id"].as_i64().unwrap();
+    assert_eq!(json["staged_publish"]["crate"], "foo_new");
+    assert_eq!(json["staged_publish"]["version"], "1.0.0");
+
+    // Staged versions are not visible until they are promoted
+    anon.get::<()>("/api/v1/crates/foo_new").assert_not_found();
+
+    // Staging the same version twice is a conflict
+    let response: Response<()> = token.put("/api/v1/crates/new/staged", &body);
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let json = token.get::<()>("/api/v1/crates/new/staged").into_json();
+    assert_eq!(json["staged_publishes"][0]["id"], staged_id);
+
+    let url = format!("/api/v1/crates/new/staged/{staged_id}/promote");
+    let response: Response<GoodCrate> = token.run(token.post_request(&url));
+    app.run_pending_background_jobs();
+    let json = response.good();
+    assert_eq!(json.krate.name, "foo_new");
+    assert_eq!(json.krate.max_version, "1.0.0");
+
+    // The staged publish is removed after it has been promoted
+    let json = token.get::<()>("/api/v1/crates/new/staged").into_json();
+    assert_eq!(json["staged_publishes"], json!([]));
+}
+
+#[test]
+fn staged_publish_is_validated() {
+    let (_, _, _, token) = TestApp::full().with_token();
+
+    let files = [("foo-1.0.0/a", b"" as &[u8]), ("bar-1.0.0/a", b"")];
+    let body = PublishBuilder::new("foo").files(&files).body();
+    let response: Response<()> = token.put("/api/v1/crates/new/staged", &body);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid tarball uploaded" }] })
+    );
+
+    let json = token.get::<()>("/api/v1/crates/new/staged").into_json();
+    assert_eq!(json["staged_publishes"], json!([]));
+}
+
+#[test]
+fn failed_promotion_keeps_staged_publish() {
+    let (_, anon, _, token) = TestApp::full().with_token();
+
+    let dependency = DependencyBuilder::new("foo_missing");
+    let body = PublishBuilder::new("foo_promote")
+        .dependency(dependency)
+        .body();
+    let json = token
+        .put::<()>("/api/v1/crates/new/staged", &body)
+        .into_json();
+    let staged_id = json["staged_publish"]["id"].as_i64().unwrap();
+
+    let url = format!("/api/v1/crates/new/staged/{staged_id}/promote");
+    let response: Response<()> = token.run(token.post_request(&url));
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "no known crate named `foo_missing`" }] })
+    );
+
+    // Neither the version was published nor the staged publish removed
+    anon.get::<()>("/api/v1/crates/foo_promote")
+        .assert_not_found();
+    let json = token.get::<()>("/api/v1/crates/new/staged").into_json();
+    assert_eq!(json["staged_publishes"][0]["id"], staged_id);
+}
+
+#[test]
+fn discard_staged_publish() {
+    let (_, _, _, token) = TestApp::full().with_token();
+
+    let body = PublishBuilder::new("foo_discard").version("1.0.0").body();
+    let json = token
+        .put::<()>("/api/v1/crates/new/staged", &body)
+        .into_json();
+    let staged_id = json["staged_publish"]["id"].as_i64().unwrap();
+
+    let url = format!("/api/v1/crates/new/staged/{staged_id}");
+    let json = token.delete::<()>(&url).into_json();
+    assert_eq!(json, json!({ "ok": true }));
+
+    let url = format!("/api/v1/crates/new/staged/{staged_id}/promote");
+    let response: Response<()> = token.run(token.post_request(&url));
+    response.assert_not_found();
+}
@@ -0,0 +1,138 @@
+use crate::builders::PublishBuilder;
+use crate::util::{MockRequestExt, RequestHelper, Response, TestApp};
+use cargo_registry::models::token::{CrateScope, EndpointScope};
+use cargo_registry::storage::{InMemoryStorage, UploadBucket};
+use cargo_registry::views::GoodCrate;
+use cargo_registry::Uploader;
+use hex::ToHex;
+use http::{Method, StatusCode};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+fn append(user: &impl RequestHelper, upload_id: i64, offset: usize, chunk: &[u8]) -> Response<()> {
+    let url = format!("/api/v1/crates/new/uploads/{upload_id}");
+    let mut request = user.request_builder(Method::PUT, &url);
+    request.header("Upload-Offset", &offset.to_string());
+    request.header(
+        "Upload-Checksum",
+        &Sha256::digest(chunk).encode_hex::<String>(),
+    );
+    request.with_body(chunk);
+    user.run(request)
+}
+
+#[test]
+fn chunked_upload() {
+    let storage = InMemoryStorage::new();
+    let uploader = Uploader::new(storage.clone());
+    let (app, _, _, token) = TestApp::full()
+        .with_config(|config| config.base.set_uploader(uploader))
+        .with_token();
+
+    let body = PublishBuilder::new("foo_new").version("1.0.0").body();
+    let cksum: String = Sha256::digest(&body).encode_hex();
+
+    let json = json!({ "name": "foo_new", "size": body.len(), "cksum": cksum });
+    let mut request = token.post_request("/api/v1/crates/new/uploads");
+    request.with_body(json.to_string().as_bytes());
+    let response: Response<Value> = token.run(request);
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response.into_json();
+    let upload_id = json["upload"]["id"].as_i64().unwrap();
+    assert_eq!(json["upload"]["offset"], 0);
+
+    let (first, rest) = body.split_at(body.len() / 2);
+
+    let response = append(&token, upload_id, 0, first);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(response.headers()["Upload-Offset"], first.len().to_string());
+
+    // Completing an unfinished upload is rejected
+    let url = format!("/api/v1/crates/new/uploads/{upload_id}/complete");
+    let response: Response<()> = token.run(token.post_request(&url));
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Retrying an already received chunk is a conflict, and the current
+    // offset can be queried to resume the upload
+    let response = append(&token, upload_id, 0, first);
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let url = format!("/api/v1/crates/new/uploads/{upload_id}");
+    let json = token.get::<()>(&url).into_json();
+    assert_eq!(json["upload"]["offset"], first.len());
+
+    // Chunks with a wrong checksum are rejected
+    let mut request = token.request_builder(Method::PUT, &url);
+    request.header("Upload-Offset", &first.len().to_string());
+    request.header("Upload-Checksum", &"0".repeat(64));
+    request.with_body(rest);
+    let response: Response<()> = token.run(request);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = append(&token, upload_id, first.len(), rest);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let url = format!("/api/v1/crates/new/uploads/{upload_id}/complete");
+    let response: Response<GoodCrate> = token.run(token.post_request(&url));
+    app.run_pending_background_jobs();
+    let json = response.good();
+    assert_eq!(json.krate.name, "foo_new");
+    assert_eq!(json.krate.max_version, "1.0.0");
+
+    // The upload and its stored chunks are removed after it has been published
+    let url = format!("/api/v1/crates/new/uploads/{upload_id}");
+    token.get::<()>(&url).assert_not_found();
+    let paths = storage.paths(UploadBucket::Default);
+    assert!(paths.iter().all(|path| !path.starts_with("uploads/")));
+}
+
+#[test]
+fn chunked_upload_with_scoped_token() {
+    let storage = InMemoryStorage::new();
+    let uploader = Uploader::new(storage);
+    let (app, _, user, token) = TestApp::full()
+        .with_config(|config| config.base.set_uploader(uploader))
+        .with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo_scoped").version("1.0.0");
+    token.publish_crate(crate_to_publish).good();
+
+    let token = user.db_new_scoped_token(
+        "publish-update",
+        Some(vec![CrateScope::try_from("foo_scoped").unwrap()]),
+        Some(vec![EndpointScope::PublishUpdate]),
+    );
+
+    let body = PublishBuilder::new("foo_scoped").version("1.0.1").body();
+    let cksum: String = Sha256::digest(&body).encode_hex();
+
+    let json = json!({ "name": "foo_scoped", "size": body.len(), "cksum": cksum });
+    let mut request = token.post_request("/api/v1/crates/new/uploads");
+    request.with_body(json.to_string().as_bytes());
+    let json: Value = token.run(request).good();
+    let upload_id = json["upload"]["id"].as_i64().unwrap();
+
+    // The scoped token can be used for all following steps of the upload
+    let url = format!("/api/v1/crates/new/uploads/{upload_id}");
+    let json: Value = token.get(&url).good();
+    assert_eq!(json["upload"]["offset"], 0);
+
+    let response = append(&token, upload_id, 0, &body);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let url = format!("/api/v1/crates/new/uploads/{upload_id}/complete");
+    let response: Response<GoodCrate> = token.run(token.post_request(&url));
+    app.run_pending_background_jobs();
+    assert_eq!(response.good().krate.max_version, "1.0.1");
+}
+
+#[test]
+fn chunked_upload_requires_authentication() {
+    let (_, anon) = TestApp::init().empty();
+
+    let json = json!({ "name": "foo_new", "size": 10, "cksum": "0".repeat(64) });
+    let mut request = anon.post_request("/api/v1/crates/new/uploads");
+    request.with_body(json.to_string().as_bytes());
+    let response: Response<()> = anon.run(request);
+    response.assert_forbidden();
+}
@@ -0,0 +1,191 @@
+use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::util::{receive_one_request, MockRequestExt, RequestHelper, Response, TestApp};
+use cargo_registry::models::{Crate, NewWebhook};
+use diesel::prelude::*;
+use hex::ToHex;
+use hmac::{Hmac, Mac};
+use http::StatusCode;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+fn create_webhook(user: &impl RequestHelper, crate_name: &str, url: &str) -> Response<Value> {
+    let body = json!({ "webhook": { "url": url } }).to_string();
+    let path = format!("/api/v1/crates/{crate_name}/webhooks");
+    let mut request = user.post_request(&path);
+    request.with_body(body.as_bytes());
+    user.run(request)
+}
+
+#[test]
+fn manage_webhooks() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+
+    let json = create_webhook(&user, "foo", "https://example.com/hook").good();
+    let webhook_id = json["webhook"]["id"].as_i64().unwrap();
+    assert_eq!(json["webhook"]["url"], "https://example.com/hook");
+    assert!(json["webhook"]["secret"]
+        .as_str()
+        .unwrap()
+        .starts_with("cwh"));
+
+    // The secret is only shown once
+    let json = user.get::<Value>("/api/v1/crates/foo/webhooks").good();
+    assert_eq!(json["webhooks"][0]["id"], webhook_id);
+    assert_eq!(json["webhooks"][0]["secret"], Value::Null);
+
+    let url = format!("/api/v1/crates/foo/webhooks/{webhook_id}");
+    let json = user.delete::<Value>(&url).good();
+    assert_eq!(json, json!({ "ok": true }));
+
+    let json = user.get::<Value>("/api/v1/crates/foo/webhooks").good();
+    assert_eq!(json["webhooks"], json!([]));
+}
+
+#[test]
+fn webhook_urls_must_use_https() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+
+    let response = create_webhook(&user, "foo", "http://example.com/hook");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "webhook URLs must be valid `https` URLs" }] })
+    );
+}
+
+#[test]
+fn webhook_urls_must_not_point_to_internal_addresses() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+
+    for url in [
+        "https://169.254.169.254/latest/meta-data",
+        "https://127.0.0.1/hook",
+        "https://[::1]/hook",
+    ] {
+        let response = create_webhook(&user, "foo", url);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.into_json(),
+            json!({ "errors": [{ "detail": "webhook URLs must not point to private or local addresses" }] })
+        );
+    }
+}
+
+#[test]
+fn webhooks_require_ownership() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+
+    let other = app.db_new_user("other");
+    let response = create_webhook(&other, "foo", "https://example.com/hook");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only owners have permission to manage webhooks" }] })
+    );
+
+    let response = other.get::<Value>("/api/v1/crates/foo/webhooks");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn api_tokens_cannot_create_webhooks() {
+    let (app, _, user, token) = TestApp::init().with_token();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+
+    let response = create_webhook(&token, "foo", "https://example.com/hook");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn yank_creates_webhook_delivery() {
+    let (_, _, user, token) = TestApp::full().with_token();
+
+    token.publish_crate(PublishBuilder::new("fyk")).good();
+
+    let json = create_webhook(&user, "fyk", "https://example.com/hook").good();
+    let webhook_id = json["webhook"]["id"].as_i64().unwrap();
+
+    let json = token
+        .delete::<Value>("/api/v1/crates/fyk/1.0.0/yank")
+        .good();
+    assert_eq!(json, json!({ "ok": true }));
+
+    let url = format!("/api/v1/crates/fyk/webhooks/{webhook_id}/deliveries");
+    let json = user.get::<Value>(&url).good();
+    let delivery = &json["deliveries"][0];
+    assert_eq!(delivery["event"], "yank");
+    assert_eq!(delivery["attempts"], 0);
+    assert_eq!(delivery["delivered_at"], Value::Null);
+    assert_eq!(
+        delivery["payload"],
+        json!({
+            "event": "yank",
+            "crate": "fyk",
+            "data": { "version": "1.0.0", "reason": null, "replacement": null },
+        })
+    );
+
+    // Pending deliveries are dropped together with their webhook
+    let url = format!("/api/v1/crates/fyk/webhooks/{webhook_id}");
+    user.delete::<Value>(&url).good();
+}
+
+#[test]
+fn deliveries_are_signed_with_the_secret() {
+    let (app, _, user, token) = TestApp::full().with_token();
+
+    token.publish_crate(PublishBuilder::new("fyk")).good();
+
+    // Webhooks are created directly, since the API rejects local URLs
+    let (url, request) = receive_one_request();
+    let secret = app.db(|conn| {
+        let krate: Crate = Crate::by_name("fyk").first(conn).unwrap();
+        let new_webhook = NewWebhook {
+            crate_id: krate.id,
+            url: &url,
+            created_by: user.as_model().id,
+        };
+        new_webhook.create(conn).unwrap().plaintext_secret
+    });
+
+    token
+        .delete::<Value>("/api/v1/crates/fyk/1.0.0/yank")
+        .good();
+    app.run_pending_background_jobs();
+
+    let (headers, body) = request.join().unwrap();
+    assert!(headers.contains(&"x-crates-io-event: yank".to_string()));
+
+    // The signatures are keyed with the SHA-256 hash of the secret
+    let key = Sha256::digest(secret.as_bytes());
+    let mut mac = Hmac::<Sha256>::new_from_slice(&key).unwrap();
+    mac.update(&body);
+    let signature: String = mac.finalize().into_bytes().encode_hex();
+    assert!(headers.contains(&format!("x-crates-io-signature: sha256={signature}")));
+
+    let payload: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(payload["event"], "yank");
+    assert_eq!(payload["crate"], "fyk");
+
+    let json = user.get::<Value>("/api/v1/crates/fyk/webhooks").good();
+    let webhook_id = json["webhooks"][0]["id"].as_i64().unwrap();
+    let url = format!("/api/v1/crates/fyk/webhooks/{webhook_id}/deliveries");
+    let json = user.get::<Value>(&url).good();
+    assert_eq!(json["deliveries"][0]["attempts"], 1);
+    assert_eq!(json["deliveries"][0]["response_status"], 200);
+    assert_ne!(json["deliveries"][0]["delivered_at"], Value::Null);
+}
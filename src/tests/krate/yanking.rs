@@ -1,4 +1,4 @@
-use crate::builders::PublishBuilder;
+use crate::builders::{CrateBuilder, PublishBuilder};
 use crate::routes::crates::versions::yank_unyank::YankRequestHelper;
 use crate::util::{RequestHelper, TestApp};
 
@@ -143,3 +143,75 @@ fn publish_after_yank_max_version() {
     let json = anon.show_crate("fyk_max");
     assert_eq!(json.krate.max_version, "2.0.0");
 }
+
+#[test]
+fn yank_with_reason_and_replacement() {
+    let (app, anon, _, token) = TestApp::full().with_token();
+
+    // Upload a new crate, putting it in the git index
+    let crate_to_publish = PublishBuilder::new("fyk");
+    token.publish_crate(crate_to_publish).good();
+
+    // yank it with a reason and a replacement
+    let body = json!({ "reason": "contains a soundness bug", "replacement": "fyk-ng@1.0.0" });
+    token
+        .yank_with_body("fyk", "1.0.0", body.to_string().as_bytes())
+        .good();
+
+    let crates = app.crates_from_index_head("fyk");
+    assert_some_eq!(crates[0].yank_reason.as_deref(), "contains a soundness bug");
+    assert_some_eq!(crates[0].yank_replacement.as_deref(), "fyk-ng@1.0.0");
+
+    let json = anon.show_version("fyk", "1.0.0");
+    assert!(json.version.yanked);
+    assert_some_eq!(json.version.yank_reason, "contains a soundness bug");
+    assert_some_eq!(json.version.yank_replacement, "fyk-ng@1.0.0");
+
+    // yanking it again only updates the fields that were given
+    let body = json!({ "replacement": "fyk-ng@1.0.1" });
+    token
+        .yank_with_body("fyk", "1.0.0", body.to_string().as_bytes())
+        .good();
+
+    let json = anon.show_version("fyk", "1.0.0");
+    assert_some_eq!(json.version.yank_reason, "contains a soundness bug");
+    assert_some_eq!(json.version.yank_replacement, "fyk-ng@1.0.1");
+
+    // un-yanking removes the reason and the replacement again
+    token.unyank("fyk", "1.0.0").good();
+
+    let crates = app.crates_from_index_head("fyk");
+    assert_none!(&crates[0].yank_reason);
+    assert_none!(&crates[0].yank_replacement);
+
+    let json = anon.show_version("fyk", "1.0.0");
+    assert_none!(json.version.yank_reason);
+    assert_none!(json.version.yank_replacement);
+}
+
+#[test]
+fn yank_with_invalid_replacement() {
+    let (app, _, _, token) = TestApp::full().with_token();
+    let user = token.as_model().user_id;
+
+    app.db(|conn| {
+        CrateBuilder::new("fyk", user)
+            .version("1.0.0")
+            .expect_build(conn);
+    });
+
+    let body = json!({ "replacement": "not a version" });
+    let response = token.yank_with_body("fyk", "1.0.0", body.to_string().as_bytes());
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid yank replacement `not a version`, expected `<version>` or `<crate>@<version>`" }] })
+    );
+
+    let reason = "x".repeat(257);
+    let body = json!({ "reason": reason });
+    let response = token.yank_with_body("fyk", "1.0.0", body.to_string().as_bytes());
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the yank reason must not be longer than 256 characters" }] })
+    );
+}
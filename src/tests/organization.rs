@@ -0,0 +1,276 @@
+use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::util::{MockCookieUser, MockRequestExt, RequestHelper, TestApp};
+use cargo_registry::models::Organization;
+use http::StatusCode;
+use serde_json::Value;
+
+fn create_organization(user: &MockCookieUser, name: &str) -> i32 {
+    let body = json!({ "organization": { "name": name, "description": "A test organization" } });
+    let mut request = user.post_request("/api/v1/organizations");
+    request.with_body(body.to_string().as_bytes());
+    let json: Value = user.run(request).good();
+    json["organization"]["id"].as_i64().unwrap() as i32
+}
+
+fn invite(user: &MockCookieUser, organization: &str, login: &str, role: &str) -> Value {
+    let body = json!({ "member": { "login": login, "role": role } });
+    let url = format!("/api/v1/organizations/{organization}/members");
+    user.put::<Value>(&url, &serde_json::to_vec(&body).unwrap())
+        .good()
+}
+
+fn accept(user: &MockCookieUser, organization_id: i32) {
+    let body = json!({ "organization_invitation": { "accepted": true } });
+    let url = format!("/api/v1/me/organization_invitations/{organization_id}");
+    user.put::<Value>(&url, &serde_json::to_vec(&body).unwrap())
+        .good();
+}
+
+#[test]
+fn create_and_show_organization() {
+    let (_, anon, user) = TestApp::init().with_user();
+    create_organization(&user, "rust-lang");
+
+    let json = anon.get::<Value>("/api/v1/organizations/Rust-Lang").good();
+    assert_eq!(json["organization"]["name"], "rust-lang");
+    assert_eq!(json["organization"]["description"], "A test organization");
+
+    let body = json!({ "organization": { "name": "RUST-LANG" } });
+    let mut request = user.post_request("/api/v1/organizations");
+    request.with_body(body.to_string().as_bytes());
+    let response = user.run::<()>(request);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the organization name `RUST-LANG` is already taken" }] })
+    );
+
+    let body = json!({ "organization": { "name": "org:name" } });
+    let mut request = user.post_request("/api/v1/organizations");
+    request.with_body(body.to_string().as_bytes());
+    let response = user.run::<()>(request);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn invite_and_manage_members() {
+    let (app, _, user) = TestApp::init().with_user();
+    let other = app.db_new_user("other");
+    let organization_id = create_organization(&user, "rust-lang");
+
+    let response = other.get::<()>("/api/v1/organizations/rust-lang/members");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only members of an organization can see its members" }] })
+    );
+
+    let json = invite(&user, "rust-lang", "other", "viewer");
+    assert_eq!(
+        json["msg"],
+        "user other has been invited to join organization rust-lang"
+    );
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].subject, "Organization invitation");
+    assert!(emails[0]
+        .body
+        .contains("foo has invited you to join the organization rust-lang as a viewer!"));
+
+    let json = other
+        .get::<Value>("/api/v1/me/organization_invitations")
+        .good();
+    assert_eq!(
+        json["organization_invitations"][0]["organization"]["name"],
+        "rust-lang"
+    );
+    assert_eq!(json["organization_invitations"][0]["role"], "viewer");
+    accept(&other, organization_id);
+
+    let json = other
+        .get::<Value>("/api/v1/organizations/rust-lang/members")
+        .good();
+    let members = json["members"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|member| {
+            (
+                member["user"]["login"].as_str().unwrap(),
+                member["role"].as_str().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(members, [("foo", "admin"), ("other", "viewer")]);
+
+    // Viewers can't manage the other members
+    let body = json!({ "member": { "login": "foo", "role": "viewer" } });
+    let response = other.put::<()>(
+        "/api/v1/organizations/rust-lang/members",
+        &serde_json::to_vec(&body).unwrap(),
+    );
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only admins of an organization can manage its members" }] })
+    );
+
+    // The last admin can't step down
+    let response = user.put::<()>(
+        "/api/v1/organizations/rust-lang/members",
+        &serde_json::to_vec(&body).unwrap(),
+    );
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "an organization needs at least one admin, please make another member an admin first" }] })
+    );
+
+    let json = invite(&user, "rust-lang", "other", "admin");
+    assert_eq!(
+        json["msg"],
+        "user other now has the admin role in organization rust-lang"
+    );
+    user.delete::<Value>("/api/v1/organizations/rust-lang/members/foo")
+        .good();
+
+    let json = other
+        .get::<Value>("/api/v1/organizations/rust-lang/members")
+        .good();
+    assert_eq!(json["members"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn declined_invitations_are_removed() {
+    let (app, _, user) = TestApp::init().with_user();
+    let other = app.db_new_user("other");
+    let organization_id = create_organization(&user, "rust-lang");
+    invite(&user, "rust-lang", "other", "publisher");
+
+    let body = json!({ "organization_invitation": { "accepted": false } });
+    let url = format!("/api/v1/me/organization_invitations/{organization_id}");
+    other
+        .put::<Value>(&url, &serde_json::to_vec(&body).unwrap())
+        .good();
+
+    let response = other.put::<()>(&url, &serde_json::to_vec(&body).unwrap());
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = other.get::<()>("/api/v1/organizations/rust-lang/members");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only members of an organization can see its members" }] })
+    );
+}
+
+#[test]
+fn add_organization_as_owner() {
+    let (app, _, user, token) = TestApp::full().with_token();
+    let other = app.db_new_user("other");
+    let organization_id = create_organization(&other, "rust-lang");
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_org", user.as_model().id).expect_build(conn);
+    });
+
+    // Only admins of the organization can add it as an owner
+    let response = token.add_named_owner("foo_org", "org:rust-lang");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only admins of an organization can add it as an owner" }] })
+    );
+
+    let response = token.add_named_owner("foo_org", "org:missing");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "could not find organization `missing`" }] })
+    );
+
+    invite(&other, "rust-lang", "foo", "admin");
+    accept(&user, organization_id);
+
+    token.add_named_owner("foo_org", "org:rust-lang").good();
+
+    let owners = user.show_crate_owners("foo_org");
+    let organization = owners
+        .users
+        .iter()
+        .find(|owner| owner.kind == "organization")
+        .unwrap();
+    assert_eq!(organization.login, "org:rust-lang");
+
+    // Publishers of the organization can now publish the crate
+    let publisher = app.db_new_user("publisher");
+    invite(&other, "rust-lang", "publisher", "publisher");
+    accept(&publisher, organization_id);
+
+    let crate_to_publish = PublishBuilder::new("foo_org").version("2.0.0");
+    publisher.publish_crate(crate_to_publish).good();
+}
+
+#[test]
+fn organization_tokens_are_limited_to_its_crates() {
+    let (app, _, user) = TestApp::full().with_user();
+    let organization_id = create_organization(&user, "rust-lang");
+    let token = user.db_new_organization_token("org token", organization_id);
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_not_org", user.as_model().id).expect_build(conn);
+    });
+
+    let crate_to_publish = PublishBuilder::new("foo_not_org").version("2.0.0");
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // New crates are owned by the organization from the start
+    let crate_to_publish = PublishBuilder::new("foo_new_org");
+    token.publish_crate(crate_to_publish).good();
+
+    let owners = user.show_crate_owners("foo_new_org");
+    let logins = owners
+        .users
+        .iter()
+        .map(|owner| owner.login.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(logins, ["foo", "org:rust-lang"]);
+
+    let crate_to_publish = PublishBuilder::new("foo_new_org").version("2.0.0");
+    token.publish_crate(crate_to_publish).good();
+}
+
+#[test]
+fn delete_organization() {
+    let (app, _, user, token) = TestApp::init().with_token();
+    let other = app.db_new_user("other");
+    let organization_id = create_organization(&user, "rust-lang");
+    invite(&user, "rust-lang", "other", "publisher");
+    accept(&other, organization_id);
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_org", user.as_model().id).expect_build(conn);
+    });
+    token.add_named_owner("foo_org", "org:rust-lang").good();
+
+    let response = other.delete::<()>("/api/v1/organizations/rust-lang");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only admins of an organization can delete it" }] })
+    );
+
+    // Crates can't be left without the organization that manages them
+    let response = user.delete::<()>("/api/v1/organizations/rust-lang");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this organization still owns crates, please remove it from their owners first" }] })
+    );
+
+    user.remove_named_owner("foo_org", "org:rust-lang").good();
+    user.delete::<Value>("/api/v1/organizations/rust-lang")
+        .good();
+
+    let response = user.get::<()>("/api/v1/organizations/rust-lang");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    app.db(|conn| {
+        assert_err!(Organization::find(conn, organization_id));
+    });
+}
@@ -66,7 +66,7 @@ impl MockCookieUser {
 
     /// As the currently logged in user, accept an invitation to become an owner of the named
     /// crate.
-    fn accept_ownership_invitation(&self, krate_name: &str, krate_id: i32) {
+    pub(crate) fn accept_ownership_invitation(&self, krate_name: &str, krate_id: i32) {
         #[derive(Deserialize)]
         struct CrateOwnerInvitation {
             crate_owner_invitation: InvitationResponse,
@@ -182,7 +182,7 @@ fn owners_can_remove_self() {
         .db(|conn| CrateBuilder::new("owners_selfremove", user.as_model().id).expect_build(conn));
 
     // Deleting yourself when you're the only owner isn't allowed.
-    let response = token.remove_named_owner("owners_selfremove", username);
+    let response = user.remove_named_owner("owners_selfremove", username);
     assert_eq!(response.status(), StatusCode::OK);
     assert_eq!(
         response.into_json(),
@@ -192,7 +192,7 @@ fn owners_can_remove_self() {
     create_and_add_owner(&app, &token, "secondowner", &krate);
 
     // Deleting yourself when there are other owners is allowed.
-    let response = token.remove_named_owner("owners_selfremove", username);
+    let response = user.remove_named_owner("owners_selfremove", username);
     assert_eq!(response.status(), StatusCode::OK);
     assert_eq!(
         response.into_json(),
@@ -200,7 +200,7 @@ fn owners_can_remove_self() {
     );
 
     // After you delete yourself, you no longer have permissions to manage the crate.
-    let response = token.remove_named_owner("owners_selfremove", username);
+    let response = user.remove_named_owner("owners_selfremove", username);
     assert_eq!(response.status(), StatusCode::OK);
     assert_eq!(
         response.into_json(),
@@ -209,6 +209,27 @@ fn owners_can_remove_self() {
 }
 
 /// Verify consistency when adidng or removing multiple owners in a single request.
+#[test]
+fn owner_removal_via_token_requires_sudo_mode() {
+    let (app, _, user, token) = TestApp::init().with_token();
+    let krate = app.db(|conn| {
+        CrateBuilder::new("owners_token_removal", user.as_model().id).expect_build(conn)
+    });
+    create_and_add_owner(&app, &token, "secondowner", &krate);
+
+    // API tokens can't enter sudo mode, and the user has no second factor to
+    // confirm the removal with
+    let response = token.remove_named_owner("owners_token_removal", "secondowner");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this action requires sudo mode, please confirm your identity with a second factor or log in again" }] })
+    );
+
+    let owners = app.db(|conn| krate.owners(conn).unwrap());
+    assert_eq!(owners.len(), 2);
+}
+
 #[test]
 fn modify_multiple_owners() {
     let (app, _, user, token) = TestApp::init().with_token();
@@ -221,7 +242,7 @@ fn modify_multiple_owners() {
     let user3 = create_and_add_owner(&app, &token, "user3", &krate);
 
     // Deleting all owners is not allowed.
-    let response = token.remove_named_owners("owners_multiple", &[username, "user2", "user3"]);
+    let response = user.remove_named_owners("owners_multiple", &[username, "user2", "user3"]);
     assert_eq!(response.status(), StatusCode::OK);
     assert_eq!(
         response.into_json(),
@@ -230,7 +251,7 @@ fn modify_multiple_owners() {
     assert_eq!(app.db(|conn| krate.owners(conn).unwrap()).len(), 3);
 
     // Deleting two owners at once is allowed.
-    let response = token.remove_named_owners("owners_multiple", &["user2", "user3"]);
+    let response = user.remove_named_owners("owners_multiple", &["user2", "user3"]);
     assert_eq!(response.status(), StatusCode::OK);
     assert_eq!(
         response.into_json(),
@@ -846,6 +867,67 @@ fn test_accept_expired_invitation_by_mail() {
     assert_eq!(json.users.len(), 1);
 }
 
+#[test]
+fn expiring_invitations_are_reminded_and_expired_ones_deleted() {
+    use cargo_registry::schema::crate_owner_invitations;
+    use cargo_registry::worker;
+
+    let (app, _, owner, owner_token) = TestApp::full().with_token();
+    let owner = owner.as_model();
+    let invited_user = app.db_new_user("user_bar");
+    let (expiring, expired) = app.db(|conn| {
+        let expiring = CrateBuilder::new("expiring_crate", owner.id).expect_build(conn);
+        let expired = CrateBuilder::new("expired_crate", owner.id).expect_build(conn);
+        (expiring, expired)
+    });
+
+    owner_token.add_user_owner("expiring_crate", "user_bar");
+    owner_token.add_user_owner("expired_crate", "user_bar");
+    expire_invitation(&app, expired.id);
+
+    let expiration = app.as_inner().config.ownership_invitations_expiration_days;
+    let process_invitations = || {
+        app.db(|conn| {
+            worker::process_owner_invitations(expiration, 7)
+                .enqueue(conn)
+                .unwrap();
+        });
+        app.run_pending_background_jobs();
+    };
+
+    // Move the first invitation to three days before it expires
+    app.db(|conn| {
+        let created_at = (Utc::now() - Duration::days(expiration as i64 - 3)).naive_utc();
+        diesel::update(crate_owner_invitations::table)
+            .set(crate_owner_invitations::created_at.eq(created_at))
+            .filter(crate_owner_invitations::crate_id.eq(expiring.id))
+            .execute(conn)
+            .unwrap();
+    });
+    process_invitations();
+
+    let crate_ids: Vec<i32> = app.db(|conn| {
+        crate_owner_invitations::table
+            .select(crate_owner_invitations::crate_id)
+            .load(conn)
+            .unwrap()
+    });
+    assert_eq!(crate_ids, [expiring.id]);
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 3);
+    assert_eq!(emails[2].subject, "Crate ownership invitation expires soon");
+    assert!(emails[2].body.contains("expiring_crate"));
+
+    // Invitations are only reminded of once
+    process_invitations();
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 3);
+
+    let json = invited_user.list_invitations();
+    assert_eq!(json.crate_owner_invitations.len(), 1);
+}
+
 #[test]
 fn inactive_users_dont_get_invitations() {
     use cargo_registry::models::NewUser;
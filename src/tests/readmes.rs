@@ -0,0 +1,87 @@
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
+use cargo_registry::schema::readme_renderings;
+use cargo_registry::storage::{InMemoryStorage, Storage, UploadBucket};
+use cargo_registry::{worker, Uploader};
+use diesel::prelude::*;
+use http::header;
+use reqwest::blocking::Client;
+use reqwest::header::HeaderMap;
+
+const LEGACY_PATH: &str = "readmes/foo/foo-1.0.0.html";
+const CURRENT_PATH: &str = "readmes/foo/foo-1.0.0.r1.html";
+
+#[test]
+fn outdated_readmes_are_rerendered() {
+    let client = Client::new();
+    let storage = InMemoryStorage::new();
+    let uploader = Uploader::new(storage.clone());
+
+    let (app, anon, _, token) = TestApp::full()
+        .with_config(|config| config.base.set_uploader(uploader))
+        .with_token();
+
+    let manifest = b"[package]\nname = \"foo\"\nversion = \"1.0.0\"\n";
+    let crate_to_publish = PublishBuilder::new("foo").readme("# foo").files(&[
+        ("foo-1.0.0/Cargo.toml", manifest),
+        ("foo-1.0.0/README.md", b"# Rendered again"),
+    ]);
+    token.publish_crate(crate_to_publish).good();
+
+    let rendered = storage
+        .get(&client, CURRENT_PATH, UploadBucket::Default)
+        .unwrap();
+    assert_some!(rendered);
+
+    // Pretend that the readme was rendered before renderer versions existed
+    storage
+        .delete(&client, CURRENT_PATH, UploadBucket::Default)
+        .unwrap();
+    storage
+        .put(
+            &client,
+            LEGACY_PATH,
+            "<h1>foo</h1>".into(),
+            "text/html",
+            HeaderMap::new(),
+            UploadBucket::Default,
+        )
+        .unwrap();
+    app.db(|conn| {
+        diesel::update(readme_renderings::table)
+            .set(readme_renderings::renderer_version.eq(0))
+            .execute(conn)
+            .unwrap();
+    });
+
+    let response = anon.get::<()>("/api/v1/crates/foo/1.0.0/readme");
+    let location = response.headers()[header::LOCATION].to_str().unwrap();
+    assert!(location.ends_with(LEGACY_PATH), "{location}");
+
+    app.db(|conn| worker::rerender_readmes(0).enqueue(conn).unwrap());
+    app.run_pending_background_jobs();
+
+    let legacy = storage
+        .get(&client, LEGACY_PATH, UploadBucket::Default)
+        .unwrap();
+    assert_none!(legacy);
+
+    let rendered = storage
+        .get(&client, CURRENT_PATH, UploadBucket::Default)
+        .unwrap()
+        .unwrap();
+    let rendered = String::from_utf8(rendered).unwrap();
+    assert!(rendered.contains("Rendered again"), "{rendered}");
+
+    let renderer_version: i32 = app.db(|conn| {
+        readme_renderings::table
+            .select(readme_renderings::renderer_version)
+            .first(conn)
+            .unwrap()
+    });
+    assert_eq!(renderer_version, 1);
+
+    let response = anon.get::<()>("/api/v1/crates/foo/1.0.0/readme");
+    let location = response.headers()[header::LOCATION].to_str().unwrap();
+    assert!(location.ends_with(CURRENT_PATH), "{location}");
+}
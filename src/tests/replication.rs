@@ -0,0 +1,58 @@
+use crate::util::TestApp;
+use cargo_registry::schema::file_replications;
+use cargo_registry::storage::{InMemoryStorage, Storage, UploadBucket};
+use cargo_registry::Uploader;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use reqwest::blocking::Client;
+use reqwest::header::HeaderMap;
+
+#[test]
+fn crate_files_are_copied_to_replicas() {
+    let client = Client::new();
+    let primary = InMemoryStorage::new();
+    let replica = InMemoryStorage::new();
+    let uploader = Uploader::new(primary.clone()).with_replica("replica", replica.clone());
+
+    let (app, _) = TestApp::full()
+        .with_config(|config| config.base.set_uploader(uploader.clone()))
+        .empty();
+
+    let path = "crates/foo/foo-1.0.0.crate";
+    primary
+        .put(
+            &client,
+            path,
+            "foo".into(),
+            "application/gzip",
+            HeaderMap::new(),
+            UploadBucket::Default,
+        )
+        .unwrap();
+
+    app.db(|conn| {
+        uploader.replicate_crate(conn, "foo", "1.0.0").unwrap();
+        // The crate file of this version was deleted before it was replicated
+        uploader.replicate_crate(conn, "bar", "1.0.0").unwrap();
+    });
+    app.run_pending_background_jobs();
+
+    let replicated = replica.get(&client, path, UploadBucket::Default).unwrap();
+    assert_some_eq!(replicated, b"foo");
+
+    let replications: Vec<(String, String, Option<NaiveDateTime>)> = app.db(|conn| {
+        file_replications::table
+            .select((
+                file_replications::path,
+                file_replications::replica,
+                file_replications::replicated_at,
+            ))
+            .load(conn)
+            .unwrap()
+    });
+    assert_eq!(replications.len(), 1);
+    let (replicated_path, replica_name, replicated_at) = &replications[0];
+    assert_eq!(replicated_path, path);
+    assert_eq!(replica_name, "replica");
+    assert_some!(replicated_at);
+}
@@ -0,0 +1,60 @@
+use crate::builders::CrateBuilder;
+use crate::util::{RequestHelper, TestApp};
+use http::StatusCode;
+use serde_json::Value;
+
+#[test]
+fn bulk_downloads() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("serde_json", user.id)
+            .downloads(100)
+            .recent_downloads(10)
+            .expect_build(conn);
+        CrateBuilder::new("rand", user.id)
+            .downloads(5)
+            .expect_build(conn);
+    });
+
+    let url = "/api/v1/crate_downloads";
+    let json: Value = anon
+        .get_with_query(url, "ids=rand,serde-json,missing,rand,")
+        .good();
+    assert_eq!(
+        json,
+        json!({
+            "crates": [
+                { "name": "rand", "downloads": 5, "recent_downloads": 0 },
+                { "name": "serde_json", "downloads": 100, "recent_downloads": 10 },
+            ]
+        })
+    );
+
+    let response = anon.get::<()>(url);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "missing the `ids` parameter" }] })
+    );
+
+    let ids = (0..101).map(|i| format!("crate{i}")).collect::<Vec<_>>();
+    let response = anon.get_with_query::<()>(url, &format!("ids={}", ids.join(",")));
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "at most 100 crates can be requested at once" }] })
+    );
+}
+
+#[test]
+fn crate_named_downloads() {
+    let (app, anon, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("downloads", user.as_model().id).expect_build(conn);
+    });
+
+    let json = anon.show_crate("downloads");
+    assert_eq!(json.krate.name, "downloads");
+}
@@ -0,0 +1,106 @@
+use crate::builders::CrateBuilder;
+use crate::util::{RequestHelper, TestApp};
+use http::{header, StatusCode};
+
+#[test]
+fn suggest() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("serde", user.id)
+            .downloads(100)
+            .expect_build(conn);
+        CrateBuilder::new("serde_json", user.id)
+            .downloads(500)
+            .expect_build(conn);
+        CrateBuilder::new("serde-yaml", user.id)
+            .downloads(50)
+            .expect_build(conn);
+        CrateBuilder::new("serdex", user.id)
+            .downloads(10)
+            .expect_build(conn);
+        CrateBuilder::new("sea-orm", user.id)
+            .downloads(1000)
+            .expect_build(conn);
+    });
+
+    let response = anon.get_with_query::<()>("/api/v1/crate_suggestions", "q=Serde");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CACHE_CONTROL],
+        "public,max-age=300"
+    );
+    assert_eq!(
+        response.into_json(),
+        json!({
+            "suggestions": [
+                { "name": "serde", "downloads": 100 },
+                { "name": "serde_json", "downloads": 500 },
+                { "name": "serde-yaml", "downloads": 50 },
+                { "name": "serdex", "downloads": 10 },
+            ]
+        })
+    );
+
+    // `-` and `_` are equivalent, but not wildcards
+    let json = anon
+        .get_with_query::<()>("/api/v1/crate_suggestions", "q=serde-&limit=1")
+        .into_json();
+    assert_eq!(
+        json,
+        json!({ "suggestions": [{ "name": "serde_json", "downloads": 500 }] })
+    );
+
+    let json = anon
+        .get_with_query::<()>("/api/v1/crate_suggestions", "q=")
+        .into_json();
+    assert_eq!(json, json!({ "suggestions": [] }));
+
+    let response = anon.get_with_query::<()>("/api/v1/crate_suggestions", "q=s&limit=100");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "limit must be between 1 and 25" }] })
+    );
+}
+
+#[test]
+fn suggestions_are_cached() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("tokio", user.id).expect_build(conn);
+    });
+
+    let json = anon
+        .get_with_query::<()>("/api/v1/crate_suggestions", "q=tok")
+        .into_json();
+    assert_eq!(json["suggestions"].as_array().unwrap().len(), 1);
+
+    app.db(|conn| {
+        CrateBuilder::new("tokio-util", user.id).expect_build(conn);
+    });
+
+    let json = anon
+        .get_with_query::<()>("/api/v1/crate_suggestions", "q=tok")
+        .into_json();
+    assert_eq!(json["suggestions"].as_array().unwrap().len(), 1);
+
+    let json = anon
+        .get_with_query::<()>("/api/v1/crate_suggestions", "q=toki")
+        .into_json();
+    assert_eq!(json["suggestions"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn crate_named_suggest() {
+    let (app, anon, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("suggest", user.as_model().id).expect_build(conn);
+    });
+
+    let json = anon.show_crate("suggest");
+    assert_eq!(json.krate.name, "suggest");
+}
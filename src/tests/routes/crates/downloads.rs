@@ -1,8 +1,12 @@
 use crate::builders::{CrateBuilder, VersionBuilder};
 use crate::util::{MockAnonymousUser, RequestHelper, TestApp};
+use cargo_registry::schema::{version_cargo_downloads, version_downloads, versions};
+use cargo_registry::util::hyperloglog::HyperLogLog;
 use cargo_registry::views::EncodableVersionDownload;
-use chrono::{Duration, Utc};
+use chrono::{Duration, NaiveDate, Utc};
+use diesel::prelude::*;
 use http::StatusCode;
+use serde_json::Value;
 
 #[derive(Deserialize)]
 struct Downloads {
@@ -81,3 +85,245 @@ fn download() {
     assert_dl_count(&anon, "FOO_DOWNLOAD/1.0.0", Some(&query), 2);
     assert_dl_count(&anon, "FOO_DOWNLOAD", Some(&query), 2);
 }
+
+#[test]
+fn version_downloads_with_date_range() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_range", user.id)
+            .version(VersionBuilder::new("1.0.0"))
+            .expect_build(conn);
+
+        let version_id: i32 = versions::table.select(versions::id).first(conn).unwrap();
+        let downloads = [
+            ("2023-01-02", 1),
+            ("2023-01-04", 3),
+            ("2023-01-10", 5),
+            ("2023-02-01", 7),
+            ("2023-06-15", 11),
+        ];
+        for (date, downloads) in downloads {
+            let date = NaiveDate::parse_from_str(date, "%F").unwrap();
+            diesel::insert_into(version_downloads::table)
+                .values((
+                    version_downloads::version_id.eq(version_id),
+                    version_downloads::downloads.eq(downloads),
+                    version_downloads::date.eq(date),
+                ))
+                .execute(conn)
+                .unwrap();
+        }
+    });
+
+    let url = "/api/v1/crates/foo_range/1.0.0/downloads";
+    let downloads = |query: &str| {
+        let json: Value = anon.get_with_query(url, query).good();
+        let buckets = json["version_downloads"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| {
+                let date = bucket["date"].as_str().unwrap().to_string();
+                (date, bucket["downloads"].as_i64().unwrap())
+            })
+            .collect::<Vec<_>>();
+        (json["meta"]["interval"].clone(), buckets)
+    };
+
+    // Short ranges are returned as daily counts
+    let (interval, buckets) = downloads("start=2023-01-01&end=2023-01-31");
+    assert_eq!(interval, "day");
+    assert_eq!(
+        buckets,
+        [
+            ("2023-01-02".to_string(), 1),
+            ("2023-01-04".to_string(), 3),
+            ("2023-01-10".to_string(), 5),
+        ]
+    );
+
+    // Longer ranges are aggregated by week
+    let (interval, buckets) = downloads("start=2023-01-01&end=2023-12-31");
+    assert_eq!(interval, "week");
+    assert_eq!(
+        buckets,
+        [
+            ("2023-01-02".to_string(), 4),
+            ("2023-01-09".to_string(), 5),
+            ("2023-01-30".to_string(), 7),
+            ("2023-06-12".to_string(), 11),
+        ]
+    );
+
+    let (interval, buckets) = downloads("start=2023-01-01&end=2023-12-31&interval=month");
+    assert_eq!(interval, "month");
+    assert_eq!(
+        buckets,
+        [
+            ("2023-01-01".to_string(), 9),
+            ("2023-02-01".to_string(), 7),
+            ("2023-06-01".to_string(), 11),
+        ]
+    );
+}
+
+#[test]
+fn version_downloads_with_unique_clients() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    let sketch = |clients: std::ops::Range<u64>| {
+        let mut sketch = HyperLogLog::default();
+        for client in clients {
+            sketch.insert_hash(client.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        }
+        sketch.as_bytes().to_vec()
+    };
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_unique", user.id)
+            .version(VersionBuilder::new("1.0.0"))
+            .expect_build(conn);
+
+        let version_id: i32 = versions::table.select(versions::id).first(conn).unwrap();
+        let downloads = [
+            ("2023-01-02", 10, Some(sketch(0..3))),
+            ("2023-01-03", 20, Some(sketch(2..5))),
+            ("2023-01-10", 5, None),
+        ];
+        for (date, downloads, unique_clients) in downloads {
+            let date = NaiveDate::parse_from_str(date, "%F").unwrap();
+            diesel::insert_into(version_downloads::table)
+                .values((
+                    version_downloads::version_id.eq(version_id),
+                    version_downloads::downloads.eq(downloads),
+                    version_downloads::date.eq(date),
+                    version_downloads::unique_clients.eq(unique_clients),
+                ))
+                .execute(conn)
+                .unwrap();
+        }
+    });
+
+    let url = "/api/v1/crates/foo_unique/1.0.0/downloads";
+    let unique_clients = |query: &str| {
+        let json: Value = anon.get_with_query(url, query).good();
+        json["version_downloads"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| bucket.get("unique_clients").and_then(Value::as_u64))
+            .collect::<Vec<_>>()
+    };
+
+    let daily = unique_clients("start=2023-01-01&end=2023-01-31&interval=day");
+    assert_eq!(daily, [Some(3), Some(3), None]);
+
+    // Clients that downloaded the version on several days are counted once
+    let weekly = unique_clients("start=2023-01-01&end=2023-01-31&interval=week");
+    assert_eq!(weekly, [Some(5), None]);
+}
+
+#[test]
+fn version_downloads_with_invalid_date_range() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_range", user.id)
+            .version(VersionBuilder::new("1.0.0"))
+            .expect_build(conn);
+    });
+
+    let url = "/api/v1/crates/foo_range/1.0.0/downloads";
+    let response = anon.get_with_query::<()>(url, "start=2023-02-01&end=2023-01-01");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the start date must not be after the end date" }] })
+    );
+
+    let response = anon.get_with_query::<()>(url, "start=yesterday");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid start date `yesterday`, expected the format YYYY-MM-DD" }] })
+    );
+
+    let response = anon.get_with_query::<()>(url, "interval=year");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid interval `year`, expected `day`, `week` or `month`" }] })
+    );
+}
+
+#[test]
+fn cargo_versions() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        let krate = CrateBuilder::new("foo_cargo", user.id)
+            .version(VersionBuilder::new("1.0.0"))
+            .version(VersionBuilder::new("1.1.0"))
+            .expect_build(conn);
+
+        let version_ids: Vec<(String, i32)> = versions::table
+            .filter(versions::crate_id.eq(krate.id))
+            .select((versions::num, versions::id))
+            .load(conn)
+            .unwrap();
+        let version_id = |num: &str| {
+            version_ids
+                .iter()
+                .find(|(version, _)| version == num)
+                .unwrap()
+                .1
+        };
+
+        let downloads = [
+            ("1.0.0", "2023-01-02", "1.9", 1),
+            ("1.0.0", "2023-01-02", "1.68", 2),
+            ("1.0.0", "2023-01-03", "1.68", 4),
+            ("1.1.0", "2023-01-03", "1.67", 8),
+            ("1.1.0", "2023-03-01", "1.67", 16),
+        ];
+        for (num, date, cargo_version, downloads) in downloads {
+            let date = NaiveDate::parse_from_str(date, "%F").unwrap();
+            diesel::insert_into(version_cargo_downloads::table)
+                .values((
+                    version_cargo_downloads::version_id.eq(version_id(num)),
+                    version_cargo_downloads::date.eq(date),
+                    version_cargo_downloads::cargo_version.eq(cargo_version),
+                    version_cargo_downloads::downloads.eq(downloads),
+                ))
+                .execute(conn)
+                .unwrap();
+        }
+    });
+
+    let url = "/api/v1/crates/foo_cargo/downloads/cargo_versions";
+    let json: Value = anon
+        .get_with_query(url, "start=2023-01-01&end=2023-01-31")
+        .good();
+    assert_eq!(
+        json,
+        json!({
+            "cargo_versions": [
+                { "version": "1.1.0", "cargo_version": "1.67", "downloads": 8 },
+                { "version": "1.0.0", "cargo_version": "1.68", "downloads": 6 },
+                { "version": "1.0.0", "cargo_version": "1.9", "downloads": 1 },
+            ],
+            "meta": { "start": "2023-01-01", "end": "2023-01-31" },
+        })
+    );
+
+    let response = anon.get_with_query::<()>(url, "start=2023-02-01&end=2023-01-01");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = anon.get::<()>("/api/v1/crates/missing/downloads/cargo_versions");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -133,6 +133,110 @@ fn search_includes_crates_where_name_is_stopword() {
     assert_eq!(json.meta.total, 1);
 }
 
+#[test]
+fn did_you_mean_on_misspelled_queries() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+    app.db(|conn| {
+        CrateBuilder::new("tokio", user.id)
+            .keyword("async")
+            .expect_build(conn);
+
+        // The view is usually refreshed by the `daily_db_maintenance` job
+        diesel::sql_query("REFRESH MATERIALIZED VIEW search_terms")
+            .execute(conn)
+            .unwrap();
+    });
+
+    // Typos in the name are tolerated if nothing matches the query itself
+    let json = anon.search("q=tokoi");
+    assert_eq!(json.meta.total, 1);
+    assert_eq!(json.crates[0].name, "tokio");
+    assert_eq!(json.meta.did_you_mean.as_deref(), Some("tokio"));
+
+    let json = anon.search("q=tokoi%20asyncc");
+    assert_eq!(json.meta.did_you_mean.as_deref(), Some("tokio async"));
+
+    let json = anon.search("q=tokio");
+    assert_eq!(json.meta.total, 1);
+    assert_eq!(json.meta.did_you_mean, None);
+
+    let json = anon.search("q=zzzzzz");
+    assert_eq!(json.meta.did_you_mean, None);
+}
+
+#[test]
+fn typo_tolerant_search() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+    app.db(|conn| {
+        CrateBuilder::new("serde", user.id).expect_build(conn);
+        CrateBuilder::new("serde_json", user.id).expect_build(conn);
+        CrateBuilder::new("reqwest", user.id).expect_build(conn);
+        CrateBuilder::new("request", user.id).expect_build(conn);
+    });
+
+    let json = anon.search("q=serd");
+    assert_eq!(json.meta.total, 2);
+    assert_eq!(json.crates[0].name, "serde");
+
+    let json = anon.search("q=reqwset");
+    assert_eq!(json.meta.total, 1);
+    assert_eq!(json.crates[0].name, "reqwest");
+
+    // Similar names are not included if something matches the query itself
+    let json = anon.search("q=reqwest");
+    assert_eq!(json.meta.total, 1);
+    assert_eq!(json.crates[0].name, "reqwest");
+}
+
+#[test]
+fn search_in_fields() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+    app.db(|conn| {
+        CrateBuilder::new("json", user.id).expect_build(conn);
+        CrateBuilder::new("json_tool", user.id)
+            .description("A parser")
+            .expect_build(conn);
+        CrateBuilder::new("parser", user.id)
+            .description("A fast json parser")
+            .expect_build(conn);
+    });
+
+    let json = anon.search("q=json");
+    assert_eq!(json.meta.total, 3);
+
+    let json = anon.search("q=json&in=name");
+    assert_eq!(json.meta.total, 2);
+    assert_eq!(json.crates[0].name, "json");
+    assert_eq!(json.crates[1].name, "json_tool");
+
+    let json = anon.search("q=json&in=description,readme");
+    assert_eq!(json.meta.total, 1);
+    assert_eq!(json.crates[0].name, "parser");
+
+    let json = anon.search("q=json&in=exact_name");
+    assert_eq!(json.meta.total, 1);
+    assert_eq!(json.crates[0].name, "json");
+
+    let json = anon.search("q=JSON&in=exact_name");
+    assert_eq!(json.meta.total, 1);
+
+    let json = anon.search("q=jso&in=exact_name");
+    assert_eq!(json.meta.total, 0);
+
+    let response = anon.get_with_query::<()>("/api/v1/crates", "q=json&in=license");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid search field `license`, expected one of `name`, `keywords`, `description`, `readme` or `exact_name`" }] })
+    );
+
+    let response = anon.get_with_query::<()>("/api/v1/crates", "q=json&in=exact_name,name");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
 #[test]
 fn exact_match_first_on_queries() {
     let (app, anon, user) = TestApp::init().with_user();
@@ -472,6 +576,130 @@ fn loose_search_order() {
     assert_eq!(search_temp.crates.len(), 3);
 }
 
+fn build_equally_relevant_crates(app: &TestApp, user_id: i32) {
+    app.db(|conn| {
+        CrateBuilder::new("aardvark", user_id)
+            .description("parser")
+            .expect_build(conn);
+
+        CrateBuilder::new("zebra", user_id)
+            .description("parser")
+            .recent_downloads(1000)
+            .expect_build(conn);
+    });
+}
+
+#[test]
+fn popular_crates_rank_higher() {
+    let (app, anon, user) = TestApp::init().with_user();
+    build_equally_relevant_crates(&app, user.as_model().id);
+
+    let json = anon.search("q=parser");
+    assert_eq!(json.meta.total, 2);
+    assert_eq!(json.crates[0].name, "zebra");
+    assert_eq!(json.crates[1].name, "aardvark");
+}
+
+#[test]
+fn popularity_boost_is_configurable() {
+    let (app, anon, user) = TestApp::init()
+        .with_config(|config| config.search_ranking.popularity_weight = 0.0)
+        .with_user();
+    build_equally_relevant_crates(&app, user.as_model().id);
+
+    // Without the boost, equally relevant crates are sorted by name
+    let json = anon.search("q=parser");
+    assert_eq!(json.meta.total, 2);
+    assert_eq!(json.crates[0].name, "aardvark");
+    assert_eq!(json.crates[1].name, "zebra");
+}
+
+#[test]
+fn filter_by_license() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("mit", user.id)
+            .version(VersionBuilder::new("1.0.0").license(Some("MIT")))
+            .expect_build(conn);
+
+        CrateBuilder::new("dual", user.id)
+            .version(VersionBuilder::new("1.0.0").license(Some("MIT OR GPL-3.0")))
+            .expect_build(conn);
+
+        CrateBuilder::new("gpl", user.id)
+            .version(VersionBuilder::new("1.0.0").license(Some("MIT AND GPL-3.0")))
+            .expect_build(conn);
+
+        // Only the license of the newest non-yanked version is considered
+        CrateBuilder::new("relicensed", user.id)
+            .version(VersionBuilder::new("1.0.0").license(Some("Apache-2.0")))
+            .version(VersionBuilder::new("2.0.0").license(Some("GPL-3.0")))
+            .expect_build(conn);
+
+        CrateBuilder::new("unlicensed", user.id)
+            .version(VersionBuilder::new("1.0.0").license(None))
+            .expect_build(conn);
+    });
+
+    let json = anon.search("license=MIT%20OR%20Apache-2.0");
+    assert_eq!(json.meta.total, 2);
+    assert_eq!(json.crates[0].name, "dual");
+    assert_eq!(json.crates[1].name, "mit");
+
+    let json = anon.search("license=MIT%20OR%20GPL-3.0");
+    assert_eq!(json.meta.total, 4);
+
+    let response = anon.get_with_query::<()>("/api/v1/crates", "license=MIT%20AND%20GPL-3.0");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "accepted licenses can only be combined with `OR`" }] })
+    );
+}
+
+#[test]
+fn filter_by_msrv() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("old", user.id)
+            .version(VersionBuilder::new("1.0.0").rust_version("1.56.0"))
+            .expect_build(conn);
+
+        CrateBuilder::new("new", user.id)
+            .version(VersionBuilder::new("1.0.0").rust_version("1.70.1"))
+            .expect_build(conn);
+
+        // Only the newest non-yanked version is considered
+        CrateBuilder::new("bumped", user.id)
+            .version(VersionBuilder::new("1.0.0").rust_version("1.56.0"))
+            .version(VersionBuilder::new("2.0.0").rust_version("1.80.0"))
+            .expect_build(conn);
+
+        CrateBuilder::new("unspecified", user.id)
+            .version(VersionBuilder::new("1.0.0"))
+            .expect_build(conn);
+    });
+
+    let json = anon.search("msrv%3C=1.70");
+    assert_eq!(json.meta.total, 2);
+    assert_eq!(json.crates[0].name, "old");
+    assert_eq!(json.crates[1].name, "unspecified");
+
+    let json = anon.search("msrv=1.70.1");
+    assert_eq!(json.meta.total, 3);
+
+    let response = anon.get_with_query::<()>("/api/v1/crates", "msrv=stable");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid Rust version: stable" }] })
+    );
+}
+
 #[test]
 fn index_include_yanked() {
     let (app, anon, user) = TestApp::init().with_user();
@@ -695,17 +923,16 @@ fn pagination_links_included_if_applicable() {
         CrateBuilder::new("pagination_links_3", user.id).expect_build(conn);
     });
 
-    // This uses a filter (`letter=p`) to disable seek-based pagination, as seek-based pagination
-    // does not return page numbers. If the test fails after expanding the scope of seek-based
-    // pagination replace the filter with something else still using pages.
+    // This uses an explicit `page=1` to disable seek-based pagination, as seek-based pagination
+    // does not return page numbers.
 
-    let page1 = anon.search("letter=p&per_page=1");
+    let page1 = anon.search("letter=p&page=1&per_page=1");
     let page2 = anon.search("letter=p&page=2&per_page=1");
     let page3 = anon.search("letter=p&page=3&per_page=1");
     let page4 = anon.search("letter=p&page=4&per_page=1");
 
     assert_eq!(
-        Some("?letter=p&per_page=1&page=2".to_string()),
+        Some("?letter=p&page=2&per_page=1".to_string()),
         page1.meta.next_page
     );
     assert_eq!(None, page1.meta.prev_page);
@@ -772,6 +999,104 @@ fn seek_based_pagination() {
     );
 }
 
+#[test]
+fn seek_based_pagination_with_filters_and_sorting() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("seek_1", user.id)
+            .description("Seek through crates")
+            .downloads(20)
+            .recent_downloads(5)
+            .expect_build(conn);
+        CrateBuilder::new("seek_2", user.id)
+            .description("Seek through crates")
+            .downloads(30)
+            .expect_build(conn);
+        CrateBuilder::new("seek_3", user.id)
+            .description("Seek through crates")
+            .downloads(20)
+            .recent_downloads(10)
+            .expect_build(conn);
+        CrateBuilder::new("other", user.id)
+            .downloads(50)
+            .expect_build(conn);
+    });
+
+    let seek_all = |query: &str| {
+        let mut url = Some(format!("?{query}&per_page=1"));
+        let mut results = Vec::new();
+        let mut totals = Vec::new();
+        while let Some(current_url) = url.take() {
+            let resp = anon.search(current_url.trim_start_matches('?'));
+            assert_eq!(None, resp.meta.prev_page);
+            totals.push(resp.meta.total);
+            results.extend(resp.crates.into_iter().map(|krate| krate.name));
+
+            if let Some(new_url) = resp.meta.next_page {
+                assert!(new_url.contains("seek="));
+                url = Some(new_url);
+            }
+        }
+
+        // The total of the first page is passed along to the following pages
+        assert!(totals.iter().all(|total| *total == totals[0]));
+        (totals[0], results)
+    };
+
+    let (total, results) = seek_all("sort=downloads");
+    assert_eq!(4, total);
+    assert_eq!(vec!["other", "seek_2", "seek_1", "seek_3"], results);
+
+    // Crates without recent downloads are sorted last
+    let (total, results) = seek_all("sort=recent-downloads");
+    assert_eq!(4, total);
+    assert_eq!(vec!["seek_3", "seek_1", "other", "seek_2"], results);
+
+    let (total, results) = seek_all("letter=s&sort=downloads");
+    assert_eq!(3, total);
+    assert_eq!(vec!["seek_2", "seek_1", "seek_3"], results);
+
+    // Searches are paginated in the same order as the results of a single page
+    let (total, results) = seek_all("q=seek");
+    let json = anon.search("q=seek&per_page=10");
+    assert_eq!(json.meta.total, total);
+    assert_eq!(
+        json.crates
+            .into_iter()
+            .map(|krate| krate.name)
+            .collect::<Vec<_>>(),
+        results
+    );
+
+    let (total, results) = seek_all("q=seek_3");
+    let json = anon.search("q=seek_3&per_page=10");
+    assert_eq!(json.meta.total, total);
+    assert_eq!(json.crates[0].name, "seek_3");
+    assert_eq!(
+        json.crates
+            .into_iter()
+            .map(|krate| krate.name)
+            .collect::<Vec<_>>(),
+        results
+    );
+
+    // Seek keys can't be used with a different sort order
+    let json = anon.search("sort=downloads&per_page=1");
+    let next_page = json
+        .meta
+        .next_page
+        .unwrap()
+        .replace("sort=downloads", "sort=new");
+    let response = anon.get_with_query::<()>("/api/v1/crates", next_page.trim_start_matches('?'));
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the seek parameter does not match the sort order" }] })
+    );
+}
+
 #[test]
 fn test_pages_work_even_with_seek_based_pagination() {
     let (app, anon, user) = TestApp::init().with_user();
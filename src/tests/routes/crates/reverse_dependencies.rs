@@ -1,7 +1,11 @@
 use crate::builders::{CrateBuilder, VersionBuilder};
 use crate::util::{RequestHelper, TestApp};
 use crate::CrateMeta;
+use cargo_registry::models::Crate;
 use cargo_registry::views::{EncodableDependency, EncodableVersion};
+use cargo_registry::worker;
+use diesel::prelude::*;
+use http::StatusCode;
 
 #[derive(Deserialize)]
 struct RevDeps {
@@ -15,6 +19,30 @@ impl crate::util::MockAnonymousUser {
         let url = format!("/api/v1/crates/{krate_name}/reverse_dependencies");
         self.get(&url).good()
     }
+
+    fn reverse_dependencies_with_query(&self, krate_name: &str, query: &str) -> RevDeps {
+        let url = format!("/api/v1/crates/{krate_name}/reverse_dependencies");
+        self.get_with_query(&url, query).good()
+    }
+}
+
+/// Follows the `next_page` links and returns the names of the dependent
+/// crates of all pages.
+fn all_pages(anon: &crate::util::MockAnonymousUser, krate_name: &str, query: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut query = query.to_string();
+    loop {
+        let deps = anon.reverse_dependencies_with_query(krate_name, &query);
+        // The versions are not ordered, so they are looked up in the order of the dependencies
+        names.extend(deps.dependencies.iter().map(|dependency| {
+            let version = deps.versions.iter().find(|v| v.id == dependency.version_id);
+            version.unwrap().krate.clone()
+        }));
+        match deps.meta.next_page {
+            Some(next_page) => query = next_page.trim_start_matches('?').to_string(),
+            None => return names,
+        }
+    }
 }
 
 #[test]
@@ -215,3 +243,115 @@ fn reverse_dependencies_query_supports_u64_version_number_parts() {
     assert_eq!(deps.versions[0].krate, "c2");
     assert_eq!(deps.versions[0].num, large_but_valid_version_number);
 }
+
+#[test]
+fn reverse_dependencies_seek_pagination() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        let c1 = CrateBuilder::new("c1", user.id).expect_build(conn);
+        for (name, downloads) in [("b", 10), ("a", 10), ("d", 30), ("c", 20)] {
+            CrateBuilder::new(name, user.id)
+                .downloads(downloads)
+                .version(VersionBuilder::new("1.0.0").dependency(&c1, None))
+                .expect_build(conn);
+        }
+    });
+
+    let deps = anon.reverse_dependencies_with_query("c1", "per_page=3");
+    assert_eq!(deps.dependencies.len(), 3);
+    assert_eq!(deps.meta.total, 4);
+    assert_some!(deps.meta.next_page);
+
+    assert_eq!(all_pages(&anon, "c1", "per_page=3"), ["d", "c", "a", "b"]);
+    assert_eq!(all_pages(&anon, "c1", "per_page=1"), ["d", "c", "a", "b"]);
+    assert_eq!(
+        all_pages(&anon, "c1", "per_page=2&sort=name"),
+        ["a", "b", "c", "d"]
+    );
+
+    // The total is passed along to the following pages
+    let deps = anon.reverse_dependencies_with_query("c1", "per_page=3");
+    let next_page = deps.meta.next_page.unwrap();
+    let deps = anon.reverse_dependencies_with_query("c1", next_page.trim_start_matches('?'));
+    assert_eq!(deps.meta.total, 4);
+    assert_none!(deps.meta.next_page);
+
+    // Explicit pages are still supported
+    let deps = anon.reverse_dependencies_with_query("c1", "per_page=3&page=2");
+    assert_eq!(deps.versions.len(), 1);
+    assert_eq!(deps.versions[0].krate, "b");
+    assert_none!(deps.meta.next_page);
+}
+
+#[test]
+fn reverse_dependencies_seek_must_match_sort() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        let c1 = CrateBuilder::new("c1", user.id).expect_build(conn);
+        for name in ["c2", "c3"] {
+            CrateBuilder::new(name, user.id)
+                .version(VersionBuilder::new("1.0.0").dependency(&c1, None))
+                .expect_build(conn);
+        }
+    });
+
+    let deps = anon.reverse_dependencies_with_query("c1", "per_page=1");
+    let next_page = deps.meta.next_page.unwrap();
+    let query = format!("{}&sort=name", next_page.trim_start_matches('?'));
+
+    let url = "/api/v1/crates/c1/reverse_dependencies";
+    let response = anon.get_with_query::<()>(url, &query);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the seek parameter does not match the sort order" }] })
+    );
+}
+
+#[test]
+fn reverse_dependencies_total_is_cached() {
+    let (app, anon, user) = TestApp::full().with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        let c1 = CrateBuilder::new("c1", user.id).expect_build(conn);
+        CrateBuilder::new("c2", user.id)
+            .version(VersionBuilder::new("1.0.0").dependency(&c1, None))
+            .expect_build(conn);
+
+        worker::refresh_reverse_dependency_counts()
+            .enqueue(conn)
+            .unwrap();
+    });
+    app.run_pending_background_jobs();
+
+    app.db(|conn| {
+        let c1: Crate = Crate::by_name("c1").first(conn).unwrap();
+        CrateBuilder::new("c3", user.id)
+            .version(VersionBuilder::new("1.0.0").dependency(&c1, None))
+            .expect_build(conn);
+    });
+
+    // The total is only updated by the background job
+    let deps = anon.reverse_dependencies("c1");
+    assert_eq!(deps.dependencies.len(), 2);
+    assert_eq!(deps.meta.total, 1);
+
+    app.db(|conn| {
+        worker::refresh_reverse_dependency_counts()
+            .enqueue(conn)
+            .unwrap();
+    });
+    app.run_pending_background_jobs();
+
+    let deps = anon.reverse_dependencies("c1");
+    assert_eq!(deps.meta.total, 2);
+
+    // Crates without dependents are counted as well
+    let deps = anon.reverse_dependencies("c2");
+    assert_eq!(deps.meta.total, 0);
+}
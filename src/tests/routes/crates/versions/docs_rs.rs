@@ -0,0 +1,83 @@
+use crate::builders::{CrateBuilder, VersionBuilder};
+use crate::util::{MockAnonymousUser, MockRequestExt, RequestHelper, Response, TestApp};
+use http::{header, StatusCode};
+
+fn report_status(anon: &MockAnonymousUser, token: Option<&str>, body: &str) -> Response<()> {
+    let mut request =
+        anon.request_builder(http::Method::PUT, "/api/private/docs_rs/crates/foo/1.0.0");
+    if let Some(token) = token {
+        request.header(header::AUTHORIZATION, &format!("Bearer {token}"));
+    }
+    request.with_body(body.as_bytes());
+    anon.run(request)
+}
+
+fn app_with_crate(token: Option<&str>) -> MockAnonymousUser {
+    let token = token.map(String::from);
+    let (app, anon, user) = TestApp::init()
+        .with_config(|config| config.docs_rs_callback_token = token)
+        .with_user();
+
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id)
+            .version(VersionBuilder::new("1.0.0"))
+            .expect_build(conn);
+    });
+
+    anon
+}
+
+#[test]
+fn report_build_status() {
+    let anon = app_with_crate(Some("secret"));
+
+    let json = anon.show_version("foo", "1.0.0");
+    assert_none!(json.version.docs_rs_status);
+    assert_none!(json.version.docs_rs_updated_at);
+
+    let response = report_status(&anon, Some("secret"), r#"{"status":"failure"}"#);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let json = anon.show_version("foo", "1.0.0");
+    assert_some_eq!(json.version.docs_rs_status, "failure");
+    assert_some!(json.version.docs_rs_updated_at);
+
+    let response = report_status(&anon, Some("secret"), r#"{"status":"success"}"#);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let json = anon.show_version("foo", "1.0.0");
+    assert_some_eq!(json.version.docs_rs_status, "success");
+}
+
+#[test]
+fn report_invalid_build_status() {
+    let anon = app_with_crate(Some("secret"));
+
+    let response = report_status(&anon, Some("secret"), r#"{"status":"pending"}"#);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let json = anon.show_version("foo", "1.0.0");
+    assert_none!(json.version.docs_rs_status);
+}
+
+#[test]
+fn report_build_status_wrong_auth() {
+    let anon = app_with_crate(Some("secret"));
+
+    let response = report_status(&anon, Some("foobar"), r#"{"status":"success"}"#);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = report_status(&anon, None, r#"{"status":"success"}"#);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let json = anon.show_version("foo", "1.0.0");
+    assert_none!(json.version.docs_rs_status);
+}
+
+#[test]
+fn report_build_status_disabled() {
+    let anon = app_with_crate(None);
+
+    let response = report_status(&anon, Some("secret"), r#"{"status":"success"}"#);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
@@ -97,3 +97,66 @@ fn download_caches_version_id() {
     // Check download count against the new name, rather than rename it back to the original value
     downloads::assert_dl_count(&anon, "other/1.0.0", None, 2);
 }
+
+#[test]
+fn downloads_are_not_counted_if_counted_from_cdn_logs() {
+    use super::super::downloads;
+
+    let (app, anon, user) = TestApp::init()
+        .with_config(|config| {
+            config.downloads_counted_from_cdn_logs = true;
+        })
+        .with_user();
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_download", user.as_model().id)
+            .version(VersionBuilder::new("1.0.0"))
+            .expect_build(conn);
+    });
+
+    // The first request loads the version ID from the database, the second one is cached
+    for _ in 0..2 {
+        anon.get::<()>("/api/v1/crates/foo_download/1.0.0/download")
+            .assert_redirect_ends_with("/crates/foo_download/foo_download-1.0.0.crate");
+    }
+
+    downloads::persist_downloads_count(&app);
+    downloads::assert_dl_count(&anon, "foo_download/1.0.0", None, 0);
+}
+
+#[test]
+fn download_redirects_to_signed_url() {
+    use cargo_registry::storage::S3Storage;
+    use cargo_registry::Uploader;
+    use chrono::Duration;
+    use http::header;
+
+    let bucket = s3::Bucket::new(
+        "private-bucket".into(),
+        None,
+        "access-key".into(),
+        "secret-key".into(),
+        "https",
+    );
+    let storage = S3Storage::new(bucket, None, Some("static.crates.io".into()))
+        .with_signed_urls(Duration::minutes(5));
+
+    let (app, anon, user) = TestApp::init()
+        .with_config(|config| config.base.set_uploader(Uploader::new(storage)))
+        .with_user();
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_download", user.as_model().id)
+            .version(VersionBuilder::new("1.0.0"))
+            .expect_build(conn);
+    });
+
+    let response = anon.get::<()>("/api/v1/crates/foo_download/1.0.0/download");
+    let location = response.headers()[header::LOCATION].to_str().unwrap();
+    let url = reqwest::Url::parse(location).unwrap();
+    assert_eq!(url.host_str(), Some("private-bucket.s3.amazonaws.com"));
+    assert_eq!(url.path(), "/crates/foo_download/foo_download-1.0.0.crate");
+
+    let params: Vec<String> = url.query_pairs().map(|(key, _)| key.into_owned()).collect();
+    assert_eq!(params, ["AWSAccessKeyId", "Expires", "Signature"]);
+}
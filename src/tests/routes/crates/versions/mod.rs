@@ -1,5 +1,7 @@
 mod authors;
 pub mod dependencies;
+mod docs_rs;
 pub mod download;
 mod read;
+mod signature;
 pub mod yank_unyank;
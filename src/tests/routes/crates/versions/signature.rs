@@ -0,0 +1,72 @@
+use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::util::{MockRequestExt, RequestHelper, TestApp};
+use http::{header, Method, StatusCode};
+use serde_json::Value;
+
+/// A bundle with a signature of the empty tarball that `PublishBuilder`
+/// uploads by default.
+const SIGSTORE_BUNDLE: &[u8] = include_bytes!("../../../sigstore-bundle.json");
+
+#[test]
+fn publish_with_sigstore_bundle() {
+    let (_, anon, _, token) = TestApp::full().with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo_new").signature(SIGSTORE_BUNDLE);
+    token.publish_crate(crate_to_publish).good();
+
+    let mut request = anon.request_builder(Method::GET, "/api/v1/crates/foo_new/1.0.0/signature");
+    request.header(header::ACCEPT, "application/json");
+    let json: Value = anon.run(request).good();
+    assert_eq!(json["signature"]["kind"], "sigstore_bundle");
+    assert_eq!(json["signature"]["size"], SIGSTORE_BUNDLE.len());
+    assert!(json["signature"]["url"]
+        .as_str()
+        .unwrap()
+        .ends_with("/crates/foo_new/foo_new-1.0.0.crate.sig"));
+
+    let response = anon.get::<()>("/api/v1/crates/foo_new/1.0.0/signature");
+    assert_eq!(response.status(), StatusCode::FOUND);
+    response.assert_redirect_ends_with("/crates/foo_new/foo_new-1.0.0.crate.sig");
+}
+
+#[test]
+fn sigstore_bundle_for_other_file() {
+    let (_, _, _, token) = TestApp::full().with_token();
+
+    let crate_to_publish = PublishBuilder::new("foo_new")
+        .files(&[("foo_new-1.0.0/README.md", b"hello" as &[_])])
+        .signature(SIGSTORE_BUNDLE);
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid Sigstore bundle: the bundle is for a different crate file" }] })
+    );
+}
+
+#[test]
+fn version_without_signature() {
+    let (app, anon, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id)
+            .version("1.0.0")
+            .expect_build(conn);
+    });
+
+    anon.get::<()>("/api/v1/crates/foo/1.0.0/signature")
+        .assert_not_found();
+}
+
+#[test]
+fn signature_too_large() {
+    let (_, _, _, token) = TestApp::full().with_token();
+
+    let signature = vec![b'x'; 64 * 1024 + 1];
+    let crate_to_publish = PublishBuilder::new("foo_new").signature(&signature);
+    let response = token.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "max signature size is: 65536" }] })
+    );
+}
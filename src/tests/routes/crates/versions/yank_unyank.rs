@@ -7,6 +7,9 @@ pub trait YankRequestHelper {
     /// Yank the specified version of the specified crate and run all pending background jobs
     fn yank(&self, krate_name: &str, version: &str) -> Response<OkBool>;
 
+    /// Yank the specified version with the given request body and run all pending background jobs
+    fn yank_with_body(&self, krate_name: &str, version: &str, body: &[u8]) -> Response<OkBool>;
+
     /// Unyank the specified version of the specified crate and run all pending background jobs
     fn unyank(&self, krate_name: &str, version: &str) -> Response<OkBool>;
 }
@@ -19,6 +22,13 @@ impl<T: RequestHelper> YankRequestHelper for T {
         response
     }
 
+    fn yank_with_body(&self, krate_name: &str, version: &str, body: &[u8]) -> Response<OkBool> {
+        let url = format!("/api/v1/crates/{krate_name}/{version}/yank");
+        let response = self.delete_with_body(&url, body);
+        self.app().run_pending_background_jobs();
+        response
+    }
+
     fn unyank(&self, krate_name: &str, version: &str) -> Response<OkBool> {
         let url = format!("/api/v1/crates/{krate_name}/{version}/unyank");
         let response = self.put(&url, &[]);
@@ -0,0 +1,508 @@
+use crate::builders::{CrateBuilder, VersionBuilder};
+use crate::util::{MockRequestExt, RequestHelper, TestApp, TestDatabase};
+use cargo_registry::worker;
+use cargo_registry_index::testing::UpstreamIndex;
+use flate2::write::GzEncoder;
+use http::{header, StatusCode};
+use serde_json::Value;
+use std::io::Write;
+
+fn index_entries(text: &str) -> Vec<Value> {
+    text.lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn index_file() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+    app.db(|conn| {
+        let dep = CrateBuilder::new("dep_crate", user.id)
+            .version("1.0.0")
+            .expect_build(conn);
+
+        CrateBuilder::new("Foo_Bar", user.id)
+            .version(VersionBuilder::new("1.0.0").dependency(&dep, None))
+            .version(VersionBuilder::new("1.1.0").yanked(true))
+            .expect_build(conn);
+    });
+
+    let response = anon.get::<()>("/index/fo/o_/foo_bar");
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let entries = index_entries(&response.into_text());
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["name"], "Foo_Bar");
+    assert_eq!(entries[0]["vers"], "1.0.0");
+    assert_eq!(entries[0]["yanked"], false);
+    assert_eq!(entries[0]["deps"][0]["name"], "dep_crate");
+    assert_eq!(entries[0]["deps"][0]["kind"], "normal");
+    assert_eq!(entries[1]["vers"], "1.1.0");
+    assert_eq!(entries[1]["yanked"], true);
+    assert_eq!(entries[1]["deps"], json!([]));
+
+    let mut request = anon.get_request("/index/fo/o_/foo_bar");
+    request.header(header::IF_NONE_MATCH, &etag);
+    let response = anon.run::<()>(request);
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    let mut request = anon.get_request("/index/fo/o_/foo_bar");
+    request.header(header::IF_NONE_MATCH, "\"outdated\"");
+    let response = anon.run::<()>(request);
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn index_file_not_found() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+    app.db(|conn| {
+        CrateBuilder::new("foo_bar", user.id)
+            .version("1.0.0")
+            .expect_build(conn);
+    });
+
+    // Wrong prefix directories
+    anon.get::<()>("/index/fo/ob/foo_bar").assert_not_found();
+    // Crate names in the index are lowercased
+    anon.get::<()>("/index/FO/O_/Foo_Bar").assert_not_found();
+    // `-` and `_` are distinct in the index
+    anon.get::<()>("/index/fo/o-/foo-bar").assert_not_found();
+    anon.get::<()>("/index/3/u/unknown").assert_not_found();
+}
+
+#[test]
+fn crate_index() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user = user.as_model();
+    app.db(|conn| {
+        CrateBuilder::new("Foo_Bar", user.id)
+            .version("1.0.0")
+            .version(VersionBuilder::new("1.1.0").yanked(true))
+            .expect_build(conn);
+    });
+
+    let response = anon.get::<()>("/api/v1/crates/Foo_Bar/index");
+    assert_eq!(response.status(), StatusCode::OK);
+    let text = response.into_text();
+
+    let entries = index_entries(&text);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["vers"], "1.0.0");
+    assert_eq!(entries[1]["vers"], "1.1.0");
+    assert_eq!(entries[1]["yanked"], true);
+    assert!(entries[0]["cksum"].is_string());
+
+    // The entries are identical to the sparse index file
+    let index_file = anon.get::<()>("/index/fo/o_/foo_bar").into_text();
+    assert_eq!(text, index_file);
+
+    anon.get::<()>("/api/v1/crates/unknown/index")
+        .assert_not_found();
+}
+
+#[test]
+fn config_json() {
+    let (_, anon) = TestApp::init().empty();
+    let json = anon.get::<Value>("/index/config.json").good();
+    assert!(json["dl"].as_str().unwrap().ends_with("/api/v1/crates"));
+    assert!(json["api"].is_string());
+    assert_eq!(json.get("auth-required"), None);
+}
+
+#[test]
+fn config_json_for_private_registry() {
+    let (_, _, _, token) = TestApp::init()
+        .with_config(|config| {
+            config.index_config.dl = "https://dl.example.com/{crate}/{version}".into();
+            config.index_config.api = None;
+            config.index_config.auth_required = true;
+        })
+        .with_token();
+
+    let json = token.get::<Value>("/index/config.json").good();
+    assert_eq!(
+        json,
+        json!({
+            "dl": "https://dl.example.com/{crate}/{version}",
+            "auth-required": true,
+        })
+    );
+}
+
+#[test]
+fn squash_index_if_needed() {
+    let (app, anon) = TestApp::full().empty();
+    let upstream = app.upstream_index();
+    upstream.create_empty_commit().unwrap();
+    upstream.create_empty_commit().unwrap();
+    let previous_head = upstream.repository.head().unwrap().target().unwrap();
+
+    // The initial commit and two empty commits are below the threshold
+    app.db(|conn| worker::squash_index_if_needed(3).enqueue(conn).unwrap());
+    app.run_pending_background_jobs();
+    let json = anon.get::<Value>("/api/v1/index/squashes").good();
+    assert_eq!(json["squashes"], json!([]));
+
+    app.db(|conn| worker::squash_index_if_needed(2).enqueue(conn).unwrap());
+    app.run_pending_background_jobs();
+
+    let head = upstream
+        .repository
+        .head()
+        .unwrap()
+        .peel_to_commit()
+        .unwrap();
+    assert_eq!(head.parent_count(), 0);
+
+    let json = anon.get::<Value>("/api/v1/index/squashes").good();
+    let squashes = json["squashes"].as_array().unwrap();
+    assert_eq!(squashes.len(), 1);
+    assert_eq!(squashes[0]["previous_head"], previous_head.to_string());
+    let squashed_at = squashes[0]["squashed_at"].as_str().unwrap();
+    assert_ok!(chrono::DateTime::parse_from_rfc3339(squashed_at));
+
+    let snapshot_branch = squashes[0]["snapshot_branch"].as_str().unwrap();
+    let snapshot = upstream
+        .repository
+        .refname_to_id(&format!("refs/heads/{snapshot_branch}"))
+        .unwrap();
+    assert_eq!(snapshot, previous_head);
+}
+
+#[test]
+fn check_index_consistency() {
+    use cargo_registry::schema::index_inconsistencies;
+    use diesel::prelude::*;
+
+    let (app, _, user) = TestApp::full().with_user();
+    let user = user.as_model();
+
+    // Crates created by the builder are not added to the git index
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.id)
+            .version("1.0.0")
+            .expect_build(conn);
+    });
+
+    let upstream = app.upstream_index();
+    assert!(upstream.crates_from_index_head("foo").is_err());
+
+    app.db(|conn| {
+        worker::check_index_consistency(10, false, false)
+            .enqueue(conn)
+            .unwrap()
+    });
+    app.run_pending_background_jobs();
+
+    let inconsistencies: Vec<(bool, bool)> = app.db(|conn| {
+        index_inconsistencies::table
+            .select((
+                index_inconsistencies::git_mismatch,
+                index_inconsistencies::sparse_mismatch,
+            ))
+            .load(conn)
+            .unwrap()
+    });
+    assert_eq!(inconsistencies, vec![(true, false)]);
+
+    app.db(|conn| {
+        worker::check_index_consistency(10, false, true)
+            .enqueue(conn)
+            .unwrap()
+    });
+    app.run_pending_background_jobs();
+
+    let crates = upstream.crates_from_index_head("foo").unwrap();
+    assert_eq!(crates.len(), 1);
+    assert_eq!(crates[0].vers, "1.0.0");
+
+    let count: i64 = app.db(|conn| {
+        index_inconsistencies::table
+            .count()
+            .get_result(conn)
+            .unwrap()
+    });
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn git_http_clone() {
+    let upstream = UpstreamIndex::new().unwrap();
+    let repo_path = upstream.repository.path().to_path_buf();
+    let head = upstream.repository.head().unwrap().target().unwrap();
+
+    let (_, anon) = TestApp::init()
+        .with_config(|config| config.git_http_index_path = Some(repo_path))
+        .empty();
+
+    let response = anon.get::<()>("/git/index/info/refs?service=git-upload-pack");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "application/x-git-upload-pack-advertisement"
+    );
+    let refs = response.into_text();
+    assert!(refs.starts_with("001e# service=git-upload-pack\n0000"));
+    assert!(refs.contains(&format!("{head} refs/heads/master")));
+
+    let mut request = anon.post_request("/git/index/git-upload-pack");
+    request.header(
+        header::CONTENT_TYPE,
+        "application/x-git-upload-pack-request",
+    );
+    request.with_body(format!("0032want {head}\n00000009done\n").as_bytes());
+    let response = anon.run::<()>(request);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "application/x-git-upload-pack-result"
+    );
+    let pack = response.into_text();
+    assert!(pack.starts_with("0008NAK\n"));
+    assert!(pack.contains("PACK"));
+}
+
+#[test]
+fn git_http_upload_pack_size_limits() {
+    let upstream = UpstreamIndex::new().unwrap();
+    let repo_path = upstream.repository.path().to_path_buf();
+
+    let (_, anon) = TestApp::init()
+        .with_config(|config| config.git_http_index_path = Some(repo_path))
+        .empty();
+
+    let too_large = vec![b'0'; 10 * 1024 * 1024 + 1];
+
+    let mut request = anon.post_request("/git/index/git-upload-pack");
+    request.header(
+        header::CONTENT_TYPE,
+        "application/x-git-upload-pack-request",
+    );
+    request.with_body(&too_large);
+    let response = anon.run::<()>(request);
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Small requests that decompress to too much data are rejected as well
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(&too_large).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let mut request = anon.post_request("/git/index/git-upload-pack");
+    request.header(
+        header::CONTENT_TYPE,
+        "application/x-git-upload-pack-request",
+    );
+    request.header(header::CONTENT_ENCODING, "gzip");
+    request.with_body(&compressed);
+    let response = anon.run::<()>(request);
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[test]
+fn git_http_is_read_only() {
+    let upstream = UpstreamIndex::new().unwrap();
+    let repo_path = upstream.repository.path().to_path_buf();
+
+    let (_, anon) = TestApp::init()
+        .with_config(|config| config.git_http_index_path = Some(repo_path))
+        .empty();
+
+    anon.get::<()>("/git/index/info/refs?service=git-receive-pack")
+        .assert_forbidden();
+    anon.get::<()>("/git/index/info/refs").assert_forbidden();
+
+    let request = anon.post_request("/git/index/git-receive-pack");
+    anon.run::<()>(request).assert_not_found();
+}
+
+#[test]
+fn git_http_disabled() {
+    let (_, anon) = TestApp::init().empty();
+    anon.get::<()>("/git/index/info/refs?service=git-upload-pack")
+        .assert_not_found();
+}
+
+#[test]
+fn changes() {
+    use cargo_registry::models::{IndexChange, IndexChangeAction};
+
+    let (app, anon) = TestApp::init().empty();
+    app.db(|conn| {
+        IndexChange::record(conn, "foo", Some("1.0.0"), IndexChangeAction::Publish).unwrap();
+        IndexChange::record(conn, "foo", Some("1.0.0"), IndexChangeAction::Yank).unwrap();
+        IndexChange::record(conn, "bar", None, IndexChangeAction::Deprecate).unwrap();
+    });
+
+    let json = anon.get::<Value>("/api/v1/index/changes").good();
+    let changes = json["changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 3);
+    assert_eq!(changes[0]["crate"], "foo");
+    assert_eq!(changes[0]["version"], "1.0.0");
+    assert_eq!(changes[0]["action"], "publish");
+    assert_eq!(changes[1]["action"], "yank");
+    assert_eq!(changes[2]["crate"], "bar");
+    assert_eq!(changes[2]["version"], Value::Null);
+    assert_eq!(changes[2]["action"], "deprecate");
+    assert_eq!(json["meta"]["next_since"], changes[2]["seq"]);
+    let created_at = changes[0]["created_at"].as_str().unwrap();
+    assert_ok!(chrono::DateTime::parse_from_rfc3339(created_at));
+
+    let first = changes[0]["seq"].as_i64().unwrap();
+    let last = changes[2]["seq"].as_i64().unwrap();
+    let json = anon
+        .get_with_query::<Value>("/api/v1/index/changes", &format!("since={first}&limit=1"))
+        .good();
+    let changes = json["changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0]["action"], "yank");
+
+    let json = anon
+        .get_with_query::<Value>("/api/v1/index/changes", &format!("since={last}&wait=1"))
+        .good();
+    assert_eq!(json["changes"], json!([]));
+    assert_eq!(json["meta"]["next_since"], last);
+
+    let response = anon.get_with_query::<()>("/api/v1/index/changes", "limit=0");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn changes_become_visible_in_order() {
+    use cargo_registry::models::{IndexChange, IndexChangeAction};
+    use diesel::connection::{AnsiTransactionManager, TransactionManager};
+    use diesel::prelude::*;
+
+    let (app, _) = TestApp::init()
+        .with_database(TestDatabase::SlowRealPool { replica: false })
+        .empty();
+    let url = &app.as_inner().config.db.primary.url;
+    let mut first = PgConnection::establish(url).unwrap();
+    let mut second = PgConnection::establish(url).unwrap();
+
+    // The transaction is started through diesel, so that `record` only
+    // creates a savepoint inside of it
+    AnsiTransactionManager::begin_transaction(&mut first).unwrap();
+    IndexChange::record(&mut first, "foo", Some("1.0.0"), IndexChangeAction::Publish).unwrap();
+
+    // The second change can't get a sequence number and become visible
+    // before the transaction of the first one is committed
+    let recording = std::thread::spawn(move || {
+        IndexChange::record(
+            &mut second,
+            "bar",
+            Some("1.0.0"),
+            IndexChangeAction::Publish,
+        )
+    });
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    assert!(!recording.is_finished());
+
+    AnsiTransactionManager::commit_transaction(&mut first).unwrap();
+    recording.join().unwrap().unwrap();
+
+    let changes = IndexChange::since(&mut first, 0, 10).unwrap();
+    let crates = changes
+        .iter()
+        .map(|change| change.crate_name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(crates, vec!["foo", "bar"]);
+}
+
+#[test]
+fn auth_required() {
+    let (app, anon, _, token) = TestApp::init()
+        .with_config(|config| config.index_config.auth_required = true)
+        .with_token();
+    let user = token.as_model();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.user_id)
+            .version("1.0.0")
+            .expect_build(conn);
+    });
+
+    for path in [
+        "/index/config.json",
+        "/index/3/f/foo",
+        "/api/v1/crates/foo/index",
+        "/api/v1/crates/foo/1.0.0/download",
+        "/api/v1/crates",
+        "/api/v1/crates/foo",
+        "/api/v1/crates/foo/1.0.0",
+        "/api/v1/crates/foo/owners",
+        "/api/v1/index/changes",
+    ] {
+        let response = anon.get::<()>(path);
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{path}");
+        assert_eq!(
+            response.headers()[header::WWW_AUTHENTICATE],
+            "Cargo login_url=\"https://crates.io/settings/tokens\""
+        );
+    }
+
+    let json = token.get::<Value>("/index/config.json").good();
+    assert_eq!(json["auth-required"], true);
+
+    let response = token.get::<()>("/index/3/f/foo");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(index_entries(&response.into_text()).len(), 1);
+
+    let response = token.get::<()>("/api/v1/crates/foo/1.0.0/download");
+    assert_eq!(response.status(), StatusCode::FOUND);
+
+    let json = token.get::<Value>("/api/v1/crates/foo").good();
+    assert_eq!(json["crate"]["name"], "foo");
+
+    // Logging in is still possible
+    let response = anon.get::<()>("/api/private/session/begin");
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Invalid tokens are rejected instead of challenged
+    let mut request = anon.get_request("/index/3/f/foo");
+    request.header(header::AUTHORIZATION, "cio1tkfake-token");
+    let response = anon.run::<()>(request);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn auth_required_with_local_storage() {
+    use cargo_registry::storage::LocalStorage;
+    use cargo_registry::Uploader;
+
+    let root = tempfile::tempdir().unwrap();
+    let files = [
+        "crates/foo/foo-1.0.0.crate",
+        "crates/foo/foo-1.0.0.crate.sig",
+        "readmes/foo/foo-1.0.0.html",
+        "index/3/f/foo",
+    ];
+    for file in files {
+        let path = root.path().join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "content").unwrap();
+    }
+
+    let uploader = Uploader::new(LocalStorage::new(root.path()));
+    let (_, anon, _, token) = TestApp::init()
+        .with_config(|config| {
+            config.index_config.auth_required = true;
+            config.base.set_uploader(uploader);
+        })
+        .with_token();
+
+    // The files are not served from the disk before the access is checked
+    for file in files {
+        let path = format!("/{file}");
+        let response = anon.get::<()>(&path);
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{path}");
+    }
+
+    let response = token.get::<()>("/crates/foo/foo-1.0.0.crate");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.into_text(), "content");
+}
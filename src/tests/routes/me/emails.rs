@@ -0,0 +1,176 @@
+use crate::util::{MockRequestExt, RequestHelper, Response, TestApp};
+use cargo_registry::schema::emails;
+use diesel::prelude::*;
+use http::StatusCode;
+use serde_json::Value;
+
+fn add_email(user: &impl RequestHelper, email: &str) -> Response<Value> {
+    let body = json!({ "email": { "email": email } }).to_string();
+    let mut request = user.post_request("/api/v1/me/emails");
+    request.with_body(body.as_bytes());
+    user.run(request)
+}
+
+fn update_email(user: &impl RequestHelper, id: i64, update: Value) -> Response<Value> {
+    let body = json!({ "email": update }).to_string();
+    user.put(&format!("/api/v1/me/emails/{id}"), body.as_bytes())
+}
+
+#[test]
+fn manage_emails() {
+    let (app, anon, user) = TestApp::init().with_user();
+
+    let json = add_email(&user, " work@example.com ").good();
+    let email_id = json["email"]["id"].as_i64().unwrap();
+    assert_eq!(json["email"]["email"], "work@example.com");
+    assert_eq!(json["email"]["verified"], false);
+    assert_eq!(json["email"]["verification_sent"], true);
+    assert_eq!(json["email"]["send_notifications"], false);
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].to, "work@example.com");
+
+    let response = add_email(&user, "work@example.com");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Unverified addresses can't receive notifications or be made public
+    let response = update_email(&user, email_id, json!({ "send_notifications": true }));
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "please verify this email address first" }] })
+    );
+
+    let token: String = app.db(|conn| {
+        emails::table
+            .find(email_id as i32)
+            .select(emails::token)
+            .first(conn)
+            .unwrap()
+    });
+    user.put::<Value>(&format!("/api/v1/confirm/{token}"), &[])
+        .good();
+
+    let json = update_email(&user, email_id, json!({ "send_notifications": true })).good();
+    assert_eq!(json["email"]["send_notifications"], true);
+    let json = update_email(&user, email_id, json!({ "public": true })).good();
+    assert_eq!(json["email"]["public"], true);
+
+    let json = user.get::<Value>("/api/v1/me").good();
+    assert_eq!(json["user"]["email"], "work@example.com");
+    let json = anon.get::<Value>("/api/v1/users/foo").good();
+    assert_eq!(json["user"]["email"], "work@example.com");
+
+    // The notification address can't be removed, but the previous one can
+    let json = user.get::<Value>("/api/v1/me/emails").good();
+    let emails = json["emails"].as_array().unwrap();
+    assert_eq!(emails.len(), 2);
+    assert_eq!(emails[0]["email"], "something@example.com");
+    assert_eq!(emails[0]["send_notifications"], false);
+    let previous_id = emails[0]["id"].as_i64().unwrap();
+
+    let response = user.delete::<Value>(&format!("/api/v1/me/emails/{email_id}"));
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    user.delete::<Value>(&format!("/api/v1/me/emails/{previous_id}"))
+        .good();
+
+    let json = user.get::<Value>("/api/v1/me/emails").good();
+    assert_eq!(json["emails"].as_array().unwrap().len(), 1);
+
+    // Hiding the address removes it from the public profile
+    update_email(&user, email_id, json!({ "public": false })).good();
+    let json = anon.get::<Value>("/api/v1/users/foo").good();
+    assert_eq!(json["user"].get("email"), None);
+}
+
+#[test]
+fn other_users_emails_are_not_found() {
+    let (app, _, user) = TestApp::init().with_user();
+    let other = app.db_new_user("bar");
+
+    let json = add_email(&other, "bar@example.com").good();
+    let email_id = json["email"]["id"].as_i64().unwrap();
+
+    let response = update_email(&user, email_id, json!({ "public": false }));
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = user.delete::<Value>(&format!("/api/v1/me/emails/{email_id}"));
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn moving_and_removing_the_notification_address_can_be_reverted() {
+    use cargo_registry::schema::email_changes;
+
+    let (app, anon, user) = TestApp::init().with_user();
+    let user_id = user.as_model().id;
+
+    let json = add_email(&user, "work@example.com").good();
+    let email_id = json["email"]["id"].as_i64().unwrap();
+    app.db(|conn| {
+        diesel::update(emails::table.find(email_id as i32))
+            .set(emails::verified.eq(true))
+            .execute(conn)
+            .unwrap();
+    });
+
+    update_email(&user, email_id, json!({ "send_notifications": true })).good();
+    let json = user.get::<Value>("/api/v1/me/emails").good();
+    let previous_id = json["emails"][0]["id"].as_i64().unwrap();
+    user.delete::<Value>(&format!("/api/v1/me/emails/{previous_id}"))
+        .good();
+
+    // The previous address is notified about both changes
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    let notifications = emails
+        .iter()
+        .skip(1)
+        .map(|email| (email.to.as_str(), email.subject.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        notifications,
+        [
+            (
+                "something@example.com",
+                "Your crates.io email address was changed"
+            ),
+            (
+                "something@example.com",
+                "Your crates.io email address was changed"
+            ),
+        ]
+    );
+
+    let revert_token: String = app.db(|conn| {
+        email_changes::table
+            .filter(email_changes::user_id.eq(user_id))
+            .order(email_changes::id.desc())
+            .select(email_changes::revert_token)
+            .first(conn)
+            .unwrap()
+    });
+    let url = format!("/api/v1/revert_email_change/{revert_token}");
+    anon.put::<Value>(&url, &[]).good();
+
+    // The removed address is added again and receives the notifications
+    let json = user.get::<Value>("/api/v1/me/emails").good();
+    let emails = json["emails"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|email| {
+            (
+                email["email"].as_str().unwrap(),
+                email["verified"].as_bool().unwrap(),
+                email["send_notifications"].as_bool().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        emails,
+        [
+            ("work@example.com", true, false),
+            ("something@example.com", true, true),
+        ]
+    );
+}
@@ -0,0 +1,249 @@
+use crate::builders::PublishBuilder;
+use crate::routes::crates::versions::yank_unyank::YankRequestHelper;
+use crate::util::{receive_one_request, MockCookieUser, RequestHelper, Response, TestApp};
+use crate::OkBool;
+use cargo_registry::models::{FollowNotificationFrequency, FollowNotificationSettings};
+use cargo_registry::schema::{follow_notification_settings, version_owner_actions};
+use cargo_registry::worker;
+use chrono::{Duration, Utc};
+use diesel::dsl::IntervalDsl;
+use diesel::prelude::*;
+use hex::ToHex;
+use hmac::{Hmac, Mac};
+use http::StatusCode;
+use serde_json::Value;
+use sha2::Sha256;
+
+const URL: &str = "/api/v1/me/follow_notifications";
+
+fn update_settings(user: &MockCookieUser, settings: Value) -> Response<Value> {
+    let body = json!({ "follow_notifications": settings });
+    user.put(URL, body.to_string().as_bytes())
+}
+
+fn backdate_last_notification(conn: &mut PgConnection) {
+    let yesterday = Utc::now().naive_utc() - Duration::days(1);
+    diesel::update(follow_notification_settings::table)
+        .set(follow_notification_settings::last_notified_at.eq(yesterday))
+        .execute(conn)
+        .unwrap();
+}
+
+/// Moves all version actions and the times up to which users were notified a
+/// day into the past.
+///
+/// All requests of a test share one transaction, so the actions would
+/// otherwise be recorded at the same time, and would be too recent to be
+/// notified about.
+fn backdate_version_actions(conn: &mut PgConnection) {
+    diesel::update(version_owner_actions::table)
+        .set(version_owner_actions::time.eq(version_owner_actions::time - 1.day()))
+        .execute(conn)
+        .unwrap();
+    diesel::update(follow_notification_settings::table)
+        .set(
+            follow_notification_settings::notified_until
+                .eq(follow_notification_settings::notified_until - 1.day()),
+        )
+        .execute(conn)
+        .unwrap();
+}
+
+#[test]
+fn followers_are_not_notified_by_default() {
+    let (_, _, user) = TestApp::init().with_user();
+
+    let json: Value = user.get(URL).good();
+    assert_eq!(
+        json["follow_notifications"],
+        json!({ "frequency": "never", "webhook_url": null })
+    );
+}
+
+#[test]
+fn update_follow_notifications() {
+    let (_, _, user) = TestApp::init().with_user();
+
+    let json = update_settings(&user, json!({ "frequency": "weekly" })).good();
+    assert_eq!(
+        json["follow_notifications"],
+        json!({ "frequency": "weekly", "webhook_url": null })
+    );
+
+    // The secret of a new webhook URL is only shown once
+    let settings = json!({ "frequency": "daily", "webhook_url": "https://example.com/hook" });
+    let json = update_settings(&user, settings.clone()).good();
+    let secret = json["follow_notifications"]["webhook_secret"]
+        .as_str()
+        .unwrap();
+    assert!(secret.starts_with("cwh"));
+
+    let json: Value = user.get(URL).good();
+    assert_eq!(json["follow_notifications"], settings);
+
+    // The secret is kept while the URL doesn't change
+    let settings = json!({ "frequency": "weekly", "webhook_url": "https://example.com/hook" });
+    let json = update_settings(&user, settings.clone()).good();
+    assert_eq!(json["follow_notifications"], settings);
+}
+
+#[test]
+fn follow_notifications_are_validated() {
+    let (app, _, user) = TestApp::init().with_user();
+
+    let response = update_settings(&user, json!({ "frequency": "hourly" }));
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the frequency must be `never`, `daily` or `weekly`" }] })
+    );
+
+    let settings = json!({ "frequency": "daily", "webhook_url": "http://example.com/hook" });
+    let response = update_settings(&user, settings);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "webhook URLs must be valid `https` URLs" }] })
+    );
+
+    let settings = json!({ "frequency": "daily", "webhook_url": "https://169.254.169.254/hook" });
+    let response = update_settings(&user, settings);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "webhook URLs must not point to private or local addresses" }] })
+    );
+
+    // Users without a verified email address can only be notified by webhook
+    let unverified = app.db_new_user("unverified");
+    app.db(|conn| {
+        use cargo_registry::schema::emails;
+
+        diesel::update(emails::table.filter(emails::user_id.eq(unverified.as_model().id)))
+            .set(emails::verified.eq(false))
+            .execute(conn)
+            .unwrap();
+    });
+
+    let response = update_settings(&unverified, json!({ "frequency": "daily" }));
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let settings = json!({ "frequency": "daily", "webhook_url": "https://example.com/hook" });
+    update_settings(&unverified, settings).good();
+}
+
+#[test]
+fn follow_notifications_require_login() {
+    let (_, anon) = TestApp::init().empty();
+
+    let response = anon.get::<()>(URL);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn followers_are_notified_about_new_and_yanked_versions() {
+    let (app, _, _, token) = TestApp::full().with_token();
+    token
+        .publish_crate(PublishBuilder::new("foo_followed"))
+        .good();
+    token
+        .publish_crate(PublishBuilder::new("foo_unfollowed"))
+        .good();
+
+    let follower = app.db_new_user("follower");
+    follower
+        .put::<OkBool>("/api/v1/crates/foo_followed/follow", b"")
+        .good();
+    update_settings(&follower, json!({ "frequency": "daily" })).good();
+    app.db(backdate_version_actions);
+
+    token
+        .publish_crate(PublishBuilder::new("foo_followed").version("1.1.0"))
+        .good();
+    token.yank("foo_followed", "1.0.0").good();
+    token
+        .publish_crate(PublishBuilder::new("foo_unfollowed").version("1.1.0"))
+        .good();
+    app.db(backdate_version_actions);
+
+    // Followers are notified at most once per day
+    app.db(|conn| worker::check_followed_crates().enqueue(conn).unwrap());
+    app.run_pending_background_jobs();
+    assert_eq!(app.as_inner().emails.mails_in_memory().unwrap().len(), 0);
+
+    app.db(|conn| {
+        backdate_last_notification(conn);
+        worker::check_followed_crates().enqueue(conn).unwrap();
+    });
+    app.run_pending_background_jobs();
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].to, "something@example.com");
+    assert_eq!(
+        emails[0].subject,
+        "Updates of the crates you follow on crates.io"
+    );
+    assert!(emails[0].body.contains("foo_followed 1.1.0 (published)"));
+    assert!(emails[0].body.contains("foo_followed 1.0.0 (yanked)"));
+    assert!(!emails[0].body.contains("foo_unfollowed"));
+
+    // Events are only notified about once
+    app.db(|conn| {
+        backdate_last_notification(conn);
+        worker::check_followed_crates().enqueue(conn).unwrap();
+    });
+    app.run_pending_background_jobs();
+    assert_eq!(app.as_inner().emails.mails_in_memory().unwrap().len(), 1);
+}
+
+#[test]
+fn webhook_notifications_are_signed_with_the_secret() {
+    let (app, _, _, token) = TestApp::full().with_token();
+    token
+        .publish_crate(PublishBuilder::new("foo_followed"))
+        .good();
+
+    let follower = app.db_new_user("follower");
+    follower
+        .put::<OkBool>("/api/v1/crates/foo_followed/follow", b"")
+        .good();
+
+    // The settings are saved directly, since the API rejects local URLs
+    let (url, request) = receive_one_request();
+    let (_, secret) = app.db(|conn| {
+        let frequency = FollowNotificationFrequency::Daily;
+        let user_id = follower.as_model().id;
+        FollowNotificationSettings::save(conn, user_id, frequency, Some(&url)).unwrap()
+    });
+    let secret = secret.unwrap();
+    app.db(backdate_version_actions);
+
+    token
+        .publish_crate(PublishBuilder::new("foo_followed").version("1.1.0"))
+        .good();
+    app.db(backdate_version_actions);
+
+    app.db(|conn| {
+        backdate_last_notification(conn);
+        worker::check_followed_crates().enqueue(conn).unwrap();
+    });
+    app.run_pending_background_jobs();
+
+    let (headers, body) = request.join().unwrap();
+    assert!(headers.contains(&"x-crates-io-event: followed_crates".to_string()));
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(&body);
+    let signature: String = mac.finalize().into_bytes().encode_hex();
+    assert!(headers.contains(&format!("x-crates-io-signature: sha256={signature}")));
+
+    let payload: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        payload,
+        json!({
+            "events": [{ "crate": "foo_followed", "version": "1.1.0", "action": "published" }],
+            "total": 1,
+        })
+    );
+}
@@ -0,0 +1,111 @@
+use crate::util::{MockCookieUser, RequestHelper, TestApp};
+use cargo_registry::models::{AccountProvider, NewLinkedAccount, User};
+use cargo_registry::schema::users;
+use diesel::prelude::*;
+use http::StatusCode;
+use serde_json::Value;
+
+fn link(app: &TestApp, user: &MockCookieUser, provider: AccountProvider, id: i32, login: &str) {
+    let user_id = user.as_model().id;
+    app.db(|conn| {
+        NewLinkedAccount::new(provider, id, login, None, "token")
+            .link(conn, user_id)
+            .unwrap();
+    });
+}
+
+#[test]
+fn list_and_unlink_accounts() {
+    let (app, _, user) = TestApp::init().with_user();
+    let gh_id = user.as_model().gh_id;
+    link(&app, &user, AccountProvider::GitLab, 101, "gitlab-user");
+    link(&app, &user, AccountProvider::GitHub, 4242, "second-github");
+
+    let json = user.get::<Value>("/api/v1/me/linked_accounts").good();
+    let accounts = json["linked_accounts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|account| {
+            (
+                account["provider"].as_str().unwrap(),
+                account["login"].as_str().unwrap(),
+                account["primary"].as_bool().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        accounts,
+        [
+            ("github", "foo", true),
+            ("gitlab", "gitlab-user", false),
+            ("github", "second-github", false),
+        ]
+    );
+    assert_eq!(
+        json["linked_accounts"][1]["url"],
+        "https://gitlab.com/gitlab-user"
+    );
+
+    let url = format!("/api/v1/me/linked_accounts/github/{gh_id}");
+    let response = user.delete::<()>(&url);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the GitHub account that you signed up with can't be unlinked" }] })
+    );
+
+    let response = user.delete::<()>("/api/v1/me/linked_accounts/gitlab/102");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    user.delete::<Value>("/api/v1/me/linked_accounts/gitlab/101")
+        .good();
+    user.delete::<Value>("/api/v1/me/linked_accounts/github/4242")
+        .good();
+
+    let json = user.get::<Value>("/api/v1/me/linked_accounts").good();
+    assert_eq!(json["linked_accounts"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn accounts_cant_be_linked_twice() {
+    let (app, _, user) = TestApp::init().with_user();
+    let other = app.db_new_user("other");
+    link(&app, &user, AccountProvider::GitLab, 101, "gitlab-user");
+
+    let error = app.db(|conn| {
+        NewLinkedAccount::new(AccountProvider::GitLab, 101, "gitlab-user", None, "token")
+            .link(conn, other.as_model().id)
+            .unwrap_err()
+    });
+    assert_eq!(
+        error.to_string(),
+        "this GitLab account is already linked to another crates.io account"
+    );
+}
+
+#[test]
+fn last_account_cant_be_unlinked() {
+    let (app, _, user) = TestApp::init().with_user();
+    let user_id = user.as_model().id;
+    link(&app, &user, AccountProvider::GitLab, 101, "gitlab-user");
+
+    // A user that signed up with GitLab has no GitHub account to fall back to
+    app.db(|conn| {
+        diesel::update(users::table.find(user_id))
+            .set(users::gh_id.eq(User::NO_GITHUB_ID))
+            .execute(conn)
+            .unwrap();
+    });
+
+    let response = user.delete::<()>("/api/v1/me/linked_accounts/gitlab/101");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this is the only account that you can log in with, please link another account first" }] })
+    );
+
+    link(&app, &user, AccountProvider::GitHub, 4242, "second-github");
+    user.delete::<Value>("/api/v1/me/linked_accounts/gitlab/101")
+        .good();
+}
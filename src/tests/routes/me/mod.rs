@@ -1,4 +1,12 @@
 mod email_notifications;
+mod emails;
+mod follow_notifications;
 pub mod get;
+mod linked_accounts;
+mod notification_preferences;
+mod saved_searches;
+mod security_log;
+mod sessions;
 pub mod tokens;
 mod updates;
+mod webauthn;
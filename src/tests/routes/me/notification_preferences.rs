@@ -0,0 +1,69 @@
+use crate::builders::CrateBuilder;
+use crate::util::{MockCookieUser, RequestHelper, TestApp};
+use http::StatusCode;
+use serde_json::Value;
+
+const URL: &str = "/api/v1/me/notification_preferences";
+
+fn update_preferences(user: &MockCookieUser, preferences: Value) -> Value {
+    let body = json!({ "notification_preferences": preferences });
+    user.put(URL, body.to_string().as_bytes()).good()
+}
+
+#[test]
+fn all_notifications_are_enabled_by_default() {
+    let (_, _, user) = TestApp::init().with_user();
+
+    let json: Value = user.get(URL).good();
+    assert_eq!(
+        json["notification_preferences"],
+        json!({ "owner_invites": true, "security_alerts": true, "digests": true })
+    );
+}
+
+#[test]
+fn update_only_changes_given_preferences() {
+    let (_, _, user) = TestApp::init().with_user();
+
+    let json = update_preferences(&user, json!({ "digests": false }));
+    let expected = json!({ "owner_invites": true, "security_alerts": true, "digests": false });
+    assert_eq!(json["notification_preferences"], expected);
+
+    let json = update_preferences(&user, json!({ "owner_invites": false }));
+    let expected = json!({ "owner_invites": false, "security_alerts": true, "digests": false });
+    assert_eq!(json["notification_preferences"], expected);
+
+    let json: Value = user.get(URL).good();
+    assert_eq!(json["notification_preferences"], expected);
+}
+
+#[test]
+fn preferences_require_login() {
+    let (_, anon) = TestApp::init().empty();
+
+    let response = anon.get::<()>(URL);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn owner_invites_are_not_emailed_after_opting_out() {
+    let (app, _, _, owner) = TestApp::init().with_token();
+    let invited_user = app.db_new_user("invited_user");
+    app.db(|conn| {
+        CrateBuilder::new("crate_name", owner.as_model().user_id).expect_build(conn);
+        CrateBuilder::new("other_crate", owner.as_model().user_id).expect_build(conn);
+    });
+
+    owner.add_named_owner("crate_name", "invited_user").good();
+    assert_eq!(1, app.as_inner().emails.mails_in_memory().unwrap().len());
+
+    update_preferences(&invited_user, json!({ "owner_invites": false }));
+
+    // The invitation is still created, only the email is skipped
+    owner.add_named_owner("other_crate", "invited_user").good();
+    assert_eq!(1, app.as_inner().emails.mails_in_memory().unwrap().len());
+    let json: Value = invited_user
+        .get("/api/v1/me/crate_owner_invitations")
+        .good();
+    assert_eq!(json["crate_owner_invitations"].as_array().unwrap().len(), 2);
+}
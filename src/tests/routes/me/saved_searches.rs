@@ -0,0 +1,124 @@
+use crate::builders::{CrateBuilder, VersionBuilder};
+use crate::util::{MockRequestExt, RequestHelper, Response, TestApp};
+use cargo_registry::worker;
+use http::StatusCode;
+use serde_json::Value;
+
+fn create_saved_search(user: &impl RequestHelper, saved_search: Value) -> Response<Value> {
+    let body = json!({ "saved_search": saved_search }).to_string();
+    let mut request = user.post_request("/api/v1/me/saved_searches");
+    request.with_body(body.as_bytes());
+    user.run(request)
+}
+
+#[test]
+fn manage_saved_searches() {
+    let (_, _, user) = TestApp::init().with_user();
+
+    let json = create_saved_search(&user, json!({ "query": " serde " })).good();
+    let saved_search_id = json["saved_search"]["id"].as_i64().unwrap();
+    assert_eq!(json["saved_search"]["query"], "serde");
+    assert_eq!(json["saved_search"]["webhook_url"], Value::Null);
+
+    let json = json!({ "query": "tokio", "webhook_url": "https://example.com/hook" });
+    create_saved_search(&user, json).good();
+
+    let json = user.get::<Value>("/api/v1/me/saved_searches").good();
+    assert_eq!(json["saved_searches"][0]["id"], saved_search_id);
+    assert_eq!(json["saved_searches"][1]["query"], "tokio");
+    assert_eq!(
+        json["saved_searches"][1]["webhook_url"],
+        "https://example.com/hook"
+    );
+
+    let url = format!("/api/v1/me/saved_searches/{saved_search_id}");
+    let json = user.delete::<Value>(&url).good();
+    assert_eq!(json, json!({ "ok": true }));
+
+    let json = user.get::<Value>("/api/v1/me/saved_searches").good();
+    assert_eq!(json["saved_searches"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn saved_searches_are_validated() {
+    let (_, _, user) = TestApp::init().with_user();
+
+    let response = create_saved_search(&user, json!({ "query": "  " }));
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the query of a saved search must not be empty" }] })
+    );
+
+    let json = json!({ "query": "serde", "webhook_url": "http://example.com/hook" });
+    let response = create_saved_search(&user, json);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "webhook URLs must be valid `https` URLs" }] })
+    );
+}
+
+#[test]
+fn saved_searches_are_private() {
+    let (app, _, user) = TestApp::init().with_user();
+
+    let json = create_saved_search(&user, json!({ "query": "serde" })).good();
+    let saved_search_id = json["saved_search"]["id"].as_i64().unwrap();
+
+    let other = app.db_new_user("other");
+    let json = other.get::<Value>("/api/v1/me/saved_searches").good();
+    assert_eq!(json["saved_searches"], json!([]));
+
+    let url = format!("/api/v1/me/saved_searches/{saved_search_id}");
+    let response = other.delete::<Value>(&url);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn api_tokens_cannot_create_saved_searches() {
+    let (_, _, _, token) = TestApp::init().with_token();
+
+    let response = create_saved_search(&token, json!({ "query": "serde" }));
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn new_matching_versions_are_notified_once() {
+    let (app, _, user) = TestApp::full().with_user();
+    let user_id = user.as_model().id;
+
+    // Crates that existed before the search was saved are not notified about
+    app.db(|conn| {
+        CrateBuilder::new("old_frobnicator", user_id).expect_build(conn);
+    });
+
+    create_saved_search(&user, json!({ "query": "frobnicator" })).good();
+
+    app.db(|conn| {
+        CrateBuilder::new("new_frobnicator", user_id)
+            .version(VersionBuilder::new("1.0.0"))
+            .expect_build(conn);
+        CrateBuilder::new("unrelated", user_id).expect_build(conn);
+
+        worker::check_saved_searches().enqueue(conn).unwrap();
+    });
+    app.run_pending_background_jobs();
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 1);
+    assert_eq!(
+        emails[0].subject,
+        "New crates matching \"frobnicator\" on crates.io"
+    );
+    assert!(emails[0].body.contains("new_frobnicator 1.0.0"));
+    assert!(!emails[0].body.contains("old_frobnicator"));
+    assert!(!emails[0].body.contains("unrelated"));
+
+    // Versions are only notified about once
+    app.db(|conn| worker::check_saved_searches().enqueue(conn).unwrap());
+    app.run_pending_background_jobs();
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 1);
+}
@@ -0,0 +1,91 @@
+use crate::builders::CrateBuilder;
+use crate::util::{MockRequestExt, RequestHelper, TestApp};
+use http::StatusCode;
+use serde_json::Value;
+
+const URL: &str = "/api/v1/me/security_log";
+
+#[test]
+fn security_log_records_account_changes() {
+    let (app, _, user, token) = TestApp::init().with_token();
+    let new_owner = app.db_new_user("new_owner");
+    let krate =
+        app.db(|conn| CrateBuilder::new("foo_security_log", user.as_model().id).expect_build(conn));
+
+    user.put::<Value>("/api/v1/me/tokens", br#"{ "api_token": { "name": "ci" } }"#)
+        .good();
+    token
+        .add_named_owner("foo_security_log", "new_owner")
+        .good();
+    let body = json!({ "email": { "email": "work@example.com" } }).to_string();
+    let mut request = user.post_request("/api/v1/me/emails");
+    request.with_body(body.as_bytes());
+    user.run::<Value>(request).good();
+
+    // Owners are only added once they accept their invitation
+    let json: Value = user.get(URL).good();
+    let events: Vec<_> = json["security_log"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(events, ["email_added", "token_created"]);
+
+    new_owner.accept_ownership_invitation("foo_security_log", krate.id);
+
+    let json: Value = new_owner.get(URL).good();
+    let entries = json["security_log"].as_array().unwrap();
+    assert_eq!(entries[0]["event"], "owner_added");
+    assert_eq!(
+        entries[0]["details"],
+        "crate foo_security_log, owner new_owner"
+    );
+    assert_eq!(entries[0]["ip_address"], "127.0.0.1");
+
+    let json: Value = user.get(URL).good();
+    assert_eq!(json["meta"]["more"], false);
+
+    let entries = json["security_log"].as_array().unwrap();
+    let events: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry["event"].as_str().unwrap(), entry["details"].as_str()))
+        .collect();
+    assert_eq!(
+        events,
+        [
+            (
+                "owner_added",
+                Some("crate foo_security_log, owner new_owner")
+            ),
+            ("email_added", Some("work@example.com")),
+            ("token_created", Some("ci")),
+        ]
+    );
+    // The inviter didn't make the request that added the owner
+    assert_eq!(entries[0]["ip_address"], Value::Null);
+    assert_eq!(entries[1]["ip_address"], "127.0.0.1");
+    assert_eq!(entries[1]["user_agent"], "conduit-test");
+}
+
+#[test]
+fn security_log_is_private() {
+    let (app, _, user) = TestApp::init().with_user();
+    user.put::<Value>("/api/v1/me/tokens", br#"{ "api_token": { "name": "ci" } }"#)
+        .good();
+
+    let other = app.db_new_user("other");
+    let json: Value = other.get(URL).good();
+    assert_eq!(json["security_log"], json!([]));
+}
+
+#[test]
+fn security_log_requires_login() {
+    let (_, anon, _, token) = TestApp::init().with_token();
+
+    let response = anon.get::<()>(URL);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = token.get::<()>(URL);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
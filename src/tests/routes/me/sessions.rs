@@ -0,0 +1,231 @@
+use crate::util::{
+    encode_session_header, MockCookieUser, MockRequestExt, RequestHelper, Response, TestApp,
+};
+use cargo_registry::controllers::krate::publish::OTP_HEADER;
+use cargo_registry::models::{CreatedPersistentSession, PersistentSession};
+use cargo_registry::schema::{persistent_sessions, totp_credentials};
+use cargo_registry::util::totp;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use http::{header, Method, StatusCode};
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SUDO_URL: &str = "/api/private/session/sudo";
+
+fn create_session(app: &TestApp, user_id: i32, user_agent: &str) -> CreatedPersistentSession {
+    app.db(|conn| {
+        PersistentSession::create(conn, user_id, Some("127.0.0.1"), Some(user_agent)).unwrap()
+    })
+}
+
+fn session_request(
+    user: &impl RequestHelper,
+    method: Method,
+    path: &str,
+    session: &CreatedPersistentSession,
+) -> Response<Value> {
+    let session_key = user.app().as_inner().session_key();
+    let cookie = encode_session_header(session_key, &session.plaintext);
+
+    let mut request = user.request_builder(method, path);
+    request.header(header::COOKIE, &cookie);
+    user.run(request)
+}
+
+/// Ends the sudo mode of all sessions of the user, as if they logged in a
+/// while ago.
+fn end_sudo_mode(app: &TestApp, user_id: i32) {
+    app.db(|conn| {
+        let sudo_until = Utc::now().naive_utc() - Duration::minutes(1);
+        diesel::update(persistent_sessions::table)
+            .filter(persistent_sessions::user_id.eq(user_id))
+            .set(persistent_sessions::sudo_until.eq(sudo_until))
+            .execute(conn)
+            .unwrap();
+    });
+}
+
+fn create_token(user: &MockCookieUser) -> Response<Value> {
+    user.put("/api/v1/me/tokens", br#"{ "api_token": { "name": "ci" } }"#)
+}
+
+/// Enables an authenticator for the user and returns the recovery codes.
+fn enable_totp(app: &TestApp, user: &MockCookieUser) -> Vec<String> {
+    user.run::<Value>(user.post_request("/api/v1/me/totp"))
+        .good();
+
+    let secret: Vec<u8> = app.db(|conn| {
+        totp_credentials::table
+            .find(user.as_model().id)
+            .select(totp_credentials::secret)
+            .first(conn)
+            .unwrap()
+    });
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let code = totp::code(&secret, totp::time_step(now.as_secs()));
+
+    let body = json!({ "code": code }).to_string();
+    let json = user.put::<Value>("/api/v1/me/totp", body.as_bytes()).good();
+    serde_json::from_value(json["recovery_codes"].clone()).unwrap()
+}
+
+#[test]
+fn list_and_revoke_sessions() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user_id = user.as_model().id;
+    let laptop = create_session(&app, user_id, "laptop");
+    let phone = create_session(&app, user_id, "phone");
+    let tablet = create_session(&app, user_id, "tablet");
+
+    let other_user = app.db_new_user("bar");
+    let other_session = create_session(&app, other_user.as_model().id, "other");
+
+    // The user is also logged in with the session of the mock user
+    let json = session_request(&anon, Method::GET, "/api/v1/me/sessions", &laptop).good();
+    let sessions = json["sessions"].as_array().unwrap();
+    assert_eq!(sessions.len(), 4);
+    let current: Vec<_> = sessions
+        .iter()
+        .filter(|session| session["current"] == true)
+        .collect();
+    assert_eq!(current.len(), 1);
+    assert_eq!(current[0]["last_user_agent"], "laptop");
+    assert_eq!(current[0]["last_ip_address"], "127.0.0.1");
+
+    // Revoked sessions can't be used anymore
+    let url = format!("/api/v1/me/sessions/{}", phone.model.id);
+    session_request(&anon, Method::DELETE, &url, &laptop).good();
+    let response = session_request(&anon, Method::GET, "/api/v1/me", &phone);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Sessions of other users are not found
+    let url = format!("/api/v1/me/sessions/{}", other_session.model.id);
+    let response = session_request(&anon, Method::DELETE, &url, &laptop);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Revoking all other sessions keeps the current one
+    session_request(&anon, Method::DELETE, "/api/v1/me/sessions", &laptop).good();
+    let response = session_request(&anon, Method::GET, "/api/v1/me", &tablet);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = user.get::<()>("/api/v1/me");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    session_request(&anon, Method::GET, "/api/v1/me", &laptop).good();
+
+    let json = session_request(&anon, Method::GET, "/api/v1/me/sessions", &laptop).good();
+    assert_eq!(json["sessions"].as_array().unwrap().len(), 1);
+    let json = other_user.get::<Value>("/api/v1/me/sessions").good();
+    assert_eq!(json["sessions"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn idle_sessions_expire() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let session = create_session(&app, user.as_model().id, "laptop");
+
+    session_request(&anon, Method::GET, "/api/v1/me", &session).good();
+
+    app.db(|conn| {
+        let idle_days = PersistentSession::IDLE_TIMEOUT_DAYS + 1;
+        let last_used_at = Utc::now().naive_utc() - Duration::days(idle_days);
+        diesel::update(persistent_sessions::table.find(session.model.id))
+            .set(persistent_sessions::last_used_at.eq(last_used_at))
+            .execute(conn)
+            .unwrap();
+    });
+
+    let response = session_request(&anon, Method::GET, "/api/v1/me", &session);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn logout_revokes_session() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let session = create_session(&app, user.as_model().id, "laptop");
+
+    session_request(&anon, Method::DELETE, "/api/private/session", &session).good();
+
+    let response = session_request(&anon, Method::GET, "/api/v1/me", &session);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    user.get::<Value>("/api/v1/me").good();
+}
+
+#[test]
+fn sensitive_actions_require_sudo_mode() {
+    let (app, anon, user) = TestApp::init().with_user();
+    let user_id = user.as_model().id;
+
+    // Logging in starts a session in sudo mode
+    create_token(&user).good();
+
+    end_sudo_mode(&app, user_id);
+    let response = create_token(&user);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this action requires sudo mode, please confirm your identity with a second factor or log in again" }] })
+    );
+
+    let body = json!({ "email": { "email": "work@example.com" } }).to_string();
+    let mut request = user.post_request("/api/v1/me/emails");
+    request.with_body(body.as_bytes());
+    let response = user.run::<()>(request);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // A stolen session can't add a second factor of its own
+    for path in ["/api/v1/me/totp", "/api/v1/me/webauthn/registrations"] {
+        let response = user.run::<()>(user.post_request(path));
+        assert_eq!(response.status(), StatusCode::FORBIDDEN, "{path}");
+    }
+
+    // Users without a second factor have to log in again
+    let response = user.put::<Value>(SUDO_URL, b"");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "please log in again to enter sudo mode" }] })
+    );
+
+    let session = create_session(&app, user_id, "laptop");
+    let json = session_request(&anon, Method::GET, "/api/v1/me/sessions", &session).good();
+    let current = json["sessions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|session| session["current"] == true)
+        .unwrap();
+    assert!(current["sudo_until"].is_string());
+}
+
+#[test]
+fn enter_sudo_mode_with_second_factor() {
+    let (app, _, user) = TestApp::init().with_user();
+    let recovery_codes = enable_totp(&app, &user);
+    end_sudo_mode(&app, user.as_model().id);
+
+    let response = user.put::<Value>(SUDO_URL, b"");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this action must be confirmed with a one-time password, please pass it in the `X-Crates-Io-Otp` header" }] })
+    );
+    assert_eq!(create_token(&user).status(), StatusCode::FORBIDDEN);
+
+    let mut request = user.request_builder(Method::PUT, SUDO_URL);
+    request.header(OTP_HEADER, &recovery_codes[0]);
+    let json = user.run::<Value>(request).good();
+    assert!(json["session"]["sudo_until"].is_string());
+    assert_eq!(json["session"]["current"], true);
+
+    create_token(&user).good();
+}
+
+#[test]
+fn enter_sudo_mode_requires_session() {
+    let (_, anon, _, token) = TestApp::init().with_token();
+
+    let response = anon.put::<()>(SUDO_URL, b"");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = token.put::<()>(SUDO_URL, b"");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
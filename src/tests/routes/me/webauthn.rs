@@ -0,0 +1,275 @@
+use crate::builders::CrateBuilder;
+use crate::util::{MockCookieUser, MockRequestExt, RequestHelper, Response, TestApp};
+use cargo_registry::controllers::helpers::second_factor::WEBAUTHN_HEADER;
+use http::{Method, StatusCode};
+use ring::digest::{digest, SHA256};
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+use serde_json::Value;
+
+fn encode(data: &[u8]) -> String {
+    base64::encode_config(data, base64::URL_SAFE_NO_PAD)
+}
+
+/// A software implementation of a security key.
+struct SoftwareKey {
+    key_pair: EcdsaKeyPair,
+    credential_id: Vec<u8>,
+    sign_count: u32,
+}
+
+impl SoftwareKey {
+    fn new(credential_id: &[u8]) -> Self {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref()).unwrap();
+
+        Self {
+            key_pair,
+            credential_id: credential_id.to_vec(),
+            sign_count: 0,
+        }
+    }
+
+    fn client_data(ceremony: &str, options: &Value) -> Vec<u8> {
+        let challenge = options["publicKey"]["challenge"].as_str().unwrap();
+        json!({ "type": ceremony, "challenge": challenge, "origin": "https://crates.io" })
+            .to_string()
+            .into_bytes()
+    }
+
+    fn authenticator_data(&self, flags: u8) -> Vec<u8> {
+        let rp_id_hash = digest(&SHA256, b"crates.io");
+        [
+            rp_id_hash.as_ref(),
+            &[flags],
+            &self.sign_count.to_be_bytes(),
+        ]
+        .concat()
+    }
+
+    /// Returns the response to `navigator.credentials.create()`, with a
+    /// `none` attestation.
+    fn register(&self, options: &Value) -> Value {
+        // The public key in COSE format: EC2 key type, ES256, P-256 curve
+        let point = self.key_pair.public_key().as_ref();
+        let mut cose_key = vec![0xa5, 0x01, 0x02, 0x03, 0x26, 0x20, 0x01];
+        cose_key.extend([0x21, 0x58, 0x20]);
+        cose_key.extend(&point[1..33]);
+        cose_key.extend([0x22, 0x58, 0x20]);
+        cose_key.extend(&point[33..65]);
+
+        let mut auth_data = self.authenticator_data(0x41);
+        auth_data.extend([0; 16]);
+        auth_data.extend((self.credential_id.len() as u16).to_be_bytes());
+        auth_data.extend(&self.credential_id);
+        auth_data.extend(cose_key);
+
+        let mut attestation_object = vec![0xa3];
+        attestation_object.extend(b"\x63fmt\x64none");
+        attestation_object.extend(b"\x67attStmt\xa0");
+        attestation_object.extend(b"\x68authData\x58");
+        attestation_object.push(auth_data.len() as u8);
+        attestation_object.extend(auth_data);
+
+        json!({
+            "id": encode(&self.credential_id),
+            "rawId": encode(&self.credential_id),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": encode(&Self::client_data("webauthn.create", options)),
+                "attestationObject": encode(&attestation_object),
+            },
+        })
+    }
+
+    /// Returns the response to `navigator.credentials.get()`.
+    fn sign(&mut self, options: &Value) -> Value {
+        self.sign_count += 1;
+
+        let client_data = Self::client_data("webauthn.get", options);
+        let auth_data = self.authenticator_data(0x01);
+        let message = [&auth_data[..], digest(&SHA256, &client_data).as_ref()].concat();
+        let signature = self.key_pair.sign(&SystemRandom::new(), &message).unwrap();
+
+        json!({
+            "id": encode(&self.credential_id),
+            "rawId": encode(&self.credential_id),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": encode(&client_data),
+                "authenticatorData": encode(&auth_data),
+                "signature": encode(signature.as_ref()),
+                "userHandle": null,
+            },
+        })
+    }
+}
+
+fn register(user: &MockCookieUser, key: &SoftwareKey, name: &str) -> Response<Value> {
+    let options = user
+        .run::<Value>(user.post_request("/api/v1/me/webauthn/registrations"))
+        .good();
+    assert_eq!(options["publicKey"]["rp"]["id"], "crates.io");
+    assert_eq!(options["publicKey"]["attestation"], "none");
+
+    let body = json!({ "name": name, "credential": key.register(&options) }).to_string();
+    user.put("/api/v1/me/webauthn/registrations", body.as_bytes())
+}
+
+/// Returns the value of the step-up header for a sensitive action.
+fn step_up(user: &MockCookieUser, key: &mut SoftwareKey) -> String {
+    let options = user
+        .run::<Value>(user.post_request("/api/v1/me/webauthn/assertions"))
+        .good();
+    key.sign(&options).to_string()
+}
+
+fn add_owner(user: &MockCookieUser, assertion: Option<&str>) -> Response<Value> {
+    let body = json!({ "owners": ["other"] }).to_string();
+    let mut request = user.request_builder(Method::PUT, "/api/v1/crates/foo/owners");
+    request.with_body(body.as_bytes());
+    if let Some(assertion) = assertion {
+        request.header(WEBAUTHN_HEADER, assertion);
+    }
+    user.run(request)
+}
+
+#[test]
+fn register_list_and_delete_credentials() {
+    let (_, _, user) = TestApp::init().with_user();
+    let mut key = SoftwareKey::new(b"credential-1");
+
+    let json = register(&user, &key, " My key ").good();
+    assert_eq!(json["credential"]["name"], "My key");
+    assert_eq!(json["credential"]["credential_id"], encode(b"credential-1"));
+
+    let response = register(&user, &key, "Again");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this credential is already registered" }] })
+    );
+
+    let json = user.get::<Value>("/api/v1/me/webauthn/credentials").good();
+    let credentials = json["credentials"].as_array().unwrap();
+    assert_eq!(credentials.len(), 1);
+    let id = credentials[0]["id"].as_i64().unwrap();
+    let url = format!("/api/v1/me/webauthn/credentials/{id}");
+
+    // Removing a second factor has to be confirmed with a second factor
+    let response = user.delete::<Value>(&url);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this action must be confirmed with a security key, please pass its response in the `X-Crates-Io-Webauthn` header" }] })
+    );
+
+    let assertion = step_up(&user, &mut key);
+    let mut request = user.request_builder(Method::DELETE, &url);
+    request.header(WEBAUTHN_HEADER, &assertion);
+    user.run::<Value>(request).good();
+
+    let json = user.get::<Value>("/api/v1/me/webauthn/credentials").good();
+    assert_eq!(json["credentials"], json!([]));
+}
+
+#[test]
+fn registration_requires_the_issued_challenge() {
+    let (_, _, user) = TestApp::init().with_user();
+    let key = SoftwareKey::new(b"credential-1");
+
+    let options = json!({ "publicKey": { "challenge": encode(b"forged") } });
+    let credential = key.register(&options);
+    let body = json!({ "name": "My key", "credential": credential }).to_string();
+    let response = user.put::<Value>("/api/v1/me/webauthn/registrations", body.as_bytes());
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the registration has expired, please try again" }] })
+    );
+
+    user.run::<Value>(user.post_request("/api/v1/me/webauthn/registrations"))
+        .good();
+    let response = user.put::<Value>("/api/v1/me/webauthn/registrations", body.as_bytes());
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid credential: The client response challenge differs from the latest challenge issued to the userId" }] })
+    );
+}
+
+#[test]
+fn owner_changes_require_a_security_key() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+    app.db_new_user("other");
+
+    let mut key = SoftwareKey::new(b"credential-1");
+    register(&user, &key, "My key").good();
+
+    let response = add_owner(&user, None);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this action must be confirmed with a security key, please pass its response in the `X-Crates-Io-Webauthn` header" }] })
+    );
+
+    // Responses can't be replayed
+    let assertion = step_up(&user, &mut key);
+    let json = add_owner(&user, Some(&assertion)).good();
+    assert_eq!(json["ok"], true);
+
+    let response = add_owner(&user, Some(&assertion));
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the security key challenge has expired, please try again" }] })
+    );
+}
+
+#[test]
+fn cloned_security_keys_are_rejected() {
+    let (app, _, user) = TestApp::init().with_user();
+    app.db(|conn| {
+        CrateBuilder::new("foo", user.as_model().id).expect_build(conn);
+    });
+    app.db_new_user("other");
+
+    let mut key = SoftwareKey::new(b"credential-1");
+    let json = register(&user, &key, "My key").good();
+    let id = json["credential"]["id"].as_i64().unwrap();
+
+    key.sign_count = 5;
+    let assertion = step_up(&user, &mut key);
+    add_owner(&user, Some(&assertion)).good();
+
+    // A clone of the key that was not used since it was copied
+    key.sign_count = 2;
+    let assertion = step_up(&user, &mut key);
+    let mut request = user.request_builder(
+        Method::DELETE,
+        &format!("/api/v1/me/webauthn/credentials/{id}"),
+    );
+    request.header(WEBAUTHN_HEADER, &assertion);
+    let response = user.run::<Value>(request);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid security key response: the signature counter did not increase, the authenticator may have been cloned" }] })
+    );
+}
+
+#[test]
+fn confirming_a_login_needs_a_pending_login() {
+    let (_, anon) = TestApp::init().empty();
+
+    let response = anon.run::<Value>(anon.post_request("/api/private/session/webauthn"));
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "no login is waiting for a security key" }] })
+    );
+}
@@ -13,7 +13,10 @@
 
 pub mod categories;
 pub mod category_slugs;
+pub mod crate_downloads;
+pub mod crate_suggestions;
 pub mod crates;
+pub mod index;
 pub mod keywords;
 pub mod me;
 pub mod metrics;
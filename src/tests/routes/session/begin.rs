@@ -1,4 +1,7 @@
 use crate::util::{RequestHelper, TestApp};
+use cargo_registry::config::SsoConfig;
+use http::StatusCode;
+use oauth2::{ClientId, ClientSecret};
 
 #[derive(Deserialize)]
 struct AuthResponse {
@@ -12,3 +15,99 @@ fn auth_gives_a_token() {
     let json: AuthResponse = anon.get("/api/private/session/begin").good();
     assert!(json.url.contains(&json.state));
 }
+
+#[test]
+fn gitlab_auth_gives_a_token() {
+    let (_, anon) = TestApp::init()
+        .with_config(|config| {
+            config.gitlab_client_id = Some(ClientId::new("gitlab-client".into()));
+            config.gitlab_client_secret = Some(ClientSecret::new("gitlab-secret".into()));
+        })
+        .empty();
+
+    let url = "/api/private/session/begin?provider=gitlab";
+    let json: AuthResponse = anon.get(url).good();
+    assert!(json.url.starts_with("https://gitlab.com/oauth/authorize?"));
+    assert!(json.url.contains("client_id=gitlab-client"));
+    assert!(json.url.contains("read_api"));
+    assert!(json.url.contains(&json.state));
+}
+
+#[test]
+fn gitlab_auth_needs_to_be_enabled() {
+    let (_, anon) = TestApp::init().empty();
+
+    let response = anon.get::<()>("/api/private/session/begin?provider=gitlab");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "logging in with GitLab is not enabled" }] })
+    );
+}
+
+#[test]
+fn sso_auth_gives_a_token() {
+    let (_, anon) = TestApp::init()
+        .with_config(|config| {
+            config.sso = Some(SsoConfig {
+                client_id: ClientId::new("sso-client".into()),
+                client_secret: ClientSecret::new("sso-secret".into()),
+                authorize_url: "https://sso.example.com/authorize".into(),
+                token_url: "https://sso.example.com/token".into(),
+                userinfo_url: "https://sso.example.com/userinfo".into(),
+                jwks_url: "https://sso.example.com/keys".into(),
+                issuer: "https://sso.example.com".into(),
+                redirect_url: "https://crates.io/github-redirect.html".into(),
+                groups_claim: "groups".into(),
+                group_teams: vec![],
+            });
+        })
+        .empty();
+
+    let url = "/api/private/session/begin?provider=sso";
+    let json: AuthResponse = anon.get(url).good();
+    assert!(json.url.starts_with("https://sso.example.com/authorize?"));
+    assert!(json.url.contains("client_id=sso-client"));
+    assert!(json.url.contains("openid"));
+    assert!(json.url.contains(&json.state));
+    assert!(json.url.contains("nonce="));
+}
+
+#[test]
+fn sso_auth_needs_to_be_enabled() {
+    let (_, anon) = TestApp::init().empty();
+
+    let response = anon.get::<()>("/api/private/session/begin?provider=sso");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "logging in with single sign-on is not enabled" }] })
+    );
+}
+
+#[test]
+fn unknown_provider() {
+    let (_, anon) = TestApp::init().empty();
+
+    let response = anon.get::<()>("/api/private/session/begin?provider=bitbucket");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "unknown identity provider `bitbucket`" }] })
+    );
+}
+
+#[test]
+fn linking_requires_a_login() {
+    let (_, anon, user) = TestApp::init().with_user();
+
+    let response = anon.get::<()>("/api/private/session/begin?link=true");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "must be logged in to perform that action" }] })
+    );
+
+    let json: AuthResponse = user.get("/api/private/session/begin?link=true").good();
+    assert!(json.url.contains(&json.state));
+}
@@ -3,8 +3,11 @@ use crate::new_category;
 use crate::util::{RequestHelper, TestApp};
 use cargo_registry::schema::metadata;
 use cargo_registry::views::{EncodableCategory, EncodableCrate, EncodableKeyword};
+use cargo_registry::worker;
 use chrono::Utc;
-use diesel::{update, ExpressionMethods, RunQueryDsl};
+use diesel::sql_types::{Integer, Text};
+use diesel::{sql_query, update, ExpressionMethods, PgConnection, RunQueryDsl};
+use http::StatusCode;
 
 #[derive(Deserialize)]
 struct SummaryResponse {
@@ -150,3 +153,96 @@ fn excluded_crate_id() {
     assert_eq!(json.most_recently_downloaded[0].name, "some_downloads");
     assert_eq!(json.most_recently_downloaded[0].recent_downloads, Some(10));
 }
+
+#[derive(Deserialize)]
+struct TrendingResponse {
+    trending: Vec<TrendingCrate>,
+}
+
+#[derive(Deserialize)]
+struct TrendingCrate {
+    #[serde(rename = "crate")]
+    krate: EncodableCrate,
+    downloads: i64,
+    previous_downloads: i64,
+    growth: f64,
+}
+
+fn add_downloads(conn: &mut PgConnection, krate: &str, days_ago: i32, downloads: i32) {
+    sql_query(
+        "INSERT INTO version_downloads (version_id, date, downloads) \
+         SELECT versions.id, current_date - $2, $3 FROM versions \
+         INNER JOIN crates ON crates.id = versions.crate_id \
+         WHERE crates.name = $1",
+    )
+    .bind::<Text, _>(krate)
+    .bind::<Integer, _>(days_ago)
+    .bind::<Integer, _>(downloads)
+    .execute(conn)
+    .unwrap();
+}
+
+#[test]
+fn trending() {
+    let (app, anon, user) = TestApp::full().with_user();
+    let user = user.as_model();
+    app.db(|conn| {
+        CrateBuilder::new("doubled", user.id)
+            .version("1.0.0")
+            .expect_build(conn);
+        add_downloads(conn, "doubled", 10, 1000);
+        add_downloads(conn, "doubled", 3, 2000);
+
+        CrateBuilder::new("tripled", user.id)
+            .version("1.0.0")
+            .expect_build(conn);
+        add_downloads(conn, "tripled", 10, 2000);
+        add_downloads(conn, "tripled", 3, 6000);
+
+        CrateBuilder::new("small_baseline", user.id)
+            .version("1.0.0")
+            .expect_build(conn);
+        add_downloads(conn, "small_baseline", 10, 10);
+        add_downloads(conn, "small_baseline", 3, 5000);
+
+        CrateBuilder::new("shrinking", user.id)
+            .version("1.0.0")
+            .expect_build(conn);
+        add_downloads(conn, "shrinking", 10, 4000);
+        add_downloads(conn, "shrinking", 3, 2000);
+
+        worker::update_download_trends().enqueue(conn).unwrap();
+    });
+    app.run_pending_background_jobs();
+
+    let json: TrendingResponse = anon.get("/api/v1/summary/trending").good();
+    let names = json
+        .trending
+        .iter()
+        .map(|trend| trend.krate.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["tripled", "doubled", "shrinking"]);
+    assert_eq!(json.trending[0].downloads, 6000);
+    assert_eq!(json.trending[0].previous_downloads, 2000);
+    assert_eq!(json.trending[0].growth, 2.0);
+    assert_eq!(json.trending[2].growth, -0.5);
+
+    let json: TrendingResponse = anon
+        .get_with_query("/api/v1/summary/trending", "min_baseline=0&limit=1")
+        .good();
+    assert_eq!(json.trending.len(), 1);
+    assert_eq!(json.trending[0].krate.name, "small_baseline");
+
+    // Both windows of the longer trend contain all downloads
+    let json: TrendingResponse = anon
+        .get_with_query("/api/v1/summary/trending", "window=28")
+        .good();
+    assert!(json.trending.is_empty());
+
+    let response = anon.get_with_query::<()>("/api/v1/summary/trending", "window=3");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid window `3`, expected one of 7, 28" }] })
+    );
+}
@@ -0,0 +1,65 @@
+use crate::builders::CrateBuilder;
+use crate::util::{RequestHelper, TestApp};
+use cargo_registry::config::SearchBackendConfig;
+use cargo_registry::schema::crates;
+use cargo_registry::worker;
+use diesel::prelude::*;
+
+fn meilisearch() -> SearchBackendConfig {
+    SearchBackendConfig::Meilisearch {
+        url: "http://meilisearch.example.com".into(),
+        api_key: None,
+    }
+}
+
+#[test]
+fn search_with_meilisearch() {
+    let (app, anon, user) = TestApp::with_proxy()
+        .with_config(|config| config.search_backend = meilisearch())
+        .with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("tokio", user.id).expect_build(conn);
+        CrateBuilder::new("tokio-util", user.id).expect_build(conn);
+        CrateBuilder::new("async-std", user.id).expect_build(conn);
+    });
+
+    // Only the matches returned by Meilisearch are included, in its order
+    let json = anon.search("q=asnyc");
+    assert_eq!(json.meta.total, 2);
+    assert_eq!(json.crates[0].name, "async-std");
+    assert_eq!(json.crates[1].name, "tokio");
+}
+
+#[test]
+fn sync_meilisearch_index() {
+    let (app, _, user) = TestApp::full()
+        .with_config(|config| config.search_backend = meilisearch())
+        .with_user();
+    let user = user.as_model();
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_sync", user.id)
+            .description("A crate")
+            .keyword("kw1")
+            .expect_build(conn);
+
+        worker::sync_search_index("foo_sync".into())
+            .enqueue(conn)
+            .unwrap();
+    });
+    app.run_pending_background_jobs();
+
+    // Deleted crates are removed from the index
+    app.db(|conn| {
+        diesel::delete(crates::table.filter(crates::name.eq("foo_sync")))
+            .execute(conn)
+            .unwrap();
+
+        worker::sync_search_index("foo_sync".into())
+            .enqueue(conn)
+            .unwrap();
+    });
+    app.run_pending_background_jobs();
+}
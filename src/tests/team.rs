@@ -1,9 +1,13 @@
 use crate::{
     add_team_to_crate,
     builders::{CrateBuilder, PublishBuilder},
-    new_team, OwnerTeamsResponse, RequestHelper, TestApp,
+    new_team,
+    util::MockCookieUser,
+    OwnerTeamsResponse, RequestHelper, TestApp,
 };
-use cargo_registry::models::{Crate, NewTeam};
+use cargo_registry::config::SsoConfig;
+use cargo_registry::models::{AccountProvider, Crate, NewLinkedAccount, NewTeam, SsoIdentity};
+use cargo_registry::sso::SsoUser;
 
 use diesel::*;
 use http::StatusCode;
@@ -29,7 +33,7 @@ fn not_github() {
     assert_eq!(response.status(), StatusCode::OK);
     assert_eq!(
         response.into_json(),
-        json!({ "errors": [{ "detail": "unknown organization handler, only 'github:org:team' is supported" }] })
+        json!({ "errors": [{ "detail": "unknown organization handler, only 'github:org:team' and 'gitlab:group' are supported" }] })
     );
 }
 
@@ -210,14 +214,14 @@ fn remove_team_as_named_owner() {
 
     // Removing the individual owner is not allowed, since team members don't
     // have permission to manage ownership
-    let response = token_on_both_teams.remove_named_owner("foo_remove_team", username);
+    let response = user_on_both_teams.remove_named_owner("foo_remove_team", username);
     assert_eq!(response.status(), StatusCode::OK);
     assert_eq!(
         response.into_json(),
         json!({ "errors": [{ "detail": "cannot remove all individual owners of a crate. Team member don't have permission to modify owners, so at least one individual owner is required." }] })
     );
 
-    token_on_both_teams
+    user_on_both_teams
         .remove_named_owner("foo_remove_team", "github:test-org:core")
         .good();
 
@@ -431,3 +435,549 @@ fn crates_by_team_id_not_including_deleted_owners() {
     let json = anon.search(&format!("team_id={}", team.id));
     assert_eq!(json.crates.len(), 0);
 }
+
+#[test]
+fn publish_new_crate_with_reserved_prefix_as_non_member() {
+    use cargo_registry::schema::reserved_crate_prefixes;
+
+    let (app, _) = TestApp::full().empty();
+    let user_on_one_team = app.db_new_user("user-one-team");
+
+    app.db(|conn| {
+        let team = NewTeam::new("github:test-org:core", 1000, 2001, None, None)
+            .create_or_update(conn)
+            .unwrap();
+
+        insert_into(reserved_crate_prefixes::table)
+            .values((
+                reserved_crate_prefixes::prefix.eq("core-"),
+                reserved_crate_prefixes::team_id.eq(team.id),
+            ))
+            .execute(conn)
+            .unwrap();
+    });
+
+    let crate_to_publish = PublishBuilder::new("Core_Tools").version("1.0.0");
+    let response = user_on_one_team.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "the crate name prefix `core-` is reserved for members of the `github:test-org:core` team" }] })
+    );
+}
+
+#[test]
+fn longest_reserved_prefix_applies() {
+    use cargo_registry::models::ReservedCratePrefix;
+    use cargo_registry::schema::reserved_crate_prefixes;
+
+    let (app, _) = TestApp::init().empty();
+
+    app.db(|conn| {
+        let all = NewTeam::new("github:test-org:all", 1000, 2000, None, None)
+            .create_or_update(conn)
+            .unwrap();
+        let core = NewTeam::new("github:test-org:core", 1000, 2001, None, None)
+            .create_or_update(conn)
+            .unwrap();
+
+        insert_into(reserved_crate_prefixes::table)
+            .values(&vec![
+                (
+                    reserved_crate_prefixes::prefix.eq("test-org-"),
+                    reserved_crate_prefixes::team_id.eq(all.id),
+                ),
+                (
+                    reserved_crate_prefixes::prefix.eq("test-org-core-"),
+                    reserved_crate_prefixes::team_id.eq(core.id),
+                ),
+            ])
+            .execute(conn)
+            .unwrap();
+
+        let reservation = ReservedCratePrefix::for_crate_name(conn, "test_org_core_foo")
+            .unwrap()
+            .unwrap();
+        assert_eq!(reservation.team_id, core.id);
+
+        let reservation = ReservedCratePrefix::for_crate_name(conn, "test-org-foo")
+            .unwrap()
+            .unwrap();
+        assert_eq!(reservation.team_id, all.id);
+
+        assert_none!(ReservedCratePrefix::for_crate_name(conn, "test-foo").unwrap());
+    });
+}
+
+/// Links the GitLab account with the ID `account_id` to the user, as if they
+/// logged in with GitLab.
+fn link_gitlab_account(app: &TestApp, user: &MockCookieUser, account_id: i32) {
+    let user = user.as_model();
+    app.db(|conn| {
+        let login = format!("gitlab-{account_id}");
+        NewLinkedAccount::new(AccountProvider::GitLab, account_id, &login, None, "token")
+            .link(conn, user.id)
+            .unwrap();
+    });
+}
+
+/// Links a second GitHub account to the user, as if they logged in with it
+/// while being logged in.
+fn link_github_account(app: &TestApp, user: &MockCookieUser, account_id: i32, login: &str) {
+    let user = user.as_model();
+    app.db(|conn| {
+        NewLinkedAccount::new(AccountProvider::GitHub, account_id, login, None, "token")
+            .link(conn, user.id)
+            .unwrap();
+    });
+}
+
+/// Test adding and publishing with a team that only a linked GitHub account
+/// of the user is a member of
+#[test]
+fn team_membership_of_linked_github_account() {
+    let (app, _) = TestApp::full().empty();
+    let user = app.db_new_user("user-without-teams");
+    let token = user.db_new_token("arbitrary token name");
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_linked_team", user.as_model().id).expect_build(conn);
+    });
+
+    let response = token.add_named_owner("foo_linked_team", "github:test-org:all");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only members of a team or organization owners can add it as an owner" }] })
+    );
+
+    link_github_account(&app, &user, 2, "user-all-teams");
+    token
+        .add_named_owner("foo_linked_team", "github:test-org:all")
+        .good();
+
+    // Every linked account of the user is checked
+    let other = app.db_new_user("other");
+    link_github_account(&app, &other, 3, "user-org-owner");
+    let crate_to_publish = PublishBuilder::new("foo_linked_team").version("2.0.0");
+    let response = other.publish_crate(crate_to_publish);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this crate exists but you don't seem to be an owner. If you believe this is a mistake, perhaps you need to accept an invitation to be an owner before publishing." }] })
+    );
+
+    link_github_account(&app, &other, 1, "user-one-team");
+    let crate_to_publish = PublishBuilder::new("foo_linked_team").version("2.0.0");
+    other.publish_crate(crate_to_publish).good();
+}
+
+#[test]
+fn add_gitlab_group_without_gitlab_account() {
+    let (app, _, user, token) = TestApp::init().with_token();
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_gitlab", user.as_model().id).expect_build(conn);
+    });
+
+    let response = token.add_named_owner("foo_gitlab", "gitlab:test-group");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only users who logged in with GitLab can add a gitlab group as an owner" }] })
+    );
+}
+
+#[test]
+fn add_gitlab_group() {
+    let (app, anon, user, token) = TestApp::init().with_token();
+    link_gitlab_account(&app, &user, 101);
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_gitlab", user.as_model().id).expect_build(conn);
+    });
+
+    token
+        .add_named_owners(
+            "foo_gitlab",
+            &["gitlab:Test-Group", "gitlab:test-group/subgroup"],
+        )
+        .good();
+
+    let json = anon.crate_owner_teams("foo_gitlab").good();
+    let mut teams = json
+        .teams
+        .iter()
+        .map(|t| (t.login.as_str(), t.url.as_deref().unwrap()))
+        .collect::<Vec<_>>();
+    teams.sort_unstable();
+    assert_eq!(
+        teams,
+        [
+            ("gitlab:test-group", "https://gitlab.com/test-group"),
+            (
+                "gitlab:test-group/subgroup",
+                "https://gitlab.com/test-group/subgroup"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn add_gitlab_group_errors() {
+    let (app, _, user, token) = TestApp::init().with_token();
+    link_gitlab_account(&app, &user, 102);
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_gitlab", user.as_model().id).expect_build(conn);
+    });
+
+    let response = token.add_named_owner("foo_gitlab", "gitlab:test-group/../other");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "invalid gitlab group `test-group/../other`" }] })
+    );
+
+    let response = token.add_named_owner("foo_gitlab", "gitlab:does-not-exist");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "could not find the gitlab group does-not-exist" }] })
+    );
+
+    // Guests of the group aren't considered members
+    let response = token.add_named_owner("foo_gitlab", "gitlab:test-group");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only members of a group can add it as an owner" }] })
+    );
+}
+
+/// Test publishing a crate that is owned by a GitLab group of the user
+#[test]
+fn publish_gitlab_group_owned() {
+    let (app, _) = TestApp::full().empty();
+    let owner = app.db_new_user("owner");
+    link_gitlab_account(&app, &owner, 101);
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_gitlab_owned", owner.as_model().id).expect_build(conn);
+    });
+    owner
+        .db_new_token("arbitrary token name")
+        .add_named_owner("foo_gitlab_owned", "gitlab:test-group")
+        .good();
+
+    // Neither a guest of the group nor a user without a GitLab account are
+    // members of the team
+    let guest = app.db_new_user("guest");
+    link_gitlab_account(&app, &guest, 102);
+    let other = app.db_new_user("other");
+    for user in [&guest, &other] {
+        let crate_to_publish = PublishBuilder::new("foo_gitlab_owned").version("2.0.0");
+        let response = user.publish_crate(crate_to_publish);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.into_json(),
+            json!({ "errors": [{ "detail": "this crate exists but you don't seem to be an owner. If you believe this is a mistake, perhaps you need to accept an invitation to be an owner before publishing." }] })
+        );
+    }
+
+    let developer = app.db_new_user("developer");
+    link_gitlab_account(&app, &developer, 103);
+    let crate_to_publish = PublishBuilder::new("foo_gitlab_owned").version("2.0.0");
+    developer.publish_crate(crate_to_publish).good();
+}
+
+/// Test that users who left a team are noticed by the scheduled validation of
+/// the recorded team memberships
+#[test]
+fn sync_team_memberships() {
+    use cargo_registry::schema::{team_members, teams};
+    use cargo_registry::worker;
+    use chrono::{Duration, NaiveDateTime, Utc};
+
+    let (app, _) = TestApp::full().empty();
+    let owner = app.db_new_user("user-org-owner");
+    app.db(|conn| {
+        CrateBuilder::new("foo_team_owned", owner.as_model().id).expect_build(conn);
+    });
+    owner
+        .db_new_token("arbitrary token name")
+        .add_named_owner("foo_team_owned", "github:test-org:core")
+        .good();
+
+    // Publishing through the team records the membership
+    let member = app.db_new_user("user-all-teams");
+    let crate_to_publish = PublishBuilder::new("foo_team_owned").version("2.0.0");
+    member.publish_crate(crate_to_publish).good();
+
+    let former_member = app.db_new_user("user-one-team");
+    let member_ids = app.db(|conn| {
+        let team_id: i32 = teams::table
+            .filter(teams::login.eq("github:test-org:core"))
+            .select(teams::id)
+            .first(conn)
+            .unwrap();
+
+        // The user isn't on the team anymore, but was when they last used it
+        diesel::insert_into(team_members::table)
+            .values((
+                team_members::team_id.eq(team_id),
+                team_members::user_id.eq(former_member.as_model().id),
+            ))
+            .execute(conn)
+            .unwrap();
+
+        let two_days_ago = Utc::now().naive_utc() - Duration::days(2);
+        diesel::update(team_members::table)
+            .set(team_members::verified_at.eq(two_days_ago))
+            .execute(conn)
+            .unwrap();
+
+        worker::sync_team_memberships(100).enqueue(conn).unwrap();
+
+        team_members::table
+            .select(team_members::user_id)
+            .order(team_members::user_id)
+            .load::<i32>(conn)
+            .unwrap()
+    });
+    assert_eq!(
+        member_ids,
+        [member.as_model().id, former_member.as_model().id]
+    );
+
+    app.run_pending_background_jobs();
+
+    let memberships: Vec<(i32, NaiveDateTime)> = app.db(|conn| {
+        team_members::table
+            .select((team_members::user_id, team_members::verified_at))
+            .load(conn)
+            .unwrap()
+    });
+    assert_eq!(memberships.len(), 1);
+    assert_eq!(memberships[0].0, member.as_model().id);
+    assert!(memberships[0].1 > Utc::now().naive_utc() - Duration::days(1));
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    assert_eq!(emails.len(), 1);
+    assert_eq!(
+        emails[0].subject,
+        "Your membership of github:test-org:core on crates.io was removed"
+    );
+    assert!(emails[0].body.contains("foo_team_owned"));
+}
+
+pub(crate) fn sso_config() -> SsoConfig {
+    SsoConfig {
+        client_id: oauth2::ClientId::new("sso-client".into()),
+        client_secret: oauth2::ClientSecret::new("sso-secret".into()),
+        authorize_url: "https://sso.example.com/authorize".into(),
+        token_url: "https://sso.example.com/token".into(),
+        userinfo_url: "https://sso.example.com/userinfo".into(),
+        jwks_url: "https://sso.example.com/keys".into(),
+        issuer: "https://sso.example.com".into(),
+        redirect_url: "https://crates.io/github-redirect.html".into(),
+        groups_claim: "groups".into(),
+        group_teams: vec![
+            ("Engineering".into(), "engineering".into()),
+            ("Release Managers".into(), "releases".into()),
+        ],
+    }
+}
+
+/// Logs in with single sign-on as the identity `subject`, which is a member
+/// of the groups at the identity provider.
+pub(crate) fn sso_log_in(app: &TestApp, subject: &str, groups: &[&str]) -> MockCookieUser {
+    let sso_user = SsoUser {
+        subject: subject.into(),
+        username: format!("sso-{subject}"),
+        name: None,
+        email: Some(format!("{subject}@example.com")),
+        avatar: None,
+        groups: groups.iter().map(|group| group.to_string()).collect(),
+    };
+
+    let inner = app.as_inner();
+    let config = inner.config.sso.as_ref().unwrap();
+    let user = app.db(|conn| {
+        use cargo_registry::schema::emails;
+
+        let user = SsoIdentity::log_in(conn, &sso_user, config, &inner.emails).unwrap();
+
+        // Publishing requires a verified email address
+        diesel::update(emails::table.filter(emails::user_id.eq(user.id)))
+            .set(emails::verified.eq(true))
+            .execute(conn)
+            .unwrap();
+
+        user
+    });
+    MockCookieUser::new(app, user)
+}
+
+/// Test that users are signed up when they log in with single sign-on for the
+/// first time, and join the teams of their groups
+#[test]
+fn sso_users_are_provisioned_just_in_time() {
+    use cargo_registry::schema::team_members;
+
+    let (app, _) = TestApp::init()
+        .with_config(|config| config.sso = Some(sso_config()))
+        .empty();
+
+    let user = sso_log_in(&app, "alice", &["Engineering", "Unmapped"]);
+    assert_eq!(user.as_model().gh_login, "sso-alice");
+    assert_eq!(user.as_model().gh_id, 0);
+
+    let teams = |user: &MockCookieUser| -> Vec<String> {
+        app.db(|conn| {
+            team_members::table
+                .inner_join(cargo_registry::schema::teams::table)
+                .filter(team_members::user_id.eq(user.as_model().id))
+                .select(cargo_registry::schema::teams::login)
+                .order(cargo_registry::schema::teams::login)
+                .load(conn)
+                .unwrap()
+        })
+    };
+    assert_eq!(teams(&user), ["sso:engineering"]);
+
+    // Logging in again updates the memberships of the same user
+    let again = sso_log_in(&app, "alice", &["Release Managers"]);
+    assert_eq!(again.as_model().id, user.as_model().id);
+    assert_eq!(teams(&user), ["sso:releases"]);
+
+    // Logins can't be taken twice
+    app.db_new_user("sso-bob");
+    let sso_user = SsoUser {
+        subject: "bob".into(),
+        username: "sso-bob".into(),
+        name: None,
+        email: None,
+        avatar: None,
+        groups: vec![],
+    };
+    let inner = app.as_inner();
+    let config = inner.config.sso.as_ref().unwrap();
+    let result = app.db(|conn| SsoIdentity::log_in(conn, &sso_user, config, &inner.emails));
+    assert!(result.is_err());
+}
+
+#[test]
+fn add_sso_team() {
+    let (app, anon) = TestApp::full()
+        .with_config(|config| config.sso = Some(sso_config()))
+        .empty();
+    let member = sso_log_in(&app, "alice", &["Engineering"]);
+    let other = sso_log_in(&app, "bob", &["Release Managers"]);
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_sso", member.as_model().id).expect_build(conn);
+        CrateBuilder::new("bar_sso", other.as_model().id).expect_build(conn);
+    });
+
+    member
+        .db_new_token("arbitrary token name")
+        .add_named_owner("foo_sso", "sso:Engineering")
+        .good();
+
+    let json = anon.crate_owner_teams("foo_sso").good();
+    assert_eq!(json.teams.len(), 1);
+    assert_eq!(json.teams[0].login, "sso:engineering");
+    assert_eq!(json.teams[0].url, None);
+
+    let response = other
+        .db_new_token("arbitrary token name")
+        .add_named_owner("bar_sso", "sso:engineering");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "only members of the team sso:engineering can add it as an owner" }] })
+    );
+
+    // Only members of the team can publish the crate
+    let crate_to_publish = PublishBuilder::new("foo_sso").version("2.0.0");
+    let response = other.publish_crate(crate_to_publish);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this crate exists but you don't seem to be an owner. If you believe this is a mistake, perhaps you need to accept an invitation to be an owner before publishing." }] })
+    );
+
+    let colleague = sso_log_in(&app, "carol", &["Engineering"]);
+    let crate_to_publish = PublishBuilder::new("foo_sso").version("2.0.0");
+    colleague.publish_crate(crate_to_publish).good();
+}
+
+/// Test that memberships of single sign-on teams expire if the user doesn't
+/// log in again, since they can't be checked without the user
+#[test]
+fn sso_memberships_expire() {
+    use cargo_registry::models::SSO_MEMBERSHIP_VALID_DAYS;
+    use cargo_registry::schema::team_members;
+    use cargo_registry::worker;
+    use chrono::{Duration, Utc};
+
+    let (app, _) = TestApp::full()
+        .with_config(|config| config.sso = Some(sso_config()))
+        .empty();
+    let member = sso_log_in(&app, "alice", &["Engineering"]);
+    let former_member = sso_log_in(&app, "bob", &["Engineering"]);
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_sso", member.as_model().id).expect_build(conn);
+    });
+    member
+        .db_new_token("arbitrary token name")
+        .add_named_owner("foo_sso", "sso:engineering")
+        .good();
+
+    // The user was removed from the group at the identity provider, and
+    // hasn't logged in since
+    app.db(|conn| {
+        let last_login = Duration::days(i64::from(SSO_MEMBERSHIP_VALID_DAYS) + 1);
+        let expired = Utc::now().naive_utc() - last_login;
+        diesel::update(team_members::table)
+            .filter(team_members::user_id.eq(former_member.as_model().id))
+            .set(team_members::verified_at.eq(expired))
+            .execute(conn)
+            .unwrap();
+    });
+
+    let crate_to_publish = PublishBuilder::new("foo_sso").version("2.0.0");
+    let response = former_member.publish_crate(crate_to_publish);
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "this crate exists but you don't seem to be an owner. If you believe this is a mistake, perhaps you need to accept an invitation to be an owner before publishing." }] })
+    );
+
+    let crate_to_publish = PublishBuilder::new("foo_sso").version("2.0.0");
+    member.publish_crate(crate_to_publish).good();
+
+    // The scheduled validation removes the expired membership
+    app.db(|conn| {
+        worker::sync_team_memberships(100).enqueue(conn).unwrap();
+    });
+    app.run_pending_background_jobs();
+
+    let member_ids: Vec<i32> = app.db(|conn| {
+        team_members::table
+            .select(team_members::user_id)
+            .load(conn)
+            .unwrap()
+    });
+    assert_eq!(member_ids, [member.as_model().id]);
+}
+
+#[test]
+fn add_sso_team_requires_sso() {
+    let (app, _, user, token) = TestApp::init().with_token();
+
+    app.db(|conn| {
+        CrateBuilder::new("foo_sso", user.as_model().id).expect_build(conn);
+    });
+
+    let response = token.add_named_owner("foo_sso", "sso:engineering");
+    assert_eq!(
+        response.into_json(),
+        json!({ "errors": [{ "detail": "single sign-on is not enabled" }] })
+    );
+}
@@ -53,13 +53,12 @@ fn github_without_email_does_not_overwrite_email() {
     // Simulate logging in via GitHub with an account that has no email.
     // Because faking GitHub is terrible, call what GithubUser::save_to_database does directly.
     // Don't use app.db_new_user because it adds a verified email.
-    let user_without_github_email = app.db(|conn| {
+    let u = app.db(|conn| {
         let u = new_user("arbitrary_username");
-        let u = u
-            .create_or_update(None, &app.as_inner().emails, conn)
-            .unwrap();
-        MockCookieUser::new(&app, u)
+        u.create_or_update(None, &app.as_inner().emails, conn)
+            .unwrap()
     });
+    let user_without_github_email = MockCookieUser::new(&app, u);
     let user_without_github_email_model = user_without_github_email.as_model();
 
     let json = user_without_github_email.show_me();
@@ -70,18 +69,17 @@ fn github_without_email_does_not_overwrite_email() {
     user_without_github_email.update_email("apricot@apricots.apricot");
 
     // Simulate the same user logging in via GitHub again, still with no email in GitHub.
-    let again_user_without_github_email = app.db(|conn| {
+    let u = app.db(|conn| {
         let u = NewUser {
             // Use the same github ID to link to the existing account
             gh_id: user_without_github_email_model.gh_id,
             // new_user uses a None email; the rest of the fields are arbitrary
             ..new_user("arbitrary_username")
         };
-        let u = u
-            .create_or_update(None, &app.as_inner().emails, conn)
-            .unwrap();
-        MockCookieUser::new(&app, u)
+        u.create_or_update(None, &app.as_inner().emails, conn)
+            .unwrap()
     });
+    let again_user_without_github_email = MockCookieUser::new(&app, u);
 
     let json = again_user_without_github_email.show_me();
     assert_eq!(json.user.email.unwrap(), "apricot@apricots.apricot");
@@ -105,18 +103,17 @@ fn github_with_email_does_not_overwrite_email() {
     let new_github_email = "new-email-in-github@example.com";
 
     // Simulate logging in to crates.io after changing your email in GitHub
-    let user_with_different_email_in_github = app.db(|conn| {
+    let u = app.db(|conn| {
         let u = NewUser {
             // Use the same github ID to link to the existing account
             gh_id: model.gh_id,
             // the rest of the fields are arbitrary
             ..new_user("arbitrary_username")
         };
-        let u = u
-            .create_or_update(Some(new_github_email), &app.as_inner().emails, conn)
-            .unwrap();
-        MockCookieUser::new(&app, u)
+        u.create_or_update(Some(new_github_email), &app.as_inner().emails, conn)
+            .unwrap()
     });
+    let user_with_different_email_in_github = MockCookieUser::new(&app, u);
 
     let json = user_with_different_email_in_github.show_me();
     assert_eq!(json.user.email, Some(original_email));
@@ -155,15 +152,14 @@ fn test_confirm_user_email() {
     // email directly into the database and we want to test the verification flow here.
     let email = "potato2@example.com";
 
-    let user = app.db(|conn| {
+    let u = app.db(|conn| {
         let u = NewUser {
             ..new_user("arbitrary_username")
         };
-        let u = u
-            .create_or_update(Some(email), &app.as_inner().emails, conn)
-            .unwrap();
-        MockCookieUser::new(&app, u)
+        u.create_or_update(Some(email), &app.as_inner().emails, conn)
+            .unwrap()
     });
+    let user = MockCookieUser::new(&app, u);
     let user_model = user.as_model();
 
     let email_token: String = app.db(|conn| {
@@ -195,7 +191,7 @@ fn test_existing_user_email() {
     // Simulate logging in via GitHub. Don't use app.db_new_user because it inserts a verified
     // email directly into the database and we want to test the verification flow here.
     let email = "potahto@example.com";
-    let user = app.db(|conn| {
+    let u = app.db(|conn| {
         let u = NewUser {
             ..new_user("arbitrary_username")
         };
@@ -208,11 +204,49 @@ fn test_existing_user_email() {
             .set(emails::token_generated_at.eq(None::<NaiveDateTime>))
             .execute(conn)
             .unwrap();
-        MockCookieUser::new(&app, u)
+        u
     });
+    let user = MockCookieUser::new(&app, u);
 
     let json = user.show_me();
     assert_eq!(json.user.email.unwrap(), "potahto@example.com");
     assert!(!json.user.email_verified);
     assert!(!json.user.email_verification_sent);
 }
+
+/// Given a user with a verified email, check that changing it notifies the previous address,
+/// and that the change can be reverted from there.
+#[test]
+fn test_revert_email_change() {
+    use cargo_registry::schema::email_changes;
+
+    let (app, anon, user) = TestApp::init().with_user();
+
+    user.update_email("mango@mangos.mango");
+
+    let emails = app.as_inner().emails.mails_in_memory().unwrap();
+    let recipients: Vec<_> = emails.iter().map(|email| email.to.as_str()).collect();
+    assert_eq!(recipients, ["mango@mangos.mango", "something@example.com"]);
+    assert_eq!(
+        emails[1].subject,
+        "Your crates.io email address was changed"
+    );
+
+    let revert_token: String = app.db(|conn| {
+        email_changes::table
+            .select(email_changes::revert_token)
+            .first(conn)
+            .unwrap()
+    });
+
+    let url = format!("/api/v1/revert_email_change/{revert_token}");
+    anon.put::<OkBool>(&url, &[]).good();
+
+    let json = user.show_me();
+    assert_eq!(json.user.email.unwrap(), "something@example.com");
+    assert!(json.user.email_verified);
+
+    // The change can only be reverted once
+    let response = anon.put::<()>(&url, &[]);
+    assert_eq!(response.status(), http::StatusCode::BAD_REQUEST);
+}
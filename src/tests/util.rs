@@ -24,7 +24,7 @@ use crate::{
     GoodCrate, OkBool, OwnersResponse, VersionResponse,
 };
 use cargo_registry::middleware::session;
-use cargo_registry::models::{ApiToken, CreatedApiToken, User};
+use cargo_registry::models::{ApiToken, CreatedApiToken, PersistentSession, User};
 
 use http::{Method, Request};
 
@@ -38,10 +38,12 @@ use tower_service::Service;
 mod chaosproxy;
 mod fresh_schema;
 mod github;
+mod gitlab;
 pub mod insta;
 mod mock_request;
 mod response;
 mod test_app;
+mod webhook_receiver;
 
 pub(crate) use chaosproxy::ChaosProxy;
 pub(crate) use fresh_schema::FreshSchema;
@@ -49,26 +51,32 @@ use mock_request::MockRequest;
 pub use mock_request::MockRequestExt;
 pub use response::Response;
 pub use test_app::{TestApp, TestDatabase};
+pub use webhook_receiver::receive_one_request;
 
 /// This function can be used to create a `Cookie` header for mock requests that
 /// include cookie-based authentication.
 ///
 /// ```
-/// let cookie = encode_session_header(session_key, user_id);
+/// let cookie = encode_session_header(session_key, session_token);
 /// request.header(header::COOKIE, &cookie);
 /// ```
 ///
-/// The implementation matches roughly what is happening inside of our
-/// session middleware.
-pub fn encode_session_header(session_key: &cookie::Key, user_id: i32) -> String {
-    let cookie_name = "cargo_session";
-
+/// The `session_token` is the plaintext token of a `PersistentSession`. The
+/// implementation matches roughly what is happening inside of our session
+/// middleware.
+pub fn encode_session_header(session_key: &cookie::Key, session_token: &str) -> String {
     // build session data map
     let mut map = HashMap::new();
-    map.insert("user_id".into(), user_id.to_string());
+    map.insert(session::SESSION_TOKEN_KEY.into(), session_token.into());
+
+    encode_session_data(session_key, &map)
+}
+
+pub fn encode_session_data(session_key: &cookie::Key, map: &HashMap<String, String>) -> String {
+    let cookie_name = "cargo_session";
 
     // encode the map into a cookie value string
-    let encoded = session::encode(&map);
+    let encoded = session::encode(map);
 
     // put the cookie into a signed cookie jar
     let cookie = Cookie::build(cookie_name, encoded).finish();
@@ -240,12 +248,13 @@ impl RequestHelper for MockAnonymousUser {
 pub struct MockCookieUser {
     app: TestApp,
     user: User,
+    session_token: String,
 }
 
 impl RequestHelper for MockCookieUser {
     fn request_builder(&self, method: Method, path: &str) -> MockRequest {
         let session_key = &self.app.as_inner().session_key();
-        let cookie = encode_session_header(session_key, self.user.id);
+        let cookie = encode_session_header(session_key, &self.session_token);
 
         let mut request = req(method, path);
         request.header(header::COOKIE, &cookie);
@@ -259,10 +268,15 @@ impl RequestHelper for MockCookieUser {
 
 impl MockCookieUser {
     /// Creates an instance from a database `User` instance
+    ///
+    /// This method logs the user in by creating a session in the database
     pub fn new(app: &TestApp, user: User) -> Self {
+        let session = app.db(|conn| PersistentSession::create(conn, user.id, None, None).unwrap());
+
         Self {
             app: app.clone(),
             user,
+            session_token: session.plaintext,
         }
     }
 
@@ -296,6 +310,43 @@ impl MockCookieUser {
             token,
         }
     }
+
+    /// Creates a token that acts for an organization and wraps it in a helper
+    /// struct
+    ///
+    /// This method updates the database directly
+    pub fn db_new_organization_token(&self, name: &str, organization_id: i32) -> MockTokenUser {
+        let token = self.app.db(|conn| {
+            ApiToken::insert_for_organization(
+                conn,
+                self.user.id,
+                name,
+                None,
+                None,
+                Some(organization_id),
+            )
+            .unwrap()
+        });
+        MockTokenUser {
+            app: self.app.clone(),
+            token,
+        }
+    }
+
+    /// Remove from the specified crate the specified owners.
+    ///
+    /// Unlike API tokens without a second factor, sessions can remove owners
+    /// while they are in sudo mode.
+    pub fn remove_named_owners(&self, krate_name: &str, owners: &[&str]) -> Response<OkBool> {
+        let url = format!("/api/v1/crates/{krate_name}/owners");
+        let body = json!({ "owners": owners }).to_string();
+        self.delete_with_body(&url, body.as_bytes())
+    }
+
+    /// Remove a single owner from the specified crate.
+    pub fn remove_named_owner(&self, krate_name: &str, owner: &str) -> Response<OkBool> {
+        self.remove_named_owners(krate_name, &[owner])
+    }
 }
 
 /// A type that can generate token authenticated requests
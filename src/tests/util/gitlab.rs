@@ -0,0 +1,103 @@
+use cargo_registry::gitlab::{GitLabClient, GitLabGroup, GitLabGroupMembership, GitLabUser};
+use cargo_registry::util::errors::{not_found, AppResult};
+use oauth2::AccessToken;
+
+pub(crate) const MOCK_GITLAB_DATA: MockData = MockData {
+    groups: &[
+        MockGroup {
+            id: 3000,
+            path: "test-group",
+            members: &[(101, 50), (102, 10), (103, 30)],
+        },
+        MockGroup {
+            id: 3001,
+            path: "test-group/subgroup",
+            members: &[(101, 30)],
+        },
+    ],
+    users: &[MockUser {
+        id: 101,
+        username: "gitlab-user",
+        name: "GitLab user",
+        email: "gitlab@example.com",
+    }],
+};
+
+pub(crate) struct MockGitLabClient {
+    data: &'static MockData,
+}
+
+impl MockGitLabClient {
+    pub(crate) fn new(data: &'static MockData) -> Self {
+        Self { data }
+    }
+}
+
+impl GitLabClient for MockGitLabClient {
+    fn current_user(&self, _auth: &AccessToken) -> AppResult<GitLabUser> {
+        let user = &self.data.users[0];
+        Ok(GitLabUser {
+            id: user.id,
+            username: user.username.into(),
+            name: Some(user.name.into()),
+            email: Some(user.email.into()),
+            avatar_url: Some(format!("https://avatars.example.com/gl/{}", user.id)),
+        })
+    }
+
+    fn group_by_path(&self, path: &str, _auth: &AccessToken) -> AppResult<GitLabGroup> {
+        let group = self
+            .data
+            .groups
+            .iter()
+            .find(|group| group.path == path.to_lowercase())
+            .ok_or_else(not_found)?;
+        Ok(GitLabGroup {
+            id: group.id,
+            name: group.path.rsplit('/').next().unwrap().into(),
+            full_path: group.path.into(),
+            avatar_url: None,
+        })
+    }
+
+    fn group_membership(
+        &self,
+        group_id: i32,
+        user_id: i32,
+        _auth: &AccessToken,
+    ) -> AppResult<GitLabGroupMembership> {
+        let (_, access_level) = self
+            .data
+            .groups
+            .iter()
+            .find(|group| group.id == group_id)
+            .ok_or_else(not_found)?
+            .members
+            .iter()
+            .find(|(id, _)| *id == user_id)
+            .ok_or_else(not_found)?;
+        Ok(GitLabGroupMembership {
+            state: "active".into(),
+            access_level: *access_level,
+        })
+    }
+}
+
+pub(crate) struct MockData {
+    groups: &'static [MockGroup],
+    users: &'static [MockUser],
+}
+
+struct MockUser {
+    id: i32,
+    username: &'static str,
+    name: &'static str,
+    email: &'static str,
+}
+
+struct MockGroup {
+    id: i32,
+    path: &'static str,
+    /// The GitLab user IDs and access levels of the members
+    members: &'static [(i32, i32)],
+}
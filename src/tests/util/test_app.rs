@@ -1,15 +1,25 @@
 use super::{MockAnonymousUser, MockCookieUser, MockTokenUser};
 use crate::record;
 use crate::util::{chaosproxy::ChaosProxy, fresh_schema::FreshSchema};
-use cargo_registry::config::{self, BalanceCapacityConfig, DbPoolConfig};
+use cargo_registry::config::{
+    self, BalanceCapacityConfig, DbPoolConfig, IndexConfig, MetadataLimits, SearchBackendConfig,
+    SearchRanking,
+};
+use cargo_registry::search::Meilisearch;
+use cargo_registry::util::webhooks::WebhookClient;
 use cargo_registry::{background_jobs::Environment, App, Emails};
 use cargo_registry_index::testing::UpstreamIndex;
-use cargo_registry_index::{Credentials, Repository as WorkerRepository, RepositoryConfig};
+use cargo_registry_index::{
+    Credentials, Repository as WorkerRepository, RepositoryConfig, SigningKey,
+};
 use std::{rc::Rc, sync::Arc, time::Duration};
 
 use crate::util::github::{MockGitHubClient, MOCK_GITHUB_DATA};
+use crate::util::gitlab::{MockGitLabClient, MOCK_GITLAB_DATA};
 use cargo_registry::models::token::{CrateScope, EndpointScope};
+use cargo_registry::publish_rate_limit::PublishRateLimit;
 use cargo_registry::swirl::Runner;
+use cargo_registry::worker::cdn::Cdn;
 use diesel::PgConnection;
 use oauth2::{ClientId, ClientSecret};
 use reqwest::{blocking::Client, Proxy};
@@ -73,7 +83,9 @@ impl TestApp {
             proxy: None,
             bomb: None,
             index: None,
+            index_signing_key: None,
             build_job_runner: false,
+            cdns: Vec::new(),
             test_database: TestDatabase::TestPool,
         }
     }
@@ -117,15 +129,13 @@ impl TestApp {
                     emails::user_id.eq(user.id),
                     emails::email.eq(email),
                     emails::verified.eq(true),
+                    emails::send_notifications.eq(true),
                 ))
                 .execute(conn)
                 .unwrap();
             user
         });
-        MockCookieUser {
-            app: self.clone(),
-            user,
-        }
+        MockCookieUser::new(self, user)
     }
 
     /// Obtain a reference to the upstream repository ("the index")
@@ -190,7 +200,9 @@ pub struct TestAppBuilder {
     proxy: Option<String>,
     bomb: Option<record::Bomb>,
     index: Option<UpstreamIndex>,
+    index_signing_key: Option<SigningKey>,
     build_job_runner: bool,
+    cdns: Vec<Arc<dyn Cdn>>,
     test_database: TestDatabase,
 }
 
@@ -232,13 +244,19 @@ impl TestAppBuilder {
             let repository_config = RepositoryConfig {
                 index_location: UpstreamIndex::url(),
                 credentials: Credentials::Missing,
+                signing_key: self.index_signing_key,
             };
             let index = WorkerRepository::open(&repository_config).expect("Could not clone index");
             let environment = Environment::new(
                 index,
                 app.config.uploader().clone(),
                 app.http_client().clone(),
+                self.cdns,
+                Meilisearch::from_config(&app.config.search_backend, app.http_client().clone()),
+                app.emails.clone(),
                 None,
+                Arc::new(MockGitHubClient::new(&MOCK_GITHUB_DATA)),
+                WebhookClient::allowing_local_urls(),
             );
 
             Some(Runner::test_runner(
@@ -312,6 +330,13 @@ impl TestAppBuilder {
         })
     }
 
+    pub fn with_publish_update_rate_limit(self, rate: Duration, burst: i32) -> Self {
+        self.with_config(|config| {
+            config.publish_update_rate_limit.rate = rate;
+            config.publish_update_rate_limit.burst = burst;
+        })
+    }
+
     pub fn with_git_index(mut self) -> Self {
         self.index = Some(UpstreamIndex::new().unwrap());
         self
@@ -322,6 +347,18 @@ impl TestAppBuilder {
         self
     }
 
+    /// Sign the commits that the background jobs create in the index
+    pub fn with_index_signing_key(mut self, key: SigningKey) -> Self {
+        self.index_signing_key = Some(key);
+        self
+    }
+
+    /// Invalidate files on the given CDN in the background jobs
+    pub fn with_cdn(mut self, cdn: impl Cdn + 'static) -> Self {
+        self.cdns.push(Arc::new(cdn));
+        self
+    }
+
     /// Configures the test database
     pub fn with_database(mut self, test_database: TestDatabase) -> Self {
         self.config.use_test_database_pool = false;
@@ -337,9 +374,16 @@ fn simple_config() -> config::Server {
         session_key: cookie::Key::derive_from("test this has to be over 32 bytes long".as_bytes()),
         gh_client_id: ClientId::new(dotenv::var("GH_CLIENT_ID").unwrap_or_default()),
         gh_client_secret: ClientSecret::new(dotenv::var("GH_CLIENT_SECRET").unwrap_or_default()),
+        gitlab_client_id: None,
+        gitlab_client_secret: None,
+        sso: None,
         max_upload_size: 3000,
         max_unpack_size: 2000,
+        metadata_limits: MetadataLimits::for_testing(),
+        search_ranking: SearchRanking::for_testing(),
+        search_backend: SearchBackendConfig::Postgres,
         publish_rate_limit: Default::default(),
+        publish_update_rate_limit: PublishRateLimit::for_updates(),
         new_version_rate_limit: Some(10),
         blocked_traffic: Default::default(),
         max_allowed_page_offset: 200,
@@ -347,16 +391,28 @@ fn simple_config() -> config::Server {
         page_offset_cidr_blocklist: vec![],
         excluded_crate_names: vec![],
         domain_name: "crates.io".into(),
+        index_config: IndexConfig::for_domain("crates.io"),
+        git_http_index_path: None,
         allowed_origins: Default::default(),
         downloads_persist_interval_ms: 1000,
+        downloads_counted_from_cdn_logs: false,
+        downloads_journal_path: None,
         ownership_invitations_expiration_days: 30,
+        deletion_grace_period_hours: 72,
+        crate_transfer_waiting_period_hours: 72,
+        // Most tests publish the same empty tarball for different crates and versions
+        reject_duplicate_tarballs: false,
         metrics_authorization_token: None,
+        docs_rs_callback_token: None,
         use_test_database_pool: true,
         instance_metrics_log_every_seconds: None,
         force_unconditional_redirects: false,
         blocked_routes: HashSet::new(),
         version_id_cache_size: 10000,
         version_id_cache_ttl: Duration::from_secs(5 * 60),
+        suggestions_cache_size: 10000,
+        suggestions_cache_ttl: Duration::from_secs(15 * 60),
+        licenses_cache_ttl: Duration::from_secs(5 * 60),
         cdn_user_agent: "Amazon CloudFront".to_string(),
         balance_capacity: BalanceCapacityConfig::for_testing(),
     }
@@ -376,11 +432,12 @@ fn build_app(config: config::Server, proxy: Option<String>) -> (Arc<App>, axum::
 
     // Use the in-memory email backend for all tests, allowing tests to analyze the emails sent by
     // the application. This will also prevent cluttering the filesystem.
-    app.emails = Emails::new_in_memory();
+    app.emails = Arc::new(Emails::new_in_memory());
 
     // Use a custom mock for the GitHub client, allowing to define the GitHub users and
     // organizations without actually having to create GitHub accounts.
     app.github = Box::new(MockGitHubClient::new(&MOCK_GITHUB_DATA));
+    app.gitlab = Box::new(MockGitLabClient::new(&MOCK_GITLAB_DATA));
 
     let app = Arc::new(app);
     let router = cargo_registry::build_handler(Arc::clone(&app));
@@ -0,0 +1,51 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+/// The headers and the body of a received request.
+type ReceivedRequest = (Vec<String>, Vec<u8>);
+
+/// Accepts a single request on a local port and returns its URL and a handle
+/// that yields the headers and the body of the request.
+pub fn receive_one_request() -> (String, thread::JoinHandle<ReceivedRequest>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        let mut headers = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end().to_string();
+            if line.is_empty() {
+                break;
+            }
+            headers.push(line);
+        }
+
+        let content_length = headers
+            .iter()
+            .find_map(|header| {
+                header
+                    .to_lowercase()
+                    .strip_prefix("content-length: ")
+                    .map(String::from)
+            })
+            .unwrap()
+            .parse()
+            .unwrap();
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+
+        (headers, body)
+    });
+
+    (url, handle)
+}
@@ -1,80 +1,134 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use reqwest::{blocking::Client, header};
 
 use crate::util::errors::{internal, AppResult};
 
 use reqwest::blocking::Body;
-use std::env;
-use std::fs::{self, File};
-use std::path::PathBuf;
+use std::sync::Arc;
 
-use crate::models::Crate;
+use crate::models::{Crate, CrateFile, FileReplication, PublishUpload};
+use crate::storage::Storage;
+pub use crate::storage::UploadBucket;
+use crate::swirl::errors::EnqueueError;
+use crate::worker;
+use cargo_registry_markdown::RENDERER_VERSION;
+use diesel::PgConnection;
+use hex::ToHex;
+use rand::distributions::Alphanumeric;
+use rand::rngs::OsRng;
+use rand::Rng;
+use sha2::{Digest, Sha256};
 
 const CACHE_CONTROL_IMMUTABLE: &str = "public,max-age=31536000,immutable";
 const CACHE_CONTROL_README: &str = "public,max-age=604800";
 const CACHE_CONTROL_INDEX: &str = "public,max-age=600";
 
+/// Stores crate files, readmes, and index files in the configured storage
+/// backend, at the paths that are expected by cargo and the frontend.
+///
+/// With the content-addressed layout, crate files are stored under their
+/// SHA-256 checksum instead of their crate name and version, and the key of
+/// each version's crate file is recorded in the `crate_files` table. Identical
+/// crate files are stored only once, and their contents are verified against
+/// their key when they are read back.
+///
+/// Crate files and readmes are copied to the replica buckets by the
+/// `replicate_files` background job. Crate files that are read back by the
+/// background jobs are read from a replica if the primary bucket is
+/// unavailable. Downloads are always redirected to the primary bucket, so
+/// serving them from a replica during an outage requires pointing the CDN at
+/// the replica.
 #[derive(Clone, Debug)]
-pub enum Uploader {
-    /// For production usage, uploads and redirects to s3.
-    /// For test usage with `TestApp::with_proxy()`, the recording proxy is used.
-    S3 {
-        bucket: Box<s3::Bucket>,
-        index_bucket: Option<Box<s3::Bucket>>,
-        cdn: Option<String>,
-    },
-
-    /// For development usage only: "uploads" crate files to `dist` and serves them
-    /// from there as well to enable local publishing and download
-    Local,
+pub struct Uploader {
+    storage: Arc<dyn Storage>,
+    replicas: Vec<Replica>,
+    content_addressed: bool,
 }
 
-pub enum UploadBucket {
-    Default,
-    Index,
+#[derive(Clone, Debug)]
+struct Replica {
+    name: String,
+    storage: Arc<dyn Storage>,
 }
 
 impl Uploader {
+    pub fn new(storage: impl Storage + 'static) -> Self {
+        Self {
+            storage: Arc::new(storage),
+            replicas: Vec::new(),
+            content_addressed: false,
+        }
+    }
+
+    /// Stores newly uploaded crate files under their content address.
+    pub fn with_content_addressed_layout(mut self) -> Self {
+        self.content_addressed = true;
+        self
+    }
+
+    pub fn is_content_addressed(&self) -> bool {
+        self.content_addressed
+    }
+
+    /// Adds a replica that crate files and readmes are copied to. The `name`
+    /// identifies the replica in the replication state of the files.
+    pub fn with_replica(
+        mut self,
+        name: impl Into<String>,
+        storage: impl Storage + 'static,
+    ) -> Self {
+        self.replicas.push(Replica {
+            name: name.into(),
+            storage: Arc::new(storage),
+        });
+        self
+    }
+
+    pub fn storage(&self) -> &dyn Storage {
+        &*self.storage
+    }
+
+    /// Returns the names of the replicas that files are copied to.
+    pub fn replica_names(&self) -> Vec<String> {
+        self.replicas.iter().map(|r| r.name.clone()).collect()
+    }
+
     /// Returns the URL of an uploaded crate's version archive.
     ///
     /// The function doesn't check for the existence of the file.
     pub fn crate_location(&self, crate_name: &str, version: &str) -> String {
-        match *self {
-            Uploader::S3 {
-                ref bucket,
-                ref cdn,
-                ..
-            } => {
-                let host = match *cdn {
-                    Some(ref s) => s.clone(),
-                    None => bucket.host(),
-                };
-                let path = Uploader::crate_path(crate_name, version);
-                format!("https://{host}/{path}")
-            }
-            Uploader::Local => format!("/{}", Uploader::crate_path(crate_name, version)),
-        }
+        self.storage.url(&Uploader::crate_path(crate_name, version))
+    }
+
+    /// Returns the URL of a crate file that is stored under its content
+    /// address, see [`CrateFile`].
+    pub fn crate_object_location(&self, object_key: &str) -> String {
+        self.storage.url(object_key)
     }
 
-    /// Returns the URL of an uploaded crate's version readme.
+    /// Returns the URL of an uploaded crate's version readme, as rendered by
+    /// the given version of the renderer.
     ///
     /// The function doesn't check for the existence of the file.
-    pub fn readme_location(&self, crate_name: &str, version: &str) -> String {
-        match *self {
-            Uploader::S3 {
-                ref bucket,
-                ref cdn,
-                ..
-            } => {
-                let host = match *cdn {
-                    Some(ref s) => s.clone(),
-                    None => bucket.host(),
-                };
-                let path = Uploader::readme_path(crate_name, version);
-                format!("https://{host}/{path}")
-            }
-            Uploader::Local => format!("/{}", Uploader::readme_path(crate_name, version)),
-        }
+    pub fn readme_location(
+        &self,
+        crate_name: &str,
+        version: &str,
+        renderer_version: i32,
+    ) -> String {
+        self.storage.url(&Uploader::readme_path(
+            crate_name,
+            version,
+            renderer_version,
+        ))
+    }
+
+    /// Returns the URL of the signature of an uploaded crate's version archive.
+    ///
+    /// The function doesn't check for the existence of the file.
+    pub fn signature_location(&self, crate_name: &str, version: &str) -> String {
+        self.storage
+            .url(&Uploader::signature_path(crate_name, version))
     }
 
     /// Returns the internal path of an uploaded crate's version archive.
@@ -82,9 +136,29 @@ impl Uploader {
         format!("crates/{name}/{name}-{version}.crate")
     }
 
+    /// Returns the internal path of a crate file with the SHA-256 `checksum`
+    /// in the content-addressed layout.
+    fn crate_object_path(checksum: &str) -> String {
+        format!("crates/sha256/{}/{checksum}.crate", &checksum[..2])
+    }
+
+    /// Returns the internal path of the signature of an uploaded crate's
+    /// version archive.
+    fn signature_path(name: &str, version: &str) -> String {
+        format!("crates/{name}/{name}-{version}.crate.sig")
+    }
+
     /// Returns the internal path of an uploaded crate's version readme.
-    fn readme_path(name: &str, version: &str) -> String {
-        format!("readmes/{name}/{name}-{version}.html")
+    ///
+    /// Readmes are stored under a new path whenever the renderer changes, so
+    /// that re-rendered readmes don't have to be invalidated on the CDN.
+    /// Readmes that were rendered before the path was versioned have the
+    /// renderer version 0.
+    fn readme_path(name: &str, version: &str, renderer_version: i32) -> String {
+        match renderer_version {
+            0 => format!("readmes/{name}/{name}-{version}.html"),
+            _ => format!("readmes/{name}/{name}-{version}.r{renderer_version}.html"),
+        }
     }
 
     /// Returns the internal path of an uploaded crate's index file.
@@ -92,23 +166,7 @@ impl Uploader {
         cargo_registry_index::Repository::relative_index_file_for_url(name)
     }
 
-    /// Returns the absolute path to the locally uploaded file.
-    fn local_uploads_path(path: &str, upload_bucket: UploadBucket) -> PathBuf {
-        let path = match upload_bucket {
-            UploadBucket::Index => PathBuf::from("index").join(path),
-            UploadBucket::Default => PathBuf::from(path),
-        };
-        env::current_dir().unwrap().join("local_uploads").join(path)
-    }
-
-    /// Uploads a file using the configured uploader (either `S3`, `Local`).
-    ///
-    /// It returns the path of the uploaded file.
-    ///
-    /// # Panics
-    ///
-    /// This function can panic on an `Self::Local` during development.
-    /// Production and tests use `Self::S3` which should not panic.
+    /// Uploads a file to the configured storage backend.
     pub fn upload<R: Into<Body>>(
         &self,
         client: &Client,
@@ -117,73 +175,45 @@ impl Uploader {
         content_type: &str,
         extra_headers: header::HeaderMap,
         upload_bucket: UploadBucket,
-    ) -> Result<Option<String>> {
-        match *self {
-            Uploader::S3 {
-                ref bucket,
-                ref index_bucket,
-                ..
-            } => {
-                let bucket = match upload_bucket {
-                    UploadBucket::Default => Some(bucket),
-                    UploadBucket::Index => index_bucket.as_ref(),
-                };
-
-                if let Some(bucket) = bucket {
-                    bucket.put(client, path, content, content_type, extra_headers)?;
-                }
-
-                Ok(Some(String::from(path)))
-            }
-            Uploader::Local => {
-                let filename = Self::local_uploads_path(path, upload_bucket);
-                let dir = filename.parent().unwrap();
-                fs::create_dir_all(dir)?;
-                let mut file = File::create(&filename)?;
-                let mut body = content.into();
-                let mut buffer = body.buffer()?;
-                std::io::copy(&mut buffer, &mut file)?;
-                Ok(filename.to_str().map(String::from))
-            }
-        }
+    ) -> Result<()> {
+        self.storage.put(
+            client,
+            path,
+            content.into(),
+            content_type,
+            extra_headers,
+            upload_bucket,
+        )
     }
 
-    /// Deletes a file using the configured uploader (either `S3`, `Local`).
+    /// Deletes a file from the configured storage backend.
     pub fn delete(&self, client: &Client, path: &str, upload_bucket: UploadBucket) -> Result<()> {
-        match *self {
-            Uploader::S3 {
-                ref bucket,
-                ref index_bucket,
-                ..
-            } => {
-                let bucket = match upload_bucket {
-                    UploadBucket::Default => Some(bucket),
-                    UploadBucket::Index => index_bucket.as_ref(),
-                };
-
-                if let Some(bucket) = bucket {
-                    bucket.delete(client, path)?;
-                }
-            }
-            Uploader::Local => {
-                let filename = Self::local_uploads_path(path, upload_bucket);
-                // Ignore errors if the local index file doesn't exist; this can happen if you
-                // aren't running the background job worker locally
-                let _ = std::fs::remove_file(filename);
-            }
-        }
-        Ok(())
+        self.storage.delete(client, path, upload_bucket)
     }
 
-    /// Uploads a crate and returns the checksum of the uploaded crate file.
+    /// Uploads a crate file with the SHA-256 `checksum`.
+    ///
+    /// Returns the key that the crate file is stored under in the
+    /// content-addressed layout, which has to be recorded as the
+    /// [`CrateFile`] of the version in the transaction of `conn`, or `None`
+    /// otherwise. The crate file stays locked until that transaction ends, see
+    /// [`CrateFile::lock()`].
     pub fn upload_crate<R: Into<Body>>(
         &self,
         http_client: &Client,
+        conn: &mut PgConnection,
         body: R,
         krate: &Crate,
         vers: &semver::Version,
-    ) -> AppResult<()> {
-        let path = Uploader::crate_path(&krate.name, &vers.to_string());
+        checksum: &str,
+    ) -> AppResult<Option<String>> {
+        let path = if self.content_addressed {
+            let object_key = Uploader::crate_object_path(checksum);
+            CrateFile::lock(conn, &object_key)?;
+            object_key
+        } else {
+            Uploader::crate_path(&krate.name, &vers.to_string())
+        };
         let mut extra_headers = header::HeaderMap::new();
         extra_headers.insert(
             header::CACHE_CONTROL,
@@ -198,9 +228,272 @@ impl Uploader {
             UploadBucket::Default,
         )
         .map_err(|e| internal(format!("failed to upload crate: {e}")))?;
+        Ok(self.content_addressed.then_some(path))
+    }
+
+    /// Uploads the signature of a crate file next to the crate file.
+    pub(crate) fn upload_signature<R: Into<Body>>(
+        &self,
+        http_client: &Client,
+        crate_name: &str,
+        vers: &str,
+        signature: R,
+        content_type: &str,
+    ) -> AppResult<()> {
+        let path = Uploader::signature_path(crate_name, vers);
+        let mut extra_headers = header::HeaderMap::new();
+        extra_headers.insert(
+            header::CACHE_CONTROL,
+            header::HeaderValue::from_static(CACHE_CONTROL_IMMUTABLE),
+        );
+        self.upload(
+            http_client,
+            &path,
+            signature,
+            content_type,
+            extra_headers,
+            UploadBucket::Default,
+        )
+        .map_err(|e| internal(format!("failed to upload signature: {e}")))?;
+        Ok(())
+    }
+
+    /// Returns a new path for the body of a publish request that is uploaded
+    /// in multiple chunks. The path is random, since the default bucket is
+    /// public.
+    pub(crate) fn publish_upload_path() -> String {
+        let token: String = OsRng
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+        format!("uploads/{token}")
+    }
+
+    /// Starts the multipart upload of a publish request body to `path`, and
+    /// returns the ID of the upload in the storage backend.
+    pub(crate) fn start_publish_upload(
+        &self,
+        http_client: &Client,
+        path: &str,
+    ) -> AppResult<String> {
+        self.storage
+            .start_multipart_upload(http_client, path)
+            .map_err(|e| internal(format!("failed to start upload: {e}")))
+    }
+
+    /// The minimum size of all chunks of a publish upload but the last one.
+    pub fn min_publish_chunk_size(&self) -> usize {
+        self.storage.min_part_size()
+    }
+
+    /// Stores a chunk of a publish upload as part `part_number`, and returns
+    /// the tag of the part.
+    pub(crate) fn upload_publish_chunk(
+        &self,
+        http_client: &Client,
+        upload: &PublishUpload,
+        part_number: i32,
+        chunk: Vec<u8>,
+    ) -> AppResult<String> {
+        self.storage
+            .upload_part(
+                http_client,
+                &upload.storage_path,
+                &upload.storage_upload_id,
+                part_number,
+                chunk,
+            )
+            .map_err(|e| internal(format!("failed to upload chunk: {e}")))
+    }
+
+    /// Assembles the chunks of a publish upload, and returns the complete
+    /// request body. The body is deleted from storage again, since it's only
+    /// needed for publishing.
+    pub(crate) fn complete_publish_upload(
+        &self,
+        http_client: &Client,
+        upload: &PublishUpload,
+        parts: &[(i32, String)],
+    ) -> AppResult<Vec<u8>> {
+        let path = &upload.storage_path;
+        let body = self
+            .storage
+            .complete_multipart_upload(http_client, path, &upload.storage_upload_id, parts)
+            .and_then(|_| self.storage.get(http_client, path, UploadBucket::Default))
+            .map_err(|e| internal(format!("failed to complete upload: {e}")))?
+            .ok_or_else(|| internal(format!("completed upload `{path}` does not exist")))?;
+
+        self.storage
+            .delete(http_client, path, UploadBucket::Default)
+            .map_err(|e| internal(format!("failed to delete completed upload: {e}")))?;
+        Ok(body)
+    }
+
+    /// Discards the stored chunks of a publish upload that was not completed.
+    pub(crate) fn abort_publish_upload(
+        &self,
+        http_client: &Client,
+        upload: &PublishUpload,
+        parts: &[(i32, String)],
+    ) -> Result<()> {
+        self.storage.abort_multipart_upload(
+            http_client,
+            &upload.storage_path,
+            &upload.storage_upload_id,
+            parts,
+        )
+    }
+
+    /// Downloads the crate file of a crate version.
+    pub(crate) fn download_crate(
+        &self,
+        http_client: &Client,
+        crate_name: &str,
+        vers: &str,
+    ) -> Result<Vec<u8>> {
+        let path = Uploader::crate_path(crate_name, vers);
+        self.get_with_failover(http_client, &path)?
+            .ok_or_else(|| anyhow!("crate file `{path}` does not exist"))
+    }
+
+    /// Downloads a crate file that is stored under its content address, and
+    /// verifies that its contents match the checksum in the key.
+    pub(crate) fn download_crate_object(
+        &self,
+        http_client: &Client,
+        object_key: &str,
+    ) -> Result<Vec<u8>> {
+        let content = self
+            .get_with_failover(http_client, object_key)?
+            .ok_or_else(|| anyhow!("crate file `{object_key}` does not exist"))?;
+
+        let checksum: String = Sha256::digest(&content).encode_hex();
+        if Uploader::crate_object_path(&checksum) != object_key {
+            return Err(anyhow!(
+                "crate file `{object_key}` is corrupted, its checksum is `{checksum}`"
+            ));
+        }
+        Ok(content)
+    }
+
+    /// Reads the file at `path` from the primary bucket, or from the first
+    /// replica that has it if the primary bucket is unavailable.
+    ///
+    /// Files that don't exist in the primary bucket are not looked up in the
+    /// replicas, since they were usually deleted on purpose.
+    fn get_with_failover(&self, http_client: &Client, path: &str) -> Result<Option<Vec<u8>>> {
+        let error = match self.storage.get(http_client, path, UploadBucket::Default) {
+            Ok(content) => return Ok(content),
+            Err(error) => error,
+        };
+
+        for replica in &self.replicas {
+            let replica_name = &replica.name;
+            match replica
+                .storage
+                .get(http_client, path, UploadBucket::Default)
+            {
+                Ok(Some(content)) => {
+                    warn!(%path, %replica_name, ?error, "Read file from replica instead of the primary bucket");
+                    return Ok(Some(content));
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    warn!(%path, %replica_name, ?error, "Failed to read file from replica")
+                }
+            }
+        }
+
+        Err(error)
+    }
+
+    /// Downloads the crate file of a crate version without verifying its
+    /// contents, or returns `None` if it does not exist.
+    ///
+    /// `object_key` is the key of the crate file if it is stored under its
+    /// content address.
+    pub(crate) fn get_crate_file(
+        &self,
+        http_client: &Client,
+        crate_name: &str,
+        vers: &str,
+        object_key: Option<&str>,
+    ) -> Result<Option<Vec<u8>>> {
+        let path = match object_key {
+            Some(object_key) => object_key.to_string(),
+            None => Uploader::crate_path(crate_name, vers),
+        };
+        self.storage.get(http_client, &path, UploadBucket::Default)
+    }
+
+    /// Deletes the content-addressed crate files with the given keys that are
+    /// not referenced by any version anymore.
+    pub(crate) fn delete_unreferenced_crate_objects(
+        &self,
+        http_client: &Client,
+        conn: &mut PgConnection,
+        object_keys: &[String],
+    ) -> AppResult<()> {
+        let mut unreferenced = Vec::new();
+        for object_key in object_keys {
+            CrateFile::lock(conn, object_key)?;
+            if !CrateFile::is_referenced(conn, object_key)? {
+                unreferenced.push(object_key);
+            }
+        }
+
+        let storages =
+            std::iter::once(&self.storage).chain(self.replicas.iter().map(|r| &r.storage));
+        for storage in storages {
+            for object_key in &unreferenced {
+                storage
+                    .delete(http_client, object_key, UploadBucket::Default)
+                    .map_err(|e| internal(format!("failed to delete crate files: {e}")))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes the crate file and the rendered readmes of all renderer
+    /// versions of a crate version.
+    pub(crate) fn delete_crate_files(
+        &self,
+        http_client: &Client,
+        crate_name: &str,
+        vers: &str,
+    ) -> AppResult<()> {
+        let mut paths = vec![Uploader::crate_path(crate_name, vers)];
+        paths.extend(
+            (0..=RENDERER_VERSION)
+                .map(|renderer_version| Uploader::readme_path(crate_name, vers, renderer_version)),
+        );
+        let storages =
+            std::iter::once(&self.storage).chain(self.replicas.iter().map(|r| &r.storage));
+        for storage in storages {
+            for path in &paths {
+                storage
+                    .delete(http_client, path, UploadBucket::Default)
+                    .map_err(|e| internal(format!("failed to delete crate files: {e}")))?;
+            }
+        }
         Ok(())
     }
 
+    /// Deletes the signature of the crate file of a crate version.
+    pub(crate) fn delete_signature(
+        &self,
+        http_client: &Client,
+        crate_name: &str,
+        vers: &str,
+    ) -> AppResult<()> {
+        let path = Uploader::signature_path(crate_name, vers);
+        self.delete(http_client, &path, UploadBucket::Default)
+            .map_err(|e| internal(format!("failed to delete signature: {e}")))
+    }
+
+    /// Uploads a readme that was rendered by the current version of the
+    /// renderer.
     pub(crate) fn upload_readme(
         &self,
         http_client: &Client,
@@ -208,7 +501,7 @@ impl Uploader {
         vers: &str,
         readme: String,
     ) -> Result<()> {
-        let path = Uploader::readme_path(crate_name, vers);
+        let path = Uploader::readme_path(crate_name, vers, RENDERER_VERSION);
         let mut extra_headers = header::HeaderMap::new();
         extra_headers.insert(
             header::CACHE_CONTROL,
@@ -225,6 +518,24 @@ impl Uploader {
         Ok(())
     }
 
+    /// Deletes a readme that was rendered by an outdated version of the
+    /// renderer from the primary bucket and the replicas.
+    pub(crate) fn delete_readme(
+        &self,
+        http_client: &Client,
+        crate_name: &str,
+        vers: &str,
+        renderer_version: i32,
+    ) -> Result<()> {
+        let path = Uploader::readme_path(crate_name, vers, renderer_version);
+        let storages =
+            std::iter::once(&self.storage).chain(self.replicas.iter().map(|r| &r.storage));
+        for storage in storages {
+            storage.delete(http_client, &path, UploadBucket::Default)?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn upload_index(
         &self,
         http_client: &Client,
@@ -254,6 +565,106 @@ impl Uploader {
         Ok(())
     }
 
+    /// Downloads the index file of a crate from the HTTP-based index.
+    ///
+    /// Returns `None` if the file does not exist, or if no index bucket is
+    /// configured.
+    pub(crate) fn download_index(
+        &self,
+        http_client: &Client,
+        crate_name: &str,
+    ) -> Result<Option<String>> {
+        let path = Uploader::index_path(crate_name);
+        let contents = self
+            .storage
+            .get(http_client, &path, UploadBucket::Index)?
+            .map(String::from_utf8)
+            .transpose()?;
+        Ok(contents)
+    }
+
+    /// Returns `true` if index files are uploaded to an HTTP-based index.
+    pub(crate) fn has_index_bucket(&self) -> bool {
+        self.storage.has_index_bucket()
+    }
+
+    /// Queues the replication of the crate file of a crate version to the
+    /// replicas.
+    pub fn replicate_crate(
+        &self,
+        conn: &mut PgConnection,
+        crate_name: &str,
+        vers: &str,
+    ) -> Result<(), EnqueueError> {
+        let path = match CrateFile::object_key_by_name(conn, crate_name, vers)? {
+            Some(object_key) => object_key,
+            None => Uploader::crate_path(crate_name, vers),
+        };
+        self.queue_replication(conn, &path)
+    }
+
+    /// Queues the replication of the rendered readme of a crate version to
+    /// the replicas.
+    pub fn replicate_readme(
+        &self,
+        conn: &mut PgConnection,
+        crate_name: &str,
+        vers: &str,
+    ) -> Result<(), EnqueueError> {
+        let path = Uploader::readme_path(crate_name, vers, RENDERER_VERSION);
+        self.queue_replication(conn, &path)
+    }
+
+    fn queue_replication(&self, conn: &mut PgConnection, path: &str) -> Result<(), EnqueueError> {
+        if self.replicas.is_empty() {
+            return Ok(());
+        }
+
+        FileReplication::queue(conn, path, &self.replica_names())?;
+        worker::replicate_files().enqueue(conn)
+    }
+
+    /// Copies the file at `path` from the primary bucket to the `replica`.
+    ///
+    /// Returns `false` if the file does not exist in the primary bucket
+    /// anymore, e.g. because its version was deleted in the meantime.
+    pub(crate) fn replicate(
+        &self,
+        http_client: &Client,
+        replica: &str,
+        path: &str,
+    ) -> Result<bool> {
+        let replica = self
+            .replicas
+            .iter()
+            .find(|r| r.name == replica)
+            .ok_or_else(|| anyhow!("replica `{replica}` is not configured"))?;
+
+        let Some(content) = self.storage.get(http_client, path, UploadBucket::Default)? else {
+            return Ok(false);
+        };
+
+        let (content_type, cache_control) = if path.ends_with(".crate") {
+            ("application/gzip", CACHE_CONTROL_IMMUTABLE)
+        } else {
+            ("text/html", CACHE_CONTROL_README)
+        };
+        let mut extra_headers = header::HeaderMap::new();
+        extra_headers.insert(
+            header::CACHE_CONTROL,
+            header::HeaderValue::from_static(cache_control),
+        );
+        replica.storage.put(
+            http_client,
+            path,
+            content.into(),
+            content_type,
+            extra_headers,
+            UploadBucket::Default,
+        )?;
+        Ok(true)
+    }
+
     pub(crate) fn sync_index(
         &self,
         http_client: &Client,
@@ -5,12 +5,17 @@ pub use self::io_util::{read_fill, read_le_u32, LimitErrorReader};
 pub use self::request_helpers::*;
 
 mod bytes_request;
+pub mod duration;
 pub mod errors;
+pub mod hyperloglog;
 mod io_util;
 mod request_helpers;
 pub mod rfc3339;
+pub mod sigstore;
 pub mod token;
+pub mod totp;
 pub mod tracing;
+pub mod webhooks;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Maximums {
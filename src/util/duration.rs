@@ -0,0 +1,55 @@
+//! Convenience functions for serializing and deserializing durations in JSON
+//! API responses.
+//!
+//! All durations are serialized as whole seconds, and the fields carrying them
+//! are suffixed with `_seconds`, so that clients don't have to guess the unit.
+//! Example: `"retry_after_seconds": 90`.
+
+use chrono::Duration;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A duration that is serialized in the same format as the fields that use
+/// this module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Seconds(pub Duration);
+
+impl Serialize for Seconds {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Seconds {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Self)
+    }
+}
+
+pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_i64(duration.num_seconds())
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    i64::deserialize(deserializer).map(Duration::seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Seconds;
+    use chrono::Duration;
+
+    #[test]
+    fn serializes_whole_seconds() {
+        let json = serde_json::to_string(&Seconds(Duration::milliseconds(90_500))).unwrap();
+        assert_eq!(json, "90");
+
+        let Seconds(duration) = serde_json::from_str("90").unwrap();
+        assert_eq!(duration, Duration::seconds(90));
+    }
+}
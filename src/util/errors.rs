@@ -31,8 +31,10 @@ mod json;
 
 pub use json::TOKEN_FORMAT_ERROR;
 pub(crate) use json::{
-    InsecurelyGeneratedTokenRevoked, MetricsDisabled, NotFound, OwnershipInvitationExpired,
-    ReadOnlyMode, RouteBlocked, TooManyRequests,
+    InsecurelyGeneratedTokenRevoked, MetadataLimit, MetadataLimitExceeded, MetricsDisabled,
+    MissingAdminPermission, NotFound, OwnershipInvitationExpired, ReadOnlyMode,
+    RegistryAuthRequired, ReservedCrateName, RouteBlocked, SudoModeRequired, TooManyRequests,
+    TotpLocked, UploadTooLarge,
 };
 
 pub type BoxedAppError = Box<dyn AppError>;
@@ -63,6 +65,11 @@ pub fn account_locked(reason: &str, until: Option<NaiveDateTime>) -> BoxedAppErr
     })
 }
 
+/// Return an error with status 409 and the provided description as JSON
+pub fn conflict<S: ToString + ?Sized>(error: &S) -> BoxedAppError {
+    Box::new(json::Conflict(error.to_string()))
+}
+
 pub fn forbidden() -> BoxedAppError {
     Box::new(json::Forbidden)
 }
@@ -3,9 +3,12 @@ use axum::Json;
 use std::fmt;
 
 use super::{AppError, BoxedAppError, InternalAppErrorStatic};
+use crate::publish_rate_limit::LimitedAction;
+use crate::util::duration::Seconds;
+use crate::util::rfc3339::Timestamp;
 
-use chrono::NaiveDateTime;
-use http::{header, StatusCode};
+use chrono::{Duration, NaiveDateTime, Utc};
+use http::{header, HeaderValue, StatusCode};
 
 /// Generates a response with the provided status and description as JSON
 fn json_error(detail: &str, status: StatusCode) -> Response {
@@ -69,11 +72,14 @@ pub(super) struct Ok(pub(super) String);
 #[derive(Debug)]
 pub(super) struct BadRequest(pub(super) String);
 #[derive(Debug)]
+pub(super) struct Conflict(pub(super) String);
+#[derive(Debug)]
 pub(super) struct ServerError(pub(super) String);
 #[derive(Debug)]
 pub(crate) struct ServiceUnavailable(pub(super) String);
 #[derive(Debug)]
 pub(crate) struct TooManyRequests {
+    pub action: LimitedAction,
     pub retry_after: NaiveDateTime,
 }
 
@@ -101,6 +107,18 @@ impl fmt::Display for BadRequest {
     }
 }
 
+impl AppError for Conflict {
+    fn response(&self) -> Response {
+        json_error(&self.0, StatusCode::CONFLICT)
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 impl AppError for ServerError {
     fn response(&self) -> Response {
         json_error(&self.0, StatusCode::INTERNAL_SERVER_ERROR)
@@ -131,11 +149,19 @@ impl AppError for TooManyRequests {
         let retry_after = self.retry_after.format(HTTP_DATE_FORMAT);
 
         let detail = format!(
-            "You have published too many crates in a \
-             short period of time. Please try again after {retry_after} or email \
-             help@crates.io to have your limit increased."
+            "{} Please try again after {retry_after} or email \
+             help@crates.io to have your limit increased.",
+            self.action.error_message()
         );
-        let mut response = json_error(&detail, StatusCode::TOO_MANY_REQUESTS);
+        let wait = self.retry_after - Utc::now().naive_utc();
+        let json = json!({
+            "errors": [{
+                "detail": detail,
+                "retry_after": Timestamp(self.retry_after),
+                "retry_after_seconds": Seconds(wait.max(Duration::zero())),
+            }]
+        });
+        let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(json)).into_response();
         response.headers_mut().insert(
             header::RETRY_AFTER,
             retry_after
@@ -153,6 +179,34 @@ impl fmt::Display for TooManyRequests {
     }
 }
 
+/// The registry is configured with `auth-required`, but the request was not
+/// authenticated.
+#[derive(Debug)]
+pub(crate) struct RegistryAuthRequired {
+    pub login_url: String,
+}
+
+impl AppError for RegistryAuthRequired {
+    fn response(&self) -> Response {
+        let detail = "this registry requires authentication, please run `cargo login`";
+        let mut response = json_error(detail, StatusCode::UNAUTHORIZED);
+        // Tells cargo where users can get a token for the registry
+        let challenge = format!("Cargo login_url=\"{}\"", self.login_url);
+        if let Result::Ok(challenge) = HeaderValue::try_from(challenge) {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, challenge);
+        }
+        response
+    }
+}
+
+impl fmt::Display for RegistryAuthRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "Registry authentication required".fmt(f)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct InsecurelyGeneratedTokenRevoked;
 
@@ -223,6 +277,112 @@ impl fmt::Display for AccountLocked {
     }
 }
 
+/// Returned with status 200 for compatibility with cargo, which only shows the
+/// `detail` field, while other clients can use the machine-readable limit.
+#[derive(Debug)]
+pub(crate) struct UploadTooLarge {
+    pub(crate) max_upload_size: u64,
+}
+
+impl AppError for UploadTooLarge {
+    fn response(&self) -> Response {
+        let json = json!({
+            "errors": [{
+                "detail": self.to_string(),
+                "max_upload_size": self.max_upload_size,
+            }]
+        });
+        (StatusCode::OK, Json(json)).into_response()
+    }
+}
+
+impl fmt::Display for UploadTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "max upload size is: {}", self.max_upload_size)
+    }
+}
+
+/// The limits that are checked by [`MetadataLimitExceeded`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum MetadataLimit {
+    Dependencies,
+    Features,
+    FeatureNameLength,
+    MetadataSize,
+}
+
+impl MetadataLimit {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dependencies => "max_dependencies",
+            Self::Features => "max_features",
+            Self::FeatureNameLength => "max_feature_name_length",
+            Self::MetadataSize => "max_metadata_size",
+        }
+    }
+}
+
+/// Returned with status 200 for compatibility with cargo. The `limit` and
+/// `max` fields tell other clients which limit was exceeded.
+#[derive(Debug)]
+pub(crate) struct MetadataLimitExceeded {
+    pub(crate) limit: MetadataLimit,
+    pub(crate) max: usize,
+}
+
+impl AppError for MetadataLimitExceeded {
+    fn response(&self) -> Response {
+        let json = json!({
+            "errors": [{
+                "detail": self.to_string(),
+                "code": "metadata_limit_exceeded",
+                "limit": self.limit.as_str(),
+                "max": self.max,
+            }]
+        });
+        (StatusCode::OK, Json(json)).into_response()
+    }
+}
+
+impl fmt::Display for MetadataLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let max = self.max;
+        match self.limit {
+            MetadataLimit::Dependencies => {
+                write!(f, "crates may not have more than {max} dependencies")
+            }
+            MetadataLimit::Features => write!(f, "crates may not have more than {max} features"),
+            MetadataLimit::FeatureNameLength => {
+                write!(f, "feature names may not be longer than {max} characters")
+            }
+            MetadataLimit::MetadataSize => write!(f, "max metadata size is: {max}"),
+        }
+    }
+}
+
+/// Returned with status 200 for compatibility with cargo. The `code` field
+/// allows clients to tell this apart from other publish failures.
+#[derive(Debug)]
+pub(crate) struct ReservedCrateName;
+
+impl AppError for ReservedCrateName {
+    fn response(&self) -> Response {
+        let json = json!({
+            "errors": [{
+                "detail": self.to_string(),
+                "code": "reserved_name",
+            }]
+        });
+        (StatusCode::OK, Json(json)).into_response()
+    }
+}
+
+impl fmt::Display for ReservedCrateName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("cannot upload a crate with a reserved name")
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct OwnershipInvitationExpired {
     pub(crate) crate_name: String,
@@ -245,6 +405,68 @@ impl fmt::Display for OwnershipInvitationExpired {
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct MissingAdminPermission {
+    pub(crate) permission: &'static str,
+}
+
+impl AppError for MissingAdminPermission {
+    fn response(&self) -> Response {
+        json_error(&self.to_string(), StatusCode::FORBIDDEN)
+    }
+}
+
+impl fmt::Display for MissingAdminPermission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "this action requires the `{}` admin permission",
+            self.permission
+        )
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct SudoModeRequired;
+
+impl AppError for SudoModeRequired {
+    fn response(&self) -> Response {
+        json_error(&self.to_string(), StatusCode::FORBIDDEN)
+    }
+}
+
+impl fmt::Display for SudoModeRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            "this action requires sudo mode, please confirm your identity with a second factor \
+             or log in again",
+        )
+    }
+}
+
+/// The one-time password authenticator of the user is locked after too many
+/// invalid codes were entered.
+#[derive(Debug)]
+pub(crate) struct TotpLocked {
+    pub until: NaiveDateTime,
+}
+
+impl AppError for TotpLocked {
+    fn response(&self) -> Response {
+        json_error(&self.to_string(), StatusCode::TOO_MANY_REQUESTS)
+    }
+}
+
+impl fmt::Display for TotpLocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let until = self.until.format("%Y-%m-%d at %H:%M:%S UTC");
+        write!(
+            f,
+            "too many invalid one-time passwords were entered, please try again after {until}"
+        )
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct MetricsDisabled;
 
@@ -0,0 +1,141 @@
+//! A HyperLogLog sketch for estimating the number of distinct items
+//!
+//! The sketch only stores one small register per bucket of hashes, so it can
+//! be persisted cheaply, and sketches of different periods can be merged to
+//! estimate the distinct items of their union. See
+//! <http://algo.inria.fr/flajolet/Publications/FlFuGaMe07.pdf>.
+
+/// Number of hash bits used to select a register. With 1024 registers the
+/// standard error of the estimates is about 3.25%.
+const PRECISION: u32 = 10;
+const REGISTERS: usize = 1 << PRECISION;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self {
+            registers: vec![0; REGISTERS],
+        }
+    }
+}
+
+impl HyperLogLog {
+    /// Restores a sketch from the bytes returned by `as_bytes`. Returns
+    /// `None` if the bytes don't belong to a sketch of the same precision.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        (bytes.len() == REGISTERS).then(|| Self {
+            registers: bytes.to_vec(),
+        })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.registers
+    }
+
+    /// Adds an item to the sketch, given by a uniformly distributed hash.
+    pub fn insert_hash(&mut self, hash: u64) {
+        let index = (hash >> (64 - PRECISION)) as usize;
+        // The marker bit limits the rank to the number of remaining bits
+        let remaining = (hash << PRECISION) | (1 << (PRECISION - 1));
+        let rank = remaining.leading_zeros() as u8 + 1;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    /// Merges the items of another sketch into this one.
+    pub fn merge(&mut self, other: &Self) {
+        for (register, other) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    /// Estimates the number of distinct items that were added.
+    pub fn estimate(&self) -> u64 {
+        let m = REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&register| 2f64.powi(-i32::from(register)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+
+        // Small cardinalities are estimated more precisely by linear counting
+        let zeros = self
+            .registers
+            .iter()
+            .filter(|&&register| register == 0)
+            .count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    fn hash(item: u64) -> u64 {
+        let digest = Sha256::digest(item.to_le_bytes());
+        u64::from_le_bytes(digest[..8].try_into().unwrap())
+    }
+
+    fn sketch(items: std::ops::Range<u64>) -> HyperLogLog {
+        let mut sketch = HyperLogLog::default();
+        for item in items {
+            sketch.insert_hash(hash(item));
+        }
+        sketch
+    }
+
+    #[track_caller]
+    fn assert_close(estimate: u64, expected: u64) {
+        let error = (estimate as f64 - expected as f64).abs() / expected as f64;
+        assert!(
+            error < 0.1,
+            "estimate {estimate} is not close to {expected}"
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(HyperLogLog::default().estimate(), 0);
+    }
+
+    #[test]
+    fn duplicates_are_counted_once() {
+        let mut duplicates = sketch(0..10);
+        for item in 0..10 {
+            duplicates.insert_hash(hash(item));
+        }
+        assert_eq!(duplicates, sketch(0..10));
+        assert_close(duplicates.estimate(), 10);
+    }
+
+    #[test]
+    fn estimates() {
+        assert_close(sketch(0..1_000).estimate(), 1_000);
+        assert_close(sketch(0..100_000).estimate(), 100_000);
+    }
+
+    #[test]
+    fn merge() {
+        let mut sketch_a = sketch(0..6_000);
+        sketch_a.merge(&sketch(4_000..10_000));
+        assert_close(sketch_a.estimate(), 10_000);
+    }
+
+    #[test]
+    fn bytes() {
+        let sketch = sketch(0..100);
+        assert_eq!(HyperLogLog::from_bytes(sketch.as_bytes()), Some(sketch));
+        assert_eq!(HyperLogLog::from_bytes(&[0; 16]), None);
+    }
+}
@@ -1,17 +1,45 @@
 //! Convenience functions for serializing and deserializing times in RFC 3339 format.
 //! Used for returning time values in JSON API responses.
-//! Example: `2012-02-22T14:53:18+00:00`.
+//!
+//! All timestamps are serialized in UTC with millisecond precision, so that
+//! clients can rely on a single format across all endpoints.
+//! Example: `2012-02-22T14:53:18.042Z`.
+//!
+//! For compatibility, deserialization accepts any valid RFC 3339 timestamp,
+//! including the previously emitted `2012-02-22T14:53:18.042311+00:00` format,
+//! and converts it to UTC.
+//!
+//! Fields of `#[derive(Serialize)]` structs use `#[serde(with = "rfc3339")]`,
+//! while ad-hoc `json!` payloads wrap their values in [`Timestamp`].
 
-use chrono::{DateTime, NaiveDateTime, Utc};
-use serde::{self, Deserialize, Deserializer, Serializer};
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A timestamp that is serialized in the same format as the fields that use
+/// this module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamp(pub NaiveDateTime);
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Self)
+    }
+}
 
 pub fn serialize<S>(dt: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    let s = DateTime::<Utc>::from_utc(*dt, Utc).to_rfc3339();
+    let s = DateTime::<Utc>::from_utc(*dt, Utc).to_rfc3339_opts(SecondsFormat::Millis, true);
     serializer.serialize_str(&s)
 }
+
 pub fn deserialize<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
 where
     D: Deserializer<'de>,
@@ -46,3 +74,44 @@ pub mod option {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Timestamp;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn serializes_utc_with_millis() {
+        let dt = NaiveDate::from_ymd_opt(2017, 1, 6)
+            .unwrap()
+            .and_hms_micro_opt(14, 23, 11, 42_311)
+            .unwrap();
+        let json = serde_json::to_string(&Timestamp(dt)).unwrap();
+        assert_eq!(json, r#""2017-01-06T14:23:11.042Z""#);
+
+        let dt = NaiveDate::from_ymd_opt(2017, 1, 6)
+            .unwrap()
+            .and_hms_opt(14, 23, 11)
+            .unwrap();
+        let json = serde_json::to_string(&Timestamp(dt)).unwrap();
+        assert_eq!(json, r#""2017-01-06T14:23:11.000Z""#);
+    }
+
+    #[test]
+    fn deserializes_legacy_formats() {
+        let expected = NaiveDate::from_ymd_opt(2017, 1, 6)
+            .unwrap()
+            .and_hms_opt(14, 23, 11)
+            .unwrap();
+
+        for input in [
+            r#""2017-01-06T14:23:11Z""#,
+            r#""2017-01-06T14:23:11.000Z""#,
+            r#""2017-01-06T14:23:11+00:00""#,
+            r#""2017-01-06T16:23:11+02:00""#,
+        ] {
+            let Timestamp(dt) = serde_json::from_str(input).unwrap();
+            assert_eq!(dt, expected, "{input}");
+        }
+    }
+}
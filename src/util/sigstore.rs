@@ -0,0 +1,207 @@
+//! Verification of the Sigstore bundles that are uploaded together with
+//! crate files.
+//!
+//! Only what crates.io can check without the trust root of a Sigstore
+//! instance is verified: that the bundle is for the uploaded crate file, and
+//! that its signature was made with the key of the included signing
+//! certificate. Checking the certificate chain, the identity in the
+//! certificate and the transparency log entry is left to verifiers, who have
+//! to decide which identities they trust anyway.
+//!
+//! Only bundles with a message signature of an ECDSA P-256 key are supported,
+//! which is what `cosign sign-blob` creates.
+
+use ring::digest::{digest, SHA256};
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_ASN1};
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum BundleError {
+    #[error("the bundle could not be parsed")]
+    InvalidBundle,
+    #[error("only bundles with a SHA2_256 message signature are supported")]
+    UnsupportedSignature,
+    #[error("the bundle does not contain a signing certificate")]
+    MissingCertificate,
+    #[error("the signing certificate could not be parsed")]
+    InvalidCertificate,
+    #[error("the bundle is for a different crate file")]
+    DigestMismatch,
+    #[error("the signature does not match the signing certificate")]
+    InvalidSignature,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Bundle {
+    verification_material: VerificationMaterial,
+    message_signature: Option<MessageSignature>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerificationMaterial {
+    /// Bundles up to version 0.2 include the whole certificate chain.
+    x509_certificate_chain: Option<CertificateChain>,
+    /// Newer bundles only include the signing certificate.
+    certificate: Option<Certificate>,
+}
+
+#[derive(Deserialize)]
+struct CertificateChain {
+    certificates: Vec<Certificate>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Certificate {
+    raw_bytes: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageSignature {
+    message_digest: MessageDigest,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct MessageDigest {
+    algorithm: String,
+    digest: String,
+}
+
+/// Checks that the Sigstore `bundle` contains a valid signature of `artifact`.
+pub fn verify_bundle(bundle: &[u8], artifact: &[u8]) -> Result<(), BundleError> {
+    let bundle: Bundle = serde_json::from_slice(bundle).map_err(|_| BundleError::InvalidBundle)?;
+
+    let message_signature = bundle
+        .message_signature
+        .ok_or(BundleError::UnsupportedSignature)?;
+    if message_signature.message_digest.algorithm != "SHA2_256" {
+        return Err(BundleError::UnsupportedSignature);
+    }
+
+    let expected_digest = base64::decode(&message_signature.message_digest.digest)
+        .map_err(|_| BundleError::InvalidBundle)?;
+    if digest(&SHA256, artifact).as_ref() != expected_digest.as_slice() {
+        return Err(BundleError::DigestMismatch);
+    }
+
+    // The signing certificate comes first in the chain
+    let material = bundle.verification_material;
+    let certificate = material
+        .certificate
+        .or_else(|| {
+            material
+                .x509_certificate_chain?
+                .certificates
+                .into_iter()
+                .next()
+        })
+        .ok_or(BundleError::MissingCertificate)?;
+    let certificate =
+        base64::decode(certificate.raw_bytes).map_err(|_| BundleError::InvalidCertificate)?;
+    let public_key = certificate_public_key(&certificate).ok_or(BundleError::InvalidCertificate)?;
+
+    let signature =
+        base64::decode(&message_signature.signature).map_err(|_| BundleError::InvalidBundle)?;
+    UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, public_key)
+        .verify(artifact, &signature)
+        .map_err(|_| BundleError::InvalidSignature)
+}
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_VERSION: u8 = 0xa0;
+
+/// Returns the public key of a DER encoded X.509 certificate, which is
+/// located after the serial number, signature algorithm, issuer, validity
+/// and subject fields of the `TBSCertificate`.
+fn certificate_public_key(certificate: &[u8]) -> Option<&[u8]> {
+    let (certificate, _) = read_der(certificate, TAG_SEQUENCE)?;
+    let (mut tbs_certificate, _) = read_der(certificate, TAG_SEQUENCE)?;
+
+    if tbs_certificate.first() == Some(&TAG_VERSION) {
+        tbs_certificate = read_any_der(tbs_certificate)?.1;
+    }
+    for _ in 0..5 {
+        tbs_certificate = read_any_der(tbs_certificate)?.1;
+    }
+
+    let (public_key_info, _) = read_der(tbs_certificate, TAG_SEQUENCE)?;
+    let (_algorithm, rest) = read_der(public_key_info, TAG_SEQUENCE)?;
+    let (public_key, _) = read_der(rest, TAG_BIT_STRING)?;
+
+    // The first byte of a bit string is the number of unused bits
+    match public_key.split_first()? {
+        (0, public_key) => Some(public_key),
+        _ => None,
+    }
+}
+
+/// Reads a DER element with the `tag`, and returns its contents and the
+/// remaining input.
+fn read_der(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    if *input.first()? != tag {
+        return None;
+    }
+    read_any_der(input)
+}
+
+fn read_any_der(input: &[u8]) -> Option<(&[u8], &[u8])> {
+    let length = *input.get(1)? as usize;
+    let (length, header_length) = match length {
+        0..=0x7f => (length, 2),
+        0x81..=0x84 => {
+            let length_bytes = length - 0x80;
+            let length = input
+                .get(2..2 + length_bytes)?
+                .iter()
+                .fold(0usize, |length, byte| (length << 8) | *byte as usize);
+            (length, 2 + length_bytes)
+        }
+        _ => return None,
+    };
+
+    let end = header_length.checked_add(length)?;
+    let contents = input.get(header_length..end)?;
+    Some((contents, &input[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_bundle, BundleError};
+
+    /// The gzipped empty tarball that the publish tests upload.
+    const ARTIFACT: &[u8] = &[
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xed, 0xc0, 0x01, 0x01, 0x00,
+        0x00, 0x00, 0x82, 0x20, 0xff, 0xaf, 0x6e, 0x48, 0x50, 0xc0, 0xab, 0x01, 0x2e, 0xaf, 0xb5,
+        0xef, 0x00, 0x04, 0x00, 0x00,
+    ];
+
+    const BUNDLE: &str = include_str!("../tests/sigstore-bundle.json");
+
+    #[test]
+    fn verifies_bundles() {
+        assert_eq!(verify_bundle(BUNDLE.as_bytes(), ARTIFACT), Ok(()));
+
+        let other_artifact = b"not the crate file";
+        assert_eq!(
+            verify_bundle(BUNDLE.as_bytes(), other_artifact),
+            Err(BundleError::DigestMismatch)
+        );
+
+        let tampered = BUNDLE.replace("\"signature\":\"MEUCIQDo", "\"signature\":\"MEUCIQDp");
+        assert_eq!(
+            verify_bundle(tampered.as_bytes(), ARTIFACT),
+            Err(BundleError::InvalidSignature)
+        );
+
+        let without_signature =
+            br#"{"mediaType":"application/vnd.dev.sigstore.bundle+json;version=0.1","verificationMaterial":{}}"#;
+        assert_eq!(
+            verify_bundle(without_signature, ARTIFACT),
+            Err(BundleError::UnsupportedSignature)
+        );
+    }
+}
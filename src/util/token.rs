@@ -38,6 +38,11 @@ impl SecureToken {
     pub fn hash(plaintext: &str) -> Vec<u8> {
         Sha256::digest(plaintext.as_bytes()).as_slice().to_vec()
     }
+
+    /// Returns the SHA-256 hash of the plaintext token.
+    pub(crate) fn sha256(&self) -> &[u8] {
+        &self.sha256
+    }
 }
 
 impl std::fmt::Debug for SecureToken {
@@ -122,6 +127,10 @@ secure_token_kind! {
     #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
     pub(crate) enum SecureTokenKind {
         Api => "cio", // Crates.IO
+        WebhookSecret => "cwh", // Crates.io WebHook
+        TotpRecoveryCode => "crc", // Crates.io Recovery Code
+        Session => "cse", // Crates.io Session
+        AccountDeletion => "cad", // Crates.io Account Deletion
     }
 }
 
@@ -172,6 +181,10 @@ mod tests {
         };
 
         ensure(SecureTokenKind::Api, "cio");
+        ensure(SecureTokenKind::WebhookSecret, "cwh");
+        ensure(SecureTokenKind::TotpRecoveryCode, "crc");
+        ensure(SecureTokenKind::Session, "cse");
+        ensure(SecureTokenKind::AccountDeletion, "cad");
 
         assert!(
             remaining.is_empty(),
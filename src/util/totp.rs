@@ -0,0 +1,146 @@
+//! Time-based one-time passwords, as described in RFC 6238.
+//!
+//! Only the parameters that all common authenticator apps support are
+//! implemented: HMAC-SHA1, six digits and a time step of 30 seconds.
+
+use rand::{rngs::OsRng, RngCore};
+use ring::hmac;
+
+const SECRET_LENGTH: usize = 20;
+const DIGITS: u32 = 6;
+const TIME_STEP: u64 = 30;
+
+/// The number of time steps before and after the current one for which
+/// codes are still accepted, to allow for clock drift.
+const ALLOWED_DRIFT: u64 = 1;
+
+pub(crate) fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0; SECRET_LENGTH];
+    OsRng.fill_bytes(&mut secret);
+    secret
+}
+
+/// Returns the `otpauth://` URI that authenticator apps use to enroll the
+/// secret, usually by scanning it as a QR code.
+pub(crate) fn provisioning_uri(secret: &[u8], account: &str) -> String {
+    let account =
+        percent_encoding::utf8_percent_encode(account, percent_encoding::NON_ALPHANUMERIC);
+    let secret = base32_encode(secret);
+    format!("otpauth://totp/crates.io%3A{account}?secret={secret}&issuer=crates.io")
+}
+
+/// Returns the time step of the given UNIX timestamp.
+pub fn time_step(unix_time: u64) -> u64 {
+    unix_time / TIME_STEP
+}
+
+/// Returns the code of the given time step.
+pub fn code(secret: &[u8], step: u64) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret);
+    let tag = hmac::sign(&key, &step.to_be_bytes());
+    let hash = tag.as_ref();
+
+    // Dynamic truncation, see section 5.3 of RFC 4226
+    let offset = (hash[hash.len() - 1] & 0xf) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+/// Checks the code against the time steps around the current one and returns
+/// the step that matched.
+pub(crate) fn verify(secret: &[u8], code: &str, unix_time: u64) -> Option<u64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let current = time_step(unix_time);
+    let first = current.saturating_sub(ALLOWED_DRIFT);
+    (first..=current + ALLOWED_DRIFT).find(|step| {
+        let expected = self::code(secret, *step);
+        ring::constant_time::verify_slices_are_equal(expected.as_bytes(), code.as_bytes()).is_ok()
+    })
+}
+
+/// Encodes the bytes as unpadded base32 (RFC 4648), the encoding that
+/// authenticator apps expect for secrets.
+fn base32_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut encoded = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn rfc_6238_test_vectors() {
+        // The eight digit codes of appendix B, truncated to six digits
+        assert_eq!(code(RFC_SECRET, time_step(59)), "287082");
+        assert_eq!(code(RFC_SECRET, time_step(1111111109)), "081804");
+        assert_eq!(code(RFC_SECRET, time_step(1111111111)), "050471");
+        assert_eq!(code(RFC_SECRET, time_step(1234567890)), "005924");
+        assert_eq!(code(RFC_SECRET, time_step(2000000000)), "279037");
+    }
+
+    #[test]
+    fn verify_allows_clock_drift() {
+        let now = 1111111111;
+        let step = time_step(now);
+        for drift in [step - 1, step, step + 1] {
+            assert_eq!(
+                verify(RFC_SECRET, &code(RFC_SECRET, drift), now),
+                Some(drift)
+            );
+        }
+        assert_eq!(verify(RFC_SECRET, &code(RFC_SECRET, step + 2), now), None);
+        assert_eq!(verify(RFC_SECRET, &code(RFC_SECRET, step - 2), now), None);
+    }
+
+    #[test]
+    fn verify_rejects_malformed_codes() {
+        assert_eq!(verify(RFC_SECRET, "", 59), None);
+        assert_eq!(verify(RFC_SECRET, "28708", 59), None);
+        assert_eq!(verify(RFC_SECRET, "2870821", 59), None);
+        assert_eq!(verify(RFC_SECRET, "28708a", 59), None);
+    }
+
+    #[test]
+    fn base32() {
+        assert_eq!(base32_encode(b""), "");
+        assert_eq!(base32_encode(b"f"), "MY");
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(
+            base32_encode(RFC_SECRET),
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! Validation of, and delivery to, the webhook URLs that users configure for
+//! their crates, saved searches and followed crates.
+//!
+//! The URLs are chosen by users, so the requests must not reach the internal
+//! network of crates.io or the metadata service of the cloud provider. Only
+//! `https` URLs are accepted, their hosts are resolved and checked when they
+//! are configured and again on every delivery, and the request is sent to the
+//! checked addresses without following redirects.
+
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{self, HeaderMap};
+use reqwest::redirect::Policy;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::time::Duration;
+use url::{Host, Url};
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum WebhookUrlError {
+    #[error("webhook URLs must be valid `https` URLs")]
+    InvalidUrl,
+    #[error("webhook URLs must not point to private or local addresses")]
+    InternalAddress,
+    #[error("the host of the webhook URL could not be resolved")]
+    UnresolvableHost,
+}
+
+/// Checks a webhook URL when it is configured.
+///
+/// Hosts that can't be resolved right now are accepted, since they are
+/// checked again on every delivery.
+pub fn validate_url(url: &str) -> Result<Url, WebhookUrlError> {
+    let url = parse(url, false)?;
+    match resolve(&url, false) {
+        Ok(_) | Err(WebhookUrlError::UnresolvableHost) => Ok(url),
+        Err(error) => Err(error),
+    }
+}
+
+/// Sends the payloads of webhooks.
+#[derive(Clone, Debug, Default)]
+pub struct WebhookClient {
+    allow_local: bool,
+}
+
+impl WebhookClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a client that also delivers to `http` URLs and to private and
+    /// local addresses, for tests that receive the payloads on a local server.
+    pub fn allowing_local_urls() -> Self {
+        Self { allow_local: true }
+    }
+
+    /// Posts the JSON `body` with the additional `headers` to the webhook URL.
+    ///
+    /// The host is resolved and checked again, and pinned to the checked
+    /// addresses, so that a DNS record that changed since the URL was
+    /// configured can't redirect the request to an internal address.
+    pub fn post_json(
+        &self,
+        url: &str,
+        body: Vec<u8>,
+        headers: HeaderMap,
+    ) -> anyhow::Result<Response> {
+        let url = parse(url, self.allow_local)?;
+        let addrs = resolve(&url, self.allow_local)?;
+
+        let mut builder = Client::builder()
+            .redirect(Policy::none())
+            .timeout(DELIVERY_TIMEOUT);
+        if let Some(Host::Domain(domain)) = url.host() {
+            builder = builder.resolve_to_addrs(domain, &addrs);
+        }
+
+        let response = builder
+            .build()?
+            .post(url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .headers(headers)
+            .body(body)
+            .send()?;
+
+        Ok(response)
+    }
+}
+
+fn parse(url: &str, allow_http: bool) -> Result<Url, WebhookUrlError> {
+    let url = Url::parse(url).map_err(|_| WebhookUrlError::InvalidUrl)?;
+    let scheme_allowed = url.scheme() == "https" || (allow_http && url.scheme() == "http");
+    if !scheme_allowed || url.host().is_none() {
+        return Err(WebhookUrlError::InvalidUrl);
+    }
+    Ok(url)
+}
+
+fn resolve(url: &Url, allow_internal: bool) -> Result<Vec<SocketAddr>, WebhookUrlError> {
+    let port = url
+        .port_or_known_default()
+        .ok_or(WebhookUrlError::InvalidUrl)?;
+    let addrs: Vec<SocketAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![SocketAddr::new(ip.into(), port)],
+        Some(Host::Ipv6(ip)) => vec![SocketAddr::new(ip.into(), port)],
+        Some(Host::Domain(domain)) => (domain, port)
+            .to_socket_addrs()
+            .map_err(|_| WebhookUrlError::UnresolvableHost)?
+            .collect(),
+        None => return Err(WebhookUrlError::InvalidUrl),
+    };
+
+    if addrs.is_empty() {
+        return Err(WebhookUrlError::UnresolvableHost);
+    }
+    if !allow_internal && addrs.iter().any(|addr| is_internal(addr.ip())) {
+        return Err(WebhookUrlError::InternalAddress);
+    }
+    Ok(addrs)
+}
+
+/// Whether requests to the address could reach the internal network of
+/// crates.io or the metadata service of the cloud provider.
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                // "This network" (RFC 791)
+                || a == 0
+                // Shared address space (RFC 6598)
+                || (a == 100 && (b & 0xc0) == 64)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_internal(IpAddr::V4(ip));
+            }
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local addresses (RFC 4193)
+                || (first & 0xfe00) == 0xfc00
+                // Link-local addresses
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_url, WebhookUrlError};
+
+    #[test]
+    fn rejects_internal_addresses() {
+        for url in [
+            "https://127.0.0.1/hook",
+            "https://10.0.0.1/hook",
+            "https://172.16.5.4/hook",
+            "https://192.168.1.1/hook",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.64.0.1/hook",
+            "https://0.0.0.0/hook",
+            "https://[::1]/hook",
+            "https://[fd00:ec2::254]/hook",
+            "https://[fe80::1]/hook",
+            "https://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert_eq!(
+                validate_url(url),
+                Err(WebhookUrlError::InternalAddress),
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn requires_https() {
+        for url in ["http://example.com/hook", "ftp://example.com", "not a url"] {
+            assert_eq!(validate_url(url), Err(WebhookUrlError::InvalidUrl), "{url}");
+        }
+
+        let url = validate_url("https://93.184.216.34/hook").unwrap();
+        assert_eq!(url.as_str(), "https://93.184.216.34/hook");
+    }
+}
@@ -2,11 +2,16 @@ use chrono::NaiveDateTime;
 use url::Url;
 
 use crate::github;
+use crate::gitlab;
 use crate::models::{
-    Category, Crate, CrateOwnerInvitation, CreatedApiToken, Dependency, DependencyKind, Keyword,
-    Owner, ReverseDependency, Team, TopVersions, User, Version, VersionDownload,
-    VersionOwnerAction,
+    AccountProvider, Category, Crate, CrateOwnerInvitation, CrateTransfer, CreatedApiToken,
+    CreatedWebhook, Dependency, DependencyKind, Email, FollowNotificationFrequency,
+    FollowNotificationSettings, Keyword, LinkedAccount, NotificationPreferences, Organization,
+    OrganizationInvitation, OrganizationRole, Owner, PersistentSession, PublishUpload,
+    ReverseDependency, SavedSearch, SecurityLogEntry, StagedPublish, Team, TopVersions, User,
+    Version, VersionDownload, VersionOwnerAction, VersionSignature, Webhook, WebhookDelivery,
 };
+use crate::util::hyperloglog::HyperLogLog;
 use crate::util::rfc3339;
 
 pub mod krate_publish;
@@ -106,6 +111,39 @@ pub struct EncodableCrateOwnerInvitation {
     pub expires_at: NaiveDateTime,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableCrateTransfer {
+    pub id: i32,
+    pub crate_id: i32,
+    pub crate_name: String,
+    pub from_user_id: i32,
+    pub to_user_id: i32,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(with = "rfc3339::option")]
+    pub accepted_at: Option<NaiveDateTime>,
+    #[serde(with = "rfc3339::option")]
+    pub completes_at: Option<NaiveDateTime>,
+    #[serde(with = "rfc3339::option")]
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+impl EncodableCrateTransfer {
+    pub fn new(transfer: CrateTransfer, crate_name: String) -> Self {
+        Self {
+            id: transfer.id,
+            crate_id: transfer.crate_id,
+            crate_name,
+            from_user_id: transfer.from_user_id,
+            to_user_id: transfer.to_user_id,
+            created_at: transfer.created_at,
+            accepted_at: transfer.accepted_at,
+            completes_at: transfer.completes_at,
+            completed_at: transfer.completed_at,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Copy, Clone)]
 pub struct InvitationResponse {
     pub crate_id: i32,
@@ -158,14 +196,25 @@ pub struct EncodableVersionDownload {
     pub version: i32,
     pub downloads: i32,
     pub date: String,
+    /// Estimated number of unique clients that downloaded the version, which
+    /// is only known for downloads that were counted from the CDN logs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unique_clients: Option<u64>,
 }
 
 impl From<VersionDownload> for EncodableVersionDownload {
     fn from(download: VersionDownload) -> Self {
+        let unique_clients = download
+            .unique_clients
+            .as_deref()
+            .and_then(HyperLogLog::from_bytes)
+            .map(|sketch| sketch.estimate());
+
         Self {
             version: download.version_id,
             downloads: download.downloads,
             date: download.date.to_string(),
+            unique_clients,
         }
     }
 }
@@ -219,6 +268,10 @@ pub struct EncodableCrate {
     pub homepage: Option<String>,
     pub documentation: Option<String>,
     pub repository: Option<String>,
+    pub deprecated: bool,
+    pub deprecation_message: Option<String>,
+    /// The name of the crate that the owners recommend to migrate to.
+    pub deprecation_successor: Option<String>,
     pub links: EncodableCrateLinks,
     pub exact_match: bool,
 }
@@ -244,6 +297,9 @@ impl EncodableCrate {
             homepage,
             documentation,
             repository,
+            deprecated,
+            deprecation_message,
+            deprecation_successor,
             ..
         } = krate;
         let versions_link = match versions {
@@ -299,6 +355,9 @@ impl EncodableCrate {
             exact_match,
             description,
             repository,
+            deprecated,
+            deprecation_message,
+            deprecation_successor,
             links: EncodableCrateLinks {
                 version_downloads: format!("/api/v1/crates/{name}/downloads"),
                 versions: versions_link,
@@ -373,6 +432,20 @@ fn domain_is_subdomain(potential_subdomain: &str, root: &str) -> bool {
     potential_subdomain.ends_with(&root_with_prefix)
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EncodableCrateSuggestion {
+    pub name: String,
+    pub downloads: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EncodableCrateDownloads {
+    pub name: String,
+    pub downloads: i64,
+    /// Downloads in the last 90 days.
+    pub recent_downloads: i64,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EncodableCrateLinks {
     pub version_downloads: String,
@@ -401,14 +474,15 @@ impl From<Owner> for EncodableOwner {
                 name,
                 gh_login,
                 gh_avatar,
+                gh_id,
                 ..
             }) => {
-                let url = format!("https://github.com/{gh_login}");
+                let url = github_profile_url(gh_id, &gh_login);
                 Self {
                     id,
                     login: gh_login,
                     avatar: gh_avatar,
-                    url: Some(url),
+                    url,
                     name,
                     kind: String::from("user"),
                 }
@@ -418,18 +492,27 @@ impl From<Owner> for EncodableOwner {
                 name,
                 login,
                 avatar,
+                provider,
                 ..
             }) => {
-                let url = github::team_url(&login);
+                let url = team_url(&login, provider);
                 Self {
                     id,
                     login,
-                    url: Some(url),
+                    url,
                     avatar,
                     name,
                     kind: String::from("team"),
                 }
             }
+            Owner::Organization(organization) => Self {
+                id: organization.id,
+                login: organization.login(),
+                url: None,
+                avatar: None,
+                name: Some(organization.name),
+                kind: String::from("organization"),
+            },
         }
     }
 }
@@ -443,6 +526,20 @@ pub struct EncodableTeam {
     pub url: Option<String>,
 }
 
+/// Teams of the single sign-on identity provider have no public page.
+fn team_url(login: &str, provider: i16) -> Option<String> {
+    match AccountProvider::from_i16(provider) {
+        Some(AccountProvider::GitLab) => Some(gitlab::team_url(login)),
+        Some(AccountProvider::Sso) => None,
+        _ => Some(github::team_url(login)),
+    }
+}
+
+/// Users that signed up with another identity provider have no GitHub profile.
+fn github_profile_url(gh_id: i32, gh_login: &str) -> Option<String> {
+    (gh_id != User::NO_GITHUB_ID).then(|| format!("https://github.com/{gh_login}"))
+}
+
 impl From<Team> for EncodableTeam {
     fn from(team: Team) -> Self {
         let Team {
@@ -450,16 +547,17 @@ impl From<Team> for EncodableTeam {
             name,
             login,
             avatar,
+            provider,
             ..
         } = team;
-        let url = github::team_url(&login);
+        let url = team_url(&login, provider);
 
         EncodableTeam {
             id,
             login,
             name,
             avatar,
-            url: Some(url),
+            url,
         }
     }
 }
@@ -533,9 +631,10 @@ impl EncodablePrivateUser {
             name,
             gh_login,
             gh_avatar,
+            gh_id,
             ..
         } = user;
-        let url = format!("https://github.com/{gh_login}");
+        let url = github_profile_url(gh_id, &gh_login);
 
         EncodablePrivateUser {
             id,
@@ -545,13 +644,47 @@ impl EncodablePrivateUser {
             avatar: gh_avatar,
             login: gh_login,
             name,
-            url: Some(url),
+            url,
+        }
+    }
+}
+
+/// The serialization format for the `Email` model.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct EncodableEmail {
+    pub id: i32,
+    pub email: String,
+    pub verified: bool,
+    pub verification_sent: bool,
+    pub send_notifications: bool,
+    pub public: bool,
+}
+
+impl From<Email> for EncodableEmail {
+    fn from(email: Email) -> Self {
+        let Email {
+            id,
+            email,
+            verified,
+            token_generated_at,
+            send_notifications,
+            public,
+            ..
+        } = email;
+        EncodableEmail {
+            id,
+            email,
+            verified,
+            verification_sent: verified || token_generated_at.is_some(),
+            send_notifications,
+            public,
         }
     }
 }
 
 /// The serialization format for the `User` model.
-/// Same as private user, except no email field
+/// Same as private user, except the email field is only
+/// included if the user made one of their addresses public
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
 pub struct EncodablePublicUser {
     pub id: i32,
@@ -559,6 +692,8 @@ pub struct EncodablePublicUser {
     pub name: Option<String>,
     pub avatar: Option<String>,
     pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
 }
 
 /// Converts a `User` model into an `EncodablePublicUser` for JSON serialization.
@@ -569,15 +704,17 @@ impl From<User> for EncodablePublicUser {
             name,
             gh_login,
             gh_avatar,
+            gh_id,
             ..
         } = user;
-        let url = format!("https://github.com/{gh_login}");
+        let url = github_profile_url(gh_id, &gh_login);
         EncodablePublicUser {
             id,
             avatar: gh_avatar,
             login: gh_login,
             name,
-            url: Some(url),
+            url,
+            email: None,
         }
     }
 }
@@ -606,6 +743,16 @@ pub struct EncodableVersion {
     pub downloads: i32,
     pub features: serde_json::Value,
     pub yanked: bool,
+    pub yank_reason: Option<String>,
+    pub yank_replacement: Option<String>,
+    /// The outcome of the last documentation build on docs.rs, if reported.
+    pub docs_rs_status: Option<String>,
+    #[serde(with = "rfc3339::option")]
+    pub docs_rs_updated_at: Option<NaiveDateTime>,
+    pub deprecated: bool,
+    pub deprecation_message: Option<String>,
+    /// The minimum supported Rust version from the manifest, if specified.
+    pub rust_version: Option<String>,
     // NOTE: Used by shields.io, altering `license` requires a PR with shields.io
     pub license: Option<String>,
     pub links: EncodableVersionLinks,
@@ -630,6 +777,13 @@ impl EncodableVersion {
             downloads,
             features,
             yanked,
+            yank_reason,
+            yank_replacement,
+            docs_rs_status,
+            docs_rs_updated_at,
+            deprecated,
+            deprecation_message,
+            rust_version,
             license,
             crate_size,
             checksum,
@@ -653,6 +807,13 @@ impl EncodableVersion {
             downloads,
             features,
             yanked,
+            yank_reason,
+            yank_replacement,
+            docs_rs_status,
+            docs_rs_updated_at,
+            deprecated,
+            deprecation_message,
+            rust_version,
             license,
             links,
             crate_size,
@@ -682,6 +843,377 @@ pub struct GoodCrate {
     #[serde(rename = "crate")]
     pub krate: EncodableCrate,
     pub warnings: PublishWarnings,
+    /// The SHA-256 checksum of the uploaded tarball, as computed by the server.
+    pub cksum: String,
+}
+
+/// The serialization format for an unfinished chunked publish upload.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodablePublishUpload {
+    pub id: i32,
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub size: i64,
+    pub offset: i64,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+impl From<PublishUpload> for EncodablePublishUpload {
+    fn from(upload: PublishUpload) -> Self {
+        Self {
+            id: upload.id,
+            krate: upload.crate_name,
+            size: upload.size,
+            offset: upload.received,
+            created_at: upload.created_at,
+        }
+    }
+}
+
+/// The serialization format for a staged publish that has not been promoted yet.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableStagedPublish {
+    pub id: i32,
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub version: String,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+impl From<StagedPublish> for EncodableStagedPublish {
+    fn from(staged: StagedPublish) -> Self {
+        Self {
+            id: staged.id,
+            krate: staged.crate_name,
+            version: staged.version,
+            created_at: staged.created_at,
+        }
+    }
+}
+
+/// The serialization format for the `SavedSearch` model.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableSavedSearch {
+    pub id: i32,
+    pub query: String,
+    pub webhook_url: Option<String>,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+impl From<SavedSearch> for EncodableSavedSearch {
+    fn from(search: SavedSearch) -> Self {
+        Self {
+            id: search.id,
+            query: search.query,
+            webhook_url: search.webhook_url,
+            created_at: search.created_at,
+        }
+    }
+}
+
+/// The serialization format for the `PersistentSession` model.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodablePersistentSession {
+    pub id: i64,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(with = "rfc3339")]
+    pub last_used_at: NaiveDateTime,
+    pub last_ip_address: Option<String>,
+    pub last_user_agent: Option<String>,
+    /// Until when the session can be used for sensitive actions.
+    #[serde(with = "rfc3339::option")]
+    pub sudo_until: Option<NaiveDateTime>,
+    /// Whether this is the session that the request was made with.
+    pub current: bool,
+}
+
+impl EncodablePersistentSession {
+    pub fn new(session: PersistentSession, current_id: Option<i64>) -> Self {
+        Self {
+            id: session.id,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+            last_ip_address: session.last_ip_address,
+            last_user_agent: session.last_user_agent,
+            sudo_until: session.sudo_until,
+            current: current_id == Some(session.id),
+        }
+    }
+}
+
+/// The serialization format for the `SecurityLogEntry` model.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableSecurityLogEntry {
+    pub event: String,
+    pub details: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+impl From<SecurityLogEntry> for EncodableSecurityLogEntry {
+    fn from(entry: SecurityLogEntry) -> Self {
+        Self {
+            event: entry.event.as_str().to_string(),
+            details: entry.details,
+            ip_address: entry.ip_address,
+            user_agent: entry.user_agent,
+            created_at: entry.created_at,
+        }
+    }
+}
+
+/// The serialization format for the `FollowNotificationSettings` model.
+///
+/// Users without settings are shown as never being notified. The webhook
+/// secret is only included right after a new webhook URL was set.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct EncodableFollowNotificationSettings {
+    pub frequency: String,
+    pub webhook_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_secret: Option<String>,
+}
+
+impl From<Option<FollowNotificationSettings>> for EncodableFollowNotificationSettings {
+    fn from(settings: Option<FollowNotificationSettings>) -> Self {
+        let frequency = settings
+            .as_ref()
+            .map_or(FollowNotificationFrequency::Never, |settings| {
+                settings.frequency
+            });
+
+        Self {
+            frequency: frequency.as_str().to_string(),
+            webhook_url: settings.and_then(|settings| settings.webhook_url),
+            webhook_secret: None,
+        }
+    }
+}
+
+/// The serialization format for the `NotificationPreferences` model.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct EncodableNotificationPreferences {
+    pub owner_invites: bool,
+    pub security_alerts: bool,
+    pub digests: bool,
+}
+
+impl From<NotificationPreferences> for EncodableNotificationPreferences {
+    fn from(preferences: NotificationPreferences) -> Self {
+        Self {
+            owner_invites: preferences.owner_invites,
+            security_alerts: preferences.security_alerts,
+            digests: preferences.digests,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableOrganization {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+impl From<Organization> for EncodableOrganization {
+    fn from(organization: Organization) -> Self {
+        Self {
+            id: organization.id,
+            name: organization.name,
+            description: organization.description,
+            created_at: organization.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableOrganizationMember {
+    pub user: EncodablePublicUser,
+    pub role: String,
+}
+
+impl EncodableOrganizationMember {
+    pub fn new(user: User, role: i16) -> Self {
+        Self {
+            user: user.into(),
+            role: role_name(role),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableOrganizationInvitation {
+    pub organization: EncodableOrganization,
+    pub invited_by_user_id: i32,
+    pub role: String,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+impl EncodableOrganizationInvitation {
+    pub fn new(invitation: OrganizationInvitation, organization: Organization) -> Self {
+        Self {
+            organization: organization.into(),
+            invited_by_user_id: invitation.invited_by_user_id,
+            role: role_name(invitation.role),
+            created_at: invitation.created_at,
+        }
+    }
+}
+
+fn role_name(role: i16) -> String {
+    OrganizationRole::from_i16(role)
+        .map(|role| role.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// The serialization format for the accounts that a user can log in with.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableLinkedAccount {
+    pub provider: String,
+    pub account_id: i32,
+    pub login: String,
+    pub avatar: Option<String>,
+    pub url: String,
+    /// Whether this is the GitHub account that the user signed up with, which
+    /// can't be unlinked.
+    pub primary: bool,
+}
+
+impl EncodableLinkedAccount {
+    /// Returns the GitHub account that is stored in the `users` table, if the
+    /// user has one.
+    pub fn primary(user: &User) -> Option<Self> {
+        let url = github_profile_url(user.gh_id, &user.gh_login)?;
+        Some(Self {
+            provider: AccountProvider::GitHub.as_str().to_string(),
+            account_id: user.gh_id,
+            login: user.gh_login.clone(),
+            avatar: user.gh_avatar.clone(),
+            url,
+            primary: true,
+        })
+    }
+}
+
+impl From<LinkedAccount> for EncodableLinkedAccount {
+    fn from(account: LinkedAccount) -> Self {
+        let provider = AccountProvider::from_i16(account.provider);
+        let url = match provider {
+            Some(AccountProvider::GitLab) => format!("{}/{}", gitlab::GITLAB_URL, account.login),
+            _ => format!("https://github.com/{}", account.login),
+        };
+
+        Self {
+            provider: provider.map(|p| p.as_str()).unwrap_or_default().to_string(),
+            account_id: account.account_id,
+            login: account.login,
+            avatar: account.avatar,
+            url,
+            primary: false,
+        }
+    }
+}
+
+/// The serialization format for the `Webhook` model.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableWebhook {
+    pub id: i32,
+    pub url: String,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+impl From<Webhook> for EncodableWebhook {
+    fn from(webhook: Webhook) -> Self {
+        Self {
+            id: webhook.id,
+            url: webhook.url,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+/// The serialization format for a newly created webhook, which includes the
+/// plaintext secret. The secret can't be retrieved again afterwards.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableWebhookWithSecret {
+    pub id: i32,
+    pub url: String,
+    pub secret: String,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+impl From<CreatedWebhook> for EncodableWebhookWithSecret {
+    fn from(webhook: CreatedWebhook) -> Self {
+        Self {
+            id: webhook.model.id,
+            url: webhook.model.url,
+            secret: webhook.plaintext_secret,
+            created_at: webhook.model.created_at,
+        }
+    }
+}
+
+/// The serialization format for the `WebhookDelivery` model.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableWebhookDelivery {
+    pub id: i32,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    #[serde(with = "rfc3339::option")]
+    pub delivered_at: Option<NaiveDateTime>,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+impl From<WebhookDelivery> for EncodableWebhookDelivery {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id,
+            event: delivery.event,
+            payload: delivery.payload,
+            attempts: delivery.attempts,
+            response_status: delivery.response_status,
+            error: delivery.error,
+            delivered_at: delivery.delivered_at,
+            created_at: delivery.created_at,
+        }
+    }
+}
+
+/// The serialization format for the `VersionSignature` model.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncodableVersionSignature {
+    pub kind: String,
+    pub size: i32,
+    pub url: String,
+    #[serde(with = "rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+impl EncodableVersionSignature {
+    pub fn new(signature: VersionSignature, url: String) -> Self {
+        Self {
+            kind: signature.kind,
+            size: signature.size,
+            url,
+            created_at: signature.created_at,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -712,7 +1244,7 @@ mod tests {
         let json = serde_json::to_string(&cat).unwrap();
         assert_some!(json
             .as_str()
-            .find(r#""created_at":"2017-01-06T14:23:11+00:00""#));
+            .find(r#""created_at":"2017-01-06T14:23:11.000Z""#));
     }
 
     #[test]
@@ -733,7 +1265,7 @@ mod tests {
         let json = serde_json::to_string(&cat).unwrap();
         assert_some!(json
             .as_str()
-            .find(r#""created_at":"2017-01-06T14:23:11+00:00""#));
+            .find(r#""created_at":"2017-01-06T14:23:11.000Z""#));
     }
 
     #[test]
@@ -750,7 +1282,7 @@ mod tests {
         let json = serde_json::to_string(&key).unwrap();
         assert_some!(json
             .as_str()
-            .find(r#""created_at":"2017-01-06T14:23:11+00:00""#));
+            .find(r#""created_at":"2017-01-06T14:23:11.000Z""#));
     }
 
     #[test]
@@ -772,6 +1304,13 @@ mod tests {
             downloads: 0,
             features: serde_json::from_str("{}").unwrap(),
             yanked: false,
+            yank_reason: None,
+            yank_replacement: None,
+            docs_rs_status: None,
+            docs_rs_updated_at: None,
+            deprecated: false,
+            deprecation_message: None,
+            rust_version: None,
             license: None,
             links: EncodableVersionLinks {
                 dependencies: "".to_string(),
@@ -789,6 +1328,7 @@ mod tests {
                     name: None,
                     avatar: None,
                     url: None,
+                    email: None,
                 },
                 time: NaiveDate::from_ymd_opt(2017, 1, 6)
                     .unwrap()
@@ -799,11 +1339,11 @@ mod tests {
         let json = serde_json::to_string(&ver).unwrap();
         assert_some!(json
             .as_str()
-            .find(r#""updated_at":"2017-01-06T14:23:11+00:00""#));
+            .find(r#""updated_at":"2017-01-06T14:23:11.000Z""#));
         assert_some!(json
             .as_str()
-            .find(r#""created_at":"2017-01-06T14:23:12+00:00""#));
-        assert_some!(json.as_str().find(r#""time":"2017-01-06T14:23:12+00:00""#));
+            .find(r#""created_at":"2017-01-06T14:23:12.000Z""#));
+        assert_some!(json.as_str().find(r#""time":"2017-01-06T14:23:12.000Z""#));
     }
 
     #[test]
@@ -832,6 +1372,9 @@ mod tests {
             homepage: None,
             documentation: None,
             repository: None,
+            deprecated: false,
+            deprecation_message: None,
+            deprecation_successor: None,
             links: EncodableCrateLinks {
                 version_downloads: "".to_string(),
                 versions: None,
@@ -845,10 +1388,10 @@ mod tests {
         let json = serde_json::to_string(&crt).unwrap();
         assert_some!(json
             .as_str()
-            .find(r#""updated_at":"2017-01-06T14:23:11+00:00""#));
+            .find(r#""updated_at":"2017-01-06T14:23:11.000Z""#));
         assert_some!(json
             .as_str()
-            .find(r#""created_at":"2017-01-06T14:23:12+00:00""#));
+            .find(r#""created_at":"2017-01-06T14:23:12.000Z""#));
     }
 
     #[test]
@@ -871,10 +1414,10 @@ mod tests {
         let json = serde_json::to_string(&inv).unwrap();
         assert_some!(json
             .as_str()
-            .find(r#""created_at":"2017-01-06T14:23:11+00:00""#));
+            .find(r#""created_at":"2017-01-06T14:23:11.000Z""#));
         assert_some!(json
             .as_str()
-            .find(r#""expires_at":"2020-10-24T16:30:00+00:00""#));
+            .find(r#""expires_at":"2020-10-24T16:30:00.000Z""#));
     }
 
     #[test]
@@ -32,6 +32,9 @@ pub struct EncodableCrateUpload {
     pub repository: Option<String>,
     #[serde(default)]
     pub links: Option<String>,
+    /// The SHA-256 hex digest of the tarball, as computed by the client.
+    #[serde(default)]
+    pub cksum: Option<String>,
 }
 
 #[derive(PartialEq, Eq, Hash, Serialize, Debug, Deref)]
@@ -0,0 +1,177 @@
+use crate::background_jobs::{DeleteAccountJob, Environment, Job};
+use crate::models::account_deletion::DELETED_ACCOUNT_LOCK_REASON;
+use crate::models::{AccountDeletion, OwnerKind, User};
+use crate::schema::{
+    account_deletions, admin_role_assignments, api_tokens, crate_owner_invitations, crate_owners,
+    crate_transfers, email_changes, emails, follow_notification_settings, follows, linked_accounts,
+    notification_preferences, organization_invitations, organization_members, persistent_sessions,
+    publish_limit_buckets, publish_rate_overrides, saved_searches, security_log_entries,
+    sso_identities, team_members, totp_credentials, totp_recovery_codes, users, versions,
+    versions_published_by, webauthn_challenges, webauthn_credentials,
+};
+use crate::swirl::PerformError;
+use diesel::dsl::now;
+use diesel::prelude::*;
+
+pub fn delete_account(user_id: i32) -> Job {
+    Job::DeleteAccount(DeleteAccountJob { user_id })
+}
+
+/// Removes the personal data of a user whose account deletion was confirmed,
+/// in a single transaction.
+///
+/// The row of the user is kept, but anonymized, so that the versions they
+/// published, the audit records they appear in and the public database dumps
+/// stay consistent. Their API tokens are revoked, and they are removed as an
+/// owner of all their crates; crates they were the only owner of are left
+/// without owners. Their email addresses, linked accounts, single sign-on
+/// identities, second factors, follows, saved searches, memberships and
+/// pending invitations and transfers are removed. The user is notified by
+/// email once the deletion is complete.
+#[instrument(skip(env, conn))]
+pub fn perform_delete_account(
+    env: &Environment,
+    conn: &mut PgConnection,
+    user_id: i32,
+) -> Result<(), PerformError> {
+    let deletion: AccountDeletion = account_deletions::table.find(user_id).first(conn)?;
+    if deletion.confirmed_at.is_none() || deletion.completed_at.is_some() {
+        info!("Account deletion is not confirmed or already completed, skipping");
+        return Ok(());
+    }
+
+    let user = User::find(conn, user_id)?;
+    let email = user.verified_email(conn)?;
+
+    conn.transaction(|conn| delete_personal_data(conn, user_id))?;
+
+    info!(user = %user.gh_login, "Deleted account");
+
+    if let Some(email) = email {
+        // The account is deleted either way, so a failure to notify the user
+        // must not cause the job to be retried
+        if let Err(error) = env.emails().send_account_deleted(&email, &user.gh_login) {
+            warn!(%error, "Failed to notify user of account deletion");
+        }
+    }
+
+    Ok(())
+}
+
+fn delete_personal_data(conn: &mut PgConnection, user_id: i32) -> QueryResult<()> {
+    diesel::update(api_tokens::table.filter(api_tokens::user_id.eq(user_id)))
+        .set(api_tokens::revoked.eq(true))
+        .execute(conn)?;
+
+    let ownerships = crate_owners::table
+        .filter(crate_owners::owner_id.eq(user_id))
+        .filter(crate_owners::owner_kind.eq(OwnerKind::User as i32));
+    diesel::update(ownerships)
+        .set(crate_owners::deleted.eq(true))
+        .execute(conn)?;
+
+    let pending_transfers = crate_transfers::table
+        .filter(crate_transfers::completed_at.is_null())
+        .filter(crate_transfers::cancelled_at.is_null())
+        .filter(
+            crate_transfers::from_user_id
+                .eq(user_id)
+                .or(crate_transfers::to_user_id.eq(user_id)),
+        );
+    diesel::update(pending_transfers)
+        .set(crate_transfers::cancelled_at.eq(now))
+        .execute(conn)?;
+
+    diesel::delete(
+        crate_owner_invitations::table.filter(
+            crate_owner_invitations::invited_user_id
+                .eq(user_id)
+                .or(crate_owner_invitations::invited_by_user_id.eq(user_id)),
+        ),
+    )
+    .execute(conn)?;
+    diesel::delete(
+        organization_invitations::table.filter(
+            organization_invitations::invited_user_id
+                .eq(user_id)
+                .or(organization_invitations::invited_by_user_id.eq(user_id)),
+        ),
+    )
+    .execute(conn)?;
+
+    diesel::delete(
+        admin_role_assignments::table.filter(admin_role_assignments::user_id.eq(user_id)),
+    )
+    .execute(conn)?;
+    diesel::delete(email_changes::table.filter(email_changes::user_id.eq(user_id)))
+        .execute(conn)?;
+    diesel::delete(emails::table.filter(emails::user_id.eq(user_id))).execute(conn)?;
+    diesel::delete(
+        follow_notification_settings::table
+            .filter(follow_notification_settings::user_id.eq(user_id)),
+    )
+    .execute(conn)?;
+    diesel::delete(follows::table.filter(follows::user_id.eq(user_id))).execute(conn)?;
+    diesel::delete(linked_accounts::table.filter(linked_accounts::user_id.eq(user_id)))
+        .execute(conn)?;
+    diesel::delete(
+        notification_preferences::table.filter(notification_preferences::user_id.eq(user_id)),
+    )
+    .execute(conn)?;
+    diesel::delete(organization_members::table.filter(organization_members::user_id.eq(user_id)))
+        .execute(conn)?;
+    diesel::delete(persistent_sessions::table.filter(persistent_sessions::user_id.eq(user_id)))
+        .execute(conn)?;
+    diesel::delete(publish_limit_buckets::table.filter(publish_limit_buckets::user_id.eq(user_id)))
+        .execute(conn)?;
+    diesel::delete(
+        publish_rate_overrides::table.filter(publish_rate_overrides::user_id.eq(user_id)),
+    )
+    .execute(conn)?;
+    diesel::delete(saved_searches::table.filter(saved_searches::user_id.eq(user_id)))
+        .execute(conn)?;
+    diesel::delete(security_log_entries::table.filter(security_log_entries::user_id.eq(user_id)))
+        .execute(conn)?;
+    diesel::delete(sso_identities::table.filter(sso_identities::user_id.eq(user_id)))
+        .execute(conn)?;
+    diesel::delete(team_members::table.filter(team_members::user_id.eq(user_id))).execute(conn)?;
+    diesel::delete(totp_credentials::table.filter(totp_credentials::user_id.eq(user_id)))
+        .execute(conn)?;
+    diesel::delete(totp_recovery_codes::table.filter(totp_recovery_codes::user_id.eq(user_id)))
+        .execute(conn)?;
+    diesel::delete(webauthn_challenges::table.filter(webauthn_challenges::user_id.eq(user_id)))
+        .execute(conn)?;
+    diesel::delete(webauthn_credentials::table.filter(webauthn_credentials::user_id.eq(user_id)))
+        .execute(conn)?;
+
+    // The versions stay attributed to the anonymized user, but the email
+    // address they were published with is removed
+    let published_versions = versions::table
+        .filter(versions::published_by.eq(user_id))
+        .select(versions::id);
+    diesel::delete(
+        versions_published_by::table
+            .filter(versions_published_by::version_id.eq_any(published_versions)),
+    )
+    .execute(conn)?;
+
+    diesel::update(users::table.find(user_id))
+        .set((
+            users::gh_login.eq(format!("deleted-user-{user_id}")),
+            users::name.eq(None::<String>),
+            users::gh_avatar.eq(None::<String>),
+            users::gh_access_token.eq(""),
+            users::gh_id.eq(-1),
+            users::account_lock_reason.eq(DELETED_ACCOUNT_LOCK_REASON),
+        ))
+        .execute(conn)?;
+
+    diesel::update(account_deletions::table.find(user_id))
+        .set((
+            account_deletions::token.eq(Vec::<u8>::new()),
+            account_deletions::completed_at.eq(now),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
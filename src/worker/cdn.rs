@@ -0,0 +1,112 @@
+//! Invalidation of the files that are cached by the CDNs in front of the
+//! buckets.
+//!
+//! Instead of invalidating files right away, jobs queue the paths of the
+//! changed files in the `cdn_invalidations` table. The
+//! `process_cdn_invalidations` job then invalidates the queued paths on all
+//! configured CDNs in batches, which keeps the number of requests to the CDN
+//! APIs low when many files change at once. If a CDN fails, the job is retried
+//! with the queued paths by the job runner.
+
+use std::collections::BTreeSet;
+use std::panic::RefUnwindSafe;
+use std::sync::Arc;
+
+use diesel::prelude::*;
+use reqwest::blocking::Client;
+
+use crate::background_jobs::{Environment, Job};
+use crate::schema::cdn_invalidations;
+use crate::swirl::errors::EnqueueError;
+use crate::swirl::PerformError;
+use crate::worker::cloudfront::CloudFront;
+use crate::worker::fastly::Fastly;
+
+/// At most this many queued paths are invalidated by a single job run.
+const MAX_PATHS_PER_RUN: i64 = 1000;
+
+pub trait Cdn: Send + Sync + RefUnwindSafe {
+    /// The name of the CDN, for logging.
+    fn name(&self) -> &'static str;
+
+    /// Invalidates the cached copies of the files at `paths`, such as
+    /// `config.json` or `re/ge/regex`.
+    fn invalidate(&self, client: &Client, paths: &[String]) -> anyhow::Result<()>;
+}
+
+/// Returns the CDNs that are configured in the environment, see
+/// [`CloudFront::from_environment()`] and [`Fastly::from_environment()`].
+pub fn cdns_from_environment() -> Vec<Arc<dyn Cdn>> {
+    let mut cdns: Vec<Arc<dyn Cdn>> = Vec::new();
+    if let Some(cloudfront) = CloudFront::from_environment() {
+        cdns.push(Arc::new(cloudfront));
+    }
+    if let Some(fastly) = Fastly::from_environment() {
+        cdns.push(Arc::new(fastly));
+    }
+    cdns
+}
+
+/// Queues the invalidation of `paths` on the CDNs, and enqueues a job to
+/// process the queue.
+pub fn queue_cdn_invalidations(
+    conn: &mut PgConnection,
+    paths: &[String],
+) -> Result<(), EnqueueError> {
+    let rows = paths
+        .iter()
+        .map(|path| cdn_invalidations::path.eq(path.trim_start_matches('/')))
+        .collect::<Vec<_>>();
+    diesel::insert_into(cdn_invalidations::table)
+        .values(&rows)
+        .execute(conn)?;
+
+    process_cdn_invalidations().enqueue(conn)
+}
+
+pub fn process_cdn_invalidations() -> Job {
+    Job::ProcessCdnInvalidations
+}
+
+#[instrument(skip_all)]
+pub fn perform_process_cdn_invalidations(
+    env: &Environment,
+    conn: &mut PgConnection,
+) -> Result<(), PerformError> {
+    // Concurrent job runs skip the rows that are already being processed.
+    let queued: Vec<(i64, String)> = cdn_invalidations::table
+        .select((cdn_invalidations::id, cdn_invalidations::path))
+        .order(cdn_invalidations::id)
+        .limit(MAX_PATHS_PER_RUN)
+        .for_update()
+        .skip_locked()
+        .load(conn)?;
+
+    if queued.is_empty() {
+        return Ok(());
+    }
+
+    let paths = queued
+        .iter()
+        .map(|(_, path)| path.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    for cdn in env.cdns() {
+        info!(cdn = cdn.name(), count = paths.len(), "Invalidating paths");
+        cdn.invalidate(env.http_client(), &paths)?;
+    }
+
+    // If a CDN failed, the job fails before the paths are removed from the
+    // queue, so that they are invalidated again when the job is retried.
+    let ids = queued.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    diesel::delete(cdn_invalidations::table.filter(cdn_invalidations::id.eq_any(ids)))
+        .execute(conn)?;
+
+    if queued.len() as i64 == MAX_PATHS_PER_RUN {
+        process_cdn_invalidations().enqueue(conn)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,707 @@
+//! Counting of the crate downloads that are served directly by the CDN
+//!
+//! The CDN writes its access logs into an S3 bucket. The `process_cdn_logs`
+//! job reads the log files that weren't processed yet, extracts the successful
+//! crate downloads and adds them to the `version_downloads` table, from where
+//! `update_downloads` adds them to the download counts of versions and crates.
+//!
+//! Two log formats are supported, which can also be mixed within a bucket:
+//!
+//! - The tab-separated standard log format of CloudFront.
+//! - JSON lines with the `timestamp`, `request_id`, `method`, `url` and
+//!   `status` fields, and optionally `client_ip` and `user_agent`, as
+//!   configured for the Fastly log streaming.
+//!
+//! Every log file is only counted once, which is recorded in the
+//! `processed_cdn_log_files` table. The CDNs don't deliver the log files in
+//! the order of their names, so every listed file is checked against that
+//! table, and the listing goes back to the files processed
+//! `LATE_LOG_FILE_DAYS` ago to pick up files that arrived late. Requests that appear more than once in the
+//! processed log files of a job run are only counted once as well, since the
+//! CDNs deliver logs at least once.
+//!
+//! If `CDN_LOG_COUNT_UNIQUE_CLIENTS` is set, the job also estimates how many
+//! unique clients downloaded each version per day, since the raw counts are
+//! easily inflated by CI jobs downloading the same crates over and over. A
+//! client is identified by a hash of its IP address and user agent, which is
+//! only kept as part of a HyperLogLog sketch in the
+//! `version_downloads.unique_clients` column.
+//!
+//! The downloads by cargo are also counted per minor version of cargo in the
+//! `version_cargo_downloads` table, based on the user agents in the logs.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+use anyhow::Context;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use flate2::read::GzDecoder;
+use percent_encoding::percent_decode_str;
+use reqwest::blocking::Client;
+use sha2::{Digest, Sha256};
+
+use crate::background_jobs::{Environment, Job};
+use crate::schema::{
+    crates, processed_cdn_log_files, version_cargo_downloads, version_downloads, versions,
+};
+use crate::swirl::PerformError;
+use crate::util::hyperloglog::HyperLogLog;
+
+/// At most this many log files are processed by a single job run, so that the
+/// transaction of the job doesn't grow too large.
+const MAX_FILES_PER_RUN: u32 = 100;
+
+/// Log files are picked up if they arrive up to this many days later than
+/// the files that were written after them.
+const LATE_LOG_FILE_DAYS: i64 = 2;
+
+/// The bucket containing the access logs of the CDN.
+#[derive(Clone)]
+pub struct CdnLogBucket {
+    bucket: s3::Bucket,
+    prefix: String,
+    count_unique_clients: bool,
+}
+
+impl CdnLogBucket {
+    /// Configures the bucket from the `CDN_LOG_BUCKET`, `CDN_LOG_REGION`,
+    /// `CDN_LOG_PREFIX` and `CDN_LOG_COUNT_UNIQUE_CLIENTS` environment
+    /// variables. Returns `None` if no bucket is configured.
+    pub fn from_environment() -> Option<Self> {
+        let name = dotenv::var("CDN_LOG_BUCKET").ok()?;
+        let access_key = dotenv::var("AWS_ACCESS_KEY").expect("missing AWS_ACCESS_KEY");
+        let secret_key = dotenv::var("AWS_SECRET_KEY").expect("missing AWS_SECRET_KEY");
+        let region = dotenv::var("CDN_LOG_REGION").ok();
+        let bucket = s3::Bucket::new(name, region, access_key, secret_key, "https");
+        let prefix = dotenv::var("CDN_LOG_PREFIX").unwrap_or_default();
+        let count_unique_clients = dotenv::var("CDN_LOG_COUNT_UNIQUE_CLIENTS").is_ok();
+        Some(Self {
+            bucket,
+            prefix,
+            count_unique_clients,
+        })
+    }
+
+    /// Returns the paths of the log files after `start_after`, in the order
+    /// of their names.
+    fn list(&self, client: &Client, start_after: Option<&str>) -> anyhow::Result<Vec<String>> {
+        let response = self
+            .bucket
+            .list(client, &self.prefix, start_after, MAX_FILES_PER_RUN)
+            .context("Failed to list the CDN log files")?;
+        Ok(parse_keys(&response.text()?))
+    }
+
+    /// Downloads a log file, decompressing it if necessary.
+    fn read(&self, client: &Client, path: &str) -> anyhow::Result<String> {
+        let response = self
+            .bucket
+            .get(client, path)
+            .with_context(|| format!("Failed to download the CDN log file {path}"))?;
+        let bytes = response.bytes()?;
+
+        if path.ends_with(".gz") {
+            let mut content = String::new();
+            GzDecoder::new(&*bytes).read_to_string(&mut content)?;
+            Ok(content)
+        } else {
+            Ok(String::from_utf8(bytes.to_vec())?)
+        }
+    }
+}
+
+pub fn process_cdn_logs() -> Job {
+    Job::ProcessCdnLogs
+}
+
+#[instrument(skip_all)]
+pub fn perform_process_cdn_logs(
+    env: &Environment,
+    conn: &mut PgConnection,
+) -> Result<(), PerformError> {
+    let Some(cdn_logs) = env.cdn_logs() else {
+        warn!("No CDN log bucket is configured");
+        return Ok(());
+    };
+
+    let paths = unprocessed_paths(conn, |start_after| {
+        cdn_logs.list(env.http_client(), start_after)
+    })?;
+    info!(files = paths.len(), "Processing CDN log files");
+
+    let mut request_ids = HashSet::new();
+    for path in paths {
+        let content = cdn_logs.read(env.http_client(), &path)?;
+
+        let mut downloads = parse_log(&content)
+            .filter(|download| request_ids.insert(download.request_id.clone()))
+            .collect::<Vec<_>>();
+        if !cdn_logs.count_unique_clients {
+            downloads
+                .iter_mut()
+                .for_each(|download| download.client = None);
+        }
+
+        let counted = save_downloads(conn, &downloads)?;
+
+        let inserted = diesel::insert_into(processed_cdn_log_files::table)
+            .values((
+                processed_cdn_log_files::path.eq(&path),
+                processed_cdn_log_files::downloads.eq(counted),
+            ))
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        if inserted == 0 {
+            return Err(format!("CDN log file {path} was already processed").into());
+        }
+
+        info!(%path, downloads = counted, "Processed CDN log file");
+    }
+
+    Ok(())
+}
+
+/// Returns up to `MAX_FILES_PER_RUN` paths of log files that weren't
+/// processed yet, using `list` to list the paths after a path.
+///
+/// The log files are named after the time they were written, so only the
+/// files after the last one that was processed more than
+/// `LATE_LOG_FILE_DAYS` ago are listed.
+fn unprocessed_paths(
+    conn: &mut PgConnection,
+    mut list: impl FnMut(Option<&str>) -> anyhow::Result<Vec<String>>,
+) -> Result<Vec<String>, PerformError> {
+    let cutoff = (Utc::now() - Duration::days(LATE_LOG_FILE_DAYS)).naive_utc();
+    let mut start_after: Option<String> = processed_cdn_log_files::table
+        .filter(processed_cdn_log_files::processed_at.lt(cutoff))
+        .select(diesel::dsl::max(processed_cdn_log_files::path))
+        .first(conn)?;
+
+    let mut paths = Vec::new();
+    loop {
+        let listed = list(start_after.as_deref())?;
+        let Some(last) = listed.last().cloned() else {
+            break;
+        };
+
+        let processed: HashSet<String> = processed_cdn_log_files::table
+            .filter(processed_cdn_log_files::path.eq_any(&listed))
+            .select(processed_cdn_log_files::path)
+            .load::<String>(conn)?
+            .into_iter()
+            .collect();
+
+        let is_last_page = listed.len() < MAX_FILES_PER_RUN as usize;
+        paths.extend(listed.into_iter().filter(|path| !processed.contains(path)));
+        if is_last_page || paths.len() >= MAX_FILES_PER_RUN as usize {
+            break;
+        }
+        start_after = Some(last);
+    }
+
+    paths.truncate(MAX_FILES_PER_RUN as usize);
+    Ok(paths)
+}
+
+/// Adds the downloads to the `version_downloads` table and returns how many
+/// of them belong to existing versions.
+///
+/// The clients of the downloads are added to the `unique_clients` sketches
+/// of the rows, and the downloads by cargo to `version_cargo_downloads`.
+fn save_downloads(conn: &mut PgConnection, downloads: &[LogDownload]) -> QueryResult<i32> {
+    let mut crate_names = downloads
+        .iter()
+        .map(|download| download.crate_name.as_str())
+        .collect::<Vec<_>>();
+    crate_names.sort_unstable();
+    crate_names.dedup();
+
+    let version_ids: HashMap<(String, String), i32> = versions::table
+        .inner_join(crates::table)
+        .filter(crates::name.eq_any(crate_names))
+        .select((crates::name, versions::num, versions::id))
+        .load::<(String, String, i32)>(conn)?
+        .into_iter()
+        .map(|(crate_name, num, id)| ((crate_name, num), id))
+        .collect();
+
+    let mut counts: HashMap<(i32, NaiveDate), i32> = HashMap::new();
+    let mut sketches: HashMap<(i32, NaiveDate), HyperLogLog> = HashMap::new();
+    let mut cargo_counts: HashMap<(i32, NaiveDate, &str), i32> = HashMap::new();
+    for download in downloads {
+        let key = (download.crate_name.clone(), download.version.clone());
+        // Downloads of deleted crates and versions are dropped
+        if let Some(&version_id) = version_ids.get(&key) {
+            *counts.entry((version_id, download.date)).or_default() += 1;
+            if let Some(cargo_version) = download.cargo_version.as_deref() {
+                *cargo_counts
+                    .entry((version_id, download.date, cargo_version))
+                    .or_default() += 1;
+            }
+            if let Some(client) = download.client {
+                sketches
+                    .entry((version_id, download.date))
+                    .or_default()
+                    .insert_hash(client);
+            }
+        }
+    }
+
+    if !sketches.is_empty() {
+        merge_existing_sketches(conn, &mut sketches)?;
+    }
+
+    let (with_sketch, without_sketch): (Vec<_>, Vec<_>) = counts
+        .iter()
+        .partition(|(key, _)| sketches.contains_key(*key));
+
+    // Logs can arrive after the downloads of their day were processed
+    // already, so the rows are marked for `update_downloads` again
+    if !without_sketch.is_empty() {
+        let values = without_sketch
+            .into_iter()
+            .map(|(&(version_id, date), &downloads)| {
+                (
+                    version_downloads::version_id.eq(version_id),
+                    version_downloads::date.eq(date),
+                    version_downloads::downloads.eq(downloads),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        diesel::insert_into(version_downloads::table)
+            .values(&values)
+            .on_conflict((version_downloads::version_id, version_downloads::date))
+            .do_update()
+            .set((
+                version_downloads::downloads
+                    .eq(version_downloads::downloads + excluded(version_downloads::downloads)),
+                version_downloads::processed.eq(false),
+            ))
+            .execute(conn)?;
+    }
+
+    if !with_sketch.is_empty() {
+        let values = with_sketch
+            .into_iter()
+            .map(|(&(version_id, date), &downloads)| {
+                (
+                    version_downloads::version_id.eq(version_id),
+                    version_downloads::date.eq(date),
+                    version_downloads::downloads.eq(downloads),
+                    version_downloads::unique_clients
+                        .eq(sketches[&(version_id, date)].as_bytes().to_vec()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        diesel::insert_into(version_downloads::table)
+            .values(&values)
+            .on_conflict((version_downloads::version_id, version_downloads::date))
+            .do_update()
+            .set((
+                version_downloads::downloads
+                    .eq(version_downloads::downloads + excluded(version_downloads::downloads)),
+                version_downloads::unique_clients.eq(excluded(version_downloads::unique_clients)),
+                version_downloads::processed.eq(false),
+            ))
+            .execute(conn)?;
+    }
+
+    if !cargo_counts.is_empty() {
+        let values = cargo_counts
+            .into_iter()
+            .map(|((version_id, date, cargo_version), downloads)| {
+                (
+                    version_cargo_downloads::version_id.eq(version_id),
+                    version_cargo_downloads::date.eq(date),
+                    version_cargo_downloads::cargo_version.eq(cargo_version),
+                    version_cargo_downloads::downloads.eq(downloads),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        diesel::insert_into(version_cargo_downloads::table)
+            .values(&values)
+            .on_conflict((
+                version_cargo_downloads::version_id,
+                version_cargo_downloads::date,
+                version_cargo_downloads::cargo_version,
+            ))
+            .do_update()
+            .set(
+                version_cargo_downloads::downloads.eq(version_cargo_downloads::downloads
+                    + excluded(version_cargo_downloads::downloads)),
+            )
+            .execute(conn)?;
+    }
+
+    Ok(counts.values().sum())
+}
+
+/// Merges the sketches that are already stored for the same rows into
+/// `sketches`, so that the stored sketches can be replaced by them.
+fn merge_existing_sketches(
+    conn: &mut PgConnection,
+    sketches: &mut HashMap<(i32, NaiveDate), HyperLogLog>,
+) -> QueryResult<()> {
+    let mut version_ids = sketches.keys().map(|&(id, _)| id).collect::<Vec<_>>();
+    version_ids.sort_unstable();
+    version_ids.dedup();
+    let mut dates = sketches.keys().map(|&(_, date)| date).collect::<Vec<_>>();
+    dates.sort_unstable();
+    dates.dedup();
+
+    let existing: Vec<(i32, NaiveDate, Vec<u8>)> = version_downloads::table
+        .filter(version_downloads::version_id.eq_any(version_ids))
+        .filter(version_downloads::date.eq_any(dates))
+        .filter(version_downloads::unique_clients.is_not_null())
+        .select((
+            version_downloads::version_id,
+            version_downloads::date,
+            version_downloads::unique_clients.assume_not_null(),
+        ))
+        .for_update()
+        .load(conn)?;
+
+    for (version_id, date, bytes) in existing {
+        if let Some(sketch) = sketches.get_mut(&(version_id, date)) {
+            match HyperLogLog::from_bytes(&bytes) {
+                Some(existing) => sketch.merge(&existing),
+                None => warn!(version_id, %date, "Replacing invalid unique clients sketch"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A successful crate download from a CDN access log.
+#[derive(Debug, PartialEq, Eq)]
+struct LogDownload {
+    request_id: String,
+    date: NaiveDate,
+    crate_name: String,
+    version: String,
+    /// Hash identifying the client, if the log contains its IP address.
+    client: Option<u64>,
+    /// Major and minor version of cargo, if it made the request.
+    cargo_version: Option<String>,
+}
+
+/// Extracts the major and minor version from a cargo user agent, like
+/// `cargo 1.68.0 (115f34552 2023-02-26)`.
+fn parse_cargo_version(user_agent: &str) -> Option<String> {
+    let version = user_agent.strip_prefix("cargo ")?.split(' ').next()?;
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse::<u32>().ok()?;
+    let minor = parts.next()?.parse::<u32>().ok()?;
+    Some(format!("{major}.{minor}"))
+}
+
+/// Hashes the IP address and user agent of a client into an identifier that
+/// is only used to count unique clients.
+fn client_hash(ip: &str, user_agent: &str) -> Option<u64> {
+    if ip.is_empty() || ip == "-" {
+        return None;
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(ip);
+    hasher.update([0]);
+    hasher.update(user_agent);
+    let digest = hasher.finalize();
+    Some(u64::from_le_bytes(digest[..8].try_into().unwrap()))
+}
+
+/// Parses the crate downloads from a log file, skipping all other requests
+/// and lines that can't be parsed.
+fn parse_log(content: &str) -> impl Iterator<Item = LogDownload> + '_ {
+    content.lines().filter_map(|line| {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            None
+        } else if line.starts_with('{') {
+            parse_fastly_line(line)
+        } else {
+            parse_cloudfront_line(line)
+        }
+    })
+}
+
+/// Parses a line of the CloudFront standard log format.
+///
+/// See <https://docs.aws.amazon.com/AmazonCloudFront/latest/DeveloperGuide/AccessLogs.html#LogFileFormat>.
+fn parse_cloudfront_line(line: &str) -> Option<LogDownload> {
+    let fields = line.split('\t').collect::<Vec<_>>();
+    let (date, method, path, status) = (
+        fields.first()?,
+        fields.get(5)?,
+        fields.get(7)?,
+        fields.get(8)?,
+    );
+    let (ip, user_agent) = (fields.get(4)?, fields.get(10)?);
+    let request_id = fields.get(14)?;
+
+    if *method != "GET" || *status != "200" {
+        return None;
+    }
+
+    let date = NaiveDate::parse_from_str(date, "%F").ok()?;
+    let (crate_name, version) = parse_download_path(path)?;
+    Some(LogDownload {
+        request_id: request_id.to_string(),
+        date,
+        crate_name,
+        version,
+        client: client_hash(ip, user_agent),
+        // CloudFront URL-encodes the user agent
+        cargo_version: percent_decode_str(user_agent)
+            .decode_utf8()
+            .ok()
+            .and_then(|user_agent| parse_cargo_version(&user_agent)),
+    })
+}
+
+/// Parses a JSON line of the Fastly log streaming.
+fn parse_fastly_line(line: &str) -> Option<LogDownload> {
+    #[derive(Deserialize)]
+    struct FastlyLogLine {
+        timestamp: DateTime<Utc>,
+        request_id: String,
+        method: String,
+        url: String,
+        status: u16,
+        #[serde(default)]
+        client_ip: String,
+        #[serde(default)]
+        user_agent: String,
+    }
+
+    let line: FastlyLogLine = serde_json::from_str(line).ok()?;
+    if line.method != "GET" || line.status != 200 {
+        return None;
+    }
+
+    let path = line.url.split('?').next()?;
+    let (crate_name, version) = parse_download_path(path)?;
+    Some(LogDownload {
+        request_id: line.request_id,
+        date: line.timestamp.date_naive(),
+        crate_name,
+        version,
+        client: client_hash(&line.client_ip, &line.user_agent),
+        cargo_version: parse_cargo_version(&line.user_agent),
+    })
+}
+
+/// Extracts the crate name and version from the path of a crate file, like
+/// `/crates/serde/serde-1.0.0.crate`.
+fn parse_download_path(path: &str) -> Option<(String, String)> {
+    let path = percent_decode_str(path).decode_utf8().ok()?;
+    let (crate_name, file_name) = path.strip_prefix("/crates/")?.split_once('/')?;
+    let version = file_name
+        .strip_suffix(".crate")?
+        .strip_prefix(crate_name)?
+        .strip_prefix('-')?;
+
+    if crate_name.is_empty() || version.is_empty() {
+        return None;
+    }
+    Some((crate_name.to_string(), version.to_string()))
+}
+
+/// Extracts the object keys from a `ListObjectsV2` response.
+fn parse_keys(xml: &str) -> Vec<String> {
+    xml.split("<Key>")
+        .skip(1)
+        .filter_map(|element| element.split_once("</Key>"))
+        .map(|(key, _)| {
+            key.replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::pg_connection;
+
+    fn download(
+        request_id: &str,
+        date: &str,
+        crate_name: &str,
+        version: &str,
+        client: Option<u64>,
+        cargo_version: Option<&str>,
+    ) -> LogDownload {
+        LogDownload {
+            request_id: request_id.into(),
+            date: NaiveDate::parse_from_str(date, "%F").unwrap(),
+            crate_name: crate_name.into(),
+            version: version.into(),
+            client,
+            cargo_version: cargo_version.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn download_paths() {
+        assert_eq!(
+            parse_download_path("/crates/serde/serde-1.0.0.crate"),
+            Some(("serde".into(), "1.0.0".into()))
+        );
+        assert_eq!(
+            parse_download_path("/crates/foo-bar/foo-bar-0.1.0-beta.1%2Bbuild.crate"),
+            Some(("foo-bar".into(), "0.1.0-beta.1+build".into()))
+        );
+        assert_eq!(
+            parse_download_path("/crates/serde/serde-1.0.0.tar.gz"),
+            None
+        );
+        assert_eq!(parse_download_path("/crates/serde/other-1.0.0.crate"), None);
+        assert_eq!(parse_download_path("/readmes/serde/serde-1.0.0.html"), None);
+        assert_eq!(parse_download_path("/crates/serde/serde-.crate"), None);
+    }
+
+    #[test]
+    fn cloudfront_log() {
+        let log = "\
+#Version: 1.0
+#Fields: date time x-edge-location sc-bytes c-ip cs-method cs(Host) cs-uri-stem sc-status cs(Referer) cs(User-Agent) cs-uri-query cs(Cookie) x-edge-result-type x-edge-request-id
+2023-03-12\t10:00:00\tFRA56-C1\t1024\t192.0.2.1\tGET\tstatic.crates.io\t/crates/serde/serde-1.0.0.crate\t200\t-\tcargo%201.68.0%20(115f34552%202023-02-26)\t-\t-\tHit\tabc==
+2023-03-12\t10:00:01\tFRA56-C1\t1024\t192.0.2.1\tGET\tstatic.crates.io\t/crates/serde/serde-2.0.0.crate\t403\t-\tcargo\t-\t-\tError\tdef==
+2023-03-12\t10:00:02\tFRA56-C1\t1024\t192.0.2.1\tHEAD\tstatic.crates.io\t/crates/serde/serde-1.0.0.crate\t200\t-\tcargo\t-\t-\tHit\tghi==
+2023-03-12\t10:00:03\tFRA56-C1\t1024\t192.0.2.1\tGET\tstatic.crates.io\t/readmes/serde/serde-1.0.0.html\t200\t-\tcurl\t-\t-\tHit\tjkl==
+";
+        assert_eq!(
+            parse_log(log).collect::<Vec<_>>(),
+            vec![download(
+                "abc==",
+                "2023-03-12",
+                "serde",
+                "1.0.0",
+                client_hash("192.0.2.1", "cargo%201.68.0%20(115f34552%202023-02-26)"),
+                Some("1.68")
+            )]
+        );
+    }
+
+    #[test]
+    fn fastly_log() {
+        let log = r#"
+{"timestamp":"2023-03-12T23:59:59Z","request_id":"1","method":"GET","url":"/crates/rand/rand-0.8.5.crate?x=1","status":200}
+{"timestamp":"2023-03-13T00:00:00Z","request_id":"2","method":"GET","url":"/crates/rand/rand-0.8.5.crate","status":404}
+{"timestamp":"2023-03-13T00:00:01Z","request_id":"3","method":"GET","url":"/crates/rand/rand-0.8.5.crate","status":200,"client_ip":"192.0.2.1","user_agent":"cargo 1.68.0"}
+not json
+"#;
+        assert_eq!(
+            parse_log(log).collect::<Vec<_>>(),
+            vec![
+                download("1", "2023-03-12", "rand", "0.8.5", None, None),
+                download(
+                    "3",
+                    "2023-03-13",
+                    "rand",
+                    "0.8.5",
+                    client_hash("192.0.2.1", "cargo 1.68.0"),
+                    Some("1.68")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn cargo_versions() {
+        assert_eq!(
+            parse_cargo_version("cargo 1.68.0 (115f34552 2023-02-26)"),
+            Some("1.68".into())
+        );
+        assert_eq!(
+            parse_cargo_version("cargo 1.70.0-nightly (7bf43f028 2023-03-10)"),
+            Some("1.70".into())
+        );
+        assert_eq!(parse_cargo_version("cargo 1"), None);
+        assert_eq!(parse_cargo_version("curl/7.88.1"), None);
+        assert_eq!(parse_cargo_version(""), None);
+    }
+
+    #[test]
+    fn client_hashes() {
+        assert_eq!(client_hash("", "cargo"), None);
+        assert_eq!(client_hash("-", "cargo"), None);
+        assert_eq!(
+            client_hash("192.0.2.1", "cargo"),
+            client_hash("192.0.2.1", "cargo")
+        );
+        assert_ne!(
+            client_hash("192.0.2.1", "cargo"),
+            client_hash("192.0.2.1", "curl")
+        );
+        assert_ne!(
+            client_hash("192.0.2.1", "cargo"),
+            client_hash("192.0.2.12", "argo")
+        );
+    }
+
+    #[test]
+    fn late_log_files_are_processed() {
+        let mut conn = pg_connection();
+
+        let old = (Utc::now() - Duration::days(LATE_LOG_FILE_DAYS + 1)).naive_utc();
+        diesel::insert_into(processed_cdn_log_files::table)
+            .values(&vec![
+                (
+                    processed_cdn_log_files::path.eq("cf/2023-03-10-10.gz"),
+                    processed_cdn_log_files::downloads.eq(1),
+                    processed_cdn_log_files::processed_at.eq(old),
+                ),
+                (
+                    processed_cdn_log_files::path.eq("cf/2023-03-12-12.gz"),
+                    processed_cdn_log_files::downloads.eq(1),
+                    processed_cdn_log_files::processed_at.eq(Utc::now().naive_utc()),
+                ),
+            ])
+            .execute(&mut conn)
+            .unwrap();
+
+        // `cf/2023-03-12-11.gz` arrived after `cf/2023-03-12-12.gz` was
+        // processed already
+        let bucket = [
+            "cf/2023-03-10-09.gz",
+            "cf/2023-03-10-10.gz",
+            "cf/2023-03-12-11.gz",
+            "cf/2023-03-12-12.gz",
+            "cf/2023-03-12-13.gz",
+        ];
+        let mut listed_after = Vec::new();
+        let paths = unprocessed_paths(&mut conn, |start_after| {
+            listed_after.push(start_after.map(String::from));
+            Ok(bucket
+                .iter()
+                .filter(|path| start_after.is_none_or(|start_after| **path > start_after))
+                .map(|path| path.to_string())
+                .collect())
+        })
+        .unwrap();
+
+        assert_eq!(paths, vec!["cf/2023-03-12-11.gz", "cf/2023-03-12-13.gz"]);
+        assert_eq!(listed_after, vec![Some("cf/2023-03-10-10.gz".to_string())]);
+    }
+
+    #[test]
+    fn list_keys() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult><Name>logs</Name><Contents><Key>cf/E1.2023-03-12-10.a.gz</Key><Size>1</Size></Contents><Contents><Key>cf/E1.2023-03-12-11.a&amp;b.gz</Key><Size>1</Size></Contents><IsTruncated>false</IsTruncated></ListBucketResult>"#;
+        assert_eq!(
+            parse_keys(xml),
+            vec!["cf/E1.2023-03-12-10.a.gz", "cf/E1.2023-03-12-11.a&b.gz"]
+        );
+    }
+}
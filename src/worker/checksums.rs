@@ -0,0 +1,60 @@
+use crate::background_jobs::{BackfillChecksumsJob, Environment, Job};
+use crate::schema::{crates, versions};
+use crate::swirl::PerformError;
+use diesel::prelude::*;
+use hex::ToHex;
+use sha2::{Digest, Sha256};
+
+/// The number of versions that are processed by a single job.
+const BATCH_SIZE: i64 = 100;
+
+/// Computes the missing checksums of versions by downloading their crate
+/// files from storage.
+///
+/// Versions are processed in batches, ordered by ID. If the batch was full,
+/// another job is enqueued for the versions after the last processed one.
+/// Versions whose crate file can't be downloaded are logged and skipped.
+#[instrument(skip(env, conn))]
+pub fn perform_backfill_checksums(
+    env: &Environment,
+    conn: &mut PgConnection,
+    after_id: i32,
+) -> Result<(), PerformError> {
+    let missing: Vec<(i32, String, String)> = versions::table
+        .inner_join(crates::table)
+        .filter(versions::id.gt(after_id))
+        .filter(versions::checksum.eq(""))
+        .select((versions::id, crates::name, versions::num))
+        .order(versions::id)
+        .limit(BATCH_SIZE)
+        .load(conn)?;
+
+    info!(count = missing.len(), "Backfilling version checksums");
+
+    for (id, name, num) in &missing {
+        let bytes = match env.uploader.download_crate(env.http_client(), name, num) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                warn!(%name, %num, ?error, "Failed to download crate file");
+                continue;
+            }
+        };
+
+        let checksum: String = Sha256::digest(&bytes).encode_hex();
+        diesel::update(versions::table.find(id))
+            .set(versions::checksum.eq(checksum))
+            .execute(conn)?;
+    }
+
+    if missing.len() as i64 == BATCH_SIZE {
+        if let Some((last_id, _, _)) = missing.last() {
+            backfill_checksums(*last_id).enqueue(conn)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn backfill_checksums(after_id: i32) -> Job {
+    Job::BackfillChecksums(BackfillChecksumsJob { after_id })
+}
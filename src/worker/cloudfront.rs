@@ -9,6 +9,8 @@ use reqwest::blocking::Client;
 use retry::delay::{jitter, Exponential};
 use retry::OperationResult;
 
+use crate::worker::cdn::Cdn;
+
 #[derive(Clone)]
 pub struct CloudFront {
     distribution_id: String,
@@ -17,6 +19,9 @@ pub struct CloudFront {
 }
 
 impl CloudFront {
+    /// Configures the distribution from the `CLOUDFRONT_DISTRIBUTION`,
+    /// `AWS_ACCESS_KEY` and `AWS_SECRET_KEY` environment variables. Returns
+    /// `None` if no distribution is configured.
     pub fn from_environment() -> Option<Self> {
         let distribution_id = dotenv::var("CLOUDFRONT_DISTRIBUTION").ok()?;
         let access_key = dotenv::var("AWS_ACCESS_KEY").expect("missing AWS_ACCESS_KEY");
@@ -28,12 +33,17 @@ impl CloudFront {
         })
     }
 
-    /// Invalidate a file on CloudFront
+    /// Invalidate files on CloudFront
     ///
-    /// `path` is the path to the file to invalidate, such as `config.json`, or `re/ge/regex`
+    /// `paths` are the paths to the files to invalidate, such as `config.json`, or `re/ge/regex`.
+    /// They are invalidated by a single invalidation batch.
     #[instrument(skip(self, client))]
-    pub fn invalidate(&self, client: &Client, path: &str) -> anyhow::Result<()> {
-        let path = path.trim_start_matches('/');
+    pub fn invalidate(&self, client: &Client, paths: &[String]) -> anyhow::Result<()> {
+        let items = paths
+            .iter()
+            .map(|path| format!("<Path>/{}</Path>", path.trim_start_matches('/')))
+            .collect::<String>();
+        let quantity = paths.len();
         let url = format!(
             "https://cloudfront.amazonaws.com/2020-05-31/distribution/{}/invalidation",
             self.distribution_id
@@ -54,10 +64,8 @@ impl CloudFront {
 <InvalidationBatch xmlns="http://cloudfront.amazonaws.com/doc/2020-05-31/">
     <CallerReference>{now}</CallerReference>
     <Paths>
-        <Items>
-            <Path>/{path}</Path>
-        </Items>
-        <Quantity>1</Quantity>
+        <Items>{items}</Items>
+        <Quantity>{quantity}</Quantity>
     </Paths>
 </InvalidationBatch>
 "#
@@ -111,7 +119,7 @@ impl CloudFront {
                     let body = response.text();
                     warn!(?status, ?headers, ?body, "Invalidation request failed");
 
-                    Err(error).with_context(|| format!("Failed to invalidate {path}"))
+                    Err(error).with_context(|| format!("Failed to invalidate {quantity} paths"))
                 }
             };
 
@@ -124,3 +132,13 @@ impl CloudFront {
         .map_err(|error| error.error)
     }
 }
+
+impl Cdn for CloudFront {
+    fn name(&self) -> &'static str {
+        "CloudFront"
+    }
+
+    fn invalidate(&self, client: &Client, paths: &[String]) -> anyhow::Result<()> {
+        CloudFront::invalidate(self, client, paths)
+    }
+}
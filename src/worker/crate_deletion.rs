@@ -0,0 +1,47 @@
+use crate::background_jobs::{DeleteCrateFilesJob, Environment, Job};
+use crate::swirl::PerformError;
+use diesel::prelude::*;
+
+pub fn delete_crate_files(
+    krate: String,
+    version_nums: Vec<String>,
+    object_keys: Vec<String>,
+) -> Job {
+    Job::DeleteCrateFiles(DeleteCrateFilesJob {
+        krate,
+        version_nums,
+        object_keys,
+    })
+}
+
+/// Deletes the crate files, signatures and rendered readmes of deleted
+/// versions from the primary bucket and the replicas.
+///
+/// This is enqueued in the same transaction that deletes the versions, so
+/// that the files are only removed once the deletion was committed, and
+/// retried if the storage is unavailable. Deleting files that don't exist
+/// anymore succeeds, so retries after a partial run are safe.
+#[instrument(skip(env, conn))]
+pub fn perform_delete_crate_files(
+    env: &Environment,
+    conn: &mut PgConnection,
+    krate: &str,
+    version_nums: &[String],
+    object_keys: &[String],
+) -> Result<(), PerformError> {
+    let client = env.http_client();
+    for num in version_nums {
+        env.uploader
+            .delete_crate_files(client, krate, num)
+            .map_err(|e| e.to_string())?;
+        env.uploader
+            .delete_signature(client, krate, num)
+            .map_err(|e| e.to_string())?;
+    }
+
+    env.uploader
+        .delete_unreferenced_crate_objects(client, conn, object_keys)
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use crate::background_jobs::{Environment, Job, VerifyCrateFilesJob};
+use crate::schema::{crate_file_checks, crate_files, crates, versions};
+use crate::swirl::PerformError;
+use diesel::dsl::now;
+use diesel::prelude::*;
+use hex::ToHex;
+use sha2::{Digest, Sha256};
+
+pub fn verify_crate_files(sample_size: i64) -> Job {
+    Job::VerifyCrateFiles(VerifyCrateFilesJob { sample_size })
+}
+
+/// Downloads the crate files of the versions that were checked least recently
+/// and compares their checksums against the database.
+///
+/// Versions that were never checked come first, so that repeated runs cycle
+/// through all crate files. The result of each check is recorded in the
+/// `crate_file_checks` table, which the monitor pages on if a crate file is
+/// missing or its checksum does not match. Versions whose checksum has not
+/// been backfilled yet are only checked for existence, and crate files that
+/// can't be downloaded are logged and checked again by the next run.
+#[instrument(skip(env, conn))]
+pub fn perform_verify_crate_files(
+    env: &Environment,
+    conn: &mut PgConnection,
+    sample_size: i64,
+) -> Result<(), PerformError> {
+    let sample: Vec<(i32, String, String, String, Option<String>)> = versions::table
+        .inner_join(crates::table)
+        .left_outer_join(crate_files::table)
+        .left_outer_join(crate_file_checks::table)
+        .select((
+            versions::id,
+            crates::name,
+            versions::num,
+            versions::checksum,
+            crate_files::object_key.nullable(),
+        ))
+        .order((
+            crate_file_checks::checked_at.nullable().asc().nulls_first(),
+            versions::id,
+        ))
+        .limit(sample_size)
+        .load(conn)?;
+
+    info!(count = sample.len(), "Verifying crate files");
+
+    let mut failed = 0;
+    for (version_id, name, num, expected, object_key) in &sample {
+        let content =
+            match env
+                .uploader
+                .get_crate_file(env.http_client(), name, num, object_key.as_deref())
+            {
+                Ok(content) => content,
+                Err(error) => {
+                    warn!(%name, %num, ?error, "Failed to download crate file");
+                    continue;
+                }
+            };
+
+        let (missing, checksum_mismatch) = match content {
+            None => (true, false),
+            Some(_) if expected.is_empty() => (false, false),
+            Some(content) => {
+                let checksum: String = Sha256::digest(&content).encode_hex();
+                (false, checksum != *expected)
+            }
+        };
+
+        if missing || checksum_mismatch {
+            warn!(
+                %name, %num,
+                missing, checksum_mismatch, "Crate file does not match the database"
+            );
+            failed += 1;
+        }
+
+        diesel::insert_into(crate_file_checks::table)
+            .values((
+                crate_file_checks::version_id.eq(*version_id),
+                crate_file_checks::missing.eq(missing),
+                crate_file_checks::checksum_mismatch.eq(checksum_mismatch),
+            ))
+            .on_conflict(crate_file_checks::version_id)
+            .do_update()
+            .set((
+                crate_file_checks::checked_at.eq(now),
+                crate_file_checks::missing.eq(missing),
+                crate_file_checks::checksum_mismatch.eq(checksum_mismatch),
+            ))
+            .execute(conn)?;
+    }
+
+    info!(failed, "Finished verifying crate files");
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use crate::background_jobs::{Environment, Job};
+use crate::models::{CrateTransfer, NotificationKind, TransferCompletion, User, WebhookEvent};
+use crate::schema::{crates, users};
+use crate::swirl::PerformError;
+use crate::util::errors::AppResult;
+use crate::worker::trigger_webhooks;
+use diesel::prelude::*;
+
+pub fn complete_crate_transfers() -> Job {
+    Job::CompleteCrateTransfers
+}
+
+/// Moves the ownership of the crates whose transfers were accepted, and whose
+/// waiting period is over, to the recipients of the transfers.
+///
+/// Both users of a completed transfer are notified by email, and the webhooks
+/// of the crate receive the owner changes. Transfers whose initiator isn't an
+/// owner of the crate anymore are cancelled instead. A transfer that fails is
+/// logged and retried with the next run of the job.
+#[instrument(skip_all)]
+pub fn perform_complete_crate_transfers(
+    env: &Environment,
+    conn: &mut PgConnection,
+) -> Result<(), PerformError> {
+    let transfers = CrateTransfer::due(conn)?;
+
+    info!(transfers = transfers.len(), "Completing crate transfers");
+
+    for transfer in transfers {
+        // Every transfer is completed in its own savepoint, so that a failing
+        // one doesn't roll back the progress of the others
+        let result = conn.transaction(|conn| complete_transfer(env, conn, &transfer));
+
+        if let Err(error) = result {
+            warn!(
+                transfer_id = transfer.id,
+                crate_id = transfer.crate_id,
+                %error,
+                "Failed to complete crate transfer"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn complete_transfer(
+    env: &Environment,
+    conn: &mut PgConnection,
+    transfer: &CrateTransfer,
+) -> Result<(), PerformError> {
+    let crate_name: String = crates::table
+        .find(transfer.crate_id)
+        .select(crates::name)
+        .first(conn)?;
+    let from_user: User = users::table.find(transfer.from_user_id).first(conn)?;
+    let to_user: User = users::table.find(transfer.to_user_id).first(conn)?;
+
+    match transfer.complete(conn)? {
+        TransferCompletion::Completed => {}
+        TransferCompletion::Cancelled => {
+            info!(%crate_name, "Initiator is no longer an owner, cancelled crate transfer");
+            for user in [&from_user, &to_user] {
+                notify(conn, user, |email| {
+                    let emails = env.emails();
+                    emails.send_crate_transfer_cancelled(email, &crate_name)
+                })?;
+            }
+            return Ok(());
+        }
+        TransferCompletion::NoLongerPending => {
+            info!(%crate_name, "Crate transfer is no longer pending, skipping");
+            return Ok(());
+        }
+    }
+
+    info!(%crate_name, to_user = %to_user.gh_login, "Completed crate transfer");
+
+    let owner_changes = [
+        (&to_user, WebhookEvent::OwnerAdded),
+        (&from_user, WebhookEvent::OwnerRemoved),
+    ];
+    for (user, event) in owner_changes {
+        let data = json!({ "owner": user.gh_login });
+        trigger_webhooks(conn, transfer.crate_id, &crate_name, event, data)?;
+    }
+
+    for user in [&from_user, &to_user] {
+        notify(conn, user, |email| {
+            env.emails().send_crate_transfer_completed(
+                email,
+                &crate_name,
+                &from_user.gh_login,
+                &to_user.gh_login,
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Sends an email to the user, if they have a verified email address and
+/// didn't opt out of emails about crate ownership.
+///
+/// Failures to send are only logged, so that they don't roll back the
+/// ownership change that the email is about.
+fn notify(
+    conn: &mut PgConnection,
+    user: &User,
+    send: impl FnOnce(&str) -> AppResult<()>,
+) -> QueryResult<()> {
+    if let Some(email) = user.notification_email(conn, NotificationKind::OwnerInvites)? {
+        if let Err(error) = send(&email) {
+            warn!(user = %user.gh_login, %error, "Failed to send crate transfer email");
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,4 @@
-use crate::background_jobs::Job;
-use crate::swirl::PerformError;
+use crate::background_jobs::{Environment, Job};
 /// Run daily database maintenance tasks
 ///
 /// By default PostgreSQL will run an auto-vacuum when 20% of the tuples in a table are dead.
@@ -9,12 +8,47 @@ use crate::swirl::PerformError;
 /// We only need to keep 90 days of entries in `version_downloads`. Once we have a mechanism to
 /// archive daily download counts and drop historical data, we can drop this task and rely on
 /// auto-vacuum again.
+///
+/// This task also rebuilds the `search_terms` view that is used for spelling suggestions in
+/// search results, and deletes chunked publish uploads that have never been completed, along with
+/// their stored chunks, as well as staged publishes that have never been promoted.
+use crate::models::{PublishUpload, SearchTerm, StagedPublish};
+use crate::swirl::PerformError;
 use diesel::{sql_query, PgConnection, RunQueryDsl};
 
-pub(crate) fn perform_daily_db_maintenance(conn: &mut PgConnection) -> Result<(), PerformError> {
+pub(crate) fn perform_daily_db_maintenance(
+    env: &Environment,
+    conn: &mut PgConnection,
+) -> Result<(), PerformError> {
     info!("Running VACUUM on version_downloads table");
     sql_query("VACUUM version_downloads;").execute(conn)?;
     info!("Finished running VACUUM on version_downloads table");
+
+    info!("Refreshing search_terms view");
+    SearchTerm::refresh(conn)?;
+    info!("Finished refreshing search_terms view");
+
+    let stale = PublishUpload::stale(conn)?;
+    for upload in &stale {
+        let parts = upload.parts(conn)?;
+        // Leftover chunks only take up space, so they don't prevent the
+        // deletion of the upload
+        if let Err(error) = env
+            .uploader
+            .abort_publish_upload(env.http_client(), upload, &parts)
+        {
+            warn!(
+                upload.id,
+                ?error,
+                "Failed to delete the chunks of a publish upload"
+            );
+        }
+        upload.delete(conn)?;
+    }
+    info!(deleted = stale.len(), "Deleted stale publish uploads");
+
+    let deleted = StagedPublish::delete_stale(conn)?;
+    info!(deleted, "Deleted stale staged publishes");
     Ok(())
 }
 
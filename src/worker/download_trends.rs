@@ -0,0 +1,41 @@
+use crate::background_jobs::Job;
+use crate::schema::crate_download_trends;
+use crate::swirl::PerformError;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::Integer;
+
+/// The lengths of the windows in days that download trends are computed for.
+///
+/// Both windows have to fit into the 90 days of `version_downloads` that are
+/// kept.
+pub const DOWNLOAD_TREND_WINDOWS: [i32; 2] = [7, 28];
+
+pub fn update_download_trends() -> Job {
+    Job::UpdateDownloadTrends
+}
+
+/// Recomputes the downloads of every crate in the most recent windows of
+/// days and the windows before them, which are compared by the trending
+/// crates endpoint.
+///
+/// The windows end with yesterday, since the downloads of today are still
+/// incomplete. The previous trends are replaced within the transaction of
+/// the job, so readers never see a partially computed window.
+#[instrument(skip_all)]
+pub(crate) fn perform_update_download_trends(conn: &mut PgConnection) -> Result<(), PerformError> {
+    for window_days in DOWNLOAD_TREND_WINDOWS {
+        diesel::delete(
+            crate_download_trends::table.filter(crate_download_trends::window_days.eq(window_days)),
+        )
+        .execute(conn)?;
+
+        let crates = sql_query(include_str!("download_trends.sql"))
+            .bind::<Integer, _>(window_days)
+            .execute(conn)?;
+
+        info!(window_days, crates, "Updated download trends");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,12 @@
+INSERT INTO crate_download_trends (crate_id, window_days, downloads, previous_downloads)
+SELECT
+    versions.crate_id,
+    $1,
+    COALESCE(SUM(version_downloads.downloads) FILTER (WHERE version_downloads.date >= current_date - $1), 0),
+    COALESCE(SUM(version_downloads.downloads) FILTER (WHERE version_downloads.date < current_date - $1), 0)
+FROM version_downloads
+INNER JOIN versions
+  ON versions.id = version_downloads.version_id
+WHERE version_downloads.date >= current_date - 2 * $1
+  AND version_downloads.date < current_date
+GROUP BY versions.crate_id
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use parquet::basic::Compression;
+use parquet::data_type::{ByteArray, ByteArrayType, DataType, Int32Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{SerializedFileWriter, SerializedRowGroupWriter};
+use parquet::schema::parser::parse_message_type;
+use reqwest::header;
+
+use crate::background_jobs::{Environment, ExportDownloadsJob, Job};
+use crate::schema::{crates, version_downloads, versions};
+use crate::swirl::PerformError;
+use crate::uploaders::UploadBucket;
+
+/// The schema of the exported files. The columns are written in this order
+/// by `write_parquet()`.
+const SCHEMA: &str = "
+message version_downloads {
+    REQUIRED INT32 version_id;
+    REQUIRED BYTE_ARRAY crate (UTF8);
+    REQUIRED BYTE_ARRAY version (UTF8);
+    REQUIRED INT32 date (DATE);
+    REQUIRED INT32 downloads;
+}
+";
+
+#[derive(Debug, Queryable)]
+struct ExportedDownload {
+    version_id: i32,
+    crate_name: String,
+    version: String,
+    date: NaiveDate,
+    downloads: i32,
+}
+
+pub fn export_downloads(date: NaiveDate) -> Job {
+    Job::ExportDownloads(ExportDownloadsJob { date })
+}
+
+/// Exports the downloads of all versions on the given day as a Parquet file.
+///
+/// The files are partitioned by day, using the `date=YYYY-MM-DD` directory
+/// naming that analytics tools understand, so exporting a day again replaces
+/// its previous export.
+#[instrument(skip(env, conn))]
+pub fn perform_export_downloads(
+    env: &Environment,
+    conn: &mut PgConnection,
+    date: NaiveDate,
+) -> Result<(), PerformError> {
+    let downloads: Vec<ExportedDownload> = version_downloads::table
+        .inner_join(versions::table.inner_join(crates::table))
+        .filter(version_downloads::date.eq(date))
+        .select((
+            version_downloads::version_id,
+            crates::name,
+            versions::num,
+            version_downloads::date,
+            version_downloads::downloads,
+        ))
+        .order(version_downloads::version_id)
+        .load(conn)?;
+
+    info!(count = downloads.len(), "Exporting version downloads");
+    let content = write_parquet(&downloads)?;
+
+    let path = export_path(date);
+    env.uploader.upload(
+        env.http_client(),
+        &path,
+        content,
+        "application/vnd.apache.parquet",
+        header::HeaderMap::new(),
+        UploadBucket::Default,
+    )?;
+    info!(%path, "Version downloads exported");
+
+    Ok(())
+}
+
+fn export_path(date: NaiveDate) -> String {
+    format!("downloads/version_downloads/date={date}/version_downloads.parquet")
+}
+
+fn write_parquet(downloads: &[ExportedDownload]) -> Result<Vec<u8>, PerformError> {
+    let schema = Arc::new(parse_message_type(SCHEMA)?);
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+
+    let mut buffer = Vec::new();
+    let mut writer = SerializedFileWriter::new(&mut buffer, schema, Arc::new(properties))?;
+
+    let mut row_group = writer.next_row_group()?;
+    let version_ids = downloads.iter().map(|d| d.version_id).collect::<Vec<_>>();
+    write_column::<Int32Type>(&mut row_group, &version_ids)?;
+    let crate_names = downloads
+        .iter()
+        .map(|d| ByteArray::from(d.crate_name.as_str()))
+        .collect::<Vec<_>>();
+    write_column::<ByteArrayType>(&mut row_group, &crate_names)?;
+    let versions = downloads
+        .iter()
+        .map(|d| ByteArray::from(d.version.as_str()))
+        .collect::<Vec<_>>();
+    write_column::<ByteArrayType>(&mut row_group, &versions)?;
+    let dates = downloads
+        .iter()
+        .map(|d| days_since_epoch(d.date))
+        .collect::<Vec<_>>();
+    write_column::<Int32Type>(&mut row_group, &dates)?;
+    let counts = downloads.iter().map(|d| d.downloads).collect::<Vec<_>>();
+    write_column::<Int32Type>(&mut row_group, &counts)?;
+    row_group.close()?;
+
+    writer.close()?;
+    Ok(buffer)
+}
+
+fn write_column<T: DataType>(
+    row_group: &mut SerializedRowGroupWriter<'_, &mut Vec<u8>>,
+    values: &[T::T],
+) -> Result<(), PerformError> {
+    let mut column = row_group
+        .next_column()?
+        .ok_or("Parquet schema has fewer columns than written")?;
+    column.typed::<T>().write_batch(values, None, None)?;
+    column.close()?;
+    Ok(())
+}
+
+/// Parquet stores dates as the number of days since the Unix epoch.
+fn days_since_epoch(date: NaiveDate) -> i32 {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    (date - epoch).num_days() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::RowAccessor;
+    use std::io::{Seek, SeekFrom, Write};
+
+    #[test]
+    fn export_path_is_partitioned_by_date() {
+        let date = NaiveDate::from_ymd_opt(2023, 3, 14).unwrap();
+        assert_eq!(
+            export_path(date),
+            "downloads/version_downloads/date=2023-03-14/version_downloads.parquet"
+        );
+    }
+
+    #[test]
+    fn written_file_can_be_read() {
+        let date = NaiveDate::from_ymd_opt(2023, 3, 14).unwrap();
+        let downloads = vec![
+            ExportedDownload {
+                version_id: 1,
+                crate_name: "foo".into(),
+                version: "1.0.0".into(),
+                date,
+                downloads: 42,
+            },
+            ExportedDownload {
+                version_id: 2,
+                crate_name: "bar".into(),
+                version: "0.1.0".into(),
+                date,
+                downloads: 7,
+            },
+        ];
+
+        let content = write_parquet(&downloads).unwrap();
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&content).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+
+        let reader = SerializedFileReader::new(file).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+
+        let rows = reader.get_row_iter(None).unwrap().collect::<Vec<_>>();
+        assert_eq!(rows[0].get_int(0).unwrap(), 1);
+        assert_eq!(rows[0].get_string(1).unwrap(), "foo");
+        assert_eq!(rows[0].get_string(2).unwrap(), "1.0.0");
+        assert_eq!(rows[0].get_int(4).unwrap(), 42);
+        assert_eq!(rows[1].get_string(1).unwrap(), "bar");
+        assert_eq!(rows[1].get_int(4).unwrap(), 7);
+    }
+
+    #[test]
+    fn dates_are_counted_from_the_epoch() {
+        let date = NaiveDate::from_ymd_opt(1970, 1, 2).unwrap();
+        assert_eq!(days_since_epoch(date), 1);
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::Context;
+use reqwest::blocking::Client;
+
+use crate::worker::cdn::Cdn;
+
+/// Fastly accepts at most this many surrogate keys per purge request.
+const MAX_KEYS_PER_PURGE: usize = 256;
+
+/// A Fastly service in front of the buckets.
+///
+/// Files are purged by surrogate key, so the service has to tag each response
+/// with the path of the file as its surrogate key, e.g. `re/ge/regex` or
+/// `crates/regex/regex-1.0.0.crate`.
+#[derive(Clone)]
+pub struct Fastly {
+    service_id: String,
+    api_token: String,
+}
+
+impl Fastly {
+    /// Configures the service from the `FASTLY_SERVICE_ID` and
+    /// `FASTLY_API_TOKEN` environment variables. Returns `None` if no service
+    /// is configured.
+    pub fn from_environment() -> Option<Self> {
+        let service_id = dotenv::var("FASTLY_SERVICE_ID").ok()?;
+        let api_token = dotenv::var("FASTLY_API_TOKEN").expect("missing FASTLY_API_TOKEN");
+        Some(Self {
+            service_id,
+            api_token,
+        })
+    }
+
+    /// Purges the surrogate keys of the `paths` on Fastly.
+    ///
+    /// See <https://developer.fastly.com/reference/api/purging/#bulk-purge-tag>.
+    #[instrument(skip(self, client))]
+    pub fn purge(&self, client: &Client, paths: &[String]) -> anyhow::Result<()> {
+        let url = format!("https://api.fastly.com/service/{}/purge", self.service_id);
+
+        for keys in paths.chunks(MAX_KEYS_PER_PURGE) {
+            let keys = surrogate_keys(keys);
+            debug!(%keys, "Sending purge request");
+            client
+                .post(&url)
+                .header("Fastly-Key", &self.api_token)
+                .header("Surrogate-Key", keys)
+                .send()
+                .and_then(|response| response.error_for_status())
+                .context("Failed to purge surrogate keys on Fastly")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Cdn for Fastly {
+    fn name(&self) -> &'static str {
+        "Fastly"
+    }
+
+    fn invalidate(&self, client: &Client, paths: &[String]) -> anyhow::Result<()> {
+        self.purge(client, paths)
+    }
+}
+
+/// Returns the space-separated surrogate keys of the `paths`.
+fn surrogate_keys(paths: &[String]) -> String {
+    paths
+        .iter()
+        .map(|path| path.trim_start_matches('/'))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surrogate_keys_of_paths() {
+        let paths = vec!["re/ge/regex".to_string(), "/config.json".to_string()];
+        assert_eq!(surrogate_keys(&paths), "re/ge/regex config.json");
+    }
+}
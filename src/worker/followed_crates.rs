@@ -0,0 +1,170 @@
+use crate::background_jobs::{Environment, Job};
+use crate::models::{
+    FollowNotificationFrequency, FollowNotificationSettings, NotificationKind, User, VersionAction,
+};
+use crate::schema::{
+    crates, follow_notification_settings, follows, users, version_owner_actions, versions,
+};
+use crate::swirl::PerformError;
+use crate::worker::webhooks::signature;
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use reqwest::header::{HeaderMap, HeaderValue};
+
+/// At most this many versions are listed in a single notification.
+const MAX_NOTIFIED_EVENTS: usize = 50;
+
+/// Version actions are only notified about once they are this old.
+///
+/// The `time` of an action is the start of the transaction that recorded it,
+/// so an action can become visible after newer ones. Leaving this much time
+/// for the transactions to commit keeps them from being skipped.
+const NOTIFICATION_DELAY_MINUTES: i64 = 10;
+
+pub fn check_followed_crates() -> Job {
+    Job::CheckFollowedCrates
+}
+
+/// Notifies users about versions of the crates they follow that were
+/// published or yanked since their previous notification.
+///
+/// The job is meant to run regularly, e.g. hourly. Each user is notified at
+/// most once per their chosen frequency, with all events since the previous
+/// notification batched together. The settings of every user remember up to
+/// which time they were notified, so events are only notified about once.
+#[instrument(skip_all)]
+pub fn perform_check_followed_crates(
+    env: &Environment,
+    conn: &mut PgConnection,
+) -> Result<(), PerformError> {
+    let now = Utc::now().naive_utc();
+    let cutoff = now - Duration::minutes(NOTIFICATION_DELAY_MINUTES);
+
+    let all_settings: Vec<FollowNotificationSettings> = follow_notification_settings::table
+        .filter(follow_notification_settings::frequency.ne(FollowNotificationFrequency::Never))
+        .filter(follow_notification_settings::notified_until.lt(cutoff))
+        .order(follow_notification_settings::user_id)
+        .load(conn)?;
+
+    info!(users = all_settings.len(), "Checking followed crates");
+
+    for settings in all_settings {
+        let Some(interval) = settings.frequency.interval() else {
+            continue;
+        };
+        if settings.last_notified_at + interval > now {
+            continue;
+        }
+
+        // Every user is checked in their own savepoint, so that a failing
+        // notification doesn't roll back the progress of the others
+        let result =
+            conn.transaction(|conn| check_followed_crates_of(env, conn, &settings, cutoff));
+
+        if let Err(error) = result {
+            warn!(user_id = settings.user_id, %error, "Failed to notify about followed crates");
+        }
+    }
+
+    Ok(())
+}
+
+fn check_followed_crates_of(
+    env: &Environment,
+    conn: &mut PgConnection,
+    settings: &FollowNotificationSettings,
+    cutoff: NaiveDateTime,
+) -> Result<(), PerformError> {
+    let followed_crates = follows::table
+        .filter(follows::user_id.eq(settings.user_id))
+        .select(follows::crate_id);
+
+    let events: Vec<(String, String, VersionAction)> = version_owner_actions::table
+        .inner_join(versions::table.inner_join(crates::table))
+        .filter(version_owner_actions::time.gt(settings.notified_until))
+        .filter(version_owner_actions::time.le(cutoff))
+        .filter(version_owner_actions::action.eq_any([VersionAction::Publish, VersionAction::Yank]))
+        .filter(versions::crate_id.eq_any(followed_crates))
+        .order((version_owner_actions::time, version_owner_actions::id))
+        .select((crates::name, versions::num, version_owner_actions::action))
+        .load(conn)?;
+
+    if !events.is_empty() {
+        let events: Vec<(String, String, &str)> = events
+            .into_iter()
+            .map(|(krate, num, action)| {
+                let action = match action {
+                    VersionAction::Yank => "yanked",
+                    _ => "published",
+                };
+                (krate, num, action)
+            })
+            .collect();
+
+        notify(env, conn, settings, &events)?;
+    }
+
+    settings.mark_notified(conn, cutoff)?;
+
+    Ok(())
+}
+
+fn notify(
+    env: &Environment,
+    conn: &mut PgConnection,
+    settings: &FollowNotificationSettings,
+    events: &[(String, String, &str)],
+) -> Result<(), PerformError> {
+    let total = events.len();
+    let events = &events[..total.min(MAX_NOTIFIED_EVENTS)];
+
+    if let Some(webhook_url) = &settings.webhook_url {
+        let payload = json!({
+            "events": events
+                .iter()
+                .map(|(krate, num, action)| json!({ "crate": krate, "version": num, "action": action }))
+                .collect::<Vec<_>>(),
+            "total": total,
+        });
+
+        // Webhooks that were configured before their payloads were signed
+        // have to be set again to get a secret
+        let key = settings
+            .signing_key()
+            .ok_or("the webhook has no secret, it has to be set again")?;
+
+        let body = serde_json::to_vec(&payload)?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "X-Crates-Io-Event",
+            HeaderValue::from_static("followed_crates"),
+        );
+        headers.insert(
+            "X-Crates-Io-Signature",
+            HeaderValue::from_str(&signature(key, &body))?,
+        );
+
+        let response = env
+            .webhook_client()
+            .post_json(webhook_url, body, headers)
+            .map_err(|error| error.to_string())?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("unexpected response status: {status}").into());
+        }
+    } else {
+        let user: User = users::table.find(settings.user_id).first(conn)?;
+
+        let Some(email) = user.notification_email(conn, NotificationKind::Digests)? else {
+            return Ok(());
+        };
+
+        env.emails()
+            .send_followed_crates_digest(&email, events, total)
+            .map_err(|error| error.to_string())?;
+    }
+
+    Ok(())
+}
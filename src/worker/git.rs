@@ -1,8 +1,11 @@
 use crate::background_jobs::{
-    Environment, IndexAddCrateJob, IndexSyncToHttpJob, IndexUpdateYankedJob, Job, NormalizeIndexJob,
+    Environment, IndexAddCrateJob, IndexDeleteVersionsJob, IndexSquashIfNeededJob,
+    IndexSyncDeprecatedJob, IndexSyncToHttpJob, IndexUpdateYankedJob, Job, NormalizeIndexJob,
 };
+use crate::models::{self, CrateVersions, IndexChange, IndexChangeAction};
 use crate::schema;
 use crate::swirl::PerformError;
+use crate::worker::cdn::queue_cdn_invalidations;
 use anyhow::Context;
 use cargo_registry_index::{Crate, Repository};
 use chrono::Utc;
@@ -32,6 +35,12 @@ pub fn perform_index_add_crate(
 
     let message: String = format!("Updating crate `{}#{}`", krate.name, krate.vers);
     repo.commit_and_push(&message, &dst)?;
+    IndexChange::record(
+        conn,
+        &krate.name,
+        Some(&krate.vers),
+        IndexChangeAction::Publish,
+    )?;
 
     // Queue another background job to update the http-based index as well.
     update_crate_index(krate.name.clone()).enqueue(conn)?;
@@ -42,9 +51,10 @@ pub fn add_crate(krate: Crate) -> Job {
     Job::IndexAddCrate(IndexAddCrateJob { krate })
 }
 
-#[instrument(skip(env))]
+#[instrument(skip(env, conn))]
 pub fn perform_index_sync_to_http(
     env: &Environment,
+    conn: &mut PgConnection,
     crate_name: String,
 ) -> Result<(), PerformError> {
     info!("Syncing git index to HTTP-based index");
@@ -61,10 +71,10 @@ pub fn perform_index_sync_to_http(
     env.uploader
         .sync_index(env.http_client(), &crate_name, contents)?;
 
-    if let Some(cloudfront) = env.cloudfront() {
+    if !env.cdns().is_empty() {
         let path = Repository::relative_index_file_for_url(&crate_name);
-        info!(%path, "Invalidating index file on CloudFront");
-        cloudfront.invalidate(env.http_client(), &path)?;
+        info!(%path, "Queueing invalidation of index file on the CDNs");
+        queue_cdn_invalidations(conn, &[path])?;
     }
 
     Ok(())
@@ -76,8 +86,8 @@ pub fn update_crate_index(crate_name: String) -> Job {
 
 /// Yanks or unyanks a crate version. This requires finding the index
 /// file, deserlialise the crate from JSON, change the yank boolean to
-/// `true` or `false`, write all the lines back out, and commit and
-/// push the changes.
+/// `true` or `false` (along with the optional yank reason and replacement),
+/// write all the lines back out, and commit and push the changes.
 #[instrument(skip(env, conn))]
 pub fn perform_index_update_yanked(
     env: &Environment,
@@ -89,15 +99,20 @@ pub fn perform_index_update_yanked(
 
     debug!("Loading yanked status from database");
 
-    let yanked: bool = schema::versions::table
-        .inner_join(schema::crates::table)
-        .filter(schema::crates::name.eq(&krate))
-        .filter(schema::versions::num.eq(&version_num))
-        .select(schema::versions::yanked)
-        .get_result(conn)
-        .context("Failed to load yanked status from database")?;
-
-    debug!(yanked);
+    let (yanked, yank_reason, yank_replacement): (bool, Option<String>, Option<String>) =
+        schema::versions::table
+            .inner_join(schema::crates::table)
+            .filter(schema::crates::name.eq(&krate))
+            .filter(schema::versions::num.eq(&version_num))
+            .select((
+                schema::versions::yanked,
+                schema::versions::yank_reason,
+                schema::versions::yank_replacement,
+            ))
+            .get_result(conn)
+            .context("Failed to load yanked status from database")?;
+
+    debug!(yanked, ?yank_reason, ?yank_replacement);
 
     let repo = env.lock_index()?;
     let dst = repo.index_file(krate);
@@ -112,6 +127,8 @@ pub fn perform_index_update_yanked(
                 return Ok(line.to_string());
             }
             git_crate.yanked = Some(yanked);
+            git_crate.yank_reason = yank_reason.clone();
+            git_crate.yank_replacement = yank_replacement.clone();
             Ok(serde_json::to_string(&git_crate)?)
         })
         .collect::<Result<Vec<_>, PerformError>>();
@@ -124,6 +141,13 @@ pub fn perform_index_update_yanked(
         let message = format!("{action} crate `{krate}#{version_num}`");
 
         repo.commit_and_push(&message, &dst)?;
+
+        let action = if yanked {
+            IndexChangeAction::Yank
+        } else {
+            IndexChangeAction::Unyank
+        };
+        IndexChange::record(conn, krate, Some(version_num), action)?;
     } else {
         debug!("Skipping `yanked` update because index is up-to-date");
     }
@@ -138,23 +162,184 @@ pub fn sync_yanked(krate: String, version_num: String) -> Job {
     Job::IndexUpdateYanked(IndexUpdateYankedJob { krate, version_num })
 }
 
+/// Syncs the deprecation status of the crate and all of its versions from
+/// the database into the index file of the crate, and commits and pushes
+/// the changes.
+#[instrument(skip(env, conn))]
+pub fn perform_index_sync_deprecated(
+    env: &Environment,
+    conn: &mut PgConnection,
+    krate: &str,
+) -> Result<(), PerformError> {
+    info!("Syncing deprecation status from database into the index");
+
+    debug!("Loading deprecation status from database");
+
+    let db_crate: models::Crate = models::Crate::by_exact_name(krate)
+        .first(conn)
+        .context("Failed to load crate from database")?;
+    let versions: Vec<models::Version> = db_crate
+        .all_versions()
+        .load(conn)
+        .context("Failed to load versions from database")?;
+
+    let repo = env.lock_index()?;
+    let dst = repo.index_file(krate);
+
+    let prev = fs::read_to_string(&dst)?;
+    let new = prev
+        .lines()
+        .map(|line| {
+            let mut git_crate = serde_json::from_str::<Crate>(line)
+                .map_err(|_| format!("couldn't decode: `{line}`"))?;
+            if git_crate.name != krate {
+                return Ok(line.to_string());
+            }
+            let version = versions.iter().find(|v| v.num == git_crate.vers);
+            git_crate.deprecated = db_crate.index_deprecation(version);
+            Ok(serde_json::to_string(&git_crate)?)
+        })
+        .collect::<Result<Vec<_>, PerformError>>();
+    let new = new?.join("\n") + "\n";
+
+    if new != prev {
+        fs::write(&dst, new.as_bytes())?;
+
+        let message = format!("Updating deprecation status of crate `{krate}`");
+        repo.commit_and_push(&message, &dst)?;
+        IndexChange::record(conn, krate, None, IndexChangeAction::Deprecate)?;
+    } else {
+        debug!("Skipping deprecation update because index is up-to-date");
+    }
+
+    // Queue another background job to update the http-based index as well.
+    update_crate_index(krate.to_string()).enqueue(conn)?;
+
+    Ok(())
+}
+
+pub fn sync_deprecated(krate: String) -> Job {
+    Job::IndexSyncDeprecated(IndexSyncDeprecatedJob { krate })
+}
+
+/// Removes deleted versions of a crate from the index. If no versions of the
+/// crate are left, the index file of the crate is removed as well.
+#[instrument(skip(env, conn))]
+pub fn perform_index_delete_versions(
+    env: &Environment,
+    conn: &mut PgConnection,
+    krate: &str,
+    version_nums: &[String],
+) -> Result<(), PerformError> {
+    info!("Removing deleted versions from the index");
+
+    let repo = env.lock_index()?;
+    let dst = repo.index_file(krate);
+
+    let prev = match fs::read_to_string(&dst) {
+        Ok(prev) => prev,
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut remaining = Vec::new();
+    for line in prev.lines() {
+        let git_crate = serde_json::from_str::<Crate>(line)
+            .map_err(|_| format!("couldn't decode: `{line}`"))?;
+        if git_crate.name != krate || !version_nums.contains(&git_crate.vers) {
+            remaining.push(line);
+        }
+    }
+
+    if remaining.len() != prev.lines().count() {
+        let message = if remaining.is_empty() {
+            fs::remove_file(&dst)?;
+            format!("Deleting crate `{krate}`")
+        } else {
+            fs::write(&dst, remaining.join("\n") + "\n")?;
+            let versions = version_nums.join("`, `{krate}#");
+            format!("Deleting crate `{krate}#{versions}`")
+        };
+
+        repo.commit_and_push(&message, &dst)?;
+        for version_num in version_nums {
+            IndexChange::record(conn, krate, Some(version_num), IndexChangeAction::Delete)?;
+        }
+    } else {
+        debug!("Skipping deletion because the versions are not in the index");
+    }
+
+    // Queue another background job to update the http-based index as well.
+    update_crate_index(krate.to_string()).enqueue(conn)?;
+
+    Ok(())
+}
+
+pub fn delete_versions(krate: String, version_nums: Vec<String>) -> Job {
+    Job::IndexDeleteVersions(IndexDeleteVersionsJob {
+        krate,
+        version_nums,
+    })
+}
+
 /// Collapse the index into a single commit, archiving the current history in a snapshot branch.
-#[instrument(skip(env))]
-pub fn perform_index_squash(env: &Environment) -> Result<(), PerformError> {
-    info!("Squashing the index into a single commit");
+#[instrument(skip(env, conn))]
+pub fn perform_index_squash(
+    env: &Environment,
+    conn: &mut PgConnection,
+) -> Result<(), PerformError> {
+    let repo = env.lock_index()?;
+    squash(&repo, conn)
+}
 
+/// Collapse the index into a single commit like [`perform_index_squash`], but
+/// only once its history has grown beyond `max_commits` commits.
+#[instrument(skip(env, conn))]
+pub fn perform_index_squash_if_needed(
+    env: &Environment,
+    conn: &mut PgConnection,
+    max_commits: usize,
+) -> Result<(), PerformError> {
     let repo = env.lock_index()?;
 
+    let commits = repo.commit_count()?;
+    if commits <= max_commits {
+        info!(
+            commits,
+            "Skipping squash because the index history is short enough"
+        );
+        return Ok(());
+    }
+
+    squash(&repo, conn)
+}
+
+fn squash(repo: &Repository, conn: &mut PgConnection) -> Result<(), PerformError> {
+    info!("Squashing the index into a single commit");
+
     let now = Utc::now().format("%Y-%m-%d");
     let original_head = repo.head_oid()?.to_string();
+    let snapshot_branch = format!("snapshot-{now}");
     let msg = format!("Collapse index into one commit\n\n\
-        Previous HEAD was {original_head}, now on the `snapshot-{now}` branch\n\n\
+        Previous HEAD was {original_head}, now on the `{snapshot_branch}` branch\n\n\
         More information about this change can be found [online] and on [this issue].\n\n\
         [online]: https://internals.rust-lang.org/t/cargos-crate-index-upcoming-squash-into-one-commit/8440\n\
         [this issue]: https://github.com/rust-lang/crates-io-cargo-teams/issues/47");
 
     repo.squash_to_single_commit(&msg)?;
 
+    // Record the squash in the changelog that mirrors poll to find out that
+    // they need to fetch the index from scratch. This happens before the
+    // push, so that the insert is rolled back together with the transaction
+    // of the job if the push fails, and a successful push is never left
+    // unrecorded.
+    diesel::insert_into(schema::index_squashes::table)
+        .values((
+            schema::index_squashes::previous_head.eq(&original_head),
+            schema::index_squashes::snapshot_branch.eq(&snapshot_branch),
+        ))
+        .execute(conn)?;
+
     // Shell out to git because libgit2 does not currently support push leases
 
     repo.run_command(Command::new("git").args([
@@ -167,7 +352,7 @@ pub fn perform_index_squash(env: &Environment) -> Result<(), PerformError> {
         // The new squashed commit is pushed to master
         "HEAD:refs/heads/master",
         // The previous value of HEAD is pushed to a snapshot branch
-        &format!("{original_head}:refs/heads/snapshot-{now}"),
+        &format!("{original_head}:refs/heads/{snapshot_branch}"),
     ]))?;
 
     info!("The index has been successfully squashed.");
@@ -179,6 +364,10 @@ pub fn squash_index() -> Job {
     Job::IndexSquash
 }
 
+pub fn squash_index_if_needed(max_commits: usize) -> Job {
+    Job::IndexSquashIfNeeded(IndexSquashIfNeededJob { max_commits })
+}
+
 pub fn perform_normalize_index(
     env: &Environment,
     args: NormalizeIndexJob,
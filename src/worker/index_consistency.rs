@@ -0,0 +1,118 @@
+use crate::background_jobs::{CheckIndexConsistencyJob, Environment, Job};
+use crate::models::Crate;
+use crate::schema::index_inconsistencies;
+use crate::swirl::PerformError;
+use crate::worker::git::update_crate_index;
+use diesel::dsl::{now, sql};
+use diesel::prelude::*;
+use diesel::sql_types::Double;
+use std::fs;
+use std::io::ErrorKind;
+
+pub fn check_index_consistency(sample_size: i64, sparse: bool, fix: bool) -> Job {
+    Job::CheckIndexConsistency(CheckIndexConsistencyJob {
+        sample_size,
+        sparse,
+        fix,
+    })
+}
+
+/// Compares the index files of a random sample of crates against the entries
+/// rendered from the database.
+///
+/// The git index is always checked, the sparse HTTP index only if `sparse` is
+/// set. Mismatches are recorded in the `index_inconsistencies` table, which the
+/// monitor pages on. With `fix` set, the git index file is overwritten with the
+/// rendered one, and if the sparse index is checked it is synced from the git
+/// index again.
+#[instrument(skip(env, conn))]
+pub fn perform_check_index_consistency(
+    env: &Environment,
+    conn: &mut PgConnection,
+    sample_size: i64,
+    sparse: bool,
+    fix: bool,
+) -> Result<(), PerformError> {
+    let crates: Vec<Crate> = Crate::all()
+        .order(sql::<Double>("RANDOM()"))
+        .limit(sample_size)
+        .load(conn)?;
+
+    info!(count = crates.len(), "Checking index consistency");
+
+    let check_sparse = sparse && env.uploader.has_index_bucket();
+    let mut inconsistent = 0;
+    for krate in crates {
+        let expected = krate.index_file(conn)?;
+
+        let git_mismatch = {
+            let repo = env.lock_index()?;
+            let dst = repo.index_file(&krate.name);
+            let actual = match fs::read_to_string(&dst) {
+                Ok(contents) => Some(contents),
+                Err(e) if e.kind() == ErrorKind::NotFound => None,
+                Err(e) => return Err(e.into()),
+            };
+
+            let mismatch = actual != expected;
+            if mismatch && fix {
+                match &expected {
+                    Some(expected) => {
+                        fs::create_dir_all(dst.parent().unwrap())?;
+                        fs::write(&dst, expected)?;
+                    }
+                    None => fs::remove_file(&dst)?,
+                }
+
+                let message = format!("Correcting index file of crate `{}`", krate.name);
+                repo.commit_and_push(&message, &dst)?;
+            }
+            mismatch
+        };
+
+        let sparse_mismatch = check_sparse
+            && env
+                .uploader
+                .download_index(env.http_client(), &krate.name)?
+                != expected;
+
+        if !git_mismatch && !sparse_mismatch {
+            diesel::delete(index_inconsistencies::table.find(krate.id)).execute(conn)?;
+            continue;
+        }
+
+        warn!(
+            krate.name = %krate.name,
+            git_mismatch, sparse_mismatch, "Index file does not match the database"
+        );
+        inconsistent += 1;
+
+        if fix {
+            // The git index file matches the database now, so syncing it to
+            // the sparse index corrects that one as well.
+            if check_sparse {
+                update_crate_index(krate.name.clone()).enqueue(conn)?;
+            }
+            diesel::delete(index_inconsistencies::table.find(krate.id)).execute(conn)?;
+        } else {
+            diesel::insert_into(index_inconsistencies::table)
+                .values((
+                    index_inconsistencies::crate_id.eq(krate.id),
+                    index_inconsistencies::git_mismatch.eq(git_mismatch),
+                    index_inconsistencies::sparse_mismatch.eq(sparse_mismatch),
+                ))
+                .on_conflict(index_inconsistencies::crate_id)
+                .do_update()
+                .set((
+                    index_inconsistencies::git_mismatch.eq(git_mismatch),
+                    index_inconsistencies::sparse_mismatch.eq(sparse_mismatch),
+                    index_inconsistencies::detected_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+    }
+
+    info!(inconsistent, "Finished checking index consistency");
+
+    Ok(())
+}
@@ -3,24 +3,83 @@
 //! the daily database maintenance, but also operations like rendering READMEs
 //! and uploading them to S3.
 
+mod account_deletion;
+pub mod cdn;
+pub mod cdn_logs;
+mod checksums;
 pub mod cloudfront;
+mod crate_deletion;
+mod crate_file_integrity;
+mod crate_transfers;
 mod daily_db_maintenance;
+pub mod download_trends;
 pub mod dump_db;
+mod export_downloads;
+pub mod fastly;
+mod followed_crates;
 mod git;
-mod readmes;
+mod index_consistency;
+mod owner_invitations;
+pub mod readmes;
+mod replication;
+mod reverse_dependency_counts;
+mod saved_searches;
+mod search_index;
+mod team_memberships;
 mod update_downloads;
+mod webhooks;
 
+pub use account_deletion::delete_account;
+pub use cdn::{process_cdn_invalidations, queue_cdn_invalidations};
+pub use cdn_logs::process_cdn_logs;
+pub use checksums::backfill_checksums;
+pub use crate_deletion::delete_crate_files;
+pub use crate_file_integrity::verify_crate_files;
+pub use crate_transfers::complete_crate_transfers;
 pub use daily_db_maintenance::daily_db_maintenance;
+pub use download_trends::update_download_trends;
 pub use dump_db::dump_db;
-pub use git::{add_crate, normalize_index, squash_index, sync_yanked};
-pub use readmes::render_and_upload_readme;
+pub use export_downloads::export_downloads;
+pub use followed_crates::check_followed_crates;
+pub use git::{
+    add_crate, delete_versions, normalize_index, squash_index, squash_index_if_needed,
+    sync_deprecated, sync_yanked,
+};
+pub use index_consistency::check_index_consistency;
+pub use owner_invitations::process_owner_invitations;
+pub use readmes::{render_and_upload_readme, rerender_readmes};
+pub use replication::replicate_files;
+pub use reverse_dependency_counts::refresh_reverse_dependency_counts;
+pub use saved_searches::check_saved_searches;
+pub use search_index::sync_search_index;
+pub use team_memberships::sync_team_memberships;
 pub use update_downloads::update_downloads;
+pub use webhooks::trigger_webhooks;
 
+pub(crate) use account_deletion::perform_delete_account;
+pub(crate) use cdn::perform_process_cdn_invalidations;
+pub(crate) use cdn_logs::perform_process_cdn_logs;
+pub(crate) use checksums::perform_backfill_checksums;
+pub(crate) use crate_deletion::perform_delete_crate_files;
+pub(crate) use crate_file_integrity::perform_verify_crate_files;
+pub(crate) use crate_transfers::perform_complete_crate_transfers;
 pub(crate) use daily_db_maintenance::perform_daily_db_maintenance;
+pub(crate) use download_trends::perform_update_download_trends;
 pub(crate) use dump_db::perform_dump_db;
+pub(crate) use export_downloads::perform_export_downloads;
+pub(crate) use followed_crates::perform_check_followed_crates;
 pub(crate) use git::{
-    perform_index_add_crate, perform_index_squash, perform_index_sync_to_http,
+    perform_index_add_crate, perform_index_delete_versions, perform_index_squash,
+    perform_index_squash_if_needed, perform_index_sync_deprecated, perform_index_sync_to_http,
     perform_index_update_yanked, perform_normalize_index,
 };
-pub(crate) use readmes::perform_render_and_upload_readme;
+pub(crate) use index_consistency::perform_check_index_consistency;
+pub(crate) use owner_invitations::perform_process_owner_invitations;
+pub(crate) use readmes::{perform_render_and_upload_readme, perform_rerender_readmes};
+pub(crate) use replication::perform_replicate_files;
+pub(crate) use reverse_dependency_counts::perform_refresh_reverse_dependency_counts;
+pub(crate) use saved_searches::perform_check_saved_searches;
+pub(crate) use search_index::perform_sync_search_index;
+pub(crate) use team_memberships::perform_sync_team_memberships;
 pub(crate) use update_downloads::perform_update_downloads;
+pub(crate) use webhooks::perform_deliver_webhook;
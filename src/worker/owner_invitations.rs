@@ -0,0 +1,98 @@
+use crate::background_jobs::{Environment, Job, ProcessOwnerInvitationsJob};
+use crate::models::{CrateOwnerInvitation, NotificationKind, User};
+use crate::schema::{crate_owner_invitations, crates, users};
+use crate::swirl::PerformError;
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::dsl::now;
+use diesel::prelude::*;
+
+pub fn process_owner_invitations(expiration_days: u64, reminder_days: u64) -> Job {
+    Job::ProcessOwnerInvitations(ProcessOwnerInvitationsJob {
+        expiration_days,
+        reminder_days,
+    })
+}
+
+/// Deletes the crate owner invitations that expired, and reminds the invited
+/// users of the invitations that expire within the next `reminder_days`.
+///
+/// Expired invitations can't be accepted anymore, but would otherwise stay in
+/// the database forever. Every invitation is only reminded of once, and
+/// reminders that fail to send are logged and retried with the next run of
+/// the job.
+#[instrument(skip(env, conn))]
+pub fn perform_process_owner_invitations(
+    env: &Environment,
+    conn: &mut PgConnection,
+    expiration_days: u64,
+    reminder_days: u64,
+) -> Result<(), PerformError> {
+    let expiration = Duration::days(expiration_days as i64);
+    let expired_before = Utc::now().naive_utc() - expiration;
+
+    let expired = crate_owner_invitations::table
+        .filter(crate_owner_invitations::created_at.le(expired_before));
+    let deleted = diesel::delete(expired).execute(conn)?;
+    info!(deleted, "Deleted expired crate owner invitations");
+
+    let remind_before = expired_before + Duration::days(reminder_days as i64);
+    let invitations: Vec<(CrateOwnerInvitation, String)> = crate_owner_invitations::table
+        .inner_join(crates::table)
+        .filter(crate_owner_invitations::created_at.le(remind_before))
+        .filter(crate_owner_invitations::reminder_sent_at.is_null())
+        .select((crate_owner_invitations::all_columns, crates::name))
+        .load(conn)?;
+
+    info!(
+        invitations = invitations.len(),
+        "Reminding users of expiring crate owner invitations"
+    );
+
+    for (invitation, crate_name) in invitations {
+        let expires_at = invitation.created_at + expiration;
+
+        // Every reminder is sent in its own savepoint, so that a failing one
+        // doesn't roll back the progress of the others
+        let result =
+            conn.transaction(|conn| send_reminder(env, conn, &invitation, &crate_name, expires_at));
+
+        if let Err(error) = result {
+            warn!(
+                %crate_name,
+                invited_user_id = invitation.invited_user_id,
+                %error,
+                "Failed to remind user of crate owner invitation"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn send_reminder(
+    env: &Environment,
+    conn: &mut PgConnection,
+    invitation: &CrateOwnerInvitation,
+    crate_name: &str,
+    expires_at: NaiveDateTime,
+) -> Result<(), PerformError> {
+    diesel::update(invitation)
+        .set(crate_owner_invitations::reminder_sent_at.eq(now))
+        .execute(conn)?;
+
+    let invited_user: User = users::table.find(invitation.invited_user_id).first(conn)?;
+    let Some(email) = invited_user.notification_email(conn, NotificationKind::OwnerInvites)? else {
+        return Ok(());
+    };
+
+    let inviter: String = users::table
+        .find(invitation.invited_by_user_id)
+        .select(users::gh_login)
+        .first(conn)?;
+
+    env.emails()
+        .send_owner_invite_reminder(&email, &inviter, crate_name, &invitation.token, expires_at)
+        .map_err(|error| error.to_string())?;
+
+    Ok(())
+}
@@ -1,11 +1,24 @@
 //! Render README files to HTML.
 
 use crate::swirl::PerformError;
-use cargo_registry_markdown::text_to_html;
-use diesel::PgConnection;
+use anyhow::{anyhow, Context};
+use cargo_registry_markdown::{text_to_html, RENDERER_VERSION};
+use diesel::prelude::*;
+use flate2::read::GzDecoder;
+use reqwest::blocking::Client;
+use std::io::Read;
+use std::path::Path;
+use tar::{self, Archive};
 
-use crate::background_jobs::{Environment, Job, RenderAndUploadReadmeJob};
+use crate::background_jobs::{Environment, Job, RenderAndUploadReadmeJob, RerenderReadmesJob};
 use crate::models::Version;
+use crate::schema::*;
+use crate::uploaders::Uploader;
+use crate::util::CargoVcsInfo;
+
+/// The number of versions that are processed by a single `rerender_readmes`
+/// job.
+const RERENDER_BATCH_SIZE: i64 = 100;
 
 pub fn perform_render_and_upload_readme(
     conn: &mut PgConnection,
@@ -16,9 +29,6 @@ pub fn perform_render_and_upload_readme(
     base_url: Option<&str>,
     pkg_path_in_vcs: Option<&str>,
 ) -> Result<(), PerformError> {
-    use crate::schema::*;
-    use diesel::prelude::*;
-
     let rendered = text_to_html(text, readme_path, base_url, pkg_path_in_vcs);
 
     conn.transaction(|conn| {
@@ -30,6 +40,7 @@ pub fn perform_render_and_upload_readme(
             .first(conn)?;
         env.uploader
             .upload_readme(env.http_client(), &crate_name, &vers, rendered)?;
+        env.uploader.replicate_readme(conn, &crate_name, &vers)?;
         Ok(())
     })
 }
@@ -49,3 +60,333 @@ pub fn render_and_upload_readme(
         pkg_path_in_vcs,
     })
 }
+
+/// Renders the readmes of versions again that were rendered by an outdated
+/// version of the renderer, see [`RENDERER_VERSION`].
+///
+/// The readmes are rendered from the crate files and stored under the path of
+/// the current renderer version, and the outdated readmes are deleted. Versions
+/// are processed in batches, ordered by ID. If the batch was full, another job
+/// is enqueued for the versions after the last processed one. Versions whose
+/// readme can't be rendered are logged and skipped.
+#[instrument(skip(env, conn))]
+pub fn perform_rerender_readmes(
+    env: &Environment,
+    conn: &mut PgConnection,
+    after_id: i32,
+) -> Result<(), PerformError> {
+    let outdated: Vec<(i32, String, String, i32, Option<String>)> = versions::table
+        .inner_join(crates::table)
+        .inner_join(readme_renderings::table)
+        .left_outer_join(crate_files::table)
+        .filter(versions::id.gt(after_id))
+        .filter(readme_renderings::renderer_version.lt(RENDERER_VERSION))
+        .select((
+            versions::id,
+            crates::name,
+            versions::num,
+            readme_renderings::renderer_version,
+            crate_files::object_key.nullable(),
+        ))
+        .order(versions::id)
+        .limit(RERENDER_BATCH_SIZE)
+        .load(conn)?;
+
+    info!(count = outdated.len(), "Rendering outdated readmes");
+
+    for (version_id, name, num, renderer_version, object_key) in &outdated {
+        let client = env.http_client();
+        let object_key = object_key.as_deref();
+        let rendered = match get_readme(&env.uploader, client, name, num, object_key) {
+            Ok(rendered) => rendered,
+            Err(error) => {
+                warn!(%name, %num, ?error, "Failed to render readme");
+                continue;
+            }
+        };
+
+        env.uploader.upload_readme(client, name, num, rendered)?;
+        conn.transaction::<_, PerformError, _>(|conn| {
+            Version::record_readme_rendering(*version_id, conn)?;
+            env.uploader.replicate_readme(conn, name, num)?;
+            Ok(())
+        })?;
+
+        // The readme endpoint redirects to the new path from now on
+        env.uploader
+            .delete_readme(client, name, num, *renderer_version)?;
+    }
+
+    if outdated.len() as i64 == RERENDER_BATCH_SIZE {
+        if let Some((last_id, ..)) = outdated.last() {
+            rerender_readmes(*last_id).enqueue(conn)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn rerender_readmes(after_id: i32) -> Job {
+    Job::RerenderReadmes(RerenderReadmesJob { after_id })
+}
+
+/// Renders the readme of an uploaded crate version from its crate file.
+///
+/// `object_key` is the key of the crate file if it is stored under its content
+/// address.
+pub(crate) fn get_readme(
+    uploader: &Uploader,
+    client: &Client,
+    krate_name: &str,
+    vers: &str,
+    object_key: Option<&str>,
+) -> anyhow::Result<String> {
+    let pkg_name = format!("{krate_name}-{vers}");
+
+    let crate_file = match object_key {
+        Some(object_key) => uploader.download_crate_object(client, object_key),
+        None => uploader.download_crate(client, krate_name, vers),
+    }
+    .context("Failed to fetch crate")?;
+
+    let pkg_path_in_vcs = read_pkg_path_in_vcs(&crate_file, &pkg_name);
+
+    let reader = GzDecoder::new(crate_file.as_slice());
+    let archive = Archive::new(reader);
+    render_pkg_readme(archive, &pkg_name, pkg_path_in_vcs.as_deref())
+}
+
+/// Reads the path of the package within its repository from the
+/// `.cargo_vcs_info.json` file of the crate file, which cargo only includes if
+/// the package was published from a version control checkout.
+fn read_pkg_path_in_vcs(crate_file: &[u8], pkg_name: &str) -> Option<String> {
+    let mut archive = Archive::new(GzDecoder::new(crate_file));
+    let mut entries = archive.entries().ok()?;
+    let path = format!("{pkg_name}/.cargo_vcs_info.json");
+    let contents = find_file_by_path(&mut entries, Path::new(&path)).ok()?;
+    let vcs_info = CargoVcsInfo::from_contents(&contents).ok()?;
+    Some(vcs_info.path_in_vcs)
+}
+
+fn render_pkg_readme<R: Read>(
+    mut archive: Archive<R>,
+    pkg_name: &str,
+    pkg_path_in_vcs: Option<&str>,
+) -> anyhow::Result<String> {
+    let mut entries = archive.entries().context("Invalid tar archive entries")?;
+
+    let manifest: Manifest = {
+        let path = format!("{pkg_name}/Cargo.toml");
+        let contents = find_file_by_path(&mut entries, Path::new(&path))
+            .context("Failed to read Cargo.toml file")?;
+
+        toml::from_str(&contents).context("Failed to parse manifest file")?
+    };
+
+    let rendered = {
+        let readme_path = manifest
+            .package
+            .readme
+            .clone()
+            .unwrap_or_else(|| "README.md".into());
+        let path = format!("{pkg_name}/{readme_path}");
+        let contents = find_file_by_path(&mut entries, Path::new(&path))
+            .with_context(|| format!("Failed to read {readme_path} file"))?;
+
+        text_to_html(
+            &contents,
+            &readme_path,
+            manifest.package.repository.as_deref(),
+            pkg_path_in_vcs,
+        )
+    };
+    return Ok(rendered);
+
+    #[derive(Debug, Deserialize)]
+    struct Package {
+        readme: Option<String>,
+        repository: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Manifest {
+        package: Package,
+    }
+}
+
+/// Search an entry by its path in a Tar archive.
+fn find_file_by_path<R: Read>(
+    entries: &mut tar::Entries<'_, R>,
+    path: &Path,
+) -> anyhow::Result<String> {
+    let mut file = entries
+        .filter_map(|entry| entry.ok())
+        .find(|file| match file.path() {
+            Ok(p) => p == path,
+            Err(_) => false,
+        })
+        .ok_or_else(|| anyhow!("Failed to find tarball entry: {}", path.display()))?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .context("Failed to read file contents")?;
+
+    Ok(contents)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use tar;
+
+    use super::{read_pkg_path_in_vcs, render_pkg_readme};
+
+    pub fn add_file<W: Write>(pkg: &mut tar::Builder<W>, path: &str, content: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_cksum();
+        pkg.append_data(&mut header, path, content).unwrap();
+    }
+
+    #[test]
+    fn test_render_pkg_readme() {
+        let mut pkg = tar::Builder::new(vec![]);
+        add_file(
+            &mut pkg,
+            "foo-0.0.1/Cargo.toml",
+            br#"
+[package]
+readme = "README.md"
+"#,
+        );
+        add_file(&mut pkg, "foo-0.0.1/README.md", b"readme");
+        let serialized_archive = pkg.into_inner().unwrap();
+        let result =
+            render_pkg_readme(tar::Archive::new(&*serialized_archive), "foo-0.0.1", None).unwrap();
+        assert!(result.contains("readme"))
+    }
+
+    #[test]
+    fn test_render_pkg_no_readme() {
+        let mut pkg = tar::Builder::new(vec![]);
+        add_file(
+            &mut pkg,
+            "foo-0.0.1/Cargo.toml",
+            br#"
+[package]
+"#,
+        );
+        let serialized_archive = pkg.into_inner().unwrap();
+        assert_err!(render_pkg_readme(
+            tar::Archive::new(&*serialized_archive),
+            "foo-0.0.1",
+            None
+        ));
+    }
+
+    #[test]
+    fn test_render_pkg_implicit_readme() {
+        let mut pkg = tar::Builder::new(vec![]);
+        add_file(
+            &mut pkg,
+            "foo-0.0.1/Cargo.toml",
+            br#"
+[package]
+"#,
+        );
+        add_file(&mut pkg, "foo-0.0.1/README.md", b"readme");
+        let serialized_archive = pkg.into_inner().unwrap();
+        let result =
+            render_pkg_readme(tar::Archive::new(&*serialized_archive), "foo-0.0.1", None).unwrap();
+        assert!(result.contains("readme"))
+    }
+
+    #[test]
+    fn test_render_pkg_readme_w_link() {
+        let mut pkg = tar::Builder::new(vec![]);
+        add_file(
+            &mut pkg,
+            "foo-0.0.1/Cargo.toml",
+            br#"
+[package]
+readme = "README.md"
+repository = "https://github.com/foo/foo"
+"#,
+        );
+        add_file(
+            &mut pkg,
+            "foo-0.0.1/README.md",
+            b"readme [link](./Other.md)",
+        );
+        let serialized_archive = pkg.into_inner().unwrap();
+        let result =
+            render_pkg_readme(tar::Archive::new(&*serialized_archive), "foo-0.0.1", None).unwrap();
+        assert!(result.contains("\"https://github.com/foo/foo/blob/HEAD/./Other.md\""))
+    }
+
+    #[test]
+    fn test_render_pkg_readme_not_at_root() {
+        let mut pkg = tar::Builder::new(vec![]);
+        add_file(
+            &mut pkg,
+            "foo-0.0.1/Cargo.toml",
+            br#"
+[package]
+readme = "docs/README.md"
+repository = "https://github.com/foo/foo"
+"#,
+        );
+        add_file(
+            &mut pkg,
+            "foo-0.0.1/docs/README.md",
+            b"docs/readme [link](./Other.md)",
+        );
+        let serialized_archive = pkg.into_inner().unwrap();
+        let result =
+            render_pkg_readme(tar::Archive::new(&*serialized_archive), "foo-0.0.1", None).unwrap();
+        assert!(result.contains("docs/readme"));
+        assert!(result.contains("\"https://github.com/foo/foo/blob/HEAD/docs/./Other.md\""))
+    }
+
+    #[test]
+    fn test_render_pkg_readme_in_workspace() {
+        let mut pkg = tar::Builder::new(vec![]);
+        add_file(
+            &mut pkg,
+            "foo-0.0.1/.cargo_vcs_info.json",
+            br#"{"git": {"sha1": "ab2c4e6"}, "path_in_vcs": "crates/foo"}"#,
+        );
+        add_file(
+            &mut pkg,
+            "foo-0.0.1/Cargo.toml",
+            br#"
+[package]
+readme = "README.md"
+repository = "https://github.com/foo/foo"
+"#,
+        );
+        add_file(
+            &mut pkg,
+            "foo-0.0.1/README.md",
+            b"readme [link](./Other.md)",
+        );
+        let serialized_archive = pkg.into_inner().unwrap();
+
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&serialized_archive).unwrap();
+        let crate_file = encoder.finish().unwrap();
+
+        let pkg_path_in_vcs = read_pkg_path_in_vcs(&crate_file, "foo-0.0.1");
+        assert_some_eq!(&pkg_path_in_vcs, "crates/foo");
+
+        let result = render_pkg_readme(
+            tar::Archive::new(&*serialized_archive),
+            "foo-0.0.1",
+            pkg_path_in_vcs.as_deref(),
+        )
+        .unwrap();
+        assert!(result.contains("\"https://github.com/foo/foo/blob/HEAD/crates/foo/./Other.md\""))
+    }
+}
@@ -0,0 +1,91 @@
+use crate::background_jobs::{fresh_connection, Environment, Job};
+use crate::db::ConnectionPool;
+use crate::models::FileReplication;
+use crate::swirl::PerformError;
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+
+/// At most this many files are copied by a single job run.
+const MAX_FILES_PER_RUN: i64 = 100;
+
+/// Key of the transaction-level advisory lock that is held by the job that
+/// copies files.
+const REPLICATION_LOCK_KEY: i64 = 0x7265_706c_6963_6174; // "replicat"
+
+pub fn replicate_files() -> Job {
+    Job::ReplicateFiles
+}
+
+/// Copies the crate files and readmes that are queued for replication from
+/// the primary bucket to the replicas.
+///
+/// The outcome of every copy is recorded in the replication state of the
+/// file. If any copy failed, the job returns an error, so that it is retried
+/// with an exponential backoff until the replicas are available again.
+#[instrument(skip_all)]
+pub fn perform_replicate_files(
+    env: &Environment,
+    conn: &mut PgConnection,
+    pool: Option<ConnectionPool>,
+) -> Result<(), PerformError> {
+    // Concurrent jobs wait for each other, instead of copying the same files
+    diesel::sql_query("SELECT pg_advisory_xact_lock($1)")
+        .bind::<BigInt, _>(REPLICATION_LOCK_KEY)
+        .execute(conn)?;
+
+    let pending = FileReplication::pending(conn, MAX_FILES_PER_RUN)?;
+    info!(count = pending.len(), "Replicating files");
+
+    let outcomes = pending
+        .iter()
+        .map(|replication| {
+            let replica = &replication.replica;
+            let result = env
+                .uploader
+                .replicate(env.http_client(), replica, &replication.path);
+            (replication, result)
+        })
+        .collect::<Vec<_>>();
+    let failed = outcomes
+        .iter()
+        .filter(|(_, result)| result.is_err())
+        .count();
+
+    // The outcomes of a run that is retried are recorded outside of the
+    // transaction of the job, since they would be rolled back together with
+    // the job otherwise
+    let mut fresh_conn;
+    let outcome_conn = if failed > 0 {
+        fresh_conn = fresh_connection(pool)?;
+        &mut *fresh_conn
+    } else {
+        &mut *conn
+    };
+
+    for (replication, result) in outcomes {
+        let path = &replication.path;
+        let replica = &replication.replica;
+        match result {
+            Ok(true) => replication.mark_replicated(outcome_conn)?,
+            Ok(false) => {
+                warn!(%path, %replica, "File to replicate does not exist anymore");
+                replication.delete(outcome_conn)?;
+            }
+            Err(error) => {
+                warn!(%path, %replica, ?error, "Failed to replicate file");
+                replication.record_failure(outcome_conn, &error.to_string())?;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("failed to replicate {failed} files").into());
+    }
+
+    // Continue with the next batch right away
+    if pending.len() as i64 == MAX_FILES_PER_RUN {
+        replicate_files().enqueue(conn)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use crate::background_jobs::Job;
+use crate::swirl::PerformError;
+use diesel::{sql_query, PgConnection, RunQueryDsl};
+
+pub fn refresh_reverse_dependency_counts() -> Job {
+    Job::RefreshReverseDependencyCounts
+}
+
+/// Recounts the reverse dependencies of all crates into the
+/// `reverse_dependency_counts` table, which is used for the totals of the
+/// reverse dependencies endpoint.
+#[instrument(skip_all)]
+pub(crate) fn perform_refresh_reverse_dependency_counts(
+    conn: &mut PgConnection,
+) -> Result<(), PerformError> {
+    info!("Refreshing reverse dependency counts");
+    let crates = sql_query(include_str!("reverse_dependency_counts.sql")).execute(conn)?;
+    info!(crates, "Finished refreshing reverse dependency counts");
+    Ok(())
+}
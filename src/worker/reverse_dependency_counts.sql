@@ -0,0 +1,24 @@
+WITH newest_versions AS (
+    -- The newest non-yanked version of every crate, like the versions that
+    -- are listed by the reverse dependencies endpoint
+    SELECT DISTINCT ON (crate_id) id, crate_id
+    FROM versions
+    WHERE NOT yanked
+    ORDER BY crate_id, to_semver_no_prerelease(num) DESC NULLS LAST, id DESC
+), counts AS (
+    SELECT dependencies.crate_id, COUNT(DISTINCT newest_versions.crate_id) AS dependents
+    FROM dependencies
+    INNER JOIN newest_versions
+      ON newest_versions.id = dependencies.version_id
+    GROUP BY dependencies.crate_id
+)
+INSERT INTO reverse_dependency_counts (crate_id, dependents, refreshed_at)
+-- Crates without any dependents are included, so that their count doesn't
+-- remain at the previous value
+SELECT crates.id, COALESCE(counts.dependents, 0), now()
+FROM crates
+LEFT JOIN counts
+  ON counts.crate_id = crates.id
+ON CONFLICT (crate_id) DO UPDATE
+SET dependents = excluded.dependents,
+    refreshed_at = excluded.refreshed_at
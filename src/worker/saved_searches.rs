@@ -0,0 +1,149 @@
+use crate::background_jobs::{Environment, Job};
+use crate::config::SearchRanking;
+use crate::models::{NotificationKind, SavedSearch, User};
+use crate::schema::{crates, recent_crate_downloads, saved_searches, users, versions};
+use crate::search::{PostgresSearch, SearchBackend, TextFields};
+use crate::swirl::PerformError;
+use diesel::prelude::*;
+use reqwest::header::HeaderMap;
+
+/// At most this many versions are listed in a single notification.
+const MAX_NOTIFIED_VERSIONS: usize = 50;
+
+pub fn check_saved_searches() -> Job {
+    Job::CheckSavedSearches
+}
+
+/// Notifies the owners of saved searches about versions that were published
+/// since the previous check and belong to crates matching their query.
+///
+/// Every saved search remembers the newest version ID it was checked against,
+/// so versions are only notified about once. A search whose notification
+/// fails is logged and retried with the next run of the job.
+#[instrument(skip_all)]
+pub fn perform_check_saved_searches(
+    env: &Environment,
+    conn: &mut PgConnection,
+) -> Result<(), PerformError> {
+    let newest_version_id = versions::table
+        .select(diesel::dsl::max(versions::id))
+        .first::<Option<i32>>(conn)?
+        .unwrap_or_default();
+
+    let searches: Vec<SavedSearch> = saved_searches::table
+        .filter(saved_searches::last_version_id.lt(newest_version_id))
+        .order(saved_searches::id)
+        .load(conn)?;
+
+    info!(searches = searches.len(), "Checking saved searches");
+
+    // Alerts are always matched against the database, since the external
+    // search index may not contain the newest crates yet
+    let search_backend = PostgresSearch::new(SearchRanking::from_environment());
+
+    for search in searches {
+        // Every search is checked in its own savepoint, so that a failing one
+        // doesn't roll back the progress of the others
+        let result = conn.transaction(|conn| {
+            check_saved_search(env, conn, &search_backend, &search, newest_version_id)
+        });
+
+        if let Err(error) = result {
+            warn!(saved_search_id = search.id, %error, "Failed to check saved search");
+        }
+    }
+
+    Ok(())
+}
+
+fn check_saved_search(
+    env: &Environment,
+    conn: &mut PgConnection,
+    search_backend: &PostgresSearch,
+    search: &SavedSearch,
+    newest_version_id: i32,
+) -> Result<(), PerformError> {
+    let text_matches = search_backend
+        .text_matches(&search.query, &TextFields::ALL)
+        .map_err(|error| error.to_string())?;
+
+    let new_versions = versions::table
+        .filter(versions::id.gt(search.last_version_id))
+        .filter(versions::id.le(newest_version_id))
+        .filter(versions::yanked.eq(false));
+
+    let crate_ids: Vec<i32> = crates::table
+        .left_join(recent_crate_downloads::table)
+        .filter(text_matches.filter)
+        .filter(crates::id.eq_any(new_versions.select(versions::crate_id)))
+        .select(crates::id)
+        .load(conn)?;
+
+    if !crate_ids.is_empty() {
+        let versions: Vec<(String, String)> = new_versions
+            .inner_join(crates::table)
+            .filter(versions::crate_id.eq_any(crate_ids))
+            .order(versions::id)
+            .select((crates::name, versions::num))
+            .load(conn)?;
+
+        if !versions.is_empty() {
+            notify(env, conn, search, &versions)?;
+        }
+    }
+
+    diesel::update(search)
+        .set(saved_searches::last_version_id.eq(newest_version_id))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+fn notify(
+    env: &Environment,
+    conn: &mut PgConnection,
+    search: &SavedSearch,
+    versions: &[(String, String)],
+) -> Result<(), PerformError> {
+    let total = versions.len();
+    let versions = &versions[..total.min(MAX_NOTIFIED_VERSIONS)];
+
+    if let Some(webhook_url) = &search.webhook_url {
+        let payload = json!({
+            "saved_search": {
+                "id": search.id,
+                "query": search.query,
+            },
+            "versions": versions
+                .iter()
+                .map(|(krate, num)| json!({ "crate": krate, "version": num }))
+                .collect::<Vec<_>>(),
+            "total": total,
+        });
+
+        let body = serde_json::to_vec(&payload)?;
+        let response = env
+            .webhook_client()
+            .post_json(webhook_url, body, HeaderMap::new())
+            .map_err(|error| error.to_string())?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("unexpected response status: {status}").into());
+        }
+    } else {
+        let user: User = users::table.find(search.user_id).first(conn)?;
+
+        let Some(email) = user.notification_email(conn, NotificationKind::Digests)? else {
+            // The user removed or changed their email address in the meantime,
+            // or opted out of digests
+            return Ok(());
+        };
+
+        env.emails()
+            .send_saved_search_alert(&email, &search.query, versions, total)
+            .map_err(|error| error.to_string())?;
+    }
+
+    Ok(())
+}
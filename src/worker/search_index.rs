@@ -0,0 +1,46 @@
+use crate::background_jobs::{Environment, Job, SyncSearchIndexJob};
+use crate::models::{Crate, CrateKeyword};
+use crate::schema::keywords;
+use crate::search::CrateDocument;
+use crate::swirl::PerformError;
+use diesel::prelude::*;
+
+/// Updates the document of a crate in the external search index, or removes
+/// it from the index if the crate has been deleted.
+#[instrument(skip(env, conn))]
+pub fn perform_sync_search_index(
+    env: &Environment,
+    conn: &mut PgConnection,
+    crate_name: &str,
+) -> Result<(), PerformError> {
+    let Some(search_index) = env.search_index() else {
+        // The search backend was switched to the database after the job was enqueued
+        return Ok(());
+    };
+
+    let Some(krate) = Crate::by_name(crate_name).first::<Crate>(conn).optional()? else {
+        info!("Removing crate from the search index");
+        search_index.remove_crate(crate_name)?;
+        return Ok(());
+    };
+
+    let keywords = CrateKeyword::belonging_to(&krate)
+        .inner_join(keywords::table)
+        .select(keywords::keyword)
+        .load(conn)?;
+
+    let document = CrateDocument {
+        name: krate.name,
+        description: krate.description,
+        keywords,
+        downloads: krate.downloads,
+    };
+
+    info!("Updating the search index");
+    search_index.index_crate(&document)?;
+    Ok(())
+}
+
+pub fn sync_search_index(krate: String) -> Job {
+    Job::SyncSearchIndex(SyncSearchIndexJob { krate })
+}
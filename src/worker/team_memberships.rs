@@ -0,0 +1,128 @@
+use crate::background_jobs::{Environment, Job, SyncTeamMembershipsJob};
+use crate::models::{
+    AccountProvider, CrateOwner, NotificationKind, OwnerKind, Team, User, SSO_MEMBERSHIP_VALID_DAYS,
+};
+use crate::schema::{crate_owners, crates, team_members, teams, users};
+use crate::swirl::PerformError;
+use chrono::{Duration, Utc};
+use diesel::dsl::now;
+use diesel::prelude::*;
+
+/// Memberships are validated again once they were last confirmed this long ago.
+const REVALIDATE_AFTER_HOURS: i64 = 24;
+
+pub fn sync_team_memberships(batch_size: i64) -> Job {
+    Job::SyncTeamMemberships(SyncTeamMembershipsJob { batch_size })
+}
+
+/// Asks GitHub again whether the users that were recorded as members of a
+/// team, when they last published or managed one of its crates, are still
+/// members.
+///
+/// The memberships that were confirmed least recently come first. Users who
+/// left the team lose their recorded membership and are notified by email
+/// that they can no longer publish the crates owned by the team. Memberships
+/// that can't be checked, e.g. because the user revoked our access to their
+/// GitHub account, are logged and checked again by the next run.
+///
+/// Memberships of single sign-on teams can't be checked without the user, so
+/// they are removed once the user hasn't logged in for
+/// `SSO_MEMBERSHIP_VALID_DAYS`.
+#[instrument(skip(env, conn))]
+pub fn perform_sync_team_memberships(
+    env: &Environment,
+    conn: &mut PgConnection,
+    batch_size: i64,
+) -> Result<(), PerformError> {
+    let sso_cutoff = Utc::now().naive_utc() - Duration::days(SSO_MEMBERSHIP_VALID_DAYS.into());
+    let sso_teams = teams::table
+        .filter(teams::provider.eq(AccountProvider::Sso as i16))
+        .select(teams::id);
+    let expired = diesel::delete(team_members::table)
+        .filter(team_members::team_id.eq_any(sso_teams))
+        .filter(team_members::verified_at.le(sso_cutoff))
+        .execute(conn)?;
+    info!(expired, "Removed expired single sign-on team memberships");
+
+    let cutoff = Utc::now().naive_utc() - Duration::hours(REVALIDATE_AFTER_HOURS);
+    let memberships: Vec<(Team, User)> = team_members::table
+        .inner_join(teams::table)
+        .inner_join(users::table)
+        .filter(team_members::verified_at.lt(cutoff))
+        .filter(teams::provider.eq(AccountProvider::GitHub as i16))
+        .order(team_members::verified_at)
+        .limit(batch_size)
+        .select((teams::all_columns, users::all_columns))
+        .load(conn)?;
+
+    info!(
+        memberships = memberships.len(),
+        "Validating team memberships"
+    );
+
+    for (team, user) in memberships {
+        // Every membership is checked in its own savepoint, so that a failing
+        // one doesn't roll back the progress of the others
+        let result = conn.transaction(|conn| sync_team_membership(env, conn, &team, &user));
+
+        if let Err(error) = result {
+            warn!(
+                team = %team.login,
+                user = %user.gh_login,
+                %error,
+                "Failed to validate team membership"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn sync_team_membership(
+    env: &Environment,
+    conn: &mut PgConnection,
+    team: &Team,
+    user: &User,
+) -> Result<(), PerformError> {
+    let is_member = team
+        .github_team_contains_user(env.github(), conn, user)
+        .map_err(|error| error.to_string())?;
+
+    let membership = team_members::table.find((team.id, user.id));
+    if is_member {
+        diesel::update(membership)
+            .set(team_members::verified_at.eq(now))
+            .execute(conn)?;
+        return Ok(());
+    }
+
+    info!(team = %team.login, user = %user.gh_login, "User left team, removing membership");
+    diesel::delete(membership).execute(conn)?;
+
+    let crate_names: Vec<String> = crates::table
+        .filter(
+            crates::id.eq_any(
+                CrateOwner::by_owner_kind(OwnerKind::Team)
+                    .filter(crate_owners::owner_id.eq(team.id))
+                    .select(crate_owners::crate_id),
+            ),
+        )
+        .select(crates::name)
+        .order(crates::name)
+        .load(conn)?;
+
+    // Teams that only reserve a crate name prefix don't own any crates yet
+    if crate_names.is_empty() {
+        return Ok(());
+    }
+
+    let Some(email) = user.notification_email(conn, NotificationKind::OwnerInvites)? else {
+        return Ok(());
+    };
+
+    env.emails()
+        .send_team_membership_removed(&email, &team.login, &crate_names)
+        .map_err(|error| error.to_string())?;
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use crate::background_jobs::{fresh_connection, DeliverWebhookJob, Environment, Job};
+use crate::db::ConnectionPool;
+use crate::models::{Webhook, WebhookDelivery, WebhookEvent};
+use crate::schema::{webhook_deliveries, webhooks};
+use crate::swirl::errors::EnqueueError;
+use crate::swirl::PerformError;
+use chrono::Utc;
+use diesel::prelude::*;
+use hex::ToHex;
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, HeaderValue};
+use sha2::Sha256;
+
+/// Deliveries that failed this many times are not retried anymore.
+const MAX_ATTEMPTS: i32 = 5;
+
+/// Creates a delivery of the event for every webhook of the crate and
+/// enqueues the jobs that send them.
+///
+/// The payload contains the event name, the crate name and the
+/// event-specific `data`.
+pub fn trigger_webhooks(
+    conn: &mut PgConnection,
+    crate_id: i32,
+    crate_name: &str,
+    event: WebhookEvent,
+    data: serde_json::Value,
+) -> Result<(), EnqueueError> {
+    let payload = json!({
+        "event": event.as_str(),
+        "crate": crate_name,
+        "data": data,
+    });
+
+    for delivery_id in WebhookDelivery::create_for_crate(conn, crate_id, event, &payload)? {
+        deliver_webhook(delivery_id).enqueue(conn)?;
+    }
+
+    Ok(())
+}
+
+/// Sends the payload of a delivery to its webhook.
+///
+/// The request carries an `X-Crates-Io-Signature` header with the hex encoded
+/// HMAC-SHA256 of the body, keyed with the SHA-256 hash of the webhook secret,
+/// see `signature()`.
+/// The outcome of every attempt is recorded on the delivery. Failed attempts
+/// return an error, so that the job is retried with an exponential backoff
+/// until `MAX_ATTEMPTS` is reached.
+#[instrument(skip(env, conn, pool))]
+pub fn perform_deliver_webhook(
+    env: &Environment,
+    conn: &mut PgConnection,
+    pool: Option<ConnectionPool>,
+    delivery_id: i32,
+) -> Result<(), PerformError> {
+    let delivery = webhook_deliveries::table
+        .inner_join(webhooks::table)
+        .filter(webhook_deliveries::id.eq(delivery_id))
+        .select((webhook_deliveries::all_columns, webhooks::all_columns))
+        .first::<(WebhookDelivery, Webhook)>(conn)
+        .optional()?;
+
+    let Some((delivery, webhook)) = delivery else {
+        // The webhook was deleted in the meantime
+        return Ok(());
+    };
+
+    let body = serde_json::to_vec(&delivery.payload)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert("X-Crates-Io-Event", HeaderValue::from_str(&delivery.event)?);
+    headers.insert("X-Crates-Io-Delivery", delivery.id.into());
+    headers.insert(
+        "X-Crates-Io-Signature",
+        HeaderValue::from_str(&signature(webhook.signing_key(), &body))?,
+    );
+
+    let result = env.webhook_client().post_json(&webhook.url, body, headers);
+
+    let (status, error) = match result {
+        Ok(response) if response.status().is_success() => {
+            (Some(response.status().as_u16() as i32), None)
+        }
+        Ok(response) => {
+            let status = response.status();
+            let error = format!("unexpected response status: {status}");
+            (Some(status.as_u16() as i32), Some(error))
+        }
+        Err(error) => (None, Some(error.to_string())),
+    };
+
+    let attempts = delivery.attempts + 1;
+    let delivered_at = error.is_none().then(|| Utc::now().naive_utc());
+
+    let retry = error.is_some() && attempts < MAX_ATTEMPTS;
+
+    // The outcome of an attempt that is retried is recorded outside of the
+    // transaction of the job, since it would be rolled back together with the
+    // job otherwise
+    let mut fresh_conn;
+    let conn = if retry {
+        fresh_conn = fresh_connection(pool)?;
+        &mut *fresh_conn
+    } else {
+        conn
+    };
+
+    diesel::update(&delivery)
+        .set((
+            webhook_deliveries::attempts.eq(attempts),
+            webhook_deliveries::response_status.eq(status),
+            webhook_deliveries::error.eq(&error),
+            webhook_deliveries::delivered_at.eq(delivered_at),
+        ))
+        .execute(conn)?;
+
+    match error {
+        Some(error) if retry => Err(format!("Failed to deliver webhook: {error}").into()),
+        Some(error) => {
+            warn!(%error, attempts, "Giving up on webhook delivery");
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Returns the value of the `X-Crates-Io-Signature` header of the body, in
+/// the form `sha256=<hex encoded HMAC-SHA256>`.
+pub fn signature(key: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take a key of any size");
+    mac.update(body);
+    let signature: String = mac.finalize().into_bytes().encode_hex();
+    format!("sha256={signature}")
+}
+
+fn deliver_webhook(delivery_id: i32) -> Job {
+    Job::DeliverWebhook(DeliverWebhookJob { delivery_id })
+}
@@ -0,0 +1,27 @@
+import { currentURL } from '@ember/test-helpers';
+import { module, test } from 'qunit';
+
+import { setupApplicationTest } from 'cargo/tests/helpers';
+
+import { visit } from '../helpers/visit-ignoring-abort';
+
+module('Acceptance | Email Change Revert', function (hooks) {
+  setupApplicationTest(hooks);
+
+  test('happy path', async function (assert) {
+    let user = this.server.create('user', { emailRevertToken: 'badc0ffee' });
+
+    await visit('/revert-email-change/badc0ffee');
+    assert.strictEqual(currentURL(), '/');
+    assert.dom('[data-test-notification-message="success"]').exists();
+
+    user.reload();
+    assert.strictEqual(user.emailRevertToken, null);
+  });
+
+  test('error case', async function (assert) {
+    await visit('/revert-email-change/badc0ffee');
+    assert.strictEqual(currentURL(), '/');
+    assert.dom('[data-test-notification-message]').hasText('Unknown error in reverting the email change');
+  });
+});